@@ -423,6 +423,32 @@ pub enum ServerCommands {
         /// Prefix to filter configuration entries by
         prefix: Option<String>,
     },
+
+    /// Back up an account in the server's native format
+    BackupAccount {
+        /// Account name to back up
+        account: String,
+    },
+
+    /// List account backups
+    ListAccountBackups {},
+
+    /// Restore an account backup
+    RestoreAccount {
+        /// Account name to restore the backup into
+        account: String,
+        /// Backup id, as returned by list-account-backups
+        id: String,
+    },
+
+    /// Check the consistency of the data store
+    Fsck {
+        /// Account name to check, all accounts are checked if omitted
+        account: Option<String>,
+        /// Repair any inconsistencies found
+        #[clap(long)]
+        repair: bool,
+    },
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
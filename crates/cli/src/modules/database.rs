@@ -109,6 +109,172 @@ impl ServerCommands {
                     if results.len() == 1 { "" } else { "s" }
                 );
             }
+            ServerCommands::BackupAccount { account } => {
+                let backup = client
+                    .http_request::<Value, String>(
+                        Method::GET,
+                        &format!("/api/store/backup/account/{account}"),
+                        None,
+                    )
+                    .await;
+                eprintln!(
+                    "Successfully created backup {}.",
+                    backup
+                        .get("id")
+                        .and_then(|id| id.as_str())
+                        .unwrap_or_default()
+                );
+            }
+            ServerCommands::ListAccountBackups {} => {
+                let backups = client
+                    .http_request::<Vec<Value>, String>(
+                        Method::GET,
+                        "/api/store/backups/accounts",
+                        None,
+                    )
+                    .await;
+
+                if !backups.is_empty() {
+                    let mut table = Table::new();
+                    table.add_row(Row::new(vec![
+                        Cell::new("Id").with_style(Attr::Bold),
+                        Cell::new("Size").with_style(Attr::Bold),
+                    ]));
+
+                    for backup in &backups {
+                        table.add_row(Row::new(vec![
+                            Cell::new(
+                                backup
+                                    .get("id")
+                                    .and_then(|id| id.as_str())
+                                    .unwrap_or_default(),
+                            ),
+                            Cell::new(
+                                &backup
+                                    .get("files")
+                                    .and_then(|files| files.as_array())
+                                    .map(|files| {
+                                        files
+                                            .iter()
+                                            .filter_map(|file| file.get("size")?.as_u64())
+                                            .sum::<u64>()
+                                    })
+                                    .unwrap_or_default()
+                                    .to_string(),
+                            ),
+                        ]));
+                    }
+
+                    eprintln!();
+                    table.printstd();
+                    eprintln!();
+                }
+
+                eprintln!(
+                    "\n\n{} backup{} found.\n",
+                    backups.len(),
+                    if backups.len() == 1 { "" } else { "s" }
+                );
+            }
+            ServerCommands::RestoreAccount { account, id } => {
+                client
+                    .http_request::<Value, String>(
+                        Method::GET,
+                        &format!("/api/store/restore/{account}/{id}"),
+                        None,
+                    )
+                    .await;
+                eprintln!("Successfully restored backup {id} into account {account}.");
+            }
+            ServerCommands::Fsck { account, repair } => {
+                let mut url = if let Some(account) = account {
+                    format!("/api/store/fsck/{account}")
+                } else {
+                    "/api/store/fsck".to_string()
+                };
+                if repair {
+                    url.push_str("?repair=true");
+                }
+                let id = client
+                    .http_request::<String, String>(Method::GET, &url, None)
+                    .await;
+
+                // Wait for the check to complete
+                let report = loop {
+                    let job = client
+                        .http_request::<Value, String>(
+                            Method::GET,
+                            &format!("/api/store/fsck-jobs/{id}"),
+                            None,
+                        )
+                        .await;
+                    if job.get("completed").is_some() {
+                        if let Some(error) = job.get("error").and_then(|error| error.as_str()) {
+                            eprintln!("Consistency check failed: {error}");
+                            std::process::exit(1);
+                        }
+                        break job.get("report").cloned().unwrap_or_default();
+                    }
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                };
+                let issues = report
+                    .get("issues")
+                    .and_then(|issues| issues.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+
+                if !issues.is_empty() {
+                    let mut table = Table::new();
+                    table.add_row(Row::new(vec![
+                        Cell::new("Issue").with_style(Attr::Bold),
+                        Cell::new("Account").with_style(Attr::Bold),
+                        Cell::new("Collection").with_style(Attr::Bold),
+                        Cell::new("Document").with_style(Attr::Bold),
+                        Cell::new("Details").with_style(Attr::Bold),
+                        Cell::new("Repaired").with_style(Attr::Bold),
+                    ]));
+
+                    for issue in &issues {
+                        let field = |name: &str| {
+                            issue
+                                .get(name)
+                                .map(|value| match value {
+                                    Value::String(value) => value.clone(),
+                                    value => value.to_string(),
+                                })
+                                .unwrap_or_default()
+                        };
+                        table.add_row(Row::new(vec![
+                            Cell::new(&field("kind")),
+                            Cell::new(&field("accountId")),
+                            Cell::new(&field("collection")),
+                            Cell::new(&field("documentId")),
+                            Cell::new(&field("details")),
+                            Cell::new(&field("repaired")),
+                        ]));
+                    }
+
+                    eprintln!();
+                    table.printstd();
+                    eprintln!();
+                }
+
+                let count = |name: &str| {
+                    report
+                        .get(name)
+                        .and_then(|value| value.as_u64())
+                        .unwrap_or_default()
+                };
+                let (total, accounts) = (count("total"), count("accounts"));
+                eprintln!(
+                    "\n\n{} issue{} found in {} account{}, {} repaired.\n",
+                    total,
+                    if total == 1 { "" } else { "s" },
+                    accounts,
+                    if accounts == 1 { "" } else { "s" },
+                    count("repaired"),
+                );
+            }
         }
     }
 }
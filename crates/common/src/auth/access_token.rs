@@ -6,7 +6,7 @@
 
 use directory::{
     backend::internal::{lookup::DirectoryStore, PrincipalField},
-    Permission, Principal, QueryBy,
+    Permission, Permissions, Principal, QueryBy,
 };
 use jmap_proto::{
     request::RequestMethod,
@@ -94,6 +94,27 @@ impl Server {
 
         // SPDX-SnippetEnd
 
+        // Accounts with an expired password are only allowed to change it
+        if self
+            .core
+            .auth
+            .password_policy
+            .as_ref()
+            .map_or(false, |policy| policy.is_expired(&principal))
+        {
+            let mut restricted = Permissions::new();
+            for permission in [
+                Permission::Authenticate,
+                Permission::AuthenticateOauth,
+                Permission::ManagePasswords,
+            ] {
+                if permissions.get(permission.id()) {
+                    restricted.set(permission.id());
+                }
+            }
+            permissions = restricted;
+        }
+
         Ok(AccessToken {
             primary_id: principal.id(),
             member_of: principal
@@ -363,6 +384,9 @@ impl AccessToken {
                 }
                 jmap_proto::method::get::RequestArguments::Quota => Permission::JmapQuotaGet,
                 jmap_proto::method::get::RequestArguments::Blob(_) => Permission::JmapBlobGet,
+                jmap_proto::method::get::RequestArguments::ShareNotification => {
+                    Permission::JmapShareNotificationGet
+                }
             },
             RequestMethod::Set(m) => match &m.arguments {
                 jmap_proto::method::set::RequestArguments::Email => Permission::JmapEmailSet,
@@ -380,6 +404,9 @@ impl AccessToken {
                 jmap_proto::method::set::RequestArguments::VacationResponse => {
                     Permission::JmapVacationResponseSet
                 }
+                jmap_proto::method::set::RequestArguments::ShareNotification => {
+                    Permission::JmapShareNotificationSet
+                }
             },
             RequestMethod::Changes(m) => match m.arguments {
                 jmap_proto::method::changes::RequestArguments::Email => {
@@ -400,6 +427,9 @@ impl AccessToken {
                 jmap_proto::method::changes::RequestArguments::Quota => {
                     Permission::JmapQuotaChanges
                 }
+                jmap_proto::method::changes::RequestArguments::ShareNotification => {
+                    Permission::JmapShareNotificationChanges
+                }
             },
             RequestMethod::Copy(m) => match m.arguments {
                 jmap_proto::method::copy::RequestArguments::Email => Permission::JmapEmailCopy,
@@ -426,6 +456,9 @@ impl AccessToken {
                 jmap_proto::method::query::RequestArguments::Quota => {
                     Permission::JmapQuotaQueryChanges
                 }
+                jmap_proto::method::query::RequestArguments::ShareNotification => {
+                    Permission::JmapShareNotificationQueryChanges
+                }
             },
             RequestMethod::Query(m) => match m.arguments {
                 jmap_proto::method::query::RequestArguments::Email(_) => Permission::JmapEmailQuery,
@@ -442,11 +475,16 @@ impl AccessToken {
                     Permission::JmapPrincipalQuery
                 }
                 jmap_proto::method::query::RequestArguments::Quota => Permission::JmapQuotaQuery,
+                jmap_proto::method::query::RequestArguments::ShareNotification => {
+                    Permission::JmapShareNotificationQuery
+                }
             },
             RequestMethod::SearchSnippet(_) => Permission::JmapSearchSnippet,
             RequestMethod::ValidateScript(_) => Permission::JmapSieveScriptValidate,
             RequestMethod::LookupBlob(_) => Permission::JmapBlobLookup,
             RequestMethod::UploadBlob(_) => Permission::JmapBlobUpload,
+            RequestMethod::SendMdn(_) => Permission::JmapMdnSend,
+            RequestMethod::ParseMdn(_) => Permission::JmapMdnParse,
             RequestMethod::Echo(_) => Permission::JmapEcho,
             RequestMethod::Error(_) => return Ok(()),
         };
@@ -0,0 +1,29 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::core::policy::PasswordPolicy;
+use utils::config::{cron::SimpleCron, utils::ParseValue, Config};
+
+use super::external::ExternalAuthConfig;
+
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    pub external: ExternalAuthConfig,
+    pub password_policy: Option<PasswordPolicy>,
+    pub deprovision_frequency: SimpleCron,
+}
+
+impl AuthConfig {
+    pub fn parse(config: &mut Config) -> Self {
+        AuthConfig {
+            external: ExternalAuthConfig::parse(config),
+            password_policy: PasswordPolicy::parse(config),
+            deprovision_frequency: config
+                .property_or_default::<SimpleCron>("authentication.deprovision.frequency", "0 3 7")
+                .unwrap_or_else(|| SimpleCron::parse_value("0 3 7").unwrap()),
+        }
+    }
+}
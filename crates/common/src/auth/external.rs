@@ -0,0 +1,276 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{net::IpAddr, sync::Arc};
+
+use directory::{backend::internal::PrincipalField, Directory, Permission, Principal, QueryBy};
+use regex::Regex;
+use utils::{
+    config::{utils::ParseValue, Config},
+    map::ttl_dashmap::TtlMap,
+};
+
+use crate::{listener::tls::ClientCertificate, Server};
+
+use super::AccessToken;
+
+#[derive(Debug, Clone, Default)]
+pub struct ExternalAuthConfig {
+    pub enable: bool,
+    pub sources: Vec<CertificateField>,
+    pub lookup: ExternalLookup,
+    pub rule: Option<(Regex, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertificateField {
+    SanEmail,
+    SanDns,
+    SanUri,
+    CommonName,
+    Subject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExternalLookup {
+    #[default]
+    Name,
+    Email,
+}
+
+impl ExternalAuthConfig {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut sources = config
+            .properties::<CertificateField>("authentication.external.map")
+            .into_iter()
+            .map(|(_, field)| field)
+            .collect::<Vec<_>>();
+        if sources.is_empty() {
+            sources = vec![CertificateField::SanEmail, CertificateField::CommonName];
+        }
+
+        ExternalAuthConfig {
+            enable: config
+                .property_or_default("authentication.external.enable", "false")
+                .unwrap_or(false),
+            sources,
+            lookup: config
+                .property_or_default("authentication.external.lookup", "name")
+                .unwrap_or_default(),
+            rule: config
+                .value("authentication.external.rule.match")
+                .map(|v| v.to_string())
+                .and_then(|pattern| match Regex::new(&pattern) {
+                    Ok(regex) => Some((
+                        regex,
+                        config
+                            .value("authentication.external.rule.replace")
+                            .unwrap_or("$0")
+                            .to_string(),
+                    )),
+                    Err(err) => {
+                        config.new_parse_error(
+                            "authentication.external.rule.match",
+                            format!("Invalid regular expression: {err}"),
+                        );
+                        None
+                    }
+                }),
+        }
+    }
+
+    pub fn identities(&self, cert: &ClientCertificate) -> Vec<String> {
+        let mut identities = Vec::new();
+        for field in &self.sources {
+            let values: &[String] = match field {
+                CertificateField::SanEmail => &cert.emails,
+                CertificateField::SanDns => &cert.dns_names,
+                CertificateField::SanUri => &cert.uris,
+                CertificateField::CommonName => cert.common_name.as_slice(),
+                CertificateField::Subject => std::slice::from_ref(&cert.subject),
+            };
+
+            for value in values {
+                let identity = match &self.rule {
+                    Some((regex, replace)) => {
+                        if let Some(captures) = regex.captures(value) {
+                            let mut result = String::with_capacity(value.len());
+                            captures.expand(replace, &mut result);
+                            result
+                        } else {
+                            continue;
+                        }
+                    }
+                    None => value.to_string(),
+                };
+
+                if !identity.is_empty() && !identities.contains(&identity) {
+                    identities.push(identity);
+                }
+            }
+        }
+
+        identities
+    }
+}
+
+impl Server {
+    pub async fn authenticate_external(
+        &self,
+        cert: &ClientCertificate,
+        authz_id: Option<&str>,
+        directory: Option<&Directory>,
+        session_id: u64,
+        remote_ip: IpAddr,
+    ) -> trc::Result<Arc<AccessToken>> {
+        let external = &self.core.auth.external;
+        if !external.enable {
+            return Err(trc::AuthEvent::Error
+                .into_err()
+                .details("EXTERNAL authentication is not enabled."));
+        }
+
+        // Map the certificate identities to principals
+        let directory = directory.unwrap_or(&self.core.storage.directory);
+        let mut principals: Vec<Principal> = Vec::new();
+        for identity in external.identities(cert) {
+            let principal = match external.lookup {
+                ExternalLookup::Name => directory.query(QueryBy::Name(&identity), true).await?,
+                ExternalLookup::Email => {
+                    match directory.email_to_ids(&identity).await?.as_slice() {
+                        [] => None,
+                        [id] => directory.query(QueryBy::Id(*id), true).await?,
+                        _ => {
+                            return Err(trc::AuthEvent::Failed
+                                .ctx(trc::Key::RemoteIp, remote_ip)
+                                .ctx(trc::Key::AccountName, cert.subject.clone())
+                                .details(format!(
+                                "Certificate identity {identity:?} maps to multiple principals."
+                            )));
+                        }
+                    }
+                }
+            };
+            if let Some(principal) = principal {
+                if !principals.iter().any(|p| p.id() == principal.id()) {
+                    principals.push(principal);
+                }
+            }
+        }
+
+        // Select the principal named by the authorization identity, if any
+        let principal = match authz_id {
+            Some(authz_id) => principals.into_iter().find(|principal| {
+                principal.name() == authz_id
+                    || principal.has_str_value(PrincipalField::Emails, authz_id)
+            }),
+            None => principals.into_iter().next(),
+        };
+        let principal = match principal {
+            Some(principal) => principal,
+            None => {
+                return Err(trc::AuthEvent::Failed
+                    .ctx(trc::Key::RemoteIp, remote_ip)
+                    .ctx(trc::Key::AccountName, cert.subject.clone())
+                    .ctx_opt(trc::Key::Details, authz_id.map(|s| s.to_string())));
+            }
+        };
+
+        trc::event!(
+            Auth(trc::AuthEvent::Success),
+            AccountName = principal.name().to_string(),
+            AccountId = principal.id(),
+            Details = cert.subject.clone(),
+            SpanId = session_id,
+        );
+
+        self.access_token_from_principal(principal)
+            .await
+            .and_then(|token| {
+                token
+                    .assert_has_permission(Permission::Authenticate)
+                    .map(|_| token)
+            })
+    }
+
+    pub(crate) async fn access_token_from_principal(
+        &self,
+        principal: Principal,
+    ) -> trc::Result<Arc<AccessToken>> {
+        if let Some(access_token) = self.inner.data.access_tokens.get_with_ttl(&principal.id()) {
+            Ok(access_token)
+        } else {
+            self.build_access_token(principal)
+                .await
+                .map(|access_token| {
+                    let access_token = Arc::new(access_token);
+                    self.cache_access_token(access_token.clone());
+                    access_token
+                })
+        }
+    }
+}
+
+impl ParseValue for CertificateField {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "san-email" => Ok(CertificateField::SanEmail),
+            "san-dns" => Ok(CertificateField::SanDns),
+            "san-uri" => Ok(CertificateField::SanUri),
+            "cn" => Ok(CertificateField::CommonName),
+            "subject" => Ok(CertificateField::Subject),
+            _ => Err(format!("Invalid certificate field {value:?}")),
+        }
+    }
+}
+
+impl ParseValue for ExternalLookup {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "name" => Ok(ExternalLookup::Name),
+            "email" => Ok(ExternalLookup::Email),
+            _ => Err(format!("Invalid external lookup type {value:?}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use crate::listener::tls::ClientCertificate;
+
+    use super::{CertificateField, ExternalAuthConfig, ExternalLookup};
+
+    #[test]
+    fn external_identities() {
+        let cert = ClientCertificate {
+            subject: "CN=printer01, O=Example".to_string(),
+            common_name: Some("printer01".to_string()),
+            emails: vec!["scanner@example.org".to_string()],
+            dns_names: vec!["printer01.devices.example.org".to_string()],
+            ..Default::default()
+        };
+
+        let mut config = ExternalAuthConfig {
+            enable: true,
+            sources: vec![CertificateField::SanEmail, CertificateField::CommonName],
+            lookup: ExternalLookup::Name,
+            rule: None,
+        };
+        assert_eq!(
+            config.identities(&cert),
+            vec!["scanner@example.org", "printer01"]
+        );
+
+        config.sources = vec![CertificateField::SanDns, CertificateField::SanUri];
+        config.rule = Some((
+            Regex::new(r"^([^.]+)\.devices\.example\.org$").unwrap(),
+            "$1@example.org".to_string(),
+        ));
+        assert_eq!(config.identities(&cert), vec!["printer01@example.org"]);
+    }
+}
@@ -17,6 +17,8 @@ use utils::map::{bitmap::Bitmap, ttl_dashmap::TtlMap, vec_map::VecMap};
 use crate::Server;
 
 pub mod access_token;
+pub mod config;
+pub mod external;
 pub mod oauth;
 pub mod roles;
 pub mod sasl;
@@ -72,21 +74,7 @@ impl Server {
                 }
             }
             _ => match self.authenticate_credentials(req, directory).await {
-                Ok(principal) => {
-                    if let Some(access_token) =
-                        self.inner.data.access_tokens.get_with_ttl(&principal.id())
-                    {
-                        Ok(access_token)
-                    } else {
-                        self.build_access_token(principal)
-                            .await
-                            .map(|access_token| {
-                                let access_token = Arc::new(access_token);
-                                self.cache_access_token(access_token.clone());
-                                access_token
-                            })
-                    }
-                }
+                Ok(principal) => self.access_token_from_principal(principal).await,
                 Err(err) => Err(err),
             },
         }
@@ -203,6 +191,35 @@ impl Server {
             Instant::now() + self.core.jmap.session_cache_ttl,
         );
     }
+
+    pub async fn deprovision_accounts(&self) {
+        for (id, directory) in &self.core.storage.directories {
+            match directory.deprovision().await {
+                Ok(account_ids) if !account_ids.is_empty() => {
+                    trc::event!(
+                        Housekeeper(trc::HousekeeperEvent::DeprovisionAccounts),
+                        Id = id.clone(),
+                        Total = account_ids.len(),
+                    );
+
+                    // Revoke active sessions
+                    for account_id in account_ids {
+                        self.inner.data.access_tokens.remove(&account_id);
+                        self.inner
+                            .data
+                            .http_auth_cache
+                            .retain(|_, id| id.item != account_id);
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    trc::error!(err
+                        .details("Failed to deprovision accounts")
+                        .ctx(trc::Key::Id, id.clone()));
+                }
+            }
+        }
+    }
 }
 
 impl<'x> AuthRequest<'x> {
@@ -9,7 +9,7 @@ use jmap_proto::{
     request::capability::{
         BlobCapabilities, Capabilities, Capability, CoreCapabilities, EmptyCapabilities,
         MailCapabilities, SieveAccountCapabilities, SieveSessionCapabilities,
-        SubmissionCapabilities,
+        SubmissionCapabilities, WebPushVapidCapabilities, MAX_DELAYED_SEND,
     },
     types::type_state::DataType,
 };
@@ -76,7 +76,7 @@ impl JmapConfig {
         self.capabilities.account.append(
             Capability::Submission,
             Capabilities::Submission(SubmissionCapabilities {
-                max_delayed_send: 86400 * 30,
+                max_delayed_send: MAX_DELAYED_SEND as usize,
                 submission_extensions: VecMap::from_iter([
                     ("FUTURERELEASE".to_string(), Vec::new()),
                     ("SIZE".to_string(), Vec::new()),
@@ -174,5 +174,31 @@ impl JmapConfig {
             Capability::Quota,
             Capabilities::Empty(EmptyCapabilities::default()),
         );
+
+        // Add MDN capabilities
+        self.capabilities.session.append(
+            Capability::Mdn,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+        self.capabilities.account.append(
+            Capability::Mdn,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+
+        // Add Principals capabilities, account capabilities are set per session
+        self.capabilities.session.append(
+            Capability::Principals,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+
+        // Add Web Push VAPID capabilities
+        if let Some(vapid) = &self.push_vapid {
+            self.capabilities.session.append(
+                Capability::WebPushVapid,
+                Capabilities::WebPushVapid(WebPushVapidCapabilities {
+                    application_server_key: vapid.public_key.clone(),
+                }),
+            );
+        }
     }
 }
@@ -6,3 +6,4 @@
 
 pub mod capabilities;
 pub mod settings;
+pub mod vapid;
@@ -11,9 +11,14 @@ use mail_parser::HeaderName;
 use nlp::language::Language;
 use utils::config::{cron::SimpleCron, utils::ParseValue, Config, Rate};
 
+use super::vapid::VapidKey;
+
 #[derive(Default, Clone)]
 pub struct JmapConfig {
     pub default_language: Language,
+    pub fts_attachment_types: Vec<AttachmentType>,
+    pub fts_attachment_max_size: usize,
+    pub fts_attachment_timeout: Duration,
     pub query_max_results: usize,
     pub snippet_max_results: usize,
 
@@ -57,6 +62,7 @@ pub struct JmapConfig {
     pub push_timeout: Duration,
     pub push_verify_timeout: Duration,
     pub push_throttle: Duration,
+    pub push_vapid: Option<VapidKey>,
 
     pub web_socket_throttle: Duration,
     pub web_socket_timeout: Duration,
@@ -89,6 +95,19 @@ pub struct DefaultFolder {
     pub create: bool,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AttachmentType {
+    Docx,
+    Xlsx,
+    Pptx,
+    Odt,
+    Ods,
+    Odp,
+    Csv,
+    Ics,
+    Vcf,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum SpecialUse {
     Inbox,
@@ -239,6 +258,25 @@ impl JmapConfig {
                     .unwrap_or("en"),
             )
             .unwrap_or(Language::English),
+            fts_attachment_types: if config
+                .values("storage.full-text.attachments.types")
+                .next()
+                .is_some()
+            {
+                config
+                    .properties::<AttachmentType>("storage.full-text.attachments.types")
+                    .into_iter()
+                    .map(|(_, attachment_type)| attachment_type)
+                    .collect()
+            } else {
+                AttachmentType::ALL.to_vec()
+            },
+            fts_attachment_max_size: config
+                .property("storage.full-text.attachments.max-size")
+                .unwrap_or(10 * 1024 * 1024),
+            fts_attachment_timeout: config
+                .property_or_default("storage.full-text.attachments.timeout", "5s")
+                .unwrap_or_else(|| Duration::from_secs(5)),
             query_max_results: config
                 .property("jmap.protocol.query.max-results")
                 .unwrap_or(5000),
@@ -367,6 +405,7 @@ impl JmapConfig {
             push_throttle: config
                 .property_or_default("jmap.push.throttle", "1s")
                 .unwrap_or_else(|| Duration::from_secs(1)),
+            push_vapid: VapidKey::parse(config),
             session_purge_frequency: config
                 .property_or_default::<SimpleCron>("jmap.session.purge.frequency", "15 * *")
                 .unwrap_or_else(|| SimpleCron::parse_value("15 * *").unwrap()),
@@ -395,6 +434,42 @@ impl JmapConfig {
     }
 }
 
+impl AttachmentType {
+    pub const ALL: [AttachmentType; 9] = [
+        AttachmentType::Docx,
+        AttachmentType::Xlsx,
+        AttachmentType::Pptx,
+        AttachmentType::Odt,
+        AttachmentType::Ods,
+        AttachmentType::Odp,
+        AttachmentType::Csv,
+        AttachmentType::Ics,
+        AttachmentType::Vcf,
+    ];
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "docx" => Some(AttachmentType::Docx),
+            "xlsx" => Some(AttachmentType::Xlsx),
+            "pptx" => Some(AttachmentType::Pptx),
+            "odt" => Some(AttachmentType::Odt),
+            "ods" => Some(AttachmentType::Ods),
+            "odp" => Some(AttachmentType::Odp),
+            "csv" => Some(AttachmentType::Csv),
+            "ics" => Some(AttachmentType::Ics),
+            "vcf" | "vcard" => Some(AttachmentType::Vcf),
+            _ => None,
+        }
+    }
+}
+
+impl ParseValue for AttachmentType {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        AttachmentType::from_extension(value)
+            .ok_or_else(|| format!("Unknown attachment type {value:?}"))
+    }
+}
+
 impl ParseValue for SpecialUse {
     fn parse_value(value: &str) -> Result<Self, String> {
         match value {
@@ -0,0 +1,96 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use base64::{engine::general_purpose, Engine};
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use store::write::now;
+use utils::config::Config;
+
+use crate::config::build_ecdsa_pem;
+
+// RFC 8292 - Voluntary Application Server Identification (VAPID)
+const VAPID_TOKEN_EXPIRY: u64 = 12 * 60 * 60;
+
+#[derive(Clone)]
+pub struct VapidKey {
+    key_pair: Arc<EcdsaKeyPair>,
+    pub public_key: String,
+    pub subject: String,
+}
+
+impl VapidKey {
+    pub fn parse(config: &mut Config) -> Option<Self> {
+        let key_pair = match build_ecdsa_pem(
+            &ECDSA_P256_SHA256_FIXED_SIGNING,
+            config.value("jmap.push.vapid.private-key")?,
+        ) {
+            Ok(key_pair) => key_pair,
+            Err(err) => {
+                config.new_build_error(
+                    "jmap.push.vapid.private-key",
+                    format!("Failed to build VAPID key: {}", err),
+                );
+                return None;
+            }
+        };
+        let subject = config
+            .value("jmap.push.vapid.subject")
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| {
+                format!(
+                    "mailto:postmaster@{}",
+                    config
+                        .value("lookup.default.hostname")
+                        .unwrap_or("localhost")
+                )
+            });
+
+        VapidKey {
+            public_key: general_purpose::URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+            key_pair: Arc::new(key_pair),
+            subject,
+        }
+        .into()
+    }
+
+    pub fn generate() -> Option<String> {
+        EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+            .ok()
+            .map(|pkcs8| pem::encode(&pem::Pem::new("PRIVATE KEY", pkcs8.as_ref().to_vec())))
+    }
+
+    pub fn authorization(&self, endpoint: &str) -> Option<String> {
+        // The audience is the origin of the push resource
+        let uri = endpoint.parse::<hyper::Uri>().ok()?;
+        let audience = format!("{}://{}", uri.scheme_str()?, uri.authority()?.as_str());
+
+        let header = general_purpose::URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = general_purpose::URL_SAFE_NO_PAD.encode(
+            serde_json::json!({
+                "aud": audience,
+                "exp": now() + VAPID_TOKEN_EXPIRY,
+                "sub": self.subject,
+            })
+            .to_string(),
+        );
+        let message = format!("{header}.{claims}");
+        let signature = self
+            .key_pair
+            .sign(&SystemRandom::new(), message.as_bytes())
+            .ok()?;
+
+        Some(format!(
+            "vapid t={message}.{}, k={}",
+            general_purpose::URL_SAFE_NO_PAD.encode(signature.as_ref()),
+            self.public_key
+        ))
+    }
+}
@@ -19,13 +19,19 @@ use telemetry::Metrics;
 use utils::config::{utils::AsKey, Config};
 
 use crate::{
-    auth::oauth::config::OAuthConfig, expr::*, listener::tls::AcmeProviders,
-    manager::config::ConfigManager, Core, Network, Security,
+    auth::{config::AuthConfig, oauth::config::OAuthConfig},
+    expr::*,
+    listener::tls::AcmeProviders,
+    manager::config::ConfigManager,
+    Core, Network, Security,
 };
 
 use self::{
-    imap::ImapConfig, jmap::settings::JmapConfig, scripts::Scripting, smtp::SmtpConfig,
-    storage::Storage,
+    imap::ImapConfig,
+    jmap::settings::JmapConfig,
+    scripts::Scripting,
+    smtp::SmtpConfig,
+    storage::{BackupSettings, MigrationSettings, Storage},
 };
 
 pub mod imap;
@@ -177,6 +183,7 @@ impl Core {
             smtp: SmtpConfig::parse(config).await,
             jmap: JmapConfig::parse(config),
             imap: ImapConfig::parse(config),
+            auth: AuthConfig::parse(config),
             oauth: OAuthConfig::parse(config),
             acme: AcmeProviders::parse(config),
             metrics: Metrics::parse(config),
@@ -188,6 +195,8 @@ impl Core {
                 directory,
                 directories: directories.directories,
                 purge_schedules: stores.purge_schedules,
+                backup: BackupSettings::parse(config),
+                migration: MigrationSettings::parse(config),
                 config: config_manager,
                 stores: stores.stores,
                 lookups: stores.lookup_stores,
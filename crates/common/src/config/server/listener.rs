@@ -4,12 +4,17 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{io::Cursor, net::SocketAddr, sync::Arc, time::Duration};
 
 use rustls::{
-    crypto::ring::{default_provider, ALL_CIPHER_SUITES},
-    ServerConfig, SupportedCipherSuite, ALL_VERSIONS,
+    crypto::{
+        ring::{default_provider, ALL_CIPHER_SUITES},
+        CryptoProvider,
+    },
+    server::{danger::ClientCertVerifier, WebPkiClientVerifier},
+    RootCertStore, ServerConfig, SupportedCipherSuite, ALL_VERSIONS,
 };
+use rustls_pemfile::certs;
 
 use tokio::net::TcpSocket;
 use tokio_rustls::TlsAcceptor;
@@ -273,8 +278,48 @@ impl Listeners {
                         .collect();
                 }
 
+                // Build client certificate verifier
+                let provider = Arc::new(provider);
+                let client_auth = match config
+                    .value_or_else(
+                        ("server.listener", id, "tls.client-auth"),
+                        "server.tls.client-auth",
+                    )
+                    .unwrap_or("none")
+                    .to_string()
+                    .as_str()
+                {
+                    "none" => None,
+                    mode @ ("optional" | "require") => {
+                        let is_required = mode == "require";
+                        let ca_key =
+                            if config.contains_key(("server.listener", id, "tls.client-ca")) {
+                                ("server.listener", id, "tls.client-ca").as_key()
+                            } else {
+                                "server.tls.client-ca".as_key()
+                            };
+                        match config.value_require(ca_key.as_str()).map(|ca| {
+                            build_client_verifier(ca.as_bytes(), provider.clone(), is_required)
+                        }) {
+                            Some(Ok(verifier)) => Some(verifier),
+                            Some(Err(err)) => {
+                                config.new_build_error(ca_key, err);
+                                continue;
+                            }
+                            None => continue,
+                        }
+                    }
+                    mode => {
+                        config.new_parse_error(
+                            ("server.listener", id, "tls.client-auth"),
+                            format!("Invalid client authentication mode {mode:?}"),
+                        );
+                        continue;
+                    }
+                };
+
                 // Build server config
-                let mut server_config = match ServerConfig::builder_with_provider(provider.into())
+                let mut server_config = match ServerConfig::builder_with_provider(provider)
                     .with_protocol_versions(if tls_v3 == tls_v2 {
                         ALL_VERSIONS
                     } else if tls_v3 {
@@ -282,9 +327,12 @@ impl Listeners {
                     } else {
                         TLS12_VERSION
                     }) {
-                    Ok(server_config) => server_config
-                        .with_no_client_auth()
-                        .with_cert_resolver(resolver.clone()),
+                    Ok(server_config) => if let Some(verifier) = client_auth {
+                        server_config.with_client_cert_verifier(verifier)
+                    } else {
+                        server_config.with_no_client_auth()
+                    }
+                    .with_cert_resolver(resolver.clone()),
                     Err(err) => {
                         config.new_build_error(
                             ("server.listener", id, "tls"),
@@ -320,6 +368,30 @@ impl Listeners {
     }
 }
 
+fn build_client_verifier(
+    ca: &[u8],
+    provider: Arc<CryptoProvider>,
+    is_required: bool,
+) -> Result<Arc<dyn ClientCertVerifier>, String> {
+    let mut roots = RootCertStore::empty();
+    for cert in certs(&mut Cursor::new(ca)) {
+        roots
+            .add(cert.map_err(|err| format!("Failed to read CA certificate: {err}"))?)
+            .map_err(|err| format!("Failed to add CA certificate: {err}"))?;
+    }
+    if roots.is_empty() {
+        return Err("No CA certificates found.".to_string());
+    }
+
+    let builder = WebPkiClientVerifier::builder_with_provider(roots.into(), provider);
+    if is_required {
+        builder.build()
+    } else {
+        builder.allow_unauthenticated().build()
+    }
+    .map_err(|err| format!("Failed to build client certificate verifier: {err}"))
+}
+
 impl ParseValue for ServerProtocol {
     fn parse_value(value: &str) -> Result<Self, String> {
         if value.eq_ignore_ascii_case("smtp") {
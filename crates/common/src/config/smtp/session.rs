@@ -899,6 +899,7 @@ impl ParseValue for Mechanism {
             "PLAIN" => AUTH_PLAIN,
             "XOAUTH2" => AUTH_XOAUTH2,
             "OAUTHBEARER" => AUTH_OAUTHBEARER,
+            "EXTERNAL" => AUTH_EXTERNAL,
             /*"SCRAM-SHA-256-PLUS" => AUTH_SCRAM_SHA_256_PLUS,
             "SCRAM-SHA-256" => AUTH_SCRAM_SHA_256,
             "SCRAM-SHA-1-PLUS" => AUTH_SCRAM_SHA_1_PLUS,
@@ -914,7 +915,6 @@ impl ParseValue for Mechanism {
             "EAP-AES128-PLUS" => AUTH_EAP_AES128_PLUS,
             "ECDH-X25519-CHALLENGE" => AUTH_ECDH_X25519_CHALLENGE,
             "ECDSA-NIST256P-CHALLENGE" => AUTH_ECDSA_NIST256P_CHALLENGE,
-            "EXTERNAL" => AUTH_EXTERNAL,
             "GS2-KRB5" => AUTH_GS2_KRB5,
             "GS2-KRB5-PLUS" => AUTH_GS2_KRB5_PLUS,
             "GSS-SPNEGO" => AUTH_GSS_SPNEGO,
@@ -977,7 +977,8 @@ impl ConstantValue for Mechanism {
             .add_constant("login", Mechanism(AUTH_LOGIN))
             .add_constant("plain", Mechanism(AUTH_PLAIN))
             .add_constant("xoauth2", Mechanism(AUTH_XOAUTH2))
-            .add_constant("oauthbearer", Mechanism(AUTH_OAUTHBEARER));
+            .add_constant("oauthbearer", Mechanism(AUTH_OAUTHBEARER))
+            .add_constant("external", Mechanism(AUTH_EXTERNAL));
     }
 }
 
@@ -4,11 +4,12 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use ahash::AHashMap;
 use directory::Directory;
 use store::{write::purge::PurgeSchedule, BlobStore, FtsStore, LookupStore, Store};
+use utils::config::{cron::SimpleCron, utils::ParseValue, Config};
 
 use crate::manager::config::ConfigManager;
 
@@ -21,6 +22,8 @@ pub struct Storage {
     pub directory: Arc<Directory>,
     pub directories: AHashMap<String, Arc<Directory>>,
     pub purge_schedules: Vec<PurgeSchedule>,
+    pub backup: Option<BackupSettings>,
+    pub migration: MigrationSettings,
     pub config: ConfigManager,
 
     pub stores: AHashMap<String, Store>,
@@ -28,3 +31,74 @@ pub struct Storage {
     pub lookups: AHashMap<String, LookupStore>,
     pub ftss: AHashMap<String, FtsStore>,
 }
+
+#[derive(Debug, Clone)]
+pub struct BackupSettings {
+    pub path: PathBuf,
+    pub schedule: Option<SimpleCron>,
+    pub full_interval: usize,
+    pub retention: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationSettings {
+    pub path: PathBuf,
+    pub cutover_grace: Duration,
+}
+
+impl BackupSettings {
+    pub fn parse(config: &mut Config) -> Option<Self> {
+        let path = PathBuf::from(config.value("storage.backup.path")?);
+        let schedule = if config
+            .property_or_default::<bool>("storage.backup.enable", "false")
+            .unwrap_or_default()
+        {
+            config
+                .property_or_default::<SimpleCron>("storage.backup.frequency", "0 2 *")
+                .unwrap_or_else(|| SimpleCron::parse_value("0 2 *").unwrap())
+                .into()
+        } else {
+            None
+        };
+
+        Some(BackupSettings {
+            path,
+            schedule,
+            full_interval: config
+                .property_or_default("storage.backup.full-interval", "7")
+                .unwrap_or(7),
+            retention: config
+                .property_or_default("storage.backup.retention", "4")
+                .unwrap_or(4),
+        })
+    }
+}
+
+impl MigrationSettings {
+    pub fn parse(config: &mut Config) -> Self {
+        // Migration state is kept next to the backups unless configured otherwise
+        let path = if let Some(path) = config.value("storage.migration.path") {
+            PathBuf::from(path)
+        } else if let Some(path) = config.value("storage.backup.path") {
+            PathBuf::from(path).join("migration")
+        } else {
+            MigrationSettings::default().path
+        };
+
+        MigrationSettings {
+            path,
+            cutover_grace: config
+                .property_or_default("storage.migration.cutover-grace", "5s")
+                .unwrap_or(Duration::from_secs(5)),
+        }
+    }
+}
+
+impl Default for MigrationSettings {
+    fn default() -> Self {
+        MigrationSettings {
+            path: std::env::temp_dir().join("stalwart-migration"),
+            cutover_grace: Duration::from_secs(5),
+        }
+    }
+}
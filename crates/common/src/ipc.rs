@@ -60,7 +60,22 @@ pub enum HousekeeperEvent {
         provider_id: String,
         renew_at: Instant,
     },
+    SnoozeReschedule {
+        due: Instant,
+    },
     Purge(PurgeType),
+    Backup {
+        full: bool,
+    },
+    Migrate {
+        store: String,
+        blob: Option<String>,
+    },
+    Fsck {
+        id: u64,
+        account_id: Option<u32>,
+        repair: bool,
+    },
     ReloadSettings,
     Exit,
 }
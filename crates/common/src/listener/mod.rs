@@ -22,7 +22,10 @@ use crate::{
     Server,
 };
 
-use self::limiter::{ConcurrencyLimiter, InFlight};
+use self::{
+    limiter::{ConcurrencyLimiter, InFlight},
+    tls::ClientCertificate,
+};
 
 pub mod acme;
 pub mod blocked;
@@ -77,6 +80,7 @@ pub struct SessionData<T: SessionStream> {
 pub trait SessionStream: AsyncRead + AsyncWrite + Unpin + 'static + Sync + Send {
     fn is_tls(&self) -> bool;
     fn tls_version_and_cipher(&self) -> (Cow<'static, str>, Cow<'static, str>);
+    fn client_certificate(&self) -> Option<ClientCertificate>;
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
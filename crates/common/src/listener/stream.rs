@@ -13,7 +13,7 @@ use tokio::{
 };
 use tokio_rustls::server::TlsStream;
 
-use super::SessionStream;
+use super::{tls::ClientCertificate, SessionStream};
 
 impl SessionStream for TcpStream {
     fn is_tls(&self) -> bool {
@@ -23,6 +23,10 @@ impl SessionStream for TcpStream {
     fn tls_version_and_cipher(&self) -> (Cow<'static, str>, Cow<'static, str>) {
         (Cow::Borrowed(""), Cow::Borrowed(""))
     }
+
+    fn client_certificate(&self) -> Option<ClientCertificate> {
+        None
+    }
 }
 
 impl<T: SessionStream> SessionStream for TlsStream<T> {
@@ -62,6 +66,15 @@ impl<T: SessionStream> SessionStream for TlsStream<T> {
             .into(),
         )
     }
+
+    fn client_certificate(&self) -> Option<ClientCertificate> {
+        // Certificates are only present once rustls has verified them against the client CA
+        self.get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(|cert| ClientCertificate::parse(cert.as_ref()))
+    }
 }
 
 impl SessionStream for ProxiedStream<TcpStream> {
@@ -82,6 +95,10 @@ impl SessionStream for ProxiedStream<TcpStream> {
             })
             .unwrap_or((Cow::Borrowed("unknown"), Cow::Borrowed("unknown")))
     }
+
+    fn client_certificate(&self) -> Option<ClientCertificate> {
+        None
+    }
 }
 
 #[derive(Default)]
@@ -140,4 +157,8 @@ impl SessionStream for NullIo {
             std::borrow::Cow::Borrowed(""),
         )
     }
+
+    fn client_certificate(&self) -> Option<ClientCertificate> {
+        None
+    }
 }
@@ -7,6 +7,7 @@
 use std::{
     cmp::Ordering,
     fmt::{self, Formatter},
+    net::{Ipv4Addr, Ipv6Addr},
     sync::Arc,
 };
 
@@ -19,6 +20,11 @@ use rustls::{
 };
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio_rustls::{Accept, LazyConfigAcceptor};
+use x509_parser::{
+    certificate::X509Certificate,
+    der_parser::asn1_rs::FromDer,
+    extensions::{GeneralName, ParsedExtension},
+};
 
 use crate::{Inner, Server};
 
@@ -43,6 +49,16 @@ pub struct CertificateResolver {
     pub inner: Arc<Inner>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientCertificate {
+    pub subject: String,
+    pub common_name: Option<String>,
+    pub emails: Vec<String>,
+    pub dns_names: Vec<String>,
+    pub uris: Vec<String>,
+    pub ip_addresses: Vec<String>,
+}
+
 impl CertificateResolver {
     pub fn new(inner: Arc<Inner>) -> Self {
         Self { inner }
@@ -204,6 +220,52 @@ where
     }
 }
 
+impl ClientCertificate {
+    pub fn parse(der: &[u8]) -> Option<Self> {
+        let (_, cert) = X509Certificate::from_der(der).ok()?;
+        let mut result = ClientCertificate {
+            subject: cert.subject().to_string(),
+            common_name: cert
+                .subject()
+                .iter_common_name()
+                .next()
+                .and_then(|cn| cn.as_str().ok())
+                .map(|cn| cn.to_string()),
+            ..Default::default()
+        };
+
+        for ext in cert.extensions() {
+            if let ParsedExtension::SubjectAlternativeName(san) = ext.parsed_extension() {
+                for name in &san.general_names {
+                    match name {
+                        GeneralName::RFC822Name(email) => {
+                            result.emails.push(email.to_lowercase());
+                        }
+                        GeneralName::DNSName(name) => {
+                            result.dns_names.push(name.to_lowercase());
+                        }
+                        GeneralName::URI(uri) => {
+                            result.uris.push(uri.to_string());
+                        }
+                        GeneralName::IPAddress(ip) => match ip.len() {
+                            4 => result.ip_addresses.push(
+                                Ipv4Addr::from(<[u8; 4]>::try_from(*ip).unwrap()).to_string(),
+                            ),
+                            16 => result.ip_addresses.push(
+                                Ipv6Addr::from(<[u8; 16]>::try_from(*ip).unwrap()).to_string(),
+                            ),
+                            _ => {}
+                        },
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Some(result)
+    }
+}
+
 impl std::fmt::Debug for CertificateResolver {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("CertificateResolver").finish()
@@ -5,33 +5,45 @@
  */
 
 use std::{
-    collections::BTreeSet,
     io::{BufWriter, Write},
     ops::Range,
     path::{Path, PathBuf},
-    sync::mpsc::{self, SyncSender},
+    sync::{
+        mpsc::{self, SyncSender},
+        Arc,
+    },
 };
 
 use ahash::{AHashMap, AHashSet};
 use jmap_proto::types::{collection::Collection, property::Property};
+use sha2::{Digest, Sha256};
 use store::{
+    dispatch::READ_ONLY_KEY,
+    roaring::RoaringBitmap,
     write::{
-        key::DeserializeBigEndian, AnyKey, BitmapClass, BitmapHash, BlobOp, DirectoryClass,
+        key::DeserializeBigEndian, now, AnyKey, BitmapClass, BitmapHash, BlobOp, DirectoryClass,
         LookupClass, QueueClass, QueueEvent, TagValue, ValueClass,
     },
-    BitmapKey, Deserialize, IndexKey, IterateParams, LogKey, Serialize, ValueKey,
-    SUBSPACE_BITMAP_ID, SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, U32_LEN, U64_LEN,
+    BitmapKey, IndexKey, IterateParams, LogKey, Serialize, ValueKey, SUBSPACE_BITMAP_ID,
+    SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, U32_LEN, U64_LEN,
 };
 
+use trc::AddContext;
 use utils::{
     codec::leb128::{Leb128Reader, Leb128_},
-    failed, BlobHash, UnwrapFailure, BLOB_HASH_LEN,
+    BlobHash, BLOB_HASH_LEN,
 };
 
 use crate::Core;
 
+use super::manifest::{
+    format_timestamp, BackupFile, BackupKind, BackupManifest, ChangedDocuments, ACCOUNTS_DIR,
+    MANIFEST_VERSION,
+};
+
 pub(super) const MAGIC_MARKER: u8 = 123;
 pub(super) const FILE_VERSION: u8 = 2;
+pub(super) const BLOB_INDEX_FILE: &str = "blob_index";
 
 #[derive(Debug)]
 pub(super) enum Op {
@@ -59,266 +71,473 @@ pub(super) enum Family {
     None = 255,
 }
 
-type TaskHandle = (tokio::task::JoinHandle<()>, std::thread::JoinHandle<()>);
+type WriterHandle = std::thread::JoinHandle<trc::Result<BackupFile>>;
+type TaskHandle = (tokio::task::JoinHandle<trc::Result<()>>, Vec<WriterHandle>);
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq)]
 pub struct BackupParams {
-    dest: PathBuf,
-    families: AHashSet<Family>,
+    pub(super) dest: PathBuf,
+    pub(super) families: AHashSet<Family>,
+    pub(super) scope: BackupScope,
+    pub(super) verbose: bool,
+}
+
+/// Limits an export to a set of accounts and documents and skips the contents
+/// of blobs that are already stored in a previous backup of the chain.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(super) struct BackupScope {
+    pub accounts: Option<Arc<AHashSet<u32>>>,
+    // Documents changed since the parent backup, exported along with `accounts`
+    pub changes: Option<Arc<ChangedDocuments>>,
+    pub blobs: Arc<AHashSet<Vec<u8>>>,
+    // Skips queued message links and blobs not linked by the exported accounts
+    pub accounts_only: bool,
+    // Exports blob markers without their contents
+    pub skip_contents: bool,
 }
 
+pub(super) struct BackupWriter(SyncSender<Op>);
+
 impl Core {
     pub async fn backup(&self, params: BackupParams) {
         if !params.dest.exists() {
-            std::fs::create_dir_all(&params.dest).failed("Failed to create backup directory");
+            if let Err(err) = std::fs::create_dir_all(&params.dest) {
+                eprintln!("Failed to create backup directory: {err}");
+                std::process::exit(1);
+            }
         } else if !params.dest.is_dir() {
             eprintln!("Backup destination {:?} is not a directory.", params.dest);
             std::process::exit(1);
         }
 
+        // Exports of all families are written with a manifest so they can be
+        // used as the base of incremental backups and verified on restore
+        let result = if params.families.is_empty() {
+            self.create_backup(params, None).await.map(|_| ())
+        } else {
+            self.export(&params).await.map(|_| ())
+        };
+
+        if let Err(err) = result {
+            eprintln!("Backup failed: {err}");
+            std::process::exit(1);
+        }
+    }
+
+    /// Exports the data of a single account to a new directory under the
+    /// configured backup path.
+    pub async fn backup_account(&self, account_id: u32) -> trc::Result<BackupManifest> {
+        let settings = self.storage.backup.as_ref().ok_or_else(|| {
+            trc::StoreEvent::NotConfigured
+                .into_err()
+                .details("Backup path is not configured")
+        })?;
+
+        let dest = settings
+            .path
+            .join(ACCOUNTS_DIR)
+            .join(format!("{account_id}-{}", format_timestamp(now())));
+        if dest.exists() {
+            return Err(trc::ManageEvent::AlreadyExists
+                .into_err()
+                .details("Backup already exists")
+                .ctx(trc::Key::Path, dest.to_string_lossy().into_owned()));
+        }
+
+        match self.export_account(account_id, dest.clone()).await {
+            Ok(manifest) => Ok(manifest),
+            Err(err) => {
+                let _ = std::fs::remove_dir_all(&dest);
+                Err(err)
+            }
+        }
+    }
+
+    /// Exports the properties, indexes, bitmaps, ACLs and blobs of an account,
+    /// which includes its mailboxes, Sieve scripts and identities.
+    pub async fn export_account(
+        &self,
+        account_id: u32,
+        dest: PathBuf,
+    ) -> trc::Result<BackupManifest> {
+        std::fs::create_dir_all(&dest).map_err(|err| io_error(&dest, err))?;
+        let created = now();
+        let quota = self
+            .storage
+            .data
+            .get_counter(DirectoryClass::UsedQuota(account_id))
+            .await
+            .caused_by(trc::location!())?;
+        let params = BackupParams {
+            dest,
+            families: [
+                Family::Property,
+                Family::FtsIndex,
+                Family::Acl,
+                Family::Blob,
+                Family::Index,
+                Family::Bitmap,
+            ]
+            .into_iter()
+            .collect(),
+            scope: BackupScope {
+                accounts: Some(Arc::new(AHashSet::from_iter([account_id]))),
+                changes: None,
+                blobs: Default::default(),
+                accounts_only: true,
+                skip_contents: false,
+            },
+            verbose: false,
+        };
+
+        let files = self.export(&params).await?;
+        let manifest = BackupManifest {
+            version: MANIFEST_VERSION,
+            id: params
+                .dest
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default()
+                .to_string(),
+            kind: BackupKind::Account,
+            parent: None,
+            created,
+            accounts: vec![account_id],
+            changed: vec![],
+            principals: vec![],
+            pending: vec![],
+            quota: Some(quota),
+            change_id: 0,
+            files,
+        };
+        manifest.write(&params.dest)?;
+
+        Ok(manifest)
+    }
+
+    pub(super) async fn export(&self, params: &BackupParams) -> trc::Result<Vec<BackupFile>> {
         let mut sync_handles = Vec::new();
+        let mut result = Ok(());
 
         for (async_handle, sync_handle) in [
             params
                 .has_family(Family::Property)
-                .then(|| self.backup_properties(&params.dest)),
+                .then(|| self.backup_properties(params)),
             params
                 .has_family(Family::FtsIndex)
-                .then(|| self.backup_fts_index(&params.dest)),
+                .then(|| self.backup_fts_index(params)),
             params
                 .has_family(Family::Acl)
-                .then(|| self.backup_acl(&params.dest)),
+                .then(|| self.backup_acl(params)),
             params
                 .has_family(Family::Blob)
-                .then(|| self.backup_blob(&params.dest)),
+                .then(|| self.backup_blob(params)),
             params
                 .has_family(Family::Config)
-                .then(|| self.backup_config(&params.dest)),
+                .then(|| self.backup_config(params)),
             params
                 .has_family(Family::LookupValue)
-                .then(|| self.backup_lookup(&params.dest)),
+                .then(|| self.backup_lookup(params)),
             params
                 .has_family(Family::Directory)
-                .then(|| self.backup_directory(&params.dest)),
+                .then(|| self.backup_directory(params)),
             params
                 .has_family(Family::Queue)
-                .then(|| self.backup_queue(&params.dest)),
+                .then(|| self.backup_queue(params)),
             params
                 .has_family(Family::Index)
-                .then(|| self.backup_index(&params.dest)),
+                .then(|| self.backup_index(params)),
             params
                 .has_family(Family::Bitmap)
-                .then(|| self.backup_bitmaps(&params.dest)),
+                .then(|| self.backup_bitmaps(params)),
             params
                 .has_family(Family::Log)
-                .then(|| self.backup_logs(&params.dest)),
+                .then(|| self.backup_logs(params)),
         ]
         .into_iter()
         .flatten()
         {
-            async_handle.await.failed("Task failed");
-            sync_handles.push(sync_handle);
+            let task_result = async_handle.await.map_err(|err| {
+                trc::EventType::Server(trc::ServerEvent::ThreadError)
+                    .reason(err)
+                    .details("Backup task failed")
+            });
+            if result.is_ok() {
+                result = task_result.and_then(|r| r);
+            }
+            sync_handles.extend(sync_handle);
         }
 
+        // Writers terminate once their tasks drop the sending half
+        let mut files = Vec::with_capacity(sync_handles.len());
         for handle in sync_handles {
-            handle.join().expect("Failed to join thread");
+            match handle.join() {
+                Ok(Ok(file)) => files.push(file),
+                Ok(Err(err)) => {
+                    if result.is_ok() {
+                        result = Err(err);
+                    }
+                }
+                Err(_) => {
+                    if result.is_ok() {
+                        result = Err(trc::EventType::Server(trc::ServerEvent::ThreadError)
+                            .into_err()
+                            .details("Failed to join backup writer thread"));
+                    }
+                }
+            }
         }
+
+        result.map(|_| files)
     }
 
-    fn backup_properties(&self, dest: &Path) -> TaskHandle {
+    fn backup_properties(&self, params: &BackupParams) -> TaskHandle {
         let store = self.storage.data.clone();
-        let (handle, writer) = spawn_writer(dest.join("property"));
+        let scope = params.scope.clone();
+        let (handle, writer) = spawn_writer(params.dest.join("property"), params.verbose);
         (
             tokio::spawn(async move {
-                writer
-                    .send(Op::Family(Family::Property))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::Property))?;
 
-                let mut keys = BTreeSet::new();
+                let mut last_account_id = u32::MAX;
+                let mut last_collection = u8::MAX;
+                let mut last_document_id = u32::MAX;
 
-                store
-                    .iterate(
-                        IterateParams::new(
-                            ValueKey {
-                                account_id: 0,
-                                collection: 0,
-                                document_id: 0,
-                                class: ValueClass::Property(0),
-                            },
-                            ValueKey {
-                                account_id: u32::MAX,
-                                collection: u8::MAX,
-                                document_id: u32::MAX,
-                                class: ValueClass::Property(u8::MAX),
-                            },
-                        )
-                        .no_values(),
-                        |key, _| {
-                            let account_id = key.deserialize_be_u32(0)?;
-                            let collection = key.deserialize_u8(U32_LEN)?;
-                            let field = key.deserialize_u8(U32_LEN + 1)?;
-                            let document_id = key.deserialize_be_u32(U32_LEN + 2)?;
+                for (from_account_id, to_account_id) in scope.account_ranges() {
+                    let mut mailboxes = Vec::new();
 
-                            keys.insert((account_id, collection, document_id, field));
+                    store
+                        .iterate(
+                            IterateParams::new(
+                                ValueKey {
+                                    account_id: from_account_id,
+                                    collection: 0,
+                                    document_id: 0,
+                                    class: ValueClass::Property(0),
+                                },
+                                ValueKey {
+                                    account_id: to_account_id,
+                                    collection: u8::MAX,
+                                    document_id: u32::MAX,
+                                    class: ValueClass::Property(u8::MAX),
+                                },
+                            ),
+                            |key, value| {
+                                let account_id = key.deserialize_be_u32(0)?;
+                                let collection = key.deserialize_u8(U32_LEN)?;
+                                let field = key.deserialize_u8(U32_LEN + 1)?;
+                                let document_id = key.deserialize_be_u32(U32_LEN + 2)?;
 
-                            Ok(true)
-                        },
-                    )
-                    .await
-                    .failed("Failed to iterate over data store");
+                                if !scope.has_document(account_id, collection, document_id) {
+                                    return Ok(true);
+                                }
 
-                let mut last_account_id = u32::MAX;
-                let mut last_collection = u8::MAX;
-                let mut last_document_id = u32::MAX;
+                                if account_id != last_account_id {
+                                    writer.send(Op::AccountId(account_id))?;
+                                    last_account_id = account_id;
+                                }
 
-                for (account_id, collection, document_id, field) in keys {
-                    if account_id != last_account_id {
-                        writer
-                            .send(Op::AccountId(account_id))
-                            .failed("Failed to send account id");
-                        last_account_id = account_id;
-                    }
+                                if collection != last_collection {
+                                    writer.send(Op::Collection(collection))?;
+                                    last_collection = collection;
+                                }
 
-                    if collection != last_collection {
-                        writer
-                            .send(Op::Collection(collection))
-                            .failed("Failed to send collection");
-                        last_collection = collection;
-                    }
+                                if document_id != last_document_id {
+                                    writer.send(Op::DocumentId(document_id))?;
+                                    last_document_id = document_id;
+                                }
 
-                    if document_id != last_document_id {
-                        writer
-                            .send(Op::DocumentId(document_id))
-                            .failed("Failed to send document id");
-                        last_document_id = document_id;
-                    }
+                                if collection == u8::from(Collection::Mailbox)
+                                    && u8::from(Property::Value) == field
+                                {
+                                    mailboxes.push((account_id, document_id));
+                                }
 
-                    // Obtain UID counter
-                    if collection == u8::from(Collection::Mailbox)
-                        && u8::from(Property::Value) == field
-                    {
+                                writer.send(Op::KeyValue((vec![field], value.to_vec())))?;
+
+                                Ok(true)
+                            },
+                        )
+                        .await
+                        .caused_by(trc::location!())?;
+
+                    // Obtain UID counters
+                    for (account_id, document_id) in mailboxes {
                         let value = store
                             .get_counter(ValueKey {
                                 account_id,
-                                collection,
+                                collection: Collection::Mailbox.into(),
                                 document_id,
                                 class: ValueClass::Property(Property::EmailIds.into()),
                             })
                             .await
-                            .failed("Failed to get counter");
+                            .caused_by(trc::location!())?;
                         if value != 0 {
-                            writer
-                                .send(Op::KeyValue((
-                                    vec![u8::from(Property::EmailIds)],
-                                    value.serialize(),
-                                )))
-                                .failed("Failed to send key value");
+                            writer.send(Op::AccountId(account_id))?;
+                            writer.send(Op::Collection(Collection::Mailbox.into()))?;
+                            writer.send(Op::DocumentId(document_id))?;
+                            writer.send(Op::KeyValue((
+                                vec![u8::from(Property::EmailIds)],
+                                value.serialize(),
+                            )))?;
                         }
                     }
-
-                    // Write value
-                    let value = store
-                        .get_value::<RawBytes>(ValueKey {
-                            account_id,
-                            collection,
-                            document_id,
-                            class: ValueClass::Property(field),
-                        })
-                        .await
-                        .failed("Failed to get value")
-                        .failed("Expected value")
-                        .0;
-                    writer
-                        .send(Op::KeyValue((vec![field], value)))
-                        .failed("Failed to send key value");
+                    last_account_id = u32::MAX;
+                    last_collection = u8::MAX;
+                    last_document_id = u32::MAX;
                 }
+
+                Ok(())
             }),
-            handle,
+            vec![handle],
         )
     }
 
-    fn backup_fts_index(&self, dest: &Path) -> TaskHandle {
+    fn backup_fts_index(&self, params: &BackupParams) -> TaskHandle {
         let store = self.storage.data.clone();
-        let (handle, writer) = spawn_writer(dest.join("fts_index"));
+        let scope = params.scope.clone();
+        let (handle, writer) = spawn_writer(params.dest.join("fts_index"), params.verbose);
         (
             tokio::spawn(async move {
-                writer
-                    .send(Op::Family(Family::FtsIndex))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::FtsIndex))?;
 
                 let mut last_account_id = u32::MAX;
                 let mut last_collection = u8::MAX;
 
-                store
-                    .iterate(
-                        IterateParams::new(
-                            ValueKey {
-                                account_id: 0,
-                                collection: 0,
-                                document_id: 0,
-                                class: ValueClass::FtsIndex(BitmapHash {
-                                    hash: [0; 8],
-                                    len: 1,
-                                }),
+                for (from_account_id, to_account_id) in scope.account_ranges() {
+                    store
+                        .iterate(
+                            IterateParams::new(
+                                ValueKey {
+                                    account_id: from_account_id,
+                                    collection: 0,
+                                    document_id: 0,
+                                    class: ValueClass::FtsIndex(BitmapHash {
+                                        hash: [0; 8],
+                                        len: 1,
+                                    }),
+                                },
+                                ValueKey {
+                                    account_id: to_account_id,
+                                    collection: u8::MAX,
+                                    document_id: u32::MAX,
+                                    class: ValueClass::FtsIndex(BitmapHash {
+                                        hash: [u8::MAX; 8],
+                                        len: u8::MAX,
+                                    }),
+                                },
+                            ),
+                            |key, value| {
+                                let account_id = key.deserialize_be_u32(0)?;
+                                let collection = key.deserialize_u8(key.len() - U32_LEN - 1)?;
+                                let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+
+                                if !scope.has_document(account_id, collection, document_id) {
+                                    return Ok(true);
+                                }
+
+                                if account_id != last_account_id {
+                                    writer.send(Op::AccountId(account_id))?;
+                                    last_account_id = account_id;
+                                }
+
+                                if collection != last_collection {
+                                    writer.send(Op::Collection(collection))?;
+                                    last_collection = collection;
+                                }
+
+                                writer.send(Op::DocumentId(document_id))?;
+
+                                writer.send(Op::KeyValue((
+                                    key.range(U32_LEN..key.len() - U32_LEN - 1)?.to_vec(),
+                                    value.to_vec(),
+                                )))?;
+
+                                Ok(true)
                             },
-                            ValueKey {
-                                account_id: u32::MAX,
-                                collection: u8::MAX,
-                                document_id: u32::MAX,
-                                class: ValueClass::FtsIndex(BitmapHash {
-                                    hash: [u8::MAX; 8],
-                                    len: u8::MAX,
-                                }),
+                        )
+                        .await
+                        .caused_by(trc::location!())?;
+
+                    // Term dictionary counters are only exported with all the documents of an account
+                    let mut terms = Vec::new();
+                    store
+                        .iterate(
+                            IterateParams::new(
+                                ValueKey {
+                                    account_id: from_account_id,
+                                    collection: 0,
+                                    document_id: 0,
+                                    class: ValueClass::FtsTerm(vec![]),
+                                },
+                                ValueKey {
+                                    account_id: to_account_id,
+                                    collection: u8::MAX,
+                                    document_id: u32::MAX,
+                                    class: ValueClass::FtsTerm(vec![u8::MAX]),
+                                },
+                            )
+                            .no_values(),
+                            |key, _| {
+                                let account_id = key.deserialize_be_u32(0)?;
+                                let collection = key.deserialize_u8(U32_LEN)?;
+
+                                if scope.documents(account_id, collection).is_none() {
+                                    terms.push((
+                                        account_id,
+                                        collection,
+                                        key.range(U32_LEN + 1..usize::MAX)?.to_vec(),
+                                    ));
+                                }
+
+                                Ok(true)
                             },
-                        ),
-                        |key, value| {
-                            let account_id = key.deserialize_be_u32(0)?;
-                            let collection = key.deserialize_u8(key.len() - U32_LEN - 1)?;
-                            let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+                        )
+                        .await
+                        .caused_by(trc::location!())?;
 
+                    for (account_id, collection, term) in terms {
+                        let value = store
+                            .get_counter(ValueKey {
+                                account_id,
+                                collection,
+                                document_id: 0,
+                                class: ValueClass::FtsTerm(term.clone()),
+                            })
+                            .await
+                            .caused_by(trc::location!())?;
+
+                        if value != 0 {
                             if account_id != last_account_id {
-                                writer
-                                    .send(Op::AccountId(account_id))
-                                    .failed("Failed to send account id");
+                                writer.send(Op::AccountId(account_id))?;
                                 last_account_id = account_id;
                             }
 
                             if collection != last_collection {
-                                writer
-                                    .send(Op::Collection(collection))
-                                    .failed("Failed to send collection");
+                                writer.send(Op::Collection(collection))?;
                                 last_collection = collection;
                             }
 
-                            writer
-                                .send(Op::DocumentId(document_id))
-                                .failed("Failed to send document id");
-
-                            writer
-                                .send(Op::KeyValue((
-                                    key.range(U32_LEN..key.len() - U32_LEN - 1)?.to_vec(),
-                                    value.to_vec(),
-                                )))
-                                .failed("Failed to send key value");
+                            // Counters are not linked to a document
+                            writer.send(Op::DocumentId(u32::MAX))?;
+                            writer.send(Op::KeyValue((term, value.serialize())))?;
+                        }
+                    }
+                }
 
-                            Ok(true)
-                        },
-                    )
-                    .await
-                    .failed("Failed to iterate over data store");
+                Ok(())
             }),
-            handle,
+            vec![handle],
         )
     }
 
-    fn backup_acl(&self, dest: &Path) -> TaskHandle {
+    fn backup_acl(&self, params: &BackupParams) -> TaskHandle {
         let store = self.storage.data.clone();
-        let (handle, writer) = spawn_writer(dest.join("acl"));
+        let scope = params.scope.clone();
+        let (handle, writer) = spawn_writer(params.dest.join("acl"), params.verbose);
         (
             tokio::spawn(async move {
-                writer
-                    .send(Op::Family(Family::Acl))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::Acl))?;
 
                 let mut last_account_id = u32::MAX;
                 let mut last_collection = u8::MAX;
@@ -346,55 +565,56 @@ impl Core {
                             let collection = key.deserialize_u8(U32_LEN * 2)?;
                             let document_id = key.deserialize_be_u32((U32_LEN * 2) + 1)?;
 
+                            if !scope.has_document(account_id, collection, document_id) {
+                                return Ok(true);
+                            }
+
                             if account_id != last_account_id {
-                                writer
-                                    .send(Op::AccountId(account_id))
-                                    .failed("Failed to send account id");
+                                writer.send(Op::AccountId(account_id))?;
                                 last_account_id = account_id;
                             }
 
                             if collection != last_collection {
-                                writer
-                                    .send(Op::Collection(collection))
-                                    .failed("Failed to send collection");
+                                writer.send(Op::Collection(collection))?;
                                 last_collection = collection;
                             }
 
                             if document_id != last_document_id {
-                                writer
-                                    .send(Op::DocumentId(document_id))
-                                    .failed("Failed to send document id");
+                                writer.send(Op::DocumentId(document_id))?;
                                 last_document_id = document_id;
                             }
 
-                            writer
-                                .send(Op::KeyValue((
-                                    grant_account_id.to_be_bytes().to_vec(),
-                                    value.to_vec(),
-                                )))
-                                .failed("Failed to send key value");
+                            writer.send(Op::KeyValue((
+                                grant_account_id.to_be_bytes().to_vec(),
+                                value.to_vec(),
+                            )))?;
 
                             Ok(true)
                         },
                     )
                     .await
-                    .failed("Failed to iterate over data store");
+                    .caused_by(trc::location!())?;
+
+                Ok(())
             }),
-            handle,
+            vec![handle],
         )
     }
 
-    fn backup_blob(&self, dest: &Path) -> TaskHandle {
+    fn backup_blob(&self, params: &BackupParams) -> TaskHandle {
         let store = self.storage.data.clone();
         let blob_store = self.storage.blob.clone();
-        let (handle, writer) = spawn_writer(dest.join("blob"));
+        let scope = params.scope.clone();
+        let (handle, writer) = spawn_writer(params.dest.join("blob"), params.verbose);
+        let (index_handle, index_writer) =
+            spawn_writer(params.dest.join(BLOB_INDEX_FILE), params.verbose);
         (
             tokio::spawn(async move {
-                writer
-                    .send(Op::Family(Family::Blob))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::Blob))?;
+                index_writer.send(Op::Family(Family::Blob))?;
 
                 let mut hashes = Vec::new();
+                let mut linked = AHashSet::new();
 
                 store
                     .iterate(
@@ -425,19 +645,20 @@ impl Core {
                             let hash = key.range(0..BLOB_HASH_LEN)?.to_vec();
 
                             if account_id != u32::MAX && document_id != u32::MAX {
-                                writer
-                                    .send(Op::AccountId(account_id))
-                                    .failed("Failed to send account id");
-                                writer
-                                    .send(Op::Collection(collection))
-                                    .failed("Failed to send collection");
-                                writer
-                                    .send(Op::DocumentId(document_id))
-                                    .failed("Failed to send document id");
-                                writer
-                                    .send(Op::KeyValue((hash, vec![])))
-                                    .failed("Failed to send key value");
-                            } else {
+                                // Links held by queued messages are not owned by an account
+                                if (collection == u8::MAX && !scope.accounts_only)
+                                    || (collection != u8::MAX
+                                        && scope.has_document(account_id, collection, document_id))
+                                {
+                                    if scope.accounts_only {
+                                        linked.insert(hash.clone());
+                                    }
+                                    writer.send(Op::AccountId(account_id))?;
+                                    writer.send(Op::Collection(collection))?;
+                                    writer.send(Op::DocumentId(document_id))?;
+                                    writer.send(Op::KeyValue((hash, vec![])))?;
+                                }
+                            } else if !scope.accounts_only || linked.contains(&hash) {
                                 hashes.push(hash);
                             }
 
@@ -445,44 +666,45 @@ impl Core {
                         },
                     )
                     .await
-                    .failed("Failed to iterate over data store");
+                    .caused_by(trc::location!())?;
 
                 if !hashes.is_empty() {
-                    writer
-                        .send(Op::AccountId(u32::MAX))
-                        .failed("Failed to send account id");
-                    writer
-                        .send(Op::DocumentId(u32::MAX))
-                        .failed("Failed to send document id");
+                    writer.send(Op::AccountId(u32::MAX))?;
+                    writer.send(Op::DocumentId(u32::MAX))?;
                     for hash in hashes {
-                        if let Some(value) = blob_store
+                        // Contents already stored in the backup chain are only referenced
+                        if scope.skip_contents || scope.blobs.contains(&hash) {
+                            writer.send(Op::KeyValue((hash.clone(), vec![])))?;
+                            index_writer.send(Op::KeyValue((hash, vec![])))?;
+                        } else if let Some(value) = blob_store
                             .get_blob(&hash, 0..usize::MAX)
                             .await
-                            .failed("Failed to get blob")
+                            .caused_by(trc::location!())?
                         {
-                            writer
-                                .send(Op::KeyValue((hash, value)))
-                                .failed("Failed to send key value");
+                            writer.send(Op::KeyValue((hash.clone(), value)))?;
+                            index_writer.send(Op::KeyValue((hash, vec![])))?;
                         } else {
-                            eprintln!(
-                            "Warning: blob hash {hash:?} does not exist in blob store. Skipping."
-                        );
+                            trc::event!(
+                                Store(trc::StoreEvent::NotFound),
+                                Key = hash,
+                                Details = "Blob does not exist in blob store, skipping."
+                            );
                         }
                     }
                 }
+
+                Ok(())
             }),
-            handle,
+            vec![handle, index_handle],
         )
     }
 
-    fn backup_config(&self, dest: &Path) -> TaskHandle {
+    fn backup_config(&self, params: &BackupParams) -> TaskHandle {
         let store = self.storage.data.clone();
-        let (handle, writer) = spawn_writer(dest.join("config"));
+        let (handle, writer) = spawn_writer(params.dest.join("config"), params.verbose);
         (
             tokio::spawn(async move {
-                writer
-                    .send(Op::Family(Family::Config))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::Config))?;
 
                 store
                     .iterate(
@@ -508,28 +730,29 @@ impl Core {
                             },
                         ),
                         |key, value| {
-                            writer
-                                .send(Op::KeyValue((key.to_vec(), value.to_vec())))
-                                .failed("Failed to send key value");
+                            // Skip the read-only marker left on migrated stores
+                            if key != READ_ONLY_KEY {
+                                writer.send(Op::KeyValue((key.to_vec(), value.to_vec())))?;
+                            }
 
                             Ok(true)
                         },
                     )
                     .await
-                    .failed("Failed to iterate over data store");
+                    .caused_by(trc::location!())?;
+
+                Ok(())
             }),
-            handle,
+            vec![handle],
         )
     }
 
-    fn backup_lookup(&self, dest: &Path) -> TaskHandle {
+    fn backup_lookup(&self, params: &BackupParams) -> TaskHandle {
         let store = self.storage.data.clone();
-        let (handle, writer) = spawn_writer(dest.join("lookup"));
+        let (handle, writer) = spawn_writer(params.dest.join("lookup"), params.verbose);
         (
             tokio::spawn(async move {
-                writer
-                    .send(Op::Family(Family::LookupValue))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::LookupValue))?;
 
                 store
                     .iterate(
@@ -555,19 +778,15 @@ impl Core {
                             },
                         ),
                         |key, value| {
-                            writer
-                                .send(Op::KeyValue((key.to_vec(), value.to_vec())))
-                                .failed("Failed to send key value");
+                            writer.send(Op::KeyValue((key.to_vec(), value.to_vec())))?;
 
                             Ok(true)
                         },
                     )
                     .await
-                    .failed("Failed to iterate over data store");
+                    .caused_by(trc::location!())?;
 
-                writer
-                    .send(Op::Family(Family::LookupCounter))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::LookupCounter))?;
 
                 let mut counters = Vec::new();
 
@@ -607,7 +826,7 @@ impl Core {
                         },
                     )
                     .await
-                    .failed("Failed to iterate over data store");
+                    .caused_by(trc::location!())?;
 
                 for key in counters {
                     let value = store
@@ -615,27 +834,25 @@ impl Core {
                             key.clone(),
                         ))))
                         .await
-                        .failed("Failed to get counter");
+                        .caused_by(trc::location!())?;
 
                     if value != 0 {
-                        writer
-                            .send(Op::KeyValue((key, value.serialize())))
-                            .failed("Failed to send key value");
+                        writer.send(Op::KeyValue((key, value.serialize())))?;
                     }
                 }
+
+                Ok(())
             }),
-            handle,
+            vec![handle],
         )
     }
 
-    fn backup_directory(&self, dest: &Path) -> TaskHandle {
+    fn backup_directory(&self, params: &BackupParams) -> TaskHandle {
         let store = self.storage.data.clone();
-        let (handle, writer) = spawn_writer(dest.join("directory"));
+        let (handle, writer) = spawn_writer(params.dest.join("directory"), params.verbose);
         (
             tokio::spawn(async move {
-                writer
-                    .send(Op::Family(Family::Directory))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::Directory))?;
 
                 let mut principal_ids = Vec::new();
 
@@ -663,51 +880,44 @@ impl Core {
                                 principal_ids.push(key.range(1..usize::MAX)?.to_vec());
                             }
 
-                            writer
-                                .send(Op::KeyValue((key.to_vec(), value.to_vec())))
-                                .failed("Failed to send key value");
+                            writer.send(Op::KeyValue((key.to_vec(), value.to_vec())))?;
 
                             Ok(true)
                         },
                     )
                     .await
-                    .failed("Failed to iterate over data store");
+                    .caused_by(trc::location!())?;
 
                 for principal_bytes in principal_ids {
                     let value = store
                         .get_counter(ValueKey::from(ValueClass::Directory(
                             DirectoryClass::UsedQuota(
-                                principal_bytes
-                                    .as_slice()
-                                    .deserialize_leb128()
-                                    .failed("Failed to deserialize principal id"),
+                                principal_bytes.as_slice().deserialize_leb128()?,
                             ),
                         )))
                         .await
-                        .failed("Failed to get counter");
+                        .caused_by(trc::location!())?;
                     if value != 0 {
                         let mut key = Vec::with_capacity(U32_LEN + 1);
                         key.push(4u8);
                         key.extend_from_slice(&principal_bytes);
 
-                        writer
-                            .send(Op::KeyValue((key, value.serialize())))
-                            .failed("Failed to send key value");
+                        writer.send(Op::KeyValue((key, value.serialize())))?;
                     }
                 }
+
+                Ok(())
             }),
-            handle,
+            vec![handle],
         )
     }
 
-    fn backup_queue(&self, dest: &Path) -> TaskHandle {
+    fn backup_queue(&self, params: &BackupParams) -> TaskHandle {
         let store = self.storage.data.clone();
-        let (handle, writer) = spawn_writer(dest.join("queue"));
+        let (handle, writer) = spawn_writer(params.dest.join("queue"), params.verbose);
         (
             tokio::spawn(async move {
-                writer
-                    .send(Op::Family(Family::Queue))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::Queue))?;
 
                 store
                     .iterate(
@@ -730,15 +940,13 @@ impl Core {
                             key.push(0);
                             key.extend_from_slice(key_);
 
-                            writer
-                                .send(Op::KeyValue((key, value.to_vec())))
-                                .failed("Failed to send key value");
+                            writer.send(Op::KeyValue((key, value.to_vec())))?;
 
                             Ok(true)
                         },
                     )
                     .await
-                    .failed("Failed to iterate over data store");
+                    .caused_by(trc::location!())?;
 
                 store
                     .iterate(
@@ -767,119 +975,160 @@ impl Core {
                             key.push(1);
                             key.extend_from_slice(key_);
 
-                            writer
-                                .send(Op::KeyValue((key, value.to_vec())))
-                                .failed("Failed to send key value");
+                            writer.send(Op::KeyValue((key, value.to_vec())))?;
 
                             Ok(true)
                         },
                     )
                     .await
-                    .failed("Failed to iterate over data store");
-            }),
-            handle,
-        )
-    }
-
-    fn backup_index(&self, dest: &Path) -> TaskHandle {
-        let store = self.storage.data.clone();
-        let (handle, writer) = spawn_writer(dest.join("index"));
-        (
-            tokio::spawn(async move {
-                writer
-                    .send(Op::Family(Family::Index))
-                    .failed("Failed to send family");
-
-                let mut last_account_id = u32::MAX;
-                let mut last_collection = u8::MAX;
+                    .caused_by(trc::location!())?;
 
+                // Scheduled wake-ups of snoozed messages
                 store
                     .iterate(
                         IterateParams::new(
-                            IndexKey {
+                            ValueKey {
                                 account_id: 0,
                                 collection: 0,
                                 document_id: 0,
-                                field: 0,
-                                key: vec![0],
+                                class: ValueClass::SnoozeEvent(0),
                             },
-                            IndexKey {
+                            ValueKey {
                                 account_id: u32::MAX,
                                 collection: u8::MAX,
                                 document_id: u32::MAX,
-                                field: u8::MAX,
-                                key: vec![u8::MAX, u8::MAX, u8::MAX],
+                                class: ValueClass::SnoozeEvent(u64::MAX),
                             },
                         )
                         .no_values(),
-                        |key, _| {
-                            let account_id = key.deserialize_be_u32(0)?;
-                            let collection = key.deserialize_u8(U32_LEN)?;
-                            let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
-
-                            let key = key.range(U32_LEN + 1..key.len() - U32_LEN)?.to_vec();
-
-                            if account_id != last_account_id {
-                                writer
-                                    .send(Op::AccountId(account_id))
-                                    .failed("Failed to send account id");
-                                last_account_id = account_id;
-                            }
-
-                            if collection != last_collection {
-                                writer
-                                    .send(Op::Collection(collection))
-                                    .failed("Failed to send collection");
-                                last_collection = collection;
-                            }
-
-                            writer
-                                .send(Op::DocumentId(document_id))
-                                .failed("Failed to send document id");
+                        |key_, _| {
+                            let mut key = Vec::with_capacity(U64_LEN + U32_LEN * 2 + 1);
+                            key.push(2);
+                            key.extend_from_slice(key_);
 
-                            writer
-                                .send(Op::KeyValue((key, vec![])))
-                                .failed("Failed to send key value");
+                            writer.send(Op::KeyValue((key, vec![])))?;
 
                             Ok(true)
                         },
                     )
                     .await
-                    .failed("Failed to iterate over data store");
+                    .caused_by(trc::location!())?;
+
+                Ok(())
             }),
-            handle,
+            vec![handle],
         )
     }
 
-    fn backup_bitmaps(&self, dest: &Path) -> TaskHandle {
+    fn backup_index(&self, params: &BackupParams) -> TaskHandle {
         let store = self.storage.data.clone();
+        let scope = params.scope.clone();
+        let (handle, writer) = spawn_writer(params.dest.join("index"), params.verbose);
+        (
+            tokio::spawn(async move {
+                writer.send(Op::Family(Family::Index))?;
 
-        let (handle, writer) = spawn_writer(dest.join("bitmap"));
+                let mut last_account_id = u32::MAX;
+                let mut last_collection = u8::MAX;
+
+                for (from_account_id, to_account_id) in scope.account_ranges() {
+                    store
+                        .iterate(
+                            IterateParams::new(
+                                IndexKey {
+                                    account_id: from_account_id,
+                                    collection: 0,
+                                    document_id: 0,
+                                    field: 0,
+                                    key: vec![0],
+                                },
+                                IndexKey {
+                                    account_id: to_account_id,
+                                    collection: u8::MAX,
+                                    document_id: u32::MAX,
+                                    field: u8::MAX,
+                                    key: vec![u8::MAX, u8::MAX, u8::MAX],
+                                },
+                            )
+                            .no_values(),
+                            |key, _| {
+                                let account_id = key.deserialize_be_u32(0)?;
+                                let collection = key.deserialize_u8(U32_LEN)?;
+                                let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+
+                                if !scope.has_document(account_id, collection, document_id) {
+                                    return Ok(true);
+                                }
+
+                                let key = key.range(U32_LEN + 1..key.len() - U32_LEN)?.to_vec();
+
+                                if account_id != last_account_id {
+                                    writer.send(Op::AccountId(account_id))?;
+                                    last_account_id = account_id;
+                                }
+
+                                if collection != last_collection {
+                                    writer.send(Op::Collection(collection))?;
+                                    last_collection = collection;
+                                }
+
+                                writer.send(Op::DocumentId(document_id))?;
+
+                                writer.send(Op::KeyValue((key, vec![])))?;
+
+                                Ok(true)
+                            },
+                        )
+                        .await
+                        .caused_by(trc::location!())?;
+                }
+
+                Ok(())
+            }),
+            vec![handle],
+        )
+    }
+
+    fn backup_bitmaps(&self, params: &BackupParams) -> TaskHandle {
+        let store = self.storage.data.clone();
+        let scope = params.scope.clone();
+
+        let (handle, writer) = spawn_writer(params.dest.join("bitmap"), params.verbose);
         (
             tokio::spawn(async move {
                 const BM_MARKER: u8 = 1 << 7;
 
-                writer
-                    .send(Op::Family(Family::Bitmap))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::Bitmap))?;
 
                 let mut bitmaps: AHashMap<(u32, u8), AHashSet<BitmapClass<u32>>> = AHashMap::new();
 
-                for subspace in [
+                for (subspace, (from_account_id, to_account_id)) in [
                     SUBSPACE_BITMAP_ID,
                     SUBSPACE_BITMAP_TAG,
                     SUBSPACE_BITMAP_TEXT,
-                ] {
+                ]
+                .into_iter()
+                .flat_map(|subspace| {
+                    scope
+                        .account_ranges()
+                        .into_iter()
+                        .map(move |range| (subspace, range))
+                })
+                .collect::<Vec<_>>()
+                {
+                    let mut to_key = to_account_id.to_be_bytes().to_vec();
+                    to_key.extend_from_slice(&[u8::MAX; 6]);
+
                     store
                         .iterate(
                             IterateParams::new(
                                 AnyKey {
                                     subspace,
-                                    key: vec![0u8],
+                                    key: from_account_id.to_be_bytes().to_vec(),
                                 },
                                 AnyKey {
                                     subspace,
-                                    key: vec![u8::MAX; 10],
+                                    key: to_key,
                                 },
                             )
                             .no_values(),
@@ -955,19 +1204,23 @@ impl Core {
                             },
                         )
                         .await
-                        .failed("Failed to iterate over data store");
+                        .caused_by(trc::location!())?;
                 }
 
                 for ((account_id, collection), classes) in bitmaps {
-                    writer
-                        .send(Op::AccountId(account_id))
-                        .failed("Failed to send account id");
-                    writer
-                        .send(Op::Collection(collection))
-                        .failed("Failed to send collection");
+                    let documents = scope.documents(account_id, collection);
+                    if documents
+                        .as_ref()
+                        .map_or(false, |documents| documents.is_empty())
+                    {
+                        continue;
+                    }
+
+                    writer.send(Op::AccountId(account_id))?;
+                    writer.send(Op::Collection(collection))?;
 
                     for class in classes {
-                        if let Some(bitmap) = store
+                        if let Some(mut bitmap) = store
                             .get_bitmap(BitmapKey {
                                 account_id,
                                 collection,
@@ -975,8 +1228,15 @@ impl Core {
                                 document_id: 0,
                             })
                             .await
-                            .failed("Failed to get bitmap")
+                            .caused_by(trc::location!())?
                         {
+                            if let Some(documents) = &documents {
+                                bitmap &= documents;
+                                if bitmap.is_empty() {
+                                    continue;
+                                }
+                            }
+
                             let key = match class {
                                 BitmapClass::DocumentIds => {
                                     vec![0u8]
@@ -1008,135 +1268,191 @@ impl Core {
                             };
 
                             let mut bytes = Vec::with_capacity(bitmap.serialized_size());
-                            bitmap
-                                .serialize_into(&mut bytes)
-                                .failed("Failed to serialize bitmap");
+                            bitmap.serialize_into(&mut bytes).map_err(|err| {
+                                trc::StoreEvent::UnexpectedError
+                                    .reason(err)
+                                    .caused_by(trc::location!())
+                            })?;
 
-                            writer
-                                .send(Op::KeyValue((key, bytes)))
-                                .failed("Failed to send key value");
+                            writer.send(Op::KeyValue((key, bytes)))?;
                         }
                     }
                 }
+
+                Ok(())
             }),
-            handle,
+            vec![handle],
         )
     }
 
-    fn backup_logs(&self, dest: &Path) -> TaskHandle {
+    fn backup_logs(&self, params: &BackupParams) -> TaskHandle {
         let store = self.storage.data.clone();
-        let (handle, writer) = spawn_writer(dest.join("log"));
+        let scope = params.scope.clone();
+        let (handle, writer) = spawn_writer(params.dest.join("log"), params.verbose);
         (
             tokio::spawn(async move {
-                writer
-                    .send(Op::Family(Family::Log))
-                    .failed("Failed to send family");
+                writer.send(Op::Family(Family::Log))?;
 
                 let mut last_account_id = u32::MAX;
                 let mut last_collection = u8::MAX;
 
-                store
-                    .iterate(
-                        IterateParams::new(
-                            LogKey {
-                                account_id: 0,
-                                collection: 0,
-                                change_id: 0,
-                            },
-                            LogKey {
-                                account_id: u32::MAX,
-                                collection: u8::MAX,
-                                change_id: u64::MAX,
-                            },
-                        ),
-                        |key, value| {
-                            let account_id = key.deserialize_be_u32(0)?;
-                            let collection = key.deserialize_u8(U32_LEN)?;
-                            let key = key.range(U32_LEN + 1..usize::MAX)?.to_vec();
+                for (from_account_id, to_account_id) in scope.account_ranges() {
+                    store
+                        .iterate(
+                            IterateParams::new(
+                                LogKey {
+                                    account_id: from_account_id,
+                                    collection: 0,
+                                    change_id: 0,
+                                },
+                                LogKey {
+                                    account_id: to_account_id,
+                                    collection: u8::MAX,
+                                    change_id: u64::MAX,
+                                },
+                            ),
+                            |key, value| {
+                                let account_id = key.deserialize_be_u32(0)?;
+                                let collection = key.deserialize_u8(U32_LEN)?;
+                                let key = key.range(U32_LEN + 1..usize::MAX)?.to_vec();
+
+                                if key.len() != U64_LEN {
+                                    return Err(trc::Error::corrupted_key(
+                                        &key,
+                                        value.into(),
+                                        trc::location!(),
+                                    ));
+                                }
 
-                            if key.len() != U64_LEN {
-                                failed(&format!("Found invalid log entry {key:?} {value:?}"));
-                            }
+                                if !scope
+                                    .has_change(account_id, key.as_slice().deserialize_be_u64(0)?)
+                                {
+                                    return Ok(true);
+                                }
 
-                            if account_id != last_account_id {
-                                writer
-                                    .send(Op::AccountId(account_id))
-                                    .failed("Failed to send account id");
-                                last_account_id = account_id;
-                            }
+                                if account_id != last_account_id {
+                                    writer.send(Op::AccountId(account_id))?;
+                                    last_account_id = account_id;
+                                }
 
-                            if collection != last_collection {
-                                writer
-                                    .send(Op::Collection(collection))
-                                    .failed("Failed to send collection");
-                                last_collection = collection;
-                            }
+                                if collection != last_collection {
+                                    writer.send(Op::Collection(collection))?;
+                                    last_collection = collection;
+                                }
 
-                            writer
-                                .send(Op::KeyValue((key, value.to_vec())))
-                                .failed("Failed to send key value");
+                                writer.send(Op::KeyValue((key, value.to_vec())))?;
 
-                            Ok(true)
-                        },
-                    )
-                    .await
-                    .failed("Failed to iterate over data store");
+                                Ok(true)
+                            },
+                        )
+                        .await
+                        .caused_by(trc::location!())?;
+                }
+
+                Ok(())
             }),
-            handle,
+            vec![handle],
         )
     }
 }
 
-fn spawn_writer(path: PathBuf) -> (std::thread::JoinHandle<()>, SyncSender<Op>) {
+impl BackupWriter {
+    pub fn send(&self, op: Op) -> trc::Result<()> {
+        self.0.send(op).map_err(|_| {
+            trc::EventType::Server(trc::ServerEvent::ThreadError)
+                .into_err()
+                .details("Backup writer thread terminated")
+        })
+    }
+}
+
+pub(super) fn spawn_writer(path: PathBuf, verbose: bool) -> (WriterHandle, BackupWriter) {
     let (tx, rx) = mpsc::sync_channel(10);
 
     let handle = std::thread::spawn(move || {
-        println!("Exporting database to {}.", path.to_str().unwrap());
+        if verbose {
+            println!("Exporting database to {}.", path.to_str().unwrap());
+        }
 
-        let mut file =
-            BufWriter::new(std::fs::File::create(path).failed("Failed to create backup file"));
+        let mut file = ChecksumWriter {
+            inner: BufWriter::new(
+                std::fs::File::create(&path).map_err(|err| io_error(&path, err))?,
+            ),
+            hasher: Sha256::new(),
+            size: 0,
+        };
         file.write_all(&[MAGIC_MARKER, FILE_VERSION])
-            .failed("Failed to write version");
+            .map_err(|err| io_error(&path, err))?;
 
         while let Ok(op) = rx.recv() {
             match op {
-                Op::Family(f) => {
-                    file.write_all(&[0u8, f as u8])
-                        .failed("Failed to write family");
-                }
-                Op::KeyValue((k, v)) => {
-                    file.write_all(&[if !v.is_empty() { 1u8 } else { 2u8 }])
-                        .failed("Failed to write key");
-                    file.write_all(&(k.len() as u32).serialize())
-                        .failed("Failed to write key value");
-                    file.write_all(&k).failed("Failed to write key");
-                    if !v.is_empty() {
-                        file.write_all(&(v.len() as u32).serialize())
-                            .failed("Failed to write key value");
-                        file.write_all(&v).failed("Failed to write key value");
-                    }
-                }
-                Op::AccountId(v) => {
-                    file.write_all(&[3u8]).failed("Failed to write account id");
-                    file.write_all(&v.serialize())
-                        .failed("Failed to write account id");
-                }
-                Op::Collection(v) => {
-                    file.write_all(&[4u8, v])
-                        .failed("Failed to write collection");
-                }
-                Op::DocumentId(v) => {
-                    file.write_all(&[5u8]).failed("Failed to write document id");
-                    file.write_all(&v.serialize())
-                        .failed("Failed to write document id");
-                }
+                Op::Family(f) => file.write_all(&[0u8, f as u8]),
+                Op::KeyValue((k, v)) => file
+                    .write_all(&[if !v.is_empty() { 1u8 } else { 2u8 }])
+                    .and_then(|_| file.write_all(&(k.len() as u32).serialize()))
+                    .and_then(|_| file.write_all(&k))
+                    .and_then(|_| {
+                        if !v.is_empty() {
+                            file.write_all(&(v.len() as u32).serialize())
+                                .and_then(|_| file.write_all(&v))
+                        } else {
+                            Ok(())
+                        }
+                    }),
+                Op::AccountId(v) => file
+                    .write_all(&[3u8])
+                    .and_then(|_| file.write_all(&v.serialize())),
+                Op::Collection(v) => file.write_all(&[4u8, v]),
+                Op::DocumentId(v) => file
+                    .write_all(&[5u8])
+                    .and_then(|_| file.write_all(&v.serialize())),
             }
+            .map_err(|err| io_error(&path, err))?;
         }
 
-        file.flush().failed("Failed to flush backup file");
+        file.inner.flush().map_err(|err| io_error(&path, err))?;
+
+        Ok(BackupFile {
+            name: path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default()
+                .to_string(),
+            size: file.size,
+            sha256: hex_digest(file.hasher.finalize().as_slice()),
+        })
     });
 
-    (handle, tx)
+    (handle, BackupWriter(tx))
+}
+
+struct ChecksumWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub(super) fn hex_digest(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(super) fn io_error(path: &Path, err: std::io::Error) -> trc::Error {
+    trc::EventType::Store(trc::StoreEvent::FilesystemError)
+        .from_io_error(err)
+        .ctx(trc::Key::Path, path.to_string_lossy().into_owned())
 }
 
 pub(super) trait DeserializeBytes {
@@ -1169,6 +1485,8 @@ impl BackupParams {
         let mut params = Self {
             dest,
             families: AHashSet::new(),
+            scope: BackupScope::default(),
+            verbose: true,
         };
 
         if let Ok(families) = std::env::var("EXPORT_TYPES") {
@@ -1198,6 +1516,76 @@ impl BackupParams {
     }
 }
 
+impl BackupScope {
+    pub fn has_account(&self, account_id: u32) -> bool {
+        self.accounts
+            .as_ref()
+            .map_or(true, |accounts| accounts.contains(&account_id))
+            || self
+                .changes
+                .as_ref()
+                .map_or(false, |changes| changes.documents.contains_key(&account_id))
+    }
+
+    pub fn has_document(&self, account_id: u32, collection: u8, document_id: u32) -> bool {
+        self.accounts
+            .as_ref()
+            .map_or(true, |accounts| accounts.contains(&account_id))
+            || self.changes.as_ref().map_or(false, |changes| {
+                changes.contains(account_id, collection, document_id)
+            })
+    }
+
+    pub fn has_change(&self, account_id: u32, change_id: u64) -> bool {
+        self.accounts
+            .as_ref()
+            .map_or(true, |accounts| accounts.contains(&account_id))
+            || self.changes.as_ref().map_or(false, |changes| {
+                change_id >= changes.since && changes.documents.contains_key(&account_id)
+            })
+    }
+
+    /// Returns the documents of an account to export, `None` when all of them
+    /// are exported.
+    pub fn documents(&self, account_id: u32, collection: u8) -> Option<RoaringBitmap> {
+        if self
+            .accounts
+            .as_ref()
+            .map_or(true, |accounts| accounts.contains(&account_id))
+        {
+            None
+        } else {
+            Some(
+                self.changes
+                    .as_ref()
+                    .and_then(|changes| changes.documents.get(&account_id))
+                    .and_then(|collections| collections.get(&collection))
+                    .cloned()
+                    .unwrap_or_default(),
+            )
+        }
+    }
+
+    pub fn account_ranges(&self) -> Vec<(u32, u32)> {
+        if let Some(accounts) = &self.accounts {
+            let mut ranges = accounts
+                .iter()
+                .chain(
+                    self.changes
+                        .iter()
+                        .flat_map(|changes| changes.documents.keys()),
+                )
+                .map(|account_id| (*account_id, *account_id))
+                .collect::<Vec<_>>();
+            ranges.sort_unstable();
+            ranges.dedup();
+            ranges
+        } else {
+            vec![(0, u32::MAX)]
+        }
+    }
+}
+
 impl Family {
     pub fn parse(family: &str) -> Result<Self, String> {
         match family {
@@ -1216,11 +1604,3 @@ impl Family {
         }
     }
 }
-
-struct RawBytes(Vec<u8>);
-
-impl Deserialize for RawBytes {
-    fn deserialize(bytes: &[u8]) -> trc::Result<Self> {
-        Ok(Self(bytes.to_vec()))
-    }
-}
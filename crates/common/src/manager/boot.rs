@@ -19,7 +19,7 @@ use utils::{
 };
 
 use crate::{
-    config::{server::Listeners, telemetry::Telemetry},
+    config::{jmap::vapid::VapidKey, server::Listeners, telemetry::Telemetry},
     ipc::{DeliveryEvent, HousekeeperEvent, QueueEvent, ReportingEvent, StateEvent},
     Core, Data, Inner, Ipc, IPC_CHANNEL_BUFFER,
 };
@@ -57,6 +57,7 @@ Options:
   -c, --config <PATH>              Start server with the specified configuration file
   -e, --export <PATH>              Export all store data to a specific path
   -i, --import <PATH>              Import store data from a specific path
+  -u, --until <TIMESTAMP>          Restore the latest backup created before a point in time
   -o, --console                    Open the store console
   -I, --init <PATH>                Initialize a new server at a specific path
   -h, --help                       Print help
@@ -64,10 +65,10 @@ Options:
 "#
 );
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq)]
 enum StoreOp {
     Export(BackupParams),
-    Import(PathBuf),
+    Import { path: PathBuf, until: Option<u64> },
     Console,
     None,
 }
@@ -76,6 +77,7 @@ impl BootManager {
     pub async fn init() -> Self {
         let mut config_path = std::env::var("CONFIG_PATH").ok();
         let mut import_export = StoreOp::None;
+        let mut restore_until = None;
 
         if config_path.is_none() {
             let mut args = std::env::args().skip(1);
@@ -111,7 +113,17 @@ impl BootManager {
                         import_export = StoreOp::Export(BackupParams::new(value.into()));
                     }
                     ("import" | "i", Some(value)) => {
-                        import_export = StoreOp::Import(value.into());
+                        import_export = StoreOp::Import {
+                            path: value.into(),
+                            until: None,
+                        };
+                    }
+                    ("until" | "u", Some(value)) => {
+                        restore_until = Some(parse_timestamp(&value).unwrap_or_else(|| {
+                            failed(&format!(
+                                "Invalid timestamp '{value}', expected RFC 3339 or UNIX time."
+                            ))
+                        }));
                     }
                     ("console" | "o", None) => {
                         import_export = StoreOp::Console;
@@ -125,6 +137,10 @@ impl BootManager {
                 }
             }
 
+            if let StoreOp::Import { until, .. } = &mut import_export {
+                *until = restore_until;
+            }
+
             if config_path.is_none() {
                 if import_export == StoreOp::None {
                     eprintln!("{HELP}");
@@ -219,6 +235,17 @@ impl BootManager {
                     )));
                 }
 
+                // Generate a VAPID key pair if missing
+                if config
+                    .value("jmap.push.vapid.private-key")
+                    .filter(|v| !v.is_empty())
+                    .is_none()
+                {
+                    if let Some(key) = VapidKey::generate() {
+                        insert_keys.push(ConfigKey::from(("jmap.push.vapid.private-key", key)));
+                    }
+                }
+
                 // Generate a Cluster encryption key if missing
                 if config
                     .value("cluster.key")
@@ -374,14 +401,14 @@ impl BootManager {
                     .await;
                 std::process::exit(0);
             }
-            StoreOp::Import(path) => {
+            StoreOp::Import { path, until } => {
                 // Enable telemetry
                 telemetry.enable(false);
 
                 // Parse settings and restore
                 Core::parse(&mut config, stores, manager)
                     .await
-                    .restore(path)
+                    .restore(path, until)
                     .await;
                 std::process::exit(0);
             }
@@ -394,6 +421,14 @@ impl BootManager {
     }
 }
 
+fn parse_timestamp(value: &str) -> Option<u64> {
+    value.parse::<u64>().ok().or_else(|| {
+        chrono::DateTime::parse_from_rfc3339(value)
+            .ok()
+            .and_then(|dt| u64::try_from(dt.timestamp()).ok())
+    })
+}
+
 pub fn build_ipc() -> (Ipc, IpcReceivers) {
     // Build ipc receivers
     let (delivery_tx, delivery_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
@@ -13,6 +13,7 @@ use std::{
 use ahash::AHashMap;
 use arc_swap::ArcSwap;
 use store::{
+    dispatch::READ_ONLY_KEY,
     write::{BatchBuilder, ValueClass},
     Deserialize, IterateParams, Store, ValueKey,
 };
@@ -156,7 +157,7 @@ impl ConfigManager {
                         trc::Error::corrupted_key(key, value.into(), trc::location!())
                     })?;
 
-                    if !patterns.is_local_key(key) {
+                    if !patterns.is_local_key(key) && key.as_bytes() != READ_ONLY_KEY {
                         if strip_prefix && !prefix.is_empty() {
                             key = key.strip_prefix(prefix).unwrap_or(key);
                         }
@@ -0,0 +1,651 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use ahash::{AHashMap, AHashSet};
+use jmap_proto::types::collection::Collection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use store::{
+    query::log::{Change, Changes},
+    roaring::RoaringBitmap,
+    write::{key::DeserializeBigEndian, now, AnyKey, DirectoryClass, ValueClass},
+    IterateParams, LogKey, Store, ValueKey, SUBSPACE_FTS_QUEUE, U64_LEN,
+};
+use trc::AddContext;
+use utils::{codec::leb128::Leb128Reader, snowflake::SnowflakeIdGenerator};
+
+use crate::{config::storage::BackupSettings, Core};
+
+use super::backup::{
+    hex_digest, io_error, spawn_writer, BackupParams, BackupScope, Family, Op, BLOB_INDEX_FILE,
+};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub(super) const CHANGES_FILE: &str = "changes";
+pub(super) const ACCOUNTS_DIR: &str = "accounts";
+pub(super) const MANIFEST_VERSION: u32 = 1;
+
+// Change ids are generated by each node, allow for clock drift between them
+const CLOCK_DRIFT: u64 = 300;
+
+static BACKUP_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupKind {
+    Full,
+    Incremental,
+    Account,
+}
+
+/// Describes a backup directory. Incremental backups contain the documents
+/// changed since their parent according to the change log, listed in the
+/// changes file, a full export of the accounts whose change log does not
+/// reach back to the parent plus all global data. The contents of blobs
+/// already present in the chain are only referenced. Account backups contain
+/// the data of a single account and its used quota.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub version: u32,
+    pub id: String,
+    pub kind: BackupKind,
+    #[serde(default)]
+    pub parent: Option<String>,
+    pub created: u64,
+    #[serde(default)]
+    pub accounts: Vec<u32>,
+    // Accounts with documents listed in the changes file
+    #[serde(default)]
+    pub changed: Vec<u32>,
+    #[serde(default)]
+    pub principals: Vec<u32>,
+    #[serde(default)]
+    pub pending: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota: Option<i64>,
+    // Change id the next incremental backup replays the change log from
+    #[serde(default)]
+    pub change_id: u64,
+    pub files: Vec<BackupFile>,
+}
+
+/// Documents changed since a change id, grouped by account and collection.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct ChangedDocuments {
+    pub since: u64,
+    pub documents: AHashMap<u32, AHashMap<u8, RoaringBitmap>>,
+}
+
+impl ChangedDocuments {
+    pub fn contains(&self, account_id: u32, collection: u8, document_id: u32) -> bool {
+        self.documents
+            .get(&account_id)
+            .and_then(|collections| collections.get(&collection))
+            .map_or(false, |documents| documents.contains(document_id))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl Core {
+    pub fn list_backups(&self) -> trc::Result<Vec<BackupManifest>> {
+        if let Some(settings) = &self.storage.backup {
+            load_manifests(&settings.path)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn list_account_backups(&self) -> trc::Result<Vec<BackupManifest>> {
+        if let Some(settings) = &self.storage.backup {
+            load_manifests(&settings.path.join(ACCOUNTS_DIR))
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// Runs a backup while the server is online, writing it to a new directory
+    /// under the configured backup path. An incremental backup is created
+    /// unless `full` is set, there is no previous backup or the configured
+    /// number of incrementals has been reached.
+    pub async fn online_backup(&self, full: bool) -> trc::Result<BackupManifest> {
+        let settings = self.storage.backup.as_ref().ok_or_else(|| {
+            trc::StoreEvent::NotConfigured
+                .into_err()
+                .details("Backup path is not configured")
+        })?;
+
+        if BACKUP_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+            return Err(trc::ManageEvent::AssertFailed
+                .into_err()
+                .details("Another backup is already in progress"));
+        }
+
+        let result = self.online_backup_(settings, full).await;
+        BACKUP_IN_PROGRESS.store(false, Ordering::SeqCst);
+        result
+    }
+
+    async fn online_backup_(
+        &self,
+        settings: &BackupSettings,
+        full: bool,
+    ) -> trc::Result<BackupManifest> {
+        let time = Instant::now();
+        let manifests = load_manifests(&settings.path)?;
+
+        // Start a new chain when the latest one reached the maximum length
+        let parent = manifests
+            .last()
+            .filter(|parent| !full && chain_length(&manifests, parent) <= settings.full_interval)
+            .cloned();
+
+        let created = now();
+        let id = format!(
+            "{}-{}",
+            format_timestamp(created),
+            if parent.is_some() {
+                "incremental"
+            } else {
+                "full"
+            }
+        );
+        let dest = settings.path.join(&id);
+        if dest.exists() {
+            return Err(trc::ManageEvent::AlreadyExists
+                .into_err()
+                .details("Backup already exists")
+                .ctx(trc::Key::Path, dest.to_string_lossy().into_owned()));
+        }
+        std::fs::create_dir_all(&dest).map_err(|err| io_error(&dest, err))?;
+
+        let params = BackupParams {
+            dest: dest.clone(),
+            ..Default::default()
+        };
+        match self.create_backup(params, parent.as_ref()).await {
+            Ok(manifest) => {
+                trc::event!(
+                    Housekeeper(trc::HousekeeperEvent::Backup),
+                    Id = manifest.id.clone(),
+                    Type = if manifest.kind == BackupKind::Full {
+                        "full"
+                    } else {
+                        "incremental"
+                    },
+                    Total = manifest.accounts.len() + manifest.changed.len(),
+                    Size = manifest.files.iter().map(|file| file.size).sum::<u64>(),
+                    Elapsed = time.elapsed(),
+                );
+
+                if settings.retention > 0 {
+                    let mut manifests = manifests;
+                    manifests.push(manifest.clone());
+                    prune_backups(&settings.path, &manifests, settings.retention);
+                }
+
+                Ok(manifest)
+            }
+            Err(err) => {
+                let _ = std::fs::remove_dir_all(&dest);
+                Err(err)
+            }
+        }
+    }
+
+    pub(super) async fn create_backup(
+        &self,
+        mut params: BackupParams,
+        parent: Option<&BackupManifest>,
+    ) -> trc::Result<BackupManifest> {
+        let store = &self.storage.data;
+        let created = now();
+        let change_id = SnowflakeIdGenerator::from_timestamp(created.saturating_sub(CLOCK_DRIFT))
+            .unwrap_or_default();
+        let principals = principal_ids(store).await?;
+        let pending = pending_index_accounts(store).await?;
+
+        let (kind, accounts, changes) = if let Some(parent) = parent {
+            // Replay the change log of every account from the parent's change id,
+            // accounts that were waiting to be indexed at that time are exported
+            // in full
+            let since = if parent.change_id != 0 {
+                parent.change_id
+            } else {
+                SnowflakeIdGenerator::from_timestamp(parent.created.saturating_sub(CLOCK_DRIFT))
+                    .unwrap_or_default()
+            };
+            let mut accounts = parent.pending.iter().copied().collect::<AHashSet<_>>();
+            accounts.insert(u32::MAX);
+            let changes =
+                changed_documents(store, &principals, &parent.principals, since, &mut accounts)
+                    .await?;
+
+            let parent_dir = params
+                .dest
+                .parent()
+                .map(|path| path.join(&parent.id))
+                .unwrap_or_default();
+            let changes = Arc::new(changes);
+            params.scope = BackupScope {
+                accounts: Some(Arc::new(accounts.clone())),
+                changes: Some(changes.clone()),
+                blobs: Arc::new(read_blob_index(&parent_dir.join(BLOB_INDEX_FILE)).await?),
+                accounts_only: false,
+                skip_contents: params.scope.skip_contents,
+            };
+
+            let mut accounts = accounts.into_iter().collect::<Vec<_>>();
+            accounts.sort_unstable();
+            (BackupKind::Incremental, accounts, Some(changes))
+        } else {
+            (BackupKind::Full, vec![], None)
+        };
+
+        let mut files = self.export(&params).await?;
+        let mut changed = Vec::new();
+        if let Some(changes) = changes {
+            files.push(write_changes(&params.dest.join(CHANGES_FILE), &changes)?);
+            changed.extend(changes.documents.keys().copied());
+            changed.sort_unstable();
+        }
+        let manifest = BackupManifest {
+            version: MANIFEST_VERSION,
+            id: params
+                .dest
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default()
+                .to_string(),
+            kind,
+            parent: parent.map(|parent| parent.id.clone()),
+            created,
+            accounts,
+            changed,
+            principals,
+            pending,
+            quota: None,
+            change_id,
+            files,
+        };
+        manifest.write(&params.dest)?;
+
+        Ok(manifest)
+    }
+}
+
+impl BackupManifest {
+    pub fn read(path: &Path) -> trc::Result<Self> {
+        let path = path.join(MANIFEST_FILE);
+        let bytes = std::fs::read(&path).map_err(|err| io_error(&path, err))?;
+        serde_json::from_slice(&bytes).map_err(|err| {
+            trc::EventType::Store(trc::StoreEvent::DeserializeError)
+                .from_json_error(err)
+                .ctx(trc::Key::Path, path.to_string_lossy().into_owned())
+        })
+    }
+
+    pub(super) fn write(&self, path: &Path) -> trc::Result<()> {
+        let tmp_path = path.join(format!("{MANIFEST_FILE}.tmp"));
+        let bytes = serde_json::to_vec_pretty(self).map_err(|err| {
+            trc::EventType::Store(trc::StoreEvent::UnexpectedError).from_json_error(err)
+        })?;
+        std::fs::write(&tmp_path, bytes).map_err(|err| io_error(&tmp_path, err))?;
+        std::fs::rename(&tmp_path, path.join(MANIFEST_FILE)).map_err(|err| io_error(&tmp_path, err))
+    }
+
+    /// Verifies the size and checksum of every file in the backup.
+    pub fn verify(&self, path: &Path) -> trc::Result<()> {
+        for file in &self.files {
+            let file_path = path.join(&file.name);
+            let mut hasher = Sha256::new();
+            let size = std::fs::File::open(&file_path)
+                .and_then(|mut reader| std::io::copy(&mut reader, &mut hasher))
+                .map_err(|err| io_error(&file_path, err))?;
+
+            if size != file.size || hex_digest(hasher.finalize().as_slice()) != file.sha256 {
+                return Err(trc::StoreEvent::DataCorruption
+                    .into_err()
+                    .details("Backup file checksum mismatch")
+                    .ctx(trc::Key::Path, file_path.to_string_lossy().into_owned()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(super) fn format_timestamp(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Loads the manifests of all backups in a directory, sorted by creation time.
+pub(super) fn load_manifests(path: &Path) -> trc::Result<Vec<BackupManifest>> {
+    let mut manifests = Vec::new();
+
+    if path.exists() {
+        for entry in std::fs::read_dir(path).map_err(|err| io_error(path, err))? {
+            let entry_path = entry.map_err(|err| io_error(path, err))?.path();
+            if entry_path.join(MANIFEST_FILE).is_file() {
+                manifests.push(BackupManifest::read(&entry_path)?);
+            }
+        }
+    }
+
+    manifests.sort_unstable_by(|a, b| a.created.cmp(&b.created).then_with(|| a.id.cmp(&b.id)));
+
+    Ok(manifests)
+}
+
+/// Returns the backups to restore in order, starting with the full backup the
+/// target belongs to.
+pub(super) fn resolve_chain(
+    manifests: &[BackupManifest],
+    target: &BackupManifest,
+) -> trc::Result<Vec<BackupManifest>> {
+    let by_id = manifests
+        .iter()
+        .map(|manifest| (manifest.id.as_str(), manifest))
+        .collect::<AHashMap<_, _>>();
+    let mut chain = vec![target.clone()];
+
+    while let Some(parent_id) = chain.last().and_then(|manifest| manifest.parent.as_ref()) {
+        if let Some(parent) = by_id.get(parent_id.as_str()) {
+            chain.push((*parent).clone());
+        } else {
+            return Err(trc::StoreEvent::NotFound
+                .into_err()
+                .details("Backup chain is incomplete")
+                .ctx(trc::Key::Id, parent_id.clone()));
+        }
+
+        if chain.len() > manifests.len() {
+            return Err(trc::StoreEvent::DataCorruption
+                .into_err()
+                .details("Backup chain contains a loop"));
+        }
+    }
+
+    chain.reverse();
+    Ok(chain)
+}
+
+fn chain_length(manifests: &[BackupManifest], target: &BackupManifest) -> usize {
+    resolve_chain(manifests, target).map_or(usize::MAX, |chain| chain.len())
+}
+
+fn prune_backups(path: &Path, manifests: &[BackupManifest], retention: usize) {
+    let full_ids = manifests
+        .iter()
+        .filter(|manifest| manifest.kind == BackupKind::Full)
+        .map(|manifest| manifest.id.as_str())
+        .collect::<Vec<_>>();
+    if full_ids.len() <= retention {
+        return;
+    }
+    let keep = &full_ids[full_ids.len() - retention..];
+
+    for manifest in manifests {
+        let is_expired = resolve_chain(manifests, manifest)
+            .map_or(false, |chain| !keep.contains(&chain[0].id.as_str()));
+        if is_expired {
+            let backup_path: PathBuf = path.join(&manifest.id);
+            if let Err(err) = std::fs::remove_dir_all(&backup_path) {
+                trc::error!(io_error(&backup_path, err).details("Failed to remove expired backup"));
+            }
+        }
+    }
+}
+
+async fn principal_ids(store: &Store) -> trc::Result<Vec<u32>> {
+    let mut principal_ids = Vec::new();
+
+    store
+        .iterate(
+            IterateParams::new(
+                ValueKey::from(ValueClass::Directory(DirectoryClass::Principal(0))),
+                ValueKey::from(ValueClass::Directory(DirectoryClass::Principal(u32::MAX))),
+            )
+            .no_values(),
+            |key, _| {
+                if let Some((principal_id, _)) =
+                    key.get(1..).and_then(|key| key.read_leb128::<u32>())
+                {
+                    principal_ids.push(principal_id);
+                }
+
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+    Ok(principal_ids)
+}
+
+/// Collects the documents changed since a change id from the change log of each
+/// principal. Accounts whose change log no longer reaches back to that change
+/// id, which includes the ones created since, are added to `accounts` instead
+/// so they get exported in full.
+async fn changed_documents(
+    store: &Store,
+    principals: &[u32],
+    previous_principals: &[u32],
+    since: u64,
+    accounts: &mut AHashSet<u32>,
+) -> trc::Result<ChangedDocuments> {
+    let previous_principals = previous_principals.iter().collect::<AHashSet<_>>();
+    let mut changes = ChangedDocuments {
+        since,
+        documents: AHashMap::new(),
+    };
+
+    'outer: for &account_id in principals {
+        if accounts.contains(&account_id) {
+            continue;
+        } else if !previous_principals.contains(&account_id) {
+            accounts.insert(account_id);
+            continue;
+        }
+
+        let mut documents = AHashMap::new();
+        for collection in 0..u8::from(Collection::None) {
+            if first_change_id(store, account_id, collection)
+                .await?
+                .map_or(false, |change_id| change_id > since)
+            {
+                accounts.insert(account_id);
+                continue 'outer;
+            }
+
+            // Entries are read one by one, merging them would drop documents
+            // inserted before and deleted after the parent's change id
+            let mut changed = RoaringBitmap::new();
+            store
+                .iterate(
+                    IterateParams::new(
+                        LogKey {
+                            account_id,
+                            collection,
+                            change_id: since,
+                        },
+                        LogKey {
+                            account_id,
+                            collection,
+                            change_id: u64::MAX,
+                        },
+                    ),
+                    |key, value| {
+                        let mut entry = Changes::default();
+                        entry.deserialize(value).ok_or_else(|| {
+                            trc::Error::corrupted_key(key, value.into(), trc::location!())
+                        })?;
+                        for change in entry.changes {
+                            let (Change::Insert(id)
+                            | Change::Update(id)
+                            | Change::ChildUpdate(id)
+                            | Change::Delete(id)) = change;
+                            changed.insert(id as u32);
+                        }
+
+                        Ok(true)
+                    },
+                )
+                .await
+                .caused_by(trc::location!())?;
+
+            if !changed.is_empty() {
+                documents.insert(collection, changed);
+            }
+        }
+
+        if !documents.is_empty() {
+            changes.documents.insert(account_id, documents);
+        }
+    }
+
+    Ok(changes)
+}
+
+fn write_changes(path: &Path, changes: &ChangedDocuments) -> trc::Result<BackupFile> {
+    let (handle, writer) = spawn_writer(path.to_path_buf(), false);
+    let mut result = writer.send(Op::Family(Family::Log));
+
+    for (account_id, collections) in &changes.documents {
+        if result.is_ok() {
+            result = writer.send(Op::AccountId(*account_id));
+        }
+        for (collection, documents) in collections {
+            if result.is_err() {
+                break;
+            }
+            let mut bytes = Vec::with_capacity(documents.serialized_size());
+            result = documents
+                .serialize_into(&mut bytes)
+                .map_err(|err| {
+                    trc::StoreEvent::UnexpectedError
+                        .reason(err)
+                        .caused_by(trc::location!())
+                })
+                .and_then(|_| writer.send(Op::KeyValue((vec![*collection], bytes))));
+        }
+    }
+    drop(writer);
+
+    let file = handle.join().map_err(|_| {
+        trc::EventType::Server(trc::ServerEvent::ThreadError)
+            .into_err()
+            .details("Failed to join backup writer thread")
+    })?;
+    file.and_then(|file| result.map(|_| file))
+}
+
+async fn first_change_id(
+    store: &Store,
+    account_id: u32,
+    collection: u8,
+) -> trc::Result<Option<u64>> {
+    let mut first_change_id = None;
+
+    store
+        .iterate(
+            IterateParams::new(
+                LogKey {
+                    account_id,
+                    collection,
+                    change_id: 0,
+                },
+                LogKey {
+                    account_id,
+                    collection,
+                    change_id: u64::MAX,
+                },
+            )
+            .ascending()
+            .no_values()
+            .only_first(),
+            |key, _| {
+                first_change_id = key.deserialize_be_u64(key.len() - U64_LEN)?.into();
+                Ok(false)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+    Ok(first_change_id)
+}
+
+async fn pending_index_accounts(store: &Store) -> trc::Result<Vec<u32>> {
+    let mut accounts = AHashSet::new();
+
+    store
+        .iterate(
+            IterateParams::new(
+                AnyKey {
+                    subspace: SUBSPACE_FTS_QUEUE,
+                    key: vec![0u8],
+                },
+                AnyKey {
+                    subspace: SUBSPACE_FTS_QUEUE,
+                    key: vec![u8::MAX; 16],
+                },
+            )
+            .no_values(),
+            |key, _| {
+                accounts.insert(key.deserialize_be_u32(U64_LEN)?);
+
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+    let mut accounts = accounts.into_iter().collect::<Vec<_>>();
+    accounts.sort_unstable();
+    Ok(accounts)
+}
+
+async fn read_blob_index(path: &Path) -> trc::Result<AHashSet<Vec<u8>>> {
+    let mut hashes = AHashSet::new();
+
+    if path.exists() {
+        let mut reader = super::restore::OpReader::open(path).await?;
+        while let Some(op) = reader.try_next().await? {
+            match op {
+                Op::KeyValue((hash, _)) => {
+                    hashes.insert(hash);
+                }
+                Op::Family(Family::Blob) => {}
+                _ => {
+                    return Err(trc::StoreEvent::DataCorruption
+                        .into_err()
+                        .details("Invalid blob index")
+                        .ctx(trc::Key::Path, path.to_string_lossy().into_owned()));
+                }
+            }
+        }
+    }
+
+    Ok(hashes)
+}
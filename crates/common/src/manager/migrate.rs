@@ -0,0 +1,505 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+use store::{
+    write::{now, AnyKey},
+    BlobStore, IterateParams, Store, SUBSPACE_DIRECTORY, SUBSPACE_PROPERTY,
+};
+use trc::AddContext;
+use utils::config::ConfigKey;
+
+use crate::{ipc::HousekeeperEvent, Core, Server};
+
+use super::{
+    backup::{io_error, BackupParams, BackupScope, BLOB_INDEX_FILE},
+    manifest::{load_manifests, BackupKind, BackupManifest, CHANGES_FILE},
+    restore::{import_file, purge_all, purge_replaced},
+};
+
+const STATE_FILE: &str = "state.json";
+
+// Catch-up passes stop once a pass changed this many accounts or less
+const CUTOVER_ACCOUNTS: usize = 10;
+const MAX_PASSES: usize = 10;
+
+static MIGRATION_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Progress of a migration, stored next to the passes exported from the source
+/// store so an interrupted migration can be resumed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationState {
+    pub source: String,
+    pub store: String,
+    #[serde(default)]
+    pub blob: Option<String>,
+    pub started: u64,
+    #[serde(default)]
+    pub imported: Vec<String>,
+    #[serde(default)]
+    pub completed: Option<u64>,
+}
+
+/// Destination of a migration, blob contents are not copied when `blob` is not set.
+pub struct MigrationTarget {
+    pub store: Store,
+    pub blob: Option<BlobStore>,
+}
+
+impl Server {
+    /// Copies the data store, and the blob store when requested or stored in the
+    /// data store, to `store_id` while the server is running. Once the destination
+    /// has caught up, the source is made read-only on all cluster nodes for a final
+    /// pass and the server is switched over to the destination. The source stays
+    /// read-only afterwards so nodes still using it fail to write instead of
+    /// writing data that is not migrated.
+    pub async fn migrate_store(
+        &self,
+        store_id: &str,
+        blob_id: Option<&str>,
+    ) -> trc::Result<MigrationState> {
+        if MIGRATION_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+            return Err(trc::ManageEvent::AssertFailed
+                .into_err()
+                .details("Another migration is already in progress"));
+        }
+
+        let result = self.migrate_store_(store_id, blob_id).await;
+        MIGRATION_IN_PROGRESS.store(false, Ordering::SeqCst);
+        result
+    }
+
+    async fn migrate_store_(
+        &self,
+        store_id: &str,
+        blob_id: Option<&str>,
+    ) -> trc::Result<MigrationState> {
+        let time = Instant::now();
+        let core = self.core.clone();
+        let settings = &core.storage.migration;
+        let config = &core.storage.config;
+        let source_id = config.get("storage.data").await?.unwrap_or_default();
+        if source_id == store_id {
+            return Err(trc::ManageEvent::AssertFailed
+                .into_err()
+                .details("Destination is the current data store")
+                .ctx(trc::Key::Id, store_id.to_string()));
+        }
+
+        // Blobs stored in the data store move along with it
+        let blob_id = if let Some(blob_id) = blob_id {
+            Some(blob_id.to_string())
+        } else if config.get("storage.blob").await?.as_deref() == Some(source_id.as_str()) {
+            Some(store_id.to_string())
+        } else {
+            None
+        };
+        let target = MigrationTarget {
+            store: core.storage.stores.get(store_id).cloned().ok_or_else(|| {
+                trc::ManageEvent::NotFound
+                    .into_err()
+                    .details("Data store not found")
+                    .ctx(trc::Key::Id, store_id.to_string())
+            })?,
+            blob: blob_id
+                .as_ref()
+                .map(|blob_id| {
+                    core.storage.blobs.get(blob_id).cloned().ok_or_else(|| {
+                        trc::ManageEvent::NotFound
+                            .into_err()
+                            .details("Blob store not found")
+                            .ctx(trc::Key::Id, blob_id.to_string())
+                    })
+                })
+                .transpose()?,
+        };
+
+        let path = settings.path.join(store_id);
+        let mut state = match MigrationState::read(&path)? {
+            Some(state)
+                if state.completed.is_none()
+                    && state.source == source_id
+                    && state.blob == blob_id =>
+            {
+                state
+            }
+            Some(_) => {
+                return Err(trc::ManageEvent::AlreadyExists
+                    .into_err()
+                    .details("A different migration to this store exists")
+                    .ctx(trc::Key::Path, path.to_string_lossy().into_owned()));
+            }
+            None => {
+                if !is_empty(&target.store).await? {
+                    return Err(trc::ManageEvent::AssertFailed
+                        .into_err()
+                        .details("Destination store is not empty")
+                        .ctx(trc::Key::Id, store_id.to_string()));
+                }
+                // The destination may have been the source of an earlier migration
+                if target.store.is_read_only() {
+                    target.store.set_read_only(false).await?;
+                }
+                std::fs::create_dir_all(&path).map_err(|err| io_error(&path, err))?;
+                let state = MigrationState {
+                    source: source_id.clone(),
+                    store: store_id.to_string(),
+                    blob: blob_id,
+                    started: now(),
+                    imported: vec![],
+                    completed: None,
+                };
+                state.write(&path)?;
+                state
+            }
+        };
+
+        trc::event!(
+            Store(trc::StoreEvent::MigrationStart),
+            Id = store_id.to_string(),
+            Path = path.to_string_lossy().into_owned(),
+            Total = state.imported.len(),
+        );
+
+        core.migrate_data(&path, &mut state, &target, false).await?;
+
+        // Suspend writes to the source for the final pass, other nodes reload their
+        // settings and pick up the read-only state within the grace period while
+        // sessions still holding the previous core fail to write instead of losing data
+        trc::event!(
+            Store(trc::StoreEvent::MigrationCutover),
+            Id = store_id.to_string(),
+        );
+        let source = core.storage.data.clone();
+        source.set_read_only(true).await?;
+        self.inner
+            .data
+            .config_version
+            .fetch_add(1, Ordering::Relaxed);
+        tokio::time::sleep(settings.cutover_grace).await;
+
+        if let Err(err) = self.cutover(&path, &mut state, &target).await {
+            source.set_read_only(false).await?;
+            self.inner
+                .data
+                .config_version
+                .fetch_add(1, Ordering::Relaxed);
+            return Err(err);
+        }
+
+        state.completed = Some(now());
+        state.write(&path)?;
+
+        trc::event!(
+            Store(trc::StoreEvent::MigrationComplete),
+            Id = store_id.to_string(),
+            Elapsed = time.elapsed(),
+        );
+
+        Ok(state)
+    }
+
+    async fn cutover(
+        &self,
+        path: &Path,
+        state: &mut MigrationState,
+        target: &MigrationTarget,
+    ) -> trc::Result<()> {
+        self.core.migrate_data(path, state, target, true).await?;
+
+        // Point the settings that referenced the source store to the destination
+        let config = &self.core.storage.config;
+        let mut updates = Vec::new();
+        let mut previous = Vec::new();
+        for (key, value) in config.list("storage.", false).await? {
+            let new_value = match key.as_str() {
+                "storage.data" | "storage.fts" | "storage.lookup" if value == state.source => {
+                    state.store.clone()
+                }
+                "storage.blob" => {
+                    if let Some(blob_id) = &state.blob {
+                        blob_id.clone()
+                    } else {
+                        continue;
+                    }
+                }
+                _ => continue,
+            };
+            updates.push(ConfigKey::from((key.clone(), new_value)));
+            previous.push(ConfigKey::from((key, value)));
+        }
+        for (key, value) in config.list("directory.", false).await? {
+            if key.ends_with(".store") && value == state.source {
+                updates.push(ConfigKey::from((key.clone(), state.store.clone())));
+                previous.push(ConfigKey::from((key, value)));
+            }
+        }
+        config.set(updates).await?;
+
+        let result = match self.reload().await {
+            Ok(result) => result,
+            Err(err) => {
+                config.set(previous).await?;
+                return Err(err);
+            }
+        };
+        if let Some(core) = result.new_core {
+            self.inner.shared_core.store(core.into());
+            self.inner
+                .data
+                .config_version
+                .fetch_add(1, Ordering::Relaxed);
+            self.inner
+                .ipc
+                .housekeeper_tx
+                .send(HousekeeperEvent::ReloadSettings)
+                .await
+                .map_err(|err| {
+                    trc::EventType::Server(trc::ServerEvent::ThreadError)
+                        .reason(err)
+                        .details("Failed to send settings reload event to housekeeper")
+                        .caused_by(trc::location!())
+                })
+        } else {
+            config.set(previous).await?;
+
+            Err(trc::StoreEvent::UnexpectedError
+                .into_err()
+                .details("Failed to load the destination store configuration")
+                .ctx_opt(
+                    trc::Key::Reason,
+                    result
+                        .config
+                        .errors
+                        .into_iter()
+                        .next()
+                        .map(|(key, err)| format!("{key}: {err:?}")),
+                ))
+        }
+    }
+}
+
+impl Core {
+    /// Copies the data store to the migration target by exporting passes to `path`
+    /// and importing them, a full pass first followed by incremental passes until
+    /// only a few accounts changed since the previous one. Passes exported but not
+    /// imported before an interruption are imported first. When `final_pass` is
+    /// set, one more pass is copied after catching up.
+    pub async fn migrate_data(
+        &self,
+        path: &Path,
+        state: &mut MigrationState,
+        target: &MigrationTarget,
+        final_pass: bool,
+    ) -> trc::Result<()> {
+        let mut manifests = load_manifests(path)?;
+        import_passes(self, path, &manifests, state, target).await?;
+
+        let mut final_pass = final_pass;
+        loop {
+            if is_caught_up(&manifests) {
+                if !final_pass {
+                    break;
+                }
+                final_pass = false;
+            }
+
+            let manifest = self
+                .export_pass(
+                    path,
+                    manifests.last(),
+                    target.blob.is_none(),
+                    manifests.len(),
+                )
+                .await?;
+            manifests.push(manifest);
+            import_passes(self, path, &manifests, state, target).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn export_pass(
+        &self,
+        path: &Path,
+        parent: Option<&BackupManifest>,
+        skip_contents: bool,
+        num: usize,
+    ) -> trc::Result<BackupManifest> {
+        let id = format!(
+            "{num:04}-{}",
+            if parent.is_some() {
+                "incremental"
+            } else {
+                "full"
+            }
+        );
+        let dest = path.join(id);
+
+        // Remove the leftovers of an interrupted export
+        if dest.exists() {
+            std::fs::remove_dir_all(&dest).map_err(|err| io_error(&dest, err))?;
+        }
+        std::fs::create_dir_all(&dest).map_err(|err| io_error(&dest, err))?;
+
+        let params = BackupParams {
+            dest: dest.clone(),
+            scope: BackupScope {
+                skip_contents,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        match self.create_backup(params, parent).await {
+            Ok(manifest) => Ok(manifest),
+            Err(err) => {
+                let _ = std::fs::remove_dir_all(&dest);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn list_migrations(&self) -> trc::Result<Vec<MigrationState>> {
+        let mut migrations = Vec::new();
+
+        let path = &self.storage.migration.path;
+        if path.exists() {
+            for entry in std::fs::read_dir(path).map_err(|err| io_error(path, err))? {
+                let entry_path = entry.map_err(|err| io_error(path, err))?.path();
+                if let Some(state) = MigrationState::read(&entry_path)? {
+                    migrations.push(state);
+                }
+            }
+        }
+
+        migrations.sort_unstable_by_key(|state| state.started);
+
+        Ok(migrations)
+    }
+}
+
+impl MigrationState {
+    pub fn read(path: &Path) -> trc::Result<Option<Self>> {
+        let path = path.join(STATE_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let bytes = std::fs::read(&path).map_err(|err| io_error(&path, err))?;
+        serde_json::from_slice(&bytes).map(Some).map_err(|err| {
+            trc::EventType::Store(trc::StoreEvent::DeserializeError)
+                .from_json_error(err)
+                .ctx(trc::Key::Path, path.to_string_lossy().into_owned())
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> trc::Result<()> {
+        let tmp_path = path.join(format!("{STATE_FILE}.tmp"));
+        let bytes = serde_json::to_vec_pretty(self).map_err(|err| {
+            trc::EventType::Store(trc::StoreEvent::UnexpectedError).from_json_error(err)
+        })?;
+        std::fs::write(&tmp_path, bytes).map_err(|err| io_error(&tmp_path, err))?;
+        std::fs::rename(&tmp_path, path.join(STATE_FILE)).map_err(|err| io_error(&tmp_path, err))
+    }
+}
+
+/// Imports the passes missing from the destination. The destination is wiped
+/// before importing a full pass and the data replaced by an incremental pass is
+/// removed first, which makes importing a pass again after an interruption safe.
+async fn import_passes(
+    core: &Core,
+    path: &Path,
+    manifests: &[BackupManifest],
+    state: &mut MigrationState,
+    target: &MigrationTarget,
+) -> trc::Result<()> {
+    let blob_store = target.blob.as_ref().unwrap_or(&core.storage.blob);
+    let mut previous: Option<&BackupManifest> = None;
+
+    for manifest in manifests {
+        if !state.imported.contains(&manifest.id) {
+            let time = Instant::now();
+            let pass_path = path.join(&manifest.id);
+            manifest.verify(&pass_path)?;
+
+            if let Some(previous) = previous {
+                purge_replaced(&target.store, &pass_path, previous, manifest).await?;
+            } else {
+                purge_all(&target.store).await?;
+            }
+
+            for file in &manifest.files {
+                if file.name != BLOB_INDEX_FILE && file.name != CHANGES_FILE {
+                    import_file(&target.store, blob_store, &pass_path.join(&file.name)).await?;
+                }
+            }
+
+            state.imported.push(manifest.id.clone());
+            state.write(path)?;
+
+            trc::event!(
+                Store(trc::StoreEvent::MigrationProgress),
+                Id = manifest.id.clone(),
+                Type = if manifest.kind == BackupKind::Full {
+                    "full"
+                } else {
+                    "incremental"
+                },
+                Total = manifest.accounts.len() + manifest.changed.len(),
+                Size = manifest.files.iter().map(|file| file.size).sum::<u64>(),
+                Elapsed = time.elapsed(),
+            );
+        }
+
+        previous = Some(manifest);
+    }
+
+    Ok(())
+}
+
+fn is_caught_up(manifests: &[BackupManifest]) -> bool {
+    manifests.last().map_or(false, |last| {
+        last.kind == BackupKind::Incremental
+            && (last.accounts.len() + last.changed.len() <= CUTOVER_ACCOUNTS
+                || manifests.len() >= MAX_PASSES)
+    })
+}
+
+async fn is_empty(store: &Store) -> trc::Result<bool> {
+    let mut is_empty = true;
+
+    for subspace in [SUBSPACE_DIRECTORY, SUBSPACE_PROPERTY] {
+        store
+            .iterate(
+                IterateParams::new(
+                    AnyKey {
+                        subspace,
+                        key: vec![0u8],
+                    },
+                    AnyKey {
+                        subspace,
+                        key: vec![u8::MAX; 32],
+                    },
+                )
+                .no_values(),
+                |_, _| {
+                    is_empty = false;
+                    Ok(false)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+    }
+
+    Ok(is_empty)
+}
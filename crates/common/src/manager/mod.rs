@@ -14,6 +14,8 @@ pub mod backup;
 pub mod boot;
 pub mod config;
 pub mod console;
+pub mod manifest;
+pub mod migrate;
 pub mod reload;
 pub mod restore;
 pub mod webadmin;
@@ -10,15 +10,24 @@ use std::{
 };
 
 use crate::Core;
-use jmap_proto::types::{collection::Collection, property::Property};
+use ahash::{AHashMap, AHashSet};
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, id::Id, property::Property, value::Value},
+};
 use store::{
     roaring::RoaringBitmap,
     write::{
-        key::DeserializeBigEndian, BatchBuilder, BitmapClass, BitmapHash, BlobOp, DirectoryClass,
-        FtsQueueClass, LookupClass, MaybeDynamicId, MaybeDynamicValue, Operation, TagValue,
-        ValueClass,
+        key::DeserializeBigEndian, log::ChangeLogBuilder, AnyClass, AnyKey, BatchBuilder,
+        BitmapClass, BitmapHash, BlobOp, DeserializeFrom, DirectoryClass, FtsQueueClass,
+        LookupClass, MaybeDynamicId, MaybeDynamicValue, Operation, TagValue, ValueClass,
     },
-    BlobStore, Serialize, Store, U32_LEN,
+    BitmapKey, BlobStore, IterateParams, Serialize, Store, SUBSPACE_ACL, SUBSPACE_BITMAP_ID,
+    SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SUBSPACE_BLOB_LINK, SUBSPACE_COUNTER,
+    SUBSPACE_DIRECTORY, SUBSPACE_FTS_INDEX, SUBSPACE_FTS_QUEUE, SUBSPACE_FTS_TERM,
+    SUBSPACE_INDEXES, SUBSPACE_LOGS, SUBSPACE_LOOKUP_VALUE, SUBSPACE_PROPERTY,
+    SUBSPACE_QUEUE_EVENT, SUBSPACE_QUEUE_MESSAGE, SUBSPACE_QUOTA, SUBSPACE_SETTINGS,
+    SUBSPACE_SNOOZE_EVENT, U32_LEN,
 };
 use store::{
     write::{QueueClass, QueueEvent},
@@ -28,41 +37,523 @@ use tokio::{
     fs::File,
     io::{AsyncReadExt, BufReader},
 };
-use utils::{failed, BlobHash, UnwrapFailure};
+use trc::AddContext;
+use utils::{
+    codec::leb128::{Leb128Iterator, Leb128Vec},
+    BlobHash, BLOB_HASH_LEN,
+};
 
-use super::backup::{DeserializeBytes, Family, Op, FILE_VERSION, MAGIC_MARKER};
+use super::{
+    backup::{io_error, DeserializeBytes, Family, Op, BLOB_INDEX_FILE, FILE_VERSION, MAGIC_MARKER},
+    manifest::{
+        load_manifests, resolve_chain, BackupKind, BackupManifest, ACCOUNTS_DIR, CHANGES_FILE,
+        MANIFEST_FILE,
+    },
+};
 
 impl Core {
-    pub async fn restore(&self, src: PathBuf) {
-        // Backup the core
-        if src.is_dir() {
-            // Iterate directory and spawn a task for each file
-            let mut tasks = Vec::new();
-            for entry in std::fs::read_dir(&src).failed("Failed to read directory") {
-                let entry = entry.failed("Failed to read entry");
-                let path = entry.path();
-                if path.is_file() {
-                    let storage = self.storage.clone();
-                    let blob_store = self.storage.blob.clone();
-                    tasks.push(tokio::spawn(async move {
-                        restore_file(storage.data, blob_store, &path).await;
-                    }));
+    pub async fn restore(&self, src: PathBuf, until: Option<u64>) {
+        let result = if src.join(MANIFEST_FILE).is_file() || src.is_dir() && has_backups(&src) {
+            self.restore_backup(&src, until).await
+        } else if src.is_dir() {
+            match std::fs::read_dir(&src).and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            }) {
+                Ok(paths) => {
+                    self.restore_files(paths.into_iter().filter(|path| path.is_file()).collect())
+                        .await
                 }
+                Err(err) => Err(io_error(&src, err)),
             }
+        } else {
+            restore_file(self.storage.data.clone(), self.storage.blob.clone(), &src).await
+        };
+
+        if let Err(err) = result {
+            eprintln!("Restore failed: {err}");
+            std::process::exit(1);
+        }
+    }
+
+    /// Restores a full backup followed by its chain of incremental backups, either
+    /// ending at the backup in `src` or at the latest backup created before `until`.
+    async fn restore_backup(&self, src: &Path, until: Option<u64>) -> trc::Result<()> {
+        let (root, target) = if src.join(MANIFEST_FILE).is_file() {
+            (
+                src.parent().unwrap_or(src).to_path_buf(),
+                BackupManifest::read(src)?,
+            )
+        } else {
+            let target = load_manifests(src)?
+                .into_iter()
+                .rfind(|manifest| until.map_or(true, |until| manifest.created <= until))
+                .ok_or_else(|| {
+                    trc::StoreEvent::NotFound
+                        .into_err()
+                        .details("No backup found for the requested point in time")
+                })?;
+            (src.to_path_buf(), target)
+        };
+        if target.kind == BackupKind::Account {
+            return Err(trc::StoreEvent::NotSupported
+                .into_err()
+                .details("Account backups are restored through the management API"));
+        }
+        let chain = resolve_chain(&load_manifests(&root)?, &target)?;
+
+        // Verify all checksums before modifying the store
+        for manifest in &chain {
+            println!("Verifying backup {}.", manifest.id);
+            manifest.verify(&root.join(&manifest.id))?;
+        }
+
+        let store = &self.storage.data;
+        let mut previous: Option<&BackupManifest> = None;
+        for manifest in &chain {
+            println!("Restoring backup {}.", manifest.id);
+
+            let path = root.join(&manifest.id);
+            if let Some(previous) = previous {
+                purge_replaced(store, &path, previous, manifest).await?;
+            } else {
+                // Data left in the store would be mixed with the restored data
+                purge_all(store).await?;
+            }
+
+            self.restore_files(
+                manifest
+                    .files
+                    .iter()
+                    .map(|file| path.join(&file.name))
+                    .collect(),
+            )
+            .await?;
+
+            previous = Some(manifest);
+        }
+
+        Ok(())
+    }
 
-            for task in tasks {
-                task.await.failed("Failed to wait for task");
+    async fn restore_files(&self, paths: Vec<PathBuf>) -> trc::Result<()> {
+        // Spawn a task for each file
+        let mut tasks = Vec::new();
+        for path in paths {
+            if path.file_name().map_or(false, |name| {
+                name == BLOB_INDEX_FILE || name == MANIFEST_FILE || name == CHANGES_FILE
+            }) {
+                continue;
             }
+
+            let storage = self.storage.clone();
+            let blob_store = self.storage.blob.clone();
+            tasks.push(tokio::spawn(async move {
+                restore_file(storage.data, blob_store, &path).await
+            }));
+        }
+
+        let mut result = Ok(());
+        for task in tasks {
+            let task_result = task
+                .await
+                .map_err(|err| {
+                    trc::EventType::Server(trc::ServerEvent::ThreadError)
+                        .reason(err)
+                        .details("Restore task failed")
+                })
+                .and_then(|result| result);
+            if result.is_ok() {
+                result = task_result;
+            }
+        }
+
+        result
+    }
+}
+
+fn has_backups(path: &Path) -> bool {
+    std::fs::read_dir(path).map_or(false, |entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.path().join(MANIFEST_FILE).is_file())
+    })
+}
+
+/// Removes the accounts and documents about to be replaced by the incremental
+/// backup in `path` or deleted since the previous backup, along with all
+/// global data.
+pub(super) async fn purge_replaced(
+    store: &Store,
+    path: &Path,
+    previous: &BackupManifest,
+    manifest: &BackupManifest,
+) -> trc::Result<()> {
+    let principals = manifest.principals.iter().collect::<AHashSet<_>>();
+    let accounts = manifest
+        .accounts
+        .iter()
+        .copied()
+        .chain(
+            previous
+                .principals
+                .iter()
+                .filter(|id| !principals.contains(id))
+                .copied(),
+        )
+        .collect::<AHashSet<_>>();
+
+    purge_accounts(store, &accounts).await?;
+    if manifest.files.iter().any(|file| file.name == CHANGES_FILE) {
+        purge_documents(store, &read_changes(&path.join(CHANGES_FILE)).await?).await?;
+    }
+    purge_globals(store).await
+}
+
+/// Removes all data that can be restored from a backup, blob contents are kept.
+pub(super) async fn purge_all(store: &Store) -> trc::Result<()> {
+    for subspace in [
+        SUBSPACE_ACL,
+        SUBSPACE_BITMAP_ID,
+        SUBSPACE_BITMAP_TAG,
+        SUBSPACE_BITMAP_TEXT,
+        SUBSPACE_BLOB_LINK,
+        SUBSPACE_COUNTER,
+        SUBSPACE_DIRECTORY,
+        SUBSPACE_FTS_INDEX,
+        SUBSPACE_FTS_QUEUE,
+        SUBSPACE_FTS_TERM,
+        SUBSPACE_INDEXES,
+        SUBSPACE_LOGS,
+        SUBSPACE_LOOKUP_VALUE,
+        SUBSPACE_PROPERTY,
+        SUBSPACE_QUEUE_EVENT,
+        SUBSPACE_QUEUE_MESSAGE,
+        SUBSPACE_QUOTA,
+        SUBSPACE_SETTINGS,
+        SUBSPACE_SNOOZE_EVENT,
+    ] {
+        store
+            .delete_range(
+                AnyKey {
+                    subspace,
+                    key: vec![0u8],
+                },
+                AnyKey {
+                    subspace,
+                    key: vec![u8::MAX; 32],
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+    }
+
+    Ok(())
+}
+
+async fn purge_accounts(store: &Store, accounts: &AHashSet<u32>) -> trc::Result<()> {
+    for account_id in accounts {
+        let from_key = account_id.to_be_bytes().to_vec();
+        let to_key = if *account_id < u32::MAX {
+            (account_id + 1).to_be_bytes().to_vec()
+        } else {
+            vec![u8::MAX; 32]
+        };
+
+        for subspace in [
+            SUBSPACE_BITMAP_ID,
+            SUBSPACE_BITMAP_TAG,
+            SUBSPACE_BITMAP_TEXT,
+            SUBSPACE_LOGS,
+            SUBSPACE_INDEXES,
+            SUBSPACE_PROPERTY,
+            SUBSPACE_FTS_INDEX,
+            SUBSPACE_FTS_TERM,
+        ] {
+            store
+                .delete_range(
+                    AnyKey {
+                        subspace,
+                        key: from_key.clone(),
+                    },
+                    AnyKey {
+                        subspace,
+                        key: to_key.clone(),
+                    },
+                )
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        // Mailbox UID counters
+        delete_keys(
+            store,
+            SUBSPACE_COUNTER,
+            from_key,
+            to_key,
+            is_mailbox_counter,
+        )
+        .await?;
+    }
+
+    // ACLs are keyed by grantee and blob links by hash
+    delete_keys(
+        store,
+        SUBSPACE_ACL,
+        vec![0u8],
+        vec![u8::MAX; 16],
+        |key: &[u8]| {
+            key.deserialize_be_u32(U32_LEN)
+                .map_or(false, |account_id| accounts.contains(&account_id))
+        },
+    )
+    .await?;
+    delete_keys(
+        store,
+        SUBSPACE_BLOB_LINK,
+        vec![0u8],
+        vec![u8::MAX; BLOB_HASH_LEN + 16],
+        |key: &[u8]| {
+            key.len() > BLOB_HASH_LEN + U32_LEN
+                && key
+                    .deserialize_be_u32(BLOB_HASH_LEN + U32_LEN + 1)
+                    .map_or(false, |document_id| document_id != u32::MAX)
+                && (key[BLOB_HASH_LEN + U32_LEN] == u8::MAX
+                    || key
+                        .deserialize_be_u32(BLOB_HASH_LEN)
+                        .map_or(false, |account_id| accounts.contains(&account_id)))
+        },
+    )
+    .await
+}
+
+async fn purge_documents(
+    store: &Store,
+    documents: &AHashMap<u32, AHashMap<u8, RoaringBitmap>>,
+) -> trc::Result<()> {
+    let is_changed = |account_id: u32, collection: Option<&u8>, key: &[u8]| {
+        collection
+            .and_then(|collection| {
+                documents
+                    .get(&account_id)
+                    .and_then(|collections| collections.get(collection))
+            })
+            .zip(
+                key.len()
+                    .checked_sub(U32_LEN)
+                    .and_then(|offset| key.deserialize_be_u32(offset).ok()),
+            )
+            .map_or(false, |(documents, document_id)| {
+                documents.contains(document_id)
+            })
+    };
+
+    for account_id in documents.keys() {
+        let from_key = account_id.to_be_bytes().to_vec();
+        let to_key = if *account_id < u32::MAX {
+            (account_id + 1).to_be_bytes().to_vec()
         } else {
-            restore_file(self.storage.data.clone(), self.storage.blob.clone(), &src).await;
+            vec![u8::MAX; 32]
+        };
+
+        // Keys start with the account id and end with the document id, the
+        // collection follows the account id unless an offset from the end is given
+        for (subspace, collection_offset) in [
+            (SUBSPACE_BITMAP_ID, None),
+            (SUBSPACE_BITMAP_TAG, None),
+            (SUBSPACE_BITMAP_TEXT, Some(U32_LEN + 2)),
+            (SUBSPACE_INDEXES, None),
+            (SUBSPACE_PROPERTY, None),
+            (SUBSPACE_FTS_INDEX, Some(U32_LEN + 1)),
+            (SUBSPACE_COUNTER, None),
+        ] {
+            delete_keys(
+                store,
+                subspace,
+                from_key.clone(),
+                to_key.clone(),
+                |key: &[u8]| {
+                    let collection = match collection_offset {
+                        Some(offset) => key
+                            .len()
+                            .checked_sub(offset)
+                            .and_then(|offset| key.get(offset)),
+                        None => key.get(U32_LEN),
+                    };
+
+                    (subspace != SUBSPACE_COUNTER || is_mailbox_counter(key))
+                        && is_changed(*account_id, collection, key)
+                },
+            )
+            .await?;
+        }
+    }
+
+    // ACLs are keyed by grantee and blob links by hash
+    delete_keys(
+        store,
+        SUBSPACE_ACL,
+        vec![0u8],
+        vec![u8::MAX; 16],
+        |key: &[u8]| {
+            key.deserialize_be_u32(U32_LEN).map_or(false, |account_id| {
+                is_changed(account_id, key.get(U32_LEN * 2), key)
+            })
+        },
+    )
+    .await?;
+    delete_keys(
+        store,
+        SUBSPACE_BLOB_LINK,
+        vec![0u8],
+        vec![u8::MAX; BLOB_HASH_LEN + 16],
+        |key: &[u8]| {
+            key.len() == BLOB_HASH_LEN + U32_LEN * 2 + 1
+                && key[BLOB_HASH_LEN + U32_LEN] != u8::MAX
+                && key
+                    .deserialize_be_u32(BLOB_HASH_LEN)
+                    .map_or(false, |account_id| {
+                        is_changed(account_id, key.get(BLOB_HASH_LEN + U32_LEN), key)
+                    })
+        },
+    )
+    .await
+}
+
+async fn read_changes(path: &Path) -> trc::Result<AHashMap<u32, AHashMap<u8, RoaringBitmap>>> {
+    let mut reader = OpReader::open(path).await?;
+    let mut documents: AHashMap<u32, AHashMap<u8, RoaringBitmap>> = AHashMap::new();
+    let mut account_id = u32::MAX;
+
+    while let Some(op) = reader.try_next().await? {
+        match op {
+            Op::Family(Family::Log) => {}
+            Op::AccountId(id) => account_id = id,
+            Op::KeyValue((key, value)) => {
+                documents.entry(account_id).or_default().insert(
+                    key.as_slice().deserialize_u8(0)?,
+                    deserialize_bitmap(&value)?,
+                );
+            }
+            _ => {
+                return Err(trc::StoreEvent::DataCorruption
+                    .into_err()
+                    .details("Invalid changes file")
+                    .ctx(trc::Key::Path, path.to_string_lossy().into_owned()));
+            }
+        }
+    }
+
+    Ok(documents)
+}
+
+async fn purge_globals(store: &Store) -> trc::Result<()> {
+    for subspace in [
+        SUBSPACE_SETTINGS,
+        SUBSPACE_LOOKUP_VALUE,
+        SUBSPACE_DIRECTORY,
+        SUBSPACE_QUEUE_MESSAGE,
+        SUBSPACE_QUEUE_EVENT,
+        SUBSPACE_QUOTA,
+        SUBSPACE_SNOOZE_EVENT,
+    ] {
+        store
+            .delete_range(
+                AnyKey {
+                    subspace,
+                    key: vec![0u8],
+                },
+                AnyKey {
+                    subspace,
+                    key: vec![u8::MAX; 32],
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+    }
+
+    // Lookup counters share the subspace with mailbox counters
+    delete_keys(
+        store,
+        SUBSPACE_COUNTER,
+        vec![0u8],
+        vec![u8::MAX; 32],
+        |key: &[u8]| !is_mailbox_counter(key),
+    )
+    .await
+}
+
+async fn delete_keys(
+    store: &Store,
+    subspace: u8,
+    from_key: Vec<u8>,
+    to_key: Vec<u8>,
+    filter: impl Fn(&[u8]) -> bool + Sync + Send,
+) -> trc::Result<()> {
+    let mut keys = Vec::new();
+    store
+        .iterate(
+            IterateParams::new(
+                AnyKey {
+                    subspace,
+                    key: from_key,
+                },
+                AnyKey {
+                    subspace,
+                    key: to_key,
+                },
+            )
+            .no_values(),
+            |key, _| {
+                if filter(key) {
+                    keys.push(key.to_vec());
+                }
+
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+    for keys in keys.chunks(1000) {
+        let mut batch = BatchBuilder::new();
+        for key in keys {
+            batch.clear(ValueClass::Any(AnyClass {
+                subspace,
+                key: key.clone(),
+            }));
         }
+        store
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())?;
     }
+
+    Ok(())
+}
+
+fn is_mailbox_counter(key: &[u8]) -> bool {
+    key.len() == (U32_LEN * 2) + 2
+        && key[U32_LEN] == u8::from(Collection::Mailbox)
+        && key[U32_LEN + 1] == u8::from(Property::EmailIds)
 }
 
-async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
-    println!("Importing database dump from {}.", path.to_str().unwrap());
+async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) -> trc::Result<()> {
+    println!("Importing database dump from {}.", path.display());
 
-    let mut reader = OpReader::new(path).await;
+    import_file(&store, &blob_store, path)
+        .await
+        .map_err(|err| err.ctx(trc::Key::Path, path.to_string_lossy().into_owned()))
+}
+
+/// Imports a backup file into a store, blob contents are written to `blob_store`.
+pub(super) async fn import_file(
+    store: &Store,
+    blob_store: &BlobStore,
+    path: &Path,
+) -> trc::Result<()> {
+    let mut reader = OpReader::open(path).await?;
     let mut account_id = u32::MAX;
     let mut document_id = u32::MAX;
     let mut collection = u8::MAX;
@@ -73,7 +564,7 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
     let mut batch_size = 0;
     let mut batch = BatchBuilder::new();
 
-    while let Some(op) = reader.next().await {
+    while let Some(op) = reader.try_next().await? {
         match op {
             Op::Family(f) => family = f,
             Op::AccountId(a) => {
@@ -93,54 +584,31 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
 
                 match family {
                     Family::Property => {
-                        let field = key
-                            .as_slice()
-                            .deserialize_u8(0)
-                            .expect("Failed to deserialize field");
+                        let field = key.as_slice().deserialize_u8(0)?;
                         if collection == u8::from(Collection::Mailbox)
                             && u8::from(Property::EmailIds) == field
                         {
-                            batch.add(
-                                ValueClass::Property(field),
-                                i64::deserialize(&value)
-                                    .expect("Failed to deserialize mailbox uidnext"),
-                            );
+                            batch.add(ValueClass::Property(field), i64::deserialize(&value)?);
                         } else {
                             batch.set(ValueClass::Property(field), value);
                         }
                     }
                     Family::FtsIndex => {
-                        if reader.version > 1 {
-                            let mut hash = [0u8; 8];
-                            let (hash, len) = match key.len() {
-                                9 => {
-                                    hash[..8].copy_from_slice(&key[..8]);
-                                    (hash, key[key.len() - 1])
-                                }
-                                len @ (1..=7) => {
-                                    hash[..len].copy_from_slice(&key[..len]);
-                                    (hash, len as u8)
-                                }
-                                invalid => {
-                                    panic!("Invalid text bitmap key length {invalid}");
-                                }
-                            };
-
-                            batch.set(ValueClass::FtsIndex(BitmapHash { hash, len }), value);
+                        if document_id == u32::MAX {
+                            batch.add(ValueClass::FtsTerm(key), i64::deserialize(&value)?);
+                        } else if reader.version > 1 {
+                            batch.set(ValueClass::FtsIndex(deserialize_bitmap_hash(&key)?), value);
                         }
                     }
                     Family::Acl => {
                         batch.set(
-                            ValueClass::Acl(
-                                key.as_slice()
-                                    .deserialize_be_u32(0)
-                                    .expect("Failed to deserialize acl"),
-                            ),
+                            ValueClass::Acl(key.as_slice().deserialize_be_u32(0)?),
                             value,
                         );
                     }
                     Family::Blob => {
-                        let hash = BlobHash::try_from_hash_slice(&key).expect("Invalid blob hash");
+                        let hash = BlobHash::try_from_hash_slice(&key)
+                            .map_err(|_| trc::Error::corrupted_key(&key, None, trc::location!()))?;
 
                         if account_id != u32::MAX && document_id != u32::MAX {
                             if reader.version == 1 && collection == email_collection {
@@ -155,11 +623,14 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
                             }
                             batch.set(ValueClass::Blob(BlobOp::Link { hash }), vec![]);
                         } else {
-                            batch_size -= value.len();
-                            blob_store
-                                .put_blob(&key, &value)
-                                .await
-                                .expect("Failed to write blob");
+                            // Blobs without contents were stored earlier in the backup chain
+                            if !value.is_empty() || hash == BlobHash::from(&[][..]) {
+                                batch_size -= value.len();
+                                blob_store
+                                    .put_blob(&key, &value)
+                                    .await
+                                    .caused_by(trc::location!())?;
+                            }
                             batch.set(ValueClass::Blob(BlobOp::Commit { hash }), vec![]);
                         }
                     }
@@ -172,82 +643,53 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
                     Family::LookupCounter => {
                         batch.add(
                             ValueClass::Lookup(LookupClass::Counter(key)),
-                            i64::deserialize(&value).expect("Failed to deserialize counter"),
+                            i64::deserialize(&value)?,
                         );
                     }
                     Family::Directory => {
                         let key = key.as_slice();
-                        let class: DirectoryClass<MaybeDynamicId> =
-                            match key.first().expect("Failed to read directory key type") {
-                                0 => DirectoryClass::NameToId(
-                                    key.get(1..)
-                                        .expect("Failed to read directory string")
-                                        .to_vec(),
+                        let class: DirectoryClass<MaybeDynamicId> = match key.deserialize_u8(0)? {
+                            0 => DirectoryClass::NameToId(key.range(1..usize::MAX)?.to_vec()),
+                            1 => DirectoryClass::EmailToId(key.range(1..usize::MAX)?.to_vec()),
+                            2 => DirectoryClass::Principal(MaybeDynamicId::Static(
+                                deserialize_principal_id(key)?,
+                            )),
+                            4 => {
+                                batch.add(
+                                    ValueClass::Directory(DirectoryClass::UsedQuota(
+                                        deserialize_principal_id(key)?,
+                                    )),
+                                    i64::deserialize(&value)?,
+                                );
+
+                                continue;
+                            }
+                            5 => DirectoryClass::MemberOf {
+                                principal_id: MaybeDynamicId::Static(key.deserialize_be_u32(1)?),
+                                member_of: MaybeDynamicId::Static(
+                                    key.deserialize_be_u32(1 + U32_LEN)?,
                                 ),
-                                1 => DirectoryClass::EmailToId(
-                                    key.get(1..)
-                                        .expect("Failed to read directory string")
-                                        .to_vec(),
+                            },
+                            6 => DirectoryClass::Members {
+                                principal_id: MaybeDynamicId::Static(key.deserialize_be_u32(1)?),
+                                has_member: MaybeDynamicId::Static(
+                                    key.deserialize_be_u32(1 + U32_LEN)?,
                                 ),
-                                2 => DirectoryClass::Principal(MaybeDynamicId::Static(
-                                    key.get(1..)
-                                        .expect("Failed to read range for principal id")
-                                        .deserialize_leb128::<u32>()
-                                        .expect("Failed to deserialize principal id"),
-                                )),
-                                /*3 => DirectoryClass::Domain(
-                                    key.get(1..)
-                                        .expect("Failed to read directory string")
-                                        .to_vec(),
-                                ),*/
-                                4 => {
-                                    batch.add(
-                                        ValueClass::Directory(DirectoryClass::UsedQuota(
-                                            key.get(1..)
-                                                .expect("Failed to read principal id")
-                                                .deserialize_leb128()
-                                                .expect("Failed to read principal id"),
-                                        )),
-                                        i64::deserialize(&value)
-                                            .expect("Failed to deserialize quota"),
-                                    );
-
-                                    continue;
-                                }
-                                5 => DirectoryClass::MemberOf {
-                                    principal_id: MaybeDynamicId::Static(
-                                        key.deserialize_be_u32(1)
-                                            .expect("Failed to read principal id"),
-                                    ),
-                                    member_of: MaybeDynamicId::Static(
-                                        key.deserialize_be_u32(1 + U32_LEN)
-                                            .expect("Failed to read principal id"),
-                                    ),
-                                },
-                                6 => DirectoryClass::Members {
-                                    principal_id: MaybeDynamicId::Static(
-                                        key.deserialize_be_u32(1)
-                                            .expect("Failed to read principal id"),
-                                    ),
-                                    has_member: MaybeDynamicId::Static(
-                                        key.deserialize_be_u32(1 + U32_LEN)
-                                            .expect("Failed to read principal id"),
-                                    ),
-                                },
-
-                                _ => failed("Invalid directory key"),
-                            };
+                            },
+                            _ => {
+                                return Err(trc::Error::corrupted_key(key, None, trc::location!()))
+                            }
+                        };
                         batch.set(ValueClass::Directory(class), value);
                     }
                     Family::Queue => {
                         let key = key.as_slice();
 
-                        match key.first().expect("Failed to read queue key type") {
+                        match key.deserialize_u8(0)? {
                             0 => {
                                 batch.set(
                                     ValueClass::Queue(QueueClass::Message(
-                                        key.deserialize_be_u64(1)
-                                            .expect("Failed to deserialize queue message id"),
+                                        key.deserialize_be_u64(1)?,
                                     )),
                                     value,
                                 );
@@ -255,76 +697,41 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
                             1 => {
                                 batch.set(
                                     ValueClass::Queue(QueueClass::MessageEvent(QueueEvent {
-                                        due: key
-                                            .deserialize_be_u64(1)
-                                            .expect("Failed to deserialize queue message id"),
-                                        queue_id: key
-                                            .deserialize_be_u64(1 + U64_LEN)
-                                            .expect("Failed to deserialize queue message id"),
+                                        due: key.deserialize_be_u64(1)?,
+                                        queue_id: key.deserialize_be_u64(1 + U64_LEN)?,
                                     })),
                                     value,
                                 );
                             }
-                            _ => failed("Invalid queue key"),
+                            2 => {
+                                batch
+                                    .with_account_id(key.deserialize_be_u32(1 + U64_LEN)?)
+                                    .update_document(key.deserialize_be_u32(1 + U64_LEN + U32_LEN)?)
+                                    .set(
+                                        ValueClass::SnoozeEvent(key.deserialize_be_u64(1)?),
+                                        value,
+                                    );
+                            }
+                            _ => {
+                                return Err(trc::Error::corrupted_key(key, None, trc::location!()))
+                            }
                         }
                     }
                     Family::Index => batch.ops.push(Operation::Index {
-                        field: key.first().copied().expect("Failed to read index field"),
-                        key: key.get(1..).expect("Failed to read index key").to_vec(),
+                        field: key.as_slice().deserialize_u8(0)?,
+                        key: key.get(1..).unwrap_or_default().to_vec(),
                         set: true,
                     }),
                     Family::Bitmap => {
-                        let key = key.as_slice();
-                        let class: BitmapClass<MaybeDynamicId> =
-                            match key.first().expect("Failed to read bitmap class") {
-                                0 => BitmapClass::DocumentIds,
-                                1 => BitmapClass::Tag {
-                                    field: key.get(1).copied().expect("Failed to read field"),
-                                    value: TagValue::Id(MaybeDynamicId::Static(
-                                        key.deserialize_be_u32(2).expect("Failed to read tag id"),
-                                    )),
-                                },
-                                2 => BitmapClass::Tag {
-                                    field: key.get(1).copied().expect("Failed to read field"),
-                                    value: TagValue::Text(
-                                        key.get(2..).expect("Failed to read tag text").to_vec(),
-                                    ),
-                                },
-                                3 => BitmapClass::Tag {
-                                    field: key.get(1).copied().expect("Failed to read field"),
-                                    value: TagValue::Id(MaybeDynamicId::Static(
-                                        key.get(2)
-                                            .copied()
-                                            .expect("Failed to read tag static id")
-                                            .into(),
-                                    )),
-                                },
-                                4 => {
-                                    if reader.version == 1 && collection == email_collection {
-                                        continue;
-                                    }
-
-                                    BitmapClass::Text {
-                                        field: key.get(1).copied().expect("Failed to read field"),
-                                        token: BitmapHash {
-                                            len: key
-                                                .get(2)
-                                                .copied()
-                                                .expect("Failed to read tag static id"),
-                                            hash: key
-                                                .get(3..11)
-                                                .expect("Failed to read tag static id")
-                                                .try_into()
-                                                .unwrap(),
-                                        },
-                                    }
-                                }
-                                _ => failed("Invalid bitmap class"),
-                            };
-                        let document_ids = RoaringBitmap::deserialize_from(&value[..])
-                            .expect("Failed to deserialize bitmap");
+                        if reader.version == 1
+                            && collection == email_collection
+                            && key.first() == Some(&4)
+                        {
+                            continue;
+                        }
 
-                        for document_id in document_ids {
+                        let class = deserialize_bitmap_class(&key)?;
+                        for document_id in deserialize_bitmap(&value)? {
                             batch.ops.push(Operation::DocumentId { document_id });
                             batch.ops.push(Operation::Bitmap {
                                 class: class.clone(),
@@ -335,7 +742,7 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
                                 store
                                     .write(batch.build())
                                     .await
-                                    .failed("Failed to write batch");
+                                    .caused_by(trc::location!())?;
                                 batch = BatchBuilder::new();
                                 batch
                                     .with_account_id(account_id)
@@ -345,16 +752,18 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
                     }
                     Family::Log => {
                         batch.ops.push(Operation::ChangeId {
-                            change_id: key
-                                .as_slice()
-                                .deserialize_be_u64(0)
-                                .expect("Failed to deserialize change id"),
+                            change_id: key.as_slice().deserialize_be_u64(0)?,
                         });
                         batch.ops.push(Operation::Log {
                             set: MaybeDynamicValue::Static(value),
                         });
                     }
-                    Family::None => failed("No family specified in file"),
+                    Family::None => {
+                        return Err(trc::StoreEvent::DataCorruption
+                            .into_err()
+                            .details("No family specified in file")
+                            .ctx(trc::Key::Path, path.to_string_lossy().into_owned()));
+                    }
                 }
             }
         }
@@ -363,7 +772,7 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
             store
                 .write(batch.build())
                 .await
-                .failed("Failed to write batch");
+                .caused_by(trc::location!())?;
             batch = BatchBuilder::new();
             batch
                 .with_account_id(account_id)
@@ -377,83 +786,97 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
         store
             .write(batch.build())
             .await
-            .failed("Failed to write batch");
+            .caused_by(trc::location!())?;
     }
+
+    Ok(())
+}
+
+fn deserialize_principal_id(key: &[u8]) -> trc::Result<u32> {
+    key.range(1..usize::MAX)?.deserialize_leb128::<u32>()
 }
 
-struct OpReader {
+pub(super) struct OpReader {
     version: u8,
     file: BufReader<File>,
 }
 
 impl OpReader {
-    async fn new(path: &Path) -> Self {
-        let mut file = BufReader::new(File::open(&path).await.failed("Failed to open file"));
+    pub(super) async fn open(path: &Path) -> trc::Result<Self> {
+        let mut file = BufReader::new(File::open(&path).await.map_err(|err| io_error(path, err))?);
 
-        if file
-            .read_u8()
-            .await
-            .failed(&format!("Failed to read magic marker from {path:?}"))
-            != MAGIC_MARKER
-        {
-            failed(&format!("Invalid magic marker in {path:?}"));
+        if file.read_u8().await.map_err(|err| io_error(path, err))? != MAGIC_MARKER {
+            return Err(trc::StoreEvent::DataCorruption
+                .into_err()
+                .details("Invalid magic marker")
+                .ctx(trc::Key::Path, path.to_string_lossy().into_owned()));
         }
 
-        let version = file
-            .read_u8()
-            .await
-            .failed(&format!("Failed to read version from {path:?}"));
+        let version = file.read_u8().await.map_err(|err| io_error(path, err))?;
 
         if version > FILE_VERSION {
-            failed(&format!("Invalid file version in {path:?}"));
+            return Err(trc::StoreEvent::DataCorruption
+                .into_err()
+                .details("Invalid file version")
+                .ctx(trc::Key::Path, path.to_string_lossy().into_owned()));
         }
 
-        Self { file, version }
+        Ok(Self { file, version })
     }
 
-    async fn next(&mut self) -> Option<Op> {
+    pub(super) async fn try_next(&mut self) -> trc::Result<Option<Op>> {
         match self.file.read_u8().await {
-            Ok(byte) => match byte {
-                0 => Op::Family(
-                    Family::try_from(self.expect_u8().await).failed("Failed to read family"),
-                ),
+            Ok(byte) => Ok(Some(match byte {
+                0 => Op::Family(Family::try_from(self.expect_u8().await?).map_err(|err| {
+                    trc::StoreEvent::DataCorruption
+                        .into_err()
+                        .details(err)
+                        .caused_by(trc::location!())
+                })?),
                 1 => Op::KeyValue((
-                    self.expect_sized_bytes().await,
-                    self.expect_sized_bytes().await,
+                    self.expect_sized_bytes().await?,
+                    self.expect_sized_bytes().await?,
                 )),
-                2 => Op::KeyValue((self.expect_sized_bytes().await, vec![])),
-                3 => Op::AccountId(self.expect_u32_be().await),
-                4 => Op::Collection(self.expect_u8().await),
-                5 => Op::DocumentId(self.expect_u32_be().await),
+                2 => Op::KeyValue((self.expect_sized_bytes().await?, vec![])),
+                3 => Op::AccountId(self.expect_u32_be().await?),
+                4 => Op::Collection(self.expect_u8().await?),
+                5 => Op::DocumentId(self.expect_u32_be().await?),
                 unknown => {
-                    failed(&format!("Unknown op type {unknown}"));
+                    return Err(trc::StoreEvent::DataCorruption
+                        .into_err()
+                        .details(format!("Unknown op type {unknown}"))
+                        .caused_by(trc::location!()));
                 }
-            }
-            .into(),
-            Err(err) if err.kind() == ErrorKind::UnexpectedEof => None,
-            Err(err) => failed(&format!("Failed to read file: {err:?}")),
+            })),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(trc::EventType::Store(trc::StoreEvent::FilesystemError)
+                .from_io_error(err)
+                .caused_by(trc::location!())),
         }
     }
 
-    async fn expect_u8(&mut self) -> u8 {
-        self.file.read_u8().await.failed("Failed to read u8")
+    async fn expect_u8(&mut self) -> trc::Result<u8> {
+        self.file.read_u8().await.map_err(read_error)
     }
 
-    async fn expect_u32_be(&mut self) -> u32 {
-        self.file.read_u32().await.failed("Failed to read u32")
+    async fn expect_u32_be(&mut self) -> trc::Result<u32> {
+        self.file.read_u32().await.map_err(read_error)
     }
 
-    async fn expect_sized_bytes(&mut self) -> Vec<u8> {
-        let len = self.expect_u32_be().await as usize;
+    async fn expect_sized_bytes(&mut self) -> trc::Result<Vec<u8>> {
+        let len = self.expect_u32_be().await? as usize;
         let mut bytes = vec![0; len];
-        self.file
-            .read_exact(&mut bytes)
-            .await
-            .failed("Failed to read bytes");
-        bytes
+        self.file.read_exact(&mut bytes).await.map_err(read_error)?;
+        Ok(bytes)
     }
 }
 
+fn read_error(err: std::io::Error) -> trc::Error {
+    trc::EventType::Store(trc::StoreEvent::FilesystemError)
+        .from_io_error(err)
+        .caused_by(trc::location!())
+}
+
 impl TryFrom<u8> for Family {
     type Error = String;
 
@@ -475,3 +898,598 @@ impl TryFrom<u8> for Family {
         }
     }
 }
+
+impl Core {
+    /// Restores an account backup stored under the configured backup path.
+    pub async fn restore_account(
+        &self,
+        id: &str,
+        account_id: u32,
+    ) -> trc::Result<ChangeLogBuilder> {
+        let settings = self.storage.backup.as_ref().ok_or_else(|| {
+            trc::StoreEvent::NotConfigured
+                .into_err()
+                .details("Backup path is not configured")
+        })?;
+        let path = settings.path.join(ACCOUNTS_DIR).join(id);
+
+        if !id.is_empty()
+            && !id.starts_with('.')
+            && !id.contains(['/', '\\'])
+            && path.join(MANIFEST_FILE).is_file()
+        {
+            self.import_account(&path, account_id).await
+        } else {
+            Err(trc::ManageEvent::NotFound
+                .into_err()
+                .details("Account backup not found")
+                .ctx(trc::Key::Id, id.to_string()))
+        }
+    }
+
+    /// Imports an account backup into `account_id`. Documents keep their ids when
+    /// the target collection is empty, otherwise new ids are assigned and the
+    /// references between documents are updated. The returned changes log the
+    /// restored documents as insertions and still need to be committed.
+    pub async fn import_account(
+        &self,
+        src: &Path,
+        account_id: u32,
+    ) -> trc::Result<ChangeLogBuilder> {
+        let manifest = BackupManifest::read(src)?;
+        if manifest.kind != BackupKind::Account || manifest.accounts.len() != 1 {
+            return Err(trc::StoreEvent::DataCorruption
+                .into_err()
+                .details("Not an account backup")
+                .ctx(trc::Key::Path, src.to_string_lossy().into_owned()));
+        }
+        manifest.verify(src)?;
+
+        let store = &self.storage.data;
+        let mut remap = AccountRemap {
+            source_id: manifest.accounts[0],
+            account_id,
+            ..Default::default()
+        };
+        for (collection, document_ids) in read_document_ids(&src.join("bitmap")).await? {
+            remap
+                .assign_ids(store, collection, document_ids)
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        for file in &manifest.files {
+            if file.name != BLOB_INDEX_FILE {
+                import_account_file(store, &self.storage.blob, &src.join(&file.name), &mut remap)
+                    .await?;
+            }
+        }
+
+        if let Some(quota) = manifest.quota.filter(|quota| *quota != 0) {
+            let mut batch = BatchBuilder::new();
+            batch.add(DirectoryClass::UsedQuota(account_id), quota);
+            store
+                .write(batch.build())
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(remap.changes())
+    }
+}
+
+/// Maps the document ids of an account backup to the ids used in the target
+/// account, rewriting the references between documents.
+#[derive(Default)]
+struct AccountRemap {
+    source_id: u32,
+    account_id: u32,
+    documents: AHashMap<u8, AHashMap<u32, u32>>,
+    reassigned: AHashSet<u8>,
+    threads: AHashMap<u32, u32>,
+}
+
+impl AccountRemap {
+    async fn assign_ids(
+        &mut self,
+        store: &Store,
+        collection: u8,
+        document_ids: RoaringBitmap,
+    ) -> trc::Result<()> {
+        let reassign = store
+            .get_bitmap(BitmapKey::document_ids(self.account_id, collection))
+            .await?
+            .map_or(false, |document_ids| !document_ids.is_empty());
+        let document_ids = document_ids.into_iter().collect::<Vec<_>>();
+        let mut assigned_ids = AHashMap::with_capacity(document_ids.len());
+
+        for document_ids in document_ids.chunks(1000) {
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(self.account_id)
+                .with_collection(collection);
+            for document_id in document_ids {
+                if reassign {
+                    batch.create_document();
+                } else {
+                    batch.create_document_with_id(*document_id);
+                }
+            }
+            let ids = store.write(batch.build()).await?;
+
+            if reassign {
+                assigned_ids.extend(document_ids.iter().copied().zip(ids.document_ids));
+            } else {
+                assigned_ids.extend(document_ids.iter().map(|id| (*id, *id)));
+            }
+        }
+
+        if reassign {
+            self.reassigned.insert(collection);
+        }
+        self.documents.insert(collection, assigned_ids);
+
+        Ok(())
+    }
+
+    fn document_id(&self, collection: u8, document_id: u32) -> Option<u32> {
+        if document_id != u32::MAX {
+            self.documents
+                .get(&collection)
+                .and_then(|ids| ids.get(&document_id))
+                .copied()
+        } else {
+            Some(document_id)
+        }
+    }
+
+    fn reference(&self, collection: Collection, document_id: u32) -> u32 {
+        self.document_id(collection.into(), document_id)
+            .unwrap_or(document_id)
+    }
+
+    fn is_reassigned(&self, collection: Collection) -> bool {
+        self.reassigned.contains(&u8::from(collection))
+    }
+
+    fn property(
+        &mut self,
+        collection: u8,
+        document_id: u32,
+        field: u8,
+        value: Vec<u8>,
+    ) -> trc::Result<Vec<u8>> {
+        match (Collection::from(collection), to_property(field)) {
+            (Collection::Email, Some(Property::MailboxIds)) => {
+                let mut bytes = value.iter();
+                let mut mailbox_ids = Vec::with_capacity(value.len());
+                let len: usize = bytes
+                    .next_leb128()
+                    .ok_or_else(|| trc::StoreEvent::DataCorruption.caused_by(trc::location!()))?;
+                mailbox_ids.push_leb128(len);
+                for _ in 0..len {
+                    let (mailbox_id, uid) = bytes
+                        .next_leb128::<u32>()
+                        .and_then(|mailbox_id| Some((mailbox_id, bytes.next_leb128::<u32>()?)))
+                        .ok_or_else(|| {
+                            trc::StoreEvent::DataCorruption.caused_by(trc::location!())
+                        })?;
+                    mailbox_ids.push_leb128(self.reference(Collection::Mailbox, mailbox_id));
+                    mailbox_ids.push_leb128(uid);
+                }
+                Ok(mailbox_ids)
+            }
+            (Collection::Email, Some(Property::ThreadId)) => {
+                let thread_id = self.reference(Collection::Thread, u32::deserialize(&value)?);
+                self.threads.insert(document_id, thread_id);
+                Ok(thread_id.serialize())
+            }
+            (
+                collection @ (Collection::Mailbox
+                | Collection::EmailSubmission
+                | Collection::SieveScript),
+                Some(Property::Value),
+            ) => {
+                let mut object = Object::<Value>::deserialize(&value)?;
+                match collection {
+                    Collection::Mailbox => {
+                        if let Value::Id(parent_id) = object.get(&Property::ParentId) {
+                            if parent_id.id() > 0 {
+                                let parent_id = self
+                                    .reference(Collection::Mailbox, parent_id.document_id() - 1);
+                                object.set(Property::ParentId, Value::Id(Id::from(parent_id + 1)));
+                            }
+                        }
+                        if let Value::List(subscribers) = object.get(&Property::IsSubscribed) {
+                            let subscribers = subscribers
+                                .iter()
+                                .map(|subscriber| match subscriber {
+                                    Value::Id(id) if id.document_id() == self.source_id => {
+                                        Value::Id(Id::from(self.account_id))
+                                    }
+                                    subscriber => subscriber.clone(),
+                                })
+                                .collect::<Vec<_>>();
+                            object.set(Property::IsSubscribed, Value::List(subscribers));
+                        }
+                        // Roles are unique within an account
+                        if self.is_reassigned(Collection::Mailbox) {
+                            object.remove(&Property::Role);
+                        }
+                    }
+                    Collection::EmailSubmission => {
+                        if let Value::Id(email_id) = object.get(&Property::EmailId) {
+                            let email_id = Id::from_parts(
+                                self.reference(Collection::Thread, email_id.prefix_id()),
+                                self.reference(Collection::Email, email_id.document_id()),
+                            );
+                            object.set(Property::EmailId, Value::Id(email_id));
+                        }
+                        for (property, collection) in [
+                            (Property::ThreadId, Collection::Thread),
+                            (Property::IdentityId, Collection::Identity),
+                        ] {
+                            if let Value::Id(id) = object.get(&property) {
+                                let id = self.reference(collection, id.document_id());
+                                object.set(property, Value::Id(Id::from(id)));
+                            }
+                        }
+                    }
+                    _ => {
+                        // Only one Sieve script can be active
+                        if self.is_reassigned(Collection::SieveScript) {
+                            object.set(Property::IsActive, Value::Bool(false));
+                        }
+                    }
+                }
+                Ok(object.serialize())
+            }
+            _ => Ok(value),
+        }
+    }
+
+    fn index(&self, collection: u8, field: u8, key: &[u8]) -> trc::Result<Option<Vec<u8>>> {
+        match (Collection::from(collection), to_property(field)) {
+            (Collection::Mailbox, Some(Property::ParentId)) => {
+                let parent_id = key.deserialize_be_u32(0)?;
+                Ok(Some(if parent_id > 0 {
+                    (self.reference(Collection::Mailbox, parent_id - 1) + 1).serialize()
+                } else {
+                    key.to_vec()
+                }))
+            }
+            (Collection::Mailbox, Some(Property::IsSubscribed)) => {
+                Ok(Some(if key.deserialize_be_u32(0)? == self.source_id {
+                    self.account_id.serialize()
+                } else {
+                    key.to_vec()
+                }))
+            }
+            (Collection::Mailbox, Some(Property::Role))
+                if self.is_reassigned(Collection::Mailbox) =>
+            {
+                Ok(None)
+            }
+            (Collection::EmailSubmission, Some(Property::EmailId)) => {
+                let email_id = Id::from(key.deserialize_be_u64(0)?);
+                Ok(Some(
+                    Id::from_parts(
+                        self.reference(Collection::Thread, email_id.prefix_id()),
+                        self.reference(Collection::Email, email_id.document_id()),
+                    )
+                    .id()
+                    .serialize(),
+                ))
+            }
+            (Collection::EmailSubmission, Some(Property::ThreadId)) => Ok(Some(
+                self.reference(Collection::Thread, key.deserialize_be_u32(0)?)
+                    .serialize(),
+            )),
+            (Collection::EmailSubmission, Some(Property::IdentityId)) => Ok(Some(
+                self.reference(Collection::Identity, key.deserialize_be_u32(0)?)
+                    .serialize(),
+            )),
+            (Collection::SieveScript, Some(Property::IsActive))
+                if self.is_reassigned(Collection::SieveScript) =>
+            {
+                Ok(Some(0u32.serialize()))
+            }
+            _ => Ok(Some(key.to_vec())),
+        }
+    }
+
+    fn bitmap(
+        &self,
+        collection: u8,
+        class: BitmapClass<MaybeDynamicId>,
+    ) -> Option<BitmapClass<MaybeDynamicId>> {
+        match class {
+            // Documents were created when their ids were assigned
+            BitmapClass::DocumentIds => None,
+            BitmapClass::Tag {
+                field,
+                value: TagValue::Id(MaybeDynamicId::Static(id)),
+            } if collection == u8::from(Collection::Email) => {
+                let id = match to_property(field) {
+                    Some(Property::MailboxIds) => self.reference(Collection::Mailbox, id),
+                    Some(Property::ThreadId) => self.reference(Collection::Thread, id),
+                    _ => id,
+                };
+
+                Some(BitmapClass::Tag {
+                    field,
+                    value: TagValue::Id(MaybeDynamicId::Static(id)),
+                })
+            }
+            BitmapClass::Tag { field, .. }
+                if collection == u8::from(Collection::Mailbox)
+                    && field == u8::from(Property::Role)
+                    && self.is_reassigned(Collection::Mailbox) =>
+            {
+                None
+            }
+            class => Some(class),
+        }
+    }
+
+    fn changes(&self) -> ChangeLogBuilder {
+        let mut changes = ChangeLogBuilder::new();
+
+        for (collection, document_ids) in &self.documents {
+            let collection = Collection::from(*collection);
+            for document_id in document_ids.values().copied() {
+                match collection {
+                    Collection::Email => {
+                        let thread_id = self.threads.get(&document_id).copied().unwrap_or_default();
+                        changes.log_insert(collection, Id::from_parts(thread_id, document_id));
+                    }
+                    Collection::Mailbox
+                    | Collection::Thread
+                    | Collection::Identity
+                    | Collection::EmailSubmission
+                    | Collection::SieveScript
+                    | Collection::ShareNotification => {
+                        changes.log_insert(collection, document_id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+fn to_property(field: u8) -> Option<Property> {
+    Property::deserialize_from(&mut [field].iter())
+}
+
+async fn read_document_ids(path: &Path) -> trc::Result<AHashMap<u8, RoaringBitmap>> {
+    let mut reader = OpReader::open(path).await?;
+    let mut collection = u8::MAX;
+    let mut document_ids = AHashMap::new();
+
+    while let Some(op) = reader.try_next().await? {
+        match op {
+            Op::Collection(c) => collection = c,
+            Op::KeyValue((key, value)) if key.first() == Some(&0) => {
+                document_ids.insert(collection, deserialize_bitmap(&value)?);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(document_ids)
+}
+
+async fn import_account_file(
+    store: &Store,
+    blob_store: &BlobStore,
+    path: &Path,
+    remap: &mut AccountRemap,
+) -> trc::Result<()> {
+    let mut reader = OpReader::open(path).await?;
+    let mut account_id = remap.account_id;
+    let mut collection = u8::MAX;
+    let mut document_id = Some(u32::MAX);
+    let mut family = Family::None;
+
+    let mut batch_size = 0;
+    let mut batch = BatchBuilder::new();
+
+    while let Some(op) = reader.try_next().await? {
+        match op {
+            Op::Family(f) => family = f,
+            Op::AccountId(a) => {
+                // Committed blobs are not linked to an account
+                account_id = if a != u32::MAX { remap.account_id } else { a };
+                batch.with_account_id(account_id);
+            }
+            Op::Collection(c) => {
+                collection = c;
+                batch.with_collection(collection);
+            }
+            Op::DocumentId(d) => {
+                document_id = remap.document_id(collection, d);
+                if let Some(document_id) = document_id {
+                    batch.update_document(document_id);
+                }
+            }
+            Op::KeyValue((key, value)) => {
+                batch_size += key.len() + value.len() + U32_LEN * 2;
+
+                match (family, document_id) {
+                    (Family::Property, Some(document_id)) => {
+                        let field = key.as_slice().deserialize_u8(0)?;
+                        if collection == u8::from(Collection::Mailbox)
+                            && u8::from(Property::EmailIds) == field
+                        {
+                            batch.add(ValueClass::Property(field), i64::deserialize(&value)?);
+                        } else {
+                            batch.set(
+                                ValueClass::Property(field),
+                                remap.property(collection, document_id, field, value)?,
+                            );
+                        }
+                    }
+                    (Family::FtsIndex, Some(u32::MAX)) => {
+                        batch.add(ValueClass::FtsTerm(key), i64::deserialize(&value)?);
+                    }
+                    (Family::FtsIndex, Some(_)) => {
+                        batch.set(ValueClass::FtsIndex(deserialize_bitmap_hash(&key)?), value);
+                    }
+                    (Family::Acl, Some(_)) => {
+                        batch.set(
+                            ValueClass::Acl(key.as_slice().deserialize_be_u32(0)?),
+                            value,
+                        );
+                    }
+                    (Family::Blob, Some(document_id)) => {
+                        let hash = BlobHash::try_from_hash_slice(&key)
+                            .map_err(|_| trc::Error::corrupted_key(&key, None, trc::location!()))?;
+
+                        if account_id != u32::MAX && document_id != u32::MAX {
+                            batch.set(ValueClass::Blob(BlobOp::Link { hash }), vec![]);
+                        } else {
+                            if !value.is_empty() || hash == BlobHash::from(&[][..]) {
+                                batch_size -= value.len();
+                                blob_store
+                                    .put_blob(&key, &value)
+                                    .await
+                                    .caused_by(trc::location!())?;
+                            }
+                            batch.set(ValueClass::Blob(BlobOp::Commit { hash }), vec![]);
+                        }
+                    }
+                    (Family::Index, Some(_)) => {
+                        let field = key.as_slice().deserialize_u8(0)?;
+                        if let Some(key) =
+                            remap.index(collection, field, key.get(1..).unwrap_or_default())?
+                        {
+                            batch.ops.push(Operation::Index {
+                                field,
+                                key,
+                                set: true,
+                            });
+                        }
+                    }
+                    (Family::Bitmap, _) => {
+                        if let Some(class) =
+                            remap.bitmap(collection, deserialize_bitmap_class(&key)?)
+                        {
+                            for document_id in deserialize_bitmap(&value)? {
+                                if let Some(document_id) =
+                                    remap.document_id(collection, document_id)
+                                {
+                                    batch.ops.push(Operation::DocumentId { document_id });
+                                    batch.ops.push(Operation::Bitmap {
+                                        class: class.clone(),
+                                        set: true,
+                                    });
+                                }
+
+                                if batch.ops.len() >= 1000 {
+                                    store
+                                        .write(batch.build())
+                                        .await
+                                        .caused_by(trc::location!())?;
+                                    batch = BatchBuilder::new();
+                                    batch
+                                        .with_account_id(account_id)
+                                        .with_collection(collection);
+                                }
+                            }
+                        }
+                    }
+                    (Family::Property | Family::FtsIndex | Family::Acl | Family::Index, None) => {
+                        // Data of documents that no longer exist
+                    }
+                    _ => {
+                        return Err(trc::StoreEvent::DataCorruption
+                            .into_err()
+                            .details("Unexpected data in account backup")
+                            .ctx(trc::Key::Path, path.to_string_lossy().into_owned()));
+                    }
+                }
+            }
+        }
+
+        if batch.ops.len() >= 1000 || batch_size >= 5_000_000 {
+            store
+                .write(batch.build())
+                .await
+                .caused_by(trc::location!())?;
+            batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(collection);
+            if let Some(document_id) = document_id {
+                batch.update_document(document_id);
+            }
+            batch_size = 0;
+        }
+    }
+
+    if !batch.is_empty() {
+        store
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())?;
+    }
+
+    Ok(())
+}
+
+fn deserialize_bitmap(bytes: &[u8]) -> trc::Result<RoaringBitmap> {
+    RoaringBitmap::deserialize_from(bytes).map_err(|err| {
+        trc::StoreEvent::DataCorruption
+            .reason(err)
+            .caused_by(trc::location!())
+    })
+}
+
+fn deserialize_bitmap_hash(key: &[u8]) -> trc::Result<BitmapHash> {
+    let mut hash = [0u8; 8];
+    let len = match key.len() {
+        9 => {
+            hash.copy_from_slice(&key[..8]);
+            key[8]
+        }
+        len @ (1..=7) => {
+            hash[..len].copy_from_slice(key);
+            len as u8
+        }
+        _ => return Err(trc::Error::corrupted_key(key, None, trc::location!())),
+    };
+
+    Ok(BitmapHash { hash, len })
+}
+
+fn deserialize_bitmap_class(key: &[u8]) -> trc::Result<BitmapClass<MaybeDynamicId>> {
+    match key.deserialize_u8(0)? {
+        0 => Ok(BitmapClass::DocumentIds),
+        1 => Ok(BitmapClass::Tag {
+            field: key.deserialize_u8(1)?,
+            value: TagValue::Id(MaybeDynamicId::Static(key.deserialize_be_u32(2)?)),
+        }),
+        2 => Ok(BitmapClass::Tag {
+            field: key.deserialize_u8(1)?,
+            value: TagValue::Text(key.range(2..usize::MAX)?.to_vec()),
+        }),
+        3 => Ok(BitmapClass::Tag {
+            field: key.deserialize_u8(1)?,
+            value: TagValue::Id(MaybeDynamicId::Static(key.deserialize_u8(2)?.into())),
+        }),
+        4 => Ok(BitmapClass::Text {
+            field: key.deserialize_u8(1)?,
+            token: BitmapHash {
+                len: key.deserialize_u8(2)?,
+                hash: key
+                    .range(3..11)?
+                    .try_into()
+                    .map_err(|_| trc::Error::corrupted_key(key, None, trc::location!()))?,
+            },
+        }),
+        _ => Err(trc::Error::corrupted_key(key, None, trc::location!())),
+    }
+}
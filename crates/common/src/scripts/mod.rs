@@ -26,6 +26,10 @@ pub enum ScriptModification {
         name: Arc<String>,
         value: Arc<String>,
     },
+    Snooze {
+        until: u64,
+        mailbox: String,
+    },
 }
 
 pub fn into_sieve_value(value: Value) -> Variable {
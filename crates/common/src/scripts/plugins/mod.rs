@@ -13,7 +13,9 @@ pub mod llm_prompt;
 pub mod lookup;
 pub mod pyzor;
 pub mod query;
+pub mod snooze;
 pub mod text;
+pub mod thread;
 
 use mail_parser::Message;
 use sieve::{runtime::Variable, FunctionMap, Input};
@@ -33,7 +35,7 @@ pub struct PluginContext<'x> {
     pub arguments: Vec<Variable>,
 }
 
-const PLUGINS_REGISTER: [RegisterPluginFnc; 19] = [
+const PLUGINS_REGISTER: [RegisterPluginFnc; 21] = [
     query::register,
     exec::register,
     lookup::register,
@@ -53,6 +55,8 @@ const PLUGINS_REGISTER: [RegisterPluginFnc; 19] = [
     text::register_tokenize,
     text::register_domain_part,
     llm_prompt::register,
+    snooze::register,
+    thread::register_is_muted,
 ];
 
 pub trait RegisterSievePlugins {
@@ -75,6 +79,8 @@ impl RegisterSievePlugins for FunctionMap {
 
     fn register_plugins_untrusted(mut self) -> Self {
         llm_prompt::register(18, &mut self);
+        snooze::register(19, &mut self);
+        thread::register_is_muted(20, &mut self);
         self
     }
 }
@@ -107,6 +113,8 @@ impl Core {
             16 => text::exec_tokenize(ctx),
             17 => text::exec_domain_part(ctx),
             18 => llm_prompt::exec(ctx).await,
+            19 => snooze::exec(ctx),
+            20 => thread::exec_is_muted(ctx).await,
             _ => unreachable!(),
         };
 
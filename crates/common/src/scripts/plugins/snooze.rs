@@ -0,0 +1,40 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use mail_parser::DateTime;
+use sieve::{runtime::Variable, FunctionMap};
+use store::write::now;
+
+use crate::scripts::ScriptModification;
+
+use super::PluginContext;
+
+pub fn register(plugin_id: u32, fnc_map: &mut FunctionMap) {
+    fnc_map.set_external_function("snooze", plugin_id, 2);
+}
+
+pub fn exec(ctx: PluginContext<'_>) -> trc::Result<Variable> {
+    // Integers are interpreted as seconds from now, strings as RFC 3339 dates
+    let until = match &ctx.arguments[0] {
+        Variable::Integer(seconds) if *seconds > 0 => Some(now() + *seconds as u64),
+        Variable::String(date) => DateTime::parse_rfc3339(date)
+            .map(|date| date.to_timestamp())
+            .filter(|timestamp| *timestamp > 0)
+            .map(|timestamp| timestamp as u64),
+        _ => None,
+    };
+
+    Ok(if let Some(until) = until {
+        ctx.modifications.push(ScriptModification::Snooze {
+            until,
+            mailbox: ctx.arguments[1].to_string().into_owned(),
+        });
+        true
+    } else {
+        false
+    }
+    .into())
+}
@@ -0,0 +1,99 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::types::{
+    collection::Collection,
+    keyword::{Keyword, MUTED_KEYWORD},
+    property::Property,
+};
+use mail_parser::{HeaderName, HeaderValue};
+use sieve::{runtime::Variable, FunctionMap};
+use store::{ahash::AHashSet, query::Filter, write::ValueClass, ValueKey};
+use trc::AddContext;
+
+use super::PluginContext;
+
+pub fn register_is_muted(plugin_id: u32, fnc_map: &mut FunctionMap) {
+    fnc_map.set_external_function("is_thread_muted", plugin_id, 0);
+}
+
+pub async fn exec_is_muted(ctx: PluginContext<'_>) -> trc::Result<Variable> {
+    let account_id = if let Some(access_token) = ctx.access_token {
+        access_token.primary_id
+    } else {
+        return Ok(false.into());
+    };
+
+    // Obtain message references
+    let mut filters = vec![Filter::Or];
+    for header in ctx.message.root_part().headers() {
+        if matches!(
+            header.name,
+            HeaderName::MessageId | HeaderName::InReplyTo | HeaderName::References
+        ) {
+            match &header.value {
+                HeaderValue::Text(id) => {
+                    filters.push(Filter::eq(Property::References, id.as_ref()));
+                }
+                HeaderValue::TextList(ids) => {
+                    for id in ids {
+                        filters.push(Filter::eq(Property::References, id.as_ref()));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if filters.len() == 1 {
+        return Ok(false.into());
+    }
+    filters.push(Filter::End);
+
+    // Find the threads of the referenced messages
+    let store = &ctx.server.core.storage.data;
+    let document_ids = store
+        .filter(account_id, Collection::Email, filters)
+        .await
+        .caused_by(trc::location!())?
+        .results;
+    let mut thread_ids = AHashSet::new();
+    let mut filters = vec![
+        Filter::is_in_bitmap(
+            Property::Keywords,
+            Keyword::Other(MUTED_KEYWORD.to_string()),
+        ),
+        Filter::Or,
+    ];
+    for document_id in document_ids {
+        if let Some(thread_id) = store
+            .get_value::<u32>(ValueKey {
+                account_id,
+                collection: Collection::Email.into(),
+                document_id,
+                class: ValueClass::Property(Property::ThreadId.into()),
+            })
+            .await
+            .caused_by(trc::location!())?
+        {
+            if thread_ids.insert(thread_id) {
+                filters.push(Filter::is_in_bitmap(Property::ThreadId, thread_id));
+            }
+        }
+    }
+    if thread_ids.is_empty() {
+        return Ok(false.into());
+    }
+    filters.push(Filter::End);
+
+    // A thread is muted when any of its messages has the $muted keyword
+    Ok((!store
+        .filter(account_id, Collection::Email, filters)
+        .await
+        .caused_by(trc::location!())?
+        .results
+        .is_empty())
+    .into())
+}
@@ -6,7 +6,7 @@
 
 use std::time::SystemTime;
 
-use opentelemetry::global::set_error_handler;
+use opentelemetry::{global::set_error_handler, KeyValue};
 use opentelemetry_sdk::metrics::data::{
     DataPoint, Gauge, Histogram, HistogramDataPoint, Metric, ResourceMetrics, ScopeMetrics, Sum,
     Temporality,
@@ -82,6 +82,61 @@ impl OtelMetrics {
             });
         }
 
+        // Add store operation histograms and error counters
+        let store_metrics = Collector::collect_store_histograms(is_enterprise);
+        if !store_metrics.is_empty() {
+            let id = trc::MetricType::StoreOperationTime;
+            let mut histograms = Vec::new();
+            let mut errors = Vec::new();
+            for store in &store_metrics {
+                for (operation, histogram) in store.histograms() {
+                    histograms.push(HistogramDataPoint {
+                        attributes: store_attributes(store.id(), operation.as_str()),
+                        start_time,
+                        time: now,
+                        count: histogram.count(),
+                        bounds: histogram.upper_bounds_vec(),
+                        bucket_counts: histogram.buckets_vec(),
+                        min: histogram.min(),
+                        max: histogram.max(),
+                        sum: histogram.sum(),
+                        exemplars: vec![],
+                    });
+                }
+                for (operation, value) in store.errors() {
+                    errors.push(DataPoint {
+                        attributes: store_attributes(store.id(), operation.as_str()),
+                        start_time: start_time.into(),
+                        time: now.into(),
+                        value,
+                        exemplars: vec![],
+                    });
+                }
+            }
+
+            metrics.push(Metric {
+                name: id.name().into(),
+                description: id.description().into(),
+                unit: id.unit().into(),
+                data: Box::new(Histogram {
+                    data_points: histograms,
+                    temporality: Temporality::Cumulative,
+                }),
+            });
+            if !errors.is_empty() {
+                metrics.push(Metric {
+                    name: "store.operation-errors".into(),
+                    description: "Failed store operations by store and operation".into(),
+                    unit: "events".into(),
+                    data: Box::new(Sum {
+                        data_points: errors,
+                        temporality: Temporality::Cumulative,
+                        is_monotonic: true,
+                    }),
+                });
+            }
+        }
+
         // Export metrics
         if let Err(err) = self
             .exporter
@@ -110,3 +165,10 @@ impl OtelMetrics {
         });
     }
 }
+
+fn store_attributes(store: &str, operation: &str) -> Vec<KeyValue> {
+    vec![
+        KeyValue::new("store", store.to_string()),
+        KeyValue::new("operation", operation.to_string()),
+    ]
+}
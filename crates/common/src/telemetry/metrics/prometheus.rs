@@ -5,7 +5,7 @@
  */
 
 use prometheus::{
-    proto::{Bucket, Counter, Gauge, Histogram, Metric, MetricFamily, MetricType},
+    proto::{Bucket, Counter, Gauge, Histogram, LabelPair, Metric, MetricFamily, MetricType},
     TextEncoder,
 };
 use trc::{atomics::histogram::AtomicHistogram, Collector};
@@ -52,6 +52,42 @@ impl Server {
             metrics.push(metric);
         }
 
+        // Add store operation histograms and error counters
+        let store_metrics = Collector::collect_store_histograms(is_enterprise);
+        if !store_metrics.is_empty() {
+            let id = trc::MetricType::StoreOperationTime;
+            let mut histograms = Vec::new();
+            let mut errors = Vec::new();
+            for store in &store_metrics {
+                for (operation, histogram) in store.histograms() {
+                    let mut m = new_histogram(histogram);
+                    m.set_label(store_labels(store.id(), operation.as_str()));
+                    histograms.push(m);
+                }
+                for (operation, value) in store.errors() {
+                    let mut m = new_counter(value);
+                    m.set_label(store_labels(store.id(), operation.as_str()));
+                    errors.push(m);
+                }
+            }
+
+            let mut metric = MetricFamily::default();
+            metric.set_name(metric_name(id.name()));
+            metric.set_help(id.description().into());
+            metric.set_field_type(MetricType::HISTOGRAM);
+            metric.set_metric(histograms);
+            metrics.push(metric);
+
+            if !errors.is_empty() {
+                let mut metric = MetricFamily::default();
+                metric.set_name("store_operation_errors".into());
+                metric.set_help("Failed store operations by store and operation".into());
+                metric.set_field_type(MetricType::COUNTER);
+                metric.set_metric(errors);
+                metrics.push(metric);
+            }
+        }
+
         TextEncoder::new().encode_to_string(&metrics).map_err(|e| {
             trc::EventType::Telemetry(trc::TelemetryEvent::OtelExporterError).reason(e)
         })
@@ -71,6 +107,18 @@ fn metric_name(id: impl AsRef<str>) -> String {
     name
 }
 
+fn store_labels(store: &str, operation: &str) -> Vec<LabelPair> {
+    [("store", store), ("operation", operation)]
+        .into_iter()
+        .map(|(name, value)| {
+            let mut label = LabelPair::default();
+            label.set_name(name.into());
+            label.set_value(value.into());
+            label
+        })
+        .collect()
+}
+
 fn new_counter(value: u64) -> Metric {
     let mut m = Metric::default();
     let mut counter = Counter::default();
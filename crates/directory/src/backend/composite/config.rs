@@ -0,0 +1,92 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use ahash::AHashMap;
+use utils::config::{utils::AsKey, Config};
+
+use crate::Directory;
+
+use super::{CompositeBackend, CompositeDirectory, CompositeStrategy};
+
+impl CompositeDirectory {
+    pub fn from_config(
+        config: &mut Config,
+        prefix: impl AsKey,
+        directories: &AHashMap<String, Arc<Directory>>,
+    ) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let default_timeout = config
+            .property_or_default::<Duration>((&prefix, "timeout.default"), "30s")
+            .unwrap_or_else(|| Duration::from_secs(30));
+
+        let mut backends = Vec::new();
+        for id in config
+            .values((&prefix, "backends"))
+            .map(|(_, v)| v.to_string())
+            .collect::<Vec<_>>()
+        {
+            if let Some(directory) = directories.get(&id) {
+                backends.push(CompositeBackend {
+                    timeout: config
+                        .property((prefix.as_str(), "timeout", id.as_str()))
+                        .unwrap_or(default_timeout),
+                    directory: directory.clone(),
+                    id,
+                });
+            } else {
+                // Composite directories are built in order, so they can only reference
+                // the composite directories defined before them
+                let err = match config.value(("directory", id.as_str(), "type")) {
+                    Some("composite") => {
+                        format!("Forward reference to composite directory {id:?}")
+                    }
+                    Some(_) => format!("Directory {id:?} failed to load"),
+                    None => format!("Unknown directory {id:?}"),
+                };
+                config.new_parse_error((&prefix, "backends"), err);
+                return None;
+            }
+        }
+
+        if backends.is_empty() {
+            config.new_parse_error((&prefix, "backends"), "No backends configured");
+            return None;
+        }
+
+        let strategy = match config.value((&prefix, "strategy")).unwrap_or("first-match") {
+            "first-match" => CompositeStrategy::FirstMatch,
+            "lookup-all" => CompositeStrategy::LookupAll,
+            other => {
+                let err = format!("Invalid composite strategy {other:?}");
+                config.new_parse_error((&prefix, "strategy"), err);
+                return None;
+            }
+        };
+
+        let auth_backend = if let Some(id) = config.value((&prefix, "auth.backend")) {
+            if let Some(idx) = backends.iter().position(|b| b.id == id) {
+                Some(idx)
+            } else {
+                let err = format!("Authentication backend {id:?} is not part of the chain");
+                config.new_parse_error((&prefix, "auth.backend"), err);
+                return None;
+            }
+        } else {
+            None
+        };
+
+        Some(CompositeDirectory {
+            backends,
+            strategy,
+            auth_backend,
+            merge_member_of: config
+                .property_or_default((&prefix, "merge.member-of"), "false")
+                .unwrap_or_default(),
+        })
+    }
+}
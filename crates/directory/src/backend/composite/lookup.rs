@@ -0,0 +1,189 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{future::Future, pin::Pin};
+
+use crate::{backend::internal::PrincipalField, Directory, Principal, QueryBy};
+
+use super::{CompositeBackend, CompositeDirectory, CompositeStrategy};
+
+// Backends are themselves directories, so the recursive calls are boxed
+type BoxedFuture<'x, T> = Pin<Box<dyn Future<Output = trc::Result<T>> + Send + 'x>>;
+
+impl CompositeDirectory {
+    pub fn query<'x>(
+        &'x self,
+        by: QueryBy<'x>,
+        return_member_of: bool,
+    ) -> BoxedFuture<'x, Option<Principal>> {
+        Box::pin(async move { self.query_chain(by, return_member_of).await })
+    }
+
+    async fn query_chain(
+        &self,
+        by: QueryBy<'_>,
+        return_member_of: bool,
+    ) -> trc::Result<Option<Principal>> {
+        // Credentials are only verified by the authentication backend, if configured
+        let backends = match (by, self.auth_backend) {
+            (QueryBy::Credentials(_), Some(idx)) => std::slice::from_ref(&self.backends[idx]),
+            _ => self.backends.as_slice(),
+        };
+
+        let mut failure = None;
+        let mut result = None;
+        for backend in backends {
+            match backend
+                .run(backend.directory.query(by, return_member_of))
+                .await
+            {
+                Ok(Some(principal)) => {
+                    result = Some((principal, backend));
+                    break;
+                }
+                Ok(None) => {}
+                Err(err) => backend.failed(&mut failure, err),
+            }
+        }
+
+        let (mut principal, source) = match (result, failure) {
+            (Some(result), failure) => {
+                if let Some(err) = failure {
+                    trc::error!(err);
+                }
+                result
+            }
+            (None, Some(err)) => return Err(err),
+            (None, None) => return Ok(None),
+        };
+
+        // Add memberships from the remaining backends
+        if self.merge_member_of && return_member_of {
+            let name = principal.name().to_string();
+            for backend in &self.backends {
+                if std::ptr::eq(backend, source) {
+                    continue;
+                }
+
+                match backend
+                    .run(backend.directory.query(QueryBy::Name(&name), true))
+                    .await
+                {
+                    Ok(Some(other)) => {
+                        for field in [
+                            PrincipalField::MemberOf,
+                            PrincipalField::Roles,
+                            PrincipalField::Lists,
+                        ] {
+                            for id in other.iter_int(field) {
+                                principal.append_int(field, id);
+                            }
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        trc::error!(err.ctx(trc::Key::Id, backend.id.clone()));
+                    }
+                }
+            }
+        }
+
+        Ok(Some(principal))
+    }
+
+    pub fn email_to_ids<'x>(&'x self, address: &'x str) -> BoxedFuture<'x, Vec<u32>> {
+        Box::pin(async move { self.collect(|d| d.email_to_ids(address)).await })
+    }
+
+    pub fn is_local_domain<'x>(&'x self, domain: &'x str) -> BoxedFuture<'x, bool> {
+        Box::pin(async move { self.any(|d| d.is_local_domain(domain)).await })
+    }
+
+    pub fn rcpt<'x>(&'x self, address: &'x str) -> BoxedFuture<'x, bool> {
+        Box::pin(async move { self.any(|d| d.rcpt(address)).await })
+    }
+
+    pub fn vrfy<'x>(&'x self, address: &'x str) -> BoxedFuture<'x, Vec<String>> {
+        Box::pin(async move { self.collect(|d| d.vrfy(address)).await })
+    }
+
+    pub fn expn<'x>(&'x self, address: &'x str) -> BoxedFuture<'x, Vec<String>> {
+        Box::pin(async move { self.collect(|d| d.expn(address)).await })
+    }
+
+    async fn collect<'x, T: PartialEq, F: Future<Output = trc::Result<Vec<T>>>>(
+        &'x self,
+        f: impl Fn(&'x Directory) -> F,
+    ) -> trc::Result<Vec<T>> {
+        let mut failure = None;
+        let mut results = Vec::new();
+        for backend in &self.backends {
+            match backend.run(f(&backend.directory)).await {
+                Ok(items) if !items.is_empty() => {
+                    for item in items {
+                        if !results.contains(&item) {
+                            results.push(item);
+                        }
+                    }
+                    if self.strategy == CompositeStrategy::FirstMatch {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => backend.failed(&mut failure, err),
+            }
+        }
+
+        match failure {
+            Some(err) if results.is_empty() => Err(err),
+            Some(err) => {
+                trc::error!(err);
+                Ok(results)
+            }
+            None => Ok(results),
+        }
+    }
+
+    async fn any<'x, F: Future<Output = trc::Result<bool>>>(
+        &'x self,
+        f: impl Fn(&'x Directory) -> F,
+    ) -> trc::Result<bool> {
+        let mut failure: Option<trc::Error> = None;
+        for backend in &self.backends {
+            match backend.run(f(&backend.directory)).await {
+                Ok(true) => {
+                    if let Some(err) = failure {
+                        trc::error!(err);
+                    }
+                    return Ok(true);
+                }
+                Ok(false) => {}
+                Err(err) => backend.failed(&mut failure, err),
+            }
+        }
+
+        failure.map_or(Ok(false), Err)
+    }
+}
+
+impl CompositeBackend {
+    async fn run<T>(&self, future: impl Future<Output = trc::Result<T>>) -> trc::Result<T> {
+        tokio::time::timeout(self.timeout, future)
+            .await
+            .unwrap_or_else(|_| {
+                Err(trc::NetworkEvent::Timeout
+                    .into_err()
+                    .details("Directory backend timed out"))
+            })
+    }
+
+    // Keeps the most recent error, logging the one it replaces
+    fn failed(&self, failure: &mut Option<trc::Error>, err: trc::Error) {
+        if let Some(err) = failure.replace(err.ctx(trc::Key::Id, self.id.clone())) {
+            trc::error!(err);
+        }
+    }
+}
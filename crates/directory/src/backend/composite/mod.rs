@@ -0,0 +1,39 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use crate::Directory;
+
+pub mod config;
+pub mod lookup;
+
+pub struct CompositeDirectory {
+    backends: Vec<CompositeBackend>,
+    strategy: CompositeStrategy,
+    auth_backend: Option<usize>,
+    merge_member_of: bool,
+}
+
+struct CompositeBackend {
+    id: String,
+    directory: Arc<Directory>,
+    timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompositeStrategy {
+    FirstMatch,
+    LookupAll,
+}
+
+impl CompositeDirectory {
+    pub fn has_bearer_token_support(&self) -> bool {
+        self.backends
+            .iter()
+            .any(|backend| backend.directory.has_bearer_token_support())
+    }
+}
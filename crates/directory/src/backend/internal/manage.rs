@@ -8,16 +8,16 @@ use ahash::AHashSet;
 use jmap_proto::types::collection::Collection;
 use store::{
     write::{
-        assert::HashedValue, key::DeserializeBigEndian, AssignedIds, BatchBuilder, DirectoryClass,
-        MaybeDynamicId, MaybeDynamicValue, SerializeWithId, ValueClass,
+        assert::HashedValue, key::DeserializeBigEndian, now, AssignedIds, BatchBuilder,
+        DirectoryClass, MaybeDynamicId, MaybeDynamicValue, SerializeWithId, ValueClass,
     },
     Deserialize, IterateParams, Serialize, Store, ValueKey, U32_LEN,
 };
 use trc::AddContext;
 
 use crate::{
-    Permission, Permissions, Principal, QueryBy, Type, MAX_TYPE_ID, ROLE_ADMIN, ROLE_TENANT_ADMIN,
-    ROLE_USER,
+    core::policy::PasswordPolicy, Permission, Permissions, Principal, QueryBy, Type, MAX_TYPE_ID,
+    ROLE_ADMIN, ROLE_TENANT_ADMIN, ROLE_USER,
 };
 
 use super::{
@@ -37,11 +37,12 @@ pub struct PrincipalList {
 }
 
 pub struct UpdatePrincipal<'x> {
-    query: QueryBy<'x>,
+    pub(crate) query: QueryBy<'x>,
     allowed_permissions: Option<&'x Permissions>,
-    changes: Vec<PrincipalUpdate>,
+    pub(crate) changes: Vec<PrincipalUpdate>,
     tenant_id: Option<u32>,
     create_domains: bool,
+    pub(crate) password_policy: Option<&'x PasswordPolicy>,
 }
 
 #[allow(async_fn_in_trait)]
@@ -402,6 +403,14 @@ impl ManageDirectory for Store {
             }
         }
 
+        // Record when the password was set, used for password expiration
+        if principal
+            .iter_str(PrincipalField::Secrets)
+            .any(|s| s.is_password())
+        {
+            principal.set(PrincipalField::PasswordChanged, now());
+        }
+
         // Write principal
         let mut batch = BatchBuilder::new();
         let pinfo_name = DynamicPrincipalInfo::new(principal.typ, tenant_id);
@@ -801,6 +810,26 @@ impl ManageDirectory for Store {
             Type::Role => &[Type::Role][..],
         };
         let mut valid_domains = AHashSet::new();
+        let replaced_passwords = principal
+            .inner
+            .iter_str(PrincipalField::Secrets)
+            .filter(|s| s.is_password())
+            .cloned()
+            .collect::<Vec<_>>();
+        let new_passwords = changes
+            .iter()
+            .filter(|c| {
+                c.field == PrincipalField::Secrets
+                    && matches!(c.action, PrincipalAction::Set | PrincipalAction::AddItem)
+            })
+            .flat_map(|c| {
+                c.value.iter_str().filter(|s| {
+                    s.is_password()
+                        && (c.action == PrincipalAction::AddItem || !replaced_passwords.contains(s))
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
 
         // Process changes
         for change in changes {
@@ -990,6 +1019,31 @@ impl ManageDirectory for Store {
                 {
                     principal.inner.remove(PrincipalField::Quota);
                 }
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::LastLogin,
+                    PrincipalValue::Integer(last_login),
+                ) if principal.inner.typ == Type::Individual => {
+                    principal.inner.set(PrincipalField::LastLogin, last_login);
+                }
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::ProvisionedBy,
+                    PrincipalValue::String(directory_id),
+                ) if principal.inner.typ == Type::Individual => {
+                    principal
+                        .inner
+                        .set(PrincipalField::ProvisionedBy, directory_id);
+                }
+                (
+                    PrincipalAction::Set,
+                    PrincipalField::PasswordChanged,
+                    PrincipalValue::Integer(changed),
+                ) if principal.inner.typ == Type::Individual => {
+                    principal
+                        .inner
+                        .set(PrincipalField::PasswordChanged, changed);
+                }
                 (
                     PrincipalAction::Set,
                     PrincipalField::Quota,
@@ -1454,6 +1508,20 @@ impl ManageDirectory for Store {
             }
         }
 
+        // Enforce the password policy on new passwords
+        if !new_passwords.is_empty() {
+            if let Some(policy) = params.password_policy {
+                for password in &new_passwords {
+                    policy.validate(password).await?;
+                    policy
+                        .validate_reuse(&principal.inner, &replaced_passwords, password)
+                        .await?;
+                }
+                policy.update_history(&mut principal.inner, replaced_passwords);
+            }
+            principal.inner.set(PrincipalField::PasswordChanged, now());
+        }
+
         if update_principal {
             batch.set(
                 ValueClass::Directory(DirectoryClass::Principal(MaybeDynamicId::Static(
@@ -1901,6 +1969,7 @@ impl<'x> UpdatePrincipal<'x> {
             create_domains: false,
             tenant_id: None,
             allowed_permissions: None,
+            password_policy: None,
         }
     }
 
@@ -1911,6 +1980,7 @@ impl<'x> UpdatePrincipal<'x> {
             create_domains: false,
             tenant_id: None,
             allowed_permissions: None,
+            password_policy: None,
         }
     }
 
@@ -1933,6 +2003,11 @@ impl<'x> UpdatePrincipal<'x> {
         self.create_domains = true;
         self
     }
+
+    pub fn with_password_policy(mut self, policy: Option<&'x PasswordPolicy>) -> Self {
+        self.password_policy = policy;
+        self
+    }
 }
 
 fn validate_member_of(
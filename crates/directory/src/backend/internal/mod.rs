@@ -412,6 +412,10 @@ pub enum PrincipalField {
     DisabledPermissions,
     Picture,
     Urls,
+    LastLogin,
+    PasswordHistory,
+    PasswordChanged,
+    ProvisionedBy,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -491,6 +495,10 @@ impl PrincipalField {
             PrincipalField::UsedQuota => 13,
             PrincipalField::Picture => 14,
             PrincipalField::Urls => 15,
+            PrincipalField::LastLogin => 16,
+            PrincipalField::PasswordHistory => 17,
+            PrincipalField::PasswordChanged => 18,
+            PrincipalField::ProvisionedBy => 19,
         }
     }
 
@@ -512,6 +520,10 @@ impl PrincipalField {
             13 => Some(PrincipalField::UsedQuota),
             14 => Some(PrincipalField::Picture),
             15 => Some(PrincipalField::Urls),
+            16 => Some(PrincipalField::LastLogin),
+            17 => Some(PrincipalField::PasswordHistory),
+            18 => Some(PrincipalField::PasswordChanged),
+            19 => Some(PrincipalField::ProvisionedBy),
             _ => None,
         }
     }
@@ -534,6 +546,10 @@ impl PrincipalField {
             PrincipalField::DisabledPermissions => "disabledPermissions",
             PrincipalField::Picture => "picture",
             PrincipalField::Urls => "urls",
+            PrincipalField::LastLogin => "lastLogin",
+            PrincipalField::PasswordHistory => "passwordHistory",
+            PrincipalField::PasswordChanged => "passwordChanged",
+            PrincipalField::ProvisionedBy => "provisionedBy",
         }
     }
 
@@ -555,6 +571,10 @@ impl PrincipalField {
             "disabledPermissions" => Some(PrincipalField::DisabledPermissions),
             "picture" => Some(PrincipalField::Picture),
             "urls" => Some(PrincipalField::Urls),
+            "lastLogin" => Some(PrincipalField::LastLogin),
+            "passwordHistory" => Some(PrincipalField::PasswordHistory),
+            "passwordChanged" => Some(PrincipalField::PasswordChanged),
+            "provisionedBy" => Some(PrincipalField::ProvisionedBy),
             _ => None,
         }
     }
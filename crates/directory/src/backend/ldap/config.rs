@@ -7,12 +7,19 @@
 use std::time::Duration;
 
 use ldap3::LdapConnSettings;
+use parking_lot::Mutex;
 use store::Store;
 use utils::config::{utils::AsKey, Config};
 
-use crate::core::config::build_pool;
+use crate::{
+    core::{config::build_pool, provisioning::Provisioning},
+    Permission,
+};
 
-use super::{AuthBind, Bind, LdapConnectionManager, LdapDirectory, LdapFilter, LdapMappings};
+use super::{
+    AuthBind, Bind, GroupCache, GroupPolicy, LdapConnectionManager, LdapDirectory, LdapFilter,
+    LdapGroups, LdapMappings, LdapWrite, PasswordScheme,
+};
 
 impl LdapDirectory {
     pub fn from_config(config: &mut Config, prefix: impl AsKey, data_store: Store) -> Option<Self> {
@@ -87,9 +94,24 @@ impl LdapDirectory {
                 .values((&prefix, "attributes.email-alias"))
                 .map(|(_, v)| v.to_string())
                 .collect(),
+            attr_roles: Vec::new(),
+            attr_tenant: Vec::new(),
             attrs_principal: vec!["objectClass".to_string()],
         };
 
+        // Roles and tenants are only read when provisioning accounts
+        let provisioning = Provisioning::from_config(config, &prefix);
+        if provisioning.is_some() {
+            mappings.attr_roles = config
+                .values((&prefix, "attributes.roles"))
+                .map(|(_, v)| v.to_string())
+                .collect();
+            mappings.attr_tenant = config
+                .values((&prefix, "attributes.tenant"))
+                .map(|(_, v)| v.to_string())
+                .collect();
+        }
+
         for attr in [
             &mappings.attr_name,
             &mappings.attr_type,
@@ -99,6 +121,8 @@ impl LdapDirectory {
             &mappings.attr_groups,
             &mappings.attr_email_address,
             &mappings.attr_email_alias,
+            &mappings.attr_roles,
+            &mappings.attr_tenant,
         ] {
             mappings.attrs_principal.extend(attr.iter().cloned());
         }
@@ -116,6 +140,48 @@ impl LdapDirectory {
             None
         };
 
+        let groups = LdapGroups::from_config(config, &prefix, &mappings);
+
+        let write = if config
+            .property_or_default::<bool>((&prefix, "write.enable"), "false")
+            .unwrap_or_default()
+        {
+            match config
+                .value((&prefix, "write.password"))
+                .unwrap_or("modify")
+            {
+                "modify" => Some(LdapWrite::PasswordModify),
+                "attribute" if !mappings.attr_secret.is_empty() => {
+                    match config
+                        .value((&prefix, "write.password-hash"))
+                        .unwrap_or("ssha")
+                    {
+                        "ssha" => Some(LdapWrite::PasswordAttribute(PasswordScheme::Ssha)),
+                        "crypt" => Some(LdapWrite::PasswordAttribute(PasswordScheme::Crypt)),
+                        other => {
+                            let err = format!("Invalid password hash scheme {other:?}");
+                            config.new_parse_error((&prefix, "write.password-hash"), err);
+                            None
+                        }
+                    }
+                }
+                "attribute" => {
+                    config.new_parse_error(
+                        (&prefix, "write.password"),
+                        "Password attribute writes require 'attributes.secret' to be set",
+                    );
+                    None
+                }
+                other => {
+                    let err = format!("Invalid password write method {other:?}");
+                    config.new_parse_error((&prefix, "write.password"), err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         Some(LdapDirectory {
             mappings,
             pool: build_pool(config, &prefix, manager)
@@ -124,11 +190,132 @@ impl LdapDirectory {
                 })
                 .ok()?,
             auth_bind,
+            groups,
+            write,
+            provisioning,
             data_store,
         })
     }
 }
 
+impl LdapGroups {
+    fn from_config(config: &mut Config, prefix: &str, mappings: &LdapMappings) -> Self {
+        let max_depth = if config
+            .property_or_default::<bool>((prefix, "groups.nested.enable"), "false")
+            .unwrap_or_default()
+        {
+            config
+                .property_or_default((prefix, "groups.nested.max-depth"), "8")
+                .unwrap_or(8)
+        } else {
+            0
+        };
+        let in_chain = if max_depth > 0
+            && config
+                .property_or_default::<bool>((prefix, "groups.nested.in-chain"), "false")
+                .unwrap_or_default()
+        {
+            config
+                .value((prefix, "attributes.member"))
+                .unwrap_or("member")
+                .to_string()
+                .into()
+        } else {
+            None
+        };
+
+        // Filter used to look up groups listed by name rather than by DN
+        let filter = if max_depth > 0 && in_chain.is_none() {
+            let filter = LdapFilter::from_config(config, (prefix, "filter.group"));
+            if !filter.filter.is_empty() {
+                Some(filter)
+            } else {
+                config.new_build_warning(
+                    (prefix, "filter.group"),
+                    "Nested groups listed by name will not be expanded without a group filter",
+                );
+                None
+            }
+        } else {
+            None
+        };
+
+        // Attributes to request when resolving group entries
+        let mut attrs = mappings.attr_name.clone();
+        if max_depth > 0 {
+            attrs.extend(mappings.attr_groups.iter().cloned());
+        }
+
+        let cache = config
+            .property::<usize>((prefix, "groups.cache.size"))
+            .filter(|size| *size > 0)
+            .map(|size| GroupCache {
+                entries: Mutex::new(lru_cache::LruCache::with_hasher(
+                    size,
+                    ahash::RandomState::new(),
+                )),
+                ttl: config
+                    .property_or_default((prefix, "groups.cache.ttl"), "5m")
+                    .unwrap_or_else(|| Duration::from_secs(300)),
+            });
+
+        // Group to role and permission mappings
+        let mut policies = Vec::new();
+        for policy_id in config
+            .sub_keys((prefix, "groups.policy"), ".group")
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+        {
+            let policy_id = policy_id.as_str();
+            let group = if let Some(group) =
+                config.value_require((prefix, "groups.policy", policy_id, "group"))
+            {
+                group.to_string()
+            } else {
+                continue;
+            };
+            let roles = config
+                .values((prefix, "groups.policy", policy_id, "roles"))
+                .map(|(_, v)| v.to_string())
+                .collect();
+            let mut permissions = [Vec::new(), Vec::new()];
+            for (permissions, key) in permissions
+                .iter_mut()
+                .zip(["enabled-permissions", "disabled-permissions"])
+            {
+                for (key, name) in config
+                    .values((prefix, "groups.policy", policy_id, key))
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<Vec<_>>()
+                {
+                    if let Some(permission) = Permission::from_name(&name) {
+                        permissions.push(permission);
+                    } else {
+                        config.new_parse_error(key, format!("Invalid permission {name:?}"));
+                    }
+                }
+            }
+            let [enabled_permissions, disabled_permissions] = permissions;
+
+            policies.push(GroupPolicy {
+                group,
+                roles,
+                enabled_permissions,
+                disabled_permissions,
+            });
+        }
+
+        LdapGroups {
+            max_depth,
+            in_chain,
+            filter,
+            attrs,
+            cache,
+            policies,
+        }
+    }
+}
+
 impl LdapFilter {
     fn from_config(config: &mut Config, key: impl AsKey) -> Self {
         if let Some(value) = config.value(key.clone()) {
@@ -0,0 +1,469 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{collections::VecDeque, time::Instant};
+
+use ahash::AHashSet;
+use ldap3::{ldap_escape, Ldap, Scope, SearchEntry};
+use trc::AddContext;
+
+use crate::{
+    backend::internal::{manage::ManageDirectory, PrincipalField},
+    IntoError, Principal, Type,
+};
+
+use super::{GroupCache, LdapDirectory, LdapGroups, LdapMappings};
+
+// Active Directory extensible match rule that walks the membership chain server-side
+const LDAP_MATCHING_RULE_IN_CHAIN: &str = "1.2.840.113556.1.4.1941";
+
+impl LdapDirectory {
+    pub(crate) async fn expand_groups(
+        &self,
+        conn: &mut Ldap,
+        dn: &str,
+        names: Vec<String>,
+    ) -> trc::Result<Vec<String>> {
+        if let Some(groups) = self.groups.cache.as_ref().and_then(|cache| cache.get(dn)) {
+            return Ok(groups);
+        }
+
+        let groups = if let Some(member_attr) = &self.groups.in_chain {
+            self.groups_in_chain(conn, dn, member_attr).await?
+        } else {
+            self.groups_nested(conn, names).await?
+        };
+
+        if let Some(cache) = &self.groups.cache {
+            cache.insert(dn, groups.clone());
+        }
+
+        Ok(groups)
+    }
+
+    async fn groups_in_chain(
+        &self,
+        conn: &mut Ldap,
+        dn: &str,
+        member_attr: &str,
+    ) -> trc::Result<Vec<String>> {
+        let filter = format!(
+            "({member_attr}:{LDAP_MATCHING_RULE_IN_CHAIN}:={})",
+            ldap_escape(dn)
+        );
+        let (rs, _res) = conn
+            .search(
+                &self.mappings.base_dn,
+                Scope::Subtree,
+                &filter,
+                &self.mappings.attr_name,
+            )
+            .await
+            .map_err(|err| err.into_error().caused_by(trc::location!()))?
+            .success()
+            .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+
+        trc::event!(
+            Store(trc::StoreEvent::LdapQuery),
+            Details = filter,
+            Result = rs
+                .iter()
+                .map(|e| trc::Value::from(format!("{e:?}")))
+                .collect::<Vec<_>>()
+        );
+
+        let mut groups = Vec::with_capacity(rs.len());
+        for entry in rs {
+            let mut entry = SearchEntry::construct(entry);
+            let name = self
+                .mappings
+                .entry_name(&mut entry)
+                .unwrap_or_else(|| std::mem::take(&mut entry.dn));
+            if !groups.contains(&name) {
+                groups.push(name);
+            }
+        }
+
+        Ok(groups)
+    }
+
+    async fn groups_nested(&self, conn: &mut Ldap, names: Vec<String>) -> trc::Result<Vec<String>> {
+        let mut walk = GroupWalk::new(names, self.groups.max_depth);
+
+        while let Some((group, depth)) = walk.next_group() {
+            let expand = walk.expands(depth);
+            let (base, scope, filter) =
+                if let Some(query) = self.groups.query(&self.mappings.base_dn, &group, expand) {
+                    query
+                } else {
+                    walk.visit(group, depth, Vec::new());
+                    continue;
+                };
+
+            let (rs, _res) = conn
+                .search(base, scope, &filter, &self.groups.attrs)
+                .await
+                .map_err(|err| err.into_error().caused_by(trc::location!()))?
+                .success()
+                .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+
+            let mut name = None;
+            let mut parents = Vec::new();
+            if let Some(entry) = rs.into_iter().next() {
+                let mut entry = SearchEntry::construct(entry);
+                name = self.mappings.entry_name(&mut entry);
+
+                if expand {
+                    for attr in &self.mappings.attr_groups {
+                        parents.extend(entry.attrs.remove(attr).unwrap_or_default());
+                    }
+                }
+            }
+
+            walk.visit(name.unwrap_or(group), depth, parents);
+        }
+
+        Ok(walk.groups)
+    }
+
+    pub(crate) async fn apply_group_policies(
+        &self,
+        groups: &[String],
+        principal: &mut Principal,
+    ) -> trc::Result<()> {
+        for policy in &self.groups.policies {
+            if !groups.iter().any(|g| g.eq_ignore_ascii_case(&policy.group)) {
+                continue;
+            }
+
+            for role in &policy.roles {
+                let role_id =
+                    if let Some(role_id) = PrincipalField::Roles.map_internal_role_name(role) {
+                        role_id
+                    } else if let Some(info) = self
+                        .data_store
+                        .get_principal_info(role)
+                        .await
+                        .caused_by(trc::location!())?
+                        .filter(|info| info.typ == Type::Role)
+                    {
+                        info.id
+                    } else {
+                        trc::event!(
+                            Store(trc::StoreEvent::LdapError),
+                            Reason = "Role mapped to LDAP group not found",
+                            Details = policy.group.clone(),
+                            Id = role.clone(),
+                        );
+                        continue;
+                    };
+
+                principal.append_int(PrincipalField::Roles, role_id);
+            }
+
+            for (field, permissions) in [
+                (
+                    PrincipalField::EnabledPermissions,
+                    &policy.enabled_permissions,
+                ),
+                (
+                    PrincipalField::DisabledPermissions,
+                    &policy.disabled_permissions,
+                ),
+            ] {
+                for permission in permissions {
+                    principal.append_int(field, permission.id() as u64);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl LdapGroups {
+    // Returns the search used to resolve a group, or None when a group listed by
+    // name cannot be expanded any further
+    fn query<'x>(
+        &'x self,
+        base_dn: &'x str,
+        group: &'x str,
+        expand: bool,
+    ) -> Option<(&'x str, Scope, String)> {
+        if group.contains('=') {
+            Some((group, Scope::Base, "objectClass=*".to_string()))
+        } else if expand {
+            self.filter
+                .as_ref()
+                .map(|filter| (base_dn, Scope::Subtree, filter.build(group)))
+        } else {
+            None
+        }
+    }
+}
+
+impl LdapMappings {
+    fn entry_name(&self, entry: &mut SearchEntry) -> Option<String> {
+        for attr in &self.attr_name {
+            if let Some(name) = entry
+                .attrs
+                .get_mut(attr)
+                .and_then(|values| values.drain(..).find(|v| !v.is_empty()))
+            {
+                return Some(name);
+            }
+        }
+
+        None
+    }
+}
+
+// Breadth-first walk over group memberships, so each group is reached at its
+// shallowest depth before the depth limit applies
+struct GroupWalk {
+    groups: Vec<String>,
+    seen: AHashSet<String>,
+    pending: VecDeque<(String, usize)>,
+    max_depth: usize,
+}
+
+impl GroupWalk {
+    fn new(names: Vec<String>, max_depth: usize) -> Self {
+        GroupWalk {
+            groups: Vec::with_capacity(names.len()),
+            seen: AHashSet::new(),
+            pending: names.into_iter().map(|name| (name, 0)).collect(),
+            max_depth,
+        }
+    }
+
+    fn next_group(&mut self) -> Option<(String, usize)> {
+        while let Some((group, depth)) = self.pending.pop_front() {
+            // Skip groups that were already visited, which also breaks membership cycles
+            if self.seen.insert(group.to_lowercase()) {
+                return Some((group, depth));
+            }
+        }
+
+        None
+    }
+
+    fn expands(&self, depth: usize) -> bool {
+        depth < self.max_depth
+    }
+
+    fn visit(&mut self, name: String, depth: usize, parents: Vec<String>) {
+        if self.expands(depth) {
+            for parent in parents {
+                if !parent.is_empty() && !self.seen.contains(&parent.to_lowercase()) {
+                    self.pending.push_back((parent, depth + 1));
+                }
+            }
+        }
+
+        if !self.groups.contains(&name) {
+            self.groups.push(name);
+        }
+    }
+}
+
+impl GroupCache {
+    fn get(&self, dn: &str) -> Option<Vec<String>> {
+        let dn = dn.to_lowercase();
+        let mut entries = self.entries.lock();
+        match entries.get_mut(&dn) {
+            Some((groups, valid_until)) if *valid_until >= Instant::now() => Some(groups.clone()),
+            Some(_) => {
+                entries.remove(&dn);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, dn: &str, groups: Vec<String>) {
+        self.entries
+            .lock()
+            .insert(dn.to_lowercase(), (groups, Instant::now() + self.ttl));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ahash::AHashMap;
+    use ldap3::Scope;
+    use parking_lot::Mutex;
+
+    use crate::backend::ldap::{GroupCache, LdapFilter, LdapGroups};
+
+    use super::GroupWalk;
+
+    fn walk(graph: &AHashMap<&str, Vec<&str>>, names: &[&str], max_depth: usize) -> Vec<String> {
+        let mut walk = GroupWalk::new(names.iter().map(|n| n.to_string()).collect(), max_depth);
+        let mut lookups = 0;
+
+        while let Some((group, depth)) = walk.next_group() {
+            lookups += 1;
+            assert!(
+                lookups <= graph.len() + names.len(),
+                "walk did not terminate"
+            );
+
+            let parents = if walk.expands(depth) {
+                graph
+                    .get(group.as_str())
+                    .map(|parents| parents.iter().map(|p| p.to_string()).collect())
+                    .unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            walk.visit(group, depth, parents);
+        }
+
+        walk.groups
+    }
+
+    #[test]
+    fn ldap_nested_groups() {
+        let graph = AHashMap::from_iter([
+            ("sales", vec!["emea", "staff"]),
+            ("emea", vec!["regions"]),
+            ("regions", vec!["staff", "everyone"]),
+            ("staff", vec!["everyone"]),
+            ("everyone", vec![]),
+            // Membership cycle
+            ("ops", vec!["infra"]),
+            ("infra", vec!["Ops", "admins"]),
+            ("admins", vec!["infra"]),
+        ]);
+
+        // Direct memberships only when nesting is disabled
+        assert_eq!(walk(&graph, &["sales", "ops"], 0), vec!["sales", "ops"]);
+
+        // Nested memberships are expanded once each
+        assert_eq!(
+            walk(&graph, &["sales"], 8),
+            vec!["sales", "emea", "staff", "regions", "everyone"]
+        );
+
+        // Cycles are broken by the visited set, regardless of case
+        assert_eq!(walk(&graph, &["ops"], 8), vec!["ops", "infra", "admins"]);
+        assert_eq!(
+            walk(&graph, &["admins", "OPS"], 8),
+            vec!["admins", "OPS", "infra"]
+        );
+
+        // Groups beyond the maximum depth are not followed
+        assert_eq!(walk(&graph, &["sales"], 1), vec!["sales", "emea", "staff"]);
+        assert_eq!(
+            walk(&graph, &["sales"], 2),
+            vec!["sales", "emea", "staff", "regions", "everyone"]
+        );
+        assert_eq!(walk(&graph, &["emea"], 1), vec!["emea", "regions"]);
+    }
+
+    #[test]
+    fn ldap_group_query() {
+        let base_dn = "dc=example,dc=org";
+        let groups = LdapGroups {
+            max_depth: 4,
+            filter: Some(LdapFilter {
+                filter: vec![
+                    "(&(objectClass=posixGroup)(cn=".to_string(),
+                    "))".to_string(),
+                ],
+            }),
+            ..Default::default()
+        };
+
+        // Groups listed by DN are read directly
+        assert_eq!(
+            groups.query(base_dn, "cn=sales,ou=groups,dc=example,dc=org", true),
+            Some((
+                "cn=sales,ou=groups,dc=example,dc=org",
+                Scope::Base,
+                "objectClass=*".to_string()
+            ))
+        );
+
+        // Groups listed by name are looked up with the group filter
+        assert_eq!(
+            groups.query(base_dn, "support", true),
+            Some((
+                base_dn,
+                Scope::Subtree,
+                "(&(objectClass=posixGroup)(cn=support))".to_string()
+            ))
+        );
+        assert_eq!(
+            groups.query(base_dn, "sup*port", true),
+            Some((
+                base_dn,
+                Scope::Subtree,
+                "(&(objectClass=posixGroup)(cn=sup\\2aport))".to_string()
+            ))
+        );
+        assert_eq!(groups.query(base_dn, "support", false), None);
+
+        // Groups listed by name are not expanded without a group filter
+        let groups = LdapGroups {
+            max_depth: 4,
+            ..Default::default()
+        };
+        assert_eq!(groups.query(base_dn, "support", true), None);
+        assert!(groups
+            .query(base_dn, "cn=support,ou=groups,dc=example,dc=org", true)
+            .is_some());
+    }
+
+    #[test]
+    fn ldap_group_cache() {
+        let cache = GroupCache {
+            entries: Mutex::new(lru_cache::LruCache::with_hasher(
+                2,
+                ahash::RandomState::new(),
+            )),
+            ttl: Duration::from_secs(300),
+        };
+        let groups = vec!["sales".to_string(), "staff".to_string()];
+
+        // Entries are keyed by the lowercase DN
+        cache.insert("uid=John,ou=People,dc=example,dc=org", groups.clone());
+        assert_eq!(
+            cache.get("UID=john,ou=people,dc=example,dc=org"),
+            Some(groups.clone())
+        );
+        assert_eq!(cache.get("uid=jane,ou=people,dc=example,dc=org"), None);
+
+        // Least recently used entries are evicted
+        cache.insert("uid=jane,ou=people,dc=example,dc=org", vec![]);
+        cache.insert("uid=bill,ou=people,dc=example,dc=org", vec![]);
+        assert_eq!(cache.get("uid=john,ou=people,dc=example,dc=org"), None);
+        assert_eq!(
+            cache.get("uid=bill,ou=people,dc=example,dc=org"),
+            Some(vec![])
+        );
+
+        // Expired entries are dropped on lookup
+        let cache = GroupCache {
+            entries: Mutex::new(lru_cache::LruCache::with_hasher(
+                2,
+                ahash::RandomState::new(),
+            )),
+            ttl: Duration::from_millis(10),
+        };
+        cache.insert("uid=john,ou=people,dc=example,dc=org", groups.clone());
+        assert_eq!(
+            cache.get("uid=john,ou=people,dc=example,dc=org"),
+            Some(groups)
+        );
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(cache.get("uid=john,ou=people,dc=example,dc=org"), None);
+        assert!(cache.entries.lock().is_empty());
+    }
+}
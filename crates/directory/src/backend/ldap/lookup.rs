@@ -26,15 +26,17 @@ impl LdapDirectory {
         return_member_of: bool,
     ) -> trc::Result<Option<Principal>> {
         let mut conn = self.pool.get().await.map_err(|err| err.into_error())?;
+        let is_login = matches!(by, QueryBy::Credentials(_));
 
-        let (mut external_principal, stored_principal) = match by {
+        let (mut external_principal, dn, stored_principal) = match by {
             QueryBy::Name(username) => {
-                if let Some(principal) = self
+                if let Some((principal, dn)) = self
                     .find_principal(&mut conn, &self.mappings.filter_name.build(username))
                     .await?
                 {
                     (
                         principal.with_field(PrincipalField::Name, username.to_string()),
+                        dn,
                         None,
                     )
                 } else {
@@ -47,14 +49,14 @@ impl LdapDirectory {
                     .query(QueryBy::Id(uid), return_member_of)
                     .await?
                 {
-                    if let Some(principal) = self
+                    if let Some((principal, dn)) = self
                         .find_principal(
                             &mut conn,
                             &self.mappings.filter_name.build(stored_principal_.name()),
                         )
                         .await?
                     {
-                        (principal, Some(stored_principal_))
+                        (principal, dn, Some(stored_principal_))
                     } else {
                         return Ok(None);
                     }
@@ -100,8 +102,9 @@ impl LdapDirectory {
                         self.find_principal(&mut conn, filter).await
                     };
                     match principal {
-                        Ok(Some(principal)) => (
+                        Ok(Some((principal, dn))) => (
                             principal.with_field(PrincipalField::Name, username.to_string()),
+                            dn,
                             None,
                         ),
                         Err(err)
@@ -116,13 +119,14 @@ impl LdapDirectory {
                         Ok(None) => return Ok(None),
                         Err(err) => return Err(err),
                     }
-                } else if let Some(principal) = self
+                } else if let Some((principal, dn)) = self
                     .find_principal(&mut conn, &self.mappings.filter_name.build(username))
                     .await?
                 {
                     if principal.verify_secret(secret).await? {
                         (
                             principal.with_field(PrincipalField::Name, username.to_string()),
+                            dn,
                             None,
                         )
                     } else {
@@ -135,48 +139,29 @@ impl LdapDirectory {
         };
 
         // Query groups
-        match external_principal.take_str_array(PrincipalField::MemberOf) {
-            Some(names) if return_member_of => {
-                let mut member_of = Vec::with_capacity(names.len());
-                for mut name in names {
-                    if name.contains('=') {
-                        let (rs, _res) = conn
-                            .search(
-                                &name,
-                                Scope::Base,
-                                "objectClass=*",
-                                &self.mappings.attr_name,
-                            )
-                            .await
-                            .map_err(|err| err.into_error().caused_by(trc::location!()))?
-                            .success()
-                            .map_err(|err| err.into_error().caused_by(trc::location!()))?;
-                        for entry in rs {
-                            'outer: for (attr, value) in SearchEntry::construct(entry).attrs {
-                                if self.mappings.attr_name.contains(&attr) {
-                                    if let Some(group) = value.into_iter().next() {
-                                        if !group.is_empty() {
-                                            name = group;
-                                            break 'outer;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-
+        let mut groups = Vec::new();
+        if return_member_of {
+            let names = external_principal
+                .take_str_array(PrincipalField::MemberOf)
+                .unwrap_or_default();
+            if !names.is_empty() || self.groups.in_chain.is_some() {
+                groups = self.expand_groups(&mut conn, &dn, names).await?;
+
+                let mut member_of = Vec::with_capacity(groups.len());
+                for name in &groups {
                     member_of.push(
                         self.data_store
-                            .get_or_create_principal_id(&name, Type::Group)
+                            .get_or_create_principal_id(name, Type::Group)
                             .await
                             .caused_by(trc::location!())?,
                     );
                 }
 
                 // Map ids
-                external_principal.set(PrincipalField::MemberOf, member_of);
+                if !member_of.is_empty() {
+                    external_principal.set(PrincipalField::MemberOf, member_of);
+                }
             }
-            _ => (),
         }
 
         // Obtain account ID if not available
@@ -197,7 +182,20 @@ impl LdapDirectory {
         };
 
         // Keep the internal store up to date with the LDAP server
-        let changes = principal.update_external(external_principal);
+        let mut changes = if let Some(provisioning) = &self.provisioning {
+            provisioning
+                .apply(
+                    &self.data_store,
+                    &mut principal,
+                    &mut external_principal,
+                    is_login,
+                )
+                .await
+                .caused_by(trc::location!())?
+        } else {
+            Vec::new()
+        };
+        changes.extend(principal.update_external(external_principal));
         if !changes.is_empty() {
             self.data_store
                 .update_principal(
@@ -209,6 +207,11 @@ impl LdapDirectory {
                 .caused_by(trc::location!())?;
         }
 
+        // Apply group policies
+        if !groups.is_empty() {
+            self.apply_group_policies(&groups, &mut principal).await?;
+        }
+
         Ok(Some(principal))
     }
 
@@ -414,11 +417,11 @@ impl LdapDirectory {
 }
 
 impl LdapDirectory {
-    async fn find_principal(
+    pub(crate) async fn find_principal(
         &self,
         conn: &mut Ldap,
         filter: &str,
-    ) -> trc::Result<Option<Principal>> {
+    ) -> trc::Result<Option<(Principal, String)>> {
         conn.search(
             &self.mappings.base_dn,
             Scope::Subtree,
@@ -439,8 +442,9 @@ impl LdapDirectory {
             );
 
             rs.into_iter().next().map(|entry| {
-                self.mappings
-                    .entry_to_principal(SearchEntry::construct(entry))
+                let mut entry = SearchEntry::construct(entry);
+                let dn = std::mem::take(&mut entry.dn);
+                (self.mappings.entry_to_principal(entry), dn)
             })
         })
         .map_err(|err| err.into_error().caused_by(trc::location!()))
@@ -481,6 +485,14 @@ impl LdapMappings {
                 for item in value {
                     principal.append_str(PrincipalField::MemberOf, item);
                 }
+            } else if self.attr_roles.contains(&attr) {
+                for item in value {
+                    principal.append_str(PrincipalField::Roles, item);
+                }
+            } else if self.attr_tenant.contains(&attr) {
+                if let Some(tenant) = value.into_iter().find(|v| !v.is_empty()) {
+                    principal.set(PrincipalField::Tenant, tenant);
+                }
             } else if self.attr_quota.contains(&attr) {
                 if let Ok(quota) = value.into_iter().next().unwrap_or_default().parse::<u64>() {
                     principal.set(PrincipalField::Quota, quota);
@@ -505,6 +517,11 @@ impl LdapMappings {
             }
         }
 
-        principal.with_field(PrincipalField::Roles, role)
+        // Role names read from the directory take precedence
+        if !principal.has_field(PrincipalField::Roles) {
+            principal.set(PrincipalField::Roles, role);
+        }
+
+        principal
     }
 }
@@ -4,18 +4,28 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::time::{Duration, Instant};
+
 use deadpool::managed::Pool;
 use ldap3::{ldap_escape, LdapConnSettings};
+use parking_lot::Mutex;
 use store::Store;
 
+use crate::{core::provisioning::Provisioning, Permission};
+
 pub mod config;
+pub mod groups;
 pub mod lookup;
 pub mod pool;
+pub mod write;
 
 pub struct LdapDirectory {
     pool: Pool<LdapConnectionManager>,
     mappings: LdapMappings,
     auth_bind: Option<AuthBind>,
+    groups: LdapGroups,
+    write: Option<LdapWrite>,
+    pub(crate) provisioning: Option<Provisioning>,
     pub(crate) data_store: Store,
 }
 
@@ -35,9 +45,48 @@ pub struct LdapMappings {
     attr_email_address: Vec<String>,
     attr_email_alias: Vec<String>,
     attr_quota: Vec<String>,
+    attr_roles: Vec<String>,
+    attr_tenant: Vec<String>,
     attrs_principal: Vec<String>,
 }
 
+#[derive(Debug, Default)]
+pub(crate) struct LdapGroups {
+    max_depth: usize,
+    in_chain: Option<String>,
+    filter: Option<LdapFilter>,
+    attrs: Vec<String>,
+    cache: Option<GroupCache>,
+    policies: Vec<GroupPolicy>,
+}
+
+#[derive(Debug)]
+pub(crate) struct GroupCache {
+    entries: Mutex<lru_cache::LruCache<String, (Vec<String>, Instant), ahash::RandomState>>,
+    ttl: Duration,
+}
+
+#[derive(Debug)]
+pub(crate) struct GroupPolicy {
+    group: String,
+    roles: Vec<String>,
+    enabled_permissions: Vec<Permission>,
+    disabled_permissions: Vec<Permission>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LdapWrite {
+    PasswordModify,
+    PasswordAttribute(PasswordScheme),
+}
+
+/// Scheme used to hash passwords written directly to the secret attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PasswordScheme {
+    Ssha,
+    Crypt,
+}
+
 #[derive(Debug, Default)]
 struct LdapFilter {
     filter: Vec<String>,
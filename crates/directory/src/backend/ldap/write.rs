@@ -0,0 +1,492 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::collections::HashSet;
+
+use base64::{engine::general_purpose, Engine};
+use ldap3::{exop::PasswordModify, Mod};
+use pwhash::sha512_crypt;
+use sha1::{Digest, Sha1};
+use trc::AddContext;
+
+use crate::{
+    backend::internal::{
+        manage::{self, ManageDirectory, UpdatePrincipal},
+        PrincipalAction, PrincipalField, PrincipalUpdate, PrincipalValue, SpecialSecrets,
+    },
+    core::secret::is_password_hash,
+    IntoError, Principal, QueryBy,
+};
+
+use super::{LdapDirectory, LdapMappings, LdapWrite, PasswordScheme};
+
+impl LdapDirectory {
+    pub fn is_writable(&self) -> bool {
+        self.write.is_some()
+    }
+
+    pub async fn update_principal(&self, mut params: UpdatePrincipal<'_>) -> trc::Result<()> {
+        let write = self
+            .write
+            .ok_or_else(|| manage::unsupported("LDAP directory is read-only"))?;
+
+        // Obtain principal name
+        let name = match &params.query {
+            QueryBy::Name(name) => name.to_string(),
+            QueryBy::Id(id) => self
+                .data_store
+                .get_principal(*id)
+                .await
+                .caused_by(trc::location!())?
+                .ok_or_else(|| manage::not_found(*id))?
+                .name()
+                .to_string(),
+            QueryBy::Credentials(_) => {
+                return Err(manage::unsupported("Invalid principal query"));
+            }
+        };
+
+        // Fetch the LDAP entry
+        let mut conn = self.pool.get().await.map_err(|err| err.into_error())?;
+        let (entry, dn) = self
+            .find_principal(&mut conn, &self.mappings.filter_name.build(&name))
+            .await?
+            .ok_or_else(|| manage::not_found(name.clone()))?;
+
+        // Build modifications
+        let LdapChanges {
+            mut mods,
+            password,
+            local_changes,
+        } = self
+            .mappings
+            .build_changes(&entry, std::mem::take(&mut params.changes))?;
+
+        if let Some(password) = &password {
+            // Reuse is enforced by the LDAP server, only validate the password itself
+            if let Some(policy) = params.password_policy {
+                policy.validate(password).await?;
+            }
+
+            match write {
+                LdapWrite::PasswordModify => {
+                    conn.extended(PasswordModify {
+                        user_id: Some(&dn),
+                        old_pass: None,
+                        new_pass: Some(password),
+                    })
+                    .await
+                    .map_err(|err| err.into_error().caused_by(trc::location!()))?
+                    .success()
+                    .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+                }
+                LdapWrite::PasswordAttribute(scheme) => {
+                    mods.push(Mod::Replace(
+                        self.mappings.attr_secret[0].clone(),
+                        HashSet::from([hash_password(password, scheme)?]),
+                    ));
+                }
+            }
+        }
+
+        if !mods.is_empty() {
+            let details = mods
+                .iter()
+                .map(|m| match m {
+                    Mod::Add(attr, _)
+                    | Mod::Delete(attr, _)
+                    | Mod::Replace(attr, _)
+                    | Mod::Increment(attr, _) => trc::Value::from(attr.clone()),
+                })
+                .collect::<Vec<_>>();
+
+            conn.modify(&dn, mods)
+                .await
+                .map_err(|err| err.into_error().caused_by(trc::location!()))?
+                .success()
+                .map_err(|err| err.into_error().caused_by(trc::location!()))?;
+
+            trc::event!(
+                Store(trc::StoreEvent::LdapQuery),
+                Details = dn,
+                Result = details
+            );
+        }
+
+        // Keep the internal store in sync
+        if !local_changes.is_empty() {
+            params.changes = local_changes;
+            self.data_store
+                .update_principal(params)
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+}
+
+struct LdapChanges {
+    mods: Vec<Mod<String>>,
+    password: Option<String>,
+    local_changes: Vec<PrincipalUpdate>,
+}
+
+impl LdapMappings {
+    /// Translates principal changes into LDAP modifications, returning the new password
+    /// separately so it can be validated before it is written.
+    fn build_changes(
+        &self,
+        entry: &Principal,
+        changes: Vec<PrincipalUpdate>,
+    ) -> trc::Result<LdapChanges> {
+        let mut mods = Vec::new();
+        let mut password = None;
+        let mut emails: Option<Vec<String>> = None;
+        let mut local_changes = Vec::with_capacity(changes.len());
+        for change in changes {
+            match change.field {
+                PrincipalField::Secrets => {
+                    match (change.action, change.value) {
+                        (PrincipalAction::RemoveItem, PrincipalValue::String(secret))
+                            if secret.is_empty() =>
+                        {
+                            // Removal of existing passwords prior to a password change
+                        }
+                        (
+                            PrincipalAction::AddItem | PrincipalAction::Set,
+                            PrincipalValue::String(secret),
+                        ) if secret.is_password() => {
+                            password = Some(secret);
+                        }
+                        (PrincipalAction::Set, PrincipalValue::StringList(secrets))
+                            if secrets.iter().all(|s| s.is_password()) =>
+                        {
+                            password = secrets.into_iter().next();
+                        }
+                        _ => {
+                            return Err(manage::unsupported(
+                                "LDAP directories only support password changes",
+                            ));
+                        }
+                    }
+                    continue;
+                }
+                PrincipalField::Emails => {
+                    if self.attr_email_address.is_empty() {
+                        return Err(manage::unsupported(
+                            "LDAP directory has no attribute configured for email addresses",
+                        ));
+                    }
+                    let emails = emails.get_or_insert_with(|| {
+                        entry
+                            .get_str_array(PrincipalField::Emails)
+                            .unwrap_or_default()
+                            .to_vec()
+                    });
+                    apply_emails_change(emails, &change);
+                }
+                PrincipalField::Quota => {
+                    if let (Some(attr), PrincipalAction::Set) =
+                        (self.attr_quota.first(), &change.action)
+                    {
+                        let quota = match &change.value {
+                            PrincipalValue::Integer(quota) => *quota,
+                            _ => 0,
+                        };
+                        mods.push(if quota > 0 {
+                            Mod::Replace(attr.clone(), HashSet::from([quota.to_string()]))
+                        } else {
+                            Mod::Replace(attr.clone(), HashSet::new())
+                        });
+                    }
+                }
+                PrincipalField::Description => {
+                    if let (Some(attr), PrincipalAction::Set, PrincipalValue::String(value)) =
+                        (self.attr_description.first(), &change.action, &change.value)
+                    {
+                        mods.push(Mod::Replace(attr.clone(), HashSet::from([value.clone()])));
+                    }
+                }
+                _ => (),
+            }
+
+            local_changes.push(change);
+        }
+
+        // Split addresses into the primary address and aliases
+        if let Some(emails) = emails {
+            let mut emails = emails.into_iter();
+            let attr = &self.attr_email_address[0];
+            mods.push(Mod::Replace(
+                attr.clone(),
+                emails.next().into_iter().collect(),
+            ));
+            let aliases = emails.collect::<HashSet<_>>();
+            if let Some(attr) = self.attr_email_alias.first() {
+                mods.push(Mod::Replace(attr.clone(), aliases));
+            } else if !aliases.is_empty() {
+                return Err(manage::unsupported(
+                    "LDAP directory has no attribute configured for email aliases",
+                ));
+            }
+        }
+
+        Ok(LdapChanges {
+            mods,
+            password,
+            local_changes,
+        })
+    }
+}
+
+// Passwords are never written to the directory in clear text, pre-hashed
+// passwords are stored as-is using the RFC 3112 scheme prefix.
+fn hash_password(password: &str, scheme: PasswordScheme) -> trc::Result<String> {
+    if is_password_hash(password) {
+        return Ok(if password.starts_with('{') {
+            password.to_string()
+        } else {
+            format!("{{CRYPT}}{password}")
+        });
+    }
+
+    match scheme {
+        PasswordScheme::Ssha => {
+            let salt = rand::random::<[u8; 8]>();
+            let mut hasher = Sha1::new();
+            hasher.update(password.as_bytes());
+            hasher.update(salt);
+            let mut hash = hasher.finalize().to_vec();
+            hash.extend_from_slice(&salt);
+            Ok(format!(
+                "{{SSHA}}{}",
+                general_purpose::STANDARD.encode(hash)
+            ))
+        }
+        PasswordScheme::Crypt => sha512_crypt::hash(password)
+            .map(|hash| format!("{{CRYPT}}{hash}"))
+            .map_err(|err| {
+                trc::EventType::Manage(trc::ManageEvent::Error)
+                    .reason(err)
+                    .caused_by(trc::location!())
+            }),
+    }
+}
+
+fn apply_emails_change(emails: &mut Vec<String>, change: &PrincipalUpdate) {
+    match (&change.action, &change.value) {
+        (PrincipalAction::Set, PrincipalValue::StringList(values)) => {
+            *emails = values.iter().map(|v| v.to_lowercase()).collect();
+        }
+        (PrincipalAction::Set, PrincipalValue::String(value)) => {
+            *emails = vec![value.to_lowercase()];
+        }
+        (PrincipalAction::AddItem, PrincipalValue::String(value)) => {
+            let value = value.to_lowercase();
+            if !emails.contains(&value) {
+                emails.push(value);
+            }
+        }
+        (PrincipalAction::RemoveItem, PrincipalValue::String(value)) => {
+            emails.retain(|v| !v.eq_ignore_ascii_case(value));
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ldap3::Mod;
+
+    use crate::{
+        backend::{
+            internal::{PrincipalField, PrincipalUpdate, PrincipalValue},
+            ldap::{LdapMappings, PasswordScheme},
+        },
+        core::secret::verify_secret_hash,
+        Principal, Type,
+    };
+
+    use super::hash_password;
+
+    #[test]
+    fn ldap_changes() {
+        let mappings = LdapMappings {
+            attr_secret: vec!["userPassword".to_string()],
+            attr_email_address: vec!["mail".to_string()],
+            attr_email_alias: vec!["mailAlias".to_string()],
+            attr_quota: vec!["diskQuota".to_string()],
+            attr_description: vec!["cn".to_string()],
+            ..Default::default()
+        };
+        let entry = Principal::new(1, Type::Individual).with_field(
+            PrincipalField::Emails,
+            vec![
+                "john@example.org".to_string(),
+                "jdoe@example.org".to_string(),
+            ],
+        );
+
+        for (changes, expected_mods, expected_password) in [
+            // Create, update and delete email addresses
+            (
+                vec![PrincipalUpdate::add_item(
+                    PrincipalField::Emails,
+                    PrincipalValue::String("John.Smith@example.org".to_string()),
+                )],
+                vec![
+                    ("mail", vec!["john@example.org"]),
+                    (
+                        "mailAlias",
+                        vec!["jdoe@example.org", "john.smith@example.org"],
+                    ),
+                ],
+                None,
+            ),
+            (
+                vec![PrincipalUpdate::set(
+                    PrincipalField::Emails,
+                    PrincipalValue::StringList(vec![
+                        "jane@example.org".to_string(),
+                        "john@example.org".to_string(),
+                    ]),
+                )],
+                vec![
+                    ("mail", vec!["jane@example.org"]),
+                    ("mailAlias", vec!["john@example.org"]),
+                ],
+                None,
+            ),
+            (
+                vec![PrincipalUpdate::remove_item(
+                    PrincipalField::Emails,
+                    PrincipalValue::String("jdoe@example.org".to_string()),
+                )],
+                vec![("mail", vec!["john@example.org"]), ("mailAlias", vec![])],
+                None,
+            ),
+            // Create, update and delete quotas and descriptions
+            (
+                vec![
+                    PrincipalUpdate::set(PrincipalField::Quota, PrincipalValue::Integer(1024)),
+                    PrincipalUpdate::set(
+                        PrincipalField::Description,
+                        PrincipalValue::String("John Doe".to_string()),
+                    ),
+                ],
+                vec![("diskQuota", vec!["1024"]), ("cn", vec!["John Doe"])],
+                None,
+            ),
+            (
+                vec![PrincipalUpdate::set(
+                    PrincipalField::Quota,
+                    PrincipalValue::Integer(0),
+                )],
+                vec![("diskQuota", vec![])],
+                None,
+            ),
+            // Password changes are returned separately
+            (
+                vec![
+                    PrincipalUpdate::remove_item(
+                        PrincipalField::Secrets,
+                        PrincipalValue::String(String::new()),
+                    ),
+                    PrincipalUpdate::add_item(
+                        PrincipalField::Secrets,
+                        PrincipalValue::String("Secret-pass1".to_string()),
+                    ),
+                ],
+                vec![],
+                Some("Secret-pass1"),
+            ),
+        ] {
+            let is_password = expected_password.is_some();
+            let changes = mappings.build_changes(&entry, changes).unwrap();
+            let mut mods = changes
+                .mods
+                .into_iter()
+                .map(|m| match m {
+                    Mod::Replace(attr, values) => {
+                        let mut values = values.into_iter().collect::<Vec<_>>();
+                        values.sort();
+                        (attr, values)
+                    }
+                    _ => panic!("Unexpected modification {m:?}"),
+                })
+                .collect::<Vec<_>>();
+            mods.sort();
+            let mut expected_mods = expected_mods
+                .into_iter()
+                .map(|(attr, values)| {
+                    (
+                        attr.to_string(),
+                        values.into_iter().map(String::from).collect::<Vec<_>>(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            expected_mods.sort();
+
+            assert_eq!(mods, expected_mods);
+            assert_eq!(changes.password.as_deref(), expected_password);
+            assert_eq!(changes.local_changes.is_empty(), is_password);
+        }
+
+        // Other secrets can not be written to the directory
+        assert!(mappings
+            .build_changes(
+                &entry,
+                vec![PrincipalUpdate::add_item(
+                    PrincipalField::Secrets,
+                    PrincipalValue::String("otpauth://totp/john".to_string()),
+                )],
+            )
+            .is_err());
+
+        // Email addresses can not be changed without an address attribute
+        assert!(LdapMappings::default()
+            .build_changes(
+                &entry,
+                vec![PrincipalUpdate::add_item(
+                    PrincipalField::Emails,
+                    PrincipalValue::String("john.smith@example.org".to_string()),
+                )],
+            )
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn ldap_password_hash() {
+        for (scheme, prefix) in [
+            (PasswordScheme::Ssha, "{SSHA}"),
+            (PasswordScheme::Crypt, "{CRYPT}$6$"),
+        ] {
+            let hash = hash_password("Secret-pass1", scheme).unwrap();
+            assert!(hash.starts_with(prefix), "{hash}");
+            assert_ne!(hash, hash_password("Secret-pass1", scheme).unwrap());
+            assert!(verify_secret_hash(&hash, "Secret-pass1").await.unwrap());
+            assert!(!verify_secret_hash(&hash, "Secret-pass2").await.unwrap());
+        }
+
+        // Pre-hashed passwords are stored as-is
+        for (password, expected) in [
+            (
+                "$2y$05$bvIG6Nmid91Mu9RcmmWZfO5HJIMCT8riNW0hEp8f6/FuA2/mHZFpe",
+                "{CRYPT}$2y$05$bvIG6Nmid91Mu9RcmmWZfO5HJIMCT8riNW0hEp8f6/FuA2/mHZFpe",
+            ),
+            (
+                "{SSHA}5en6G6MezRroT3XKqkdPOmY/BfQtaWNO",
+                "{SSHA}5en6G6MezRroT3XKqkdPOmY/BfQtaWNO",
+            ),
+        ] {
+            assert_eq!(
+                hash_password(password, PasswordScheme::Ssha).unwrap(),
+                expected
+            );
+        }
+    }
+}
@@ -14,6 +14,8 @@ use base64::{engine::general_purpose, Engine};
 use store::Store;
 use utils::config::{utils::AsKey, Config};
 
+use crate::core::provisioning::Provisioning;
+
 use super::{Authentication, EndpointType, OpenIdConfig, OpenIdDirectory};
 
 impl OpenIdDirectory {
@@ -59,6 +61,21 @@ impl OpenIdDirectory {
             }
         };
 
+        // Roles and tenants are only read when provisioning accounts
+        let provisioning = Provisioning::from_config(config, &prefix);
+        let (roles_field, tenant_field) = if provisioning.is_some() {
+            (
+                config
+                    .value((&prefix, "fields.roles"))
+                    .map(|v| v.to_string()),
+                config
+                    .value((&prefix, "fields.tenant"))
+                    .map(|v| v.to_string()),
+            )
+        } else {
+            (None, None)
+        };
+
         Some(OpenIdDirectory {
             config: OpenIdConfig {
                 endpoint: config.value_require((&prefix, "endpoint.url"))?.to_string(),
@@ -73,7 +90,13 @@ impl OpenIdDirectory {
                 full_name_field: config
                     .value((&prefix, "fields.full-name"))
                     .map(|v| v.to_string()),
+                quota_field: config
+                    .value((&prefix, "fields.quota"))
+                    .map(|v| v.to_string()),
+                roles_field,
+                tenant_field,
             },
+            provisioning,
             data_store,
         })
     }
@@ -87,7 +87,7 @@ impl OpenIdDirectory {
                         })?;
 
                         // Deserialize response
-                        let external_principal =
+                        let mut external_principal =
                             serde_json::from_slice::<OpenIdResponse>(&response)
                                 .map_err(|err| {
                                     AuthEvent::Error
@@ -111,7 +111,20 @@ impl OpenIdDirectory {
                             .ok_or_else(|| manage::not_found(id).caused_by(trc::location!()))?;
 
                         // Keep the internal store up to date with the OIDC server
-                        let changes = principal.update_external(external_principal);
+                        let mut changes = if let Some(provisioning) = &self.provisioning {
+                            provisioning
+                                .apply(
+                                    &self.data_store,
+                                    &mut principal,
+                                    &mut external_principal,
+                                    true,
+                                )
+                                .await
+                                .caused_by(trc::location!())?
+                        } else {
+                            Vec::new()
+                        };
+                        changes.extend(principal.update_external(external_principal));
                         if !changes.is_empty() {
                             self.data_store
                                 .update_principal(
@@ -165,6 +178,7 @@ trait BuildPrincipal {
     fn build_principal(&mut self, config: &OpenIdConfig) -> trc::Result<Principal>;
     fn take_required_field(&mut self, field: &str) -> trc::Result<String>;
     fn take_field(&mut self, field: &str) -> Option<String>;
+    fn take_field_list(&mut self, field: &str) -> Vec<String>;
 }
 
 impl BuildPrincipal for OpenIdResponse {
@@ -188,11 +202,37 @@ impl BuildPrincipal for OpenIdResponse {
             .as_ref()
             .and_then(|field| self.take_field(field));
 
-        Ok(Principal::new(u32::MAX, Type::Individual)
+        let quota = config
+            .quota_field
+            .as_ref()
+            .and_then(|field| match self.remove(field) {
+                Some(serde_json::Value::Number(quota)) => quota.as_u64(),
+                Some(serde_json::Value::String(quota)) => quota.parse().ok(),
+                _ => None,
+            });
+        let tenant = config
+            .tenant_field
+            .as_ref()
+            .and_then(|field| self.take_field(field));
+        let roles = config
+            .roles_field
+            .as_ref()
+            .map(|field| self.take_field_list(field))
+            .filter(|roles| !roles.is_empty());
+
+        let principal = Principal::new(u32::MAX, Type::Individual)
             .with_field(PrincipalField::Name, username)
             .with_field(PrincipalField::Emails, email)
-            .with_field(PrincipalField::Roles, ROLE_USER)
-            .with_opt_field(PrincipalField::Description, full_name))
+            .with_opt_field(PrincipalField::Description, full_name)
+            .with_opt_field(PrincipalField::Quota, quota)
+            .with_opt_field(PrincipalField::Tenant, tenant);
+
+        // Role names from the OIDC response are mapped during provisioning
+        Ok(if let Some(roles) = roles {
+            principal.with_field(PrincipalField::Roles, roles)
+        } else {
+            principal.with_field(PrincipalField::Roles, ROLE_USER)
+        })
     }
 
     fn take_required_field(&mut self, field: &str) -> trc::Result<String> {
@@ -216,4 +256,18 @@ impl BuildPrincipal for OpenIdResponse {
             _ => None,
         }
     }
+
+    fn take_field_list(&mut self, field: &str) -> Vec<String> {
+        match self.remove(field) {
+            Some(serde_json::Value::String(value)) if !value.is_empty() => vec![value],
+            Some(serde_json::Value::Array(values)) => values
+                .into_iter()
+                .filter_map(|value| match value {
+                    serde_json::Value::String(value) if !value.is_empty() => Some(value),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
 }
@@ -15,8 +15,11 @@ use std::time::Duration;
 
 use store::Store;
 
+use crate::core::provisioning::Provisioning;
+
 pub struct OpenIdDirectory {
     config: OpenIdConfig,
+    pub(crate) provisioning: Option<Provisioning>,
     pub(crate) data_store: Store,
 }
 
@@ -27,6 +30,9 @@ struct OpenIdConfig {
     pub email_field: String,
     pub username_field: Option<String>,
     pub full_name_field: Option<String>,
+    pub quota_field: Option<String>,
+    pub roles_field: Option<String>,
+    pub tenant_field: Option<String>,
 }
 
 #[derive(Debug)]
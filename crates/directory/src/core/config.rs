@@ -16,8 +16,8 @@ use ahash::AHashMap;
 
 use crate::{
     backend::{
-        imap::ImapDirectory, ldap::LdapDirectory, memory::MemoryDirectory, smtp::SmtpDirectory,
-        sql::SqlDirectory,
+        composite::CompositeDirectory, imap::ImapDirectory, ldap::LdapDirectory,
+        memory::MemoryDirectory, smtp::SmtpDirectory, sql::SqlDirectory,
     },
     Directories, Directory, DirectoryInner,
 };
@@ -32,6 +32,7 @@ impl Directories {
         is_enterprise: bool,
     ) -> Self {
         let mut directories = AHashMap::new();
+        let mut composite_ids = Vec::new();
 
         for id in config
             .sub_keys("directory", ".type")
@@ -84,6 +85,11 @@ impl Directories {
                 "memory" => MemoryDirectory::from_config(config, prefix, data_store.clone())
                     .await
                     .map(DirectoryInner::Memory),
+                "composite" => {
+                    // Composite directories are built once all their backends are available
+                    composite_ids.push(id.to_string());
+                    continue;
+                }
                 #[cfg(feature = "enterprise")]
                 "oidc" => crate::backend::oidc::OpenIdDirectory::from_config(
                     config,
@@ -118,6 +124,17 @@ impl Directories {
             }
         }
 
+        for id in composite_ids {
+            let prefix = ("directory", id.as_str());
+            if let Some(store) = CompositeDirectory::from_config(config, prefix, &directories) {
+                let directory = Arc::new(Directory {
+                    store: DirectoryInner::Composite(store),
+                    cache: CachedDirectory::try_from_config(config, prefix),
+                });
+                directories.insert(id, directory);
+            }
+        }
+
         Directories { directories }
     }
 }
@@ -4,10 +4,15 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use store::Store;
 use trc::AddContext;
 
 use crate::{
-    backend::internal::lookup::DirectoryStore, Directory, DirectoryInner, Principal, QueryBy,
+    backend::internal::{
+        lookup::DirectoryStore,
+        manage::{ManageDirectory, UpdatePrincipal},
+    },
+    Directory, DirectoryInner, Principal, QueryBy,
 };
 
 impl Directory {
@@ -23,6 +28,7 @@ impl Directory {
             DirectoryInner::Imap(store) => store.query(by).await,
             DirectoryInner::Smtp(store) => store.query(by).await,
             DirectoryInner::Memory(store) => store.query(by).await,
+            DirectoryInner::Composite(store) => store.query(by, return_member_of).await,
             #[cfg(feature = "enterprise")]
             DirectoryInner::OpenId(store) => store.query(by, return_member_of).await,
         }
@@ -37,6 +43,7 @@ impl Directory {
             DirectoryInner::Imap(store) => store.email_to_ids(email).await,
             DirectoryInner::Smtp(store) => store.email_to_ids(email).await,
             DirectoryInner::Memory(store) => store.email_to_ids(email).await,
+            DirectoryInner::Composite(store) => store.email_to_ids(email).await,
             #[cfg(feature = "enterprise")]
             DirectoryInner::OpenId(store) => store.email_to_ids(email).await,
         }
@@ -58,6 +65,7 @@ impl Directory {
             DirectoryInner::Imap(store) => store.is_local_domain(domain).await,
             DirectoryInner::Smtp(store) => store.is_local_domain(domain).await,
             DirectoryInner::Memory(store) => store.is_local_domain(domain).await,
+            DirectoryInner::Composite(store) => store.is_local_domain(domain).await,
             #[cfg(feature = "enterprise")]
             DirectoryInner::OpenId(store) => store.is_local_domain(domain).await,
         }
@@ -86,6 +94,7 @@ impl Directory {
             DirectoryInner::Imap(store) => store.rcpt(email).await,
             DirectoryInner::Smtp(store) => store.rcpt(email).await,
             DirectoryInner::Memory(store) => store.rcpt(email).await,
+            DirectoryInner::Composite(store) => store.rcpt(email).await,
             #[cfg(feature = "enterprise")]
             DirectoryInner::OpenId(store) => store.rcpt(email).await,
         }
@@ -107,6 +116,7 @@ impl Directory {
             DirectoryInner::Imap(store) => store.vrfy(address).await,
             DirectoryInner::Smtp(store) => store.vrfy(address).await,
             DirectoryInner::Memory(store) => store.vrfy(address).await,
+            DirectoryInner::Composite(store) => store.vrfy(address).await,
             #[cfg(feature = "enterprise")]
             DirectoryInner::OpenId(store) => store.vrfy(address).await,
         }
@@ -121,14 +131,62 @@ impl Directory {
             DirectoryInner::Imap(store) => store.expn(address).await,
             DirectoryInner::Smtp(store) => store.expn(address).await,
             DirectoryInner::Memory(store) => store.expn(address).await,
+            DirectoryInner::Composite(store) => store.expn(address).await,
             #[cfg(feature = "enterprise")]
             DirectoryInner::OpenId(store) => store.expn(address).await,
         }
         .caused_by(trc::location!())
     }
 
+    pub async fn update_principal(
+        &self,
+        data_store: &Store,
+        params: UpdatePrincipal<'_>,
+    ) -> trc::Result<()> {
+        match &self.store {
+            DirectoryInner::Ldap(store) if store.is_writable() => {
+                store.update_principal(params).await
+            }
+            _ => data_store.update_principal(params).await,
+        }
+        .caused_by(trc::location!())
+    }
+
+    pub fn is_writable(&self) -> bool {
+        match &self.store {
+            DirectoryInner::Internal(_) => true,
+            DirectoryInner::Ldap(store) => store.is_writable(),
+            DirectoryInner::Sql(_)
+            | DirectoryInner::Imap(_)
+            | DirectoryInner::Smtp(_)
+            | DirectoryInner::Memory(_)
+            | DirectoryInner::Composite(_) => false,
+            #[cfg(feature = "enterprise")]
+            DirectoryInner::OpenId(_) => false,
+        }
+    }
+
+    pub async fn deprovision(&self) -> trc::Result<Vec<u32>> {
+        let (provisioning, data_store) = match &self.store {
+            DirectoryInner::Ldap(store) => (store.provisioning.as_ref(), &store.data_store),
+            #[cfg(feature = "enterprise")]
+            DirectoryInner::OpenId(store) => (store.provisioning.as_ref(), &store.data_store),
+            _ => return Ok(Vec::new()),
+        };
+
+        if let Some(provisioning) = provisioning {
+            provisioning
+                .deprovision(data_store)
+                .await
+                .caused_by(trc::location!())
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
     pub fn has_bearer_token_support(&self) -> bool {
         match &self.store {
+            DirectoryInner::Composite(store) => store.has_bearer_token_support(),
             DirectoryInner::Internal(_)
             | DirectoryInner::Ldap(_)
             | DirectoryInner::Sql(_)
@@ -149,7 +207,8 @@ impl DirectoryInner {
             | DirectoryInner::Sql(_)
             | DirectoryInner::Imap(_)
             | DirectoryInner::Smtp(_)
-            | DirectoryInner::Memory(_) => false,
+            | DirectoryInner::Memory(_)
+            | DirectoryInner::Composite(_) => false,
             #[cfg(feature = "enterprise")]
             DirectoryInner::OpenId(_) => true,
         }
@@ -9,7 +9,9 @@ use crate::Permission;
 pub mod cache;
 pub mod config;
 pub mod dispatch;
+pub mod policy;
 pub mod principal;
+pub mod provisioning;
 pub mod secret;
 
 impl Permission {
@@ -142,6 +144,17 @@ impl Permission {
             Permission::JmapBlobLookup => "Look up blobs via JMAP",
             Permission::JmapBlobUpload => "Upload blobs via JMAP",
             Permission::JmapEcho => "Perform JMAP echo requests",
+            Permission::JmapMdnSend => "Send read receipts via JMAP",
+            Permission::JmapMdnParse => "Parse read receipts via JMAP",
+            Permission::JmapShareNotificationGet => "Retrieve share notifications via JMAP",
+            Permission::JmapShareNotificationSet => "Dismiss share notifications via JMAP",
+            Permission::JmapShareNotificationChanges => {
+                "Retrieve changes to share notifications via JMAP"
+            }
+            Permission::JmapShareNotificationQuery => "Query share notifications via JMAP",
+            Permission::JmapShareNotificationQueryChanges => {
+                "Track share notification query changes via JMAP"
+            }
             Permission::ImapAuthenticate => "Authenticate via IMAP",
             Permission::ImapAclGet => "Retrieve ACLs via IMAP",
             Permission::ImapAclSet => "Set ACLs via IMAP",
@@ -198,6 +211,11 @@ impl Permission {
             Permission::OauthClientUpdate => "Modify OAuth clients",
             Permission::OauthClientDelete => "Remove OAuth clients",
             Permission::AiModelInteract => "Interact with AI models",
+            Permission::Backup => "Create and list data store backups",
+            Permission::Restore => "Restore accounts from data store backups",
+            Permission::Migrate => "Migrate the data store to a different backend",
+            Permission::Fsck => "Check and repair data store consistency",
+            Permission::BlobUsage => "View blob storage usage and deduplication statistics",
         }
     }
 }
@@ -0,0 +1,256 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{io::ErrorKind, path::PathBuf, time::Duration};
+
+use sha1::{Digest, Sha1};
+use store::write::now;
+use utils::config::Config;
+
+use crate::{backend::internal::PrincipalField, Principal};
+
+use super::secret::{is_password_hash, verify_secret_hash};
+
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+    pub allow_hashed: bool,
+    pub history: usize,
+    pub max_age: Option<Duration>,
+    pub breached: Option<BreachedPasswords>,
+}
+
+/// Offline breached password lookups using k-anonymity range files, one file per
+/// 5-character SHA-1 prefix containing `SUFFIX:COUNT` lines.
+#[derive(Debug, Clone)]
+pub struct BreachedPasswords {
+    pub path: PathBuf,
+    pub min_count: u64,
+}
+
+impl PasswordPolicy {
+    pub fn parse(config: &mut Config) -> Option<Self> {
+        if !config
+            .property_or_default::<bool>("authentication.password-policy.enable", "false")
+            .unwrap_or_default()
+        {
+            return None;
+        }
+
+        let breached = config
+            .value("authentication.password-policy.breached.path")
+            .map(PathBuf::from)
+            .map(|path| BreachedPasswords { path, min_count: 1 });
+
+        Some(PasswordPolicy {
+            min_length: config
+                .property_or_default("authentication.password-policy.length.min", "8")
+                .unwrap_or(8),
+            max_length: config
+                .property_or_default("authentication.password-policy.length.max", "128")
+                .unwrap_or(128),
+            require_lowercase: config
+                .property_or_default("authentication.password-policy.require.lowercase", "false")
+                .unwrap_or_default(),
+            require_uppercase: config
+                .property_or_default("authentication.password-policy.require.uppercase", "false")
+                .unwrap_or_default(),
+            require_digit: config
+                .property_or_default("authentication.password-policy.require.digit", "false")
+                .unwrap_or_default(),
+            require_special: config
+                .property_or_default("authentication.password-policy.require.special", "false")
+                .unwrap_or_default(),
+            allow_hashed: config
+                .property_or_default("authentication.password-policy.allow-hashed", "false")
+                .unwrap_or_default(),
+            history: config
+                .property_or_default("authentication.password-policy.history", "0")
+                .unwrap_or_default(),
+            max_age: config.property("authentication.password-policy.max-age"),
+            breached: breached.map(|breached| BreachedPasswords {
+                min_count: config
+                    .property_or_default("authentication.password-policy.breached.min-count", "1")
+                    .unwrap_or(1),
+                ..breached
+            }),
+        })
+    }
+
+    /// Validates a new password, hashed passwords can only be checked for reuse and
+    /// are rejected unless explicitly allowed.
+    pub async fn validate(&self, password: &str) -> trc::Result<()> {
+        if is_password_hash(password) {
+            return if self.allow_hashed {
+                Ok(())
+            } else {
+                Err(violation("hashed", "Pre-hashed passwords are not allowed"))
+            };
+        }
+
+        let length = password.chars().count();
+        if length < self.min_length {
+            return Err(violation(
+                "minLength",
+                format!(
+                    "Password must be at least {} characters long",
+                    self.min_length
+                ),
+            ));
+        } else if length > self.max_length {
+            return Err(violation(
+                "maxLength",
+                format!(
+                    "Password must be at most {} characters long",
+                    self.max_length
+                ),
+            ));
+        }
+
+        for (required, class, details, matches) in [
+            (
+                self.require_lowercase,
+                "lowercase",
+                "Password must contain a lowercase letter",
+                char::is_lowercase as fn(char) -> bool,
+            ),
+            (
+                self.require_uppercase,
+                "uppercase",
+                "Password must contain an uppercase letter",
+                char::is_uppercase,
+            ),
+            (
+                self.require_digit,
+                "digit",
+                "Password must contain a digit",
+                |ch: char| ch.is_ascii_digit(),
+            ),
+            (
+                self.require_special,
+                "special",
+                "Password must contain a special character",
+                |ch: char| !ch.is_alphanumeric() && !ch.is_whitespace(),
+            ),
+        ] {
+            if required && !password.chars().any(matches) {
+                return Err(violation(class, details));
+            }
+        }
+
+        if let Some(breached) = &self.breached {
+            if breached.is_breached(password).await? {
+                return Err(violation(
+                    "breached",
+                    "Password has appeared in a data breach",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rejects passwords matching the replaced passwords or any of the previous ones
+    /// kept in the password history.
+    pub async fn validate_reuse(
+        &self,
+        principal: &Principal,
+        replaced: &[String],
+        password: &str,
+    ) -> trc::Result<()> {
+        if self.history == 0 {
+            return Ok(());
+        }
+
+        for hash in replaced
+            .iter()
+            .chain(principal.iter_str(PrincipalField::PasswordHistory))
+        {
+            if hash == password
+                || (!is_password_hash(password) && verify_secret_hash(hash, password).await?)
+            {
+                return Err(violation(
+                    "history",
+                    format!(
+                        "Password must not match any of the last {} passwords",
+                        self.history
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the replaced passwords to the history, keeping only the configured
+    /// number of entries.
+    pub fn update_history(&self, principal: &mut Principal, replaced: Vec<String>) {
+        if self.history == 0 {
+            principal.remove(PrincipalField::PasswordHistory);
+            return;
+        }
+
+        let mut history = replaced;
+        history.extend(
+            principal
+                .take_str_array(PrincipalField::PasswordHistory)
+                .unwrap_or_default(),
+        );
+        history.truncate(self.history);
+        principal.set(PrincipalField::PasswordHistory, history);
+    }
+
+    /// Returns whether the password must be changed before the account can be used,
+    /// either because it expired or because an administrator requested a change.
+    pub fn is_expired(&self, principal: &Principal) -> bool {
+        match principal.get_int(PrincipalField::PasswordChanged) {
+            Some(0) => true,
+            Some(changed) => self
+                .max_age
+                .map_or(false, |max_age| changed + max_age.as_secs() < now()),
+            None => false,
+        }
+    }
+}
+
+impl BreachedPasswords {
+    pub async fn is_breached(&self, password: &str) -> trc::Result<bool> {
+        let hash = Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<String>();
+        let (prefix, suffix) = hash.split_at(5);
+
+        match tokio::fs::read_to_string(self.path.join(format!("{prefix}.txt"))).await {
+            Ok(contents) => {
+                for line in contents.lines() {
+                    if let Some((line_suffix, count)) = line.split_once(':') {
+                        if line_suffix.trim().eq_ignore_ascii_case(suffix) {
+                            return Ok(count.trim().parse::<u64>().unwrap_or(1) >= self.min_count);
+                        }
+                    }
+                }
+                Ok(false)
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(trc::EventType::Resource(trc::ResourceEvent::Error)
+                .reason(err)
+                .details("Failed to read breached password range file")
+                .ctx(trc::Key::Path, self.path.to_string_lossy().into_owned())),
+        }
+    }
+}
+
+pub fn violation(rule: &'static str, details: impl Into<trc::Value>) -> trc::Error {
+    trc::ManageEvent::PasswordPolicy
+        .ctx(trc::Key::Key, rule)
+        .ctx(trc::Key::Details, details)
+}
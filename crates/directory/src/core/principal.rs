@@ -854,7 +854,11 @@ impl<'de> serde::Deserialize<'de> for Principal {
                                 }
                             }
                         },
-                        PrincipalField::UsedQuota => {
+                        PrincipalField::UsedQuota
+                        | PrincipalField::LastLogin
+                        | PrincipalField::PasswordHistory
+                        | PrincipalField::PasswordChanged
+                        | PrincipalField::ProvisionedBy => {
                             // consume and ignore
                             map.next_value::<IgnoredAny>()?;
                             continue;
@@ -1042,6 +1046,13 @@ impl Permission {
                 | Permission::JmapBlobLookup
                 | Permission::JmapBlobUpload
                 | Permission::JmapEcho
+                | Permission::JmapMdnSend
+                | Permission::JmapMdnParse
+                | Permission::JmapShareNotificationGet
+                | Permission::JmapShareNotificationSet
+                | Permission::JmapShareNotificationChanges
+                | Permission::JmapShareNotificationQuery
+                | Permission::JmapShareNotificationQueryChanges
                 | Permission::ImapAuthenticate
                 | Permission::ImapAclGet
                 | Permission::ImapAclSet
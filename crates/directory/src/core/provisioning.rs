@@ -0,0 +1,328 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use ahash::AHashMap;
+use store::{write::now, Store};
+use trc::AddContext;
+use utils::config::{utils::AsKey, Config};
+
+use crate::{
+    backend::internal::{
+        manage::{self, ManageDirectory, UpdatePrincipal},
+        PrincipalField, PrincipalUpdate, PrincipalValue,
+    },
+    Permission, Principal, Type, ROLE_USER,
+};
+
+// Last login times are only written back once per interval
+const LAST_LOGIN_RESOLUTION: u64 = 3600;
+
+#[derive(Debug, Default)]
+pub struct Provisioning {
+    directory_id: String,
+    roles: AHashMap<String, Vec<String>>,
+    #[cfg(feature = "enterprise")]
+    default_tenant: Option<String>,
+    deprovision_after: Option<Duration>,
+}
+
+impl Provisioning {
+    pub fn from_config(config: &mut Config, prefix: impl AsKey) -> Option<Self> {
+        let prefix = prefix.as_key();
+        if !config
+            .property_or_default::<bool>((&prefix, "provisioning.enable"), "false")
+            .unwrap_or_default()
+        {
+            return None;
+        }
+
+        // External role values to internal roles
+        let mut roles = AHashMap::new();
+        for rule_id in config
+            .sub_keys((&prefix, "provisioning.role"), ".value")
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+        {
+            let rule_id = rule_id.as_str();
+            if let Some(value) =
+                config.value_require((prefix.as_str(), "provisioning.role", rule_id, "value"))
+            {
+                let value = value.to_lowercase();
+                let internal = config
+                    .values((prefix.as_str(), "provisioning.role", rule_id, "roles"))
+                    .map(|(_, v)| v.to_string())
+                    .collect::<Vec<_>>();
+                roles.entry(value).or_insert_with(Vec::new).extend(internal);
+            }
+        }
+
+        Some(Provisioning {
+            directory_id: prefix
+                .strip_prefix("directory.")
+                .unwrap_or(&prefix)
+                .to_string(),
+            roles,
+            #[cfg(feature = "enterprise")]
+            default_tenant: config
+                .value((&prefix, "provisioning.tenant.default"))
+                .map(|v| v.to_string()),
+            deprovision_after: config.property((&prefix, "provisioning.deprovision.after")),
+        })
+    }
+
+    /// Builds the updates required to bring the stored principal in line with
+    /// the roles, tenant and last login time obtained from the external directory.
+    /// Role and tenant names are taken out of the external principal, the remaining
+    /// fields are synchronized by `Principal::update_external`. A successful login
+    /// re-enables accounts that were deprovisioned, as the external directory is
+    /// the source of truth for the account status.
+    pub(crate) async fn apply(
+        &self,
+        store: &Store,
+        principal: &mut Principal,
+        external: &mut Principal,
+        is_login: bool,
+    ) -> trc::Result<Vec<PrincipalUpdate>> {
+        let mut updates = Vec::new();
+
+        // Record the directory that owns the account
+        if principal.get_str(PrincipalField::ProvisionedBy) != Some(self.directory_id.as_str()) {
+            updates.push(PrincipalUpdate::set(
+                PrincipalField::ProvisionedBy,
+                PrincipalValue::String(self.directory_id.clone()),
+            ));
+            principal.set(PrincipalField::ProvisionedBy, self.directory_id.clone());
+        }
+
+        // Map external roles, falling back to the ones assigned by the backend
+        let mut role_ids = Vec::new();
+        match external.take(PrincipalField::Roles) {
+            Some(PrincipalValue::String(value)) => {
+                self.map_roles(store, &[value], &mut role_ids).await?;
+            }
+            Some(PrincipalValue::StringList(values)) => {
+                self.map_roles(store, &values, &mut role_ids).await?;
+            }
+            Some(value) => {
+                role_ids.extend(value.into_int_array());
+            }
+            None => {}
+        }
+        if role_ids.is_empty() {
+            role_ids.push(ROLE_USER as u64);
+        }
+        self.sync_roles(store, principal, role_ids, &mut updates)
+            .await?;
+
+        // SPDX-SnippetBegin
+        // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+        // SPDX-License-Identifier: LicenseRef-SEL
+        #[cfg(feature = "enterprise")]
+        if let Some(tenant) = external
+            .take_str(PrincipalField::Tenant)
+            .filter(|t| !t.is_empty())
+            .or_else(|| self.default_tenant.clone())
+        {
+            match store
+                .get_principal_info(&tenant)
+                .await
+                .caused_by(trc::location!())?
+                .filter(|info| info.typ == Type::Tenant)
+            {
+                Some(info) if principal.tenant() != Some(info.id) => {
+                    updates.push(PrincipalUpdate::set(
+                        PrincipalField::Tenant,
+                        PrincipalValue::String(tenant),
+                    ));
+                    principal.set(PrincipalField::Tenant, info.id);
+                }
+                Some(_) => {}
+                None => {
+                    trc::error!(manage::not_found(tenant)
+                        .details("Provisioned tenant does not exist")
+                        .caused_by(trc::location!()));
+                }
+            }
+        }
+        // SPDX-SnippetEnd
+
+        // Record the login time used for deprovisioning inactive accounts
+        if is_login {
+            let now = now();
+            if principal
+                .get_int(PrincipalField::LastLogin)
+                .map_or(true, |last_login| now >= last_login + LAST_LOGIN_RESOLUTION)
+            {
+                updates.push(PrincipalUpdate::set(
+                    PrincipalField::LastLogin,
+                    PrincipalValue::Integer(now),
+                ));
+                principal.set(PrincipalField::LastLogin, now);
+            }
+
+            // Re-enable deprovisioned accounts
+            let authenticate = Permission::Authenticate.id() as u64;
+            if principal.has_int_value(PrincipalField::DisabledPermissions, authenticate) {
+                updates.push(PrincipalUpdate::remove_item(
+                    PrincipalField::DisabledPermissions,
+                    PrincipalValue::String(Permission::Authenticate.name().to_string()),
+                ));
+                principal.retain_int(PrincipalField::DisabledPermissions, |v| *v != authenticate);
+            }
+        }
+
+        Ok(updates)
+    }
+
+    async fn map_roles(
+        &self,
+        store: &Store,
+        values: &[String],
+        role_ids: &mut Vec<u64>,
+    ) -> trc::Result<()> {
+        let roles = values
+            .iter()
+            .filter_map(|value| self.roles.get(&value.to_lowercase()))
+            .flatten()
+            .collect::<Vec<_>>();
+        for role in roles {
+            let role_id = if let Some(role_id) = PrincipalField::Roles.map_internal_role_name(role)
+            {
+                role_id
+            } else if let Some(info) = store
+                .get_principal_info(role)
+                .await
+                .caused_by(trc::location!())?
+                .filter(|info| info.typ == Type::Role)
+            {
+                info.id
+            } else {
+                trc::error!(manage::not_found(role.clone())
+                    .details("Provisioned role does not exist")
+                    .caused_by(trc::location!()));
+                continue;
+            };
+
+            if !role_ids.contains(&(role_id as u64)) {
+                role_ids.push(role_id as u64);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn sync_roles(
+        &self,
+        store: &Store,
+        principal: &mut Principal,
+        role_ids: Vec<u64>,
+        updates: &mut Vec<PrincipalUpdate>,
+    ) -> trc::Result<()> {
+        let current = store
+            .get_member_of(principal.id())
+            .await
+            .caused_by(trc::location!())?
+            .into_iter()
+            .filter(|member| member.typ == Type::Role)
+            .map(|member| member.principal_id as u64)
+            .collect::<Vec<_>>();
+
+        let added = role_ids
+            .iter()
+            .filter(|id| !current.contains(id))
+            .copied()
+            .collect::<Vec<_>>();
+        let removed = current
+            .iter()
+            .filter(|id| !role_ids.contains(id))
+            .copied()
+            .collect::<Vec<_>>();
+
+        // Role updates are expressed by name
+        for (ids, is_add) in [(added, true), (removed, false)] {
+            if ids.is_empty() {
+                continue;
+            }
+
+            let mut names = Principal::new(principal.id(), Type::Individual)
+                .with_field(PrincipalField::Roles, ids);
+            store
+                .map_field_ids(&mut names, &[PrincipalField::Roles])
+                .await
+                .caused_by(trc::location!())?;
+            for name in names
+                .take_str_array(PrincipalField::Roles)
+                .unwrap_or_default()
+            {
+                let value = PrincipalValue::String(name);
+                updates.push(if is_add {
+                    PrincipalUpdate::add_item(PrincipalField::Roles, value)
+                } else {
+                    PrincipalUpdate::remove_item(PrincipalField::Roles, value)
+                });
+            }
+        }
+
+        principal.set(PrincipalField::Roles, role_ids);
+
+        Ok(())
+    }
+
+    /// Disables the accounts provisioned by this directory that have not logged in
+    /// during the configured period, returning their ids. Accounts provisioned before
+    /// their origin was recorded are only considered after their next login.
+    pub(crate) async fn deprovision(&self, store: &Store) -> trc::Result<Vec<u32>> {
+        let cutoff = if let Some(after) = self.deprovision_after {
+            now().saturating_sub(after.as_secs())
+        } else {
+            return Ok(Vec::new());
+        };
+
+        let authenticate = Permission::Authenticate.id() as u64;
+        let mut account_ids = Vec::new();
+        for account in store
+            .list_principals(
+                None,
+                None,
+                &[Type::Individual],
+                &[PrincipalField::Name],
+                0,
+                0,
+            )
+            .await
+            .caused_by(trc::location!())?
+            .items
+        {
+            // Only accounts that were provisioned by this directory are deprovisioned
+            if let Some(principal) = store
+                .get_principal(account.id())
+                .await
+                .caused_by(trc::location!())?
+                .filter(|p| {
+                    p.get_str(PrincipalField::ProvisionedBy) == Some(self.directory_id.as_str())
+                        && p.get_int(PrincipalField::LastLogin)
+                            .map_or(false, |last_login| last_login < cutoff)
+                        && !p.has_int_value(PrincipalField::DisabledPermissions, authenticate)
+                })
+            {
+                store
+                    .update_principal(UpdatePrincipal::by_id(principal.id()).with_updates(vec![
+                        PrincipalUpdate::add_item(
+                            PrincipalField::DisabledPermissions,
+                            PrincipalValue::String(Permission::Authenticate.name().to_string()),
+                        ),
+                    ]))
+                    .await
+                    .caused_by(trc::location!())?;
+                account_ids.push(principal.id());
+            }
+        }
+
+        Ok(account_ids)
+    }
+}
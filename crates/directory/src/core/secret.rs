@@ -268,3 +268,100 @@ pub async fn verify_secret_hash(hashed_secret: &str, secret: &str) -> trc::Resul
         Ok(hashed_secret == secret)
     }
 }
+
+/// Returns whether a secret is a well-formed hash in one of the formats supported by
+/// `verify_secret_hash`, secrets that merely start with a hash prefix are not hashes.
+pub fn is_password_hash(secret: &str) -> bool {
+    if let Some(secret) = secret.strip_prefix('{') {
+        if let Some((algo, hashed_secret)) = secret.split_once('}') {
+            let decoded_len = || {
+                base64_decode(hashed_secret.as_bytes())
+                    .map(|decoded| decoded.len())
+                    .unwrap_or_default()
+            };
+
+            match algo {
+                "ARGON2" | "ARGON2I" | "ARGON2ID" | "PBKDF2" => is_phc_hash(hashed_secret),
+                "SHA" => decoded_len() == 20,
+                "SSHA" => decoded_len() > 20,
+                "SHA256" => decoded_len() == 32,
+                "SSHA256" => decoded_len() > 32,
+                "SHA512" => decoded_len() == 64,
+                "SSHA512" => decoded_len() > 64,
+                "MD5" => decoded_len() == 16,
+                "CRYPT" | "crypt" => {
+                    is_crypt_hash(hashed_secret)
+                        || (hashed_secret.len() == 13 && is_crypt_base64(hashed_secret))
+                }
+                _ => false,
+            }
+        } else {
+            false
+        }
+    } else {
+        is_crypt_hash(secret)
+    }
+}
+
+fn is_crypt_hash(hashed_secret: &str) -> bool {
+    if let Some(hash) = hashed_secret.strip_prefix('_') {
+        // Enhanced DES-based hash
+        return hash.len() == 19 && is_crypt_base64(hash);
+    } else if !hashed_secret.starts_with('$') {
+        return false;
+    } else if hashed_secret.starts_with("$argon2")
+        || hashed_secret.starts_with("$pbkdf2")
+        || hashed_secret.starts_with("$scrypt")
+    {
+        return is_phc_hash(hashed_secret);
+    }
+
+    let fields = hashed_secret.split('$').skip(1).collect::<Vec<_>>();
+    match fields.as_slice() {
+        ["2a" | "2b" | "2x" | "2y", cost, hash] => {
+            // Blowfish crypt
+            cost.len() == 2
+                && cost.bytes().all(|ch| ch.is_ascii_digit())
+                && hash.len() == 53
+                && is_crypt_base64(hash)
+        }
+        ["1", salt, hash] => {
+            // MD5 based hash
+            (1..=8).contains(&salt.len()) && hash.len() == 22 && is_crypt_base64(hash)
+        }
+        ["5" | "6", params @ .., salt, hash] => {
+            // SHA-256 and SHA-512 crypt
+            let hash_len = if fields[0] == "5" { 43 } else { 86 };
+            let valid_params = match params {
+                [] => true,
+                [rounds] => rounds
+                    .strip_prefix("rounds=")
+                    .is_some_and(|rounds| rounds.parse::<u32>().is_ok()),
+                _ => false,
+            };
+            valid_params
+                && (1..=16).contains(&salt.len())
+                && hash.len() == hash_len
+                && is_crypt_base64(hash)
+        }
+        ["sha1", rounds, salt, hash] => {
+            // SHA-1 crypt
+            rounds.parse::<u32>().is_ok()
+                && (1..=64).contains(&salt.len())
+                && hash.len() == 28
+                && is_crypt_base64(hash)
+        }
+        _ => false,
+    }
+}
+
+fn is_phc_hash(hashed_secret: &str) -> bool {
+    PasswordHash::new(hashed_secret).is_ok_and(|hash| hash.hash.is_some())
+}
+
+fn is_crypt_base64(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == b'.' || ch == b'/')
+}
@@ -9,6 +9,7 @@ use std::{fmt::Debug, sync::Arc};
 
 use ahash::AHashMap;
 use backend::{
+    composite::CompositeDirectory,
     imap::{ImapDirectory, ImapError},
     internal::{PrincipalField, PrincipalValue},
     ldap::LdapDirectory,
@@ -264,6 +265,18 @@ pub enum Permission {
     OauthClientOverride,
 
     AiModelInteract,
+    Backup,
+    Restore,
+    Migrate,
+    Fsck,
+    BlobUsage,
+    JmapMdnSend,
+    JmapMdnParse,
+    JmapShareNotificationGet,
+    JmapShareNotificationSet,
+    JmapShareNotificationChanges,
+    JmapShareNotificationQuery,
+    JmapShareNotificationQueryChanges,
     // WARNING: add new ids at the end (TODO: use static ids)
 }
 
@@ -284,8 +297,10 @@ pub enum DirectoryInner {
     Imap(ImapDirectory),
     Smtp(SmtpDirectory),
     Memory(MemoryDirectory),
+    Composite(CompositeDirectory),
 }
 
+#[derive(Clone, Copy)]
 pub enum QueryBy<'x> {
     Name(&'x str),
     Id(u32),
@@ -27,6 +27,7 @@ pub mod thread;
 use std::{borrow::Cow, str::FromStr};
 
 use chrono::{DateTime, NaiveDate};
+use jmap_proto::types::keyword::{MUTED_KEYWORD, SNOOZED_KEYWORD};
 
 use crate::{
     protocol::{Flag, Sequence},
@@ -128,6 +129,10 @@ impl Flag {
                         Flag::Phishing
                     } else if value.eq_ignore_ascii_case(b"$Important") {
                         Flag::Important
+                    } else if value.eq_ignore_ascii_case(b"$Snoozed") {
+                        Flag::Keyword(SNOOZED_KEYWORD.to_string())
+                    } else if value.eq_ignore_ascii_case(b"$Muted") {
+                        Flag::Keyword(MUTED_KEYWORD.to_string())
                     } else {
                         Flag::Keyword(
                             String::from_utf8(value).map_err(|_| Cow::from("Invalid UTF-8."))?,
@@ -100,6 +100,7 @@ pub fn parse_filters(
     let mut filters_len = 0;
     let mut filters_stack = Vec::new();
     let mut operator = Filter::And;
+    let mut is_fuzzy = false;
 
     while let Some(token) = tokens.next() {
         let mut found_parenthesis = false;
@@ -312,6 +313,9 @@ pub fn parse_filters(
                             .ok_or_else(|| Cow::from("Expected an THREADID value."))?
                             .unwrap_string()?,
                     ));
+                } else if value.eq_ignore_ascii_case(b"FUZZY") {
+                    is_fuzzy = true;
+                    continue;
                 } else if value.eq_ignore_ascii_case(b"OR") {
                     if filters_stack.len() > 10 {
                         return Err(Cow::from("Too many nested filters"));
@@ -336,6 +340,14 @@ pub fn parse_filters(
                     filters.push(Filter::Sequence(parse_sequence_set(&value)?, false));
                 }
 
+                // FUZZY has no effect on non-text search keys
+                if is_fuzzy {
+                    is_fuzzy = false;
+                    if let Some(filter) = filters.pop() {
+                        filters.push(filter.into_fuzzy());
+                    }
+                }
+
                 filters_len += 1;
             }
             Token::ParenthesisOpen => {
@@ -419,6 +431,8 @@ impl ResultOption {
             Ok(Self::Save)
         } else if value.eq_ignore_ascii_case(b"context") {
             Ok(Self::Context)
+        } else if value.eq_ignore_ascii_case(b"relevancy") {
+            Ok(Self::Relevancy)
         } else {
             Err(format!("Invalid result option {:?}", String::from_utf8_lossy(value)).into())
         }
@@ -731,6 +745,19 @@ mod tests {
                     sort: None,
                 },
             ),
+            (
+                b"a SEARCH RETURN (RELEVANCY ALL) FUZZY TEXT \"Helo\" FUZZY SEEN\r\n".to_vec(),
+                search::Arguments {
+                    tag: "a".to_string(),
+                    result_options: vec![ResultOption::Relevancy, ResultOption::All],
+                    filter: vec![
+                        Filter::Fuzzy(Box::new(Filter::Text("Helo".to_string()))),
+                        Filter::Seen,
+                    ],
+                    is_esearch: true,
+                    sort: None,
+                },
+            ),
             (
                 b"5 UID SEARCH BEFORE 1-Dec-2023\r\n".to_vec(),
                 search::Arguments {
@@ -110,6 +110,8 @@ impl Sort {
             Ok(Self::DisplayFrom)
         } else if value.eq_ignore_ascii_case(b"DISPLAYTO") {
             Ok(Self::DisplayTo)
+        } else if value.eq_ignore_ascii_case(b"RELEVANCY") {
+            Ok(Self::Relevancy)
         } else {
             Err(format!("Invalid sort criteria {:?}", String::from_utf8_lossy(value)).into())
         }
@@ -166,6 +168,22 @@ mod tests {
                     tag: "A283".to_string(),
                 },
             ),
+            (
+                b"A285 SORT (REVERSE RELEVANCY) UTF-8 FUZZY SUBJECT ravioli\r\n".to_vec(),
+                Arguments {
+                    sort: vec![Comparator {
+                        sort: Sort::Relevancy,
+                        ascending: false,
+                    }]
+                    .into(),
+                    filter: vec![Filter::Fuzzy(Box::new(Filter::Subject(
+                        "ravioli".to_string(),
+                    )))],
+                    result_options: Vec::new(),
+                    is_esearch: false,
+                    tag: "A285".to_string(),
+                },
+            ),
             (
                 b"A284 SORT (SUBJECT) US-ASCII TEXT \"not in mailbox\"\r\n".to_vec(),
                 Arguments {
@@ -31,6 +31,7 @@ pub enum Capability {
     Within,
     Enable,
     SearchRes,
+    SearchFuzzy, //SEARCH=FUZZY
     Sort,
     Thread,       //THREAD=REFERENCES
     ListExtended, //LIST-EXTENDED
@@ -84,6 +85,7 @@ impl Capability {
             Capability::Within => b"WITHIN",
             Capability::Enable => b"ENABLE",
             Capability::SearchRes => b"SEARCHRES",
+            Capability::SearchFuzzy => b"SEARCH=FUZZY",
             Capability::Sort => b"SORT",
             Capability::Thread => b"THREAD=REFERENCES",
             Capability::ListExtended => b"LIST-EXTENDED",
@@ -121,6 +123,7 @@ impl Capability {
                 Capability::ESearch,
                 Capability::Within,
                 Capability::SearchRes,
+                Capability::SearchFuzzy,
                 Capability::Sort,
                 Capability::Thread,
                 Capability::ListExtended,
@@ -28,6 +28,7 @@ pub enum Sort {
     Subject,
     To,
     DisplayTo,
+    Relevancy,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -46,6 +47,7 @@ pub struct Response {
     pub max: Option<u32>,
     pub count: Option<u32>,
     pub highest_modseq: Option<u64>,
+    pub relevancy: Option<Vec<u32>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -56,6 +58,7 @@ pub enum ResultOption {
     Count,
     Save,
     Context,
+    Relevancy,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -112,6 +115,9 @@ pub enum Filter {
     // RFC 8474 - ObjectID
     EmailId(String),
     ThreadId(String),
+
+    // RFC 6203 - SEARCH=FUZZY
+    Fuzzy(Box<Filter>),
 }
 
 impl FilterItem for Filter {
@@ -124,7 +130,8 @@ impl FilterItem for Filter {
             | Filter::Subject(_)
             | Filter::Body(_)
             | Filter::Text(_)
-            | Filter::Header(_, _) => FilterType::Fts,
+            | Filter::Header(_, _)
+            | Filter::Fuzzy(_) => FilterType::Fts,
             Filter::And => FilterType::And,
             Filter::Or => FilterType::Or,
             Filter::Not => FilterType::Not,
@@ -162,6 +169,20 @@ impl Filter {
     pub fn seq_range(start: Option<u32>, end: Option<u32>) -> Filter {
         Filter::Sequence(Sequence::Range { start, end }, false)
     }
+
+    pub fn into_fuzzy(self) -> Filter {
+        match self {
+            Filter::From(_)
+            | Filter::To(_)
+            | Filter::Cc(_)
+            | Filter::Bcc(_)
+            | Filter::Subject(_)
+            | Filter::Body(_)
+            | Filter::Text(_)
+            | Filter::Header(_, _) => Filter::Fuzzy(Box::new(self)),
+            filter => filter,
+        }
+    }
 }
 
 impl Response {
@@ -190,6 +211,16 @@ impl Response {
                 buf.extend_from_slice(b" ALL ");
                 serialize_sequence(&mut buf, &self.ids);
             }
+            if let Some(relevancy) = &self.relevancy {
+                buf.extend_from_slice(b" RELEVANCY (");
+                for (pos, score) in relevancy.iter().enumerate() {
+                    if pos > 0 {
+                        buf.push(b' ');
+                    }
+                    buf.extend_from_slice(score.to_string().as_bytes());
+                }
+                buf.push(b')');
+            }
             if let Some(highest_modseq) = self.highest_modseq {
                 buf.extend_from_slice(b" MODSEQ ");
                 buf.extend_from_slice(highest_modseq.to_string().as_bytes());
@@ -233,6 +264,7 @@ mod tests {
                     max: 11.into(),
                     count: 3.into(),
                     highest_modseq: None,
+                    relevancy: None,
                 },
                 "A283",
                 concat!("* ESEARCH (TAG \"A283\") COUNT 3 MIN 2 MAX 11 ALL 2,10:11\r\n",),
@@ -250,6 +282,7 @@ mod tests {
                     max: None,
                     count: None,
                     highest_modseq: None,
+                    relevancy: None,
                 },
                 "A283",
                 concat!("* ESEARCH (TAG \"A283\") ALL 1:3,5,10:13,90,92:99\r\n",),
@@ -265,6 +298,7 @@ mod tests {
                     max: None,
                     count: None,
                     highest_modseq: None,
+                    relevancy: None,
                 },
                 "A283",
                 concat!("* ESEARCH (TAG \"A283\")\r\n",),
@@ -280,11 +314,28 @@ mod tests {
                     max: None,
                     count: None,
                     highest_modseq: 12345.into(),
+                    relevancy: None,
                 },
                 "A283",
                 concat!("* ESEARCH (TAG \"A283\") ALL 10:13,21 MODSEQ 12345\r\n",),
                 concat!("* SEARCH 10 11 12 13 21 (MODSEQ 12345)\r\n",),
             ),
+            (
+                super::Response {
+                    is_uid: true,
+                    is_esearch: true,
+                    is_sort: false,
+                    ids: vec![4, 7, 8],
+                    min: None,
+                    max: None,
+                    count: None,
+                    highest_modseq: None,
+                    relevancy: vec![100, 42, 7].into(),
+                },
+                "A284",
+                concat!("* ESEARCH (TAG \"A284\") UID ALL 4,7:8 RELEVANCY (100 42 7)\r\n",),
+                concat!("* SEARCH 4 7 8\r\n",),
+            ),
         ] {
             let response_v2 = String::from_utf8(response.clone().serialize(tag)).unwrap();
             response.is_esearch = false;
@@ -11,7 +11,7 @@ use std::{
 
 use common::{
     auth::AccessToken,
-    listener::{limiter::InFlight, ServerInstance, SessionStream},
+    listener::{limiter::InFlight, tls::ClientCertificate, ServerInstance, SessionStream},
     Account, ImapId, Inner, MailboxId, MailboxState, Server,
 };
 use imap_proto::{protocol::ProtocolVersion, receiver::Receiver, Command};
@@ -44,6 +44,7 @@ pub struct Session<T: SessionStream> {
     pub version: ProtocolVersion,
     pub state: State<T>,
     pub is_tls: bool,
+    pub client_cert: Option<ClientCertificate>,
     pub is_condstore: bool,
     pub is_qresync: bool,
     pub stream_rx: ReadHalf<T>,
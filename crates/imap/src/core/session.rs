@@ -139,6 +139,7 @@ impl<T: SessionStream> Session<T> {
         let _ = session.stream.flush().await;
 
         // Split stream into read and write halves
+        let client_cert = session.stream.client_certificate();
         let (stream_rx, stream_tx) = tokio::io::split(session.stream);
         let server = manager.inner.build_server();
 
@@ -147,6 +148,7 @@ impl<T: SessionStream> Session<T> {
             version: ProtocolVersion::Rev1,
             state: State::NotAuthenticated { auth_failures: 0 },
             is_tls,
+            client_cert,
             is_condstore: false,
             is_qresync: false,
             server,
@@ -192,8 +194,9 @@ impl<T: SessionStream> Session<T> {
         };
 
         // Upgrade to TLS
-        let (stream_rx, stream_tx) =
-            tokio::io::split(self.instance.tls_accept(stream, self.session_id).await?);
+        let stream = self.instance.tls_accept(stream, self.session_id).await?;
+        let client_cert = stream.client_certificate();
+        let (stream_rx, stream_tx) = tokio::io::split(stream);
         let stream_tx = Arc::new(tokio::sync::Mutex::new(stream_tx));
 
         Ok(Session {
@@ -203,6 +206,7 @@ impl<T: SessionStream> Session<T> {
             version: self.version,
             state: state.try_replace_stream_tx(stream_tx.clone()).unwrap(),
             is_tls: true,
+            client_cert,
             is_condstore: self.is_condstore,
             is_qresync: self.is_qresync,
             session_id: self.session_id,
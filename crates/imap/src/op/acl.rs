@@ -18,7 +18,7 @@ use imap_proto::{
 
 use jmap::{
     auth::acl::EffectiveAcl, changes::write::ChangeLog, mailbox::set::SCHEMA,
-    services::state::StateManager, JmapMethods,
+    services::state::StateManager, share_notification::notify::ShareNotify, JmapMethods,
 };
 use jmap_proto::{
     object::{index::ObjectIndexBuilder, Object},
@@ -159,46 +159,9 @@ impl<T: SessionStream> Session<T> {
                 .await
                 .imap_ctx(&arguments.tag, trc::location!())?;
             let rights = if access_token.is_shared(mailbox.account_id) {
-                let acl = values.inner.effective_acl(&access_token);
-                let mut rights = Vec::with_capacity(5);
-                if acl.contains(Acl::ReadItems) {
-                    rights.push(Rights::Read);
-                    rights.push(Rights::Lookup);
-                }
-                if acl.contains(Acl::AddItems) {
-                    rights.push(Rights::Insert);
-                }
-                if acl.contains(Acl::RemoveItems) {
-                    rights.push(Rights::DeleteMessages);
-                    rights.push(Rights::Expunge);
-                }
-                if acl.contains(Acl::ModifyItems) {
-                    rights.push(Rights::Seen);
-                    rights.push(Rights::Write);
-                }
-                if acl.contains(Acl::CreateChild) {
-                    rights.push(Rights::CreateMailbox);
-                }
-                if acl.contains(Acl::Delete) {
-                    rights.push(Rights::DeleteMailbox);
-                }
-                if acl.contains(Acl::Submit) {
-                    rights.push(Rights::Post);
-                }
-                rights
+                my_rights(&values.inner.effective_acl(&access_token))
             } else {
-                vec![
-                    Rights::Read,
-                    Rights::Lookup,
-                    Rights::Insert,
-                    Rights::DeleteMessages,
-                    Rights::Expunge,
-                    Rights::Seen,
-                    Rights::Write,
-                    Rights::CreateMailbox,
-                    Rights::DeleteMailbox,
-                    Rights::Post,
-                ]
+                my_rights(&Bitmap::all())
             };
 
             trc::event!(
@@ -240,7 +203,7 @@ impl<T: SessionStream> Session<T> {
 
         spawn_op!(data, {
             // Validate mailbox
-            let (mailbox, values, _) = data
+            let (mailbox, values, access_token) = data
                 .get_acl_mailbox(&arguments, false)
                 .await
                 .imap_ctx(&arguments.tag, trc::location!())?;
@@ -264,6 +227,13 @@ impl<T: SessionStream> Session<T> {
                 .id();
 
             // Prepare changes
+            let current_acl = values
+                .inner
+                .properties
+                .get(&Property::Acl)
+                .and_then(|v| v.as_acl())
+                .cloned()
+                .unwrap_or_default();
             let mut changes = Object::with_capacity(1);
             let (op, rights) = arguments
                 .mod_rights
@@ -334,6 +304,13 @@ impl<T: SessionStream> Session<T> {
                 .iter()
                 .map(|r| trc::Value::from(r.account_id))
                 .collect::<Vec<_>>();
+            let new_acl = acl.clone();
+            let mailbox_name = values
+                .inner
+                .get(&Property::Name)
+                .as_string()
+                .unwrap_or_default()
+                .to_string();
 
             // Write changes
             let mailbox_id = mailbox.mailbox_id;
@@ -365,6 +342,18 @@ impl<T: SessionStream> Session<T> {
                             .with_change(DataType::Mailbox, change_id),
                     )
                     .await;
+
+                // Notify sharees
+                data.server
+                    .notify_share_changes(
+                        access_token.primary_id(),
+                        mailbox.account_id,
+                        mailbox_id,
+                        &mailbox_name,
+                        &current_acl,
+                        &new_acl,
+                    )
+                    .await;
             }
 
             // Invalidate ACLs
@@ -483,3 +472,39 @@ impl<T: SessionStream> SessionData<T> {
         }
     }
 }
+
+/// Maps ACL grants to IMAP rights, using the same mapping as GETACL
+/// so that MYRIGHTS and JMAP myRights report the same permissions.
+fn my_rights(acl: &Bitmap<Acl>) -> Vec<Rights> {
+    let mut rights = Vec::with_capacity(11);
+    if acl.contains(Acl::ReadItems) {
+        rights.push(Rights::Read);
+    }
+    if acl.contains(Acl::Read) {
+        rights.push(Rights::Lookup);
+    }
+    if acl.contains(Acl::AddItems) {
+        rights.push(Rights::Insert);
+    }
+    if acl.contains(Acl::RemoveItems) {
+        rights.push(Rights::DeleteMessages);
+        rights.push(Rights::Expunge);
+    }
+    if acl.contains(Acl::ModifyItems) {
+        rights.push(Rights::Seen);
+        rights.push(Rights::Write);
+    }
+    if acl.contains_any([Acl::CreateChild, Acl::Modify].into_iter()) {
+        rights.push(Rights::CreateMailbox);
+    }
+    if acl.contains(Acl::Delete) {
+        rights.push(Rights::DeleteMailbox);
+    }
+    if acl.contains(Acl::Submit) {
+        rights.push(Rights::Post);
+    }
+    if acl.contains(Acl::Administer) {
+        rights.push(Rights::Administer);
+    }
+    rights
+}
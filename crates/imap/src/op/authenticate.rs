@@ -7,7 +7,7 @@
 use common::{
     auth::{
         sasl::{sasl_decode_challenge_oauth, sasl_decode_challenge_plain},
-        AuthRequest,
+        AccessToken, AuthRequest,
     },
     listener::SessionStream,
 };
@@ -63,6 +63,38 @@ impl<T: SessionStream> Session<T> {
                     self.write_bytes(b"+ \"\"\r\n".to_vec()).await
                 }
             }
+            Mechanism::External => {
+                if let Some(param) = args.params.pop() {
+                    // An empty response or "=" requests the identity from the certificate
+                    let authz_id = if !param.is_empty() && param != "=" {
+                        base64_decode(param.as_bytes())
+                            .and_then(|authz_id| String::from_utf8(authz_id).ok())
+                            .ok_or_else(|| {
+                                trc::AuthEvent::Error
+                                    .into_err()
+                                    .details("Failed to decode authorization identity.")
+                                    .id(args.tag.clone())
+                                    .code(ResponseCode::Parse)
+                            })?
+                            .into()
+                    } else {
+                        None
+                    };
+
+                    self.authenticate_external(authz_id, args.tag).await
+                } else {
+                    self.receiver.request = receiver::Request {
+                        tag: args.tag,
+                        command: Command::Authenticate,
+                        tokens: vec![
+                            receiver::Token::Argument(args.mechanism.into_bytes()),
+                            receiver::Token::Argument(b"=".to_vec()),
+                        ],
+                    };
+                    self.receiver.state = receiver::State::Argument { last_ch: b' ' };
+                    self.write_bytes(b"+ \r\n".to_vec()).await
+                }
+            }
             _ => Err(trc::AuthEvent::Error
                 .into_err()
                 .details("Authentication mechanism not supported.")
@@ -83,14 +115,55 @@ impl<T: SessionStream> Session<T> {
             .map_err(|err| err.id(tag.clone()))?;
 
         // Authenticate
-        let access_token = self
+        let result = self
             .server
             .authenticate(&AuthRequest::from_credentials(
                 credentials,
                 self.session_id,
                 self.remote_addr,
             ))
+            .await;
+
+        self.handle_auth_result(result, tag).await
+    }
+
+    pub async fn authenticate_external(
+        &mut self,
+        authz_id: Option<String>,
+        tag: String,
+    ) -> trc::Result<()> {
+        // Throttle authentication requests
+        self.server
+            .is_auth_allowed_soft(&self.remote_addr)
             .await
+            .map_err(|err| err.id(tag.clone()))?;
+
+        // Authenticate using the verified client certificate
+        let result = if let Some(cert) = &self.client_cert {
+            self.server
+                .authenticate_external(
+                    cert,
+                    authz_id.as_deref(),
+                    None,
+                    self.session_id,
+                    self.remote_addr,
+                )
+                .await
+        } else {
+            Err(trc::AuthEvent::Failed
+                .into_err()
+                .details("No client certificate was presented."))
+        };
+
+        self.handle_auth_result(result, tag).await
+    }
+
+    async fn handle_auth_result(
+        &mut self,
+        result: trc::Result<Arc<AccessToken>>,
+        tag: String,
+    ) -> trc::Result<()> {
+        let access_token = result
             .map_err(|err| {
                 if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
                     let auth_failures = self.state.auth_failures();
@@ -11,6 +11,7 @@ use common::listener::SessionStream;
 use directory::Permission;
 use imap_proto::{
     protocol::{
+        authenticate::Mechanism,
         capability::{Capability, Response},
         ImapResponse,
     },
@@ -32,18 +33,21 @@ impl<T: SessionStream> Session<T> {
             Elapsed = op_start.elapsed()
         );
 
+        let mut capabilities = Capability::all_capabilities(
+            self.state.is_authenticated(),
+            !self.is_tls && self.instance.acceptor.is_tls(),
+        );
+        if !self.state.is_authenticated()
+            && self.client_cert.is_some()
+            && self.server.core.auth.external.enable
+        {
+            capabilities.push(Capability::Auth(Mechanism::External));
+        }
+
         self.write_bytes(
             StatusResponse::completed(Command::Capability)
                 .with_tag(request.tag)
-                .serialize(
-                    Response {
-                        capabilities: Capability::all_capabilities(
-                            self.state.is_authenticated(),
-                            !self.is_tls && self.instance.acceptor.is_tls(),
-                        ),
-                    }
-                    .serialize(),
-                ),
+                .serialize(Response { capabilities }.serialize()),
         )
         .await
     }
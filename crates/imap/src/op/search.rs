@@ -21,6 +21,7 @@ use jmap_proto::types::{collection::Collection, id::Id, keyword::Keyword, proper
 use mail_parser::HeaderName;
 use nlp::language::Language;
 use store::{
+    ahash::AHashMap,
     fts::{Field, FilterGroup, FtsFilter, IntoFilterGroup},
     query::{self, log::Query, sort::Pagination, ResultSet},
     roaring::RoaringBitmap,
@@ -116,8 +117,20 @@ impl<T: SessionStream> SessionData<T> {
         op_start: Instant,
     ) -> trc::Result<search::Response> {
         // Run query
+        let include_relevancy = arguments.result_options.contains(&ResultOption::Relevancy);
+        let is_ranked = include_relevancy
+            || arguments
+                .sort
+                .as_ref()
+                .is_some_and(|sort| sort.iter().any(|item| item.sort == search::Sort::Relevancy));
+        let mut scores = AHashMap::new();
         let (result_set, include_highest_modseq) = self
-            .query(arguments.filter, &mailbox, &prev_saved_search)
+            .query(
+                arguments.filter,
+                &mailbox,
+                &prev_saved_search,
+                is_ranked.then_some(&mut scores),
+            )
             .await?;
 
         // Obtain modseq
@@ -172,6 +185,10 @@ impl<T: SessionStream> SessionData<T> {
                                 search::Sort::To | search::Sort::DisplayTo => {
                                     query::Comparator::field(Property::To, item.ascending)
                                 }
+                                search::Sort::Relevancy => {
+                                    // Higher scores are sorted first unless reversed
+                                    query::Comparator::score(scores.clone(), !item.ascending)
+                                }
                             })
                             .collect::<Vec<_>>(),
                         Pagination::new(results_len, 0, None, 0),
@@ -207,6 +224,33 @@ impl<T: SessionStream> SessionData<T> {
             false
         };
 
+        // Obtain relevancy scores, normalized to 1..=100
+        let relevancy = if include_relevancy {
+            let max_score = scores.values().copied().fold(0.0, f64::max);
+            let state = mailbox.state.lock();
+            let relevancy = scores
+                .into_iter()
+                .filter_map(|(document_id, score)| {
+                    let (id, _) = state.map_result_id(document_id, is_uid)?;
+                    let score = if max_score > 0.0 {
+                        ((score / max_score) * 100.0).round().max(1.0) as u32
+                    } else {
+                        1
+                    };
+                    Some((id, score))
+                })
+                .collect::<AHashMap<_, _>>();
+
+            Some(
+                imap_ids
+                    .iter()
+                    .map(|id| relevancy.get(id).copied().unwrap_or(1))
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            None
+        };
+
         // Save results
         if let (Some(results_tx), Some(saved_results)) = (results_tx, saved_results) {
             let saved_results = Arc::new(saved_results);
@@ -241,6 +285,7 @@ impl<T: SessionStream> SessionData<T> {
             },
             ids: if arguments.result_options.is_empty()
                 || arguments.result_options.contains(&ResultOption::All)
+                || include_relevancy
             {
                 imap_ids
             } else {
@@ -249,6 +294,7 @@ impl<T: SessionStream> SessionData<T> {
             is_sort,
             is_esearch: arguments.is_esearch,
             highest_modseq,
+            relevancy,
         })
     }
 
@@ -257,6 +303,7 @@ impl<T: SessionStream> SessionData<T> {
         imap_filter: Vec<Filter>,
         mailbox: &SelectedMailbox,
         prev_saved_search: &Option<Option<Arc<Vec<ImapId>>>>,
+        mut scores: Option<&mut AHashMap<u32, f64>>,
     ) -> trc::Result<(ResultSet, bool)> {
         // Obtain message ids
         let mut filters = Vec::with_capacity(imap_filter.len() + 1);
@@ -279,6 +326,12 @@ impl<T: SessionStream> SessionData<T> {
                 FilterGroup::Fts(conds) => {
                     let mut fts_filters = Vec::with_capacity(filters.len());
                     for cond in conds {
+                        let (cond, is_fuzzy) = match cond {
+                            search::Filter::Fuzzy(cond) => (*cond, true),
+                            cond => (cond, false),
+                        };
+                        let fts_filters_len = fts_filters.len();
+
                         match cond {
                             search::Filter::Bcc(text) => {
                                 fts_filters.push(FtsFilter::has_text(
@@ -414,13 +467,34 @@ impl<T: SessionStream> SessionData<T> {
                             }
                             _ => (),
                         }
+
+                        if is_fuzzy {
+                            let fuzzy_filters = fts_filters.split_off(fts_filters_len);
+                            fts_filters
+                                .extend(fuzzy_filters.into_iter().map(FtsFilter::into_fuzzy));
+                        }
                     }
 
-                    filters.push(query::Filter::is_in_set(
+                    let document_ids = if let Some(scores) = scores.as_mut() {
+                        let ranked = self
+                            .server
+                            .fts_filter_ranked(
+                                mailbox.id.account_id,
+                                Collection::Email,
+                                fts_filters,
+                            )
+                            .await?;
+                        let document_ids = ranked.keys().copied().collect::<RoaringBitmap>();
+                        for (document_id, score) in ranked {
+                            *scores.entry(document_id).or_insert(0.0) += score;
+                        }
+                        document_ids
+                    } else {
                         self.server
                             .fts_filter(mailbox.id.account_id, Collection::Email, fts_filters)
-                            .await?,
-                    ));
+                            .await?
+                    };
+                    filters.push(query::Filter::is_in_set(document_ids));
                 }
                 FilterGroup::Store(cond) => match cond {
                     search::Filter::Sequence(sequence, uid_filter) => {
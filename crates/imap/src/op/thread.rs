@@ -65,7 +65,7 @@ impl<T: SessionStream> SessionData<T> {
         op_start: Instant,
     ) -> trc::Result<Response> {
         // Run query
-        let (result_set, _) = self.query(arguments.filter, &mailbox, &None).await?;
+        let (result_set, _) = self.query(arguments.filter, &mailbox, &None, None).await?;
 
         // Synchronize mailbox
         if !result_set.results.is_empty() {
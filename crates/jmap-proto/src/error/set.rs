@@ -82,6 +82,8 @@ pub enum SetErrorType {
     InvalidScript,
     #[serde(rename = "scriptIsActive")]
     ScriptIsActive,
+    #[serde(rename = "mdnAlreadySent")]
+    MdnAlreadySent,
 }
 
 impl SetErrorType {
@@ -112,6 +114,7 @@ impl SetErrorType {
             SetErrorType::AlreadyExists => "alreadyExists",
             SetErrorType::InvalidScript => "invalidScript",
             SetErrorType::ScriptIsActive => "scriptIsActive",
+            SetErrorType::MdnAlreadySent => "mdnAlreadySent",
         }
     }
 }
@@ -51,6 +51,7 @@ pub enum RequestArguments {
     Identity,
     EmailSubmission,
     Quota,
+    ShareNotification,
 }
 
 impl JsonObjectParser for ChangesRequest {
@@ -66,6 +67,7 @@ impl JsonObjectParser for ChangesRequest {
                 MethodObject::Identity => RequestArguments::Identity,
                 MethodObject::EmailSubmission => RequestArguments::EmailSubmission,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
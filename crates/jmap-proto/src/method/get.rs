@@ -36,6 +36,7 @@ pub enum RequestArguments {
     Principal,
     Quota,
     Blob(blob::GetArguments),
+    ShareNotification,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -71,6 +72,7 @@ impl JsonObjectParser for GetRequest<RequestArguments> {
                 MethodObject::Principal => RequestArguments::Principal,
                 MethodObject::Blob => RequestArguments::Blob(Default::default()),
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
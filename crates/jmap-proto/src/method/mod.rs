@@ -11,6 +11,7 @@ pub mod copy;
 pub mod get;
 pub mod import;
 pub mod lookup;
+pub mod mdn;
 pub mod parse;
 pub mod query;
 pub mod query_changes;
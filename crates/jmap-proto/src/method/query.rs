@@ -99,6 +99,9 @@ pub enum Filter {
     IsActive(bool),
     Scope(String),
     ResourceType(String),
+    ObjectType(String),
+    ObjectAccountId(Id),
+    IsScheduled(bool),
     _T(String),
 
     And,
@@ -136,6 +139,8 @@ pub enum SortProperty {
     AllInThreadHaveKeyword,
     SomeInThreadHaveKeyword,
     Used,
+    Created,
+    Relevance,
     _T(String),
 }
 
@@ -147,6 +152,7 @@ pub enum RequestArguments {
     SieveScript,
     Principal,
     Quota,
+    ShareNotification,
 }
 
 impl JsonObjectParser for QueryRequest<RequestArguments> {
@@ -162,6 +168,7 @@ impl JsonObjectParser for QueryRequest<RequestArguments> {
                 MethodObject::SieveScript => RequestArguments::SieveScript,
                 MethodObject::Principal => RequestArguments::Principal,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -424,6 +431,17 @@ pub fn parse_filter(parser: &mut Parser) -> trc::Result<Vec<Filter>> {
                                 .next_token::<String>()?
                                 .unwrap_string("resourceType")?,
                         ),
+                        (0x6570_7954_7463_656a_626f, _) => Filter::ObjectType(
+                            parser.next_token::<String>()?.unwrap_string("objectType")?,
+                        ),
+                        (0x0064_4974_6e75_6f63_6341_7463_656a_626f, _) => Filter::ObjectAccountId(
+                            parser
+                                .next_token::<Id>()?
+                                .unwrap_string("objectAccountId")?,
+                        ),
+                        (0x0064_656c_7564_6568_6353_7369, _) => Filter::IsScheduled(
+                            parser.next_token::<String>()?.unwrap_bool("isScheduled")?,
+                        ),
                         _ => {
                             if parser.is_eof || parser.skip_string() {
                                 let filter = Filter::_T(
@@ -566,6 +584,8 @@ impl JsonObjectParser for SortProperty {
             0x4b65_7661_4864_6165_7268_546e_496c_6c61 => Ok(SortProperty::AllInThreadHaveKeyword),
             0x6576_6148_6461_6572_6854_6e49_656d_6f73 => Ok(SortProperty::SomeInThreadHaveKeyword),
             0x6465_7375 => Ok(SortProperty::Used),
+            0x0064_6574_6165_7263 => Ok(SortProperty::Created),
+            0x65_636e_6176_656c_6572 => Ok(SortProperty::Relevance),
             _ => {
                 if parser.is_eof || parser.skip_string() {
                     Ok(SortProperty::_T(
@@ -628,6 +648,9 @@ impl Display for Filter {
             Filter::IsActive(_) => "isActive",
             Filter::ResourceType(_) => "resourceType",
             Filter::Scope(_) => "scope",
+            Filter::ObjectType(_) => "objectType",
+            Filter::ObjectAccountId(_) => "objectAccountId",
+            Filter::IsScheduled(_) => "isScheduled",
             Filter::_T(v) => v.as_str(),
             Filter::And => "and",
             Filter::Or => "or",
@@ -659,6 +682,8 @@ impl Display for SortProperty {
             SortProperty::AllInThreadHaveKeyword => "allInThreadHaveKeyword",
             SortProperty::SomeInThreadHaveKeyword => "someInThreadHaveKeyword",
             SortProperty::Used => "used",
+            SortProperty::Created => "created",
+            SortProperty::Relevance => "relevance",
             SortProperty::_T(s) => s,
         })
     }
@@ -694,6 +719,8 @@ impl Filter {
                 | Filter::Id(_)
                 | Filter::SentBefore(_)
                 | Filter::SentAfter(_)
+                | Filter::ObjectType(_)
+                | Filter::ObjectAccountId(_)
         )
     }
 }
@@ -727,6 +754,7 @@ impl Comparator {
                 | SortProperty::To
                 | SortProperty::Subject
                 | SortProperty::Cc
+                | SortProperty::Created
         )
     }
 }
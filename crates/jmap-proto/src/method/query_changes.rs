@@ -69,6 +69,7 @@ impl JsonObjectParser for QueryChangesRequest {
                 MethodObject::Mailbox => RequestArguments::Mailbox(Default::default()),
                 MethodObject::EmailSubmission => RequestArguments::EmailSubmission,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -9,7 +9,11 @@ use utils::map::{bitmap::Bitmap, vec_map::VecMap};
 
 use crate::{
     error::set::{InvalidProperty, SetError},
-    object::{email_submission, mailbox, sieve, Object},
+    object::{
+        email_submission,
+        mailbox::{self, mailbox_rights_to_acl},
+        sieve, Object,
+    },
     parser::{json::Parser, JsonObjectParser, Token},
     request::{
         method::MethodObject,
@@ -24,7 +28,7 @@ use crate::{
         date::UTCDate,
         id::Id,
         keyword::Keyword,
-        property::{HeaderForm, ObjectProperty, Property, SetProperty},
+        property::{HeaderForm, IntoProperty, ObjectProperty, Property, SetProperty},
         state::{State, StateChange},
         value::{SetValue, SetValueMap, Value},
     },
@@ -51,6 +55,7 @@ pub enum RequestArguments {
     PushSubscription,
     SieveScript(sieve::SetArguments),
     VacationResponse,
+    ShareNotification,
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize)]
@@ -111,6 +116,7 @@ impl JsonObjectParser for SetRequest<RequestArguments> {
                 MethodObject::PushSubscription => RequestArguments::PushSubscription,
                 MethodObject::VacationResponse => RequestArguments::VacationResponse,
                 MethodObject::SieveScript => RequestArguments::SieveScript(Default::default()),
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -193,7 +199,8 @@ impl JsonObjectParser for Object<SetValue> {
                     | Property::ReceivedAt
                     | Property::Expires
                     | Property::FromDate
-                    | Property::ToDate => parser
+                    | Property::ToDate
+                    | Property::SendAt => parser
                         .next_token::<UTCDate>()?
                         .unwrap_string_or_null("")?
                         .map(|date| SetValue::Value(Value::Date(date)))
@@ -302,6 +309,33 @@ impl JsonObjectParser for Object<SetValue> {
                         }
                         _ => unreachable!(),
                     },
+                    Property::ShareWith => match key.patch.len() {
+                        0 => match parser.next_token::<String>()? {
+                            Token::DictStart => {
+                                let mut share_with = Vec::new();
+                                while let Some(principal_id) = parser.next_dict_key::<Id>()? {
+                                    share_with.push(Value::Id(principal_id));
+                                    share_with.push(Value::UnsignedInt(
+                                        parse_mailbox_rights(parser)?.into(),
+                                    ));
+                                }
+                                SetValue::Value(Value::List(share_with))
+                            }
+                            Token::Null => SetValue::Value(Value::List(vec![])),
+                            token => return Err(token.error("shareWith", "object or null")),
+                        },
+                        1 => {
+                            key.patch
+                                .push(Value::UnsignedInt(parse_mailbox_rights(parser)?.into()));
+                            SetValue::Patch(key.patch)
+                        }
+                        2 => {
+                            key.patch.push(Value::Bool(bool::parse(parser)?));
+                            SetValue::Patch(key.patch)
+                        }
+                        _ => unreachable!(),
+                    },
+                    Property::Snoozed => SetValue::Value(parse_snoozed(parser)?),
                     Property::Aliases
                     | Property::Attachments
                     | Property::Bcc
@@ -355,6 +389,48 @@ impl JsonObjectParser for Object<SetValue> {
     }
 }
 
+fn parse_mailbox_rights(parser: &mut Parser) -> trc::Result<Bitmap<Acl>> {
+    match Value::parse::<ObjectProperty, String>(parser.next_token()?, parser)? {
+        Value::Object(rights) => Ok(mailbox_rights_to_acl(&rights)),
+        Value::Null => Ok(Bitmap::new()),
+        _ => Err(trc::JmapEvent::InvalidArguments
+            .into_err()
+            .details("Invalid MailboxRights object.")),
+    }
+}
+
+fn parse_snoozed(parser: &mut Parser) -> trc::Result<Value> {
+    match parser.next_token::<String>()? {
+        Token::DictStart => {
+            let mut snoozed = Object::with_capacity(2);
+            while let Some(key) = parser.next_dict_key::<ObjectProperty>()? {
+                match key.into_property() {
+                    Property::Until => {
+                        snoozed.append(
+                            Property::Until,
+                            Value::Date(parser.next_token::<UTCDate>()?.unwrap_string("until")?),
+                        );
+                    }
+                    Property::MoveToMailboxId => {
+                        if let Some(mailbox_id) = parser
+                            .next_token::<Id>()?
+                            .unwrap_string_or_null("moveToMailboxId")?
+                        {
+                            snoozed.append(Property::MoveToMailboxId, Value::Id(mailbox_id));
+                        }
+                    }
+                    _ => {
+                        parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                    }
+                }
+            }
+            Ok(Value::Object(snoozed))
+        }
+        Token::Null => Ok(Value::Null),
+        token => Err(token.error("snoozed", "object or null")),
+    }
+}
+
 impl<T: Into<AnyId>> From<MaybeReference<T, String>> for SetValue {
     fn from(reference: MaybeReference<T, String>) -> Self {
         match reference {
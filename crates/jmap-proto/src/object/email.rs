@@ -22,6 +22,7 @@ pub struct GetArguments {
 #[derive(Debug, Clone, Default)]
 pub struct QueryArguments {
     pub collapse_threads: Option<bool>,
+    pub fuzzy: Option<bool>,
 }
 
 impl RequestPropertyParser for GetArguments {
@@ -59,13 +60,20 @@ impl RequestPropertyParser for GetArguments {
 
 impl RequestPropertyParser for QueryArguments {
     fn parse(&mut self, parser: &mut Parser, property: RequestProperty) -> trc::Result<bool> {
-        if property.hash[0] == 0x0073_6461_6572_6854_6573_7061_6c6c_6f63 {
-            self.collapse_threads = parser
-                .next_token::<Ignore>()?
-                .unwrap_bool_or_null("collapseThreads")?;
-            Ok(true)
-        } else {
-            Ok(false)
+        match property.hash[0] {
+            0x0073_6461_6572_6854_6573_7061_6c6c_6f63 => {
+                self.collapse_threads = parser
+                    .next_token::<Ignore>()?
+                    .unwrap_bool_or_null("collapseThreads")?;
+            }
+            0x0079_7a7a_7566 => {
+                self.fuzzy = parser
+                    .next_token::<Ignore>()?
+                    .unwrap_bool_or_null("fuzzy")?;
+            }
+            _ => return Ok(false),
         }
+
+        Ok(true)
     }
 }
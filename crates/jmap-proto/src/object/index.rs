@@ -502,6 +502,13 @@ fn build_batch(
                     set,
                 });
             }
+            (Value::Date(date), IndexAs::LongInteger) => {
+                batch.ops.push(Operation::Index {
+                    field: (&item.property).into(),
+                    key: (date.timestamp() as u64).serialize(),
+                    set,
+                });
+            }
             (Value::Id(id), IndexAs::Integer | IndexAs::LongInteger) => {
                 batch.ops.push(Operation::Index {
                     field: (&item.property).into(),
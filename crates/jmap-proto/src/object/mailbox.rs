@@ -4,11 +4,16 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use utils::map::bitmap::Bitmap;
+
 use crate::{
     parser::{json::Parser, Ignore},
     request::{RequestProperty, RequestPropertyParser},
+    types::{acl::Acl, property::Property, value::Value},
 };
 
+use super::Object;
+
 #[derive(Debug, Clone, Default)]
 pub struct SetArguments {
     pub on_destroy_remove_emails: Option<bool>,
@@ -54,3 +59,48 @@ impl RequestPropertyParser for QueryArguments {
         Ok(true)
     }
 }
+
+/// Returns the ACL grants required by a MailboxRights property.
+pub fn mailbox_right_to_acl(right: &Property) -> Option<Bitmap<Acl>> {
+    match right {
+        Property::MayReadItems => Bitmap::new().with_item(Acl::Read).with_item(Acl::ReadItems),
+        Property::MayAddItems => Bitmap::new().with_item(Acl::AddItems),
+        Property::MayRemoveItems => Bitmap::new().with_item(Acl::RemoveItems),
+        Property::MaySetSeen | Property::MaySetKeywords => {
+            Bitmap::new().with_item(Acl::ModifyItems)
+        }
+        Property::MayCreateChild => Bitmap::new().with_item(Acl::CreateChild),
+        Property::MayRename => Bitmap::new().with_item(Acl::Modify),
+        Property::MayDelete => Bitmap::new().with_item(Acl::Delete),
+        Property::MaySubmit => Bitmap::new().with_item(Acl::Submit),
+        Property::MayAdmin => Bitmap::new().with_item(Acl::Administer),
+        _ => return None,
+    }
+    .into()
+}
+
+/// Returns the ACL grants of a MailboxRights object.
+pub fn mailbox_rights_to_acl(rights: &Object<Value>) -> Bitmap<Acl> {
+    let mut acl = Bitmap::new();
+    for (right, value) in rights.properties.iter() {
+        if let (Some(grants), Value::Bool(true)) = (mailbox_right_to_acl(right), value) {
+            acl.union(&grants);
+        }
+    }
+    acl
+}
+
+/// Builds a MailboxRights object from ACL grants.
+pub fn acl_to_mailbox_rights(acl: &Bitmap<Acl>) -> Object<Value> {
+    Object::with_capacity(10)
+        .with_property(Property::MayReadItems, acl.contains(Acl::ReadItems))
+        .with_property(Property::MayAddItems, acl.contains(Acl::AddItems))
+        .with_property(Property::MayRemoveItems, acl.contains(Acl::RemoveItems))
+        .with_property(Property::MaySetSeen, acl.contains(Acl::ModifyItems))
+        .with_property(Property::MaySetKeywords, acl.contains(Acl::ModifyItems))
+        .with_property(Property::MayCreateChild, acl.contains(Acl::CreateChild))
+        .with_property(Property::MayRename, acl.contains(Acl::Modify))
+        .with_property(Property::MayDelete, acl.contains(Acl::Delete))
+        .with_property(Property::MaySubmit, acl.contains(Acl::Submit))
+        .with_property(Property::MayAdmin, acl.contains(Acl::Administer))
+}
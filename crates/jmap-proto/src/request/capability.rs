@@ -71,6 +71,14 @@ pub enum Capability {
     Blob = 1 << 8,
     #[serde(rename(serialize = "urn:ietf:params:jmap:quota"))]
     Quota = 1 << 9,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:mdn"))]
+    Mdn = 1 << 10,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:principals"))]
+    Principals = 1 << 11,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:principals:owner"))]
+    PrincipalsOwner = 1 << 12,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:webpush-vapid"))]
+    WebPushVapid = 1 << 13,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -84,6 +92,9 @@ pub enum Capabilities {
     SieveAccount(SieveAccountCapabilities),
     SieveSession(SieveSessionCapabilities),
     Blob(BlobCapabilities),
+    Principals(PrincipalCapabilities),
+    PrincipalsOwner(PrincipalOwnerCapabilities),
+    WebPushVapid(WebPushVapidCapabilities),
     Empty(EmptyCapabilities),
 }
 
@@ -155,6 +166,8 @@ pub struct MailCapabilities {
     pub may_create_top_level_mailbox: bool,
 }
 
+pub const MAX_DELAYED_SEND: u64 = 86400 * 30;
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct SubmissionCapabilities {
     #[serde(rename(serialize = "maxDelayedSend"))]
@@ -175,6 +188,26 @@ pub struct BlobCapabilities {
     pub supported_digest_algorithms: Vec<&'static str>,
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PrincipalCapabilities {
+    #[serde(rename(serialize = "currentUserPrincipalId"))]
+    pub current_user_principal_id: Option<Id>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PrincipalOwnerCapabilities {
+    #[serde(rename(serialize = "accountIdForPrincipal"))]
+    pub account_id_for_principal: Id,
+    #[serde(rename(serialize = "principalId"))]
+    pub principal_id: Id,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WebPushVapidCapabilities {
+    #[serde(rename(serialize = "applicationServerKey"))]
+    pub application_server_key: String,
+}
+
 #[derive(Debug, Clone, Default, serde::Serialize)]
 pub struct EmptyCapabilities {}
 
@@ -255,6 +288,24 @@ impl Session {
         );
     }
 
+    pub fn add_principal_capabilities(&mut self, account_id: Id, current_user_principal_id: Id) {
+        if let Some(account) = self.accounts.get_mut(&account_id) {
+            account.account_capabilities.set(
+                Capability::Principals,
+                Capabilities::Principals(PrincipalCapabilities {
+                    current_user_principal_id: Some(current_user_principal_id),
+                }),
+            );
+            account.account_capabilities.set(
+                Capability::PrincipalsOwner,
+                Capabilities::PrincipalsOwner(PrincipalOwnerCapabilities {
+                    account_id_for_principal: account_id,
+                    principal_id: account_id,
+                }),
+            );
+        }
+    }
+
     pub fn set_state(&mut self, state: u32) {
         self.state = state;
     }
@@ -340,6 +391,10 @@ impl JsonObjectParser for Capability {
                 0x0065_7665_6973 => Ok(Capability::Sieve),
                 0x626f_6c62 => Ok(Capability::Blob),
                 0x0061_746f_7571 => Ok(Capability::Quota),
+                0x006e_646d => Ok(Capability::Mdn),
+                0x736c_6170_6963_6e69_7270 => Ok(Capability::Principals),
+                0x7265_6e77_6f3a_736c_6170_6963_6e69_7270 => Ok(Capability::PrincipalsOwner),
+                0x0064_6970_6176_2d68_7375_7062_6577 => Ok(Capability::WebPushVapid),
                 _ => Err(parser.error_capability()),
             },
             Err(err) if err.is_jmap_method_error() => Err(parser.error_capability()),
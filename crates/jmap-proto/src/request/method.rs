@@ -29,6 +29,8 @@ pub enum MethodObject {
     SieveScript,
     Principal,
     Quota,
+    Mdn,
+    ShareNotification,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -45,6 +47,7 @@ pub enum MethodFunction {
     Lookup,
     Upload,
     Echo,
+    Send,
 }
 
 impl JsonObjectParser for MethodName {
@@ -54,6 +57,7 @@ impl JsonObjectParser for MethodName {
     {
         let mut shift = 0;
         let mut obj_hash: u128 = 0;
+        let mut obj_hash_ext: u128 = 0;
         let mut fnc_hash: u128 = 0;
 
         loop {
@@ -63,10 +67,12 @@ impl JsonObjectParser for MethodName {
             if ch != b'/' {
                 if shift < 128 {
                     obj_hash |= (ch as u128) << shift;
-                    shift += 8;
+                } else if shift < 256 {
+                    obj_hash_ext |= (ch as u128) << (shift - 128);
                 } else {
                     return Err(parser.error_value());
                 }
+                shift += 8;
             } else {
                 break;
             }
@@ -83,20 +89,24 @@ impl JsonObjectParser for MethodName {
         }
 
         Ok(MethodName {
-            obj: match obj_hash {
-                0x006c_6961_6d45 => MethodObject::Email,
-                0x0078_6f62_6c69_614d => MethodObject::Mailbox,
-                0x6461_6572_6854 => MethodObject::Thread,
-                0x626f_6c42 => MethodObject::Blob,
-                0x006e_6f69_7373_696d_6275_536c_6961_6d45 => MethodObject::EmailSubmission,
-                0x0074_6570_7069_6e53_6863_7261_6553 => MethodObject::SearchSnippet,
-                0x7974_6974_6e65_6449 => MethodObject::Identity,
-                0x6573_6e6f_7073_6552_6e6f_6974_6163_6156 => MethodObject::VacationResponse,
-                0x6e6f_6974_7069_7263_7362_7553_6873_7550 => MethodObject::PushSubscription,
-                0x0074_7069_7263_5365_7665_6953 => MethodObject::SieveScript,
-                0x006c_6170_6963_6e69_7250 => MethodObject::Principal,
-                0x0061_746f_7551 => MethodObject::Quota,
-                0x6572_6f43 => MethodObject::Core,
+            obj: match (obj_hash, obj_hash_ext) {
+                (0x006c_6961_6d45, 0) => MethodObject::Email,
+                (0x0078_6f62_6c69_614d, 0) => MethodObject::Mailbox,
+                (0x6461_6572_6854, 0) => MethodObject::Thread,
+                (0x626f_6c42, 0) => MethodObject::Blob,
+                (0x006e_6f69_7373_696d_6275_536c_6961_6d45, 0) => MethodObject::EmailSubmission,
+                (0x0074_6570_7069_6e53_6863_7261_6553, 0) => MethodObject::SearchSnippet,
+                (0x7974_6974_6e65_6449, 0) => MethodObject::Identity,
+                (0x6573_6e6f_7073_6552_6e6f_6974_6163_6156, 0) => MethodObject::VacationResponse,
+                (0x6e6f_6974_7069_7263_7362_7553_6873_7550, 0) => MethodObject::PushSubscription,
+                (0x0074_7069_7263_5365_7665_6953, 0) => MethodObject::SieveScript,
+                (0x006c_6170_6963_6e69_7250, 0) => MethodObject::Principal,
+                (0x0061_746f_7551, 0) => MethodObject::Quota,
+                (0x004e_444d, 0) => MethodObject::Mdn,
+                (0x6572_6f43, 0) => MethodObject::Core,
+                (0x6f69_7461_6369_6669_746f_4e65_7261_6853, 0x006e) => {
+                    MethodObject::ShareNotification
+                }
                 _ => return Err(parser.error_value()),
             },
             fnc: match fnc_hash {
@@ -112,6 +122,7 @@ impl JsonObjectParser for MethodName {
                 0x7075_6b6f_6f6c => MethodFunction::Lookup,
                 0x6461_6f6c_7075 => MethodFunction::Upload,
                 0x6f68_6365 => MethodFunction::Echo,
+                0x646e_6573 => MethodFunction::Send,
                 _ => return Err(parser.error_value()),
             },
         })
@@ -195,6 +206,19 @@ impl MethodName {
             (MethodFunction::Lookup, MethodObject::Blob) => "Blob/lookup",
             (MethodFunction::Upload, MethodObject::Blob) => "Blob/upload",
 
+            (MethodFunction::Send, MethodObject::Mdn) => "MDN/send",
+            (MethodFunction::Parse, MethodObject::Mdn) => "MDN/parse",
+
+            (MethodFunction::Get, MethodObject::ShareNotification) => "ShareNotification/get",
+            (MethodFunction::Changes, MethodObject::ShareNotification) => {
+                "ShareNotification/changes"
+            }
+            (MethodFunction::Query, MethodObject::ShareNotification) => "ShareNotification/query",
+            (MethodFunction::QueryChanges, MethodObject::ShareNotification) => {
+                "ShareNotification/queryChanges"
+            }
+            (MethodFunction::Set, MethodObject::ShareNotification) => "ShareNotification/set",
+
             (MethodFunction::Echo, MethodObject::Core) => "Core/echo",
             _ => "error",
         }
@@ -217,6 +241,8 @@ impl Display for MethodObject {
             MethodObject::Thread => "Thread",
             MethodObject::Email => "Email",
             MethodObject::Quota => "Quota",
+            MethodObject::Mdn => "MDN",
+            MethodObject::ShareNotification => "ShareNotification",
         })
     }
 }
@@ -23,6 +23,7 @@ use crate::{
         get::{self, GetRequest},
         import::ImportEmailRequest,
         lookup::BlobLookupRequest,
+        mdn::{MdnParseRequest, MdnSendRequest},
         parse::ParseEmailRequest,
         query::{self, QueryRequest},
         query_changes::QueryChangesRequest,
@@ -72,6 +73,8 @@ pub enum RequestMethod {
     ValidateScript(ValidateSieveScriptRequest),
     LookupBlob(BlobLookupRequest),
     UploadBlob(BlobUploadRequest),
+    SendMdn(MdnSendRequest),
+    ParseMdn(MdnParseRequest),
     Echo(Echo),
     Error(trc::Error),
 }
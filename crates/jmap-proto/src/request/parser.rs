@@ -13,6 +13,7 @@ use crate::{
         get::GetRequest,
         import::ImportEmailRequest,
         lookup::BlobLookupRequest,
+        mdn::{MdnParseRequest, MdnSendRequest},
         parse::ParseEmailRequest,
         query::QueryRequest,
         query_changes::QueryChangesRequest,
@@ -132,7 +133,8 @@ impl Request {
                                 | MethodObject::SieveScript
                                 | MethodObject::Principal
                                 | MethodObject::Quota
-                                | MethodObject::Blob,
+                                | MethodObject::Blob
+                                | MethodObject::ShareNotification,
                             ) => GetRequest::parse(parser).map(RequestMethod::Get),
                             (MethodFunction::Get, MethodObject::SearchSnippet) => {
                                 GetSearchSnippetRequest::parse(parser)
@@ -172,6 +174,12 @@ impl Request {
                                 ValidateSieveScriptRequest::parse(parser)
                                     .map(RequestMethod::ValidateScript)
                             }
+                            (MethodFunction::Send, MethodObject::Mdn) => {
+                                MdnSendRequest::parse(parser).map(RequestMethod::SendMdn)
+                            }
+                            (MethodFunction::Parse, MethodObject::Mdn) => {
+                                MdnParseRequest::parse(parser).map(RequestMethod::ParseMdn)
+                            }
                             (MethodFunction::Echo, MethodObject::Core) => {
                                 Echo::parse(parser).map(RequestMethod::Echo)
                             }
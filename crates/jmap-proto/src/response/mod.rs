@@ -17,6 +17,7 @@ use crate::{
         get::GetResponse,
         import::ImportEmailResponse,
         lookup::BlobLookupResponse,
+        mdn::{MdnParseResponse, MdnSendResponse},
         parse::ParseEmailResponse,
         query::QueryResponse,
         query_changes::QueryChangesResponse,
@@ -47,6 +48,8 @@ pub enum ResponseMethod {
     ValidateScript(ValidateSieveScriptResponse),
     LookupBlob(BlobLookupResponse),
     UploadBlob(BlobUploadResponse),
+    SendMdn(MdnSendResponse),
+    ParseMdn(MdnParseResponse),
     Echo(Echo),
     Error(MethodErrorWrapper),
 }
@@ -190,6 +193,18 @@ impl From<BlobLookupResponse> for ResponseMethod {
     }
 }
 
+impl From<MdnSendResponse> for ResponseMethod {
+    fn from(send_mdn: MdnSendResponse) -> Self {
+        ResponseMethod::SendMdn(send_mdn)
+    }
+}
+
+impl From<MdnParseResponse> for ResponseMethod {
+    fn from(parse_mdn: MdnParseResponse) -> Self {
+        ResponseMethod::ParseMdn(parse_mdn)
+    }
+}
+
 impl<T: Into<ResponseMethod>> From<trc::Result<T>> for ResponseMethod {
     fn from(result: trc::Result<T>) -> Self {
         match result {
@@ -24,7 +24,8 @@ pub enum Collection {
     SieveScript = 5,
     PushSubscription = 6,
     Principal = 7,
-    None = 8,
+    ShareNotification = 8,
+    None = 9,
 }
 
 impl From<u8> for Collection {
@@ -38,6 +39,7 @@ impl From<u8> for Collection {
             5 => Collection::SieveScript,
             6 => Collection::PushSubscription,
             7 => Collection::Principal,
+            8 => Collection::ShareNotification,
             _ => Collection::None,
         }
     }
@@ -54,6 +56,7 @@ impl From<u64> for Collection {
             5 => Collection::SieveScript,
             6 => Collection::PushSubscription,
             7 => Collection::Principal,
+            8 => Collection::ShareNotification,
             _ => Collection::None,
         }
     }
@@ -83,6 +86,7 @@ impl TryFrom<Collection> for DataType {
             Collection::EmailSubmission => Ok(DataType::EmailSubmission),
             Collection::SieveScript => Ok(DataType::SieveScript),
             Collection::PushSubscription => Ok(DataType::PushSubscription),
+            Collection::ShareNotification => Ok(DataType::ShareNotification),
             _ => Err(()),
         }
     }
@@ -105,6 +109,7 @@ impl Collection {
             Collection::EmailSubmission => "emailSubmission",
             Collection::SieveScript => "sieveScript",
             Collection::Principal => "principal",
+            Collection::ShareNotification => "shareNotification",
             Collection::None => "",
         }
     }
@@ -123,6 +128,7 @@ impl FromStr for Collection {
             "emailSubmission" => Ok(Collection::EmailSubmission),
             "sieveScript" => Ok(Collection::SieveScript),
             "principal" => Ok(Collection::Principal),
+            "shareNotification" => Ok(Collection::ShareNotification),
             _ => Err(()),
         }
     }
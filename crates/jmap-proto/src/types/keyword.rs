@@ -30,6 +30,9 @@ pub const FORWARDED: usize = 10;
 pub const MDN_SENT: usize = 11;
 pub const OTHER: usize = 12;
 
+pub const MUTED_KEYWORD: &str = "$muted";
+pub const SNOOZED_KEYWORD: &str = "$snoozed";
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
 #[serde(untagged)]
 pub enum Keyword {
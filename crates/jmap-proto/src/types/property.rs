@@ -10,7 +10,10 @@ use mail_parser::HeaderName;
 use serde::Serialize;
 use store::write::{DeserializeFrom, SerializeInto};
 
-use crate::parser::{json::Parser, JsonObjectParser};
+use crate::{
+    object::mailbox::mailbox_right_to_acl,
+    parser::{json::Parser, JsonObjectParser},
+};
 
 use super::{acl::Acl, id::Id, keyword::Keyword, value::Value};
 
@@ -119,6 +122,19 @@ pub enum Property {
     WarnLimit,
     SoftLimit,
     Scope,
+    ShareWith,
+    MayAdmin,
+    Created,
+    ChangedBy,
+    ObjectType,
+    ObjectAccountId,
+    ObjectId,
+    OldRights,
+    NewRights,
+    PrincipalId,
+    Snoozed,
+    Until,
+    MoveToMailboxId,
     Digest(DigestProperty),
     Data(DataProperty),
     _T(String),
@@ -304,6 +320,40 @@ impl JsonObjectParser for SetProperty {
                         }
                     }
                 }
+                Property::ShareWith => {
+                    let mut has_right = false;
+                    let mut principal_id = Vec::with_capacity(16);
+
+                    while let Some(ch) = parser.next_unescaped()? {
+                        if ch != b'/' {
+                            principal_id.push(ch);
+                        } else {
+                            has_right = true;
+                            break;
+                        }
+                    }
+
+                    if let Some(principal_id) = Id::from_bytes(&principal_id) {
+                        patch.push(Value::Id(principal_id));
+                        if has_right {
+                            match ObjectProperty::parse(parser)
+                                .map(|right| mailbox_right_to_acl(&right.0))
+                            {
+                                Ok(Some(acl)) => {
+                                    patch.push(Value::UnsignedInt(acl.into()));
+                                }
+                                Ok(None) => {
+                                    property = parser.invalid_property()?;
+                                }
+                                Err(err) => {
+                                    return Err(err);
+                                }
+                            }
+                        }
+                    } else {
+                        property = parser.invalid_property()?;
+                    }
+                }
                 Property::Aliases => match String::parse(parser) {
                     Ok(text) if !text.is_empty() => {
                         patch.push(Value::Text(text));
@@ -349,6 +399,8 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x63 => Property::Cc,
             0x7465_7372_6168 => Property::Charset,
             0x6469 => Property::Cid,
+            0x6465_7461_6572 => Property::Created,
+            0x7942_6465_676e_6168 => Property::ChangedBy,
             _ => return None,
         },
         b'd' => match hash {
@@ -410,6 +462,14 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
         },
         b'n' => match hash {
             0x0065_6d61 => Property::Name,
+            0x7374_6867_6952_7765 => Property::NewRights,
+            _ => return None,
+        },
+        b'o' => match hash {
+            0x0065_7079_5474_6365_6a62 => Property::ObjectType,
+            0x6449_746e_756f_6363_4174_6365_6a62 => Property::ObjectAccountId,
+            0x0064_4974_6365_6a62 => Property::ObjectId,
+            0x7374_6867_6952_646c => Property::OldRights,
             _ => return None,
         },
         b'p' => match hash {
@@ -417,6 +477,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0064_4974_7261 => Property::PartId,
             0x6572_7574_6369 => Property::Picture,
             0x7765_6976_6572 => Property::Preview,
+            0x6449_6c61_7069_636e_6972 => Property::PrincipalId,
             _ => return None,
         },
         b'q' => match hash {
@@ -439,6 +500,8 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x7265_6472_4f74_726f => Property::SortOrder,
             0x7463_656a_6275 => Property::Subject,
             0x7374_7261_5062_7573 => Property::SubParts,
+            0x6874_6957_6572_6168 => Property::ShareWith,
+            0x6465_7a6f_6f6e => Property::Snoozed,
             _ => return None,
         },
         b't' => match hash {
@@ -660,6 +723,8 @@ impl JsonObjectParser for ObjectProperty {
                 0x656d_616e_6552_7961 => Property::MayRename,
                 0x6574_656c_6544_7961 => Property::MayDelete,
                 0x7469_6d62_7553_7961 => Property::MaySubmit,
+                0x006e_696d_6441_7961 => Property::MayAdmin,
+                0x6449_786f_626c_6961_4d6f_5465_766f => Property::MoveToMailboxId,
                 _ => parser.invalid_property()?,
             },
             b'n' => match hash {
@@ -691,6 +756,7 @@ impl JsonObjectParser for ObjectProperty {
             },
             b'u' => match hash {
                 0x0064_6573 => Property::Used,
+                0x6c69_746e => Property::Until,
                 _ => parser.invalid_property()?,
             },
             b'v' => match hash {
@@ -881,6 +947,19 @@ impl Display for Property {
             Property::Used => write!(f, "used"),
             Property::HardLimit => write!(f, "hardLimit"),
             Property::Scope => write!(f, "scope"),
+            Property::ShareWith => write!(f, "shareWith"),
+            Property::MayAdmin => write!(f, "mayAdmin"),
+            Property::Created => write!(f, "created"),
+            Property::ChangedBy => write!(f, "changedBy"),
+            Property::ObjectType => write!(f, "objectType"),
+            Property::ObjectAccountId => write!(f, "objectAccountId"),
+            Property::ObjectId => write!(f, "objectId"),
+            Property::OldRights => write!(f, "oldRights"),
+            Property::NewRights => write!(f, "newRights"),
+            Property::PrincipalId => write!(f, "principalId"),
+            Property::Snoozed => write!(f, "snoozed"),
+            Property::Until => write!(f, "until"),
+            Property::MoveToMailboxId => write!(f, "moveToMailboxId"),
             Property::WarnLimit => write!(f, "warnLimit"),
             Property::SoftLimit => write!(f, "softLimit"),
             Property::_T(s) => write!(f, "{s}"),
@@ -1063,6 +1142,19 @@ impl From<&Property> for u8 {
             Property::WarnLimit => 101,
             Property::SoftLimit => 102,
             Property::Scope => 103,
+            Property::ShareWith => 104,
+            Property::MayAdmin => 105,
+            Property::Created => 106,
+            Property::ChangedBy => 107,
+            Property::ObjectType => 108,
+            Property::ObjectAccountId => 109,
+            Property::ObjectId => 110,
+            Property::OldRights => 111,
+            Property::NewRights => 112,
+            Property::PrincipalId => 113,
+            Property::Snoozed => 114,
+            Property::Until => 115,
+            Property::MoveToMailboxId => 116,
             Property::Digest(_) | Property::Data(_) => unreachable!("invalid property"),
         }
     }
@@ -1205,6 +1297,19 @@ impl SerializeInto for Property {
             Property::WarnLimit => 101,
             Property::SoftLimit => 102,
             Property::Scope => 103,
+            Property::ShareWith => 104,
+            Property::MayAdmin => 105,
+            Property::Created => 106,
+            Property::ChangedBy => 107,
+            Property::ObjectType => 108,
+            Property::ObjectAccountId => 109,
+            Property::ObjectId => 110,
+            Property::OldRights => 111,
+            Property::NewRights => 112,
+            Property::PrincipalId => 113,
+            Property::Snoozed => 114,
+            Property::Until => 115,
+            Property::MoveToMailboxId => 116,
             Property::Digest(_) | Property::Data(_) => {
                 unreachable!("Property::Digest and Property::Data are not serializable")
             }
@@ -1323,6 +1428,19 @@ impl DeserializeFrom for Property {
             101 => Some(Property::WarnLimit),
             102 => Some(Property::SoftLimit),
             103 => Some(Property::Scope),
+            104 => Some(Property::ShareWith),
+            105 => Some(Property::MayAdmin),
+            106 => Some(Property::Created),
+            107 => Some(Property::ChangedBy),
+            108 => Some(Property::ObjectType),
+            109 => Some(Property::ObjectAccountId),
+            110 => Some(Property::ObjectId),
+            111 => Some(Property::OldRights),
+            112 => Some(Property::NewRights),
+            113 => Some(Property::PrincipalId),
+            114 => Some(Property::Snoozed),
+            115 => Some(Property::Until),
+            116 => Some(Property::MoveToMailboxId),
             _ => None,
         }
     }
@@ -41,7 +41,9 @@ pub enum DataType {
     Quota = 11,
     #[serde(rename = "SieveScript")]
     SieveScript = 12,
-    None = 13,
+    #[serde(rename = "ShareNotification")]
+    ShareNotification = 13,
+    None = 14,
 }
 
 impl BitmapItem for DataType {
@@ -70,6 +72,7 @@ impl From<u64> for DataType {
             10 => DataType::Mdn,
             11 => DataType::Quota,
             12 => DataType::SieveScript,
+            13 => DataType::ShareNotification,
             _ => {
                 debug_assert!(false, "Invalid type_state value: {}", value);
                 DataType::None
@@ -90,31 +93,35 @@ impl JsonObjectParser for DataType {
         Self: Sized,
     {
         let mut hash = 0;
+        let mut hash_ext = 0;
         let mut shift = 0;
 
         while let Some(ch) = parser.next_unescaped()? {
             if shift < 128 {
                 hash |= (ch as u128) << shift;
-                shift += 8;
+            } else if shift < 256 {
+                hash_ext |= (ch as u128) << (shift - 128);
             } else {
                 return Err(parser.error_value());
             }
+            shift += 8;
         }
 
-        match hash {
-            0x006c_6961_6d45 => Ok(DataType::Email),
-            0x0079_7265_7669_6c65_446c_6961_6d45 => Ok(DataType::EmailDelivery),
-            0x006e_6f69_7373_696d_6275_536c_6961_6d45 => Ok(DataType::EmailSubmission),
-            0x0078_6f62_6c69_614d => Ok(DataType::Mailbox),
-            0x6461_6572_6854 => Ok(DataType::Thread),
-            0x7974_6974_6e65_6449 => Ok(DataType::Identity),
-            0x6572_6f43 => Ok(DataType::Core),
-            0x6e6f_6974_7069_7263_7362_7553_6873_7550 => Ok(DataType::PushSubscription),
-            0x0074_6570_7069_6e53_6863_7261_6553 => Ok(DataType::SearchSnippet),
-            0x6573_6e6f_7073_6552_6e6f_6974_6163_6156 => Ok(DataType::VacationResponse),
-            0x004e_444d => Ok(DataType::Mdn),
-            0x0061_746f_7551 => Ok(DataType::Quota),
-            0x0074_7069_7263_5365_7665_6953 => Ok(DataType::SieveScript),
+        match (hash, hash_ext) {
+            (0x006c_6961_6d45, 0) => Ok(DataType::Email),
+            (0x0079_7265_7669_6c65_446c_6961_6d45, 0) => Ok(DataType::EmailDelivery),
+            (0x006e_6f69_7373_696d_6275_536c_6961_6d45, 0) => Ok(DataType::EmailSubmission),
+            (0x0078_6f62_6c69_614d, 0) => Ok(DataType::Mailbox),
+            (0x6461_6572_6854, 0) => Ok(DataType::Thread),
+            (0x7974_6974_6e65_6449, 0) => Ok(DataType::Identity),
+            (0x6572_6f43, 0) => Ok(DataType::Core),
+            (0x6e6f_6974_7069_7263_7362_7553_6873_7550, 0) => Ok(DataType::PushSubscription),
+            (0x0074_6570_7069_6e53_6863_7261_6553, 0) => Ok(DataType::SearchSnippet),
+            (0x6573_6e6f_7073_6552_6e6f_6974_6163_6156, 0) => Ok(DataType::VacationResponse),
+            (0x004e_444d, 0) => Ok(DataType::Mdn),
+            (0x0061_746f_7551, 0) => Ok(DataType::Quota),
+            (0x0074_7069_7263_5365_7665_6953, 0) => Ok(DataType::SieveScript),
+            (0x6f69_7461_6369_6669_746f_4e65_7261_6853, 0x006e) => Ok(DataType::ShareNotification),
             _ => Err(parser.error_value()),
         }
     }
@@ -125,31 +132,35 @@ impl TryFrom<&str> for DataType {
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         let mut hash = 0;
+        let mut hash_ext = 0;
         let mut shift = 0;
 
         for &ch in value.as_bytes() {
             if shift < 128 {
                 hash |= (ch as u128) << shift;
-                shift += 8;
+            } else if shift < 256 {
+                hash_ext |= (ch as u128) << (shift - 128);
             } else {
                 return Err(());
             }
+            shift += 8;
         }
 
-        match hash {
-            0x006c_6961_6d45 => Ok(DataType::Email),
-            0x0079_7265_7669_6c65_446c_6961_6d45 => Ok(DataType::EmailDelivery),
-            0x006e_6f69_7373_696d_6275_536c_6961_6d45 => Ok(DataType::EmailSubmission),
-            0x0078_6f62_6c69_614d => Ok(DataType::Mailbox),
-            0x6461_6572_6854 => Ok(DataType::Thread),
-            0x7974_6974_6e65_6449 => Ok(DataType::Identity),
-            0x6572_6f43 => Ok(DataType::Core),
-            0x6e6f_6974_7069_7263_7362_7553_6873_7550 => Ok(DataType::PushSubscription),
-            0x0074_6570_7069_6e53_6863_7261_6553 => Ok(DataType::SearchSnippet),
-            0x6573_6e6f_7073_6552_6e6f_6974_6163_6156 => Ok(DataType::VacationResponse),
-            0x004e_444d => Ok(DataType::Mdn),
-            0x0061_746f_7551 => Ok(DataType::Quota),
-            0x0074_7069_7263_5365_7665_6953 => Ok(DataType::SieveScript),
+        match (hash, hash_ext) {
+            (0x006c_6961_6d45, 0) => Ok(DataType::Email),
+            (0x0079_7265_7669_6c65_446c_6961_6d45, 0) => Ok(DataType::EmailDelivery),
+            (0x006e_6f69_7373_696d_6275_536c_6961_6d45, 0) => Ok(DataType::EmailSubmission),
+            (0x0078_6f62_6c69_614d, 0) => Ok(DataType::Mailbox),
+            (0x6461_6572_6854, 0) => Ok(DataType::Thread),
+            (0x7974_6974_6e65_6449, 0) => Ok(DataType::Identity),
+            (0x6572_6f43, 0) => Ok(DataType::Core),
+            (0x6e6f_6974_7069_7263_7362_7553_6873_7550, 0) => Ok(DataType::PushSubscription),
+            (0x0074_6570_7069_6e53_6863_7261_6553, 0) => Ok(DataType::SearchSnippet),
+            (0x6573_6e6f_7073_6552_6e6f_6974_6163_6156, 0) => Ok(DataType::VacationResponse),
+            (0x004e_444d, 0) => Ok(DataType::Mdn),
+            (0x0061_746f_7551, 0) => Ok(DataType::Quota),
+            (0x0074_7069_7263_5365_7665_6953, 0) => Ok(DataType::SieveScript),
+            (0x6f69_7461_6369_6669_746f_4e65_7261_6853, 0x006e) => Ok(DataType::ShareNotification),
             _ => Err(()),
         }
     }
@@ -171,6 +182,7 @@ impl DataType {
             DataType::Mdn => "MDN",
             DataType::Quota => "Quota",
             DataType::SieveScript => "SieveScript",
+            DataType::ShareNotification => "ShareNotification",
             DataType::None => "",
         }
     }
@@ -204,6 +216,7 @@ impl DeserializeFrom for DataType {
             10 => Some(DataType::Mdn),
             11 => Some(DataType::Quota),
             12 => Some(DataType::SieveScript),
+            13 => Some(DataType::ShareNotification),
             _ => None,
         }
     }
@@ -171,7 +171,8 @@ impl Value {
             | Property::MayCreateChild
             | Property::MayRename
             | Property::MayDelete
-            | Property::MaySubmit => Ok(parser
+            | Property::MaySubmit
+            | Property::MayAdmin => Ok(parser
                 .next_token::<String>()?
                 .unwrap_bool_or_null("")?
                 .map(Value::Bool)
@@ -318,6 +319,7 @@ impl Value {
             Value::UnsignedInt(u) => Some(*u),
             Value::Id(id) => Some(id.id()),
             Value::Bool(b) => Some(*b as u64),
+            Value::Date(d) => Some(d.timestamp() as u64),
             _ => None,
         }
     }
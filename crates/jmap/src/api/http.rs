@@ -960,6 +960,12 @@ impl ToHttpResponse for &trc::Error {
                             .unwrap_or("Requested action is unsupported"),
                     },
                     trc::ManageEvent::AssertFailed => ManagementApiError::AssertFailed,
+                    trc::ManageEvent::PasswordPolicy => ManagementApiError::PasswordPolicy {
+                        rule: self.value_as_str(trc::Key::Key).unwrap_or_default(),
+                        details: self
+                            .value_as_str(trc::Key::Details)
+                            .unwrap_or("Password policy violation"),
+                    },
                     trc::ManageEvent::Error => ManagementApiError::Other {
                         reason: self.value_as_str(trc::Key::Reason),
                         details: self
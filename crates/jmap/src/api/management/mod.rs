@@ -64,6 +64,10 @@ pub enum ManagementApiError<'x> {
         details: &'x str,
     },
     AssertFailed,
+    PasswordPolicy {
+        rule: &'x str,
+        details: &'x str,
+    },
     Other {
         details: &'x str,
         reason: Option<&'x str>,
@@ -13,6 +13,7 @@ use directory::{
         manage::{self, not_found, ManageDirectory, UpdatePrincipal},
         PrincipalAction, PrincipalField, PrincipalUpdate, PrincipalValue, SpecialSecrets,
     },
+    core::{policy::violation, secret::is_password_hash},
     DirectoryInner, Permission, Principal, QueryBy, Type,
 };
 
@@ -66,7 +67,7 @@ pub trait PrincipalManager: Sync + Send {
         body: Option<Vec<u8>>,
     ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
 
-    fn assert_supported_directory(&self) -> trc::Result<()>;
+    fn assert_supported_directory(&self, is_update: bool) -> trc::Result<()>;
 }
 
 impl PrincipalManager for Server {
@@ -116,7 +117,17 @@ impl PrincipalManager for Server {
 
                 // Make sure the current directory supports updates
                 if matches!(principal.typ(), Type::Individual) {
-                    self.assert_supported_directory()?;
+                    self.assert_supported_directory(false)?;
+                }
+
+                // Enforce the password policy
+                if let Some(policy) = &self.core.auth.password_policy {
+                    for secret in principal
+                        .iter_str(PrincipalField::Secrets)
+                        .filter(|s| s.is_password())
+                    {
+                        policy.validate(secret).await?;
+                    }
                 }
 
                 // Validate roles
@@ -412,6 +423,9 @@ impl PrincipalManager for Server {
                                     expire_session = true;
                                     needs_assert = true;
                                 }
+                                PrincipalField::PasswordChanged => {
+                                    expire_token = true;
+                                }
                                 PrincipalField::Name
                                 | PrincipalField::Emails
                                 | PrincipalField::Quota
@@ -422,7 +436,10 @@ impl PrincipalManager for Server {
                                 | PrincipalField::MemberOf
                                 | PrincipalField::Members
                                 | PrincipalField::Lists
-                                | PrincipalField::Urls => (),
+                                | PrincipalField::Urls
+                                | PrincipalField::LastLogin
+                                | PrincipalField::PasswordHistory
+                                | PrincipalField::ProvisionedBy => (),
                                 PrincipalField::Tenant => {
                                     // Tenants are not allowed to change their tenantId
                                     if access_token.tenant.is_some() {
@@ -495,18 +512,20 @@ impl PrincipalManager for Server {
                         }
 
                         if needs_assert {
-                            self.assert_supported_directory()?;
+                            self.assert_supported_directory(true)?;
                         }
 
                         // Update principal
                         self.core
                             .storage
-                            .data
+                            .directory
                             .update_principal(
+                                &self.core.storage.data,
                                 UpdatePrincipal::by_id(account_id)
                                     .with_updates(changes)
                                     .with_tenant(access_token.tenant.map(|t| t.id))
-                                    .with_allowed_permissions(&access_token.permissions),
+                                    .with_allowed_permissions(&access_token.permissions)
+                                    .with_password_policy(self.core.auth.password_policy.as_ref()),
                             )
                             .await?;
 
@@ -618,6 +637,18 @@ impl PrincipalManager for Server {
             ));
         }
 
+        // Pre-hashed secrets would bypass the password policy
+        if requests.iter().any(|r| {
+            matches!(
+                r,
+                AccountAuthRequest::SetPassword { password }
+                    | AccountAuthRequest::AddAppPassword { password, .. }
+                    if is_password_hash(password)
+            )
+        }) {
+            return Err(violation("hashed", "Pre-hashed passwords are not allowed"));
+        }
+
         // Handle Fallback admin password changes
         if access_token.primary_id() == u32::MAX {
             match requests.into_iter().next().unwrap() {
@@ -649,7 +680,7 @@ impl PrincipalManager for Server {
         }
 
         // Make sure the current directory supports updates
-        self.assert_supported_directory()?;
+        self.assert_supported_directory(true)?;
 
         // Build actions
         let mut actions = Vec::with_capacity(requests.len());
@@ -687,11 +718,13 @@ impl PrincipalManager for Server {
         // Update password
         self.core
             .storage
-            .data
+            .directory
             .update_principal(
+                &self.core.storage.data,
                 UpdatePrincipal::by_id(access_token.primary_id())
                     .with_updates(actions)
-                    .with_tenant(access_token.tenant.map(|t| t.id)),
+                    .with_tenant(access_token.tenant.map(|t| t.id))
+                    .with_password_policy(self.core.auth.password_policy.as_ref()),
             )
             .await?;
 
@@ -700,6 +733,10 @@ impl PrincipalManager for Server {
             .data
             .http_auth_cache
             .retain(|_, id| id.item != access_token.primary_id());
+        self.inner
+            .data
+            .access_tokens
+            .remove(&access_token.primary_id());
 
         Ok(JsonResponse::new(json!({
             "data": (),
@@ -707,14 +744,16 @@ impl PrincipalManager for Server {
         .into_http_response())
     }
 
-    fn assert_supported_directory(&self) -> trc::Result<()> {
+    fn assert_supported_directory(&self, is_update: bool) -> trc::Result<()> {
         let class = match &self.core.storage.directory.store {
             DirectoryInner::Internal(_) => return Ok(()),
+            DirectoryInner::Ldap(store) if is_update && store.is_writable() => return Ok(()),
             DirectoryInner::Ldap(_) => "LDAP",
             DirectoryInner::Sql(_) => "SQL",
             DirectoryInner::Imap(_) => "IMAP",
             DirectoryInner::Smtp(_) => "SMTP",
             DirectoryInner::Memory(_) => "In-Memory",
+            DirectoryInner::Composite(_) => "Composite",
             #[cfg(feature = "enterprise")]
             DirectoryInner::OpenId(_) => "OpenID",
         };
@@ -17,6 +17,14 @@ use directory::{
 };
 use hyper::Method;
 use serde_json::json;
+use store::{
+    ahash::AHashMap,
+    write::{
+        blob::{BlobAccountUsage, MAX_LISTED_BLOBS},
+        BlobOp,
+    },
+};
+use trc::{Collector, MetricType};
 use utils::url_params::UrlParams;
 
 use crate::{
@@ -24,7 +32,8 @@ use crate::{
         http::{HttpSessionData, ToHttpResponse},
         HttpRequest, HttpResponse, JsonResponse,
     },
-    services::index::Indexer,
+    changes::write::ChangeLog,
+    services::{fsck::StoreCheck, index::Indexer},
 };
 
 use super::decode_path_element;
@@ -46,6 +55,10 @@ pub trait ManageStore: Sync + Send {
         &self,
         event: HousekeeperEvent,
     ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+
+    fn blob_usage(&self) -> impl Future<Output = trc::Result<serde_json::Value>> + Send;
+
+    fn blob_purge_dry_run(&self) -> impl Future<Output = trc::Result<serde_json::Value>> + Send;
 }
 
 impl ManageStore for Server {
@@ -98,12 +111,28 @@ impl ManageStore for Server {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::PurgeBlobStore)?;
 
+                if UrlParams::new(req.uri().query()).parse("dry-run") == Some(true) {
+                    return Ok(JsonResponse::new(json!({
+                        "data": self.blob_purge_dry_run().await?,
+                    }))
+                    .into_http_response());
+                }
+
                 self.housekeeper_request(HousekeeperEvent::Purge(PurgeType::Blobs {
                     store: self.core.storage.data.clone(),
                     blob_store: self.core.storage.blob.clone(),
                 }))
                 .await
             }
+            (Some("usage"), Some("blob"), None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::BlobUsage)?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": self.blob_usage().await?,
+                }))
+                .into_http_response())
+            }
             (Some("purge"), Some("data"), id, &Method::GET) => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::PurgeDataStore)?;
@@ -157,6 +186,141 @@ impl ManageStore for Server {
                 self.housekeeper_request(HousekeeperEvent::Purge(PurgeType::Account(account_id)))
                     .await
             }
+            (Some("backup"), Some("account"), Some(name), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::Backup)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(name).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": self.core.backup_account(account_id).await?,
+                }))
+                .into_http_response())
+            }
+            (Some("backup"), kind, None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::Backup)?;
+
+                if self.core.storage.backup.is_none() {
+                    return Err(manage::not_found("backup"));
+                }
+
+                self.housekeeper_request(HousekeeperEvent::Backup {
+                    full: kind == Some("full"),
+                })
+                .await
+            }
+            (Some("backups"), None, None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::Backup)?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": self.core.list_backups()?,
+                }))
+                .into_http_response())
+            }
+            (Some("backups"), Some("accounts"), None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::Backup)?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": self.core.list_account_backups()?,
+                }))
+                .into_http_response())
+            }
+            (Some("restore"), Some(name), Some(id), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::Restore)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(name).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+                let changes = self
+                    .core
+                    .restore_account(decode_path_element(id).as_ref(), account_id)
+                    .await?;
+                if !changes.is_empty() {
+                    self.commit_changes(account_id, changes).await?;
+                }
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            (Some("migrate"), Some(store_id), None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::Migrate)?;
+
+                let store_id = decode_path_element(store_id).into_owned();
+                if !self.core.storage.stores.contains_key(&store_id) {
+                    return Err(manage::not_found(store_id));
+                }
+
+                self.housekeeper_request(HousekeeperEvent::Migrate {
+                    store: store_id,
+                    blob: UrlParams::new(req.uri().query())
+                        .get("blob")
+                        .map(|blob| blob.to_string()),
+                })
+                .await
+            }
+            (Some("migrations"), None, None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::Migrate)?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": self.core.list_migrations()?,
+                }))
+                .into_http_response())
+            }
+            (Some("fsck"), id, None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::Fsck)?;
+
+                let account_id = if let Some(id) = id {
+                    self.core
+                        .storage
+                        .data
+                        .get_principal_id(decode_path_element(id).as_ref())
+                        .await?
+                        .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?
+                        .into()
+                } else {
+                    None
+                };
+                let repair = UrlParams::new(req.uri().query()).parse("repair") == Some(true);
+
+                Ok(JsonResponse::new(json!({
+                    "data": self.start_check(account_id, repair).await?.to_string(),
+                }))
+                .into_http_response())
+            }
+            (Some("fsck-jobs"), Some(id), None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::Fsck)?;
+
+                let job = if let Ok(id) = id.parse::<u64>() {
+                    self.check_status(id).await?
+                } else {
+                    None
+                };
+
+                Ok(JsonResponse::new(json!({
+                    "data": job.ok_or_else(|| trc::ManageEvent::NotFound.into_err())?,
+                }))
+                .into_http_response())
+            }
             (Some("reindex"), id, None, &Method::GET) => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::FtsReindex)?;
@@ -232,4 +396,105 @@ impl ManageStore for Server {
         }))
         .into_http_response())
     }
+
+    async fn blob_usage(&self) -> trc::Result<serde_json::Value> {
+        let usage = self
+            .core
+            .storage
+            .data
+            .blob_usage(&self.core.storage.blob)
+            .await?;
+
+        Collector::update_gauge(MetricType::BlobCount, usage.blobs);
+        Collector::update_gauge(MetricType::BlobPhysicalSize, usage.physical_bytes);
+        Collector::update_gauge(MetricType::BlobLogicalSize, usage.logical_bytes);
+
+        // Group account usage by tenant, blobs shared between the accounts
+        // of a tenant are counted once per account
+        let mut accounts = Vec::with_capacity(usage.accounts.len());
+        let mut tenants: AHashMap<u32, BlobAccountUsage> = AHashMap::new();
+        for (account_id, account) in usage.accounts {
+            let principal = self.core.storage.data.get_principal(account_id).await?;
+            if let Some(tenant_id) = principal.as_ref().and_then(|p| p.tenant()) {
+                let tenant = tenants.entry(tenant_id).or_default();
+                tenant.links += account.links;
+                tenant.physical_bytes += account.physical_bytes;
+                tenant.logical_bytes += account.logical_bytes;
+            }
+            accounts.push(json!({
+                "accountId": account_id,
+                "name": principal.as_ref().map(|p| p.name()),
+                "links": account.links,
+                "physicalBytes": account.physical_bytes,
+                "logicalBytes": account.logical_bytes,
+            }));
+        }
+        let mut tenant_list = Vec::with_capacity(tenants.len());
+        for (tenant_id, tenant) in tenants {
+            tenant_list.push(json!({
+                "tenantId": tenant_id,
+                "name": self
+                    .core
+                    .storage
+                    .data
+                    .get_principal(tenant_id)
+                    .await?
+                    .map(|p| p.name().to_string()),
+                "links": tenant.links,
+                "physicalBytes": tenant.physical_bytes,
+                "logicalBytes": tenant.logical_bytes,
+            }));
+        }
+
+        Ok(json!({
+            "blobs": usage.blobs,
+            "links": usage.links,
+            "physicalBytes": usage.physical_bytes,
+            "logicalBytes": usage.logical_bytes,
+            "savedBytes": usage.logical_bytes.saturating_sub(usage.physical_bytes),
+            "accounts": accounts,
+            "tenants": tenant_list,
+            "purge": {
+                "blobs": usage.purge.blobs.iter().map(|hash| hash.to_hex()).collect::<Vec<_>>(),
+                "count": usage.purge.count,
+                "bytes": usage.purge.bytes,
+                "expiredReservations": usage.purge.expired_reservations,
+            },
+        }))
+    }
+
+    async fn blob_purge_dry_run(&self) -> trc::Result<serde_json::Value> {
+        let mut blobs = Vec::new();
+        let mut count = 0;
+        let mut bytes = 0;
+        let mut expired_reservations = 0;
+        for (_, op) in self.core.storage.data.blob_purge_candidates().await? {
+            match op {
+                BlobOp::Commit { hash } => {
+                    bytes += self
+                        .core
+                        .storage
+                        .blob
+                        .blob_size(hash.as_slice())
+                        .await?
+                        .unwrap_or_default();
+                    count += 1;
+                    if blobs.len() < MAX_LISTED_BLOBS {
+                        blobs.push(hash.to_hex());
+                    }
+                }
+                BlobOp::Reserve { .. } => {
+                    expired_reservations += 1;
+                }
+                _ => (),
+            }
+        }
+
+        Ok(json!({
+            "blobs": blobs,
+            "count": count,
+            "bytes": bytes,
+            "expiredReservations": expired_reservations,
+        }))
+    }
 }
@@ -27,10 +27,14 @@ use crate::{
     },
     identity::{get::IdentityGet, set::IdentitySet},
     mailbox::{get::MailboxGet, query::MailboxQuery, set::MailboxSet},
+    mdn::{parse::MdnParse, send::MdnSend},
     principal::{get::PrincipalGet, query::PrincipalQuery},
     push::{get::PushSubscriptionFetch, set::PushSubscriptionSet},
     quota::{get::QuotaGet, query::QuotaQuery},
     services::state::StateManager,
+    share_notification::{
+        get::ShareNotificationGet, query::ShareNotificationQuery, set::ShareNotificationSet,
+    },
     sieve::{
         get::SieveScriptGet, query::SieveScriptQuery, set::SieveScriptSet,
         validate::SieveScriptValidate,
@@ -235,6 +239,11 @@ impl RequestHandler for Server {
                         .await?
                         .into()
                 }
+                get::RequestArguments::ShareNotification => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.share_notification_get(req).await?.into()
+                }
             },
             RequestMethod::Query(mut req) => match req.take_arguments() {
                 query::RequestArguments::Email(arguments) => {
@@ -269,6 +278,11 @@ impl RequestHandler for Server {
 
                     self.quota_query(req, access_token).await?.into()
                 }
+                query::RequestArguments::ShareNotification => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.share_notification_query(req).await?.into()
+                }
             },
             RequestMethod::Set(mut req) => match req.take_arguments() {
                 set::RequestArguments::Email => {
@@ -314,6 +328,11 @@ impl RequestHandler for Server {
 
                     self.vacation_response_set(req, access_token).await?.into()
                 }
+                set::RequestArguments::ShareNotification => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.share_notification_set(req).await?.into()
+                }
             },
             RequestMethod::Changes(req) => self.changes(req, access_token).await?.into(),
             RequestMethod::Copy(req) => {
@@ -361,6 +380,18 @@ impl RequestHandler for Server {
 
                 self.blob_upload_many(req, access_token).await?.into()
             }
+            RequestMethod::SendMdn(req) => {
+                access_token.assert_is_member(req.account_id)?;
+
+                self.mdn_send(req, &session.instance, next_call)
+                    .await?
+                    .into()
+            }
+            RequestMethod::ParseMdn(req) => {
+                access_token.assert_has_access(req.account_id, Collection::Email)?;
+
+                self.mdn_parse(req, access_token).await?.into()
+            }
             RequestMethod::Echo(req) => req.into(),
             RequestMethod::Error(error) => return Err(error),
         };
@@ -43,6 +43,10 @@ impl SessionHandler for Server {
             None,
             &self.core.jmap.capabilities.account,
         );
+        session.add_principal_capabilities(
+            access_token.primary_id().into(),
+            access_token.primary_id().into(),
+        );
 
         // Add secondary accounts
         for id in access_token.secondary_ids() {
@@ -66,9 +70,15 @@ impl SessionHandler for Server {
                     .unwrap_or_else(|| Id::from(*id).to_string()),
                 is_personal,
                 is_readonly,
-                Some(&[Capability::Mail, Capability::Quota, Capability::Blob]),
+                Some(&[
+                    Capability::Mail,
+                    Capability::Quota,
+                    Capability::Blob,
+                    Capability::Mdn,
+                ]),
                 &self.core.jmap.capabilities.account,
             );
+            session.add_principal_capabilities((*id).into(), access_token.primary_id().into());
         }
 
         Ok(session)
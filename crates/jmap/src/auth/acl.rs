@@ -94,6 +94,11 @@ pub trait AclMethods: Sync + Send {
         &self,
         acl_patch: Vec<Value>,
     ) -> impl Future<Output = Result<(AclGrant, Option<bool>), SetError>> + Send;
+
+    fn map_acl_grantee(
+        &self,
+        grantee: &Value,
+    ) -> impl Future<Output = Result<u32, SetError>> + Send;
 }
 
 impl AclMethods for Server {
@@ -261,7 +266,7 @@ impl AclMethods for Server {
                     .set(Property::Acl, Value::Acl(self.map_acl_set(values).await?));
             }
             MaybePatchValue::Patch(patch) => {
-                let (mut patch, is_update) = self.map_acl_patch(patch).await?;
+                let (patch, is_update) = self.map_acl_patch(patch).await?;
                 let acl = if let Value::Acl(acl) =
                     changes
                         .properties
@@ -285,11 +290,12 @@ impl AclMethods for Server {
                             .iter_mut()
                             .find(|item| item.account_id == patch.account_id)
                         {
-                            let item = patch.grants.pop().unwrap();
                             if is_set {
-                                acl_item.grants.insert(item);
+                                acl_item.grants.union(&patch.grants);
                             } else {
-                                acl_item.grants.remove(item);
+                                acl_item.grants = Bitmap::from(
+                                    u64::from(acl_item.grants) & !u64::from(patch.grants),
+                                );
                                 if acl_item.grants.is_empty() {
                                     acl.retain(|item| item.account_id != patch.account_id);
                                 }
@@ -399,31 +405,11 @@ impl AclMethods for Server {
     async fn map_acl_set(&self, acl_set: Vec<Value>) -> Result<Vec<AclGrant>, SetError> {
         let mut acls = Vec::with_capacity(acl_set.len() / 2);
         for item in acl_set.chunks_exact(2) {
-            if let (Value::Text(account_name), Value::UnsignedInt(grants)) = (&item[0], &item[1]) {
-                match self
-                    .core
-                    .storage
-                    .directory
-                    .query(QueryBy::Name(account_name), false)
-                    .await
-                {
-                    Ok(Some(principal)) => {
-                        acls.push(AclGrant {
-                            account_id: principal.id(),
-                            grants: Bitmap::from(*grants),
-                        });
-                    }
-                    Ok(None) => {
-                        return Err(SetError::invalid_properties()
-                            .with_property(Property::Acl)
-                            .with_description(format!("Account {account_name} does not exist.")));
-                    }
-                    _ => {
-                        return Err(SetError::forbidden()
-                            .with_property(Property::Acl)
-                            .with_description("Temporary server failure during lookup"));
-                    }
-                }
+            if let Value::UnsignedInt(grants) = &item[1] {
+                acls.push(AclGrant {
+                    account_id: self.map_acl_grantee(&item[0]).await?,
+                    grants: Bitmap::from(*grants),
+                });
             } else {
                 return Err(SetError::invalid_properties()
                     .with_property(Property::Acl)
@@ -438,36 +424,45 @@ impl AclMethods for Server {
         &self,
         acl_patch: Vec<Value>,
     ) -> Result<(AclGrant, Option<bool>), SetError> {
-        if let (Value::Text(account_name), Value::UnsignedInt(grants)) =
-            (&acl_patch[0], &acl_patch[1])
-        {
-            match self
-                .core
-                .storage
-                .directory
-                .query(QueryBy::Name(account_name), false)
-                .await
-            {
-                Ok(Some(principal)) => Ok((
-                    AclGrant {
-                        account_id: principal.id(),
-                        grants: Bitmap::from(*grants),
-                    },
-                    acl_patch.get(2).map(|v| v.as_bool().unwrap_or(false)),
-                )),
-                Ok(None) => Err(SetError::invalid_properties()
-                    .with_property(Property::Acl)
-                    .with_description(format!("Account {account_name} does not exist."))),
-                _ => Err(SetError::forbidden()
-                    .with_property(Property::Acl)
-                    .with_description("Temporary server failure during lookup")),
-            }
+        if let Value::UnsignedInt(grants) = &acl_patch[1] {
+            Ok((
+                AclGrant {
+                    account_id: self.map_acl_grantee(&acl_patch[0]).await?,
+                    grants: Bitmap::from(*grants),
+                },
+                acl_patch.get(2).map(|v| v.as_bool().unwrap_or(false)),
+            ))
         } else {
             Err(SetError::invalid_properties()
                 .with_property(Property::Acl)
                 .with_description("Invalid ACL value found."))
         }
     }
+
+    async fn map_acl_grantee(&self, grantee: &Value) -> Result<u32, SetError> {
+        let (query, grantee) = match grantee {
+            Value::Text(account_name) => (QueryBy::Name(account_name), account_name.to_string()),
+            Value::Id(principal_id) => (
+                QueryBy::Id(principal_id.document_id()),
+                principal_id.to_string(),
+            ),
+            _ => {
+                return Err(SetError::invalid_properties()
+                    .with_property(Property::Acl)
+                    .with_description("Invalid ACL value found."));
+            }
+        };
+
+        match self.core.storage.directory.query(query, false).await {
+            Ok(Some(principal)) => Ok(principal.id()),
+            Ok(None) => Err(SetError::invalid_properties()
+                .with_property(Property::Acl)
+                .with_description(format!("Account {grantee} does not exist."))),
+            _ => Err(SetError::forbidden()
+                .with_property(Property::Acl)
+                .with_description("Temporary server failure during lookup")),
+        }
+    }
 }
 
 pub trait EffectiveAcl {
@@ -65,6 +65,11 @@ impl ChangesLookup for Server {
 
                 return Err(trc::JmapEvent::CannotCalculateChanges.into_err());
             }
+            RequestArguments::ShareNotification => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::ShareNotification
+            }
         };
 
         let max_changes = if self.core.jmap.changes_max_results > 0
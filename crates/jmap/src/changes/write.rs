@@ -88,6 +88,7 @@ impl ChangeLog for Server {
             Collection::Thread,
             Collection::Identity,
             Collection::EmailSubmission,
+            Collection::ShareNotification,
         ] {
             self.core
                 .storage
@@ -30,7 +30,7 @@ use crate::{
     JmapMethods,
 };
 
-use super::{index::EmailIndexBuilder, metadata::MessageMetadata};
+use super::{index::EmailIndexBuilder, metadata::MessageMetadata, snooze::SnoozeDetails};
 use rand::prelude::SliceRandom;
 use std::future::Future;
 
@@ -472,6 +472,22 @@ impl EmailDeletion for Server {
                 );
             }
 
+            // Remove snooze
+            if let Some(snooze) = self
+                .core
+                .storage
+                .data
+                .get_value::<Bincode<SnoozeDetails>>(ValueKey {
+                    account_id,
+                    collection: Collection::Email.into(),
+                    document_id,
+                    class: ValueClass::Property(Property::Snoozed.into()),
+                })
+                .await?
+            {
+                snooze.inner.clear(&mut batch);
+            }
+
             // Remove message metadata
             if let Some(metadata) = self
                 .core
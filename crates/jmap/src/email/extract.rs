@@ -0,0 +1,432 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    io::{Cursor, Read},
+    time::Instant,
+};
+
+use common::config::jmap::settings::{AttachmentType, JmapConfig};
+use mail_parser::{MessagePart, MimeHeaders, PartType};
+use quick_xml::{events::Event, Reader};
+use zip::ZipArchive;
+
+struct XmlRules {
+    entries: fn(&str) -> bool,
+    text_tags: &'static [&'static [u8]],
+    break_tags: &'static [&'static [u8]],
+}
+
+const DOCX: XmlRules = XmlRules {
+    entries: |name| {
+        name.strip_prefix("word/").is_some_and(|name| {
+            name == "document.xml"
+                || ["header", "footer", "footnotes", "endnotes"]
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+        })
+    },
+    text_tags: &[b"t"],
+    break_tags: &[b"p", b"tab", b"br", b"cr"],
+};
+
+const XLSX: XmlRules = XmlRules {
+    entries: |name| name == "xl/sharedStrings.xml" || name.starts_with("xl/worksheets/sheet"),
+    text_tags: &[b"t"],
+    break_tags: &[b"si", b"is"],
+};
+
+const PPTX: XmlRules = XmlRules {
+    entries: |name| {
+        name.starts_with("ppt/slides/slide") || name.starts_with("ppt/notesSlides/notesSlide")
+    },
+    text_tags: &[b"t"],
+    break_tags: &[b"p", b"br"],
+};
+
+const ODF: XmlRules = XmlRules {
+    entries: |name| name == "content.xml",
+    text_tags: &[b"p", b"h"],
+    break_tags: &[b"p", b"h", b"tab", b"s", b"line-break"],
+};
+
+const ICS_PROPERTIES: &[&str] = &[
+    "SUMMARY",
+    "DESCRIPTION",
+    "LOCATION",
+    "COMMENT",
+    "CATEGORIES",
+    "CONTACT",
+    "RESOURCES",
+    "ORGANIZER",
+    "ATTENDEE",
+];
+
+const VCF_PROPERTIES: &[&str] = &[
+    "FN",
+    "N",
+    "NICKNAME",
+    "ORG",
+    "TITLE",
+    "ROLE",
+    "EMAIL",
+    "TEL",
+    "ADR",
+    "NOTE",
+    "URL",
+    "CATEGORIES",
+];
+
+struct TextExtractor {
+    text: String,
+    max_len: usize,
+    deadline: Instant,
+}
+
+// Extracts the text of an attachment, limits are enforced by returning the text
+// obtained until the size or time limit was reached.
+pub(super) fn extract_attachment_text(
+    part: &MessagePart<'_>,
+    config: &JmapConfig,
+) -> Option<String> {
+    let bytes = match &part.body {
+        PartType::Binary(bytes) | PartType::InlineBinary(bytes) => bytes.as_ref(),
+        PartType::Text(text) => text.as_bytes(),
+        _ => return None,
+    };
+    if bytes.is_empty() || bytes.len() > config.fts_attachment_max_size {
+        return None;
+    }
+    let attachment_type = attachment_type(part)
+        .filter(|attachment_type| config.fts_attachment_types.contains(attachment_type))?;
+    let mut extractor = TextExtractor {
+        text: String::new(),
+        max_len: config.fts_attachment_max_size,
+        deadline: Instant::now() + config.fts_attachment_timeout,
+    };
+
+    match attachment_type {
+        AttachmentType::Docx => extractor.extract_zip(bytes, &DOCX),
+        AttachmentType::Xlsx => extractor.extract_zip(bytes, &XLSX),
+        AttachmentType::Pptx => extractor.extract_zip(bytes, &PPTX),
+        AttachmentType::Odt | AttachmentType::Ods | AttachmentType::Odp => {
+            extractor.extract_zip(bytes, &ODF)
+        }
+        AttachmentType::Csv => extractor.push(&String::from_utf8_lossy(bytes)),
+        AttachmentType::Ics => extractor.extract_properties(bytes, ICS_PROPERTIES),
+        AttachmentType::Vcf => extractor.extract_properties(bytes, VCF_PROPERTIES),
+    }
+
+    Some(extractor.text).filter(|text| !text.trim().is_empty())
+}
+
+fn attachment_type(part: &MessagePart<'_>) -> Option<AttachmentType> {
+    part.content_type()
+        .and_then(|content_type| {
+            match (
+                content_type.ctype().to_ascii_lowercase().as_str(),
+                content_type
+                    .subtype()
+                    .unwrap_or_default()
+                    .to_ascii_lowercase()
+                    .as_str(),
+            ) {
+                ("application", "vnd.openxmlformats-officedocument.wordprocessingml.document") => {
+                    Some(AttachmentType::Docx)
+                }
+                ("application", "vnd.openxmlformats-officedocument.spreadsheetml.sheet") => {
+                    Some(AttachmentType::Xlsx)
+                }
+                (
+                    "application",
+                    "vnd.openxmlformats-officedocument.presentationml.presentation",
+                ) => Some(AttachmentType::Pptx),
+                ("application", "vnd.oasis.opendocument.text") => Some(AttachmentType::Odt),
+                ("application", "vnd.oasis.opendocument.spreadsheet") => Some(AttachmentType::Ods),
+                ("application", "vnd.oasis.opendocument.presentation") => Some(AttachmentType::Odp),
+                ("text", "csv" | "comma-separated-values") => Some(AttachmentType::Csv),
+                ("text", "calendar") | ("application", "ics") => Some(AttachmentType::Ics),
+                ("text", "vcard" | "x-vcard" | "directory") => Some(AttachmentType::Vcf),
+                _ => None,
+            }
+        })
+        .or_else(|| {
+            // Attachments are often sent as application/octet-stream
+            part.attachment_name()
+                .and_then(|name| name.rsplit_once('.'))
+                .and_then(|(_, extension)| AttachmentType::from_extension(extension))
+        })
+}
+
+impl TextExtractor {
+    fn extract_zip(&mut self, bytes: &[u8], rules: &XmlRules) {
+        let mut archive = if let Ok(archive) = ZipArchive::new(Cursor::new(bytes)) {
+            archive
+        } else {
+            return;
+        };
+        let mut xml = Vec::new();
+
+        for idx in 0..archive.len() {
+            if let Ok(file) = archive.by_index(idx) {
+                if file.is_dir() || !(rules.entries)(file.name()) {
+                    continue;
+                }
+
+                // Limit the uncompressed size to guard against zip bombs
+                xml.clear();
+                if file
+                    .take(self.max_len as u64)
+                    .read_to_end(&mut xml)
+                    .is_err()
+                {
+                    continue;
+                }
+                self.extract_xml(&xml, rules);
+                if self.is_done() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn extract_xml(&mut self, xml: &[u8], rules: &XmlRules) {
+        let mut reader = Reader::from_reader(xml);
+        let mut text_depth = 0usize;
+
+        while !self.is_done() {
+            match reader.read_event() {
+                Ok(Event::Start(e)) => {
+                    if has_tag(rules.text_tags, e.local_name().as_ref()) {
+                        text_depth += 1;
+                    }
+                }
+                Ok(Event::End(e)) => {
+                    let name = e.local_name();
+                    if has_tag(rules.text_tags, name.as_ref()) {
+                        text_depth = text_depth.saturating_sub(1);
+                    }
+                    if has_tag(rules.break_tags, name.as_ref()) {
+                        self.push_break();
+                    }
+                }
+                Ok(Event::Empty(e)) => {
+                    if has_tag(rules.break_tags, e.local_name().as_ref()) {
+                        self.push_break();
+                    }
+                }
+                Ok(Event::Text(e)) if text_depth > 0 => {
+                    if let Ok(text) = e.unescape() {
+                        self.push(&text);
+                    }
+                }
+                Ok(Event::CData(e)) if text_depth > 0 => {
+                    self.push(&String::from_utf8_lossy(&e));
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                _ => (),
+            }
+        }
+    }
+
+    fn extract_properties(&mut self, bytes: &[u8], properties: &[&str]) {
+        let contents = String::from_utf8_lossy(bytes);
+
+        // Unfold content lines
+        let mut lines: Vec<String> = Vec::new();
+        for line in contents.lines() {
+            if let Some(line) = line.strip_prefix(|ch| ch == ' ' || ch == '\t') {
+                if let Some(last_line) = lines.last_mut() {
+                    last_line.push_str(line);
+                }
+            } else {
+                lines.push(line.to_string());
+            }
+        }
+
+        for line in lines {
+            if self.is_done() {
+                break;
+            }
+
+            if let Some((name, value)) = split_property(&line) {
+                // Remove parameters and group names
+                let name = name.split(';').next().unwrap_or_default();
+                let name = name.rsplit('.').next().unwrap_or_default();
+
+                if properties
+                    .iter()
+                    .any(|property| property.eq_ignore_ascii_case(name))
+                {
+                    self.push(
+                        &value
+                            .replace("\\n", " ")
+                            .replace("\\N", " ")
+                            .replace("\\,", ",")
+                            .replace("\\;", ";")
+                            .replace("\\\\", "\\"),
+                    );
+                    self.push_break();
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        let mut len = std::cmp::min(text.len(), self.max_len.saturating_sub(self.text.len()));
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.text.push_str(&text[..len]);
+    }
+
+    fn push_break(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with(char::is_whitespace) {
+            self.text.push('\n');
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.text.len() >= self.max_len || Instant::now() >= self.deadline
+    }
+}
+
+fn split_property(line: &str) -> Option<(&str, &str)> {
+    // Colons can appear in quoted parameter values
+    let mut in_quotes = false;
+    for (pos, ch) in line.char_indices() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => return Some((&line[..pos], &line[pos + 1..])),
+            _ => (),
+        }
+    }
+    None
+}
+
+fn has_tag(tags: &[&[u8]], name: &[u8]) -> bool {
+    tags.iter().any(|tag| *tag == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use common::config::jmap::settings::{AttachmentType, JmapConfig};
+    use mail_parser::MessageParser;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::extract_attachment_text;
+
+    #[test]
+    fn extract_attachments() {
+        let config = JmapConfig {
+            fts_attachment_types: AttachmentType::ALL.to_vec(),
+            fts_attachment_max_size: 1024 * 1024,
+            fts_attachment_timeout: std::time::Duration::from_secs(5),
+            ..Default::default()
+        };
+
+        for (file_name, contents, expected) in [
+            (
+                "report.docx",
+                zip_file(
+                    "word/document.xml",
+                    concat!(
+                        "<w:document xmlns:w=\"w\"><w:body>",
+                        "<w:p><w:r><w:t>Quarterly</w:t></w:r><w:r><w:t xml:space=\"preserve\"> ",
+                        "report</w:t></w:r></w:p><w:p><w:r><w:t>Tom &amp; Jerry</w:t></w:r>",
+                        "<w:r><w:delText>deleted</w:delText></w:r></w:p>",
+                        "</w:body></w:document>"
+                    ),
+                ),
+                "Quarterly report\nTom & Jerry\n",
+            ),
+            (
+                "budget.xlsx",
+                zip_file(
+                    "xl/sharedStrings.xml",
+                    concat!(
+                        "<sst><si><t>Revenue</t></si>",
+                        "<si><r><t>Net</t></r><r><t xml:space=\"preserve\"> income</t></r></si></sst>"
+                    ),
+                ),
+                "Revenue\nNet income\n",
+            ),
+            (
+                "slides.pptx",
+                zip_file(
+                    "ppt/slides/slide1.xml",
+                    concat!(
+                        "<p:sld xmlns:a=\"a\" xmlns:p=\"p\"><a:p><a:r><a:t>Roadmap</a:t></a:r></a:p>",
+                        "<a:p><a:r><a:t>Launch</a:t></a:r></a:p></p:sld>"
+                    ),
+                ),
+                "Roadmap\nLaunch\n",
+            ),
+            (
+                "notes.odt",
+                zip_file(
+                    "content.xml",
+                    concat!(
+                        "<office:document-content xmlns:text=\"t\" xmlns:office=\"o\">",
+                        "<office:body><office:text><text:h>Minutes</text:h>",
+                        "<text:p>Action<text:s/><text:span>items</text:span></text:p>",
+                        "</office:text></office:body></office:document-content>"
+                    ),
+                ),
+                "Minutes\nAction\nitems\n",
+            ),
+            (
+                "invite.ics",
+                concat!(
+                    "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:1234\r\n",
+                    "SUMMARY;LANGUAGE=en:Project kickoff\r\n",
+                    "DESCRIPTION:Agenda\\, budget and\r\n  schedule\r\n",
+                    "DTSTART:20240101T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+                )
+                .as_bytes()
+                .to_vec(),
+                "Project kickoff\nAgenda, budget and schedule\n",
+            ),
+            (
+                "contact.vcf",
+                concat!(
+                    "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Jane Doe\r\n",
+                    "item1.EMAIL;TYPE=\"work:main\":jane@example.org\r\nEND:VCARD\r\n"
+                )
+                .as_bytes()
+                .to_vec(),
+                "Jane Doe\njane@example.org\n",
+            ),
+        ] {
+            let message = format!(
+                concat!(
+                    "Content-Type: application/octet-stream\r\n",
+                    "Content-Disposition: attachment; filename=\"{}\"\r\n",
+                    "Content-Transfer-Encoding: base64\r\n\r\n{}\r\n"
+                ),
+                file_name,
+                STANDARD.encode(&contents)
+            );
+            let message = MessageParser::new().parse(message.as_bytes()).unwrap();
+
+            assert_eq!(
+                extract_attachment_text(message.part(0).unwrap(), &config).as_deref(),
+                Some(expected),
+                "{file_name}"
+            );
+        }
+    }
+
+    fn zip_file(name: &str, contents: &str) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+}
@@ -34,6 +34,7 @@ use super::{
     cache::ThreadCache,
     headers::IntoForm,
     metadata::{MessageMetadata, MetadataPartType},
+    snooze::SnoozeDetails,
 };
 
 pub trait EmailGet: Sync + Send {
@@ -423,6 +424,34 @@ impl EmailGet for Server {
                         }
                         email.append(Property::BodyValues, body_values);
                     }
+                    Property::Snoozed => {
+                        email.append(
+                            Property::Snoozed,
+                            self.get_property::<Bincode<SnoozeDetails>>(
+                                account_id,
+                                Collection::Email,
+                                id.document_id(),
+                                Property::Snoozed,
+                            )
+                            .await?
+                            .map(|snooze| {
+                                Value::Object(
+                                    Object::with_capacity(2)
+                                        .with_property(
+                                            Property::Until,
+                                            Value::Date(UTCDate::from_timestamp(
+                                                snooze.inner.until as i64,
+                                            )),
+                                        )
+                                        .with_property(
+                                            Property::MoveToMailboxId,
+                                            Id::from(snooze.inner.move_to),
+                                        ),
+                                )
+                            })
+                            .unwrap_or(Value::Null),
+                        );
+                    }
 
                     _ => {
                         return Err(trc::JmapEvent::InvalidArguments
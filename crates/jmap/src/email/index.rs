@@ -6,6 +6,7 @@
 
 use std::borrow::Cow;
 
+use common::config::jmap::settings::JmapConfig;
 use jmap_proto::types::{keyword::Keyword, property::Property};
 use mail_parser::{
     decoders::html::html_to_text,
@@ -23,7 +24,7 @@ use utils::BlobHash;
 
 use crate::mailbox::UidMailbox;
 
-use super::metadata::MessageMetadata;
+use super::{extract::extract_attachment_text, metadata::MessageMetadata};
 
 pub const MAX_MESSAGE_PARTS: usize = 1000;
 pub const MAX_ID_LENGTH: usize = 100;
@@ -54,7 +55,7 @@ pub(super) trait IndexMessage {
 }
 
 pub trait IndexMessageText<'x>: Sized {
-    fn index_message(self, message: &'x Message<'x>) -> Self;
+    fn index_message(self, message: &'x Message<'x>, config: &JmapConfig) -> Self;
 }
 
 impl IndexMessage for BatchBuilder {
@@ -276,7 +277,7 @@ impl IndexMessage for BatchBuilder {
 }
 
 impl<'x> IndexMessageText<'x> for FtsDocument<'x, HeaderName<'x>> {
-    fn index_message(mut self, message: &'x Message<'x>) -> Self {
+    fn index_message(mut self, message: &'x Message<'x>, config: &JmapConfig) -> Self {
         let mut language = Language::Unknown;
 
         for (part_id, part) in message.parts.iter().take(MAX_MESSAGE_PARTS).enumerate() {
@@ -340,6 +341,8 @@ impl<'x> IndexMessageText<'x> for FtsDocument<'x, HeaderName<'x>> {
                     if message.text_body.contains(&part_id) || message.html_body.contains(&part_id)
                     {
                         self.index(Field::Body, text.as_ref(), part_language);
+                    } else if let Some(text) = extract_attachment_text(part, config) {
+                        self.index(Field::Attachment, text, part_language);
                     } else {
                         self.index(Field::Attachment, text.as_ref(), part_language);
                     }
@@ -374,10 +377,20 @@ impl<'x> IndexMessageText<'x> for FtsDocument<'x, HeaderName<'x>> {
                             PartType::Html(html) => {
                                 self.index(Field::Attachment, html_to_text(html), language);
                             }
+                            PartType::Binary(_) | PartType::InlineBinary(_) => {
+                                if let Some(text) = extract_attachment_text(sub_part, config) {
+                                    self.index(Field::Attachment, text, language);
+                                }
+                            }
                             _ => (),
                         }
                     }
                 }
+                PartType::Binary(_) | PartType::InlineBinary(_) => {
+                    if let Some(text) = extract_attachment_text(part, config) {
+                        self.index(Field::Attachment, text, part_language);
+                    }
+                }
                 _ => {}
             }
         }
@@ -40,8 +40,9 @@ use crate::{
     blob::upload::BlobUpload,
     changes::write::ChangeLog,
     email::index::{IndexMessage, VisitValues, MAX_ID_LENGTH},
-    mailbox::{UidMailbox, INBOX_ID, JUNK_ID},
+    mailbox::{get::MailboxGet, UidMailbox, INBOX_ID, JUNK_ID},
     services::index::Indexer,
+    thread::mute::ThreadMute,
     JmapMethods,
 };
 
@@ -221,6 +222,34 @@ impl EmailIngest for Server {
             }
         };
 
+        // Mark as read and archive new messages in muted threads
+        if let Some(thread_id) =
+            thread_id.filter(|_| params.source == IngestSource::Smtp && !is_spam)
+        {
+            if self
+                .is_thread_muted(account_id, thread_id)
+                .await
+                .caused_by(trc::location!())?
+            {
+                if !params.keywords.contains(&Keyword::Seen) {
+                    params.keywords.push(Keyword::Seen);
+                }
+                if let Some(pos) = params.mailbox_ids.iter().position(|id| *id == INBOX_ID) {
+                    if let Some(archive_id) = self
+                        .mailbox_get_by_role(account_id, "archive")
+                        .await
+                        .caused_by(trc::location!())?
+                    {
+                        if params.mailbox_ids.contains(&archive_id) {
+                            params.mailbox_ids.remove(pos);
+                        } else {
+                            params.mailbox_ids[pos] = archive_id;
+                        }
+                    }
+                }
+            }
+        }
+
         // Encrypt message
         if params.encrypt && !message.is_encrypted() {
             if let Some(encrypt_params) = self
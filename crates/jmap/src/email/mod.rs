@@ -9,6 +9,7 @@ pub mod cache;
 pub mod copy;
 pub mod crypto;
 pub mod delete;
+pub mod extract;
 pub mod get;
 pub mod headers;
 pub mod import;
@@ -19,3 +20,4 @@ pub mod parse;
 pub mod query;
 pub mod set;
 pub mod snippet;
+pub mod snooze;
@@ -14,6 +14,7 @@ use mail_parser::HeaderName;
 use nlp::language::Language;
 use std::future::Future;
 use store::{
+    ahash::AHashMap,
     fts::{Field, FilterGroup, FtsFilter, IntoFilterGroup},
     query::{self},
     roaring::RoaringBitmap,
@@ -48,6 +49,12 @@ impl EmailQuery for Server {
     ) -> trc::Result<QueryResponse> {
         let account_id = request.account_id.document_id();
         let mut filters = Vec::with_capacity(request.filter.len());
+        let is_fuzzy = request.arguments.fuzzy.unwrap_or(false);
+        let is_ranked = request.sort.as_ref().is_some_and(|sort| {
+            sort.iter()
+                .any(|comparator| comparator.property == SortProperty::Relevance)
+        });
+        let mut scores = AHashMap::new();
 
         for cond_group in std::mem::take(&mut request.filter).into_filter_group() {
             match cond_group {
@@ -180,10 +187,25 @@ impl EmailQuery for Server {
                             }
                         }
                     }
-                    filters.push(query::Filter::is_in_set(
-                        self.fts_filter(account_id, Collection::Email, fts_filters)
-                            .await?,
-                    ));
+                    if is_fuzzy {
+                        fts_filters = fts_filters.into_iter().map(FtsFilter::into_fuzzy).collect();
+                    }
+                    if is_ranked {
+                        let ranked = self
+                            .fts_filter_ranked(account_id, Collection::Email, fts_filters)
+                            .await?;
+                        filters.push(query::Filter::is_in_set(
+                            ranked.keys().copied().collect::<RoaringBitmap>(),
+                        ));
+                        for (document_id, score) in ranked {
+                            *scores.entry(document_id).or_insert(0.0) += score;
+                        }
+                    } else {
+                        filters.push(query::Filter::is_in_set(
+                            self.fts_filter(account_id, Collection::Email, fts_filters)
+                                .await?,
+                        ));
+                    }
                 }
                 FilterGroup::Store(cond) => {
                     match cond {
@@ -351,6 +373,10 @@ impl EmailQuery for Server {
                     SortProperty::Cc => {
                         query::Comparator::field(Property::Cc, comparator.is_ascending)
                     }
+                    SortProperty::Relevance => query::Comparator::score(
+                        std::mem::take(&mut scores),
+                        comparator.is_ascending,
+                    ),
 
                     other => {
                         return Err(trc::JmapEvent::UnsupportedSort
@@ -14,7 +14,7 @@ use jmap_proto::{
     types::{
         acl::Acl,
         collection::Collection,
-        keyword::Keyword,
+        keyword::{Keyword, SNOOZED_KEYWORD},
         property::Property,
         state::{State, StateChange},
         type_state::DataType,
@@ -34,8 +34,8 @@ use store::{
     ahash::AHashSet,
     roaring::RoaringBitmap,
     write::{
-        assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, DeserializeFrom, SerializeInto,
-        ToBitmaps, ValueClass, F_BITMAP, F_CLEAR, F_VALUE,
+        assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, Bincode, DeserializeFrom,
+        SerializeInto, ToBitmaps, ValueClass, F_BITMAP, F_CLEAR, F_VALUE,
     },
     Serialize,
 };
@@ -46,7 +46,7 @@ use crate::{
     auth::acl::AclMethods,
     blob::download::BlobDownload,
     changes::{state::StateManager, write::ChangeLog},
-    mailbox::{set::MailboxSet, UidMailbox},
+    mailbox::{set::MailboxSet, UidMailbox, INBOX_ID},
     JmapMethods,
 };
 use std::future::Future;
@@ -55,6 +55,7 @@ use super::{
     delete::EmailDeletion,
     headers::{BuildHeader, ValueToHeader},
     ingest::{EmailIngest, IngestEmail, IngestSource},
+    snooze::{EmailSnooze, SnoozeDetails},
 };
 
 pub trait EmailSet: Sync + Send {
@@ -795,6 +796,7 @@ impl EmailSet for Server {
             batch
                 .with_account_id(account_id)
                 .with_collection(Collection::Email);
+            let mut snooze = None;
 
             for (property, value) in object.properties {
                 let value = match response.eval_object_references(value) {
@@ -841,6 +843,32 @@ impl EmailSet for Server {
                             );
                         }
                     }
+                    (Property::Snoozed, MaybePatchValue::Value(Value::Object(snoozed))) => {
+                        let move_to = match snoozed.get(&Property::MoveToMailboxId) {
+                            Value::Id(mailbox_id) => mailbox_id.document_id(),
+                            _ => INBOX_ID,
+                        };
+                        match snoozed.get(&Property::Until) {
+                            Value::Date(until) if mailbox_ids.contains(move_to) => {
+                                snooze = Some(Some(SnoozeDetails {
+                                    until: until.timestamp() as u64,
+                                    move_to,
+                                }));
+                            }
+                            _ => {
+                                response.not_updated.append(
+                                    id,
+                                    SetError::invalid_properties()
+                                        .with_property(Property::Snoozed)
+                                        .with_description("Invalid snooze time or target mailbox."),
+                                );
+                                continue 'update;
+                            }
+                        }
+                    }
+                    (Property::Snoozed, MaybePatchValue::Value(Value::Null)) => {
+                        snooze = Some(None);
+                    }
                     (property, _) => {
                         response.invalid_property_update(id, property);
                         continue 'update;
@@ -848,7 +876,15 @@ impl EmailSet for Server {
                 }
             }
 
-            if !mailboxes.has_changes() && !keywords.has_changes() {
+            // Snoozed messages are flagged with the $snoozed keyword
+            if let Some(snooze) = &snooze {
+                keywords.update(
+                    Keyword::Other(SNOOZED_KEYWORD.to_string()),
+                    snooze.is_some(),
+                );
+            }
+
+            if !mailboxes.has_changes() && !keywords.has_changes() && snooze.is_none() {
                 response.not_updated.append(
                     id,
                     SetError::invalid_properties()
@@ -862,6 +898,26 @@ impl EmailSet for Server {
             let mut changed_mailboxes = AHashSet::new();
             changes.log_update(Collection::Email, id);
 
+            // Process snooze
+            let mut snooze_until = None;
+            if let Some(snooze) = snooze {
+                if let Some(current) = self
+                    .get_property::<Bincode<SnoozeDetails>>(
+                        account_id,
+                        Collection::Email,
+                        document_id,
+                        Property::Snoozed,
+                    )
+                    .await?
+                {
+                    current.inner.clear(&mut batch);
+                }
+                if let Some(snooze) = snooze {
+                    snooze.set(&mut batch);
+                    snooze_until = Some(snooze.until);
+                }
+            }
+
             // Process keywords
             if keywords.has_changes() {
                 // Verify permissions on shared accounts
@@ -981,6 +1037,11 @@ impl EmailSet for Server {
                     Ok(_) => {
                         // Add to updated list
                         response.updated.append(id, None);
+
+                        // Schedule wake-up
+                        if let Some(until) = snooze_until {
+                            self.email_snooze_reschedule(until).await;
+                        }
                     }
                     Err(err) if err.is_assertion_failure() => {
                         response.not_updated.append(
@@ -0,0 +1,323 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::{Duration, Instant};
+
+use common::{ipc::HousekeeperEvent, Server};
+use jmap_proto::types::{
+    collection::Collection,
+    id::Id,
+    keyword::{Keyword, SNOOZED_KEYWORD},
+    property::Property,
+    state::StateChange,
+    type_state::DataType,
+};
+use store::{
+    ahash::{AHashMap, AHashSet},
+    write::{
+        assert::HashedValue, key::DeserializeBigEndian, log::ChangeLogBuilder, now, BatchBuilder,
+        Bincode, ValueClass, F_VALUE,
+    },
+    IterateParams, ValueKey, U32_LEN, U64_LEN,
+};
+use trc::AddContext;
+
+use crate::{
+    changes::write::ChangeLog,
+    mailbox::{get::MailboxGet, set::MailboxSet, UidMailbox, INBOX_ID},
+    services::state::StateManager,
+    JmapMethods,
+};
+use std::future::Future;
+
+use super::{ingest::EmailIngest, set::TagManager};
+
+const SNOOZE_RETRY_INTERVAL: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SnoozeDetails {
+    pub until: u64,
+    pub move_to: u32,
+}
+
+pub trait EmailSnooze: Sync + Send {
+    fn email_wake_snoozed(&self) -> impl Future<Output = ()> + Send;
+
+    fn email_wake_account(
+        &self,
+        account_id: u32,
+        due: Vec<(u32, u64)>,
+    ) -> impl Future<Output = trc::Result<Option<u64>>> + Send;
+
+    fn email_snooze_reschedule(&self, until: u64) -> impl Future<Output = ()> + Send;
+}
+
+impl EmailSnooze for Server {
+    async fn email_wake_snoozed(&self) {
+        // Obtain the snoozed messages that are due, sorted by due time
+        let now = now();
+        let mut accounts: AHashMap<u32, Vec<(u32, u64)>> = AHashMap::new();
+        let mut next_due: Option<u64> = None;
+        if let Err(err) = self
+            .core
+            .storage
+            .data
+            .iterate(
+                IterateParams::new(
+                    ValueKey {
+                        account_id: 0,
+                        collection: 0,
+                        document_id: 0,
+                        class: ValueClass::SnoozeEvent(0),
+                    },
+                    ValueKey {
+                        account_id: u32::MAX,
+                        collection: u8::MAX,
+                        document_id: u32::MAX,
+                        class: ValueClass::SnoozeEvent(u64::MAX),
+                    },
+                )
+                .no_values()
+                .ascending(),
+                |key, _| {
+                    let due = key.deserialize_be_u64(0)?;
+                    if due <= now {
+                        accounts
+                            .entry(key.deserialize_be_u32(U64_LEN)?)
+                            .or_default()
+                            .push((key.deserialize_be_u32(U64_LEN + U32_LEN)?, due));
+                        Ok(true)
+                    } else {
+                        next_due = Some(due);
+                        Ok(false)
+                    }
+                },
+            )
+            .await
+        {
+            trc::error!(err
+                .caused_by(trc::location!())
+                .details("Failed to obtain snoozed messages."));
+        }
+
+        for (account_id, due) in accounts {
+            let retry_at = match self.email_wake_account(account_id, due).await {
+                Ok(retry_at) => retry_at,
+                Err(err) => {
+                    trc::error!(err
+                        .account_id(account_id)
+                        .details("Failed to wake up snoozed messages."));
+                    Some(now + SNOOZE_RETRY_INTERVAL)
+                }
+            };
+            if let Some(retry_at) = retry_at {
+                next_due = Some(next_due.map_or(retry_at, |due| due.min(retry_at)));
+            }
+        }
+
+        if let Some(due) = next_due {
+            self.email_snooze_reschedule(due).await;
+        }
+    }
+
+    async fn email_wake_account(
+        &self,
+        account_id: u32,
+        due: Vec<(u32, u64)>,
+    ) -> trc::Result<Option<u64>> {
+        let now = now();
+        let mut retry_at = None;
+        let mailbox_ids = self
+            .mailbox_get_or_create(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let snooze_mailbox_id = self
+            .mailbox_get_by_role(account_id, "snoozed")
+            .await
+            .caused_by(trc::location!())?;
+        let snoozed_keyword = Keyword::Other(SNOOZED_KEYWORD.to_string());
+        let mut changes = ChangeLogBuilder::new();
+        let mut total_woken = 0;
+
+        for (document_id, due) in due {
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Email)
+                .update_document(document_id);
+
+            // Wake-ups left behind by messages that were deleted or snoozed again are removed
+            let snooze = match self
+                .get_property::<Bincode<SnoozeDetails>>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::Snoozed,
+                )
+                .await?
+            {
+                Some(snooze) if snooze.inner.until == due => snooze.inner,
+                _ => {
+                    batch.clear(ValueClass::SnoozeEvent(due));
+                    self.core
+                        .storage
+                        .data
+                        .write(batch.build())
+                        .await
+                        .caused_by(trc::location!())?;
+                    continue;
+                }
+            };
+            snooze.clear(&mut batch);
+            let mut is_woken = false;
+
+            // Messages that were unsnoozed by removing the keyword are left in place
+            if let (Some(mailboxes), Some(keywords), Some(thread_id)) = (
+                self.get_property::<HashedValue<Vec<UidMailbox>>>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::MailboxIds,
+                )
+                .await?,
+                self.get_property::<HashedValue<Vec<Keyword>>>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::Keywords,
+                )
+                .await?,
+                self.get_property::<u32>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::ThreadId,
+                )
+                .await?,
+            ) {
+                let mut mailboxes = TagManager::new(mailboxes);
+                let mut keywords = TagManager::new(keywords);
+
+                if keywords.current().contains(&snoozed_keyword) {
+                    let target_id = if mailbox_ids.contains(snooze.move_to) {
+                        snooze.move_to
+                    } else {
+                        INBOX_ID
+                    };
+
+                    // Move the message out of the snooze mailbox, other mailboxes are kept
+                    let mut changed_mailboxes = AHashSet::new();
+                    if let Some(snooze_mailbox) = mailboxes
+                        .current()
+                        .iter()
+                        .find(|mailbox| Some(mailbox.mailbox_id) == snooze_mailbox_id)
+                        .copied()
+                    {
+                        if snooze_mailbox.mailbox_id != target_id {
+                            mailboxes.update(snooze_mailbox, false);
+                            changed_mailboxes.insert(snooze_mailbox.mailbox_id);
+                        }
+                    }
+                    if !mailboxes
+                        .current()
+                        .iter()
+                        .any(|mailbox| mailbox.mailbox_id == target_id)
+                    {
+                        let uid = self
+                            .assign_imap_uid(account_id, target_id)
+                            .await
+                            .caused_by(trc::location!())?;
+                        mailboxes.update(UidMailbox::new(target_id, uid), true);
+                        changed_mailboxes.insert(target_id);
+                    }
+                    keywords.update(snoozed_keyword.clone(), false);
+
+                    if changes.change_id == u64::MAX {
+                        changes.change_id = self.assign_change_id(account_id).await?;
+                    }
+                    if mailboxes.has_changes() {
+                        mailboxes.update_batch(&mut batch, Property::MailboxIds);
+                    }
+                    keywords.update_batch(&mut batch, Property::Keywords);
+                    batch.value(Property::Cid, changes.change_id, F_VALUE);
+
+                    changes.log_update(Collection::Email, Id::from_parts(thread_id, document_id));
+                    for mailbox_id in changed_mailboxes {
+                        changes.log_child_update(Collection::Mailbox, mailbox_id);
+                    }
+                    is_woken = true;
+                }
+            }
+
+            match self.core.storage.data.write(batch.build()).await {
+                Ok(_) => {
+                    if is_woken {
+                        total_woken += 1;
+                    }
+                }
+                Err(err) if err.is_assertion_failure() => {
+                    // The message was modified concurrently, try again later
+                    retry_at = Some(now + SNOOZE_RETRY_INTERVAL);
+                }
+                Err(err) => {
+                    return Err(err.caused_by(trc::location!()));
+                }
+            }
+        }
+
+        // Write and broadcast changes
+        if !changes.is_empty() {
+            trc::event!(
+                Housekeeper(trc::HousekeeperEvent::WakeSnoozed),
+                AccountId = account_id,
+                Total = total_woken,
+            );
+
+            let change_id = self.commit_changes(account_id, changes).await?;
+            self.broadcast_state_change(
+                StateChange::new(account_id)
+                    .with_change(DataType::Email, change_id)
+                    .with_change(DataType::Mailbox, change_id),
+            )
+            .await;
+        }
+
+        Ok(retry_at)
+    }
+
+    async fn email_snooze_reschedule(&self, until: u64) {
+        let due = Instant::now() + Duration::from_secs(until.saturating_sub(now()));
+        if self
+            .inner
+            .ipc
+            .housekeeper_tx
+            .send(HousekeeperEvent::SnoozeReschedule { due })
+            .await
+            .is_err()
+        {
+            trc::event!(
+                Server(trc::ServerEvent::ThreadError),
+                Details = "Failed to send snooze event to housekeeper.",
+                CausedBy = trc::location!()
+            );
+        }
+    }
+}
+
+// Wake-ups are stored by due time, followed by the account and document ids
+impl SnoozeDetails {
+    pub fn set(self, batch: &mut BatchBuilder) {
+        batch
+            .set(ValueClass::SnoozeEvent(self.until), vec![])
+            .value(Property::Snoozed, Bincode::new(self), F_VALUE);
+    }
+
+    pub fn clear(self, batch: &mut BatchBuilder) {
+        batch
+            .clear(ValueClass::SnoozeEvent(self.until))
+            .clear(Property::Snoozed);
+    }
+}
@@ -26,6 +26,7 @@ use services::{
 };
 
 use store::{
+    ahash::AHashMap,
     dispatch::DocumentSet,
     fts::FtsFilter,
     query::{sort::Pagination, Comparator, Filter, ResultSet, SortedResultSet},
@@ -45,10 +46,12 @@ pub mod changes;
 pub mod email;
 pub mod identity;
 pub mod mailbox;
+pub mod mdn;
 pub mod principal;
 pub mod push;
 pub mod quota;
 pub mod services;
+pub mod share_notification;
 pub mod sieve;
 pub mod submission;
 pub mod thread;
@@ -391,6 +394,24 @@ impl JmapMethods for Server {
             })
     }
 
+    async fn fts_filter_ranked<T: Into<u8> + Display + Clone + std::fmt::Debug + Sync + Send>(
+        &self,
+        account_id: u32,
+        collection: Collection,
+        filters: Vec<FtsFilter<T>>,
+    ) -> trc::Result<AHashMap<u32, f64>> {
+        self.core
+            .storage
+            .fts
+            .query_ranked(account_id, collection, filters)
+            .await
+            .add_context(|err| {
+                err.caused_by(trc::location!())
+                    .account_id(account_id)
+                    .collection(collection)
+            })
+    }
+
     async fn build_query_response<T: Sync + Send>(
         &self,
         result_set: &ResultSet,
@@ -560,6 +581,13 @@ pub trait JmapMethods: Sync + Send {
         filters: Vec<FtsFilter<T>>,
     ) -> impl Future<Output = trc::Result<RoaringBitmap>> + Send;
 
+    fn fts_filter_ranked<T: Into<u8> + Display + Clone + std::fmt::Debug + Sync + Send>(
+        &self,
+        account_id: u32,
+        collection: Collection,
+        filters: Vec<FtsFilter<T>>,
+    ) -> impl Future<Output = trc::Result<AHashMap<u32, f64>>> + Send;
+
     fn build_query_response<T: Sync + Send>(
         &self,
         result_set: &ResultSet,
@@ -7,11 +7,15 @@
 use common::{auth::AccessToken, Server};
 use jmap_proto::{
     method::get::{GetRequest, GetResponse, RequestArguments},
-    object::Object,
-    types::{acl::Acl, collection::Collection, keyword::Keyword, property::Property, value::Value},
+    object::{mailbox::acl_to_mailbox_rights, Object},
+    types::{
+        acl::Acl, collection::Collection, id::Id, keyword::Keyword, property::Property,
+        value::Value,
+    },
 };
 use store::{ahash::AHashSet, query::Filter, roaring::RoaringBitmap};
 use trc::AddContext;
+use utils::map::bitmap::Bitmap;
 
 use crate::{
     auth::acl::{AclMethods, EffectiveAcl},
@@ -108,6 +112,7 @@ impl MailboxGet for Server {
                     | Property::Role
                     | Property::SortOrder
                     | Property::Acl
+                    | Property::ShareWith
                     | Property::MyRights
             )
         });
@@ -207,43 +212,12 @@ impl MailboxGet for Server {
                         )
                         .await? as u64,
                     ),
-                    Property::MyRights => {
-                        if access_token.is_shared(account_id) {
-                            let acl = values.effective_acl(access_token);
-                            Object::with_capacity(9)
-                                .with_property(Property::MayReadItems, acl.contains(Acl::ReadItems))
-                                .with_property(Property::MayAddItems, acl.contains(Acl::AddItems))
-                                .with_property(
-                                    Property::MayRemoveItems,
-                                    acl.contains(Acl::RemoveItems),
-                                )
-                                .with_property(Property::MaySetSeen, acl.contains(Acl::ModifyItems))
-                                .with_property(
-                                    Property::MaySetKeywords,
-                                    acl.contains(Acl::ModifyItems),
-                                )
-                                .with_property(
-                                    Property::MayCreateChild,
-                                    acl.contains(Acl::CreateChild),
-                                )
-                                .with_property(Property::MayRename, acl.contains(Acl::Modify))
-                                .with_property(Property::MayDelete, acl.contains(Acl::Delete))
-                                .with_property(Property::MaySubmit, acl.contains(Acl::Submit))
-                                .into()
-                        } else {
-                            Object::with_capacity(9)
-                                .with_property(Property::MayReadItems, true)
-                                .with_property(Property::MayAddItems, true)
-                                .with_property(Property::MayRemoveItems, true)
-                                .with_property(Property::MaySetSeen, true)
-                                .with_property(Property::MaySetKeywords, true)
-                                .with_property(Property::MayCreateChild, true)
-                                .with_property(Property::MayRename, true)
-                                .with_property(Property::MayDelete, true)
-                                .with_property(Property::MaySubmit, true)
-                                .into()
-                        }
+                    Property::MyRights => if access_token.is_shared(account_id) {
+                        acl_to_mailbox_rights(&values.effective_acl(access_token))
+                    } else {
+                        acl_to_mailbox_rights(&Bitmap::all())
                     }
+                    .into(),
                     Property::IsSubscribed => values
                         .properties
                         .remove(property)
@@ -257,6 +231,29 @@ impl MailboxGet for Server {
                             _ => Value::Bool(false),
                         })
                         .unwrap_or(Value::Bool(false)),
+                    Property::ShareWith => {
+                        let acl = values
+                            .properties
+                            .get(&Property::Acl)
+                            .and_then(|v| v.as_acl())
+                            .map(|v| &v[..])
+                            .unwrap_or_else(|| &[]);
+                        if !acl.is_empty()
+                            && (access_token.is_member(account_id)
+                                || values.effective_acl(access_token).contains(Acl::Administer))
+                        {
+                            let mut share_with = Object::with_capacity(acl.len());
+                            for item in acl {
+                                share_with.append(
+                                    Property::_T(Id::from(item.account_id).to_string()),
+                                    acl_to_mailbox_rights(&item.grants),
+                                );
+                            }
+                            Value::Object(share_with)
+                        } else {
+                            Value::Null
+                        }
+                    }
                     Property::Acl => {
                         self.acl_get(
                             values
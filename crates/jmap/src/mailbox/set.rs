@@ -22,7 +22,7 @@ use jmap_proto::{
         property::Property,
         state::StateChange,
         type_state::DataType,
-        value::{MaybePatchValue, SetValue, Value},
+        value::{AclGrant, MaybePatchValue, SetValue, Value},
     },
 };
 use store::{
@@ -40,6 +40,7 @@ use crate::{
     auth::acl::{AclMethods, EffectiveAcl},
     changes::write::ChangeLog,
     email::delete::EmailDeletion,
+    share_notification::notify::ShareNotify,
     JmapMethods,
 };
 
@@ -136,6 +137,7 @@ impl MailboxSet for Server {
         'create: for (id, object) in request.unwrap_create() {
             match self.mailbox_set_item(object, None, &ctx).await? {
                 Ok(builder) => {
+                    let acl_changes = acl_changes(&builder);
                     let mut batch = BatchBuilder::new();
                     batch
                         .with_account_id(account_id)
@@ -166,6 +168,19 @@ impl MailboxSet for Server {
                             changes.log_insert(Collection::Mailbox, document_id);
                             ctx.mailbox_ids.insert(document_id);
                             ctx.response.created(id, document_id);
+
+                            // Notify sharees
+                            if let Some((current, acl, name)) = acl_changes {
+                                self.notify_share_changes(
+                                    access_token.primary_id(),
+                                    account_id,
+                                    document_id,
+                                    &name,
+                                    &current,
+                                    &acl,
+                                )
+                                .await;
+                            }
                         }
                         Err(err) if err.is_assertion_failure() => {
                             ctx.response.not_created.append(
@@ -219,7 +234,8 @@ impl MailboxSet for Server {
                                 .with_description("You are not allowed to modify this mailbox."),
                         );
                         continue 'update;
-                    } else if object.properties.contains_key(&Property::Acl)
+                    } else if (object.properties.contains_key(&Property::Acl)
+                        || object.properties.contains_key(&Property::ShareWith))
                         && !acl.contains(Acl::Administer)
                     {
                         ctx.response.not_updated.append(
@@ -237,6 +253,7 @@ impl MailboxSet for Server {
                     .await?
                 {
                     Ok(builder) => {
+                        let acl_changes = acl_changes(&builder);
                         let mut batch = BatchBuilder::new();
                         batch
                             .with_account_id(account_id)
@@ -259,6 +276,19 @@ impl MailboxSet for Server {
                             match self.core.storage.data.write(batch.build()).await {
                                 Ok(_) => {
                                     changes.log_update(Collection::Mailbox, document_id);
+
+                                    // Notify sharees
+                                    if let Some((current, acl, name)) = acl_changes {
+                                        self.notify_share_changes(
+                                            access_token.primary_id(),
+                                            account_id,
+                                            document_id,
+                                            &name,
+                                            &current,
+                                            &acl,
+                                        )
+                                        .await;
+                                    }
                                 }
                                 Err(err) if err.is_assertion_failure() => {
                                     ctx.response.not_updated.append(id, SetError::forbidden().with_description(
@@ -581,7 +611,7 @@ impl MailboxSet for Server {
                 (Property::Role, MaybePatchValue::Value(Value::Text(value))) => {
                     let role = value.trim().to_lowercase();
                     if [
-                        "inbox", "trash", "spam", "junk", "drafts", "archive", "sent",
+                        "inbox", "trash", "spam", "junk", "drafts", "archive", "sent", "snoozed",
                     ]
                     .contains(&role.as_str())
                     {
@@ -596,7 +626,7 @@ impl MailboxSet for Server {
                 (Property::SortOrder, MaybePatchValue::Value(Value::UnsignedInt(value))) => {
                     Value::UnsignedInt(value)
                 }
-                (Property::Acl, value) => {
+                (Property::Acl | Property::ShareWith, value) => {
                     match self
                         .acl_set(&mut changes, update.as_ref().map(|(_, obj)| obj), value)
                         .await
@@ -984,3 +1014,32 @@ impl MailboxSubscribe for Object<Value> {
         }
     }
 }
+
+/// Returns the current and new ACL grants, along with the mailbox name,
+/// if the mailbox permissions are being modified.
+fn acl_changes(builder: &ObjectIndexBuilder) -> Option<(Vec<AclGrant>, Vec<AclGrant>, String)> {
+    let acl = builder
+        .changes()?
+        .properties
+        .get(&Property::Acl)?
+        .as_acl()?;
+    let current = builder
+        .current()
+        .and_then(|current| current.inner.properties.get(&Property::Acl))
+        .and_then(|current| current.as_acl())
+        .cloned()
+        .unwrap_or_default();
+    if &current != acl {
+        Some((
+            current,
+            acl.clone(),
+            builder
+                .get(&Property::Name)
+                .as_string()
+                .unwrap_or_default()
+                .to_string(),
+        ))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,182 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{auth::AccessToken, Server};
+use jmap_proto::{
+    method::mdn::{
+        ActionMode, Disposition, DispositionType, Mdn, MdnParseRequest, MdnParseResponse,
+        SendingMode,
+    },
+    types::{collection::Collection, id::Id, property::Property},
+};
+use mail_parser::{MessageParser, MimeHeaders, PartType};
+use std::future::Future;
+use store::query::Filter;
+use trc::AddContext;
+use utils::map::vec_map::VecMap;
+
+use crate::{blob::download::BlobDownload, JmapMethods};
+
+pub trait MdnParse: Sync + Send {
+    fn mdn_parse(
+        &self,
+        request: MdnParseRequest,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<MdnParseResponse>> + Send;
+}
+
+impl MdnParse for Server {
+    async fn mdn_parse(
+        &self,
+        request: MdnParseRequest,
+        access_token: &AccessToken,
+    ) -> trc::Result<MdnParseResponse> {
+        if request.blob_ids.len() > self.core.jmap.mail_parse_max_items {
+            return Err(trc::JmapEvent::RequestTooLarge.into_err());
+        }
+        let account_id = request.account_id.document_id();
+        let mut response = MdnParseResponse {
+            account_id: request.account_id,
+            parsed: VecMap::with_capacity(request.blob_ids.len()),
+            not_parsable: vec![],
+            not_found: vec![],
+        };
+
+        for blob_id in request.blob_ids {
+            // Fetch raw message to parse
+            let raw_message = match self.blob_download(&blob_id, access_token).await? {
+                Some(raw_message) => raw_message,
+                None => {
+                    response.not_found.push(blob_id);
+                    continue;
+                }
+            };
+            let message = if let Some(message) = MessageParser::new().parse(&raw_message) {
+                message
+            } else {
+                response.not_parsable.push(blob_id);
+                continue;
+            };
+
+            // Find the disposition notification part
+            let report = message.parts.iter().find_map(|part| {
+                part.content_type()
+                    .filter(|ct| {
+                        ct.ctype().eq_ignore_ascii_case("message")
+                            && ct.subtype().map_or(false, |st| {
+                                st.eq_ignore_ascii_case("disposition-notification")
+                            })
+                    })
+                    .and_then(|_| raw_message.get(part.offset_body..part.offset_end))
+            });
+            let mut mdn = if let Some(mdn) = report.and_then(parse_disposition_notification) {
+                mdn
+            } else {
+                response.not_parsable.push(blob_id);
+                continue;
+            };
+            mdn.subject = message.subject().map(Into::into);
+            mdn.text_body = message.body_text(0).map(|text| text.into_owned());
+            mdn.include_original_message = message
+                .parts
+                .iter()
+                .any(|part| matches!(part.body, PartType::Message(_)))
+                .into();
+
+            // Look up the original email in the account
+            if let Some(message_id) = mdn.original_message_id.as_deref().map(|id| {
+                id.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            }) {
+                if let Some(document_id) = self
+                    .core
+                    .storage
+                    .data
+                    .filter(
+                        account_id,
+                        Collection::Email,
+                        vec![Filter::eq(Property::MessageId, &message_id)],
+                    )
+                    .await
+                    .caused_by(trc::location!())?
+                    .results
+                    .min()
+                {
+                    if let Some(thread_id) = self
+                        .get_property::<u32>(
+                            account_id,
+                            Collection::Email,
+                            document_id,
+                            Property::ThreadId,
+                        )
+                        .await?
+                    {
+                        mdn.for_email_id = Id::from_parts(thread_id, document_id).into();
+                    }
+                }
+            }
+
+            response.parsed.append(blob_id, mdn);
+        }
+
+        Ok(response)
+    }
+}
+
+fn parse_disposition_notification(report: &[u8]) -> Option<Mdn> {
+    let report = String::from_utf8_lossy(report);
+    let mut mdn = Mdn::default();
+    let mut fields: Vec<(String, String)> = Vec::new();
+
+    // Unfold fields
+    for line in report.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            fields.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    for (name, value) in fields {
+        match name.to_ascii_lowercase().as_str() {
+            "reporting-ua" => mdn.reporting_ua = value.into(),
+            "mdn-gateway" => mdn.mdn_gateway = value.into(),
+            "original-recipient" => mdn.original_recipient = value.into(),
+            "final-recipient" => mdn.final_recipient = value.into(),
+            "original-message-id" => mdn.original_message_id = value.into(),
+            "disposition" => {
+                // Disposition: manual-action/MDN-sent-manually; displayed
+                let (modes, type_) = value.split_once(';')?;
+                let (action_mode, sending_mode) = modes.split_once('/')?;
+                let type_ = type_.trim();
+                let type_ = type_.split_once('/').map_or(type_, |(type_, _)| type_);
+                mdn.disposition = Disposition {
+                    action_mode: ActionMode::parse_str(action_mode.trim())?,
+                    sending_mode: SendingMode::parse_str(sending_mode.trim())?,
+                    type_: DispositionType::parse_str(type_.trim())?,
+                }
+                .into();
+            }
+            "error" => mdn.error.get_or_insert_with(Vec::new).push(value),
+            _ => {
+                mdn.extension_fields
+                    .get_or_insert_with(VecMap::new)
+                    .append(name, value);
+            }
+        }
+    }
+
+    if mdn.disposition.is_some() {
+        Some(mdn)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,574 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{collections::HashMap, fmt::Write, sync::Arc};
+
+use common::{listener::ServerInstance, Server};
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::{
+        mdn::{Mdn, MdnSendRequest, MdnSendResponse, SendingMode},
+        set::{self, SetRequest},
+    },
+    object::Object,
+    request::{
+        method::{MethodFunction, MethodName, MethodObject},
+        reference::MaybeReference,
+        Call, RequestMethod,
+    },
+    types::{
+        collection::Collection,
+        id::Id,
+        keyword::Keyword,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{SetValue, Value},
+    },
+};
+use mail_builder::{
+    headers::{
+        address::{Address, EmailAddress},
+        content_type::ContentType,
+        HeaderType,
+    },
+    mime::{make_boundary, BodyPart, MimePart},
+    MessageBuilder,
+};
+use mail_parser::{parsers::MessageStream, HeaderValue, MessageParser};
+use smtp_proto::{MailFrom, RcptTo};
+use store::write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, Bincode, F_VALUE};
+use trc::AddContext;
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    blob::download::BlobDownload,
+    changes::write::ChangeLog,
+    email::{metadata::MessageMetadata, set::TagManager},
+    identity::set::sanitize_email,
+    services::state::StateManager,
+    submission::set::EmailSubmissionSet,
+    JmapMethods,
+};
+use std::future::Future;
+
+pub trait MdnSend: Sync + Send {
+    fn mdn_send(
+        &self,
+        request: MdnSendRequest,
+        instance: &Arc<ServerInstance>,
+        next_call: &mut Option<Call<RequestMethod>>,
+    ) -> impl Future<Output = trc::Result<MdnSendResponse>> + Send;
+
+    fn send_mdn(
+        &self,
+        account_id: u32,
+        email_id: Id,
+        from: (Option<&str>, &str),
+        instance: &Arc<ServerInstance>,
+        mdn: Mdn,
+    ) -> impl Future<Output = trc::Result<Result<Mdn, SetError>>> + Send;
+}
+
+impl MdnSend for Server {
+    async fn mdn_send(
+        &self,
+        request: MdnSendRequest,
+        instance: &Arc<ServerInstance>,
+        next_call: &mut Option<Call<RequestMethod>>,
+    ) -> trc::Result<MdnSendResponse> {
+        if request.send.len() > self.core.jmap.set_max_objects {
+            return Err(trc::JmapEvent::RequestTooLarge.into_err());
+        }
+        let account_id = request.account_id.document_id();
+        let mut response = MdnSendResponse {
+            account_id: request.account_id,
+            ..Default::default()
+        };
+
+        // Obtain the identity used as the sender of the MDNs
+        let mut identity = self
+            .get_property::<Object<Value>>(
+                account_id,
+                Collection::Identity,
+                request.identity_id.document_id(),
+                Property::Value,
+            )
+            .await?
+            .ok_or_else(|| {
+                trc::JmapEvent::InvalidArguments
+                    .into_err()
+                    .details("Identity not found.")
+            })?;
+        let identity_email = identity
+            .properties
+            .remove(&Property::Email)
+            .and_then(|value| value.try_unwrap_string())
+            .ok_or_else(|| {
+                trc::JmapEvent::InvalidArguments
+                    .into_err()
+                    .details("Identity has no email address.")
+            })?;
+        let identity_name = identity
+            .properties
+            .remove(&Property::Name)
+            .and_then(|value| value.try_unwrap_string())
+            .filter(|name| !name.is_empty());
+
+        let mut changes = ChangeLogBuilder::new();
+        let mut success_email_ids = HashMap::new();
+        for (id, mdn) in request.send {
+            let email_id = if let Some(email_id) = mdn.for_email_id {
+                email_id
+            } else {
+                response.not_sent.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::_T("forEmailId".to_string()))
+                        .with_description("forEmailId is required."),
+                );
+                continue;
+            };
+            match self
+                .send_mdn(
+                    account_id,
+                    email_id,
+                    (identity_name.as_deref(), identity_email.as_str()),
+                    instance,
+                    mdn,
+                )
+                .await?
+            {
+                Ok(sent) => {
+                    // Flag the email as having had an MDN sent
+                    let document_id = email_id.document_id();
+                    for _ in 0..3 {
+                        let mut keywords = if let Some(keywords) = self
+                            .get_property::<HashedValue<Vec<Keyword>>>(
+                                account_id,
+                                Collection::Email,
+                                document_id,
+                                Property::Keywords,
+                            )
+                            .await?
+                        {
+                            TagManager::new(keywords)
+                        } else {
+                            break;
+                        };
+                        keywords.update(Keyword::MdnSent, true);
+                        if !keywords.has_changes() {
+                            break;
+                        }
+                        if changes.change_id == u64::MAX {
+                            changes.change_id = self.assign_change_id(account_id).await?;
+                        }
+
+                        let mut batch = BatchBuilder::new();
+                        batch
+                            .with_account_id(account_id)
+                            .with_collection(Collection::Email)
+                            .update_document(document_id);
+                        keywords.update_batch(&mut batch, Property::Keywords);
+                        batch.value(Property::Cid, changes.change_id, F_VALUE);
+                        match self.core.storage.data.write(batch.build()).await {
+                            Ok(_) => {
+                                changes.log_update(Collection::Email, email_id);
+                                break;
+                            }
+                            Err(err) if err.is_assertion_failure() => {}
+                            Err(err) => {
+                                return Err(err.caused_by(trc::location!()));
+                            }
+                        }
+                    }
+
+                    success_email_ids.insert(id.clone(), email_id);
+                    response.sent.append(id, sent);
+                }
+                Err(err) => {
+                    response.not_sent.append(id, err);
+                }
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            let change_id = self.commit_changes(account_id, changes).await?;
+            self.broadcast_state_change(
+                StateChange::new(account_id).with_change(DataType::Email, change_id),
+            )
+            .await;
+        }
+
+        // On success
+        if let Some(update) = request
+            .on_success_update_email
+            .filter(|update| !update.is_empty() && !success_email_ids.is_empty())
+        {
+            *next_call = Call {
+                id: String::new(),
+                name: MethodName::new(MethodObject::Email, MethodFunction::Set),
+                method: RequestMethod::Set(SetRequest {
+                    account_id: request.account_id,
+                    if_in_state: None,
+                    create: None,
+                    update: Some(
+                        update
+                            .into_iter()
+                            .filter_map(|(id, value)| {
+                                (
+                                    match id {
+                                        MaybeReference::Value(id) => id,
+                                        MaybeReference::Reference(id_ref) => {
+                                            *(success_email_ids.get(&id_ref)?)
+                                        }
+                                    },
+                                    value,
+                                )
+                                    .into()
+                            })
+                            .collect::<VecMap<Id, Object<SetValue>>>(),
+                    ),
+                    destroy: None,
+                    arguments: set::RequestArguments::Email,
+                }),
+            }
+            .into();
+        }
+
+        Ok(response)
+    }
+
+    async fn send_mdn(
+        &self,
+        account_id: u32,
+        email_id: Id,
+        (from_name, from_email): (Option<&str>, &str),
+        instance: &Arc<ServerInstance>,
+        mut mdn: Mdn,
+    ) -> trc::Result<Result<Mdn, SetError>> {
+        // Validate MDN
+        let disposition = if let Some(disposition) = &mdn.disposition {
+            disposition.clone()
+        } else {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::_T("disposition".to_string()))
+                .with_description("disposition is required.")));
+        };
+        if let Some(fields) = &mdn.extension_fields {
+            for name in fields.keys() {
+                if name.is_empty()
+                    || !name
+                        .bytes()
+                        .all(|ch| ch.is_ascii_alphanumeric() || ch == b'-')
+                {
+                    return Ok(Err(SetError::invalid_properties()
+                        .with_property(Property::_T("extensionFields".to_string()))
+                        .with_description(format!(
+                            "Invalid extension field name {name:?}."
+                        ))));
+                }
+            }
+        }
+
+        // Make sure an MDN was not already sent for this email
+        let document_id = email_id.document_id();
+        match self
+            .get_property::<Vec<Keyword>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::Keywords,
+            )
+            .await?
+        {
+            Some(keywords) if keywords.contains(&Keyword::MdnSent) => {
+                return Ok(Err(SetError::new(SetErrorType::MdnAlreadySent)
+                    .with_description("An MDN was already sent for this email.")));
+            }
+            Some(_) => (),
+            None => {
+                return Ok(Err(SetError::not_found()
+                    .with_property(Property::_T("forEmailId".to_string()))
+                    .with_description("Email not found.")));
+            }
+        }
+
+        // Obtain original message
+        let raw_message = if let Some(raw_message) = self
+            .get_property::<Bincode<MessageMetadata>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::BodyStructure,
+            )
+            .await?
+        {
+            if let Some(raw_message) = self
+                .get_blob(&raw_message.inner.blob_hash, 0..usize::MAX)
+                .await?
+            {
+                raw_message
+            } else {
+                return Ok(Err(SetError::not_found()
+                    .with_property(Property::_T("forEmailId".to_string()))
+                    .with_description("Blob for email not found.")));
+            }
+        } else {
+            return Ok(Err(SetError::not_found()
+                .with_property(Property::_T("forEmailId".to_string()))
+                .with_description("Email not found.")));
+        };
+        let message = if let Some(message) = MessageParser::new().parse(&raw_message) {
+            message
+        } else {
+            return Ok(Err(SetError::new(SetErrorType::InvalidEmail)
+                .with_description("Failed to parse original email.")));
+        };
+
+        // Obtain MDN recipients from the Disposition-Notification-To header
+        let mut rcpt_to: Vec<RcptTo<String>> = Vec::new();
+        let mut original_recipient = None;
+        for header in &message.root_part().headers {
+            let name = header.name();
+            if name.eq_ignore_ascii_case("Disposition-Notification-To") {
+                if let HeaderValue::Address(addr) = MessageStream::new(
+                    raw_message
+                        .get(header.offset_start..header.offset_end)
+                        .unwrap_or_default(),
+                )
+                .parse_address()
+                {
+                    for address in addr.iter() {
+                        if let Some(address) = address.address().and_then(sanitize_email) {
+                            if !rcpt_to.iter().any(|rcpt| rcpt.address == address) {
+                                rcpt_to.push(RcptTo {
+                                    address,
+                                    ..Default::default()
+                                });
+                            }
+                        }
+                    }
+                }
+            } else if name.eq_ignore_ascii_case("Original-Recipient") {
+                original_recipient = raw_message
+                    .get(header.offset_start..header.offset_end)
+                    .map(|value| String::from_utf8_lossy(value).trim().to_string());
+            }
+        }
+        if rcpt_to.is_empty() {
+            return Ok(Err(SetError::new(SetErrorType::NoRecipients)
+                .with_description(
+                    "Original email does not request a disposition notification.",
+                )));
+        }
+
+        // Fill in server-set properties
+        let mut sent = Mdn::default();
+        let original_subject = message.subject().unwrap_or_default();
+        let from_domain = from_email.rsplit_once('@').map_or(from_email, |(_, d)| d);
+        if mdn.subject.is_none() {
+            let subject = format!(
+                "Return Receipt ({}): {}",
+                disposition.type_.as_str(),
+                original_subject
+            );
+            mdn.subject = Some(subject.clone());
+            sent.subject = Some(subject);
+        }
+        if mdn.reporting_ua.is_none() {
+            let reporting_ua = format!("{from_domain}; Stalwart JMAP");
+            mdn.reporting_ua = Some(reporting_ua.clone());
+            sent.reporting_ua = Some(reporting_ua);
+        }
+        if mdn.final_recipient.is_none() {
+            let final_recipient = format!("rfc822; {from_email}");
+            mdn.final_recipient = Some(final_recipient.clone());
+            sent.final_recipient = Some(final_recipient);
+        }
+        if mdn.original_recipient.is_none() && original_recipient.is_some() {
+            mdn.original_recipient.clone_from(&original_recipient);
+            sent.original_recipient = original_recipient;
+        }
+        if mdn.original_message_id.is_none() {
+            if let Some(message_id) = message.message_id() {
+                let message_id = format!("<{message_id}>");
+                mdn.original_message_id = Some(message_id.clone());
+                sent.original_message_id = Some(message_id);
+            }
+        }
+        if mdn.text_body.is_none() {
+            let text_body = format!(
+                "This is a Return Receipt for the message with subject \"{}\".\r\n\r\nThe message has been {} by {}.\r\n",
+                original_subject,
+                disposition.type_.as_str(),
+                from_email
+            );
+            mdn.text_body = Some(text_body.clone());
+            sent.text_body = Some(text_body);
+        }
+        if mdn.include_original_message.is_none() {
+            mdn.include_original_message = Some(false);
+            sent.include_original_message = Some(false);
+        }
+
+        // Build disposition notification fields
+        let mut report = String::with_capacity(256);
+        write_field(&mut report, "Reporting-UA", mdn.reporting_ua.as_deref());
+        write_field(&mut report, "MDN-Gateway", mdn.mdn_gateway.as_deref());
+        write_recipient(
+            &mut report,
+            "Original-Recipient",
+            mdn.original_recipient.as_deref(),
+        );
+        write_recipient(
+            &mut report,
+            "Final-Recipient",
+            mdn.final_recipient.as_deref(),
+        );
+        write_field(
+            &mut report,
+            "Original-Message-ID",
+            mdn.original_message_id.as_deref(),
+        );
+        let _ = write!(
+            report,
+            "Disposition: {}/{}; {}\r\n",
+            disposition.action_mode.as_str(),
+            disposition.sending_mode.as_str(),
+            disposition.type_.as_str()
+        );
+        for error in mdn.error.iter().flatten() {
+            write_field(&mut report, "Error", Some(error));
+        }
+        for (name, value) in mdn.extension_fields.iter().flatten() {
+            write_field(&mut report, name, Some(value));
+        }
+
+        // Build MDN message
+        let mut parts = vec![
+            MimePart::new(
+                ContentType::new("text/plain").attribute("charset", "utf-8"),
+                BodyPart::Text(mdn.text_body.clone().unwrap_or_default().into()),
+            ),
+            MimePart::new(
+                ContentType::new("message/disposition-notification"),
+                BodyPart::Text(report.into()),
+            ),
+        ];
+        if mdn.include_original_message == Some(true) {
+            // Attach the original message as is, without any transfer encoding
+            parts.push(
+                MimePart::new(
+                    ContentType::new("message/rfc822"),
+                    BodyPart::Binary(raw_message.as_slice().into()),
+                )
+                .transfer_encoding(if raw_message.is_ascii() {
+                    "7bit"
+                } else {
+                    "8bit"
+                }),
+            );
+        } else if let Some(headers) = raw_message.get(..message.root_part().offset_body) {
+            parts.push(MimePart::new(
+                ContentType::new("text/rfc822-headers"),
+                BodyPart::Binary(headers.into()),
+            ));
+        }
+        let mut builder = MessageBuilder::new()
+            .from(Address::Address(EmailAddress {
+                name: from_name.map(|name| name.into()),
+                email: from_email.into(),
+            }))
+            .header(
+                "To",
+                HeaderType::Address(Address::List(
+                    rcpt_to
+                        .iter()
+                        .map(|rcpt| {
+                            Address::Address(EmailAddress {
+                                name: None,
+                                email: rcpt.address.as_str().into(),
+                            })
+                        })
+                        .collect(),
+                )),
+            )
+            .message_id(format!("<{}@{}>", make_boundary("."), from_domain))
+            .subject(mdn.subject.clone().unwrap_or_default());
+        if let Some(message_id) = message.message_id() {
+            builder = builder.in_reply_to(message_id).references(message_id);
+        }
+        if disposition.sending_mode == SendingMode::Automatic {
+            builder = builder.header("Auto-Submitted", HeaderType::Text("auto-replied".into()));
+        }
+        let mdn_message = builder
+            .body(MimePart::new(
+                ContentType::new("multipart/report")
+                    .attribute("report-type", "disposition-notification"),
+                BodyPart::Multipart(parts),
+            ))
+            .write_to_vec()
+            .unwrap_or_default();
+        if mdn_message.len() > self.core.jmap.mail_max_size {
+            return Ok(Err(SetError::new(SetErrorType::TooLarge).with_description(
+                format!(
+                    "MDN exceeds maximum size of {} bytes.",
+                    self.core.jmap.mail_max_size
+                ),
+            )));
+        }
+
+        // Submit MDN with a null return path (RFC 8098 section 2.1)
+        match self
+            .submit_message(
+                instance,
+                &mut MailFrom {
+                    address: String::new(),
+                    ..Default::default()
+                },
+                rcpt_to,
+                mdn_message,
+            )
+            .await
+        {
+            Ok((Some(_), _)) => Ok(Ok(sent)),
+            Ok((None, responses)) => Ok(Err(SetError::new(SetErrorType::ForbiddenToSend)
+                .with_description(format!(
+                    "Server rejected all recipients: {}",
+                    responses
+                        .into_iter()
+                        .filter_map(|(_, response)| response)
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                )))),
+            Err(err) => Ok(Err(err)),
+        }
+    }
+}
+
+fn write_field(report: &mut String, name: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        report.push_str(name);
+        report.push_str(": ");
+        for ch in value.chars() {
+            report.push(if ch == '\r' || ch == '\n' { ' ' } else { ch });
+        }
+        report.push_str("\r\n");
+    }
+}
+
+fn write_recipient(report: &mut String, name: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        if value.contains(';') {
+            write_field(report, name, Some(value));
+        } else {
+            write_field(report, name, Some(&format!("rfc822; {value}")));
+        }
+    }
+}
@@ -5,17 +5,23 @@
  */
 
 use base64::{engine::general_purpose, Engine};
-use common::{core::BuildServer, Inner, IPC_CHANNEL_BUFFER};
-use jmap_proto::types::id::Id;
-use store::ahash::{AHashMap, AHashSet};
+use common::{config::jmap::vapid::VapidKey, core::BuildServer, Inner, Server, IPC_CHANNEL_BUFFER};
+use jmap_proto::types::{collection::Collection, id::Id, property::Property};
+use store::{
+    ahash::{AHashMap, AHashSet},
+    write::{BatchBuilder, F_CLEAR, F_VALUE},
+};
 use tokio::sync::mpsc;
 use trc::PushSubscriptionEvent;
 
-use crate::{api::StateChangeResponse, LONG_SLUMBER};
+use crate::{api::StateChangeResponse, services::state::StateManager, JmapMethods, LONG_SLUMBER};
 
 use super::{ece::ece_encrypt, EncryptionKeys, Event, PushServer, PushUpdate};
 
-use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::{
+    header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE},
+    StatusCode,
+};
 use std::{
     collections::hash_map::Entry,
     sync::Arc,
@@ -45,6 +51,7 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
             let push_timeout = server.core.jmap.push_timeout;
             let push_verify_timeout = server.core.jmap.push_verify_timeout;
             let push_throttle = server.core.jmap.push_throttle;
+            let push_vapid = server.core.jmap.push_vapid.clone();
 
             match event_or_timeout {
                 Ok(Some(event)) => match event {
@@ -76,8 +83,11 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                         })
                                         .unwrap_or(true)
                                     {
+                                        let push_id = Id::from_parts(account_id, id);
+                                        let push_vapid = push_vapid.clone();
+                                        let server = server.clone();
                                         tokio::spawn(async move {
-                                            http_request(
+                                            if http_request(
                                                 url,
                                                 format!(
                                                     concat!(
@@ -89,9 +99,14 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                                     code
                                                 ),
                                                 keys,
+                                                push_vapid,
                                                 push_timeout,
                                             )
-                                            .await;
+                                            .await
+                                                == PushResult::Gone
+                                            {
+                                                destroy_subscription(server, push_id).await;
+                                            }
                                         });
 
                                         last_verify.insert(account_id, current_time);
@@ -139,7 +154,12 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                             .contains(&subscription.num_attempts)
                                             && last_request > push_attempt_interval))
                                 {
-                                    subscription.send(id, push_tx.clone(), push_timeout);
+                                    subscription.send(
+                                        id,
+                                        push_tx.clone(),
+                                        push_vapid.clone(),
+                                        push_timeout,
+                                    );
                                     retry_ids.remove(&id);
                                 } else {
                                     retry_ids.insert(id);
@@ -162,6 +182,13 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                             retry_ids.remove(&id);
                         }
                     }
+                    Event::DeliveryGone { id } => {
+                        // The push service no longer accepts messages for this subscription
+                        if subscriptions.remove(&id).is_some() {
+                            retry_ids.remove(&id);
+                            tokio::spawn(destroy_subscription(server.clone(), id));
+                        }
+                    }
                     Event::DeliveryFailure { id, state_changes } => {
                         if let Some(subscription) = subscriptions.get_mut(&id) {
                             subscription.last_request = Instant::now();
@@ -195,7 +222,12 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
                                         && last_request >= push_attempt_interval))
                             {
                                 if subscription.num_attempts < push_attempts_max {
-                                    subscription.send(*retry_id, push_tx.clone(), push_timeout);
+                                    subscription.send(
+                                        *retry_id,
+                                        push_tx.clone(),
+                                        push_vapid.clone(),
+                                        push_timeout,
+                                    );
                                 } else {
                                     trc::event!(
                                         PushSubscription(PushSubscriptionEvent::Error),
@@ -237,7 +269,13 @@ pub fn spawn_push_manager(inner: Arc<Inner>) -> mpsc::Sender<Event> {
 }
 
 impl PushServer {
-    fn send(&mut self, id: Id, push_tx: mpsc::Sender<Event>, push_timeout: Duration) {
+    fn send(
+        &mut self,
+        id: Id,
+        push_tx: mpsc::Sender<Event>,
+        push_vapid: Option<VapidKey>,
+        push_timeout: Duration,
+    ) {
         let url = self.url.clone();
         let keys = self.keys.clone();
         let state_changes = std::mem::take(&mut self.state_changes);
@@ -258,17 +296,18 @@ impl PushServer {
 
             push_tx
                 .send(
-                    if http_request(
+                    match http_request(
                         url,
                         serde_json::to_string(&response).unwrap(),
                         keys,
+                        push_vapid,
                         push_timeout,
                     )
                     .await
                     {
-                        Event::DeliverySuccess { id }
-                    } else {
-                        Event::DeliveryFailure { id, state_changes }
+                        PushResult::Success => Event::DeliverySuccess { id },
+                        PushResult::Failure => Event::DeliveryFailure { id, state_changes },
+                        PushResult::Gone => Event::DeliveryGone { id },
                     },
                 )
                 .await
@@ -277,12 +316,20 @@ impl PushServer {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PushResult {
+    Success,
+    Failure,
+    Gone,
+}
+
 async fn http_request(
     url: String,
     mut body: String,
     keys: Option<EncryptionKeys>,
+    vapid: Option<VapidKey>,
     push_timeout: Duration,
-) -> bool {
+) -> PushResult {
     let client_builder = reqwest::Client::builder().timeout(push_timeout);
 
     #[cfg(feature = "test_mode")]
@@ -295,6 +342,11 @@ async fn http_request(
         .header(CONTENT_TYPE, "application/json")
         .header("TTL", "86400");
 
+    // Identify ourselves to the push service (RFC 8292)
+    if let Some(authorization) = vapid.and_then(|vapid| vapid.authorization(&url)) {
+        client = client.header(AUTHORIZATION, authorization);
+    }
+
     if let Some(keys) = keys {
         match ece_encrypt(&keys.p256dh, &keys.auth, body.as_bytes())
             .map(|b| general_purpose::URL_SAFE.encode(b))
@@ -312,7 +364,7 @@ async fn http_request(
                     Url = url,
                     Reason = err
                 );
-                return true;
+                return PushResult::Success;
             }
         }
     }
@@ -322,7 +374,16 @@ async fn http_request(
             if response.status().is_success() {
                 trc::event!(PushSubscription(PushSubscriptionEvent::Success), Url = url,);
 
-                true
+                PushResult::Success
+            } else if matches!(response.status(), StatusCode::NOT_FOUND | StatusCode::GONE) {
+                trc::event!(
+                    PushSubscription(PushSubscriptionEvent::Error),
+                    Details = "Push subscription expired or unsubscribed",
+                    Url = url,
+                    Code = response.status().as_u16(),
+                );
+
+                PushResult::Gone
             } else {
                 trc::event!(
                     PushSubscription(PushSubscriptionEvent::Error),
@@ -331,7 +392,7 @@ async fn http_request(
                     Code = response.status().as_u16(),
                 );
 
-                false
+                PushResult::Failure
             }
         }
         Err(err) => {
@@ -342,7 +403,30 @@ async fn http_request(
                 Reason = err.to_string()
             );
 
-            false
+            PushResult::Failure
+        }
+    }
+}
+
+async fn destroy_subscription(server: Server, id: Id) {
+    let account_id = id.prefix_id();
+    let document_id = id.document_id();
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(account_id)
+        .with_collection(Collection::PushSubscription)
+        .delete_document(document_id)
+        .value(Property::Value, (), F_VALUE | F_CLEAR);
+
+    match server.write_batch(batch).await {
+        Ok(_) => {
+            server.update_push_subscriptions(account_id).await;
+        }
+        Err(err) => {
+            trc::error!(err
+                .account_id(account_id)
+                .document_id(document_id)
+                .details("Failed to destroy push subscription"));
         }
     }
 }
@@ -30,6 +30,9 @@ pub enum Event {
         id: Id,
         state_changes: Vec<StateChange>,
     },
+    DeliveryGone {
+        id: Id,
+    },
     Reset,
 }
 
@@ -0,0 +1,873 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{collections::BTreeMap, future::Future, time::Instant};
+
+use common::{ipc::HousekeeperEvent, Server};
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, property::Property, value::Value},
+};
+use serde::Serialize;
+use store::{
+    ahash::{AHashMap, AHashSet},
+    roaring::RoaringBitmap,
+    write::{
+        key::{DeserializeBigEndian, KeySerializer},
+        now, AnyClass, AnyKey, AssertValue, BatchBuilder, Bincode, DirectoryClass, MaybeDynamicId,
+        Operation, ValueClass,
+    },
+    BitmapKey, IterateParams, Store, SUBSPACE_BITMAP_ID, SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT,
+    SUBSPACE_BLOB_LINK, SUBSPACE_INDEXES, SUBSPACE_PROPERTY, U32_LEN,
+};
+use trc::AddContext;
+use utils::{codec::leb128::Leb128Reader, BLOB_HASH_LEN};
+
+use crate::{
+    changes::write::ChangeLog,
+    email::{index::EmailIndexBuilder, metadata::MessageMetadata},
+    sieve::set::ObjectBlobId,
+    JmapMethods,
+};
+
+// Only the first issues are included in the report, all of them are counted
+const MAX_REPORTED_ISSUES: usize = 1000;
+const MAX_BATCH_OPS: usize = 1000;
+
+// Blob links are keyed by hash, they are scanned once for each group of accounts
+const MAX_BLOB_CHECK_ACCOUNTS: usize = 1000;
+
+// Finished jobs can be queried for a week
+const JOB_EXPIRY: u64 = 7 * 86400;
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsckReport {
+    pub accounts: u64,
+    pub total: u64,
+    pub repaired: u64,
+    pub issues: Vec<FsckIssue>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsckJob {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<u32>,
+    pub repair: bool,
+    pub started: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<FsckReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsckIssue {
+    pub kind: FsckIssueKind,
+    pub account_id: u32,
+    pub collection: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FsckIssueKind {
+    DanglingBitmap,
+    DanglingIndex,
+    OrphanedProperty,
+    OrphanedBlobLink,
+    OrphanedThread,
+    MissingThread,
+    MissingIndex,
+    MissingMetadata,
+    QuotaMismatch,
+}
+
+pub trait StoreCheck: Sync + Send {
+    fn start_check(
+        &self,
+        account_id: Option<u32>,
+        repair: bool,
+    ) -> impl Future<Output = trc::Result<u64>> + Send;
+
+    fn run_check(
+        &self,
+        id: u64,
+        account_id: Option<u32>,
+        repair: bool,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+
+    fn check_status(
+        &self,
+        id: u64,
+    ) -> impl Future<Output = trc::Result<Option<serde_json::Value>>> + Send;
+
+    fn check_store(
+        &self,
+        account_id: Option<u32>,
+        repair: bool,
+    ) -> impl Future<Output = trc::Result<FsckReport>> + Send;
+
+    fn check_account(
+        &self,
+        account_id: u32,
+        repair: bool,
+        report: &mut FsckReport,
+    ) -> impl Future<Output = trc::Result<AHashMap<u8, RoaringBitmap>>> + Send;
+
+    fn check_blob_links(
+        &self,
+        documents: &AHashMap<u32, AHashMap<u8, RoaringBitmap>>,
+        repair: bool,
+        report: &mut FsckReport,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl StoreCheck for Server {
+    async fn start_check(&self, account_id: Option<u32>, repair: bool) -> trc::Result<u64> {
+        let id = self.generate_snowflake_id()?;
+        store_job(
+            self,
+            &FsckJob {
+                id: id.to_string(),
+                account_id,
+                repair,
+                started: now(),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        self.inner
+            .ipc
+            .housekeeper_tx
+            .send(HousekeeperEvent::Fsck {
+                id,
+                account_id,
+                repair,
+            })
+            .await
+            .map_err(|err| {
+                trc::EventType::Server(trc::ServerEvent::ThreadError)
+                    .reason(err)
+                    .details("Failed to send housekeeper event")
+            })?;
+
+        Ok(id)
+    }
+
+    async fn run_check(&self, id: u64, account_id: Option<u32>, repair: bool) -> trc::Result<()> {
+        let mut job = FsckJob {
+            id: id.to_string(),
+            account_id,
+            repair,
+            started: now(),
+            ..Default::default()
+        };
+        match self.check_store(account_id, repair).await {
+            Ok(report) => {
+                job.report = report.into();
+            }
+            Err(err) => {
+                job.error = err.to_string().into();
+            }
+        }
+        job.completed = now().into();
+
+        store_job(self, &job).await
+    }
+
+    async fn check_status(&self, id: u64) -> trc::Result<Option<serde_json::Value>> {
+        self.core
+            .storage
+            .lookup
+            .key_get::<String>(format!("fsck:{id}").into_bytes())
+            .await?
+            .map(|job| {
+                serde_json::from_str(&job).map_err(|err| {
+                    trc::EventType::Store(trc::StoreEvent::DeserializeError)
+                        .from_json_error(err)
+                        .caused_by(trc::location!())
+                })
+            })
+            .transpose()
+    }
+
+    async fn check_store(&self, account_id: Option<u32>, repair: bool) -> trc::Result<FsckReport> {
+        let time = Instant::now();
+        let account_ids = if let Some(account_id) = account_id {
+            RoaringBitmap::from_iter([account_id])
+        } else {
+            self.get_document_ids(u32::MAX, Collection::Principal)
+                .await
+                .caused_by(trc::location!())?
+                .unwrap_or_default()
+        };
+
+        let mut report = FsckReport::default();
+        let mut documents = AHashMap::new();
+        for account_id in account_ids {
+            documents.insert(
+                account_id,
+                self.check_account(account_id, repair, &mut report)
+                    .await
+                    .caused_by(trc::location!())?,
+            );
+            report.accounts += 1;
+
+            if documents.len() >= MAX_BLOB_CHECK_ACCOUNTS {
+                self.check_blob_links(&documents, repair, &mut report)
+                    .await
+                    .caused_by(trc::location!())?;
+                documents.clear();
+            }
+        }
+        if !documents.is_empty() {
+            self.check_blob_links(&documents, repair, &mut report)
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        trc::event!(
+            Store(trc::StoreEvent::ConsistencyCheck),
+            AccountId = account_id,
+            Total = report.total,
+            Details = report.repaired,
+            Elapsed = time.elapsed(),
+        );
+
+        Ok(report)
+    }
+
+    async fn check_account(
+        &self,
+        account_id: u32,
+        repair: bool,
+        report: &mut FsckReport,
+    ) -> trc::Result<AHashMap<u8, RoaringBitmap>> {
+        let store = &self.core.storage.data;
+        let mut check = AccountCheck {
+            account_id,
+            repair,
+            report,
+            orphans: BTreeMap::new(),
+        };
+
+        // Quota changes made while the account is checked invalidate the computed quota
+        let quota_state = QuotaState::read(store, account_id).await?;
+
+        // Obtain the documents of each collection
+        let mut documents: AHashMap<u8, RoaringBitmap> = AHashMap::new();
+        iterate_account(store, SUBSPACE_BITMAP_ID, account_id, |key| {
+            documents
+                .entry(key[U32_LEN])
+                .or_default()
+                .insert(key.deserialize_be_u32(key.len() - U32_LEN)?);
+            Ok(())
+        })
+        .await?;
+        let exists = |collection: u8, document_id: u32| {
+            documents
+                .get(&collection)
+                .map_or(false, |ids| ids.contains(document_id))
+        };
+
+        // Bitmaps pointing to deleted documents, and the threads referenced by emails
+        let mut email_threads: AHashMap<u32, u32> = AHashMap::new();
+        let thread_field = u8::from(Property::ThreadId);
+        iterate_account(store, SUBSPACE_BITMAP_TAG, account_id, |key| {
+            let collection = key[U32_LEN];
+            let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+            if !is_checked(collection) {
+                return Ok(());
+            } else if !exists(collection, document_id) {
+                check.orphan(
+                    FsckIssueKind::DanglingBitmap,
+                    SUBSPACE_BITMAP_TAG,
+                    key,
+                    collection,
+                    document_id,
+                );
+            } else if collection == u8::from(Collection::Email) && key[U32_LEN + 1] == thread_field
+            {
+                let (thread_id, _) = key
+                    .get(U32_LEN + 2..key.len() - U32_LEN)
+                    .and_then(|bytes| bytes.read_leb128::<u32>())
+                    .ok_or_else(|| trc::Error::corrupted_key(key, None, trc::location!()))?;
+                email_threads.insert(document_id, thread_id);
+            }
+            Ok(())
+        })
+        .await?;
+        iterate_account(store, SUBSPACE_BITMAP_TEXT, account_id, |key| {
+            let collection = key[key.len() - U32_LEN - 2];
+            let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+            if is_checked(collection) && !exists(collection, document_id) {
+                check.orphan(
+                    FsckIssueKind::DanglingBitmap,
+                    SUBSPACE_BITMAP_TEXT,
+                    key,
+                    collection,
+                    document_id,
+                );
+            }
+            Ok(())
+        })
+        .await?;
+
+        // Index entries without a document, and the size of each message
+        let mut email_sizes: AHashMap<u32, u32> = AHashMap::new();
+        let size_field = u8::from(Property::Size);
+        iterate_account(store, SUBSPACE_INDEXES, account_id, |key| {
+            let collection = key[U32_LEN];
+            let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+            if !is_checked(collection) {
+                return Ok(());
+            } else if !exists(collection, document_id) {
+                check.orphan(
+                    FsckIssueKind::DanglingIndex,
+                    SUBSPACE_INDEXES,
+                    key,
+                    collection,
+                    document_id,
+                );
+            } else if collection == u8::from(Collection::Email)
+                && key[U32_LEN + 1] == size_field
+                && key.len() == (U32_LEN * 3) + 2
+            {
+                email_sizes.insert(document_id, key.deserialize_be_u32(U32_LEN + 2)?);
+            }
+            Ok(())
+        })
+        .await?;
+
+        // Properties of deleted documents, and the messages with metadata
+        let mut email_metadata = RoaringBitmap::new();
+        let metadata_field = u8::from(Property::BodyStructure);
+        iterate_account(store, SUBSPACE_PROPERTY, account_id, |key| {
+            let collection = key[U32_LEN];
+            let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+            if !is_checked(collection) {
+                return Ok(());
+            } else if !exists(collection, document_id) {
+                check.orphan(
+                    FsckIssueKind::OrphanedProperty,
+                    SUBSPACE_PROPERTY,
+                    key,
+                    collection,
+                    document_id,
+                );
+            } else if collection == u8::from(Collection::Email)
+                && key[U32_LEN + 1] == metadata_field
+            {
+                email_metadata.insert(document_id);
+            }
+            Ok(())
+        })
+        .await?;
+        check.remove_orphans(store).await?;
+
+        // Threads without messages and messages linked to missing threads
+        let email_collection = u8::from(Collection::Email);
+        let thread_collection = u8::from(Collection::Thread);
+        let used_threads = email_threads.values().copied().collect::<AHashSet<_>>();
+        let threads = documents
+            .get(&thread_collection)
+            .cloned()
+            .unwrap_or_default();
+        for thread_id in &threads {
+            if used_threads.contains(&thread_id) {
+                continue;
+            }
+            let repaired = if repair {
+                // Messages might have joined the thread since it was checked
+                if self
+                    .get_tag(account_id, Collection::Email, Property::ThreadId, thread_id)
+                    .await?
+                    .is_some_and(|ids| !ids.is_empty())
+                {
+                    continue;
+                }
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::Thread)
+                    .delete_document(thread_id);
+                write_checked(store, batch).await?
+            } else {
+                false
+            };
+            check.issue(
+                FsckIssueKind::OrphanedThread,
+                thread_collection,
+                thread_id.into(),
+                None,
+                repaired,
+            );
+        }
+        let mut missing_threads = used_threads
+            .into_iter()
+            .filter(|thread_id| !threads.contains(*thread_id))
+            .collect::<Vec<_>>();
+        missing_threads.sort_unstable();
+        for thread_id in missing_threads {
+            let repaired = if repair {
+                // Skip threads created since the check started
+                let mut batch = BatchBuilder::new();
+                batch
+                    .assert_value(
+                        document_class(account_id, thread_collection, thread_id),
+                        AssertValue::None,
+                    )
+                    .with_account_id(account_id)
+                    .with_collection(Collection::Thread)
+                    .create_document_with_id(thread_id);
+                if !write_checked(store, batch).await? {
+                    continue;
+                }
+                true
+            } else {
+                false
+            };
+            check.issue(
+                FsckIssueKind::MissingThread,
+                thread_collection,
+                thread_id.into(),
+                None,
+                repaired,
+            );
+        }
+
+        // Messages without metadata or index entries
+        let mut used_quota = 0i64;
+        for document_id in documents
+            .get(&email_collection)
+            .cloned()
+            .unwrap_or_default()
+        {
+            if let Some(size) = email_sizes.get(&document_id) {
+                used_quota += *size as i64;
+            } else if !email_metadata.contains(document_id) {
+                // Messages can't be rebuilt without their metadata
+                check.issue(
+                    FsckIssueKind::MissingMetadata,
+                    email_collection,
+                    document_id.into(),
+                    None,
+                    false,
+                );
+            } else if let Some(metadata) = self
+                .get_property::<Bincode<MessageMetadata>>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::BodyStructure,
+                )
+                .await?
+            {
+                used_quota += metadata.inner.size as i64;
+
+                let repaired = if repair {
+                    // Rebuild the index entries of the message, leaving its
+                    // values and the quota untouched, unless it was deleted
+                    // since the check started
+                    let mut index = BatchBuilder::new();
+                    EmailIndexBuilder::set(metadata.inner).build(&mut index, account_id, None);
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .assert_value(
+                            document_class(account_id, email_collection, document_id),
+                            AssertValue::Some,
+                        )
+                        .with_account_id(account_id)
+                        .with_collection(Collection::Email)
+                        .update_document(document_id);
+                    batch.ops.extend(
+                        index
+                            .ops
+                            .into_iter()
+                            .filter(|op| matches!(op, Operation::Index { .. })),
+                    );
+                    if !write_checked(store, batch).await? {
+                        continue;
+                    }
+                    true
+                } else {
+                    false
+                };
+                check.issue(
+                    FsckIssueKind::MissingIndex,
+                    email_collection,
+                    document_id.into(),
+                    None,
+                    repaired,
+                );
+            }
+        }
+
+        // Sieve scripts count towards the quota
+        for document_id in documents
+            .get(&u8::from(Collection::SieveScript))
+            .cloned()
+            .unwrap_or_default()
+        {
+            if let Some(size) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::SieveScript,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+                .as_ref()
+                .and_then(|script| script.blob_id())
+                .and_then(|blob_id| blob_id.section.as_ref())
+                .map(|section| section.size)
+            {
+                used_quota += size as i64;
+            }
+        }
+
+        let stored_quota = quota_state.used_quota;
+        if stored_quota != used_quota {
+            // The delta is only valid while the account is unchanged since the
+            // stored quota was read, otherwise the mismatch is just reported
+            let repaired = repair && QuotaState::read(store, account_id).await? == quota_state;
+            if repaired {
+                let delta = used_quota - stored_quota;
+                let mut batch = BatchBuilder::new();
+                batch.add(DirectoryClass::UsedQuota(account_id), delta);
+                if let Some(tenant) = self
+                    .get_cached_access_token(account_id)
+                    .await
+                    .caused_by(trc::location!())?
+                    .tenant
+                {
+                    batch.add(DirectoryClass::UsedQuota(tenant.id), delta);
+                }
+                store
+                    .write(batch.build())
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+            check.issue(
+                FsckIssueKind::QuotaMismatch,
+                u8::from(Collection::Principal),
+                None,
+                format!("stored {stored_quota}, computed {used_quota}").into(),
+                repaired,
+            );
+        }
+
+        Ok(documents)
+    }
+
+    async fn check_blob_links(
+        &self,
+        documents: &AHashMap<u32, AHashMap<u8, RoaringBitmap>>,
+        repair: bool,
+        report: &mut FsckReport,
+    ) -> trc::Result<()> {
+        // Blob links are keyed by hash, scan them once for all checked accounts
+        let store = &self.core.storage.data;
+        let mut orphaned: BTreeMap<u32, Vec<(Vec<u8>, u8, u32)>> = BTreeMap::new();
+        store
+            .iterate(
+                IterateParams::new(
+                    AnyKey {
+                        subspace: SUBSPACE_BLOB_LINK,
+                        key: vec![0u8],
+                    },
+                    AnyKey {
+                        subspace: SUBSPACE_BLOB_LINK,
+                        key: vec![u8::MAX; BLOB_HASH_LEN + U32_LEN * 2 + 1],
+                    },
+                )
+                .no_values(),
+                |key, _| {
+                    if key.len() == BLOB_HASH_LEN + U32_LEN * 2 + 1 {
+                        let account_id = key.deserialize_be_u32(BLOB_HASH_LEN)?;
+                        if let Some(documents) = documents.get(&account_id) {
+                            let collection = key[BLOB_HASH_LEN + U32_LEN];
+                            let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+                            if is_checked(collection)
+                                && !documents
+                                    .get(&collection)
+                                    .map_or(false, |ids| ids.contains(document_id))
+                            {
+                                orphaned.entry(account_id).or_default().push((
+                                    key.to_vec(),
+                                    collection,
+                                    document_id,
+                                ));
+                            }
+                        }
+                    }
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        for (account_id, keys) in orphaned {
+            let mut check = AccountCheck {
+                account_id,
+                repair,
+                report: &mut *report,
+                orphans: BTreeMap::new(),
+            };
+            for (key, collection, document_id) in keys {
+                check.orphan(
+                    FsckIssueKind::OrphanedBlobLink,
+                    SUBSPACE_BLOB_LINK,
+                    &key,
+                    collection,
+                    document_id,
+                );
+            }
+            check.remove_orphans(store).await?;
+        }
+
+        Ok(())
+    }
+}
+
+// Quota changes are written along with a change log entry or the removal of
+// a message, so an unchanged state means the quota was not modified
+#[derive(Debug, PartialEq, Eq)]
+struct QuotaState {
+    used_quota: i64,
+    email_change_id: Option<u64>,
+    script_change_id: Option<u64>,
+    emails: u64,
+}
+
+impl QuotaState {
+    async fn read(store: &Store, account_id: u32) -> trc::Result<Self> {
+        Ok(QuotaState {
+            used_quota: store
+                .get_counter(DirectoryClass::UsedQuota(account_id))
+                .await
+                .caused_by(trc::location!())?,
+            email_change_id: store
+                .get_last_change_id(account_id, Collection::Email)
+                .await
+                .caused_by(trc::location!())?,
+            script_change_id: store
+                .get_last_change_id(account_id, Collection::SieveScript)
+                .await
+                .caused_by(trc::location!())?,
+            emails: store
+                .get_bitmap(BitmapKey::document_ids(account_id, Collection::Email))
+                .await
+                .caused_by(trc::location!())?
+                .map_or(0, |ids| ids.len()),
+        })
+    }
+}
+
+// Keys left behind by a document that does not exist
+struct Orphan {
+    kind: FsckIssueKind,
+    subspace: u8,
+    key: Vec<u8>,
+}
+
+struct AccountCheck<'x> {
+    account_id: u32,
+    repair: bool,
+    report: &'x mut FsckReport,
+    orphans: BTreeMap<(u8, u32), Vec<Orphan>>,
+}
+
+impl AccountCheck<'_> {
+    fn issue(
+        &mut self,
+        kind: FsckIssueKind,
+        collection: u8,
+        document_id: Option<u32>,
+        details: Option<String>,
+        repaired: bool,
+    ) {
+        let collection = Collection::from(collection);
+
+        trc::event!(
+            Store(trc::StoreEvent::ConsistencyIssue),
+            AccountId = self.account_id,
+            Collection = collection,
+            DocumentId = document_id,
+            Type = kind.as_str(),
+            Details = details.clone(),
+        );
+
+        self.report.total += 1;
+        if repaired {
+            self.report.repaired += 1;
+        }
+        if self.report.issues.len() < MAX_REPORTED_ISSUES {
+            self.report.issues.push(FsckIssue {
+                kind,
+                account_id: self.account_id,
+                collection: collection.as_str(),
+                document_id,
+                details,
+                repaired,
+            });
+        }
+    }
+
+    fn orphan(
+        &mut self,
+        kind: FsckIssueKind,
+        subspace: u8,
+        key: &[u8],
+        collection: u8,
+        document_id: u32,
+    ) {
+        self.orphans
+            .entry((collection, document_id))
+            .or_default()
+            .push(Orphan {
+                kind,
+                subspace,
+                key: key.to_vec(),
+            });
+    }
+
+    async fn remove_orphans(&mut self, store: &Store) -> trc::Result<()> {
+        for ((collection, document_id), orphans) in std::mem::take(&mut self.orphans) {
+            let repaired = if self.repair {
+                // The document might have been created after the snapshot of the
+                // document ids was taken, its keys are only removed while it
+                // still does not exist
+                let mut removed = true;
+                for chunk in orphans.chunks(MAX_BATCH_OPS) {
+                    let mut batch = BatchBuilder::new();
+                    batch.assert_value(
+                        document_class(self.account_id, collection, document_id),
+                        AssertValue::None,
+                    );
+                    for orphan in chunk {
+                        batch.clear(ValueClass::Any(AnyClass {
+                            subspace: orphan.subspace,
+                            key: orphan.key.clone(),
+                        }));
+                    }
+                    if !write_checked(store, batch).await? {
+                        removed = false;
+                        break;
+                    }
+                }
+                if !removed {
+                    continue;
+                }
+                true
+            } else {
+                false
+            };
+
+            for orphan in orphans {
+                self.issue(orphan.kind, collection, document_id.into(), None, repaired);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FsckIssueKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FsckIssueKind::DanglingBitmap => "dangling-bitmap",
+            FsckIssueKind::DanglingIndex => "dangling-index",
+            FsckIssueKind::OrphanedProperty => "orphaned-property",
+            FsckIssueKind::OrphanedBlobLink => "orphaned-blob-link",
+            FsckIssueKind::OrphanedThread => "orphaned-thread",
+            FsckIssueKind::MissingThread => "missing-thread",
+            FsckIssueKind::MissingIndex => "missing-index",
+            FsckIssueKind::MissingMetadata => "missing-metadata",
+            FsckIssueKind::QuotaMismatch => "quota-mismatch",
+        }
+    }
+}
+
+// Principal data is stored under its own account id and is not checked
+fn is_checked(collection: u8) -> bool {
+    collection < u8::from(Collection::Principal)
+        || collection == u8::from(Collection::ShareNotification)
+}
+
+// Key of the document in the document ids bitmap
+fn document_class(account_id: u32, collection: u8, document_id: u32) -> ValueClass<MaybeDynamicId> {
+    ValueClass::Any(AnyClass {
+        subspace: SUBSPACE_BITMAP_ID,
+        key: KeySerializer::new(U32_LEN * 2 + 1)
+            .write(account_id)
+            .write(collection)
+            .write(document_id)
+            .finalize(),
+    })
+}
+
+// Returns false when an assertion failed because the document changed
+async fn write_checked(store: &Store, batch: BatchBuilder) -> trc::Result<bool> {
+    match store.write(batch.build()).await {
+        Ok(_) => Ok(true),
+        Err(err) if err.is_assertion_failure() => Ok(false),
+        Err(err) => Err(err.caused_by(trc::location!())),
+    }
+}
+
+async fn store_job(server: &Server, job: &FsckJob) -> trc::Result<()> {
+    server
+        .core
+        .storage
+        .lookup
+        .key_set(
+            format!("fsck:{}", job.id).into_bytes(),
+            serde_json::to_string(job).unwrap_or_default().into_bytes(),
+            JOB_EXPIRY.into(),
+        )
+        .await
+        .caused_by(trc::location!())
+}
+
+async fn iterate_account(
+    store: &Store,
+    subspace: u8,
+    account_id: u32,
+    mut cb: impl FnMut(&[u8]) -> trc::Result<()> + Sync + Send,
+) -> trc::Result<()> {
+    store
+        .iterate(
+            IterateParams::new(
+                AnyKey {
+                    subspace,
+                    key: KeySerializer::new(U32_LEN).write(account_id).finalize(),
+                },
+                AnyKey {
+                    subspace,
+                    key: KeySerializer::new(U32_LEN + 1)
+                        .write(account_id)
+                        .write(u8::MAX)
+                        .finalize(),
+                },
+            )
+            .no_values(),
+            |key, _| cb(key).map(|_| true),
+        )
+        .await
+        .caused_by(trc::location!())
+}
@@ -0,0 +1,45 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use store::dispatch::cache::{apply_invalidation, take_invalidations, CacheInvalidation};
+
+use super::request::Request;
+use super::Gossiper;
+
+// Worst case LEB128 encoding of an account id is 5 bytes
+const MAX_ACCOUNTS_PER_PACKET: usize = (super::UDP_MAX_PAYLOAD - 128) / 5;
+
+impl Gossiper {
+    pub async fn broadcast_invalidations(&self) {
+        let invalidation = if let Some(invalidation) = take_invalidations() {
+            invalidation
+        } else {
+            return;
+        };
+
+        let requests = match invalidation {
+            CacheInvalidation::Accounts(account_ids) => account_ids
+                .chunks(MAX_ACCOUNTS_PER_PACKET)
+                .map(|account_ids| {
+                    Request::Invalidate(CacheInvalidation::Accounts(account_ids.to_vec()))
+                })
+                .collect::<Vec<_>>(),
+            CacheInvalidation::All => vec![Request::Invalidate(CacheInvalidation::All)],
+        };
+
+        for peer in &self.peers {
+            if !peer.is_offline() && !peer.is_seed() {
+                for request in &requests {
+                    self.send_gossip(peer.addr, request.clone()).await;
+                }
+            }
+        }
+    }
+
+    pub fn handle_invalidation(&self, invalidation: CacheInvalidation) {
+        apply_invalidation(&invalidation);
+    }
+}
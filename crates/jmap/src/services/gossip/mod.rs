@@ -5,6 +5,7 @@
  */
 
 pub mod heartbeat;
+pub mod invalidate;
 pub mod leave;
 pub mod peer;
 pub mod ping;
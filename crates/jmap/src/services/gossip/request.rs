@@ -8,23 +8,38 @@ use super::{EpochId, PeerStatus};
 
 use common::auth::oauth::crypto::SymmetricEncrypt;
 use std::net::IpAddr;
+use store::dispatch::cache::CacheInvalidation;
 use utils::codec::leb128::Leb128_;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Request {
     Ping(Vec<PeerStatus>),
     Pong(Vec<PeerStatus>),
     Leave(Vec<PeerStatus>),
+    Invalidate(CacheInvalidation),
 }
 
 impl Request {
     const PING: u8 = 0;
     const PONG: u8 = 1;
     const LEAVE: u8 = 2;
+    const INVALIDATE_ACCOUNTS: u8 = 3;
+    const INVALIDATE_ALL: u8 = 4;
 
     pub fn from_bytes(bytes: &[u8]) -> Option<Request> {
         let mut it = bytes.iter();
         let flags = it.next().copied()?;
+        match flags {
+            Self::INVALIDATE_ACCOUNTS => {
+                let mut account_ids = Vec::with_capacity(bytes.len() / 2);
+                while it.len() > 0 {
+                    account_ids.push(u32::from_leb128_it(&mut it)?);
+                }
+                return Request::Invalidate(CacheInvalidation::Accounts(account_ids)).into();
+            }
+            Self::INVALIDATE_ALL => return Request::Invalidate(CacheInvalidation::All).into(),
+            _ => (),
+        }
         let is_ipv6 = flags & (1 << 7) != 0;
 
         let mut peers = Vec::with_capacity(bytes.len() / std::mem::size_of::<PeerStatus>());
@@ -73,6 +88,22 @@ impl Request {
             Request::Ping(peers) => (Self::PING, peers),
             Request::Pong(peers) => (Self::PONG, peers),
             Request::Leave(peers) => (Self::LEAVE, peers),
+            Request::Invalidate(CacheInvalidation::Accounts(account_ids)) => {
+                let mut bytes = Vec::with_capacity(
+                    1 + (account_ids.len() * std::mem::size_of::<u32>())
+                        + SymmetricEncrypt::ENCRYPT_TAG_LEN,
+                );
+                bytes.push(Self::INVALIDATE_ACCOUNTS);
+                for account_id in account_ids {
+                    account_id.to_leb128_bytes(&mut bytes);
+                }
+                return bytes;
+            }
+            Request::Invalidate(CacheInvalidation::All) => {
+                let mut bytes = Vec::with_capacity(1 + SymmetricEncrypt::ENCRYPT_TAG_LEN);
+                bytes.push(Self::INVALIDATE_ALL);
+                return bytes;
+            }
         };
 
         debug_assert!(!peers.is_empty());
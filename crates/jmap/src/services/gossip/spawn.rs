@@ -90,6 +90,9 @@ impl GossiperBuilder {
             LocalPort = self.port,
         );
 
+        // Start collecting store cache invalidations for other nodes
+        store::dispatch::cache::enable_cluster_invalidations();
+
         // Create gossiper
         let (gossip_tx, mut gossip_rx) = mpsc::channel::<(SocketAddr, Request)>(IPC_CHANNEL_BUFFER);
         let mut gossiper = Gossiper {
@@ -165,6 +168,9 @@ impl GossiperBuilder {
                                                 Request::Leave(peers) => {
                                                     gossiper.handle_leave(peers).await;
                                                 },
+                                                Request::Invalidate(invalidation) => {
+                                                    gossiper.handle_invalidation(invalidation);
+                                                },
                                             }
                                         } else {
                                             trc::event!(
@@ -197,8 +203,9 @@ impl GossiperBuilder {
                         }
                     },
                     _ = tokio::time::sleep(wait) => {
-                        // Send ping
+                        // Send ping and pending cache invalidations
                         gossiper.ping_peers().await;
+                        gossiper.broadcast_invalidations().await;
                         last_ping = Instant::now();
                     },
                     _ = shutdown_rx.changed() => {
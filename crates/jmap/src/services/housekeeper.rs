@@ -29,7 +29,11 @@ use tokio::sync::mpsc;
 use trc::{Collector, MetricType};
 use utils::map::ttl_dashmap::TtlMap;
 
-use crate::{email::delete::EmailDeletion, JmapMethods, LONG_SLUMBER};
+use crate::{
+    email::{delete::EmailDeletion, snooze::EmailSnooze},
+    services::fsck::StoreCheck,
+    JmapMethods, LONG_SLUMBER,
+};
 
 #[derive(PartialEq, Eq)]
 struct Action {
@@ -41,8 +45,11 @@ struct Action {
 enum ActionClass {
     Session,
     Account,
+    Deprovision,
     Store(usize),
+    Backup,
     Acme(String),
+    Snooze,
     OtelMetrics,
     #[cfg(feature = "enterprise")]
     InternalMetrics,
@@ -83,6 +90,12 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                 ActionClass::Account,
             );
 
+            // Deprovisioning of inactive accounts
+            queue.schedule(
+                Instant::now() + server.core.auth.deprovision_frequency.time_to_next(),
+                ActionClass::Deprovision,
+            );
+
             // Store purges
             for (idx, schedule) in server.core.storage.purge_schedules.iter().enumerate() {
                 queue.schedule(
@@ -91,6 +104,20 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                 );
             }
 
+            // Scheduled backups
+            if let Some(schedule) = server
+                .core
+                .storage
+                .backup
+                .as_ref()
+                .and_then(|backup| backup.schedule.as_ref())
+            {
+                queue.schedule(
+                    Instant::now() + schedule.time_to_next(),
+                    ActionClass::Backup,
+                );
+            }
+
             // OTEL Push Metrics
             if let Some(otel) = &server.core.metrics.otel {
                 OtelMetrics::enable_errors();
@@ -100,6 +127,9 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
             // Calculate expensive metrics
             queue.schedule(Instant::now(), ActionClass::CalculateMetrics);
 
+            // Wake up snoozed messages
+            queue.schedule(Instant::now(), ActionClass::Snooze);
+
             // Add all ACME renewals to heap
             for provider in server.core.acme.providers.values() {
                 match server.init_acme(provider).await {
@@ -168,6 +198,22 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                             _ => {}
                         }
 
+                        // Reload backup schedule
+                        if let Some(schedule) = server
+                            .core
+                            .storage
+                            .backup
+                            .as_ref()
+                            .and_then(|backup| backup.schedule.as_ref())
+                        {
+                            if !queue.has_action(&ActionClass::Backup) {
+                                queue.schedule(
+                                    Instant::now() + schedule.time_to_next(),
+                                    ActionClass::Backup,
+                                );
+                            }
+                        }
+
                         // SPDX-SnippetBegin
                         // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
                         // SPDX-License-Identifier: LicenseRef-SEL
@@ -229,6 +275,12 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                         queue.remove_action(&action);
                         queue.schedule(renew_at, action);
                     }
+                    HousekeeperEvent::SnoozeReschedule { due } => {
+                        if !queue.has_action_before(&ActionClass::Snooze, due) {
+                            queue.remove_action(&ActionClass::Snooze);
+                            queue.schedule(due, ActionClass::Snooze);
+                        }
+                    }
                     HousekeeperEvent::Purge(purge) => match purge {
                         PurgeType::Data(store) => {
                             // SPDX-SnippetBegin
@@ -317,6 +369,34 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                             });
                         }
                     },
+                    HousekeeperEvent::Backup { full } => {
+                        let server = inner.build_server();
+                        tokio::spawn(async move {
+                            if let Err(err) = server.core.online_backup(full).await {
+                                trc::error!(err.details("Failed to backup data store"));
+                            }
+                        });
+                    }
+                    HousekeeperEvent::Migrate { store, blob } => {
+                        let server = inner.build_server();
+                        tokio::spawn(async move {
+                            if let Err(err) = server.migrate_store(&store, blob.as_deref()).await {
+                                trc::error!(err.details("Failed to migrate data store"));
+                            }
+                        });
+                    }
+                    HousekeeperEvent::Fsck {
+                        id,
+                        account_id,
+                        repair,
+                    } => {
+                        let server = inner.build_server();
+                        tokio::spawn(async move {
+                            if let Err(err) = server.run_check(id, account_id, repair).await {
+                                trc::error!(err.details("Failed to run consistency check"));
+                            }
+                        });
+                    }
                     HousekeeperEvent::Exit => {
                         trc::event!(Housekeeper(trc::HousekeeperEvent::Stop));
 
@@ -390,6 +470,23 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                     server.purge_accounts().await;
                                 });
                             }
+                            ActionClass::Deprovision => {
+                                let server = server.clone();
+                                queue.schedule(
+                                    Instant::now()
+                                        + server.core.auth.deprovision_frequency.time_to_next(),
+                                    ActionClass::Deprovision,
+                                );
+                                tokio::spawn(async move {
+                                    server.deprovision_accounts().await;
+                                });
+                            }
+                            ActionClass::Snooze => {
+                                let server = server.clone();
+                                tokio::spawn(async move {
+                                    server.email_wake_snoozed().await;
+                                });
+                            }
                             ActionClass::Session => {
                                 let server = server.clone();
                                 queue.schedule(
@@ -437,6 +534,10 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                             PurgeStore::Lookup(lookup_store) => {
                                                 ("lookup", lookup_store.purge_lookup_store().await)
                                             }
+                                            PurgeStore::BlobTiers(blob_store) => (
+                                                "tiered blob",
+                                                blob_store.migrate_cold_blobs().await,
+                                            ),
                                         };
 
                                         match result {
@@ -457,6 +558,27 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                     });
                                 }
                             }
+                            ActionClass::Backup => {
+                                if let Some(schedule) = server
+                                    .core
+                                    .storage
+                                    .backup
+                                    .as_ref()
+                                    .and_then(|backup| backup.schedule.as_ref())
+                                {
+                                    queue.schedule(
+                                        Instant::now() + schedule.time_to_next(),
+                                        ActionClass::Backup,
+                                    );
+
+                                    let server = server.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(err) = server.core.online_backup(false).await {
+                                            trc::error!(err.details("Failed to backup data store"));
+                                        }
+                                    });
+                                }
+                            }
                             ActionClass::OtelMetrics => {
                                 if let Some(otel) = &server.core.metrics.otel {
                                     queue.schedule(
@@ -683,6 +805,10 @@ impl Queue {
     pub fn has_action(&self, event: &ActionClass) -> bool {
         self.heap.iter().any(|e| &e.event == event)
     }
+
+    pub fn has_action_before(&self, event: &ActionClass, due: Instant) -> bool {
+        self.heap.iter().any(|e| &e.event == event && e.due <= due)
+    }
 }
 
 impl Ord for Action {
@@ -166,7 +166,7 @@ impl Indexer for Server {
                             .with_account_id(event.account_id)
                             .with_collection(Collection::Email)
                             .with_document_id(event.document_id)
-                            .index_message(&message);
+                            .index_message(&message, &self.core.jmap);
                     if let Err(err) = self.core.storage.fts.index(document).await {
                         trc::error!(err
                             .account_id(event.account_id)
@@ -10,7 +10,7 @@ use common::{
 };
 use directory::Permission;
 use jmap_proto::types::{state::StateChange, type_state::DataType};
-use mail_parser::MessageParser;
+use mail_parser::{MessageParser, MimeHeaders};
 use std::future::Future;
 use store::ahash::AHashMap;
 
@@ -18,6 +18,7 @@ use crate::{
     email::ingest::{EmailIngest, IngestEmail, IngestSource},
     mailbox::INBOX_ID,
     sieve::{get::SieveScriptGet, ingest::SieveScriptIngest},
+    submission::report::{DeliveryReport, EmailSubmissionReport},
 };
 
 use super::state::StateManager;
@@ -68,6 +69,15 @@ impl MailDelivery for Server {
             }
         };
 
+        // Check whether this is a delivery status notification or a read receipt,
+        // only reports are parsed in full
+        let report = MessageParser::new()
+            .parse_headers(&raw_message)
+            .filter(|headers| headers.root_part().is_content_type("multipart", "report"))
+            .and_then(|_| MessageParser::new().parse(&raw_message))
+            .as_ref()
+            .and_then(DeliveryReport::parse);
+
         // Obtain the UIDs for each recipient
         let mut recipients = Vec::with_capacity(message.recipients.len());
         let mut deliver_names = AHashMap::with_capacity(message.recipients.len());
@@ -149,6 +159,19 @@ impl MailDelivery for Server {
                                 .with_change(DataType::Thread, ingested_message.change_id),
                         )
                         .await;
+
+                        // Update the originating submission
+                        if let Some(report) = &report {
+                            if let Err(err) = self
+                                .email_submission_report(*uid, report, &ingested_message.blob_id)
+                                .await
+                            {
+                                trc::error!(err
+                                    .details("Failed to update email submission.")
+                                    .span_id(message.session_id)
+                                    .caused_by(trc::location!()));
+                            }
+                        }
                     }
                 }
                 Err(err) => {
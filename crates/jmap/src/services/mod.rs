@@ -5,6 +5,7 @@
  */
 
 pub mod delivery;
+pub mod fsck;
 pub mod gossip;
 pub mod housekeeper;
 pub mod index;
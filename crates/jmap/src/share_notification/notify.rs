@@ -0,0 +1,189 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use directory::{backend::internal::PrincipalField, QueryBy};
+use jmap_proto::{
+    object::{index::ObjectIndexBuilder, mailbox::acl_to_mailbox_rights, Object},
+    types::{
+        acl::Acl,
+        collection::Collection,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{AclGrant, Value},
+    },
+};
+use std::future::Future;
+use store::write::{log::ChangeLogBuilder, now, BatchBuilder};
+use trc::AddContext;
+use utils::map::bitmap::Bitmap;
+
+use crate::{changes::write::ChangeLog, services::state::StateManager, JmapMethods};
+
+use super::set::SCHEMA;
+
+pub trait ShareNotify: Sync + Send {
+    fn notify_share_changes(
+        &self,
+        changed_by: u32,
+        account_id: u32,
+        mailbox_id: u32,
+        mailbox_name: &str,
+        current: &[AclGrant],
+        changes: &[AclGrant],
+    ) -> impl Future<Output = ()> + Send;
+
+    fn write_share_notifications(
+        &self,
+        changed_by: u32,
+        account_id: u32,
+        mailbox_id: u32,
+        mailbox_name: &str,
+        current: &[AclGrant],
+        changes: &[AclGrant],
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl ShareNotify for Server {
+    async fn notify_share_changes(
+        &self,
+        changed_by: u32,
+        account_id: u32,
+        mailbox_id: u32,
+        mailbox_name: &str,
+        current: &[AclGrant],
+        changes: &[AclGrant],
+    ) {
+        // The mailbox has already been updated at this point, failures are only logged
+        if let Err(err) = self
+            .write_share_notifications(
+                changed_by,
+                account_id,
+                mailbox_id,
+                mailbox_name,
+                current,
+                changes,
+            )
+            .await
+        {
+            trc::error!(err
+                .account_id(account_id)
+                .document_id(mailbox_id)
+                .caused_by(trc::location!()));
+        }
+    }
+
+    async fn write_share_notifications(
+        &self,
+        changed_by: u32,
+        account_id: u32,
+        mailbox_id: u32,
+        mailbox_name: &str,
+        current: &[AclGrant],
+        changes: &[AclGrant],
+    ) -> trc::Result<()> {
+        // Obtain the principals whose rights have changed
+        let mut notify = Vec::new();
+        for grant in current.iter().chain(changes.iter()) {
+            if notify
+                .iter()
+                .any(|(notify_id, _, _)| *notify_id == grant.account_id)
+            {
+                continue;
+            }
+            let old_rights = current
+                .iter()
+                .find(|item| item.account_id == grant.account_id)
+                .map(|item| item.grants)
+                .unwrap_or_default();
+            let new_rights = changes
+                .iter()
+                .find(|item| item.account_id == grant.account_id)
+                .map(|item| item.grants)
+                .unwrap_or_default();
+            if old_rights != new_rights {
+                notify.push((grant.account_id, old_rights, new_rights));
+            }
+        }
+        if notify.is_empty() {
+            return Ok(());
+        }
+
+        // Obtain the details of the principal that made the change
+        let mut changed_by_obj = Object::with_capacity(3)
+            .with_property(Property::PrincipalId, Value::Id(changed_by.into()));
+        if let Some(principal) = self
+            .core
+            .storage
+            .directory
+            .query(QueryBy::Id(changed_by), false)
+            .await
+            .caused_by(trc::location!())?
+        {
+            changed_by_obj.append(
+                Property::Name,
+                principal
+                    .description()
+                    .unwrap_or_else(|| principal.name())
+                    .to_string(),
+            );
+            changed_by_obj.append(
+                Property::Email,
+                principal
+                    .get_str(PrincipalField::Emails)
+                    .map(|email| Value::Text(email.to_string()))
+                    .unwrap_or_default(),
+            );
+        }
+
+        let created = now();
+        for (notify_account_id, old_rights, new_rights) in notify {
+            // The owner of the mailbox does not need to be notified
+            if notify_account_id == account_id {
+                continue;
+            }
+
+            let notification = Object::with_capacity(8)
+                .with_property(Property::Created, Value::UnsignedInt(created))
+                .with_property(Property::ChangedBy, Value::Object(changed_by_obj.clone()))
+                .with_property(Property::ObjectType, "Mailbox")
+                .with_property(Property::ObjectAccountId, Value::Id(account_id.into()))
+                .with_property(Property::ObjectId, Value::Id(mailbox_id.into()))
+                .with_property(Property::OldRights, rights_to_value(old_rights))
+                .with_property(Property::NewRights, rights_to_value(new_rights))
+                .with_property(Property::Name, mailbox_name);
+
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(notify_account_id)
+                .with_collection(Collection::ShareNotification)
+                .create_document()
+                .custom(ObjectIndexBuilder::new(SCHEMA).with_changes(notification));
+            let document_id = self.write_batch_expect_id(batch).await?;
+
+            // Notify clients
+            let mut changes = ChangeLogBuilder::new();
+            changes.log_insert(Collection::ShareNotification, document_id);
+            let change_id = self.commit_changes(notify_account_id, changes).await?;
+            self.broadcast_state_change(
+                StateChange::new(notify_account_id)
+                    .with_change(DataType::ShareNotification, change_id),
+            )
+            .await;
+        }
+
+        Ok(())
+    }
+}
+
+fn rights_to_value(rights: Bitmap<Acl>) -> Value {
+    if !rights.is_empty() {
+        Value::Object(acl_to_mailbox_rights(&rights))
+    } else {
+        Value::Null
+    }
+}
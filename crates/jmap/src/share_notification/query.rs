@@ -0,0 +1,94 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::{
+    method::query::{
+        Comparator, Filter, QueryRequest, QueryResponse, RequestArguments, SortProperty,
+    },
+    types::{collection::Collection, property::Property},
+};
+use std::future::Future;
+use store::query::{self};
+
+use crate::JmapMethods;
+
+pub trait ShareNotificationQuery: Sync + Send {
+    fn share_notification_query(
+        &self,
+        request: QueryRequest<RequestArguments>,
+    ) -> impl Future<Output = trc::Result<QueryResponse>> + Send;
+}
+
+impl ShareNotificationQuery for Server {
+    async fn share_notification_query(
+        &self,
+        mut request: QueryRequest<RequestArguments>,
+    ) -> trc::Result<QueryResponse> {
+        let account_id = request.account_id.document_id();
+        let mut filters = Vec::with_capacity(request.filter.len());
+
+        for cond in std::mem::take(&mut request.filter) {
+            match cond {
+                Filter::Before(before) => filters.push(query::Filter::lt(
+                    Property::Created,
+                    before.timestamp() as u64,
+                )),
+                Filter::After(after) => filters.push(query::Filter::ge(
+                    Property::Created,
+                    after.timestamp() as u64,
+                )),
+                Filter::ObjectType(object_type) => {
+                    filters.push(query::Filter::eq(Property::ObjectType, object_type))
+                }
+                Filter::ObjectAccountId(id) => filters.push(query::Filter::eq(
+                    Property::ObjectAccountId,
+                    id.document_id(),
+                )),
+                Filter::And | Filter::Or | Filter::Not | Filter::Close => {
+                    filters.push(cond.into());
+                }
+                other => {
+                    return Err(trc::JmapEvent::UnsupportedFilter
+                        .into_err()
+                        .details(other.to_string()))
+                }
+            }
+        }
+
+        let result_set = self
+            .filter(account_id, Collection::ShareNotification, filters)
+            .await?;
+
+        let (response, paginate) = self.build_query_response(&result_set, &request).await?;
+
+        if let Some(paginate) = paginate {
+            // Parse sort criteria
+            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
+            for comparator in request
+                .sort
+                .and_then(|s| if !s.is_empty() { s.into() } else { None })
+                .unwrap_or_else(|| vec![Comparator::descending(SortProperty::Created)])
+            {
+                comparators.push(match comparator.property {
+                    SortProperty::Created => {
+                        query::Comparator::field(Property::Created, comparator.is_ascending)
+                    }
+                    other => {
+                        return Err(trc::JmapEvent::UnsupportedSort
+                            .into_err()
+                            .details(other.to_string()))
+                    }
+                });
+            }
+
+            // Sort results
+            self.sort(result_set, comparators, paginate, response).await
+        } else {
+            Ok(response)
+        }
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::{
+    error::set::SetError,
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    object::{
+        index::{IndexAs, IndexProperty, ObjectIndexBuilder},
+        Object,
+    },
+    types::{
+        collection::Collection, property::Property, state::StateChange, type_state::DataType,
+        value::Value,
+    },
+};
+use std::future::Future;
+use store::write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder};
+use trc::AddContext;
+
+use crate::{changes::write::ChangeLog, JmapMethods};
+
+pub static SCHEMA: &[IndexProperty] = &[
+    IndexProperty::new(Property::Created).index_as(IndexAs::LongInteger),
+    IndexProperty::new(Property::ObjectType).index_as(IndexAs::Text {
+        tokenize: false,
+        index: true,
+    }),
+    IndexProperty::new(Property::ObjectAccountId).index_as(IndexAs::Integer),
+];
+
+pub trait ShareNotificationSet: Sync + Send {
+    fn share_notification_set(
+        &self,
+        request: SetRequest<RequestArguments>,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+}
+
+impl ShareNotificationSet for Server {
+    async fn share_notification_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let mut response = self
+            .prepare_set_response(&request, Collection::ShareNotification)
+            .await?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Share notifications are created by the server
+        for (id, _) in request.unwrap_create() {
+            response.not_created.append(
+                id,
+                SetError::forbidden()
+                    .with_description("Share notifications cannot be created by clients."),
+            );
+        }
+
+        // Share notifications are immutable
+        for (id, _) in request.unwrap_update() {
+            response.not_updated.append(
+                id,
+                SetError::forbidden().with_description("Share notifications cannot be modified."),
+            );
+        }
+
+        // Process deletions
+        let mut changes = ChangeLogBuilder::new();
+        for id in will_destroy {
+            let document_id = id.document_id();
+            if let Some(notification) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::ShareNotification,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::ShareNotification)
+                    .delete_document(document_id)
+                    .custom(ObjectIndexBuilder::new(SCHEMA).with_current(notification));
+                match self.core.storage.data.write(batch.build()).await {
+                    Ok(_) => {
+                        changes.log_delete(Collection::ShareNotification, document_id);
+                        response.destroyed.push(id);
+                    }
+                    Err(err) if err.is_assertion_failure() => {
+                        response.not_destroyed.append(
+                            id,
+                            SetError::forbidden().with_description(
+                                "Another process modified this notification, please try again.",
+                            ),
+                        );
+                    }
+                    Err(err) => {
+                        return Err(err.caused_by(trc::location!()));
+                    }
+                }
+            } else {
+                response.not_destroyed.append(id, SetError::not_found());
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            let change_id = self.commit_changes(account_id, changes).await?;
+            response.state_change = StateChange::new(account_id)
+                .with_change(DataType::ShareNotification, change_id)
+                .into();
+            response.new_state = Some(change_id.into());
+        }
+
+        Ok(response)
+    }
+}
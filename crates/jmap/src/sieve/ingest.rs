@@ -7,10 +7,18 @@
 use std::borrow::Cow;
 
 use common::{
-    auth::AccessToken, listener::stream::NullIo, scripts::plugins::PluginContext, Server,
+    auth::AccessToken,
+    listener::stream::NullIo,
+    scripts::{plugins::PluginContext, ScriptModification},
+    Server,
 };
 use directory::{backend::internal::PrincipalField, QueryBy};
-use jmap_proto::types::{collection::Collection, id::Id, keyword::Keyword, property::Property};
+use jmap_proto::types::{
+    collection::Collection,
+    id::Id,
+    keyword::{Keyword, SNOOZED_KEYWORD},
+    property::Property,
+};
 use mail_parser::MessageParser;
 use sieve::{Envelope, Event, Input, Mailbox, Recipient};
 use smtp::core::{Session, SessionAddress};
@@ -21,7 +29,10 @@ use store::{
 use trc::{AddContext, SieveEvent};
 
 use crate::{
-    email::ingest::{EmailIngest, IngestEmail, IngestSource, IngestedEmail},
+    email::{
+        ingest::{EmailIngest, IngestEmail, IngestSource, IngestedEmail},
+        snooze::{EmailSnooze, SnoozeDetails},
+    },
     mailbox::{get::MailboxGet, set::MailboxSet, INBOX_ID, TRASH_ID},
     sieve::SeenIdHash,
     JmapMethods,
@@ -111,6 +122,7 @@ impl SieveScriptIngest for Server {
 
         let mut new_ids = AHashSet::new();
         let mut reject_reason = None;
+        let mut modifications = Vec::new();
         let mut messages: Vec<SieveMessage> = vec![SieveMessage {
             raw_message: raw_message.into(),
             file_into: Vec::new(),
@@ -414,7 +426,7 @@ impl SieveScriptIngest for Server {
                                     session_id,
                                     server: self,
                                     message: instance.message(),
-                                    modifications: &mut Vec::new(),
+                                    modifications: &mut modifications,
                                     access_token: access_token.into(),
                                     arguments,
                                 },
@@ -453,6 +465,28 @@ impl SieveScriptIngest for Server {
             messages[0].file_into.push(INBOX_ID);
         }
 
+        // Snooze the original message on delivery
+        let mut snooze = None;
+        for modification in modifications {
+            if let ScriptModification::Snooze { until, mailbox } = modification {
+                let move_to = if !mailbox.is_empty() {
+                    self.mailbox_get_by_name(account_id, &mailbox)
+                        .await
+                        .caused_by(trc::location!())?
+                        .unwrap_or(INBOX_ID)
+                } else {
+                    INBOX_ID
+                };
+                snooze = SnoozeDetails { until, move_to }.into();
+            }
+        }
+        if snooze.is_some() {
+            let snoozed_keyword = Keyword::Other(SNOOZED_KEYWORD.to_string());
+            if !messages[0].flags.contains(&snoozed_keyword) {
+                messages[0].flags.push(snoozed_keyword);
+            }
+        }
+
         // Deliver messages
         let mut last_temp_error = None;
         let mut has_delivered = false;
@@ -493,6 +527,27 @@ impl SieveScriptIngest for Server {
                     Ok(ingested_message_) => {
                         has_delivered = true;
                         ingested_message = ingested_message_;
+
+                        if message_id == 0 {
+                            if let Some(snooze) = snooze.take() {
+                                let mut batch = BatchBuilder::new();
+                                batch
+                                    .with_account_id(account_id)
+                                    .with_collection(Collection::Email)
+                                    .update_document(ingested_message.id.document_id());
+                                snooze.set(&mut batch);
+                                match self.write_batch(batch).await {
+                                    Ok(_) => {
+                                        self.email_snooze_reschedule(snooze.until).await;
+                                    }
+                                    Err(err) => {
+                                        trc::error!(err
+                                            .span_id(session_id)
+                                            .details("Failed to snooze message."));
+                                    }
+                                }
+                            }
+                        }
                     }
                     Err(err) => {
                         last_temp_error = err.into();
@@ -99,6 +99,15 @@ impl EmailSubmissionGet for Server {
                         match (queued_message.as_ref(), push.remove(property)) {
                             (Some(message), Value::Object(mut status)) => {
                                 for rcpt in &message.recipients {
+                                    // Keep any read receipt status received so far
+                                    let displayed = status
+                                        .get(&Property::_T(rcpt.address.clone()))
+                                        .as_obj()
+                                        .and_then(|status| {
+                                            status.get(&Property::Displayed).as_string()
+                                        })
+                                        .unwrap_or("unknown")
+                                        .to_string();
                                     status.set(
                                         Property::_T(rcpt.address.clone()),
                                         Object::with_capacity(3)
@@ -132,7 +141,7 @@ impl EmailSubmissionGet for Server {
                                                     }
                                                 },
                                             )
-                                            .with_property(Property::Displayed, "unknown"),
+                                            .with_property(Property::Displayed, displayed),
                                     );
                                 }
 
@@ -145,7 +154,13 @@ impl EmailSubmissionGet for Server {
                         if queued_message.is_some() {
                             Value::Text("pending".to_string())
                         } else {
-                            push.remove(property)
+                            match push.remove(property) {
+                                // Scheduled messages that have left the queue
+                                Value::Text(undo_status) if undo_status == "pending" => {
+                                    Value::Text("final".to_string())
+                                }
+                                value => value,
+                            }
                         }
                     }
                     Property::EmailId
@@ -153,7 +168,10 @@ impl EmailSubmissionGet for Server {
                     | Property::ThreadId
                     | Property::Envelope
                     | Property::SendAt => push.remove(property),
-                    Property::MdnBlobIds | Property::DsnBlobIds => Value::List(vec![]),
+                    Property::MdnBlobIds | Property::DsnBlobIds => match push.remove(property) {
+                        Value::Null => Value::List(vec![]),
+                        value => value,
+                    },
                     _ => Value::Null,
                 };
 
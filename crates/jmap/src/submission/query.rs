@@ -9,18 +9,35 @@ use jmap_proto::{
     method::query::{
         Comparator, Filter, QueryRequest, QueryResponse, RequestArguments, SortProperty,
     },
-    types::{collection::Collection, property::Property},
+    object::{index::ObjectIndexBuilder, Object},
+    types::{
+        collection::Collection, property::Property, state::StateChange, type_state::DataType,
+        value::Value,
+    },
 };
+use smtp::queue::{spool::SmtpSpool, Status};
 use std::future::Future;
-use store::query::{self};
+use store::{
+    query::{self},
+    roaring::RoaringBitmap,
+    write::{assert::HashedValue, log::ChangeLogBuilder, now, BatchBuilder},
+};
+use trc::AddContext;
 
-use crate::JmapMethods;
+use crate::{changes::write::ChangeLog, services::state::StateManager, JmapMethods};
+
+use super::set::SCHEMA;
 
 pub trait EmailSubmissionQuery: Sync + Send {
     fn email_submission_query(
         &self,
         request: QueryRequest<RequestArguments>,
     ) -> impl Future<Output = trc::Result<QueryResponse>> + Send;
+
+    fn queued_submissions(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<(RoaringBitmap, RoaringBitmap)>> + Send;
 }
 
 impl EmailSubmissionQuery for Server {
@@ -31,6 +48,17 @@ impl EmailSubmissionQuery for Server {
         let account_id = request.account_id.document_id();
         let mut filters = Vec::with_capacity(request.filter.len());
 
+        // Pending and scheduled submissions are obtained from the queue
+        let queued = if request
+            .filter
+            .iter()
+            .any(|cond| matches!(cond, Filter::UndoStatus(_) | Filter::IsScheduled(_)))
+        {
+            self.queued_submissions(account_id).await?
+        } else {
+            Default::default()
+        };
+
         for cond in std::mem::take(&mut request.filter) {
             match cond {
                 Filter::IdentityIds(ids) => {
@@ -55,7 +83,11 @@ impl EmailSubmissionQuery for Server {
                     filters.push(query::Filter::End);
                 }
                 Filter::UndoStatus(undo_status) => {
-                    filters.push(query::Filter::eq(Property::UndoStatus, undo_status))
+                    if undo_status == "pending" {
+                        filters.push(query::Filter::is_in_set(queued.0.clone()));
+                    } else {
+                        filters.push(query::Filter::eq(Property::UndoStatus, undo_status));
+                    }
                 }
                 Filter::Before(before) => filters.push(query::Filter::lt(
                     Property::SendAt,
@@ -65,6 +97,15 @@ impl EmailSubmissionQuery for Server {
                     Property::SendAt,
                     after.timestamp() as u64,
                 )),
+                Filter::IsScheduled(is_scheduled) => {
+                    if !is_scheduled {
+                        filters.push(query::Filter::Not);
+                    }
+                    filters.push(query::Filter::is_in_set(queued.1.clone()));
+                    if !is_scheduled {
+                        filters.push(query::Filter::End);
+                    }
+                }
                 Filter::And | Filter::Or | Filter::Not | Filter::Close => {
                     filters.push(cond.into());
                 }
@@ -114,4 +155,89 @@ impl EmailSubmissionQuery for Server {
             Ok(response)
         }
     }
+
+    /// Returns the submissions still in the queue and, out of those, the ones
+    /// whose delivery has not been attempted yet. Submissions that have left
+    /// the queue since they were created are marked as final.
+    async fn queued_submissions(
+        &self,
+        account_id: u32,
+    ) -> trc::Result<(RoaringBitmap, RoaringBitmap)> {
+        let pending_ids = self
+            .filter(
+                account_id,
+                Collection::EmailSubmission,
+                vec![query::Filter::eq(Property::UndoStatus, "pending")],
+            )
+            .await?
+            .results;
+        let mut pending = RoaringBitmap::new();
+        let mut scheduled = RoaringBitmap::new();
+        let mut changes = ChangeLogBuilder::new();
+        let current_time = now();
+
+        for document_id in pending_ids {
+            let submission = if let Some(submission) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::EmailSubmission,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                submission
+            } else {
+                continue;
+            };
+
+            let queue_id = submission
+                .inner
+                .get(&Property::MessageId)
+                .as_uint()
+                .unwrap_or(u64::MAX);
+            if let Some(message) = self.read_message(queue_id).await {
+                pending.insert(document_id);
+                if message.domains.iter().all(|domain| {
+                    matches!(domain.status, Status::Scheduled) && domain.retry.due > current_time
+                }) {
+                    scheduled.insert(document_id);
+                }
+                continue;
+            }
+
+            // The message was released by the queue
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::EmailSubmission)
+                .update_document(document_id)
+                .custom(
+                    ObjectIndexBuilder::new(SCHEMA)
+                        .with_current(submission)
+                        .with_changes(
+                            Object::with_capacity(1).with_property(Property::UndoStatus, "final"),
+                        ),
+                );
+            match self.write_batch(batch).await {
+                Ok(_) => {
+                    changes.log_update(Collection::EmailSubmission, document_id);
+                }
+                Err(err) if err.is_assertion_failure() => {
+                    // Updated concurrently
+                }
+                Err(err) => return Err(err.caused_by(trc::location!())),
+            }
+        }
+
+        if !changes.is_empty() {
+            let change_id = self.commit_changes(account_id, changes).await?;
+            self.broadcast_state_change(
+                StateChange::new(account_id).with_change(DataType::EmailSubmission, change_id),
+            )
+            .await;
+        }
+
+        Ok((pending, scheduled))
+    }
 }
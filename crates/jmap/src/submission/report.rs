@@ -0,0 +1,421 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::{
+    object::{index::ObjectIndexBuilder, Object},
+    types::{
+        blob::BlobId, collection::Collection, id::Id, property::Property, state::StateChange,
+        type_state::DataType, value::Value,
+    },
+};
+use mail_parser::{Message, MessageParser, MimeHeaders, PartType};
+use std::future::Future;
+use store::{
+    query::Filter,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder},
+};
+use trc::AddContext;
+
+use crate::{changes::write::ChangeLog, services::state::StateManager, JmapMethods};
+
+use super::set::SCHEMA;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryReport {
+    pub message_id: String,
+    pub envelope_id: Option<String>,
+    pub kind: DeliveryReportKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryReportKind {
+    Dsn(Vec<DsnRecipient>),
+    Mdn {
+        recipients: Vec<String>,
+        displayed: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DsnRecipient {
+    pub addresses: Vec<String>,
+    pub delivered: &'static str,
+    pub smtp_reply: Option<String>,
+}
+
+pub trait EmailSubmissionReport: Sync + Send {
+    fn email_submission_report(
+        &self,
+        account_id: u32,
+        report: &DeliveryReport,
+        blob_id: &BlobId,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl EmailSubmissionReport for Server {
+    async fn email_submission_report(
+        &self,
+        account_id: u32,
+        report: &DeliveryReport,
+        blob_id: &BlobId,
+    ) -> trc::Result<()> {
+        // Find the emails the report refers to
+        let document_ids = self
+            .core
+            .storage
+            .data
+            .filter(
+                account_id,
+                Collection::Email,
+                vec![Filter::eq(Property::MessageId, &report.message_id)],
+            )
+            .await
+            .caused_by(trc::location!())?
+            .results;
+        if document_ids.is_empty() {
+            return Ok(());
+        }
+        let mut filters = Vec::with_capacity(document_ids.len() as usize + 2);
+        filters.push(Filter::Or);
+        for document_id in document_ids {
+            if let Some(thread_id) = self
+                .get_property::<u32>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::ThreadId,
+                )
+                .await?
+            {
+                filters.push(Filter::eq(
+                    Property::EmailId,
+                    Id::from_parts(thread_id, document_id).id(),
+                ));
+            }
+        }
+        filters.push(Filter::End);
+
+        // Update the matching submissions
+        let submission_ids = self
+            .filter(account_id, Collection::EmailSubmission, filters)
+            .await?
+            .results;
+        let mut changes = ChangeLogBuilder::new();
+        for document_id in submission_ids {
+            for _ in 0..3 {
+                let submission = if let Some(submission) = self
+                    .get_property::<HashedValue<Object<Value>>>(
+                        account_id,
+                        Collection::EmailSubmission,
+                        document_id,
+                        Property::Value,
+                    )
+                    .await?
+                {
+                    submission
+                } else {
+                    break;
+                };
+                let update = if let Some(update) = report.apply(&submission.inner, blob_id) {
+                    update
+                } else {
+                    break;
+                };
+
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::EmailSubmission)
+                    .update_document(document_id)
+                    .custom(
+                        ObjectIndexBuilder::new(SCHEMA)
+                            .with_current(submission)
+                            .with_changes(update),
+                    );
+                match self.core.storage.data.write(batch.build()).await {
+                    Ok(_) => {
+                        changes.log_update(Collection::EmailSubmission, document_id);
+                        break;
+                    }
+                    Err(err) if err.is_assertion_failure() => {}
+                    Err(err) => {
+                        return Err(err.caused_by(trc::location!()));
+                    }
+                }
+            }
+        }
+
+        // Notify clients
+        if !changes.is_empty() {
+            let change_id = self.commit_changes(account_id, changes).await?;
+            self.broadcast_state_change(
+                StateChange::new(account_id).with_change(DataType::EmailSubmission, change_id),
+            )
+            .await;
+        }
+
+        Ok(())
+    }
+}
+
+impl DeliveryReport {
+    pub fn parse(message: &Message<'_>) -> Option<Self> {
+        if !message.root_part().is_content_type("multipart", "report") {
+            return None;
+        }
+
+        let mut kind = None;
+        let mut envelope_id = None;
+        let mut message_id = None;
+        for part in &message.parts {
+            if part.is_content_type("message", "delivery-status")
+                || part.is_content_type("message", "global-delivery-status")
+            {
+                let mut fields = parse_report_fields(part.contents()).into_iter();
+                envelope_id = fields.next().and_then(|fields| {
+                    fields
+                        .into_iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case("Original-Envelope-Id"))
+                        .map(|(_, value)| value)
+                });
+                kind = DeliveryReportKind::Dsn(fields.filter_map(DsnRecipient::parse).collect())
+                    .into();
+            } else if part.is_content_type("message", "disposition-notification") {
+                let mut recipients = Vec::new();
+                let mut displayed = false;
+                for (name, value) in parse_report_fields(part.contents()).into_iter().flatten() {
+                    if name.eq_ignore_ascii_case("Original-Recipient")
+                        || name.eq_ignore_ascii_case("Final-Recipient")
+                    {
+                        recipients.push(parse_address_type(&value));
+                    } else if name.eq_ignore_ascii_case("Original-Message-ID") {
+                        message_id = parse_message_id(&value).into();
+                    } else if name.eq_ignore_ascii_case("Disposition") {
+                        displayed = value.rsplit_once(';').map_or(false, |(_, type_)| {
+                            let type_ = type_.trim();
+                            type_
+                                .split_once('/')
+                                .map_or(type_, |(type_, _)| type_)
+                                .trim()
+                                .eq_ignore_ascii_case("displayed")
+                        });
+                    }
+                }
+                if let Some(address) = message
+                    .from()
+                    .and_then(|from| from.first())
+                    .and_then(|from| from.address())
+                {
+                    recipients.push(address.to_lowercase());
+                }
+                kind = DeliveryReportKind::Mdn {
+                    recipients,
+                    displayed,
+                }
+                .into();
+            } else if message_id.is_none() {
+                // Obtain the Message-ID of the original message
+                message_id = match &part.body {
+                    PartType::Message(original) => original.message_id().map(Into::into),
+                    PartType::Text(_) | PartType::Binary(_) | PartType::InlineBinary(_)
+                        if part.is_content_type("text", "rfc822-headers") =>
+                    {
+                        MessageParser::new()
+                            .parse(part.contents())
+                            .and_then(|original| original.message_id().map(Into::into))
+                    }
+                    _ => None,
+                };
+            }
+        }
+
+        Some(DeliveryReport {
+            message_id: message_id.filter(|id| !id.is_empty())?,
+            envelope_id,
+            kind: kind?,
+        })
+    }
+
+    fn apply(&self, submission: &Object<Value>, blob_id: &BlobId) -> Option<Object<Value>> {
+        // DSNs carrying an envelope id must match the one of the submission,
+        // otherwise reports are matched by Message-ID and recipient
+        let submission_envelope_id = submission
+            .get(&Property::Envelope)
+            .as_obj()
+            .and_then(|envelope| envelope.get(&Property::MailFrom).as_obj())
+            .and_then(|mail_from| mail_from.get(&Property::Parameters).as_obj())
+            .and_then(|params| {
+                params
+                    .properties
+                    .iter()
+                    .find_map(|(name, value)| match name {
+                        Property::_T(name) if name.eq_ignore_ascii_case("ENVID") => {
+                            value.as_string()
+                        }
+                        _ => None,
+                    })
+            });
+        if let (DeliveryReportKind::Dsn(_), Some(envelope_id), Some(submission_envelope_id)) =
+            (&self.kind, &self.envelope_id, submission_envelope_id)
+        {
+            if envelope_id.as_str() != submission_envelope_id {
+                return None;
+            }
+        }
+
+        // Update the delivery status of each recipient
+        let mut delivery_status = submission
+            .get(&Property::DeliveryStatus)
+            .as_obj()
+            .cloned()
+            .unwrap_or_default();
+        let mut has_match = false;
+        for (address, status) in delivery_status.properties.iter_mut() {
+            let (address, status) = match (address, status) {
+                (Property::_T(address), Value::Object(status)) => (address, status),
+                _ => continue,
+            };
+            match &self.kind {
+                DeliveryReportKind::Dsn(recipients) => {
+                    if let Some(rcpt) = recipients.iter().find(|rcpt| {
+                        rcpt.addresses
+                            .iter()
+                            .any(|rcpt| rcpt.eq_ignore_ascii_case(address))
+                    }) {
+                        status.set(Property::Delivered, rcpt.delivered);
+                        if let Some(smtp_reply) = &rcpt.smtp_reply {
+                            status.set(Property::SmtpReply, smtp_reply.as_str());
+                        }
+                        has_match = true;
+                    }
+                }
+                DeliveryReportKind::Mdn {
+                    recipients,
+                    displayed,
+                } => {
+                    if recipients
+                        .iter()
+                        .any(|rcpt| rcpt.eq_ignore_ascii_case(address))
+                    {
+                        if *displayed {
+                            status.set(Property::Displayed, "yes");
+                        }
+                        has_match = true;
+                    }
+                }
+            }
+        }
+        if !has_match {
+            return None;
+        }
+
+        // Attach the report
+        let property = match &self.kind {
+            DeliveryReportKind::Dsn(_) => Property::DsnBlobIds,
+            DeliveryReportKind::Mdn { .. } => Property::MdnBlobIds,
+        };
+        let mut blob_ids = submission
+            .get(&property)
+            .as_list()
+            .cloned()
+            .unwrap_or_default();
+        if !blob_ids
+            .iter()
+            .any(|value| value.as_blob_id() == Some(blob_id))
+        {
+            blob_ids.push(Value::BlobId(blob_id.clone()));
+        }
+
+        Some(
+            Object::with_capacity(2)
+                .with_property(Property::DeliveryStatus, delivery_status)
+                .with_property(property, Value::List(blob_ids)),
+        )
+    }
+}
+
+impl DsnRecipient {
+    fn parse(fields: Vec<(String, String)>) -> Option<Self> {
+        let mut addresses = Vec::new();
+        let mut delivered = None;
+        let mut smtp_reply = None;
+        for (name, value) in fields {
+            if name.eq_ignore_ascii_case("Original-Recipient")
+                || name.eq_ignore_ascii_case("Final-Recipient")
+            {
+                addresses.push(parse_address_type(&value));
+            } else if name.eq_ignore_ascii_case("Action") {
+                delivered = match value.to_ascii_lowercase().as_str() {
+                    "failed" => "no",
+                    "delayed" => "queued",
+                    "delivered" | "relayed" | "expanded" => "yes",
+                    _ => "unknown",
+                }
+                .into();
+            } else if name.eq_ignore_ascii_case("Diagnostic-Code") {
+                smtp_reply = value
+                    .split_once(';')
+                    .filter(|(type_, _)| type_.trim().eq_ignore_ascii_case("smtp"))
+                    .map(|(_, reply)| reply.trim().to_string());
+            }
+        }
+
+        if !addresses.is_empty() {
+            Some(DsnRecipient {
+                addresses,
+                delivered: delivered?,
+                smtp_reply,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+fn parse_report_fields(report: &[u8]) -> Vec<Vec<(String, String)>> {
+    let report = String::from_utf8_lossy(report);
+    let mut groups = Vec::new();
+    let mut fields: Vec<(String, String)> = Vec::new();
+
+    for line in report.lines() {
+        if line.trim().is_empty() {
+            if !fields.is_empty() {
+                groups.push(std::mem::take(&mut fields));
+            }
+        } else if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            fields.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    if !fields.is_empty() {
+        groups.push(fields);
+    }
+
+    groups
+}
+
+fn parse_address_type(value: &str) -> String {
+    value
+        .split_once(';')
+        .map_or(value, |(_, address)| address)
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_lowercase()
+}
+
+fn parse_message_id(value: &str) -> String {
+    value
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_string()
+}
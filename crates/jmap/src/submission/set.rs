@@ -7,6 +7,7 @@
 use std::{collections::HashMap, sync::Arc};
 
 use common::{
+    ipc::QueueEvent,
     listener::{stream::NullIo, ServerInstance},
     Server,
 };
@@ -19,6 +20,7 @@ use jmap_proto::{
         Object,
     },
     request::{
+        capability::MAX_DELAYED_SEND,
         method::{MethodFunction, MethodName, MethodObject},
         reference::MaybeReference,
         Call, RequestMethod,
@@ -27,22 +29,34 @@ use jmap_proto::{
     types::{
         collection::Collection,
         date::UTCDate,
+        id::Id,
+        keyword::Keyword,
         property::Property,
+        state::StateChange,
+        type_state::DataType,
         value::{MaybePatchValue, SetValue, Value},
     },
 };
 use mail_parser::{HeaderName, HeaderValue};
 use smtp::{
     core::{Session, SessionData, State},
-    queue::spool::SmtpSpool,
+    queue::{spool::SmtpSpool, QueueId, Status},
 };
 use smtp_proto::{request::parser::Rfc5321Parser, MailFrom, RcptTo};
-use store::write::{assert::HashedValue, log::ChangeLogBuilder, now, BatchBuilder, Bincode};
+use store::{
+    ahash::AHashSet,
+    write::{assert::HashedValue, log::ChangeLogBuilder, now, BatchBuilder, Bincode, F_VALUE},
+};
+use trc::AddContext;
 use utils::map::vec_map::VecMap;
 
 use crate::{
-    blob::download::BlobDownload, changes::write::ChangeLog, email::metadata::MessageMetadata,
-    identity::set::sanitize_email, JmapMethods,
+    blob::download::BlobDownload,
+    changes::write::ChangeLog,
+    email::{ingest::EmailIngest, metadata::MessageMetadata, set::TagManager},
+    identity::set::sanitize_email,
+    mailbox::{get::MailboxGet, UidMailbox},
+    JmapMethods,
 };
 use std::future::Future;
 
@@ -72,8 +86,27 @@ pub trait EmailSubmissionSet: Sync + Send {
         instance: &Arc<ServerInstance>,
         object: Object<SetValue>,
     ) -> impl Future<Output = trc::Result<Result<Object<Value>, SetError>>> + Send;
+
+    fn submit_message(
+        &self,
+        instance: &Arc<ServerInstance>,
+        mail_from: &mut MailFrom<String>,
+        rcpt_to: Vec<RcptTo<String>>,
+        message: Vec<u8>,
+    ) -> impl Future<Output = Result<SubmissionResult, SetError>> + Send;
+
+    fn restore_draft(
+        &self,
+        account_id: u32,
+        email_id: Id,
+        changes: &mut ChangeLogBuilder,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
 }
 
+/// Queue id of the accepted message, if any recipient was accepted,
+/// and the SMTP error reply, if any, for each recipient.
+pub type SubmissionResult = (Option<QueueId>, Vec<(String, Option<String>)>);
+
 impl EmailSubmissionSet for Server {
     async fn email_submission_set(
         &self,
@@ -142,8 +175,13 @@ impl EmailSubmissionSet for Server {
                 continue 'update;
             };
 
-            let mut queue_id = u64::MAX;
+            let queue_id = submission
+                .inner
+                .get(&Property::MessageId)
+                .as_uint()
+                .unwrap_or(u64::MAX);
             let mut undo_status = None;
+            let mut send_at = None;
 
             for (property, value) in object.properties {
                 let value = match response.eval_object_references(value) {
@@ -153,32 +191,51 @@ impl EmailSubmissionSet for Server {
                         continue 'update;
                     }
                 };
-                if let (
-                    Property::UndoStatus,
-                    MaybePatchValue::Value(Value::Text(undo_status_)),
-                    Value::UnsignedInt(queue_id_),
-                ) = (&property, value, submission.inner.get(&Property::MessageId))
-                {
-                    undo_status = undo_status_.into();
-                    queue_id = *queue_id_;
-                } else {
-                    response.not_updated.append(
-                        id,
-                        SetError::invalid_properties()
-                            .with_property(property)
-                            .with_description("Field could not be set."),
-                    );
-                    continue 'update;
+                match (&property, value) {
+                    (Property::UndoStatus, MaybePatchValue::Value(Value::Text(undo_status_)))
+                        if queue_id != u64::MAX =>
+                    {
+                        undo_status = undo_status_.into();
+                    }
+                    (Property::SendAt, MaybePatchValue::Value(Value::Date(send_at_)))
+                        if queue_id != u64::MAX =>
+                    {
+                        send_at = send_at_.into();
+                    }
+                    _ => {
+                        response.not_updated.append(
+                            id,
+                            SetError::invalid_properties()
+                                .with_property(property)
+                                .with_description("Field could not be set."),
+                        );
+                        continue 'update;
+                    }
                 }
             }
 
-            match undo_status {
-                Some(undo_status) if undo_status == "canceled" => {
+            match (undo_status, send_at) {
+                (Some(undo_status), None) if undo_status == "canceled" => {
                     if let Some(queue_message) = self.read_message(queue_id).await {
+                        // Messages not delivered to any recipient are moved back to Drafts
+                        let is_unsent = !queue_message
+                            .recipients
+                            .iter()
+                            .any(|rcpt| matches!(rcpt.status, Status::Completed(_)));
+
                         // Delete message from queue
                         let message_due = queue_message.next_event().unwrap_or_default();
                         queue_message.remove(self, message_due).await;
 
+                        if is_unsent {
+                            if let Some(email_id) =
+                                submission.inner.get(&Property::EmailId).as_id().copied()
+                            {
+                                self.restore_draft(account_id, email_id, &mut changes)
+                                    .await?;
+                            }
+                        }
+
                         // Update record
                         let mut batch = BatchBuilder::new();
                         batch
@@ -205,7 +262,88 @@ impl EmailSubmissionSet for Server {
                         );
                     }
                 }
-                Some(_) => {
+                (None, Some(send_at)) => {
+                    // Validate the new delivery time
+                    let send_at_ts = send_at.timestamp() as u64;
+                    let current_time = now();
+                    if send_at_ts <= current_time || send_at_ts > current_time + MAX_DELAYED_SEND {
+                        response.not_updated.append(
+                            id,
+                            SetError::invalid_properties()
+                                .with_property(Property::SendAt)
+                                .with_description(
+                                    "sendAt must be in the future and within the maximum delayed send period.",
+                                ),
+                        );
+                        continue 'update;
+                    }
+
+                    // Only messages that have not been attempted yet can be rescheduled
+                    if let Some(mut queue_message) =
+                        self.read_message(queue_id).await.filter(|message| {
+                            message.domains.iter().all(|domain| {
+                                matches!(domain.status, Status::Scheduled)
+                                    && domain.retry.due > current_time
+                            })
+                        })
+                    {
+                        // Reschedule queue event
+                        let prev_event = queue_message.next_event().unwrap_or_default();
+                        for domain in &mut queue_message.domains {
+                            let expires_in = domain.expires.saturating_sub(domain.retry.due);
+                            let notify_in = domain.notify.due.saturating_sub(domain.retry.due);
+                            domain.retry.due = send_at_ts;
+                            domain.notify.due = send_at_ts + notify_in;
+                            domain.expires = send_at_ts + expires_in;
+                        }
+                        let next_event = queue_message.next_event().unwrap_or_default();
+                        if !queue_message
+                            .save_changes(self, prev_event.into(), next_event.into())
+                            .await
+                        {
+                            return Err(trc::StoreEvent::UnexpectedError
+                                .into_err()
+                                .details("Failed to reschedule message.")
+                                .caused_by(trc::location!()));
+                        }
+                        let _ = self.inner.ipc.queue_tx.send(QueueEvent::Reload).await;
+
+                        // Update record
+                        let mut batch = BatchBuilder::new();
+                        batch
+                            .with_account_id(account_id)
+                            .with_collection(Collection::EmailSubmission)
+                            .update_document(document_id)
+                            .custom(
+                                ObjectIndexBuilder::new(SCHEMA)
+                                    .with_current(submission)
+                                    .with_changes(
+                                        Object::with_capacity(1)
+                                            .with_property(Property::SendAt, send_at),
+                                    ),
+                            );
+                        self.write_batch(batch).await?;
+                        changes.log_update(Collection::EmailSubmission, document_id);
+                        response.updated.append(id, None);
+                    } else {
+                        response.not_updated.append(
+                            id,
+                            SetError::new(SetErrorType::CannotUnsend)
+                                .with_description("The requested message has already been sent."),
+                        );
+                    }
+                }
+                (Some(_), Some(_)) => {
+                    response.not_updated.append(
+                        id,
+                        SetError::invalid_properties()
+                            .with_properties([Property::UndoStatus, Property::SendAt])
+                            .with_description(
+                                "Email submissions cannot be cancelled and rescheduled at the same time.",
+                            ),
+                    );
+                }
+                (Some(_), None) => {
                     response.not_updated.append(
                         id,
                         SetError::invalid_properties()
@@ -213,7 +351,7 @@ impl EmailSubmissionSet for Server {
                             .with_description("Email submissions can only be cancelled."),
                     );
                 }
-                None => {
+                (None, None) => {
                     response.not_updated.append(
                         id,
                         SetError::invalid_properties()
@@ -252,7 +390,17 @@ impl EmailSubmissionSet for Server {
 
         // Write changes
         if !changes.is_empty() {
-            response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+            let has_email_changes = changes.changes.contains_key(&u8::from(Collection::Email));
+            let change_id = self.commit_changes(account_id, changes).await?;
+            let mut state_change =
+                StateChange::new(account_id).with_change(DataType::EmailSubmission, change_id);
+            if has_email_changes {
+                state_change = state_change
+                    .with_change(DataType::Email, change_id)
+                    .with_change(DataType::Mailbox, change_id);
+            }
+            response.state_change = state_change.into();
+            response.new_state = Some(change_id.into());
         }
 
         // On success
@@ -462,7 +610,7 @@ impl EmailSubmissionSet for Server {
         };
 
         // Make sure the envelope address matches the identity email address
-        let mail_from = if let Some(mail_from) = mail_from {
+        let mut mail_from = if let Some(mail_from) = mail_from {
             if !mail_from.address.eq_ignore_ascii_case(&identity_mail_from) {
                 return Ok(Err(SetError::new(SetErrorType::ForbiddenFrom)
                     .with_description(
@@ -549,16 +697,15 @@ impl EmailSubmissionSet for Server {
         }
 
         // Update sendAt
-        submission.append(
-            Property::SendAt,
-            UTCDate::from_timestamp(if mail_from.hold_until > 0 {
-                mail_from.hold_until
-            } else if mail_from.hold_for > 0 {
-                mail_from.hold_for + now()
-            } else {
-                now()
-            } as i64),
-        );
+        let current_time = now();
+        let send_at = if mail_from.hold_until > 0 {
+            mail_from.hold_until
+        } else if mail_from.hold_for > 0 {
+            mail_from.hold_for + current_time
+        } else {
+            current_time
+        };
+        submission.append(Property::SendAt, UTCDate::from_timestamp(send_at as i64));
 
         // Obtain raw message
         let message =
@@ -578,52 +725,54 @@ impl EmailSubmissionSet for Server {
                     .with_description("Blob for email not found.")));
             };
 
-        // Begin local SMTP session
-        let mut session =
-            Session::<NullIo>::local(self.clone(), instance.clone(), SessionData::default());
-
-        // MAIL FROM
-        let _ = session.handle_mail_from(mail_from).await;
-        if let Some(error) = session.has_failed() {
-            return Ok(Err(SetError::new(SetErrorType::ForbiddenMailFrom)
-                .with_description(format!(
-                    "Server rejected MAIL-FROM: {}",
-                    error.trim()
-                ))));
-        }
+        // Submit message
+        let has_env_id = mail_from.env_id.is_some();
+        let (queue_id, responses) = match self
+            .submit_message(instance, &mut mail_from, rcpt_to, message)
+            .await
+        {
+            Ok(result) => result,
+            Err(err) => return Ok(Err(err)),
+        };
 
-        // RCPT TO
-        let mut responses = Vec::new();
-        let mut has_success = false;
-        for rcpt in rcpt_to {
-            let addr = rcpt.address.clone();
-            let _ = session.handle_rcpt_to(rcpt).await;
-            let response = session.has_failed();
-            if response.is_none() {
-                has_success = true;
+        // Store the envelope id assigned by the server, used to match DSNs
+        if let Some(env_id) = mail_from.env_id.filter(|_| !has_env_id) {
+            if let Some(params) = submission
+                .properties
+                .get_mut(&Property::Envelope)
+                .and_then(|envelope| envelope.as_obj_mut())
+                .and_then(|envelope| envelope.properties.get_mut(&Property::MailFrom))
+                .and_then(|mail_from| mail_from.as_obj_mut())
+                .map(|mail_from| {
+                    mail_from
+                        .properties
+                        .get_mut_or_insert_with(Property::Parameters, || Value::Null)
+                })
+            {
+                if !matches!(params, Value::Object(_)) {
+                    *params = Value::Object(Object::with_capacity(1));
+                }
+                params
+                    .as_obj_mut()
+                    .unwrap()
+                    .set(Property::_T("ENVID".to_string()), env_id);
             }
-            responses.push((addr, response));
         }
-
-        // DATA
-        if has_success {
-            session.data.message = message;
-            let response = session.queue_message().await;
-            if let State::Accepted(queue_id) = session.state {
-                submission.append(Property::MessageId, queue_id);
-            } else {
-                return Ok(Err(SetError::new(SetErrorType::ForbiddenToSend)
-                    .with_description(format!(
-                        "Server rejected DATA: {}",
-                        std::str::from_utf8(&response).unwrap().trim()
-                    ))));
-            }
+        let has_success = queue_id.is_some();
+        if let Some(queue_id) = queue_id {
+            submission.append(Property::MessageId, queue_id);
         }
 
-        // Set responses
+        // Set responses, scheduled messages remain pending until they are released
         submission.append(
             Property::UndoStatus,
-            if has_success { "final" } else { "failed" },
+            if !has_success {
+                "failed"
+            } else if send_at > current_time {
+                "pending"
+            } else {
+                "final"
+            },
         );
         submission.append(
             Property::DeliveryStatus,
@@ -653,6 +802,164 @@ impl EmailSubmissionSet for Server {
 
         Ok(Ok(submission))
     }
+
+    async fn submit_message(
+        &self,
+        instance: &Arc<ServerInstance>,
+        mail_from: &mut MailFrom<String>,
+        rcpt_to: Vec<RcptTo<String>>,
+        message: Vec<u8>,
+    ) -> Result<SubmissionResult, SetError> {
+        // Begin local SMTP session
+        let mut session =
+            Session::<NullIo>::local(self.clone(), instance.clone(), SessionData::default());
+
+        // Assign an envelope id, when DSNs are enabled, so reports can be matched
+        if mail_from.env_id.is_none()
+            && !mail_from.address.is_empty()
+            && self
+                .eval_if::<bool, _>(
+                    &self.core.smtp.session.extensions.dsn,
+                    &session,
+                    session.data.session_id,
+                )
+                .await
+                .unwrap_or(false)
+        {
+            mail_from.env_id = self
+                .inner
+                .data
+                .jmap_id_gen
+                .generate()
+                .map(|id| format!("{id:x}"));
+        }
+
+        // MAIL FROM
+        let _ = session.handle_mail_from(mail_from.clone()).await;
+        if let Some(error) = session.has_failed() {
+            return Err(SetError::new(SetErrorType::ForbiddenMailFrom)
+                .with_description(format!("Server rejected MAIL-FROM: {}", error.trim())));
+        }
+
+        // RCPT TO
+        let mut responses = Vec::new();
+        let mut has_success = false;
+        for rcpt in rcpt_to {
+            let addr = rcpt.address.clone();
+            let _ = session.handle_rcpt_to(rcpt).await;
+            let response = session.has_failed();
+            if response.is_none() {
+                has_success = true;
+            }
+            responses.push((addr, response));
+        }
+
+        // DATA
+        if has_success {
+            session.data.message = message;
+            let response = session.queue_message().await;
+            if let State::Accepted(queue_id) = session.state {
+                Ok((Some(queue_id), responses))
+            } else {
+                Err(
+                    SetError::new(SetErrorType::ForbiddenToSend).with_description(format!(
+                        "Server rejected DATA: {}",
+                        std::str::from_utf8(&response).unwrap().trim()
+                    )),
+                )
+            }
+        } else {
+            Ok((None, responses))
+        }
+    }
+
+    async fn restore_draft(
+        &self,
+        account_id: u32,
+        email_id: Id,
+        changes: &mut ChangeLogBuilder,
+    ) -> trc::Result<()> {
+        let document_id = email_id.document_id();
+        let (drafts_id, mut mailboxes, mut keywords) = match (
+            self.mailbox_get_by_role(account_id, "drafts").await?,
+            self.get_property::<HashedValue<Vec<UidMailbox>>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::MailboxIds,
+            )
+            .await?,
+            self.get_property::<HashedValue<Vec<Keyword>>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::Keywords,
+            )
+            .await?,
+        ) {
+            (Some(drafts_id), Some(mailboxes), Some(keywords)) => (
+                drafts_id,
+                TagManager::new(mailboxes),
+                TagManager::new(keywords),
+            ),
+            _ => return Ok(()),
+        };
+
+        // Move the message to the Drafts folder and flag it as a draft
+        let mut changed_mailboxes = mailboxes
+            .current()
+            .iter()
+            .map(|mailbox| mailbox.mailbox_id)
+            .collect::<AHashSet<_>>();
+        changed_mailboxes.insert(drafts_id);
+        let drafts_mailbox = mailboxes
+            .current()
+            .iter()
+            .find(|mailbox| mailbox.mailbox_id == drafts_id)
+            .copied()
+            .unwrap_or_else(|| UidMailbox::new_unassigned(drafts_id));
+        mailboxes.set(vec![drafts_mailbox]);
+        keywords.update(Keyword::Draft, true);
+        if !mailboxes.has_changes() && !keywords.has_changes() {
+            return Ok(());
+        }
+
+        // Obtain IMAP UIDs for added mailboxes
+        for uid_mailbox in mailboxes.inner_tags_mut() {
+            if uid_mailbox.uid == 0 {
+                uid_mailbox.uid = self
+                    .assign_imap_uid(account_id, uid_mailbox.mailbox_id)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+        }
+
+        if changes.change_id == u64::MAX {
+            changes.change_id = self.assign_change_id(account_id).await?;
+        }
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Email)
+            .update_document(document_id);
+        mailboxes.update_batch(&mut batch, Property::MailboxIds);
+        keywords.update_batch(&mut batch, Property::Keywords);
+        batch.value(Property::Cid, changes.change_id, F_VALUE);
+        match self.core.storage.data.write(batch.build()).await {
+            Ok(_) => {
+                changes.log_update(Collection::Email, email_id);
+                for mailbox_id in changed_mailboxes {
+                    changes.log_child_update(Collection::Mailbox, mailbox_id);
+                }
+                Ok(())
+            }
+            Err(err) if err.is_assertion_failure() => {
+                // The message was modified by another process, leave it untouched
+                Ok(())
+            }
+            Err(err) => Err(err.caused_by(trc::location!())),
+        }
+    }
 }
 
 fn parse_envelope_address(envelope: &Value) -> Result<(String, Option<String>), SetError> {
@@ -0,0 +1,45 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::types::{
+    collection::Collection,
+    keyword::{Keyword, MUTED_KEYWORD},
+    property::Property,
+};
+use std::future::Future;
+
+use crate::JmapMethods;
+
+pub trait ThreadMute: Sync + Send {
+    fn is_thread_muted(
+        &self,
+        account_id: u32,
+        thread_id: u32,
+    ) -> impl Future<Output = trc::Result<bool>> + Send;
+}
+
+impl ThreadMute for Server {
+    async fn is_thread_muted(&self, account_id: u32, thread_id: u32) -> trc::Result<bool> {
+        // A thread is muted when any of its messages has the $muted keyword
+        if let (Some(mut muted_ids), Some(thread_email_ids)) = (
+            self.get_tag(
+                account_id,
+                Collection::Email,
+                Property::Keywords,
+                Keyword::Other(MUTED_KEYWORD.to_string()),
+            )
+            .await?,
+            self.get_tag(account_id, Collection::Email, Property::ThreadId, thread_id)
+                .await?,
+        ) {
+            muted_ids &= thread_email_ids;
+            Ok(!muted_ids.is_empty())
+        } else {
+            Ok(false)
+        }
+    }
+}
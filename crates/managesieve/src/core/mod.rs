@@ -11,7 +11,7 @@ use std::{borrow::Cow, net::IpAddr, sync::Arc};
 
 use common::{
     auth::AccessToken,
-    listener::{limiter::InFlight, ServerInstance},
+    listener::{limiter::InFlight, tls::ClientCertificate, ServerInstance},
     Inner, Server,
 };
 use imap_proto::receiver::{CommandParser, Receiver};
@@ -24,6 +24,7 @@ pub struct Session<T: AsyncRead + AsyncWrite> {
     pub state: State,
     pub remote_addr: IpAddr,
     pub stream: T,
+    pub client_cert: Option<ClientCertificate>,
     pub session_id: u64,
     pub in_flight: InFlight,
 }
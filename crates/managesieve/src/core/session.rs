@@ -31,6 +31,7 @@ impl SessionManager for ManageSieveSessionManager {
                 instance: session.instance,
                 state: State::NotAuthenticated { auth_failures: 0 },
                 session_id: session.session_id,
+                client_cert: session.stream.client_certificate(),
                 stream: session.stream,
                 in_flight: session.in_flight,
                 remote_addr: session.remote_ip,
@@ -134,11 +135,14 @@ impl<T: SessionStream> Session<T> {
     }
 
     pub async fn into_tls(self) -> Result<Session<TlsStream<T>>, ()> {
+        let stream = self
+            .instance
+            .tls_accept(self.stream, self.session_id)
+            .await?;
+
         Ok(Session {
-            stream: self
-                .instance
-                .tls_accept(self.stream, self.session_id)
-                .await?,
+            client_cert: stream.client_certificate(),
+            stream,
             state: self.state,
             instance: self.instance,
             in_flight: self.in_flight,
@@ -38,9 +38,9 @@ impl<T: SessionStream> Session<T> {
             .filter_map(|token| token.unwrap_string().ok())
             .collect();
 
-        let credentials = match mechanism {
+        let result = match mechanism {
             Mechanism::Plain | Mechanism::OAuthBearer => {
-                if !params.is_empty() {
+                let credentials = if !params.is_empty() {
                     base64_decode(params.pop().unwrap().as_bytes())
                         .and_then(|challenge| {
                             if mechanism == Mechanism::Plain {
@@ -62,6 +62,66 @@ impl<T: SessionStream> Session<T> {
                     };
                     self.receiver.state = receiver::State::Argument { last_ch: b' ' };
                     return Ok(b"{0}\r\n".to_vec());
+                };
+
+                // Throttle authentication requests
+                self.server.is_auth_allowed_soft(&self.remote_addr).await?;
+
+                // Authenticate
+                self.server
+                    .authenticate(&AuthRequest::from_credentials(
+                        credentials,
+                        self.session_id,
+                        self.remote_addr,
+                    ))
+                    .await
+            }
+            Mechanism::External => {
+                let authz_id = if let Some(param) = params.pop() {
+                    // An empty response or "=" requests the identity from the certificate
+                    if !param.is_empty() && param != "=" {
+                        base64_decode(param.as_bytes())
+                            .and_then(|authz_id| String::from_utf8(authz_id).ok())
+                            .ok_or_else(|| {
+                                trc::AuthEvent::Error
+                                    .into_err()
+                                    .details("Failed to decode authorization identity.")
+                            })?
+                            .into()
+                    } else {
+                        None
+                    }
+                } else {
+                    self.receiver.request = receiver::Request {
+                        tag: String::new(),
+                        command: Command::Authenticate,
+                        tokens: vec![
+                            receiver::Token::Argument(mechanism.into_bytes()),
+                            receiver::Token::Argument(b"=".to_vec()),
+                        ],
+                    };
+                    self.receiver.state = receiver::State::Argument { last_ch: b' ' };
+                    return Ok(b"{0}\r\n".to_vec());
+                };
+
+                // Throttle authentication requests
+                self.server.is_auth_allowed_soft(&self.remote_addr).await?;
+
+                // Authenticate using the verified client certificate
+                if let Some(cert) = &self.client_cert {
+                    self.server
+                        .authenticate_external(
+                            cert,
+                            authz_id.as_deref(),
+                            None,
+                            self.session_id,
+                            self.remote_addr,
+                        )
+                        .await
+                } else {
+                    Err(trc::AuthEvent::Failed
+                        .into_err()
+                        .details("No client certificate was presented."))
                 }
             }
             _ => {
@@ -71,18 +131,7 @@ impl<T: SessionStream> Session<T> {
             }
         };
 
-        // Throttle authentication requests
-        self.server.is_auth_allowed_soft(&self.remote_addr).await?;
-
-        // Authenticate
-        let access_token = self
-            .server
-            .authenticate(&AuthRequest::from_credentials(
-                credentials,
-                self.session_id,
-                self.remote_addr,
-            ))
-            .await
+        let access_token = result
             .map_err(|err| {
                 if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
                     match &self.state {
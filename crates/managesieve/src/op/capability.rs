@@ -21,11 +21,16 @@ impl<T: SessionStream> Session<T> {
         if !self.stream.is_tls() {
             response.extend_from_slice(b"\"STARTTLS\"\r\n");
         }
+        response.extend_from_slice(b"\"SASL\" \"");
         if self.stream.is_tls() || self.server.core.imap.allow_plain_auth {
-            response.extend_from_slice(b"\"SASL\" \"PLAIN OAUTHBEARER\"\r\n");
+            response.extend_from_slice(b"PLAIN OAUTHBEARER");
         } else {
-            response.extend_from_slice(b"\"SASL\" \"OAUTHBEARER\"\r\n");
+            response.extend_from_slice(b"OAUTHBEARER");
         };
+        if self.server.core.auth.external.enable && self.client_cert.is_some() {
+            response.extend_from_slice(b" EXTERNAL");
+        }
+        response.extend_from_slice(b"\"\r\n");
         if let Some(sieve) =
             self.server
                 .core
@@ -8,7 +8,7 @@ use std::{net::IpAddr, sync::Arc};
 
 use common::{
     auth::AccessToken,
-    listener::{limiter::InFlight, ServerInstance, SessionStream},
+    listener::{limiter::InFlight, tls::ClientCertificate, ServerInstance, SessionStream},
     Inner, Server,
 };
 use mailbox::Mailbox;
@@ -39,6 +39,7 @@ pub struct Session<T: SessionStream> {
     pub receiver: Parser,
     pub state: State,
     pub stream: T,
+    pub client_cert: Option<ClientCertificate>,
     pub in_flight: InFlight,
     pub remote_addr: IpAddr,
     pub session_id: u64,
@@ -7,7 +7,7 @@
 use common::{
     auth::{
         sasl::{sasl_decode_challenge_oauth, sasl_decode_challenge_plain},
-        AuthRequest,
+        AccessToken, AuthRequest,
     },
     listener::{limiter::ConcurrencyLimiter, SessionStream},
     ConcurrencyLimiters,
@@ -61,6 +61,36 @@ impl<T: SessionStream> Session<T> {
                     self.write_bytes("+\r\n").await
                 }
             }
+            Mechanism::External => {
+                if let Some(param) = params.pop() {
+                    // An empty response or "=" requests the identity from the certificate
+                    let authz_id = if !param.is_empty() && param != "=" {
+                        base64_decode(param.as_bytes())
+                            .and_then(|authz_id| String::from_utf8(authz_id).ok())
+                            .ok_or_else(|| {
+                                trc::AuthEvent::Error
+                                    .into_err()
+                                    .details("Invalid SASL challenge")
+                            })?
+                            .into()
+                    } else {
+                        None
+                    };
+
+                    self.handle_external_auth(authz_id).await
+                } else {
+                    self.receiver.state = request::State::Argument {
+                        request: Command::Auth {
+                            mechanism: mechanism.as_str().as_bytes().to_vec(),
+                            params: vec![b"=".to_vec()],
+                        },
+                        num: 2,
+                        last_is_space: true,
+                    };
+
+                    self.write_bytes("+\r\n").await
+                }
+            }
             _ => Err(trc::AuthEvent::Error
                 .into_err()
                 .details("Authentication mechanism not supported.")),
@@ -72,14 +102,47 @@ impl<T: SessionStream> Session<T> {
         self.server.is_auth_allowed_soft(&self.remote_addr).await?;
 
         // Authenticate
-        let access_token = self
+        let result = self
             .server
             .authenticate(&AuthRequest::from_credentials(
                 credentials,
                 self.session_id,
                 self.remote_addr,
             ))
-            .await
+            .await;
+
+        self.handle_auth_result(result).await
+    }
+
+    pub async fn handle_external_auth(&mut self, authz_id: Option<String>) -> trc::Result<()> {
+        // Throttle authentication requests
+        self.server.is_auth_allowed_soft(&self.remote_addr).await?;
+
+        // Authenticate using the verified client certificate
+        let result = if let Some(cert) = &self.client_cert {
+            self.server
+                .authenticate_external(
+                    cert,
+                    authz_id.as_deref(),
+                    None,
+                    self.session_id,
+                    self.remote_addr,
+                )
+                .await
+        } else {
+            Err(trc::AuthEvent::Failed
+                .into_err()
+                .details("No client certificate was presented."))
+        };
+
+        self.handle_auth_result(result).await
+    }
+
+    async fn handle_auth_result(
+        &mut self,
+        result: trc::Result<Arc<AccessToken>>,
+    ) -> trc::Result<()> {
+        let access_token = result
             .map_err(|err| {
                 if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) {
                     match &self.state {
@@ -18,11 +18,14 @@ pub mod list;
 
 impl<T: SessionStream> Session<T> {
     pub async fn handle_capa(&mut self) -> trc::Result<()> {
-        let mechanisms = if self.stream.is_tls() || self.server.core.imap.allow_plain_auth {
+        let mut mechanisms = if self.stream.is_tls() || self.server.core.imap.allow_plain_auth {
             vec![Mechanism::Plain, Mechanism::OAuthBearer]
         } else {
             vec![Mechanism::OAuthBearer]
         };
+        if self.server.core.auth.external.enable && self.client_cert.is_some() {
+            mechanisms.push(Mechanism::External);
+        }
 
         trc::event!(
             Pop3(trc::Pop3Event::Capabilities),
@@ -37,6 +37,7 @@ impl SessionManager for Pop3SessionManager {
                     auth_failures: 0,
                     username: None,
                 },
+                client_cert: session.stream.client_certificate(),
                 stream: session.stream,
                 in_flight: session.in_flight,
                 remote_addr: session.remote_ip,
@@ -137,11 +138,14 @@ impl<T: SessionStream> Session<T> {
     }
 
     pub async fn into_tls(self) -> Result<Session<TlsStream<T>>, ()> {
+        let stream = self
+            .instance
+            .tls_accept(self.stream, self.session_id)
+            .await?;
+
         Ok(Session {
-            stream: self
-                .instance
-                .tls_accept(self.stream, self.session_id)
-                .await?,
+            client_cert: stream.client_certificate(),
+            stream,
             server: self.server,
             instance: self.instance,
             receiver: self.receiver,
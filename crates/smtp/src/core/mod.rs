@@ -16,6 +16,7 @@ use common::{
     config::smtp::auth::VerifyStrategy,
     listener::{
         limiter::{ConcurrencyLimiter, InFlight},
+        tls::ClientCertificate,
         ServerInstance,
     },
     Inner, Server,
@@ -64,6 +65,7 @@ pub struct Session<T: AsyncWrite + AsyncRead> {
     pub instance: Arc<ServerInstance>,
     pub server: Server,
     pub stream: T,
+    pub client_cert: Option<ClientCertificate>,
     pub data: SessionData,
     pub params: SessionParameters,
     pub in_flight: Vec<InFlight>,
@@ -237,6 +239,7 @@ impl Session<common::listener::stream::NullIo> {
             instance,
             server,
             stream: common::listener::stream::NullIo::default(),
+            client_cert: None,
             data,
             params: SessionParameters {
                 timeout: Default::default(),
@@ -9,14 +9,17 @@ use common::{
         sasl::{
             sasl_decode_challenge_oauth, sasl_decode_challenge_plain, sasl_decode_challenge_xoauth,
         },
-        AuthRequest,
+        AccessToken, AuthRequest,
     },
     listener::SessionStream,
 };
 use directory::Permission;
 use mail_parser::decoders::base64::base64_decode;
 use mail_send::Credentials;
-use smtp_proto::{IntoString, AUTH_LOGIN, AUTH_OAUTHBEARER, AUTH_PLAIN, AUTH_XOAUTH2};
+use smtp_proto::{
+    IntoString, AUTH_EXTERNAL, AUTH_LOGIN, AUTH_OAUTHBEARER, AUTH_PLAIN, AUTH_XOAUTH2,
+};
+use std::sync::Arc;
 use trc::{AuthEvent, SmtpEvent};
 
 use crate::core::Session;
@@ -24,6 +27,7 @@ use crate::core::Session;
 pub struct SaslToken {
     mechanism: u64,
     credentials: Credentials<String>,
+    is_continuation: bool,
 }
 
 impl SaslToken {
@@ -35,6 +39,7 @@ impl SaslToken {
                     username: String::new(),
                     secret: String::new(),
                 },
+                is_continuation: false,
             }
             .into(),
             AUTH_OAUTHBEARER => SaslToken {
@@ -42,6 +47,7 @@ impl SaslToken {
                 credentials: Credentials::OAuthBearer {
                     token: String::new(),
                 },
+                is_continuation: false,
             }
             .into(),
             AUTH_XOAUTH2 => SaslToken {
@@ -50,6 +56,16 @@ impl SaslToken {
                     username: String::new(),
                     secret: String::new(),
                 },
+                is_continuation: false,
+            }
+            .into(),
+            AUTH_EXTERNAL => SaslToken {
+                mechanism,
+                credentials: Credentials::Plain {
+                    username: String::new(),
+                    secret: String::new(),
+                },
+                is_continuation: false,
             }
             .into(),
             _ => None,
@@ -63,7 +79,23 @@ impl<T: SessionStream> Session<T> {
         token: &mut SaslToken,
         response: &[u8],
     ) -> Result<bool, ()> {
-        if response.is_empty() {
+        if token.mechanism == AUTH_EXTERNAL {
+            // An empty response or "=" means that the authorization identity
+            // is derived from the client certificate (RFC 4422, RFC 4954)
+            return if response.is_empty() && !token.is_continuation {
+                token.is_continuation = true;
+                self.write(b"334 \r\n").await?;
+                Ok(true)
+            } else if response.is_empty() || response == b"=" {
+                self.authenticate_external(None).await
+            } else if let Some(authz_id) =
+                base64_decode(response).and_then(|authz_id| String::from_utf8(authz_id).ok())
+            {
+                self.authenticate_external(Some(authz_id)).await
+            } else {
+                self.auth_error(b"500 5.5.6 Invalid challenge.\r\n").await
+            };
+        } else if response.is_empty() {
             match (token.mechanism, &token.credentials) {
                 (AUTH_PLAIN | AUTH_XOAUTH2 | AUTH_OAUTHBEARER, _) => {
                     self.write(b"334 Go ahead.\r\n").await?;
@@ -126,65 +158,94 @@ impl<T: SessionStream> Session<T> {
                     )
                     .with_directory(directory),
                 )
-                .await
-                .and_then(|access_token| {
-                    access_token
-                        .assert_has_permission(Permission::EmailSend)
-                        .map(|_| access_token)
-                });
-
-            match result {
-                Ok(access_token) => {
-                    self.data.authenticated_as = access_token.into();
-                    self.eval_post_auth_params().await;
-                    self.write(b"235 2.7.0 Authentication succeeded.\r\n")
-                        .await?;
-                    return Ok(false);
-                }
-                Err(err) => {
-                    let reason = *err.as_ref();
-
-                    trc::error!(err.span_id(self.data.session_id));
-
-                    match reason {
-                        trc::EventType::Auth(trc::AuthEvent::Failed) => {
-                            return self
-                                .auth_error(b"535 5.7.8 Authentication credentials invalid.\r\n")
-                                .await;
-                        }
-                        trc::EventType::Auth(trc::AuthEvent::TokenExpired) => {
-                            return self.auth_error(b"535 5.7.8 OAuth token expired.\r\n").await;
-                        }
-                        trc::EventType::Auth(trc::AuthEvent::MissingTotp) => {
-                            return self
+                .await;
+
+            self.handle_auth_result(result).await
+        } else {
+            self.missing_auth_directory().await
+        }
+    }
+
+    pub async fn authenticate_external(&mut self, authz_id: Option<String>) -> Result<bool, ()> {
+        if let Some(directory) = &self.params.auth_directory {
+            let result = if let Some(cert) = &self.client_cert {
+                self.server
+                    .authenticate_external(
+                        cert,
+                        authz_id.as_deref(),
+                        Some(directory),
+                        self.data.session_id,
+                        self.data.remote_ip,
+                    )
+                    .await
+            } else {
+                Err(AuthEvent::Failed
+                    .into_err()
+                    .details("No client certificate was presented."))
+            };
+
+            self.handle_auth_result(result).await
+        } else {
+            self.missing_auth_directory().await
+        }
+    }
+
+    async fn handle_auth_result(
+        &mut self,
+        result: trc::Result<Arc<AccessToken>>,
+    ) -> Result<bool, ()> {
+        let result = result.and_then(|access_token| {
+            access_token
+                .assert_has_permission(Permission::EmailSend)
+                .map(|_| access_token)
+        });
+
+        match result {
+            Ok(access_token) => {
+                self.data.authenticated_as = access_token.into();
+                self.eval_post_auth_params().await;
+                self.write(b"235 2.7.0 Authentication succeeded.\r\n")
+                    .await?;
+                return Ok(false);
+            }
+            Err(err) => {
+                let reason = *err.as_ref();
+
+                trc::error!(err.span_id(self.data.session_id));
+
+                match reason {
+                    trc::EventType::Auth(trc::AuthEvent::Failed) => {
+                        return self
+                            .auth_error(b"535 5.7.8 Authentication credentials invalid.\r\n")
+                            .await;
+                    }
+                    trc::EventType::Auth(trc::AuthEvent::TokenExpired) => {
+                        return self.auth_error(b"535 5.7.8 OAuth token expired.\r\n").await;
+                    }
+                    trc::EventType::Auth(trc::AuthEvent::MissingTotp) => {
+                        return self
                             .auth_error(
                                 b"334 5.7.8 Missing TOTP token, try with 'secret$totp_code'.\r\n",
                             )
                             .await;
-                        }
-                        trc::EventType::Security(trc::SecurityEvent::Unauthorized) => {
-                            self.write(
-                                concat!(
-                                    "550 5.7.1 Your account is not authorized ",
-                                    "to use this service.\r\n"
-                                )
-                                .as_bytes(),
+                    }
+                    trc::EventType::Security(trc::SecurityEvent::Unauthorized) => {
+                        self.write(
+                            concat!(
+                                "550 5.7.1 Your account is not authorized ",
+                                "to use this service.\r\n"
                             )
-                            .await?;
-                            return Ok(false);
-                        }
-                        trc::EventType::Security(_) => {
-                            return Err(());
-                        }
-                        _ => (),
+                            .as_bytes(),
+                        )
+                        .await?;
+                        return Ok(false);
                     }
+                    trc::EventType::Security(_) => {
+                        return Err(());
+                    }
+                    _ => (),
                 }
             }
-        } else {
-            trc::event!(
-                Smtp(SmtpEvent::MissingAuthDirectory),
-                SpanId = self.data.session_id,
-            );
         }
         self.write(b"454 4.7.0 Temporary authentication failure\r\n")
             .await?;
@@ -192,6 +253,29 @@ impl<T: SessionStream> Session<T> {
         Ok(false)
     }
 
+    async fn missing_auth_directory(&mut self) -> Result<bool, ()> {
+        trc::event!(
+            Smtp(SmtpEvent::MissingAuthDirectory),
+            SpanId = self.data.session_id,
+        );
+
+        self.write(b"454 4.7.0 Temporary authentication failure\r\n")
+            .await?;
+
+        Ok(false)
+    }
+
+    pub fn filter_auth_mechanisms(&self, mechanisms: u64) -> u64 {
+        // EXTERNAL is only offered when a verified client certificate is available
+        if (mechanisms & AUTH_EXTERNAL) != 0
+            && (!self.server.core.auth.external.enable || self.client_cert.is_none())
+        {
+            mechanisms & !AUTH_EXTERNAL
+        } else {
+            mechanisms
+        }
+    }
+
     pub async fn auth_error(&mut self, response: &[u8]) -> Result<bool, ()> {
         tokio::time::sleep(self.params.auth_errors_wait).await;
         self.data.auth_errors += 1;
@@ -622,6 +622,7 @@ impl<T: SessionStream> Session<T> {
                     ScriptModification::SetEnvelope { name, value } => {
                         self.data.apply_envelope_modification(name, value);
                     }
+                    ScriptModification::Snooze { .. } => {}
                 }
             }
         }
@@ -83,16 +83,17 @@ impl<T: SessionStream> Session<T> {
                                 mechanism,
                                 initial_response,
                             } => {
-                                let auth: u64 = self
-                                    .server
-                                    .eval_if::<Mechanism, _>(
-                                        &self.server.core.smtp.session.auth.mechanisms,
-                                        self,
-                                        self.data.session_id,
-                                    )
-                                    .await
-                                    .unwrap_or_default()
-                                    .into();
+                                let auth = self.filter_auth_mechanisms(
+                                    self.server
+                                        .eval_if::<Mechanism, _>(
+                                            &self.server.core.smtp.session.auth.mechanisms,
+                                            self,
+                                            self.data.session_id,
+                                        )
+                                        .await
+                                        .unwrap_or_default()
+                                        .into(),
+                                );
                                 if auth == 0 || self.params.auth_directory.is_none() {
                                     trc::event!(
                                         Smtp(SmtpEvent::AuthNotAllowed),
@@ -30,6 +30,7 @@ impl SessionManager for SmtpSessionManager {
             server: self.inner.build_server(),
             instance: session.instance,
             state: State::default(),
+            client_cert: session.stream.client_certificate(),
             stream: session.stream,
             in_flight: vec![session.in_flight],
             data: SessionData::new(
@@ -265,12 +266,15 @@ impl<T: SessionStream> Session<T> {
     }
 
     pub async fn into_tls(self) -> Result<Session<TlsStream<T>>, ()> {
+        let stream = self
+            .instance
+            .tls_accept(self.stream, self.data.session_id)
+            .await?;
+
         Ok(Session {
             hostname: self.hostname,
-            stream: self
-                .instance
-                .tls_accept(self.stream, self.data.session_id)
-                .await?,
+            client_cert: stream.client_certificate(),
+            stream,
             state: self.state,
             data: self.data,
             instance: self.instance,
@@ -71,6 +71,8 @@ impl DistributedBlob {
                     Store::MySQL(store) => store.get_blob(key, read_range).await,
                     #[cfg(feature = "rocks")]
                     Store::RocksDb(store) => store.get_blob(key, read_range).await,
+                    #[cfg(feature = "redb")]
+                    Store::Redb(store) => store.get_blob(key, read_range).await,
                     #[cfg(all(
                         feature = "enterprise",
                         any(feature = "postgres", feature = "mysql")
@@ -81,6 +83,40 @@ impl DistributedBlob {
                 BlobBackend::Fs(store) => store.get_blob(key, read_range).await,
                 #[cfg(feature = "s3")]
                 BlobBackend::S3(store) => store.get_blob(key, read_range).await,
+                BlobBackend::Tiered(store) => store.get_blob(key, read_range).await,
+                BlobBackend::Composite(_) => unimplemented!(),
+            }
+        })
+        .await
+    }
+
+    pub async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        Box::pin(async move {
+            match self.get_store(key) {
+                BlobBackend::Store(store) => match store {
+                    #[cfg(feature = "sqlite")]
+                    Store::SQLite(store) => store.blob_size(key).await,
+                    #[cfg(feature = "foundation")]
+                    Store::FoundationDb(store) => store.blob_size(key).await,
+                    #[cfg(feature = "postgres")]
+                    Store::PostgreSQL(store) => store.blob_size(key).await,
+                    #[cfg(feature = "mysql")]
+                    Store::MySQL(store) => store.blob_size(key).await,
+                    #[cfg(feature = "rocks")]
+                    Store::RocksDb(store) => store.blob_size(key).await,
+                    #[cfg(feature = "redb")]
+                    Store::Redb(store) => store.blob_size(key).await,
+                    #[cfg(all(
+                        feature = "enterprise",
+                        any(feature = "postgres", feature = "mysql")
+                    ))]
+                    Store::SQLReadReplica(store) => store.blob_size(key).await,
+                    Store::None => Err(trc::StoreEvent::NotConfigured.into()),
+                },
+                BlobBackend::Fs(store) => store.blob_size(key).await,
+                #[cfg(feature = "s3")]
+                BlobBackend::S3(store) => store.blob_size(key).await,
+                BlobBackend::Tiered(store) => store.blob_size(key).await,
                 BlobBackend::Composite(_) => unimplemented!(),
             }
         })
@@ -101,6 +137,8 @@ impl DistributedBlob {
                     Store::MySQL(store) => store.put_blob(key, data).await,
                     #[cfg(feature = "rocks")]
                     Store::RocksDb(store) => store.put_blob(key, data).await,
+                    #[cfg(feature = "redb")]
+                    Store::Redb(store) => store.put_blob(key, data).await,
                     #[cfg(all(
                         feature = "enterprise",
                         any(feature = "postgres", feature = "mysql")
@@ -111,6 +149,7 @@ impl DistributedBlob {
                 BlobBackend::Fs(store) => store.put_blob(key, data).await,
                 #[cfg(feature = "s3")]
                 BlobBackend::S3(store) => store.put_blob(key, data).await,
+                BlobBackend::Tiered(store) => store.put_blob(key, data).await,
                 BlobBackend::Composite(_) => unimplemented!(),
             }
         })
@@ -131,6 +170,8 @@ impl DistributedBlob {
                     Store::MySQL(store) => store.delete_blob(key).await,
                     #[cfg(feature = "rocks")]
                     Store::RocksDb(store) => store.delete_blob(key).await,
+                    #[cfg(feature = "redb")]
+                    Store::Redb(store) => store.delete_blob(key).await,
                     #[cfg(all(
                         feature = "enterprise",
                         any(feature = "postgres", feature = "mysql")
@@ -141,6 +182,7 @@ impl DistributedBlob {
                 BlobBackend::Fs(store) => store.delete_blob(key).await,
                 #[cfg(feature = "s3")]
                 BlobBackend::S3(store) => store.delete_blob(key).await,
+                BlobBackend::Tiered(store) => store.delete_blob(key).await,
                 BlobBackend::Composite(_) => unimplemented!(),
             }
         })
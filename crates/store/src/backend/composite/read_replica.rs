@@ -152,6 +152,19 @@ impl SQLReadReplica {
         .await
     }
 
+    pub async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        self.run_op(move |store| async move {
+            match store {
+                #[cfg(feature = "postgres")]
+                Store::PostgreSQL(store) => store.blob_size(key).await,
+                #[cfg(feature = "mysql")]
+                Store::MySQL(store) => store.blob_size(key).await,
+                _ => panic!("Invalid store type"),
+            }
+        })
+        .await
+    }
+
     pub async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
         match &self.primary {
             #[cfg(feature = "postgres")]
@@ -6,6 +6,7 @@
 
 use std::{borrow::Cow, fmt::Display};
 
+use ahash::AHashMap;
 use elasticsearch::SearchParts;
 use roaring::RoaringBitmap;
 use serde_json::{json, Value};
@@ -21,6 +22,32 @@ impl ElasticSearchStore {
         collection: impl Into<u8>,
         filters: Vec<FtsFilter<T>>,
     ) -> trc::Result<RoaringBitmap> {
+        self.fts_search(account_id, collection, filters)
+            .await
+            .map(|hits| {
+                hits.into_iter()
+                    .map(|(document_id, _)| document_id)
+                    .collect()
+            })
+    }
+
+    pub async fn fts_query_ranked<T: Into<u8> + Display + Clone + std::fmt::Debug>(
+        &self,
+        account_id: u32,
+        collection: impl Into<u8>,
+        filters: Vec<FtsFilter<T>>,
+    ) -> trc::Result<AHashMap<u32, f64>> {
+        self.fts_search(account_id, collection, filters)
+            .await
+            .map(|hits| hits.into_iter().collect())
+    }
+
+    async fn fts_search<T: Into<u8> + Display + Clone + std::fmt::Debug>(
+        &self,
+        account_id: u32,
+        collection: impl Into<u8>,
+        filters: Vec<FtsFilter<T>>,
+    ) -> trc::Result<Vec<(u32, f64)>> {
         let mut stack: Vec<(FtsFilter<T>, Vec<Value>)> = vec![];
         let mut conditions = vec![json!({ "match": { "account_id": account_id } })];
         let mut logical_op = FtsFilter::And;
@@ -54,6 +81,30 @@ impl ElasticSearchStore {
                         }));
                     }
                 }
+                FtsFilter::Fuzzy { field, text, .. } => {
+                    let query = json!({ "query": text, "fuzziness": "AUTO" });
+
+                    if let Field::Header(name) = field {
+                        conditions.push(json!({"bool": {
+                          "must": [
+                            {
+                              "term": {
+                                "header.name": name.to_string()
+                              }
+                            },
+                            {
+                              "match": {
+                                "header.value": query
+                              }
+                            }
+                          ]
+                        }}));
+                    } else {
+                        conditions.push(json!({
+                            "match": { field.name(): query }
+                        }));
+                    }
+                }
                 FtsFilter::And | FtsFilter::Or | FtsFilter::Not => {
                     stack.push((logical_op, conditions));
                     logical_op = filter;
@@ -108,14 +159,18 @@ impl ElasticSearchStore {
             .json()
             .await
             .map_err(|err| trc::StoreEvent::ElasticsearchError.reason(err))?;
-        let mut results = RoaringBitmap::new();
+        let mut results = Vec::new();
 
         for hit in json["hits"]["hits"].as_array().ok_or_else(|| {
             trc::StoreEvent::ElasticsearchError.reason("Invalid response from ElasticSearch")
         })? {
-            results.insert(hit["_source"]["document_id"].as_u64().ok_or_else(|| {
-                trc::StoreEvent::ElasticsearchError.reason("Invalid response from ElasticSearch")
-            })? as u32);
+            results.push((
+                hit["_source"]["document_id"].as_u64().ok_or_else(|| {
+                    trc::StoreEvent::ElasticsearchError
+                        .reason("Invalid response from ElasticSearch")
+                })? as u32,
+                hit["_score"].as_f64().unwrap_or_default(),
+            ));
         }
 
         Ok(results)
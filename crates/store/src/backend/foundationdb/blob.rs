@@ -92,6 +92,43 @@ impl FdbStore {
         Ok(blob_data)
     }
 
+    pub(crate) async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        // Only the last chunk is read, all others are full
+        let begin = KeySerializer::new(key.len() + 3)
+            .write(SUBSPACE_BLOBS)
+            .write(key)
+            .write(0u16)
+            .finalize();
+        let end = KeySerializer::new(key.len() + 3)
+            .write(SUBSPACE_BLOBS)
+            .write(key)
+            .write(u16::MAX)
+            .finalize();
+        let key_len = begin.len();
+        let trx = self.read_trx().await?;
+        let mut values = trx.get_ranges_keyvalues(
+            RangeOption {
+                begin: KeySelector::first_greater_or_equal(begin),
+                end: KeySelector::first_greater_or_equal(end),
+                mode: StreamingMode::Small,
+                limit: Some(1),
+                reverse: true,
+                ..RangeOption::default()
+            },
+            true,
+        );
+
+        while let Some(value) = values.try_next().await.map_err(into_error)? {
+            let chunk_key = value.key();
+            if chunk_key.len() == key_len {
+                let chunk = u16::from_be_bytes([chunk_key[key_len - 2], chunk_key[key_len - 1]]);
+                return Ok(Some(chunk as usize * MAX_VALUE_SIZE + value.value().len()));
+            }
+        }
+
+        Ok(None)
+    }
+
     pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
         const N_CHUNKS: usize = (1 << 5) - 1;
         let last_chunk = std::cmp::max(
@@ -4,14 +4,14 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{io::SeekFrom, ops::Range, path::PathBuf};
+use std::{io::SeekFrom, ops::Range, path::PathBuf, time::SystemTime};
 
 use tokio::{
     fs::{self, File},
     io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
 };
 use utils::{
-    codec::base32_custom::Base32Writer,
+    codec::base32_custom::{Base32Reader, Base32Writer},
     config::{utils::AsKey, Config},
 };
 
@@ -81,6 +81,14 @@ impl FsStore {
         }))
     }
 
+    pub(crate) async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        match fs::metadata(self.build_path(key)).await {
+            Ok(m) => Ok(Some(m.len() as usize)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(into_error(err)),
+        }
+    }
+
     pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
         let blob_path = self.build_path(key);
 
@@ -109,6 +117,33 @@ impl FsStore {
         }
     }
 
+    pub(crate) async fn blobs_modified_before(
+        &self,
+        before: SystemTime,
+    ) -> trc::Result<Vec<Vec<u8>>> {
+        let mut keys = Vec::new();
+        let mut dirs = vec![self.path.clone()];
+
+        while let Some(dir) = dirs.pop() {
+            let mut entries = fs::read_dir(&dir).await.map_err(into_error)?;
+            while let Some(entry) = entries.next_entry().await.map_err(into_error)? {
+                let metadata = entry.metadata().await.map_err(into_error)?;
+                if metadata.is_dir() {
+                    dirs.push(entry.path());
+                } else if metadata
+                    .modified()
+                    .map_or(false, |modified| modified < before)
+                {
+                    if let Some(name) = entry.file_name().to_str() {
+                        keys.push(Base32Reader::new(name.as_bytes()).collect());
+                    }
+                }
+            }
+        }
+
+        Ok(keys)
+    }
+
     fn build_path(&self, key: &[u8]) -> PathBuf {
         let mut path = self.path.clone();
 
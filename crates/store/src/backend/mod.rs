@@ -16,6 +16,8 @@ pub mod memory;
 pub mod mysql;
 #[cfg(feature = "postgres")]
 pub mod postgres;
+#[cfg(feature = "redb")]
+pub mod redb;
 #[cfg(feature = "redis")]
 pub mod redis;
 #[cfg(feature = "rocks")]
@@ -24,6 +26,9 @@ pub mod rocksdb;
 pub mod s3;
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
+pub mod tiered;
+
+use crate::{SUBSPACE_BITMAP_ID, SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SUBSPACE_INDEXES};
 
 pub const MAX_TOKEN_LENGTH: usize = (u8::MAX >> 1) as usize;
 pub const MAX_TOKEN_MASK: usize = MAX_TOKEN_LENGTH - 1;
@@ -34,3 +39,13 @@ fn deserialize_i64_le(key: &[u8], bytes: &[u8]) -> trc::Result<i64> {
         trc::Error::corrupted_key(key, bytes.into(), trc::location!())
     })?))
 }
+
+// SQL backends store these subspaces as keys without a value, assertions on
+// them only check whether the key exists
+#[allow(dead_code)]
+fn value_column(subspace: u8) -> &'static str {
+    match subspace {
+        SUBSPACE_INDEXES | SUBSPACE_BITMAP_ID | SUBSPACE_BITMAP_TAG | SUBSPACE_BITMAP_TEXT => "k",
+        _ => "v",
+    }
+}
@@ -38,6 +38,18 @@ impl MysqlStore {
             .map_err(into_error)
     }
 
+    pub(crate) async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        let mut conn = self.conn_pool.get_conn().await.map_err(into_error)?;
+        let s = conn
+            .prep("SELECT LENGTH(v) FROM t WHERE k = ?")
+            .await
+            .map_err(into_error)?;
+        conn.exec_first::<u64, _, _>(&s, (key,))
+            .await
+            .map(|size| size.map(|size| size as usize))
+            .map_err(into_error)
+    }
+
     pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
         let mut conn = self.conn_pool.get_conn().await.map_err(into_error)?;
         let s = conn
@@ -108,6 +108,7 @@ impl MysqlStore {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_SNOOZE_EVENT,
         ] {
             let table = char::from(table);
             conn.query_drop(format!(
@@ -149,7 +150,7 @@ impl MysqlStore {
             .map_err(into_error)?;
         }
 
-        for table in [SUBSPACE_COUNTER, SUBSPACE_QUOTA] {
+        for table in [SUBSPACE_COUNTER, SUBSPACE_QUOTA, SUBSPACE_FTS_TERM] {
             conn.query_drop(format!(
                 "CREATE TABLE IF NOT EXISTS {} (
                 k TINYBLOB,
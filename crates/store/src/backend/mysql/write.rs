@@ -13,11 +13,12 @@ use rand::Rng;
 use roaring::RoaringBitmap;
 
 use crate::{
+    backend::value_column,
     write::{
         key::DeserializeBigEndian, AssignedIds, Batch, BitmapClass, Operation, RandomAvailableId,
         ValueOp, MAX_COMMIT_ATTEMPTS, MAX_COMMIT_TIME,
     },
-    BitmapKey, IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_QUOTA, U32_LEN,
+    BitmapKey, IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_FTS_TERM, SUBSPACE_QUOTA, U32_LEN,
 };
 
 use super::{into_error, MysqlStore};
@@ -298,10 +299,15 @@ impl MysqlStore {
                 } => {
                     let key =
                         class.serialize(account_id, collection, document_id, 0, (&result).into());
-                    let table = char::from(class.subspace(collection));
+                    let subspace = class.subspace(collection);
+                    let table = char::from(subspace);
 
                     let s = trx
-                        .prep(format!("SELECT v FROM {} WHERE k = ? FOR UPDATE", table))
+                        .prep(format!(
+                            "SELECT {} FROM {} WHERE k = ? FOR UPDATE",
+                            value_column(subspace),
+                            table
+                        ))
                         .await?;
                     let (exists, matches) = trx
                         .exec_first::<Vec<u8>, _, _>(&s, (&key,))
@@ -322,7 +328,7 @@ impl MysqlStore {
 
     pub(crate) async fn purge_store(&self) -> trc::Result<()> {
         let mut conn = self.conn_pool.get_conn().await.map_err(into_error)?;
-        for subspace in [SUBSPACE_QUOTA, SUBSPACE_COUNTER] {
+        for subspace in [SUBSPACE_QUOTA, SUBSPACE_COUNTER, SUBSPACE_FTS_TERM] {
             let s = conn
                 .prep(format!("DELETE FROM {} WHERE v = 0", char::from(subspace),))
                 .await
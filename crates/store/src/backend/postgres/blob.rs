@@ -39,6 +39,21 @@ impl PostgresStore {
             .map_err(into_error)
     }
 
+    pub(crate) async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        let conn = self.conn_pool.get().await.map_err(into_error)?;
+        let s = conn
+            .prepare_cached("SELECT octet_length(v) FROM t WHERE k = $1")
+            .await
+            .map_err(into_error)?;
+        conn.query_opt(&s, &[&key])
+            .await
+            .and_then(|row| {
+                row.map(|row| row.try_get::<_, i32>(0).map(|size| size as usize))
+                    .transpose()
+            })
+            .map_err(into_error)
+    }
+
     pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
         let conn = self.conn_pool.get().await.map_err(into_error)?;
         let s = conn
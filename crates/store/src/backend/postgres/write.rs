@@ -14,11 +14,12 @@ use roaring::RoaringBitmap;
 use tokio_postgres::{error::SqlState, IsolationLevel};
 
 use crate::{
+    backend::value_column,
     write::{
         key::DeserializeBigEndian, AssignedIds, Batch, BitmapClass, Operation, RandomAvailableId,
         ValueOp, MAX_COMMIT_ATTEMPTS, MAX_COMMIT_TIME,
     },
-    BitmapKey, IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_QUOTA, U32_LEN,
+    BitmapKey, IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_FTS_TERM, SUBSPACE_QUOTA, U32_LEN,
 };
 
 use super::{into_error, PostgresStore};
@@ -308,10 +309,15 @@ impl PostgresStore {
                 } => {
                     let key =
                         class.serialize(account_id, collection, document_id, 0, (&result).into());
-                    let table = char::from(class.subspace(collection));
+                    let subspace = class.subspace(collection);
+                    let table = char::from(subspace);
 
                     let s = trx
-                        .prepare_cached(&format!("SELECT v FROM {} WHERE k = $1 FOR UPDATE", table))
+                        .prepare_cached(&format!(
+                            "SELECT {} FROM {} WHERE k = $1 FOR UPDATE",
+                            value_column(subspace),
+                            table
+                        ))
                         .await?;
                     let (exists, matches) = trx
                         .query_opt(&s, &[&key])
@@ -335,7 +341,7 @@ impl PostgresStore {
     pub(crate) async fn purge_store(&self) -> trc::Result<()> {
         let conn = self.conn_pool.get().await.map_err(into_error)?;
 
-        for subspace in [SUBSPACE_QUOTA, SUBSPACE_COUNTER] {
+        for subspace in [SUBSPACE_QUOTA, SUBSPACE_COUNTER, SUBSPACE_FTS_TERM] {
             let s = conn
                 .prepare_cached(&format!("DELETE FROM {} WHERE v = 0", char::from(subspace),))
                 .await
@@ -0,0 +1,78 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::ops::Range;
+
+use crate::SUBSPACE_BLOBS;
+
+use super::{into_error, RedbStore, TableHandle};
+
+impl RedbStore {
+    pub(crate) async fn get_blob(
+        &self,
+        key: &[u8],
+        range: Range<usize>,
+    ) -> trc::Result<Option<Vec<u8>>> {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let txn = db.begin_read().map_err(into_error)?;
+            let table = txn.subspace_table(SUBSPACE_BLOBS)?;
+            table.get(key).map_err(into_error).map(|obj| {
+                obj.map(|bytes| {
+                    let bytes = bytes.value();
+                    if range.start == 0 && range.end == usize::MAX {
+                        bytes.to_vec()
+                    } else {
+                        bytes
+                            .get(range.start..std::cmp::min(bytes.len(), range.end))
+                            .unwrap_or_default()
+                            .to_vec()
+                    }
+                })
+            })
+        })
+        .await
+    }
+
+    pub(crate) async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let txn = db.begin_read().map_err(into_error)?;
+            let table = txn.subspace_table(SUBSPACE_BLOBS)?;
+            table
+                .get(key)
+                .map_err(into_error)
+                .map(|obj| obj.map(|bytes| bytes.value().len()))
+        })
+        .await
+    }
+
+    pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let txn = db.begin_write().map_err(into_error)?;
+            (&txn)
+                .subspace_table(SUBSPACE_BLOBS)?
+                .insert(key, data)
+                .map_err(into_error)?;
+            txn.commit().map_err(into_error)
+        })
+        .await
+    }
+
+    pub(crate) async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let txn = db.begin_write().map_err(into_error)?;
+            (&txn)
+                .subspace_table(SUBSPACE_BLOBS)?
+                .remove(key)
+                .map_err(into_error)?;
+            txn.commit().map_err(into_error).map(|_| true)
+        })
+        .await
+    }
+}
@@ -0,0 +1,130 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::path::PathBuf;
+
+use redb::Database;
+use tokio::sync::oneshot;
+use utils::config::{utils::AsKey, Config};
+
+use crate::*;
+
+use super::{RedbStore, TableHandle};
+
+impl RedbStore {
+    pub async fn open(config: &mut Config, prefix: impl AsKey) -> Option<Self> {
+        let prefix = prefix.as_key();
+        // Create the database directory if it doesn't exist
+        let path: PathBuf = PathBuf::from(config.value_require((&prefix, "path"))?);
+        std::fs::create_dir_all(&path)
+            .map_err(|err| {
+                config.new_build_error(
+                    (&prefix, "path"),
+                    format!(
+                        "Failed to create database directory {}: {:?}",
+                        path.display(),
+                        err
+                    ),
+                )
+            })
+            .ok()?;
+
+        let db = Database::builder()
+            .set_cache_size(
+                config
+                    .property_or_default((&prefix, "cache-size"), "1073741824")
+                    .unwrap_or(1073741824),
+            )
+            .create(path.join("data.redb"))
+            .map_err(|err| {
+                config.new_build_error(
+                    prefix.as_str(),
+                    format!("Failed to open database: {:?}", err),
+                )
+            })
+            .ok()?;
+
+        // Create all tables so read transactions can open them
+        if let Err(err) = db.begin_write().map_err(super::into_error).and_then(|txn| {
+            for subspace in [
+                SUBSPACE_ACL,
+                SUBSPACE_BITMAP_ID,
+                SUBSPACE_BITMAP_TAG,
+                SUBSPACE_BITMAP_TEXT,
+                SUBSPACE_DIRECTORY,
+                SUBSPACE_FTS_QUEUE,
+                SUBSPACE_INDEXES,
+                SUBSPACE_BLOB_RESERVE,
+                SUBSPACE_BLOB_LINK,
+                SUBSPACE_BLOBS,
+                SUBSPACE_LOGS,
+                SUBSPACE_COUNTER,
+                SUBSPACE_LOOKUP_VALUE,
+                SUBSPACE_PROPERTY,
+                SUBSPACE_SETTINGS,
+                SUBSPACE_QUEUE_MESSAGE,
+                SUBSPACE_QUEUE_EVENT,
+                SUBSPACE_QUOTA,
+                SUBSPACE_REPORT_OUT,
+                SUBSPACE_REPORT_IN,
+                SUBSPACE_FTS_INDEX,
+                SUBSPACE_TELEMETRY_SPAN,
+                SUBSPACE_TELEMETRY_INDEX,
+                SUBSPACE_TELEMETRY_METRIC,
+                SUBSPACE_SNOOZE_EVENT,
+                SUBSPACE_FTS_TERM,
+            ] {
+                (&txn).subspace_table(subspace)?;
+            }
+            txn.commit().map_err(super::into_error)
+        }) {
+            config.new_build_error(
+                prefix.as_str(),
+                format!("Failed to create database tables: {:?}", err),
+            );
+            return None;
+        }
+
+        Some(RedbStore {
+            db: db.into(),
+            worker_pool: rayon::ThreadPoolBuilder::new()
+                .num_threads(std::cmp::max(
+                    config
+                        .property::<usize>((&prefix, "pool.workers"))
+                        .filter(|v| *v > 0)
+                        .unwrap_or_else(num_cpus::get),
+                    4,
+                ))
+                .build()
+                .map_err(|err| {
+                    config.new_build_error(
+                        (&prefix, "pool.workers"),
+                        format!("Failed to build worker pool: {:?}", err),
+                    )
+                })
+                .ok()?,
+        })
+    }
+
+    pub async fn spawn_worker<U, V>(&self, mut f: U) -> trc::Result<V>
+    where
+        U: FnMut() -> trc::Result<V> + Send,
+        V: Sync + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+
+        self.worker_pool.scope(|s| {
+            s.spawn(|_| {
+                tx.send(f()).ok();
+            });
+        });
+
+        match rx.await {
+            Ok(result) => result,
+            Err(err) => Err(trc::EventType::Server(trc::ServerEvent::ThreadError).reason(err)),
+        }
+    }
+}
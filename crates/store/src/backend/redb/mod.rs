@@ -0,0 +1,61 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{fmt::Display, sync::Arc};
+
+use redb::{Database, ReadOnlyTable, ReadTransaction, Table, TableDefinition, WriteTransaction};
+
+pub mod blob;
+pub mod main;
+pub mod read;
+pub mod write;
+
+// Each subspace is stored in its own table, named after the subspace byte
+type SubspaceTable<'x> = TableDefinition<'x, &'static [u8], &'static [u8]>;
+
+pub(crate) trait TableHandle {
+    type Table;
+
+    fn subspace_table(&self, subspace: u8) -> trc::Result<Self::Table>;
+}
+
+impl TableHandle for ReadTransaction {
+    type Table = ReadOnlyTable<&'static [u8], &'static [u8]>;
+
+    #[inline(always)]
+    fn subspace_table(&self, subspace: u8) -> trc::Result<Self::Table> {
+        let name = [subspace];
+        self.open_table(SubspaceTable::new(table_name(&name)))
+            .map_err(into_error)
+    }
+}
+
+impl<'x> TableHandle for &'x WriteTransaction {
+    type Table = Table<'x, &'static [u8], &'static [u8]>;
+
+    #[inline(always)]
+    fn subspace_table(&self, subspace: u8) -> trc::Result<Self::Table> {
+        let txn: &'x WriteTransaction = self;
+        let name = [subspace];
+        txn.open_table(SubspaceTable::new(table_name(&name)))
+            .map_err(into_error)
+    }
+}
+
+#[inline(always)]
+fn table_name(name: &[u8; 1]) -> &str {
+    std::str::from_utf8(name).unwrap_or_default()
+}
+
+pub struct RedbStore {
+    db: Arc<Database>,
+    worker_pool: rayon::ThreadPool,
+}
+
+#[inline(always)]
+fn into_error(err: impl Display) -> trc::Error {
+    trc::StoreEvent::RedbError.reason(err)
+}
@@ -0,0 +1,121 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use roaring::RoaringBitmap;
+
+use super::{into_error, RedbStore, TableHandle};
+
+use crate::{
+    backend::deserialize_i64_le,
+    write::{key::DeserializeBigEndian, BitmapClass, ValueClass},
+    BitmapKey, Deserialize, IterateParams, Key, ValueKey, U32_LEN,
+};
+
+impl RedbStore {
+    pub(crate) async fn get_value<U>(&self, key: impl Key) -> trc::Result<Option<U>>
+    where
+        U: Deserialize + 'static,
+    {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let txn = db.begin_read().map_err(into_error)?;
+            let table = txn.subspace_table(key.subspace())?;
+            let key = key.serialize(0);
+
+            if let Some(value) = table.get(key.as_slice()).map_err(into_error)? {
+                U::deserialize(value.value()).map(Some)
+            } else {
+                Ok(None)
+            }
+        })
+        .await
+    }
+
+    pub(crate) async fn get_bitmap(
+        &self,
+        mut key: BitmapKey<BitmapClass<u32>>,
+    ) -> trc::Result<Option<RoaringBitmap>> {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let mut bm = RoaringBitmap::new();
+            let txn = db.begin_read().map_err(into_error)?;
+            let table = txn.subspace_table(key.subspace())?;
+            let begin = key.serialize(0);
+            key.document_id = u32::MAX;
+            let end = key.serialize(0);
+            let key_len = begin.len();
+
+            for row in table
+                .range(begin.as_slice()..=end.as_slice())
+                .map_err(into_error)?
+            {
+                let (key, _) = row.map_err(into_error)?;
+                let key = key.value();
+                if key.len() == key_len {
+                    bm.insert(key.deserialize_be_u32(key.len() - U32_LEN)?);
+                }
+            }
+
+            Ok(if !bm.is_empty() { Some(bm) } else { None })
+        })
+        .await
+    }
+
+    pub(crate) async fn iterate<T: Key>(
+        &self,
+        params: IterateParams<T>,
+        mut cb: impl for<'x> FnMut(&'x [u8], &'x [u8]) -> trc::Result<bool> + Sync + Send,
+    ) -> trc::Result<()> {
+        let db = self.db.clone();
+
+        self.spawn_worker(move || {
+            let txn = db.begin_read().map_err(into_error)?;
+            let table = txn.subspace_table(params.begin.subspace())?;
+            let begin = params.begin.serialize(0);
+            let end = params.end.serialize(0);
+            if begin > end {
+                return Ok(());
+            }
+
+            let mut rows = table
+                .range(begin.as_slice()..=end.as_slice())
+                .map_err(into_error)?;
+            while let Some(row) = if params.ascending {
+                rows.next()
+            } else {
+                rows.next_back()
+            } {
+                let (key, value) = row.map_err(into_error)?;
+                if !cb(key.value(), value.value())? || params.first {
+                    break;
+                }
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    pub(crate) async fn get_counter(
+        &self,
+        key: impl Into<ValueKey<ValueClass<u32>>> + Sync + Send,
+    ) -> trc::Result<i64> {
+        let key = key.into();
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let txn = db.begin_read().map_err(into_error)?;
+            let table = txn.subspace_table(key.subspace())?;
+            let key = key.serialize(0);
+
+            if let Some(bytes) = table.get(key.as_slice()).map_err(into_error)? {
+                deserialize_i64_le(&key, bytes.value())
+            } else {
+                Ok(0)
+            }
+        })
+        .await
+    }
+}
@@ -0,0 +1,226 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use redb::{ReadableTable, WriteTransaction};
+use roaring::RoaringBitmap;
+
+use super::{into_error, RedbStore, TableHandle};
+use crate::{
+    backend::deserialize_i64_le,
+    write::{
+        key::DeserializeBigEndian, AssignedIds, Batch, BitmapClass, Operation, RandomAvailableId,
+        ValueOp,
+    },
+    BitmapKey, Deserialize, IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_FTS_TERM,
+    SUBSPACE_INDEXES, SUBSPACE_LOGS, SUBSPACE_QUOTA, U32_LEN,
+};
+
+impl RedbStore {
+    pub(crate) async fn write(&self, batch: Batch) -> trc::Result<AssignedIds> {
+        let db = self.db.clone();
+
+        // Write transactions are serialized by redb, so unlike optimistic
+        // backends there are no conflicts to retry
+        self.spawn_worker(move || {
+            let txn = db.begin_write().map_err(into_error)?;
+            match commit(&txn, &batch) {
+                Ok(result) => txn.commit().map(|_| result).map_err(into_error),
+                Err(err) => {
+                    txn.abort().map_err(into_error)?;
+                    Err(err)
+                }
+            }
+        })
+        .await
+    }
+
+    pub(crate) async fn delete_range(&self, from: impl Key, to: impl Key) -> trc::Result<()> {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let from_key = from.serialize(0);
+            let to_key = to.serialize(0);
+            if from_key >= to_key {
+                return Ok(());
+            }
+
+            let txn = db.begin_write().map_err(into_error)?;
+            (&txn)
+                .subspace_table(from.subspace())?
+                .retain_in(from_key.as_slice()..to_key.as_slice(), |_, _| false)
+                .map_err(into_error)?;
+            txn.commit().map_err(into_error)
+        })
+        .await
+    }
+
+    pub(crate) async fn purge_store(&self) -> trc::Result<()> {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let txn = db.begin_write().map_err(into_error)?;
+            for subspace in [SUBSPACE_QUOTA, SUBSPACE_COUNTER, SUBSPACE_FTS_TERM] {
+                (&txn)
+                    .subspace_table(subspace)?
+                    .retain(|_, value| i64::deserialize(value).map_or(true, |v| v != 0))
+                    .map_err(into_error)?;
+            }
+            txn.commit().map_err(into_error)
+        })
+        .await
+    }
+}
+
+fn commit(txn: &WriteTransaction, batch: &Batch) -> trc::Result<AssignedIds> {
+    let mut account_id = u32::MAX;
+    let mut collection = u8::MAX;
+    let mut document_id = u32::MAX;
+    let mut change_id = u64::MAX;
+    let mut result = AssignedIds::default();
+
+    for op in &batch.ops {
+        match op {
+            Operation::AccountId {
+                account_id: account_id_,
+            } => {
+                account_id = *account_id_;
+            }
+            Operation::Collection {
+                collection: collection_,
+            } => {
+                collection = *collection_;
+            }
+            Operation::DocumentId {
+                document_id: document_id_,
+            } => {
+                document_id = *document_id_;
+            }
+            Operation::ChangeId {
+                change_id: change_id_,
+            } => {
+                change_id = *change_id_;
+            }
+            Operation::Value { class, op } => {
+                let key = class.serialize(account_id, collection, document_id, 0, (&result).into());
+                let mut table = txn.subspace_table(class.subspace(collection))?;
+
+                match op {
+                    ValueOp::Set(value) => {
+                        table
+                            .insert(key.as_slice(), value.resolve(&result)?.as_ref())
+                            .map_err(into_error)?;
+                    }
+                    ValueOp::AtomicAdd(by) | ValueOp::AddAndGet(by) => {
+                        let num =
+                            if let Some(bytes) = table.get(key.as_slice()).map_err(into_error)? {
+                                deserialize_i64_le(&key, bytes.value())? + *by
+                            } else {
+                                *by
+                            };
+                        table
+                            .insert(key.as_slice(), &num.to_le_bytes()[..])
+                            .map_err(into_error)?;
+                        if matches!(op, ValueOp::AddAndGet(_)) {
+                            result.push_counter_id(num);
+                        }
+                    }
+                    ValueOp::Clear => {
+                        table.remove(key.as_slice()).map_err(into_error)?;
+                    }
+                }
+            }
+            Operation::Index { field, key, set } => {
+                let key = IndexKey {
+                    account_id,
+                    collection,
+                    document_id,
+                    field: *field,
+                    key,
+                }
+                .serialize(0);
+                let mut table = txn.subspace_table(SUBSPACE_INDEXES)?;
+
+                if *set {
+                    table.insert(key.as_slice(), &[][..]).map_err(into_error)?;
+                } else {
+                    table.remove(key.as_slice()).map_err(into_error)?;
+                }
+            }
+            Operation::Bitmap { class, set } => {
+                let is_document_id = matches!(class, BitmapClass::DocumentIds);
+                let mut table = txn.subspace_table(class.subspace())?;
+                if *set && is_document_id && document_id == u32::MAX {
+                    let begin = BitmapKey {
+                        account_id,
+                        collection,
+                        class: BitmapClass::DocumentIds,
+                        document_id: 0,
+                    }
+                    .serialize(0);
+                    let end = BitmapKey {
+                        account_id,
+                        collection,
+                        class: BitmapClass::DocumentIds,
+                        document_id: u32::MAX,
+                    }
+                    .serialize(0);
+                    let key_len = begin.len();
+                    let mut found_ids = RoaringBitmap::new();
+
+                    for row in table
+                        .range(begin.as_slice()..=end.as_slice())
+                        .map_err(into_error)?
+                    {
+                        let (key, _) = row.map_err(into_error)?;
+                        let key = key.value();
+                        if key.len() == key_len {
+                            found_ids.insert(key.deserialize_be_u32(key.len() - U32_LEN)?);
+                        }
+                    }
+
+                    document_id = found_ids.random_available_id();
+                    result.push_document_id(document_id);
+                }
+                let key = class.serialize(account_id, collection, document_id, 0, (&result).into());
+
+                if *set {
+                    table.insert(key.as_slice(), &[][..]).map_err(into_error)?;
+                } else {
+                    table.remove(key.as_slice()).map_err(into_error)?;
+                }
+            }
+            Operation::Log { set } => {
+                let key = LogKey {
+                    account_id,
+                    collection,
+                    change_id,
+                }
+                .serialize(0);
+
+                txn.subspace_table(SUBSPACE_LOGS)?
+                    .insert(key.as_slice(), set.resolve(&result)?.as_ref())
+                    .map_err(into_error)?;
+            }
+            Operation::AssertValue {
+                class,
+                assert_value,
+            } => {
+                let key = class.serialize(account_id, collection, document_id, 0, (&result).into());
+                let table = txn.subspace_table(class.subspace(collection))?;
+
+                let matches = table
+                    .get(key.as_slice())
+                    .map_err(into_error)?
+                    .map(|value| assert_value.matches(value.value()))
+                    .unwrap_or_else(|| assert_value.is_none());
+
+                if !matches {
+                    return Err(trc::StoreEvent::AssertValueFailed.into());
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
@@ -36,6 +36,15 @@ impl RedisStore {
                 )
                 .await
             }
+            RedisPool::Sentinel(pool) => {
+                self.key_set_(
+                    pool.get().await.map_err(into_error)?.as_mut(),
+                    key,
+                    value,
+                    expires,
+                )
+                .await
+            }
         }
     }
 
@@ -64,6 +73,15 @@ impl RedisStore {
                 )
                 .await
             }
+            RedisPool::Sentinel(pool) => {
+                self.key_incr_(
+                    pool.get().await.map_err(into_error)?.as_mut(),
+                    key,
+                    value,
+                    expires,
+                )
+                .await
+            }
         }
     }
 
@@ -77,6 +95,10 @@ impl RedisStore {
                 self.key_delete_(pool.get().await.map_err(into_error)?.as_mut(), key)
                     .await
             }
+            RedisPool::Sentinel(pool) => {
+                self.key_delete_(pool.get().await.map_err(into_error)?.as_mut(), key)
+                    .await
+            }
         }
     }
 
@@ -93,6 +115,10 @@ impl RedisStore {
                 self.key_get_(pool.get().await.map_err(into_error)?.as_mut(), key)
                     .await
             }
+            RedisPool::Sentinel(pool) => {
+                self.key_get_(pool.get().await.map_err(into_error)?.as_mut(), key)
+                    .await
+            }
         }
     }
 
@@ -106,6 +132,10 @@ impl RedisStore {
                 self.counter_get_(pool.get().await.map_err(into_error)?.as_mut(), key)
                     .await
             }
+            RedisPool::Sentinel(pool) => {
+                self.counter_get_(pool.get().await.map_err(into_error)?.as_mut(), key)
+                    .await
+            }
         }
     }
 
@@ -119,6 +149,10 @@ impl RedisStore {
                 self.key_exists_(pool.get().await.map_err(into_error)?.as_mut(), key)
                     .await
             }
+            RedisPool::Sentinel(pool) => {
+                self.key_exists_(pool.get().await.map_err(into_error)?.as_mut(), key)
+                    .await
+            }
         }
     }
 
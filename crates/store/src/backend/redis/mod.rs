@@ -12,8 +12,10 @@ use deadpool::{
 };
 use redis::{
     cluster::{ClusterClient, ClusterClientBuilder},
-    Client,
+    sentinel::{SentinelClient, SentinelNodeConnectionInfo, SentinelServerType},
+    Client, RedisConnectionInfo, TlsMode,
 };
+use tokio::sync::Mutex;
 use utils::config::{utils::AsKey, Config};
 
 pub mod lookup;
@@ -34,9 +36,15 @@ struct RedisClusterConnectionManager {
     timeout: Duration,
 }
 
+struct RedisSentinelConnectionManager {
+    client: Mutex<SentinelClient>,
+    timeout: Duration,
+}
+
 enum RedisPool {
     Single(Pool<RedisConnectionManager>),
     Cluster(Pool<RedisClusterConnectionManager>),
+    Sentinel(Pool<RedisSentinelConnectionManager>),
 }
 
 impl RedisStore {
@@ -136,6 +144,70 @@ impl RedisStore {
                         ),
                     }
                 }
+                "sentinel" => {
+                    let master_name = config.value_require((&prefix, "master-name"))?.to_string();
+                    let tls_mode = if config
+                        .property_or_default((&prefix, "tls.enable"), "false")
+                        .unwrap_or(false)
+                    {
+                        if config
+                            .property_or_default((&prefix, "tls.allow-invalid-certs"), "false")
+                            .unwrap_or(false)
+                        {
+                            Some(TlsMode::Insecure)
+                        } else {
+                            Some(TlsMode::Secure)
+                        }
+                    } else {
+                        None
+                    };
+                    let node_info = SentinelNodeConnectionInfo {
+                        tls_mode,
+                        redis_connection_info: Some(RedisConnectionInfo {
+                            db: config.property::<u32>((&prefix, "db")).unwrap_or(0) as i64,
+                            username: config.property((&prefix, "user")),
+                            password: config.property((&prefix, "password")),
+                            ..Default::default()
+                        }),
+                    };
+
+                    let client = SentinelClient::build(
+                        urls,
+                        master_name,
+                        Some(node_info),
+                        SentinelServerType::Master,
+                    )
+                    .map_err(|err| {
+                        config.new_build_error(
+                            prefix.as_str(),
+                            format!("Failed to open Redis Sentinel client: {err:?}"),
+                        )
+                    })
+                    .ok()?;
+                    let timeout = config
+                        .property_or_default::<Duration>((&prefix, "timeout"), "10s")
+                        .unwrap_or_else(|| Duration::from_secs(10));
+
+                    Self {
+                        pool: RedisPool::Sentinel(
+                            build_pool(
+                                config,
+                                &prefix,
+                                RedisSentinelConnectionManager {
+                                    client: Mutex::new(client),
+                                    timeout,
+                                },
+                            )
+                            .map_err(|err| {
+                                config.new_build_error(
+                                    prefix.as_str(),
+                                    format!("Failed to build Redis pool: {err:?}"),
+                                )
+                            })
+                            .ok()?,
+                        ),
+                    }
+                }
                 invalid => {
                     let err = format!("Invalid Redis type {invalid:?}");
                     config.new_parse_error((&prefix, "redis-type"), err);
@@ -193,6 +265,7 @@ impl std::fmt::Debug for RedisPool {
         match self {
             Self::Single(_) => f.debug_tuple("Single").finish(),
             Self::Cluster(_) => f.debug_tuple("Cluster").finish(),
+            Self::Sentinel(_) => f.debug_tuple("Sentinel").finish(),
         }
     }
 }
@@ -8,9 +8,13 @@ use deadpool::managed;
 use redis::{
     aio::{ConnectionLike, MultiplexedConnection},
     cluster_async::ClusterConnection,
+    FromRedisValue, Value,
 };
 
-use super::{into_error, RedisClusterConnectionManager, RedisConnectionManager};
+use super::{
+    into_error, RedisClusterConnectionManager, RedisConnectionManager,
+    RedisSentinelConnectionManager,
+};
 
 impl managed::Manager for RedisConnectionManager {
     type Type = MultiplexedConnection;
@@ -59,3 +63,47 @@ impl managed::Manager for RedisClusterConnectionManager {
             .map_err(|err| managed::RecycleError::Backend(into_error(err)))
     }
 }
+
+impl managed::Manager for RedisSentinelConnectionManager {
+    type Type = MultiplexedConnection;
+    type Error = trc::Error;
+
+    async fn create(&self) -> Result<MultiplexedConnection, trc::Error> {
+        // The sentinels are queried for the current master on every new connection
+        match tokio::time::timeout(self.timeout, async {
+            self.client.lock().await.get_async_connection().await
+        })
+        .await
+        {
+            Ok(conn) => conn.map_err(into_error),
+            Err(_) => Err(trc::StoreEvent::RedisError.ctx(trc::Key::Details, "Connection Timeout")),
+        }
+    }
+
+    async fn recycle(
+        &self,
+        conn: &mut MultiplexedConnection,
+        _: &managed::Metrics,
+    ) -> managed::RecycleResult<trc::Error> {
+        // Discard connections to a demoted master after a failover
+        let role = conn
+            .req_packed_command(&redis::cmd("ROLE"))
+            .await
+            .map_err(|err| managed::RecycleError::Backend(into_error(err)))?;
+        let role = match role {
+            Value::Array(values) => values
+                .first()
+                .and_then(|value| String::from_redis_value(value).ok()),
+            _ => None,
+        };
+
+        if role.map_or(false, |role| role.eq_ignore_ascii_case("master")) {
+            Ok(())
+        } else {
+            Err(managed::RecycleError::Backend(
+                trc::StoreEvent::RedisError
+                    .ctx(trc::Key::Details, "Redis node is no longer a master"),
+            ))
+        }
+    }
+}
@@ -34,6 +34,16 @@ impl RocksDbStore {
         .await
     }
 
+    pub(crate) async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            db.get_pinned_cf(&db.cf_handle(CF_BLOBS).unwrap(), key)
+                .map(|obj| obj.map(|bytes| bytes.len()))
+                .map_err(into_error)
+        })
+        .await
+    }
+
     pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
         let db = self.db.clone();
         self.spawn_worker(move || {
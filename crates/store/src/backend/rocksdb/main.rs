@@ -50,7 +50,7 @@ impl RocksDbStore {
         }
 
         // Counters
-        for subspace in [SUBSPACE_COUNTER, SUBSPACE_QUOTA] {
+        for subspace in [SUBSPACE_COUNTER, SUBSPACE_QUOTA, SUBSPACE_FTS_TERM] {
             let mut cf_opts = Options::default();
             cf_opts.set_merge_operator_associative("merge", numeric_value_merge);
             cfs.push(ColumnFamilyDescriptor::new(
@@ -90,6 +90,7 @@ impl RocksDbStore {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_SNOOZE_EVENT,
         ] {
             let cf_opts = Options::default();
             cfs.push(ColumnFamilyDescriptor::new(
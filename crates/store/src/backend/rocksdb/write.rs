@@ -24,7 +24,8 @@ use crate::{
         key::DeserializeBigEndian, AssignedIds, Batch, BitmapClass, Operation, RandomAvailableId,
         ValueOp, MAX_COMMIT_ATTEMPTS, MAX_COMMIT_TIME,
     },
-    BitmapKey, Deserialize, IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_QUOTA, U32_LEN,
+    BitmapKey, Deserialize, IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_FTS_TERM,
+    SUBSPACE_QUOTA, U32_LEN,
 };
 
 impl RocksDbStore {
@@ -101,7 +102,7 @@ impl RocksDbStore {
     pub(crate) async fn purge_store(&self) -> trc::Result<()> {
         let db = self.db.clone();
         self.spawn_worker(move || {
-            for subspace in [SUBSPACE_QUOTA, SUBSPACE_COUNTER] {
+            for subspace in [SUBSPACE_QUOTA, SUBSPACE_COUNTER, SUBSPACE_FTS_TERM] {
                 let cf = db
                     .cf_handle(std::str::from_utf8(&[subspace]).unwrap())
                     .unwrap();
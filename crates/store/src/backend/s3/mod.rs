@@ -115,6 +115,32 @@ impl S3Store {
         }
     }
 
+    pub(crate) async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        let path = self.build_key(key);
+        let mut retries_left = self.max_retries;
+
+        loop {
+            let (head, code) = self.bucket.head_object(&path).await.map_err(into_error)?;
+
+            match code {
+                200..=299 => {
+                    return Ok(Some(head.content_length.unwrap_or_default().max(0) as usize))
+                }
+                404 => return Ok(None),
+                500..=599 if retries_left > 0 => {
+                    // wait backoff
+                    tokio::time::sleep(Duration::from_secs(
+                        1 << (self.max_retries - retries_left).max(16),
+                    ))
+                    .await;
+
+                    retries_left -= 1;
+                }
+                code => return Err(trc::StoreEvent::S3Error.ctx(trc::Key::Code, code)),
+            }
+        }
+    }
+
     pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
         let mut retries_left = self.max_retries;
 
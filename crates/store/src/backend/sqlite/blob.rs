@@ -41,6 +41,19 @@ impl SqliteStore {
         .await
     }
 
+    pub(crate) async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        let conn = self.conn_pool.get().map_err(into_error)?;
+        self.spawn_worker(move || {
+            conn.prepare_cached("SELECT LENGTH(v) FROM t WHERE k = ?")
+                .map_err(into_error)?
+                .query_row([&key], |row| row.get::<_, i64>(0))
+                .optional()
+                .map(|size| size.map(|size| size as usize))
+                .map_err(into_error)
+        })
+        .await
+    }
+
     pub(crate) async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
         let conn = self.conn_pool.get().map_err(into_error)?;
         self.spawn_worker(move || {
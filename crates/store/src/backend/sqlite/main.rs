@@ -107,6 +107,7 @@ impl SqliteStore {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_SNOOZE_EVENT,
         ] {
             let table = char::from(table);
             conn.execute(
@@ -139,7 +140,7 @@ impl SqliteStore {
             .map_err(into_error)?;
         }
 
-        for table in [SUBSPACE_COUNTER, SUBSPACE_QUOTA] {
+        for table in [SUBSPACE_COUNTER, SUBSPACE_QUOTA, SUBSPACE_FTS_TERM] {
             conn.execute(
                 &format!(
                     "CREATE TABLE IF NOT EXISTS {} (
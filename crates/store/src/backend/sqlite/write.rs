@@ -8,11 +8,12 @@ use roaring::RoaringBitmap;
 use rusqlite::{params, OptionalExtension, TransactionBehavior};
 
 use crate::{
+    backend::value_column,
     write::{
         key::DeserializeBigEndian, AssignedIds, Batch, BitmapClass, Operation, RandomAvailableId,
         ValueOp,
     },
-    BitmapKey, IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_QUOTA, U32_LEN,
+    BitmapKey, IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_FTS_TERM, SUBSPACE_QUOTA, U32_LEN,
 };
 
 use super::{into_error, SqliteStore};
@@ -232,10 +233,15 @@ impl SqliteStore {
                             0,
                             (&result).into(),
                         );
-                        let table = char::from(class.subspace(collection));
+                        let subspace = class.subspace(collection);
+                        let table = char::from(subspace);
 
                         let matches = trx
-                            .prepare_cached(&format!("SELECT v FROM {} WHERE k = ?", table))
+                            .prepare_cached(&format!(
+                                "SELECT {} FROM {} WHERE k = ?",
+                                value_column(subspace),
+                                table
+                            ))
                             .map_err(into_error)?
                             .query_row([&key], |row| {
                                 Ok(assert_value.matches(row.get_ref(0)?.as_bytes()?))
@@ -259,7 +265,7 @@ impl SqliteStore {
     pub(crate) async fn purge_store(&self) -> trc::Result<()> {
         let conn = self.conn_pool.get().map_err(into_error)?;
         self.spawn_worker(move || {
-            for subspace in [SUBSPACE_QUOTA, SUBSPACE_COUNTER] {
+            for subspace in [SUBSPACE_QUOTA, SUBSPACE_COUNTER, SUBSPACE_FTS_TERM] {
                 conn.prepare_cached(&format!("DELETE FROM {} WHERE v = 0", char::from(subspace),))
                     .map_err(into_error)?
                     .execute([])
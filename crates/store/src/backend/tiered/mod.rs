@@ -0,0 +1,164 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    ops::Range,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use trc::AddContext;
+use utils::config::{utils::AsKey, Config};
+
+use crate::{BlobBackend, BlobStore, CompressionAlgo, Stores};
+
+use super::fs::FsStore;
+
+pub struct TieredBlob {
+    pub hot: Arc<FsStore>,
+    pub cold: BlobStore,
+    pub max_age: Duration,
+    pub promote: bool,
+}
+
+impl TieredBlob {
+    pub fn open(config: &mut Config, prefix: impl AsKey, stores: &Stores) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let hot_id = config.value_require((&prefix, "hot"))?.to_string();
+        let cold_id = config.value_require((&prefix, "cold"))?.to_string();
+
+        let hot = match stores.blob_stores.get(&hot_id).map(|store| &store.backend) {
+            Some(BlobBackend::Fs(store)) => store.clone(),
+            Some(_) => {
+                config.new_build_error(
+                    (&prefix, "hot"),
+                    format!("Blob store {hot_id} is not a filesystem store"),
+                );
+                return None;
+            }
+            None => {
+                config.new_build_error((&prefix, "hot"), format!("Blob store {hot_id} not found"));
+                return None;
+            }
+        };
+        let cold = match stores.blob_stores.get(&cold_id).map(|store| &store.backend) {
+            Some(BlobBackend::Tiered(_)) => {
+                config.new_build_error(
+                    (&prefix, "cold"),
+                    format!("Blob store {cold_id} cannot be a tiered store"),
+                );
+                return None;
+            }
+            Some(backend) => BlobStore {
+                backend: backend.clone(),
+                compression: CompressionAlgo::None,
+            },
+            None => {
+                config
+                    .new_build_error((&prefix, "cold"), format!("Blob store {cold_id} not found"));
+                return None;
+            }
+        };
+
+        Some(TieredBlob {
+            hot,
+            cold,
+            max_age: config
+                .property_or_default::<Duration>((&prefix, "migrate.max-age"), "30d")
+                .unwrap_or_else(|| Duration::from_secs(30 * 86400)),
+            promote: config
+                .property_or_default((&prefix, "promote"), "false")
+                .unwrap_or(false),
+        })
+    }
+
+    pub async fn get_blob(
+        &self,
+        key: &[u8],
+        read_range: Range<usize>,
+    ) -> trc::Result<Option<Vec<u8>>> {
+        if let Some(blob) = self.hot.get_blob(key, read_range.clone()).await? {
+            return Ok(Some(blob));
+        }
+
+        Box::pin(async move {
+            if !self.promote {
+                return self.cold.get_blob(key, read_range).await;
+            }
+
+            // Copy the blob back to the hot tier, it will be moved again once it ages
+            if let Some(blob) = self.cold.get_blob(key, 0..usize::MAX).await? {
+                self.hot.put_blob(key, &blob).await?;
+                self.cold.delete_blob(key).await?;
+
+                trc::event!(
+                    Store(trc::StoreEvent::BlobPromoted),
+                    Key = key,
+                    Size = blob.len(),
+                );
+
+                Ok(Some(
+                    if read_range.start == 0 && read_range.end >= blob.len() {
+                        blob
+                    } else {
+                        blob.get(read_range.start..std::cmp::min(read_range.end, blob.len()))
+                            .unwrap_or_default()
+                            .to_vec()
+                    },
+                ))
+            } else {
+                Ok(None)
+            }
+        })
+        .await
+    }
+
+    /// Returns the stored size of a blob without promoting it to the hot tier.
+    pub async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        if let Some(size) = self.hot.blob_size(key).await? {
+            return Ok(Some(size));
+        }
+
+        Box::pin(self.cold.blob_size(key)).await
+    }
+
+    pub async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
+        self.hot.put_blob(key, data).await
+    }
+
+    pub async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
+        let deleted = self.hot.delete_blob(key).await?;
+        Box::pin(async move { Ok(self.cold.delete_blob(key).await? || deleted) }).await
+    }
+
+    pub async fn migrate_cold_blobs(&self) -> trc::Result<()> {
+        let before = SystemTime::now() - self.max_age;
+        let keys = self
+            .hot
+            .blobs_modified_before(before)
+            .await
+            .caused_by(trc::location!())?;
+
+        for key in keys {
+            if let Some(blob) = self.hot.get_blob(&key, 0..usize::MAX).await? {
+                // Write to the cold tier before removing the blob from the hot tier,
+                // readers fall back to the cold tier if the blob is not found
+                Box::pin(self.cold.put_blob(&key, &blob))
+                    .await
+                    .caused_by(trc::location!())?;
+                self.hot.delete_blob(&key).await?;
+
+                trc::event!(
+                    Store(trc::StoreEvent::BlobDemoted),
+                    Key = key,
+                    Size = blob.len(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
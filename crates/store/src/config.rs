@@ -4,14 +4,18 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use utils::config::{cron::SimpleCron, utils::ParseValue, Config};
 
 use crate::{
-    backend::fs::FsStore,
+    backend::{fs::FsStore, tiered::TieredBlob},
+    dispatch::{
+        cache::{register_cache, StoreCache},
+        register_telemetry,
+    },
     write::purge::{PurgeSchedule, PurgeStore},
-    BlobStore, CompressionAlgo, FtsStore, LookupStore, QueryStore, Store, Stores,
+    BlobBackend, BlobStore, CompressionAlgo, FtsStore, LookupStore, QueryStore, Store, Stores,
 };
 
 #[cfg(feature = "s3")]
@@ -29,6 +33,8 @@ use crate::backend::sqlite::SqliteStore;
 #[cfg(feature = "foundation")]
 use crate::backend::foundationdb::FdbStore;
 
+#[cfg(feature = "redb")]
+use crate::backend::redb::RedbStore;
 #[cfg(feature = "rocks")]
 use crate::backend::rocksdb::RocksDbStore;
 
@@ -55,6 +61,7 @@ impl Stores {
         let is_reload = !self.stores.is_empty();
         #[cfg(feature = "enterprise")]
         let mut composite_stores = Vec::new();
+        let mut tiered_stores = Vec::new();
         let store_ids = config
             .sub_keys("store", ".type")
             .map(|id| id.to_string())
@@ -106,6 +113,28 @@ impl Stores {
                         self.lookup_stores.insert(store_id, db.into());
                     }
                 }
+                #[cfg(feature = "redb")]
+                "redb" => {
+                    // Avoid opening the same store twice
+                    if is_reload
+                        && self
+                            .stores
+                            .values()
+                            .any(|store| matches!(store, Store::Redb(_)))
+                    {
+                        continue;
+                    }
+
+                    if let Some(db) = RedbStore::open(config, prefix).await.map(Store::from) {
+                        self.stores.insert(store_id.clone(), db.clone());
+                        self.fts_stores.insert(store_id.clone(), db.clone().into());
+                        self.blob_stores.insert(
+                            store_id.clone(),
+                            BlobStore::from(db.clone()).with_compression(compression_algo),
+                        );
+                        self.lookup_stores.insert(store_id, db.into());
+                    }
+                }
                 #[cfg(feature = "foundation")]
                 "foundationdb" => {
                     // Avoid opening the same store twice
@@ -216,6 +245,9 @@ impl Stores {
                 "sql-read-replica" | "distributed-blob" => {
                     composite_stores.push((store_id, protocol));
                 }
+                "tiered" => {
+                    tiered_stores.push((store_id, compression_algo));
+                }
                 unknown => {
                     config.new_parse_warning(
                         ("store", id, "type"),
@@ -271,6 +303,76 @@ impl Stores {
                 _ => (),
             }
         }
+
+        // Tiered stores are parsed last, their tiers can be any other blob store
+        for (id, compression) in tiered_stores {
+            if let Some(db) = TieredBlob::open(config, ("store", id.as_str()), self) {
+                self.blob_stores.insert(
+                    id,
+                    BlobStore {
+                        backend: BlobBackend::Tiered(db.into()),
+                        compression,
+                    },
+                );
+            }
+        }
+
+        // Label store operation metrics with the store id
+        for (id, backend) in self
+            .stores
+            .iter()
+            .map(|(id, store)| (id, store.backend()))
+            .chain(
+                self.blob_stores
+                    .iter()
+                    .filter(|(_, store)| !matches!(store.backend, BlobBackend::Store(_)))
+                    .map(|(id, store)| (id, store.backend.backend())),
+            )
+        {
+            let slow_threshold = config
+                .property::<Option<Duration>>(("store", id.as_str(), "telemetry.slow-threshold"))
+                .unwrap_or_default();
+            register_telemetry(backend, id, slow_threshold);
+        }
+
+        // Reject writes to stores frozen by a migration
+        for (id, store) in &self.stores {
+            if let Err(err) = store.refresh_read_only().await {
+                config.new_build_warning(
+                    ("store", id.as_str()),
+                    format!("Failed to read the read-only state of the store: {err}"),
+                );
+            }
+        }
+
+        // Read-through caching of properties and bitmaps, invalidations are
+        // gossiped to other cluster nodes over UDP and might be lost, so their
+        // cached entries always expire
+        let is_clustered = config.value("cluster.bind-addr").is_some()
+            || config.value("cluster.node-id").is_some();
+        for (id, store) in &self.stores {
+            let cache = config
+                .property::<usize>(("store", id.as_str(), "cache.size"))
+                .filter(|size| *size > 0)
+                .and_then(|size| {
+                    let ttl = config
+                        .property_or_default::<Option<Duration>>(
+                            ("store", id.as_str(), "cache.ttl"),
+                            if is_clustered { "5s" } else { "false" },
+                        )
+                        .unwrap_or_default();
+                    if ttl.is_none() && is_clustered {
+                        config.new_build_error(
+                            ("store", id.as_str(), "cache.ttl"),
+                            "A cache TTL is required when clustering is enabled",
+                        );
+                        None
+                    } else {
+                        Some(StoreCache::new(size, ttl))
+                    }
+                });
+            register_cache(store, cache);
+        }
     }
 
     pub async fn parse_lookups(&mut self, config: &mut Config) {
@@ -365,6 +467,20 @@ impl Stores {
                 });
             }
         }
+        for (store_id, store) in &self.blob_stores {
+            if let BlobBackend::Tiered(store) = &store.backend {
+                self.purge_schedules.push(PurgeSchedule {
+                    cron: config
+                        .property_or_default::<SimpleCron>(
+                            ("store", store_id.as_str(), "migrate.frequency"),
+                            "0 2 *",
+                        )
+                        .unwrap_or_else(|| SimpleCron::parse_value("0 2 *").unwrap()),
+                    store_id: store_id.clone(),
+                    store: PurgeStore::BlobTiers(store.clone()),
+                });
+            }
+        }
     }
 }
 
@@ -6,10 +6,12 @@
 
 use std::{borrow::Cow, ops::Range, time::Instant};
 
-use trc::{AddContext, StoreEvent};
+use trc::{ipc::metrics::StoreOperation, AddContext, StoreEvent};
 use utils::config::utils::ParseValue;
 
-use crate::{BlobBackend, BlobStore, CompressionAlgo, Store};
+use crate::{BlobBackend, BlobStore, CompressionAlgo, Store, SUBSPACE_BLOBS};
+
+use super::record_operation;
 
 impl BlobStore {
     pub async fn get_blob(&self, key: &[u8], range: Range<usize>) -> trc::Result<Option<Vec<u8>>> {
@@ -30,6 +32,8 @@ impl BlobStore {
                 Store::MySQL(store) => store.get_blob(key, read_range).await,
                 #[cfg(feature = "rocks")]
                 Store::RocksDb(store) => store.get_blob(key, read_range).await,
+                #[cfg(feature = "redb")]
+                Store::Redb(store) => store.get_blob(key, read_range).await,
                 #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
                 Store::SQLReadReplica(store) => store.get_blob(key, read_range).await,
                 Store::None => Err(trc::StoreEvent::NotConfigured.into()),
@@ -39,6 +43,7 @@ impl BlobStore {
             BlobBackend::S3(store) => store.get_blob(key, read_range).await,
             #[cfg(feature = "enterprise")]
             BlobBackend::Composite(store) => store.get_blob(key, read_range).await,
+            BlobBackend::Tiered(store) => store.get_blob(key, read_range).await,
         };
 
         trc::event!(
@@ -49,6 +54,14 @@ impl BlobStore {
                 .as_ref()
                 .map_or(0, |data| data.as_ref().map_or(0, |data| data.len())),
         );
+        record_operation(
+            self.backend.backend_id(),
+            StoreOperation::BlobGet,
+            start_time,
+            Some(SUBSPACE_BLOBS),
+            None,
+            result.is_err(),
+        );
 
         let decompressed = match self.compression {
             CompressionAlgo::Lz4 => match result.caused_by(trc::location!())? {
@@ -87,6 +100,37 @@ impl BlobStore {
         }
     }
 
+    /// Returns the size of a blob as stored by the backend, after compression,
+    /// without reading its contents.
+    pub async fn blob_size(&self, key: &[u8]) -> trc::Result<Option<usize>> {
+        match &self.backend {
+            BlobBackend::Store(store) => match store {
+                #[cfg(feature = "sqlite")]
+                Store::SQLite(store) => store.blob_size(key).await,
+                #[cfg(feature = "foundation")]
+                Store::FoundationDb(store) => store.blob_size(key).await,
+                #[cfg(feature = "postgres")]
+                Store::PostgreSQL(store) => store.blob_size(key).await,
+                #[cfg(feature = "mysql")]
+                Store::MySQL(store) => store.blob_size(key).await,
+                #[cfg(feature = "rocks")]
+                Store::RocksDb(store) => store.blob_size(key).await,
+                #[cfg(feature = "redb")]
+                Store::Redb(store) => store.blob_size(key).await,
+                #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
+                Store::SQLReadReplica(store) => store.blob_size(key).await,
+                Store::None => Err(trc::StoreEvent::NotConfigured.into()),
+            },
+            BlobBackend::Fs(store) => store.blob_size(key).await,
+            #[cfg(feature = "s3")]
+            BlobBackend::S3(store) => store.blob_size(key).await,
+            #[cfg(feature = "enterprise")]
+            BlobBackend::Composite(store) => store.blob_size(key).await,
+            BlobBackend::Tiered(store) => store.blob_size(key).await,
+        }
+        .caused_by(trc::location!())
+    }
+
     pub async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
         let data: Cow<[u8]> = match self.compression {
             CompressionAlgo::None => data.into(),
@@ -110,6 +154,8 @@ impl BlobStore {
                 Store::MySQL(store) => store.put_blob(key, data.as_ref()).await,
                 #[cfg(feature = "rocks")]
                 Store::RocksDb(store) => store.put_blob(key, data.as_ref()).await,
+                #[cfg(feature = "redb")]
+                Store::Redb(store) => store.put_blob(key, data.as_ref()).await,
                 #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
                 Store::SQLReadReplica(store) => store.put_blob(key, data.as_ref()).await,
                 Store::None => Err(trc::StoreEvent::NotConfigured.into()),
@@ -119,6 +165,7 @@ impl BlobStore {
             BlobBackend::S3(store) => store.put_blob(key, data.as_ref()).await,
             #[cfg(feature = "enterprise")]
             BlobBackend::Composite(store) => store.put_blob(key, data.as_ref()).await,
+            BlobBackend::Tiered(store) => store.put_blob(key, data.as_ref()).await,
         }
         .caused_by(trc::location!());
 
@@ -128,6 +175,14 @@ impl BlobStore {
             Elapsed = start_time.elapsed(),
             Size = data.len(),
         );
+        record_operation(
+            self.backend.backend_id(),
+            StoreOperation::BlobPut,
+            start_time,
+            Some(SUBSPACE_BLOBS),
+            None,
+            result.is_err(),
+        );
 
         result
     }
@@ -146,6 +201,8 @@ impl BlobStore {
                 Store::MySQL(store) => store.delete_blob(key).await,
                 #[cfg(feature = "rocks")]
                 Store::RocksDb(store) => store.delete_blob(key).await,
+                #[cfg(feature = "redb")]
+                Store::Redb(store) => store.delete_blob(key).await,
                 #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
                 Store::SQLReadReplica(store) => store.delete_blob(key).await,
                 Store::None => Err(trc::StoreEvent::NotConfigured.into()),
@@ -155,14 +212,23 @@ impl BlobStore {
             BlobBackend::S3(store) => store.delete_blob(key).await,
             #[cfg(feature = "enterprise")]
             BlobBackend::Composite(store) => store.delete_blob(key).await,
+            BlobBackend::Tiered(store) => store.delete_blob(key).await,
         }
         .caused_by(trc::location!());
 
         trc::event!(
-            Store(StoreEvent::BlobWrite),
+            Store(StoreEvent::BlobDelete),
             Key = key,
             Elapsed = start_time.elapsed(),
         );
+        record_operation(
+            self.backend.backend_id(),
+            StoreOperation::BlobDelete,
+            start_time,
+            Some(SUBSPACE_BLOBS),
+            None,
+            result.is_err(),
+        );
 
         result
     }
@@ -0,0 +1,378 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, LazyLock,
+    },
+    time::{Duration, Instant},
+};
+
+use ahash::{AHashMap, AHashSet, RandomState};
+use lru_cache::LruCache;
+use parking_lot::{Mutex, RwLock};
+use roaring::RoaringBitmap;
+use trc::StoreEvent;
+
+use crate::{
+    dispatch::BackendMap,
+    write::{AnyClass, Batch, Operation, ValueClass},
+    Deserialize, Store, SUBSPACE_BITMAP_ID, SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT,
+    SUBSPACE_PROPERTY, U32_LEN,
+};
+
+static STORE_CACHES: LazyLock<RwLock<BackendMap<Arc<StoreCache>>>> =
+    LazyLock::new(Default::default);
+static HAS_STORE_CACHES: AtomicBool = AtomicBool::new(false);
+
+// Accounts invalidated locally, pending broadcast to other cluster nodes
+static PENDING_INVALIDATIONS: Mutex<Option<PendingInvalidations>> = Mutex::new(None);
+
+/// In-memory, size-bounded read-through cache of property values and bitmaps.
+///
+/// Entries are invalidated per account: every write bumps the generation of
+/// the accounts it touches, and entries recorded under an older generation
+/// are treated as misses. Other cluster nodes learn about invalidations on
+/// the next gossip heartbeat. Gossip runs over UDP and may drop them, so
+/// clustered nodes require `cache.ttl` (5 seconds by default) which bounds how
+/// long a node can return values written by another node before the write.
+pub struct StoreCache {
+    inner: Mutex<CacheInner>,
+    max_size: usize,
+    ttl: Option<Duration>,
+}
+
+struct CacheInner {
+    entries: LruCache<Vec<u8>, CacheEntry, RandomState>,
+    generations: AHashMap<u32, u64>,
+    epoch: u64,
+    size: usize,
+}
+
+struct CacheEntry {
+    value: CachedValue,
+    size: usize,
+    generation: u64,
+    expires: Option<Instant>,
+}
+
+#[derive(Clone)]
+pub(crate) enum CachedValue {
+    Value(Option<Arc<[u8]>>),
+    Bitmap(Option<Arc<RoaringBitmap>>),
+}
+
+pub(crate) enum CacheLookup {
+    Hit(CachedValue),
+    Miss(CacheToken),
+}
+
+/// Snapshot of the account generation taken before reading from the backend,
+/// values read concurrently with a write are not cached.
+#[derive(Clone, Copy)]
+pub(crate) struct CacheToken {
+    epoch: u64,
+    generation: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheInvalidation {
+    Accounts(Vec<u32>),
+    All,
+}
+
+#[derive(Default)]
+struct PendingInvalidations {
+    account_ids: AHashSet<u32>,
+    all: bool,
+}
+
+pub(crate) struct RawValue(pub Vec<u8>);
+
+impl StoreCache {
+    pub fn new(max_size: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            inner: Mutex::new(CacheInner {
+                entries: LruCache::with_hasher(usize::MAX, RandomState::new()),
+                generations: AHashMap::new(),
+                epoch: 0,
+                size: 0,
+            }),
+            max_size,
+            ttl,
+        }
+    }
+
+    pub(crate) fn lookup(&self, key: &[u8], account_id: u32) -> CacheLookup {
+        let mut guard = self.inner.lock();
+        let inner = &mut *guard;
+        let generation = inner.generation(account_id);
+        let token = CacheToken {
+            epoch: inner.epoch,
+            generation,
+        };
+
+        match inner.entries.get_mut(key) {
+            Some(entry)
+                if entry.generation == generation
+                    && entry
+                        .expires
+                        .map_or(true, |expires| expires > Instant::now()) =>
+            {
+                CacheLookup::Hit(entry.value.clone())
+            }
+            Some(_) => {
+                // Stale entry
+                if let Some(entry) = inner.entries.remove(key) {
+                    inner.size -= entry.size;
+                }
+                CacheLookup::Miss(token)
+            }
+            None => CacheLookup::Miss(token),
+        }
+    }
+
+    pub(crate) fn insert(
+        &self,
+        key: Vec<u8>,
+        account_id: u32,
+        token: CacheToken,
+        value: CachedValue,
+    ) {
+        let size = key.len()
+            + match &value {
+                CachedValue::Value(value) => value.as_ref().map_or(0, |value| value.len()),
+                CachedValue::Bitmap(bitmap) => {
+                    bitmap.as_ref().map_or(0, |bitmap| bitmap.serialized_size())
+                }
+            };
+        if size > self.max_size {
+            return;
+        }
+
+        let mut guard = self.inner.lock();
+        let inner = &mut *guard;
+        if inner.epoch != token.epoch || inner.generation(account_id) != token.generation {
+            return;
+        }
+        if let Some(entry) = inner.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                size,
+                generation: token.generation,
+                expires: self.ttl.map(|ttl| Instant::now() + ttl),
+            },
+        ) {
+            inner.size -= entry.size;
+        }
+        inner.size += size;
+
+        while inner.size > self.max_size {
+            if let Some((_, entry)) = inner.entries.remove_lru() {
+                inner.size -= entry.size;
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn invalidate(&self, invalidation: &CacheInvalidation) {
+        let mut inner = self.inner.lock();
+        match invalidation {
+            CacheInvalidation::Accounts(account_ids) => {
+                for account_id in account_ids {
+                    *inner.generations.entry(*account_id).or_default() += 1;
+                }
+            }
+            CacheInvalidation::All => {
+                inner.entries.clear();
+                inner.size = 0;
+                inner.epoch += 1;
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.inner.lock().size
+    }
+}
+
+impl CacheInner {
+    fn generation(&self, account_id: u32) -> u64 {
+        self.generations
+            .get(&account_id)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl Store {
+    pub(crate) fn cache(&self) -> Option<Arc<StoreCache>> {
+        if HAS_STORE_CACHES.load(Ordering::Relaxed) {
+            STORE_CACHES.read().get(self.backend_id()).cloned()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the cache entries a batch will invalidate once written.
+    pub(crate) fn cache_invalidation(
+        &self,
+        batch: &Batch,
+    ) -> Option<(Arc<StoreCache>, CacheInvalidation)> {
+        let cache = self.cache()?;
+        let mut account_id = u32::MAX;
+        let mut account_ids = Vec::new();
+        for op in &batch.ops {
+            let changed_account_id = match op {
+                Operation::AccountId {
+                    account_id: account_id_,
+                } => {
+                    account_id = *account_id_;
+                    continue;
+                }
+                Operation::Value {
+                    class: ValueClass::Property(_),
+                    ..
+                }
+                | Operation::Bitmap { .. } => account_id,
+                Operation::Value {
+                    class: ValueClass::Any(AnyClass { subspace, key }),
+                    ..
+                } if is_cached_subspace(*subspace) => {
+                    if let Some(account_id) = key.get(0..U32_LEN) {
+                        u32::from_be_bytes(account_id.try_into().unwrap())
+                    } else {
+                        return Some((cache, CacheInvalidation::All));
+                    }
+                }
+                _ => continue,
+            };
+
+            if !account_ids.contains(&changed_account_id) {
+                account_ids.push(changed_account_id);
+            }
+        }
+
+        if !account_ids.is_empty() {
+            Some((cache, CacheInvalidation::Accounts(account_ids)))
+        } else {
+            None
+        }
+    }
+
+    /// Drops all cached entries, used after range deletions.
+    pub(crate) fn invalidate_cache_all(&self) {
+        if let Some(cache) = self.cache() {
+            invalidate_local(&cache, CacheInvalidation::All);
+        }
+    }
+}
+
+impl CachedValue {
+    pub(crate) fn into_value<U: Deserialize>(self) -> trc::Result<Option<U>> {
+        match self {
+            CachedValue::Value(Some(bytes)) => U::deserialize(&bytes).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn into_bitmap(self) -> Option<RoaringBitmap> {
+        match self {
+            CachedValue::Bitmap(Some(bitmap)) => {
+                Some(Arc::try_unwrap(bitmap).unwrap_or_else(|bitmap| bitmap.as_ref().clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Deserialize for RawValue {
+    fn deserialize(bytes: &[u8]) -> trc::Result<Self> {
+        Ok(RawValue(bytes.to_vec()))
+    }
+}
+
+pub(crate) fn is_cached_subspace(subspace: u8) -> bool {
+    matches!(
+        subspace,
+        SUBSPACE_PROPERTY | SUBSPACE_BITMAP_ID | SUBSPACE_BITMAP_TAG | SUBSPACE_BITMAP_TEXT
+    )
+}
+
+pub(crate) fn record_lookup(is_hit: bool, account_id: u32) {
+    if is_hit {
+        trc::event!(Store(StoreEvent::CacheHit), AccountId = account_id);
+    } else {
+        trc::event!(Store(StoreEvent::CacheMiss), AccountId = account_id);
+    }
+}
+
+/// Enables read-through caching for a store backend.
+pub(crate) fn register_cache(store: &Store, cache: Option<StoreCache>) {
+    if let Some(backend) = store.backend() {
+        let mut caches = STORE_CACHES.write();
+        if let Some(cache) = cache {
+            caches.insert(backend, Arc::new(cache));
+        } else {
+            caches.remove(store.backend_id());
+        }
+        HAS_STORE_CACHES.store(!caches.is_empty(), Ordering::Relaxed);
+    }
+}
+
+/// Starts collecting local invalidations so they can be broadcast to other
+/// cluster nodes.
+pub fn enable_cluster_invalidations() {
+    let mut pending = PENDING_INVALIDATIONS.lock();
+    if pending.is_none() {
+        *pending = Some(PendingInvalidations::default());
+    }
+}
+
+/// Returns the invalidations recorded since the last call.
+pub fn take_invalidations() -> Option<CacheInvalidation> {
+    let mut pending = PENDING_INVALIDATIONS.lock();
+    let pending = pending.as_mut()?;
+    if pending.all {
+        pending.all = false;
+        pending.account_ids.clear();
+        Some(CacheInvalidation::All)
+    } else if !pending.account_ids.is_empty() {
+        Some(CacheInvalidation::Accounts(
+            pending.account_ids.drain().collect(),
+        ))
+    } else {
+        None
+    }
+}
+
+/// Applies invalidations received from another cluster node.
+pub fn apply_invalidation(invalidation: &CacheInvalidation) {
+    if HAS_STORE_CACHES.load(Ordering::Relaxed) {
+        for cache in STORE_CACHES.read().values() {
+            cache.invalidate(invalidation);
+        }
+    }
+}
+
+/// Applies a local invalidation and queues it for broadcast.
+pub(crate) fn invalidate_local(cache: &StoreCache, invalidation: CacheInvalidation) {
+    cache.invalidate(&invalidation);
+
+    if let Some(pending) = PENDING_INVALIDATIONS.lock().as_mut() {
+        match invalidation {
+            CacheInvalidation::Accounts(account_ids) => {
+                pending.account_ids.extend(account_ids);
+            }
+            CacheInvalidation::All => {
+                pending.all = true;
+            }
+        }
+    }
+}
@@ -6,6 +6,7 @@
 
 use std::fmt::Display;
 
+use ahash::AHashMap;
 use roaring::RoaringBitmap;
 use trc::AddContext;
 
@@ -45,6 +46,28 @@ impl FtsStore {
         .caused_by(trc::location!())
     }
 
+    pub async fn query_ranked<T: Into<u8> + Display + Clone + std::fmt::Debug>(
+        &self,
+        account_id: u32,
+        collection: impl Into<u8>,
+        filters: Vec<FtsFilter<T>>,
+    ) -> trc::Result<AHashMap<u32, f64>> {
+        match self {
+            FtsStore::Store(store) => {
+                store
+                    .fts_query_ranked(account_id, collection, filters)
+                    .await
+            }
+            #[cfg(feature = "elastic")]
+            FtsStore::ElasticSearch(store) => {
+                store
+                    .fts_query_ranked(account_id, collection, filters)
+                    .await
+            }
+        }
+        .caused_by(trc::location!())
+    }
+
     pub async fn remove(
         &self,
         account_id: u32,
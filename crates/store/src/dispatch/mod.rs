@@ -4,11 +4,30 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, LazyLock, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use parking_lot::RwLock;
 use roaring::RoaringBitmap;
+use trc::{
+    ipc::metrics::{StoreMetrics, StoreOperation},
+    Collector,
+};
 
-use crate::Store;
+use crate::{
+    write::{now, Batch, BatchBuilder, Operation, ValueClass},
+    BlobBackend, Store, ValueKey,
+};
 
 pub mod blob;
+pub mod cache;
 pub mod fts;
 pub mod lookup;
 pub mod store;
@@ -26,11 +45,279 @@ impl Store {
             Self::MySQL(_) => "mysql",
             #[cfg(feature = "rocks")]
             Self::RocksDb(_) => "rocksdb",
+            #[cfg(feature = "redb")]
+            Self::Redb(_) => "redb",
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(_) => "read_replica",
             Self::None => "none",
         }
     }
+
+    /// Rejects or allows writes to this store, used while switching the server
+    /// over to a different data store. The state is persisted in the store so
+    /// other cluster nodes, and this node after a restart, pick it up when they
+    /// reload their settings.
+    pub async fn set_read_only(&self, read_only: bool) -> trc::Result<()> {
+        let mut batch = BatchBuilder::new();
+        if read_only {
+            batch.set(
+                ValueClass::Config(READ_ONLY_KEY.to_vec()),
+                now().to_string(),
+            );
+            self.write(batch.build()).await?;
+            self.mark_read_only(true);
+        } else {
+            self.mark_read_only(false);
+            batch.clear(ValueClass::Config(READ_ONLY_KEY.to_vec()));
+            self.write(batch.build()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads the read-only state persisted in the store.
+    pub async fn refresh_read_only(&self) -> trc::Result<bool> {
+        let read_only = self
+            .get_value::<String>(ValueKey::from(ValueClass::Config(READ_ONLY_KEY.to_vec())))
+            .await?
+            .is_some();
+        self.mark_read_only(read_only);
+        Ok(read_only)
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        HAS_READ_ONLY_STORES.load(Ordering::Relaxed)
+            && READ_ONLY_STORES.read().get(self.backend_id()).is_some()
+    }
+
+    pub(crate) fn assert_writable(&self) -> trc::Result<()> {
+        if self.is_read_only() {
+            Err(trc::StoreEvent::NotSupported
+                .into_err()
+                .details("Store is read-only")
+                .caused_by(trc::location!()))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Lookup keys, such as counters and rate limits, are not migrated and
+    // keep being written while the store is read-only
+    pub(crate) fn assert_writable_batch(&self, batch: &Batch) -> trc::Result<()> {
+        if batch.ops.iter().all(|op| {
+            matches!(
+                op,
+                Operation::AccountId { .. }
+                    | Operation::Collection { .. }
+                    | Operation::DocumentId { .. }
+                    | Operation::Value {
+                        class: ValueClass::Lookup(_),
+                        ..
+                    }
+                    | Operation::AssertValue {
+                        class: ValueClass::Lookup(_),
+                        ..
+                    }
+            )
+        }) {
+            Ok(())
+        } else {
+            self.assert_writable()
+        }
+    }
+
+    fn mark_read_only(&self, read_only: bool) {
+        if let Some(backend) = self.backend() {
+            let mut stores = READ_ONLY_STORES.write();
+            if read_only {
+                stores.insert(backend, ());
+            } else {
+                stores.remove(self.backend_id());
+            }
+            HAS_READ_ONLY_STORES.store(!stores.is_empty(), Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn backend(&self) -> Option<BackendRef> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::SQLite(store) => Some(Arc::downgrade(store) as BackendRef),
+            #[cfg(feature = "foundation")]
+            Self::FoundationDb(store) => Some(Arc::downgrade(store) as BackendRef),
+            #[cfg(feature = "postgres")]
+            Self::PostgreSQL(store) => Some(Arc::downgrade(store) as BackendRef),
+            #[cfg(feature = "mysql")]
+            Self::MySQL(store) => Some(Arc::downgrade(store) as BackendRef),
+            #[cfg(feature = "rocks")]
+            Self::RocksDb(store) => Some(Arc::downgrade(store) as BackendRef),
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => Some(Arc::downgrade(store) as BackendRef),
+            #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
+            Self::SQLReadReplica(store) => Some(Arc::downgrade(store) as BackendRef),
+            Self::None => None,
+        }
+    }
+
+    pub(crate) fn backend_id(&self) -> usize {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::SQLite(store) => Arc::as_ptr(store) as *const u8 as usize,
+            #[cfg(feature = "foundation")]
+            Self::FoundationDb(store) => Arc::as_ptr(store) as *const u8 as usize,
+            #[cfg(feature = "postgres")]
+            Self::PostgreSQL(store) => Arc::as_ptr(store) as *const u8 as usize,
+            #[cfg(feature = "mysql")]
+            Self::MySQL(store) => Arc::as_ptr(store) as *const u8 as usize,
+            #[cfg(feature = "rocks")]
+            Self::RocksDb(store) => Arc::as_ptr(store) as *const u8 as usize,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => Arc::as_ptr(store) as *const u8 as usize,
+            #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
+            Self::SQLReadReplica(store) => Arc::as_ptr(store) as *const u8 as usize,
+            Self::None => 0,
+        }
+    }
+}
+
+impl BlobBackend {
+    pub(crate) fn backend(&self) -> Option<BackendRef> {
+        match self {
+            BlobBackend::Store(store) => store.backend(),
+            BlobBackend::Fs(store) => Some(Arc::downgrade(store) as BackendRef),
+            #[cfg(feature = "s3")]
+            BlobBackend::S3(store) => Some(Arc::downgrade(store) as BackendRef),
+            #[cfg(feature = "enterprise")]
+            BlobBackend::Composite(store) => Some(Arc::downgrade(store) as BackendRef),
+            BlobBackend::Tiered(store) => Some(Arc::downgrade(store) as BackendRef),
+        }
+    }
+
+    pub(crate) fn backend_id(&self) -> usize {
+        match self {
+            BlobBackend::Store(store) => store.backend_id(),
+            BlobBackend::Fs(store) => Arc::as_ptr(store) as *const u8 as usize,
+            #[cfg(feature = "s3")]
+            BlobBackend::S3(store) => Arc::as_ptr(store) as *const u8 as usize,
+            #[cfg(feature = "enterprise")]
+            BlobBackend::Composite(store) => Arc::as_ptr(store) as *const u8 as usize,
+            BlobBackend::Tiered(store) => Arc::as_ptr(store) as *const u8 as usize,
+        }
+    }
+}
+
+/// Settings key of the marker that makes a store read-only, it is neither listed
+/// as a setting nor exported by backups.
+pub const READ_ONLY_KEY: &[u8] = b"storage.read-only";
+
+static READ_ONLY_STORES: LazyLock<RwLock<BackendMap<()>>> = LazyLock::new(Default::default);
+static HAS_READ_ONLY_STORES: AtomicBool = AtomicBool::new(false);
+
+static STORE_TELEMETRY: LazyLock<RwLock<BackendMap<Arc<StoreTelemetry>>>> =
+    LazyLock::new(Default::default);
+
+struct StoreTelemetry {
+    metrics: Arc<StoreMetrics>,
+    slow_threshold: Option<Duration>,
+}
+
+/// Labels the operations of a backend with its configured store id.
+pub(crate) fn register_telemetry(
+    backend: Option<BackendRef>,
+    store_id: &str,
+    slow_threshold: Option<Duration>,
+) {
+    if let Some(backend) = backend {
+        STORE_TELEMETRY.write().insert(
+            backend,
+            Arc::new(StoreTelemetry {
+                metrics: Collector::store_metrics(store_id),
+                slow_threshold,
+            }),
+        );
+    }
+}
+
+pub(crate) fn record_operation(
+    backend_id: usize,
+    operation: StoreOperation,
+    start_time: Instant,
+    subspace: Option<u8>,
+    account_id: Option<u32>,
+    is_error: bool,
+) {
+    let telemetry = if let Some(telemetry) = STORE_TELEMETRY.read().get(backend_id) {
+        telemetry.clone()
+    } else {
+        return;
+    };
+    let elapsed = start_time.elapsed();
+    telemetry
+        .metrics
+        .observe(operation, elapsed.as_millis() as u64, is_error);
+
+    if telemetry
+        .slow_threshold
+        .map_or(false, |threshold| elapsed >= threshold)
+    {
+        trc::event!(
+            Store(trc::StoreEvent::SlowOperation),
+            Id = telemetry.metrics.id().to_string(),
+            Type = operation.as_str(),
+            Details = subspace.map(|subspace| (subspace as char).to_string()),
+            AccountId = account_id,
+            Elapsed = elapsed,
+        );
+    }
+}
+
+pub(crate) type BackendRef = Weak<dyn Any + Send + Sync>;
+
+/// State kept for store backends, keyed by the address of the backend. Entries
+/// hold a weak reference to their backend so that its address can not be reused
+/// by another backend while the entry exists, entries of backends that have been
+/// dropped are removed on the next insertion or removal.
+pub(crate) struct BackendMap<T> {
+    entries: AHashMap<usize, (BackendRef, T)>,
+}
+
+impl<T> Default for BackendMap<T> {
+    fn default() -> Self {
+        Self {
+            entries: AHashMap::new(),
+        }
+    }
+}
+
+impl<T> BackendMap<T> {
+    pub fn get(&self, backend_id: usize) -> Option<&T> {
+        self.entries.get(&backend_id).map(|(_, value)| value)
+    }
+
+    pub fn insert(&mut self, backend: BackendRef, value: T) {
+        self.prune();
+        self.entries.insert(
+            Weak::as_ptr(&backend) as *const u8 as usize,
+            (backend, value),
+        );
+    }
+
+    pub fn remove(&mut self, backend_id: usize) -> Option<T> {
+        self.prune();
+        self.entries.remove(&backend_id).map(|(_, value)| value)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.entries.values().map(|(_, value)| value)
+    }
+
+    fn prune(&mut self) {
+        self.entries
+            .retain(|_, (backend, _)| backend.strong_count() > 0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
 }
 
 #[allow(clippy::len_without_is_empty)]
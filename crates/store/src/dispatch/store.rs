@@ -6,11 +6,12 @@
 
 use std::{
     ops::{BitAndAssign, Range},
+    sync::Arc,
     time::Instant,
 };
 
 use roaring::RoaringBitmap;
-use trc::{AddContext, StoreEvent};
+use trc::{ipc::metrics::StoreOperation, AddContext, StoreEvent};
 
 use crate::{
     write::{
@@ -19,10 +20,16 @@ use crate::{
         Operation, ReportClass, ValueClass, ValueOp,
     },
     BitmapKey, Deserialize, IterateParams, Key, Store, ValueKey, SUBSPACE_BITMAP_ID,
-    SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SUBSPACE_INDEXES, SUBSPACE_LOGS, U32_LEN,
+    SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SUBSPACE_FTS_TERM, SUBSPACE_INDEXES, SUBSPACE_LOGS,
+    SUBSPACE_PROPERTY, U32_LEN, WITH_SUBSPACE,
 };
 
-use super::DocumentSet;
+use super::{
+    cache::{
+        invalidate_local, is_cached_subspace, record_lookup, CacheLookup, CachedValue, RawValue,
+    },
+    record_operation, DocumentSet,
+};
 
 #[cfg(feature = "test_mode")]
 #[allow(clippy::type_complexity)]
@@ -39,7 +46,40 @@ impl Store {
     where
         U: Deserialize + 'static,
     {
-        match self {
+        if key.subspace() == SUBSPACE_PROPERTY {
+            if let (Some(cache), Some(account_id)) = (self.cache(), key.account_id()) {
+                let cache_key = key.serialize(WITH_SUBSPACE);
+                let value = match cache.lookup(&cache_key, account_id) {
+                    CacheLookup::Hit(value) => {
+                        record_lookup(true, account_id);
+                        value
+                    }
+                    CacheLookup::Miss(token) => {
+                        record_lookup(false, account_id);
+                        let value = CachedValue::Value(
+                            self.get_value_uncached::<RawValue>(key)
+                                .await?
+                                .map(|value| value.0.into()),
+                        );
+                        cache.insert(cache_key, account_id, token, value.clone());
+                        value
+                    }
+                };
+
+                return value.into_value().caused_by(trc::location!());
+            }
+        }
+
+        self.get_value_uncached(key).await
+    }
+
+    async fn get_value_uncached<U>(&self, key: impl Key) -> trc::Result<Option<U>>
+    where
+        U: Deserialize + 'static,
+    {
+        let start_time = Instant::now();
+        let (subspace, account_id) = (key.subspace(), key.account_id());
+        let result = match self {
             #[cfg(feature = "sqlite")]
             Self::SQLite(store) => store.get_value(key).await,
             #[cfg(feature = "foundation")]
@@ -50,18 +90,62 @@ impl Store {
             Self::MySQL(store) => store.get_value(key).await,
             #[cfg(feature = "rocks")]
             Self::RocksDb(store) => store.get_value(key).await,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => store.get_value(key).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.get_value(key).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
-        .caused_by(trc::location!())
+        .caused_by(trc::location!());
+
+        record_operation(
+            self.backend_id(),
+            StoreOperation::GetValue,
+            start_time,
+            Some(subspace),
+            account_id,
+            result.is_err(),
+        );
+
+        result
     }
 
     pub async fn get_bitmap(
         &self,
         key: BitmapKey<BitmapClass<u32>>,
     ) -> trc::Result<Option<RoaringBitmap>> {
-        match self {
+        if let Some(cache) = self.cache() {
+            let account_id = key.account_id;
+            let cache_key = key.serialize(WITH_SUBSPACE);
+            return match cache.lookup(&cache_key, account_id) {
+                CacheLookup::Hit(value) => {
+                    record_lookup(true, account_id);
+                    Ok(value.into_bitmap())
+                }
+                CacheLookup::Miss(token) => {
+                    record_lookup(false, account_id);
+                    let bitmap = self.get_bitmap_uncached(key).await?;
+                    cache.insert(
+                        cache_key,
+                        account_id,
+                        token,
+                        CachedValue::Bitmap(bitmap.clone().map(Arc::new)),
+                    );
+                    Ok(bitmap)
+                }
+            };
+        }
+
+        self.get_bitmap_uncached(key).await
+    }
+
+    async fn get_bitmap_uncached(
+        &self,
+        key: BitmapKey<BitmapClass<u32>>,
+    ) -> trc::Result<Option<RoaringBitmap>> {
+        let start_time = Instant::now();
+        let (subspace, account_id) = (key.subspace(), key.account_id());
+        let result = match self {
             #[cfg(feature = "sqlite")]
             Self::SQLite(store) => store.get_bitmap(key).await,
             #[cfg(feature = "foundation")]
@@ -72,11 +156,24 @@ impl Store {
             Self::MySQL(store) => store.get_bitmap(key).await,
             #[cfg(feature = "rocks")]
             Self::RocksDb(store) => store.get_bitmap(key).await,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => store.get_bitmap(key).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.get_bitmap(key).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
-        .caused_by(trc::location!())
+        .caused_by(trc::location!());
+
+        record_operation(
+            self.backend_id(),
+            StoreOperation::GetBitmap,
+            start_time,
+            Some(subspace),
+            account_id,
+            result.is_err(),
+        );
+
+        result
     }
 
     pub async fn get_bitmaps_intersection(
@@ -107,6 +204,7 @@ impl Store {
         cb: impl for<'x> FnMut(&'x [u8], &'x [u8]) -> trc::Result<bool> + Sync + Send,
     ) -> trc::Result<()> {
         let start_time = Instant::now();
+        let (subspace, account_id) = (params.begin.subspace(), params.begin.account_id());
         let result = match self {
             #[cfg(feature = "sqlite")]
             Self::SQLite(store) => store.iterate(params, cb).await,
@@ -118,6 +216,8 @@ impl Store {
             Self::MySQL(store) => store.iterate(params, cb).await,
             #[cfg(feature = "rocks")]
             Self::RocksDb(store) => store.iterate(params, cb).await,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => store.iterate(params, cb).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.iterate(params, cb).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
@@ -128,6 +228,14 @@ impl Store {
             Store(StoreEvent::DataIterate),
             Elapsed = start_time.elapsed(),
         );
+        record_operation(
+            self.backend_id(),
+            StoreOperation::Iterate,
+            start_time,
+            Some(subspace),
+            account_id,
+            result.is_err(),
+        );
 
         result
     }
@@ -136,7 +244,10 @@ impl Store {
         &self,
         key: impl Into<ValueKey<ValueClass<u32>>> + Sync + Send,
     ) -> trc::Result<i64> {
-        match self {
+        let start_time = Instant::now();
+        let key = key.into();
+        let (subspace, account_id) = (key.subspace(), key.account_id());
+        let result = match self {
             #[cfg(feature = "sqlite")]
             Self::SQLite(store) => store.get_counter(key).await,
             #[cfg(feature = "foundation")]
@@ -147,14 +258,31 @@ impl Store {
             Self::MySQL(store) => store.get_counter(key).await,
             #[cfg(feature = "rocks")]
             Self::RocksDb(store) => store.get_counter(key).await,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => store.get_counter(key).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.get_counter(key).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
-        .caused_by(trc::location!())
+        .caused_by(trc::location!());
+
+        record_operation(
+            self.backend_id(),
+            StoreOperation::GetCounter,
+            start_time,
+            Some(subspace),
+            account_id,
+            result.is_err(),
+        );
+
+        result
     }
 
     pub async fn write(&self, batch: Batch) -> trc::Result<AssignedIds> {
+        self.assert_writable_batch(&batch)?;
+
+        let invalidation = self.cache_invalidation(&batch);
+
         #[cfg(feature = "test_mode")]
         if std::env::var("PARANOID_WRITE").map_or(false, |v| v == "1") {
             let mut account_id = u32::MAX;
@@ -212,12 +340,18 @@ impl Store {
                 Self::MySQL(store) => store.write(batch).await,
                 #[cfg(feature = "rocks")]
                 Self::RocksDb(store) => store.write(batch).await,
+                #[cfg(feature = "redb")]
+                Self::Redb(store) => store.write(batch).await,
                 #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
                 Self::SQLReadReplica(store) => store.write(batch).await,
                 Self::None => Err(trc::StoreEvent::NotConfigured.into()),
             }
             .caused_by(trc::location!())?;
 
+            if let Some((cache, invalidation)) = invalidation {
+                invalidate_local(&cache, invalidation);
+            }
+
             for (key, class, document_id, set) in bitmaps {
                 let mut bitmaps = BITMAPS.lock();
                 let map = bitmaps.entry(key).or_default();
@@ -247,6 +381,7 @@ impl Store {
 
         let start_time = Instant::now();
         let ops = batch.ops.len();
+        let account_id = batch.first_account_id();
 
         let result = match self {
             #[cfg(feature = "sqlite")]
@@ -259,21 +394,38 @@ impl Store {
             Self::MySQL(store) => store.write(batch).await,
             #[cfg(feature = "rocks")]
             Self::RocksDb(store) => store.write(batch).await,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => store.write(batch).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.write(batch).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         };
 
+        // Invalidate even on failure, non-atomic batches may be partially applied
+        if let Some((cache, invalidation)) = invalidation {
+            invalidate_local(&cache, invalidation);
+        }
+
         trc::event!(
             Store(StoreEvent::DataWrite),
             Elapsed = start_time.elapsed(),
             Total = ops,
         );
+        record_operation(
+            self.backend_id(),
+            StoreOperation::Write,
+            start_time,
+            None,
+            account_id,
+            result.is_err(),
+        );
 
         result
     }
 
     pub async fn purge_store(&self) -> trc::Result<()> {
+        self.assert_writable()?;
+
         // Delete expired reports
         let now = now();
         self.delete_range(
@@ -315,6 +467,8 @@ impl Store {
             Self::MySQL(store) => store.purge_store().await,
             #[cfg(feature = "rocks")]
             Self::RocksDb(store) => store.purge_store().await,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => store.purge_store().await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.purge_store().await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
@@ -323,7 +477,10 @@ impl Store {
     }
 
     pub async fn delete_range(&self, from: impl Key, to: impl Key) -> trc::Result<()> {
-        match self {
+        self.assert_writable()?;
+
+        let invalidate_cache = is_cached_subspace(from.subspace());
+        let result = match self {
             #[cfg(feature = "sqlite")]
             Self::SQLite(store) => store.delete_range(from, to).await,
             #[cfg(feature = "foundation")]
@@ -334,11 +491,19 @@ impl Store {
             Self::MySQL(store) => store.delete_range(from, to).await,
             #[cfg(feature = "rocks")]
             Self::RocksDb(store) => store.delete_range(from, to).await,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => store.delete_range(from, to).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.delete_range(from, to).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         }
-        .caused_by(trc::location!())
+        .caused_by(trc::location!());
+
+        if invalidate_cache {
+            self.invalidate_cache_all();
+        }
+
+        result
     }
 
     pub async fn delete_documents(
@@ -427,6 +592,7 @@ impl Store {
             SUBSPACE_BITMAP_TEXT,
             SUBSPACE_LOGS,
             SUBSPACE_INDEXES,
+            SUBSPACE_FTS_TERM,
         ] {
             self.delete_range(
                 AnyKey {
@@ -507,6 +673,8 @@ impl Store {
             Self::MySQL(store) => store.get_blob(key, range).await,
             #[cfg(feature = "rocks")]
             Self::RocksDb(store) => store.get_blob(key, range).await,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => store.get_blob(key, range).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.get_blob(key, range).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
@@ -515,6 +683,8 @@ impl Store {
     }
 
     pub async fn put_blob(&self, key: &[u8], data: &[u8]) -> trc::Result<()> {
+        self.assert_writable()?;
+
         match self {
             #[cfg(feature = "sqlite")]
             Self::SQLite(store) => store.put_blob(key, data).await,
@@ -526,6 +696,8 @@ impl Store {
             Self::MySQL(store) => store.put_blob(key, data).await,
             #[cfg(feature = "rocks")]
             Self::RocksDb(store) => store.put_blob(key, data).await,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => store.put_blob(key, data).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.put_blob(key, data).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
@@ -534,6 +706,8 @@ impl Store {
     }
 
     pub async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
+        self.assert_writable()?;
+
         match self {
             #[cfg(feature = "sqlite")]
             Self::SQLite(store) => store.delete_blob(key).await,
@@ -545,6 +719,8 @@ impl Store {
             Self::MySQL(store) => store.delete_blob(key).await,
             #[cfg(feature = "rocks")]
             Self::RocksDb(store) => store.delete_blob(key).await,
+            #[cfg(feature = "redb")]
+            Self::Redb(store) => store.delete_blob(key).await,
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(store) => store.delete_blob(key).await,
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
@@ -581,6 +757,8 @@ impl Store {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_SNOOZE_EVENT,
+            SUBSPACE_FTS_TERM,
         ] {
             self.delete_range(
                 AnyKey {
@@ -766,6 +944,8 @@ impl Store {
             (SUBSPACE_TELEMETRY_SPAN, true),
             (SUBSPACE_TELEMETRY_METRIC, true),
             (SUBSPACE_TELEMETRY_INDEX, true),
+            (SUBSPACE_SNOOZE_EVENT, true),
+            (SUBSPACE_FTS_TERM, false),
         ] {
             let from_key = crate::write::AnyKey {
                 subspace,
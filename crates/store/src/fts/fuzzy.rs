@@ -0,0 +1,190 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use ahash::AHashMap;
+use trc::AddContext;
+
+use crate::{write::ValueClass, IterateParams, Store, ValueKey, U32_LEN};
+
+// Terms longer than 8 bytes are hashed in the full-text index, the
+// per-document dictionary keeps their original form so they can be
+// removed from the account dictionary once the document is deleted.
+// An empty term marks documents that were added to the account dictionary.
+pub(crate) const TERM_DICTIONARY_FIELD: u8 = u8::MAX;
+pub(crate) const TERM_DICTIONARY_MIN_LEN: usize = 9;
+
+const MAX_FUZZY_EXPANSIONS: usize = 50;
+const MAX_FUZZY_SCANNED_TERMS: usize = 10_000;
+
+impl Store {
+    /// Returns the indexed terms within the allowed edit distance of `word`.
+    ///
+    /// Candidates are read from the account term dictionary, which holds one
+    /// reference counted key per distinct term. Documents indexed before the
+    /// dictionary existed are not part of it until the account is reindexed.
+    pub(crate) async fn fts_fuzzy_terms(
+        &self,
+        account_id: u32,
+        collection: u8,
+        word: &str,
+    ) -> trc::Result<Vec<(String, u32)>> {
+        // Candidates must share the first byte with the searched word
+        let max_distance = max_edit_distance(word);
+        let prefix = match word.as_bytes().first() {
+            Some(prefix) if max_distance > 0 && *prefix < u8::MAX => *prefix,
+            _ => return Ok(vec![]),
+        };
+        let word = word.chars().collect::<Vec<_>>();
+        let mut terms: AHashMap<String, u32> = AHashMap::new();
+        let mut scanned = 0;
+        let term_offset = U32_LEN + 1;
+
+        self.iterate(
+            IterateParams::new(
+                term_counter_key(account_id, collection, vec![prefix]),
+                term_counter_key(account_id, collection, vec![prefix + 1]),
+            )
+            .no_values(),
+            |key, _| {
+                let term = key
+                    .get(term_offset..)
+                    .ok_or_else(|| trc::Error::corrupted_key(key, None, trc::location!()))?;
+                add_fuzzy_term(&mut terms, &word, term, max_distance);
+                scanned += 1;
+
+                Ok(scanned < MAX_FUZZY_SCANNED_TERMS)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+        // Terms no longer referenced by any document are purged lazily
+        let mut matches = Vec::with_capacity(terms.len());
+        for (term, distance) in terms {
+            if self
+                .get_counter(term_counter_key(
+                    account_id,
+                    collection,
+                    term.as_bytes().to_vec(),
+                ))
+                .await
+                .caused_by(trc::location!())?
+                > 0
+            {
+                matches.push((term, distance));
+            }
+        }
+
+        // Keep the closest matches
+        matches.sort_unstable_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        matches.truncate(MAX_FUZZY_EXPANSIONS);
+
+        Ok(matches)
+    }
+}
+
+/// Reference counter of a term in the account dictionary, keyed by
+/// account, collection and the term itself.
+fn term_counter_key(account_id: u32, collection: u8, term: Vec<u8>) -> ValueKey<ValueClass<u32>> {
+    ValueKey {
+        account_id,
+        collection,
+        document_id: 0,
+        class: ValueClass::FtsTerm(term),
+    }
+}
+
+fn add_fuzzy_term(
+    terms: &mut AHashMap<String, u32>,
+    word: &[char],
+    term: &[u8],
+    max_distance: u32,
+) {
+    if let Ok(term) = std::str::from_utf8(term) {
+        if !terms.contains_key(term) {
+            if let Some(distance) =
+                edit_distance(word, &term.chars().collect::<Vec<_>>(), max_distance)
+                    .filter(|distance| *distance > 0)
+            {
+                terms.insert(term.to_string(), distance);
+            }
+        }
+    }
+}
+
+fn max_edit_distance(word: &str) -> u32 {
+    match word.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+// Optimal string alignment distance, gives up once the distance exceeds the limit
+fn edit_distance(a: &[char], b: &[char], max_distance: u32) -> Option<u32> {
+    if a.len().abs_diff(b.len()) > max_distance as usize {
+        return None;
+    }
+
+    let mut prev_prev = vec![0u32; b.len() + 1];
+    let mut prev = (0..=b.len() as u32).collect::<Vec<_>>();
+    let mut current = vec![0u32; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i as u32;
+        let mut row_min = current[0];
+
+        for j in 1..=b.len() {
+            let cost = u32::from(a[i - 1] != b[j - 1]);
+            let mut distance = (prev[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(prev_prev[j - 2] + 1);
+            }
+            current[j] = distance;
+            row_min = row_min.min(distance);
+        }
+
+        if row_min > max_distance {
+            return None;
+        }
+
+        std::mem::swap(&mut prev_prev, &mut prev);
+        std::mem::swap(&mut prev, &mut current);
+    }
+
+    Some(prev[b.len()]).filter(|distance| *distance <= max_distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, max_edit_distance};
+
+    #[test]
+    fn fuzzy_edit_distance() {
+        for (a, b, max_distance, expected) in [
+            ("receive", "receive", 2, Some(0)),
+            ("recieve", "receive", 2, Some(1)),
+            ("recive", "receive", 2, Some(1)),
+            ("receeve", "receive", 2, Some(1)),
+            ("reciev", "receive", 2, Some(2)),
+            ("rcv", "receive", 2, None),
+            ("meeting", "meating", 1, Some(1)),
+            ("meeting", "mating", 1, None),
+            ("año", "ano", 1, Some(1)),
+            ("", "abc", 3, Some(3)),
+        ] {
+            let a = a.chars().collect::<Vec<_>>();
+            let b = b.chars().collect::<Vec<_>>();
+            assert_eq!(edit_distance(&a, &b, max_distance), expected, "{a:?} {b:?}");
+        }
+
+        for (word, expected) in [("it", 0), ("cat", 1), ("house", 1), ("meeting", 2)] {
+            assert_eq!(max_edit_distance(word), expected, "{word}");
+        }
+    }
+}
@@ -6,7 +6,7 @@
 
 use std::{borrow::Cow, fmt::Display};
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use nlp::{
     language::{
         detect::{LanguageDetector, MIN_LANGUAGE_SCORE},
@@ -24,10 +24,14 @@ use crate::{
         hash::TokenType, key::DeserializeBigEndian, BatchBuilder, BitmapHash, MaybeDynamicId,
         Operation, ValueClass, ValueOp,
     },
-    IterateParams, Serialize, Store, ValueKey, U32_LEN,
+    IndexKey, IterateParams, Serialize, Store, ValueKey, U32_LEN,
 };
 
-use super::{postings::Postings, Field};
+use super::{
+    fuzzy::{TERM_DICTIONARY_FIELD, TERM_DICTIONARY_MIN_LEN},
+    postings::Postings,
+    Field,
+};
 pub const TERM_INDEX_VERSION: u8 = 1;
 
 #[derive(Debug)]
@@ -125,6 +129,7 @@ impl Store {
     ) -> trc::Result<()> {
         let mut detect = LanguageDetector::new();
         let mut tokens: AHashMap<BitmapHash, Postings> = AHashMap::new();
+        let mut terms = AHashSet::new();
         let mut parts = Vec::new();
         let mut position = 0;
 
@@ -145,6 +150,9 @@ impl Store {
                             .entry(BitmapHash::new(token.word.as_ref()))
                             .or_default()
                             .insert(TokenType::word(field), position);
+                        if token.word.len() >= TERM_DICTIONARY_MIN_LEN {
+                            terms.insert(token.word.into_owned());
+                        }
                         position += 1;
                     }
                     position += 10;
@@ -187,6 +195,10 @@ impl Store {
                         .insert_keyword(TokenType::stemmed(field));
                 }
 
+                if token.word.len() >= TERM_DICTIONARY_MIN_LEN {
+                    terms.insert(token.word.into_owned());
+                }
+
                 position += 1;
             }
 
@@ -197,14 +209,53 @@ impl Store {
             return Ok(());
         }
 
+        // Documents that are indexed again (i.e. reindexing) are already
+        // accounted for in the term dictionary
+        let is_counted = self
+            .has_term_marker(
+                document.account_id,
+                document.collection,
+                document.document_id,
+            )
+            .await
+            .caused_by(trc::location!())?;
+
         // Serialize keys
-        let mut keys = Vec::with_capacity(tokens.len());
+        let mut keys = Vec::with_capacity(tokens.len() * 2 + terms.len() + 1);
+        let mut counters = Vec::new();
         for (hash, postings) in tokens.into_iter() {
+            if !is_counted && (1..=8).contains(&hash.len) {
+                counters.push(hash.hash[..hash.len as usize].to_vec());
+            }
             keys.push(Operation::Value {
                 class: ValueClass::FtsIndex(hash),
                 op: ValueOp::Set(postings.serialize().into()),
             });
         }
+        for term in terms {
+            let term = term.into_bytes();
+            if !is_counted {
+                counters.push(term.clone());
+            }
+            keys.push(Operation::Index {
+                field: TERM_DICTIONARY_FIELD,
+                key: term,
+                set: true,
+            });
+        }
+        if !is_counted {
+            for term in counters {
+                keys.push(Operation::Value {
+                    class: ValueClass::FtsTerm(term),
+                    op: ValueOp::AtomicAdd(1),
+                });
+            }
+            keys.push(Operation::Index {
+                field: TERM_DICTIONARY_FIELD,
+                key: vec![],
+                set: true,
+            });
+        }
 
         // Commit index
         let mut batch = BatchBuilder::new();
@@ -240,6 +291,7 @@ impl Store {
     ) -> trc::Result<()> {
         // Find keys to delete
         let mut delete_keys: AHashMap<u32, Vec<ValueClass<MaybeDynamicId>>> = AHashMap::new();
+        let mut delete_counters: AHashMap<u32, Vec<Vec<u8>>> = AHashMap::new();
         self.iterate(
             IterateParams::new(
                 ValueKey {
@@ -286,6 +338,12 @@ impl Store {
                         }
                     };
 
+                    if (1..=8).contains(&len) {
+                        delete_counters
+                            .entry(document_id)
+                            .or_default()
+                            .push(hash[..len as usize].to_vec());
+                    }
                     delete_keys
                         .entry(document_id)
                         .or_default()
@@ -298,7 +356,44 @@ impl Store {
         .await
         .caused_by(trc::location!())?;
 
-        // Remove keys
+        // Find dictionary terms to delete
+        let mut delete_terms: AHashMap<u32, Vec<Vec<u8>>> = AHashMap::new();
+        self.iterate(
+            IterateParams::new(
+                IndexKey {
+                    account_id,
+                    collection,
+                    document_id: 0,
+                    field: TERM_DICTIONARY_FIELD,
+                    key: &b""[..],
+                },
+                IndexKey {
+                    account_id,
+                    collection,
+                    document_id: u32::MAX,
+                    field: TERM_DICTIONARY_FIELD,
+                    key: &[u8::MAX][..],
+                },
+            )
+            .no_values(),
+            |key, _| {
+                let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+                if document_ids.contains(document_id) {
+                    delete_terms.entry(document_id).or_default().push(
+                        key.get(U32_LEN + 2..key.len() - U32_LEN)
+                            .ok_or_else(|| trc::Error::corrupted_key(key, None, trc::location!()))?
+                            .to_vec(),
+                    );
+                }
+
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+        // Remove keys, only documents carrying the marker were added to the
+        // term dictionary
         let mut batch = BatchBuilder::new();
         batch
             .with_account_id(account_id)
@@ -307,7 +402,33 @@ impl Store {
         for (document_id, keys) in delete_keys {
             batch.update_document(document_id);
 
-            for key in keys {
+            let terms = delete_terms.remove(&document_id).unwrap_or_default();
+            let counters = if terms.iter().any(|term| term.is_empty()) {
+                delete_counters
+                    .remove(&document_id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .chain(terms.iter().filter(|term| !term.is_empty()).cloned())
+                    .collect::<Vec<_>>()
+            } else {
+                vec![]
+            };
+            for op in keys
+                .into_iter()
+                .map(|key| Operation::Value {
+                    class: key,
+                    op: ValueOp::Clear,
+                })
+                .chain(terms.into_iter().map(|term| Operation::Index {
+                    field: TERM_DICTIONARY_FIELD,
+                    key: term,
+                    set: false,
+                }))
+                .chain(counters.into_iter().map(|term| Operation::Value {
+                    class: ValueClass::FtsTerm(term),
+                    op: ValueOp::AtomicAdd(-1),
+                }))
+            {
                 if batch.ops.len() >= 1000 {
                     self.write(batch.build()).await?;
                     batch = BatchBuilder::new();
@@ -316,10 +437,7 @@ impl Store {
                         .with_collection(collection)
                         .update_document(document_id);
                 }
-                batch.ops.push(Operation::Value {
-                    class: key,
-                    op: ValueOp::Clear,
-                });
+                batch.ops.push(op);
             }
         }
 
@@ -330,6 +448,33 @@ impl Store {
         Ok(())
     }
 
+    async fn has_term_marker(
+        &self,
+        account_id: u32,
+        collection: u8,
+        document_id: u32,
+    ) -> trc::Result<bool> {
+        let key = IndexKey {
+            account_id,
+            collection,
+            document_id,
+            field: TERM_DICTIONARY_FIELD,
+            key: &b""[..],
+        };
+        let mut found = false;
+        self.iterate(
+            IterateParams::new(key.clone(), key)
+                .no_values()
+                .only_first(),
+            |_, _| {
+                found = true;
+                Ok(false)
+            },
+        )
+        .await
+        .map(|_| found)
+    }
+
     pub async fn fts_remove_all(&self, _: u32) -> trc::Result<()> {
         // No-op
         // Term indexes are stored in the same key range as the document
@@ -8,6 +8,7 @@ use std::fmt::Display;
 
 use nlp::language::Language;
 
+pub mod fuzzy;
 pub mod index;
 pub mod postings;
 pub mod query;
@@ -36,6 +37,11 @@ pub enum FtsFilter<T: Into<u8> + Display + Clone + std::fmt::Debug> {
         field: Field<T>,
         text: String,
     },
+    Fuzzy {
+        field: Field<T>,
+        text: String,
+        language: Language,
+    },
     And,
     Or,
     Not,
@@ -89,6 +95,22 @@ impl<T: Into<u8> + Display + Clone + std::fmt::Debug> FtsFilter<T> {
     pub fn has_english_text(field: Field<T>, text: impl Into<String>) -> Self {
         Self::has_text(field, text, Language::English)
     }
+
+    // Quoted phrases and keywords are always matched exactly
+    pub fn into_fuzzy(self) -> Self {
+        match self {
+            FtsFilter::Contains {
+                field,
+                text,
+                language,
+            } => FtsFilter::Fuzzy {
+                field,
+                text,
+                language,
+            },
+            filter => filter,
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
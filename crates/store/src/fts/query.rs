@@ -9,7 +9,7 @@ use std::{
     ops::{BitAndAssign, BitOrAssign, BitXorAssign},
 };
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use nlp::language::stemmer::Stemmer;
 use roaring::RoaringBitmap;
 use trc::AddContext;
@@ -25,23 +25,25 @@ use crate::{
 
 use super::postings::SerializedPostings;
 
+// BM25 term frequency saturation
+const BM25_K1: f64 = 1.2;
+// Weight of stemmed and fuzzy term variants relative to exact matches
+const STEMMED_WEIGHT: f64 = 0.8;
+// Score added when two consecutive query terms appear next to each other
+const PROXIMITY_WEIGHT: f64 = 1.0;
+
+// Query terms in order, each one with its alternative forms and their weights
+type RankedPhrase = Vec<Vec<(BitmapHash, u8, f64)>>;
+
 struct State {
     pub op: FtsTokenized,
     pub bm: Option<RoaringBitmap>,
 }
 
 enum FtsTokenized {
-    Exact {
-        tokens: Vec<(BitmapHash, u8)>,
-    },
-    Contains {
-        field: u8,
-        tokens: Vec<(BitmapHash, Option<BitmapHash>)>,
-    },
-    Keyword {
-        field: u8,
-        token: BitmapHash,
-    },
+    Exact { tokens: Vec<(BitmapHash, u8)> },
+    Contains { tokens: Vec<Vec<(BitmapHash, u8)>> },
+    Keyword { field: u8, token: BitmapHash },
     And,
     Or,
     Not,
@@ -55,12 +57,42 @@ impl Store {
         collection: impl Into<u8>,
         filters: Vec<FtsFilter<T>>,
     ) -> trc::Result<RoaringBitmap> {
+        self.fts_query_(account_id, collection.into(), filters, None)
+            .await
+    }
+
+    pub async fn fts_query_ranked<T: Into<u8> + Display + Clone + std::fmt::Debug>(
+        &self,
+        account_id: u32,
+        collection: impl Into<u8>,
+        filters: Vec<FtsFilter<T>>,
+    ) -> trc::Result<AHashMap<u32, f64>> {
         let collection = collection.into();
+        let mut phrases = Vec::new();
+        let results = self
+            .fts_query_(account_id, collection, filters, Some(&mut phrases))
+            .await?;
+
+        self.fts_rank(account_id, collection, phrases, results)
+            .await
+    }
 
+    async fn fts_query_<T: Into<u8> + Display + Clone + std::fmt::Debug>(
+        &self,
+        account_id: u32,
+        collection: u8,
+        filters: Vec<FtsFilter<T>>,
+        mut ranked_phrases: Option<&mut Vec<RankedPhrase>>,
+    ) -> trc::Result<RoaringBitmap> {
         // Tokenize text
         let mut tokenized_filters = Vec::with_capacity(filters.len());
         let mut token_count = AHashMap::new();
+        let mut negations = Vec::new();
         for filter in filters {
+            // Terms under a negation do not contribute to the ranking
+            let mut phrase =
+                (ranked_phrases.is_some() && !negations.contains(&true)).then(RankedPhrase::new);
+
             let filter = match filter {
                 FtsFilter::Exact {
                     field,
@@ -74,6 +106,9 @@ impl Store {
                         let hash = BitmapHash::new(token.word.as_ref());
                         token_count.entry(hash).and_modify(|c| *c += 1).or_insert(1);
                         tokens.push((hash, field));
+                        if let Some(phrase) = &mut phrase {
+                            phrase.push(vec![(hash, field, 1.0)]);
+                        }
                     }
                     FtsTokenized::Exact { tokens }
                 }
@@ -82,6 +117,7 @@ impl Store {
                     text,
                     language,
                 } => {
+                    let field = field.into();
                     let mut tokens = Vec::new();
                     for token in Stemmer::new(text.as_ref(), language, MAX_TOKEN_LENGTH) {
                         let hash = BitmapHash::new(token.word.as_ref());
@@ -95,28 +131,109 @@ impl Store {
                                 .or_insert(1);
                         }
 
-                        tokens.push((hash, stemmed_hash));
+                        let alternatives = vec![
+                            (hash, TokenType::word(field), 1.0),
+                            (
+                                stemmed_hash.unwrap_or(hash),
+                                TokenType::stemmed(field),
+                                STEMMED_WEIGHT,
+                            ),
+                        ];
+                        tokens.push(unweighted(&alternatives));
+                        if let Some(phrase) = &mut phrase {
+                            phrase.push(alternatives);
+                        }
                     }
-                    FtsTokenized::Contains {
-                        field: field.into(),
-                        tokens,
+                    FtsTokenized::Contains { tokens }
+                }
+                FtsFilter::Fuzzy {
+                    field,
+                    text,
+                    language,
+                } => {
+                    let field = field.into();
+                    let words = Stemmer::new(text.as_ref(), language, MAX_TOKEN_LENGTH)
+                        .map(|token| {
+                            (
+                                token.word.into_owned(),
+                                token.stemmed_word.map(|word| word.into_owned()),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let mut tokens = Vec::with_capacity(words.len());
+
+                    for (word, stemmed_word) in words {
+                        let hash = BitmapHash::new(&word);
+                        let stemmed_hash = stemmed_word.as_deref().map(BitmapHash::new);
+                        let mut alternatives = vec![
+                            (hash, TokenType::word(field), 1.0),
+                            (
+                                stemmed_hash.unwrap_or(hash),
+                                TokenType::stemmed(field),
+                                STEMMED_WEIGHT,
+                            ),
+                        ];
+
+                        // Expand the word to similar indexed terms
+                        let mut seen_hashes = AHashSet::from([hash]);
+                        seen_hashes.extend(stemmed_hash);
+                        for (term, distance) in
+                            self.fts_fuzzy_terms(account_id, collection, &word).await?
+                        {
+                            let term_hash = BitmapHash::new(&term);
+                            if seen_hashes.insert(term_hash) {
+                                alternatives.push((
+                                    term_hash,
+                                    TokenType::word(field),
+                                    STEMMED_WEIGHT / (1.0 + distance as f64),
+                                ));
+                            }
+                        }
+
+                        for hash in seen_hashes {
+                            token_count.entry(hash).and_modify(|c| *c += 1).or_insert(1);
+                        }
+                        tokens.push(unweighted(&alternatives));
+                        if let Some(phrase) = &mut phrase {
+                            phrase.push(alternatives);
+                        }
                     }
+                    FtsTokenized::Contains { tokens }
                 }
                 FtsFilter::Keyword { field, text } => {
                     let hash = BitmapHash::new(text);
+                    let field = TokenType::word(field.into());
                     token_count.entry(hash).and_modify(|c| *c += 1).or_insert(1);
-
-                    FtsTokenized::Keyword {
-                        field: field.into(),
-                        token: hash,
+                    if let Some(phrase) = &mut phrase {
+                        phrase.push(vec![(hash, field, 1.0)]);
                     }
+
+                    FtsTokenized::Keyword { field, token: hash }
+                }
+                FtsFilter::And => {
+                    negations.push(false);
+                    FtsTokenized::And
+                }
+                FtsFilter::Or => {
+                    negations.push(false);
+                    FtsTokenized::Or
+                }
+                FtsFilter::Not => {
+                    negations.push(true);
+                    FtsTokenized::Not
+                }
+                FtsFilter::End => {
+                    negations.pop();
+                    FtsTokenized::End
                 }
-                FtsFilter::And => FtsTokenized::And,
-                FtsFilter::Or => FtsTokenized::Or,
-                FtsFilter::Not => FtsTokenized::Not,
-                FtsFilter::End => FtsTokenized::End,
             };
 
+            if let (Some(ranked_phrases), Some(phrase)) = (ranked_phrases.as_mut(), phrase) {
+                if !phrase.is_empty() {
+                    ranked_phrases.push(phrase);
+                }
+            }
+
             tokenized_filters.push(filter);
         }
 
@@ -141,18 +258,15 @@ impl Store {
                     )
                     .await?
                 }
-                FtsTokenized::Contains { field, tokens } => {
+                FtsTokenized::Contains { tokens } => {
                     let mut result = RoaringBitmap::new();
 
-                    for (token, stemmed_token) in tokens {
+                    for alternatives in tokens {
                         match self
                             .get_postings(
                                 account_id,
                                 collection,
-                                &[
-                                    (token, TokenType::word(field)),
-                                    (stemmed_token.unwrap_or(token), TokenType::stemmed(field)),
-                                ],
+                                &alternatives,
                                 &token_count,
                                 &mut token_cache,
                                 false,
@@ -183,7 +297,7 @@ impl Store {
                     self.get_postings(
                         account_id,
                         collection,
-                        &[(token, TokenType::word(field))],
+                        &[(token, field)],
                         &token_count,
                         &mut token_cache,
                         false,
@@ -403,6 +517,142 @@ impl Store {
             None
         })
     }
+
+    async fn fts_rank(
+        &self,
+        account_id: u32,
+        collection: u8,
+        phrases: Vec<RankedPhrase>,
+        results: RoaringBitmap,
+    ) -> trc::Result<AHashMap<u32, f64>> {
+        let mut scores: AHashMap<u32, f64> = results
+            .iter()
+            .map(|document_id| (document_id, 0.0))
+            .collect();
+        if phrases.is_empty() || results.is_empty() {
+            return Ok(scores);
+        }
+
+        let total_docs = self
+            .get_bitmap(BitmapKey::document_ids(account_id, collection))
+            .await?
+            .map_or(0, |bm| bm.len()) as f64;
+        let mut postings_cache: AHashMap<BitmapHash, AHashMap<u32, SerializedPostings<Vec<u8>>>> =
+            AHashMap::new();
+
+        for phrase in phrases {
+            let mut prev_positions: Option<AHashMap<u32, Vec<u32>>> = None;
+
+            for alternatives in phrase {
+                // Score each document by the best matching form of the term
+                let mut term_scores: AHashMap<u32, f64> = AHashMap::new();
+                let mut term_positions: AHashMap<u32, Vec<u32>> = AHashMap::new();
+
+                for (token, field, weight) in alternatives {
+                    if !postings_cache.contains_key(&token) {
+                        let postings = self
+                            .get_term_postings(account_id, collection, token)
+                            .await?;
+                        postings_cache.insert(token, postings);
+                    }
+                    let postings = &postings_cache[&token];
+                    let doc_freq = postings
+                        .values()
+                        .filter(|postings| postings.has_field(field))
+                        .count() as f64;
+                    if doc_freq == 0.0 {
+                        continue;
+                    }
+                    let idf =
+                        (1.0 + ((total_docs - doc_freq).max(0.0) + 0.5) / (doc_freq + 0.5)).ln();
+
+                    for (document_id, postings) in postings {
+                        if results.contains(*document_id) && postings.has_field(field) {
+                            let positions = postings.positions();
+                            let term_freq = positions.len().max(1) as f64;
+                            let score = weight * idf * (term_freq * (BM25_K1 + 1.0))
+                                / (term_freq + BM25_K1);
+                            let best_score = term_scores.entry(*document_id).or_default();
+                            if score > *best_score {
+                                *best_score = score;
+                            }
+                            if !positions.is_empty() {
+                                term_positions
+                                    .entry(*document_id)
+                                    .or_default()
+                                    .extend(positions);
+                            }
+                        }
+                    }
+                }
+
+                for (document_id, score) in term_scores {
+                    *scores.entry(document_id).or_default() += score;
+                }
+
+                // Reward documents where the term follows the previous one closely
+                for positions in term_positions.values_mut() {
+                    positions.sort_unstable();
+                    positions.dedup();
+                }
+                if let Some(prev_positions) = &prev_positions {
+                    for (document_id, positions) in &term_positions {
+                        if let Some(distance) = prev_positions
+                            .get(document_id)
+                            .and_then(|prev_positions| min_distance(prev_positions, positions))
+                        {
+                            *scores.entry(*document_id).or_default() +=
+                                PROXIMITY_WEIGHT / distance as f64;
+                        }
+                    }
+                }
+                prev_positions = Some(term_positions);
+            }
+        }
+
+        Ok(scores)
+    }
+
+    async fn get_term_postings(
+        &self,
+        account_id: u32,
+        collection: u8,
+        token: BitmapHash,
+    ) -> trc::Result<AHashMap<u32, SerializedPostings<Vec<u8>>>> {
+        let mut postings = AHashMap::new();
+        let key_len = ValueClass::FtsIndex::<DynamicDocumentId>(token).serialized_size();
+
+        self.iterate(
+            IterateParams::new(
+                ValueKey {
+                    account_id,
+                    collection,
+                    document_id: 0,
+                    class: ValueClass::FtsIndex(token),
+                },
+                ValueKey {
+                    account_id,
+                    collection,
+                    document_id: u32::MAX,
+                    class: ValueClass::FtsIndex(token),
+                },
+            ),
+            |key, value| {
+                if key.len() == key_len {
+                    postings.insert(
+                        key.deserialize_be_u32(key.len() - U32_LEN)?,
+                        SerializedPostings::new(value.to_vec()),
+                    );
+                }
+
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+        Ok(postings)
+    }
 }
 
 impl From<FtsTokenized> for State {
@@ -413,3 +663,29 @@ impl From<FtsTokenized> for State {
         }
     }
 }
+
+fn unweighted(alternatives: &[(BitmapHash, u8, f64)]) -> Vec<(BitmapHash, u8)> {
+    alternatives
+        .iter()
+        .map(|(token, field, _)| (*token, *field))
+        .collect()
+}
+
+// Smallest gap between a position and the closest preceding one
+fn min_distance(prev_positions: &[u32], positions: &[u32]) -> Option<u32> {
+    let mut prev_positions = prev_positions.iter().peekable();
+    let mut last_prev_pos = None;
+    let mut distance: Option<u32> = None;
+
+    for pos in positions {
+        while let Some(prev_pos) = prev_positions.next_if(|prev_pos| *prev_pos < pos) {
+            last_prev_pos = Some(*prev_pos);
+        }
+        if let Some(last_prev_pos) = last_prev_pos {
+            let gap = pos - last_prev_pos;
+            distance = Some(distance.map_or(gap, |distance| distance.min(gap)));
+        }
+    }
+
+    distance
+}
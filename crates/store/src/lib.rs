@@ -37,6 +37,8 @@ use backend::sqlite::SqliteStore;
 #[cfg(feature = "foundation")]
 use backend::foundationdb::FdbStore;
 
+#[cfg(feature = "redb")]
+use backend::redb::RedbStore;
 #[cfg(feature = "rocks")]
 use backend::rocksdb::RocksDbStore;
 
@@ -60,6 +62,9 @@ pub(crate) const WITH_SUBSPACE: u32 = 1;
 pub trait Key: Sync + Send + Clone {
     fn serialize(&self, flags: u32) -> Vec<u8>;
     fn subspace(&self) -> u8;
+    fn account_id(&self) -> Option<u32> {
+        None
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -150,9 +155,8 @@ pub const SUBSPACE_FTS_INDEX: u8 = b'g';
 pub const SUBSPACE_TELEMETRY_SPAN: u8 = b'o';
 pub const SUBSPACE_TELEMETRY_INDEX: u8 = b'w';
 pub const SUBSPACE_TELEMETRY_METRIC: u8 = b'x';
-
-pub const SUBSPACE_RESERVED_1: u8 = b'y';
-pub const SUBSPACE_RESERVED_2: u8 = b'z';
+pub const SUBSPACE_SNOOZE_EVENT: u8 = b'y';
+pub const SUBSPACE_FTS_TERM: u8 = b'z';
 
 #[derive(Clone)]
 pub struct IterateParams<T: Key> {
@@ -184,6 +188,8 @@ pub enum Store {
     MySQL(Arc<MysqlStore>),
     #[cfg(feature = "rocks")]
     RocksDb(Arc<RocksDbStore>),
+    #[cfg(feature = "redb")]
+    Redb(Arc<RedbStore>),
     #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
     SQLReadReplica(Arc<backend::composite::read_replica::SQLReadReplica>),
     #[default]
@@ -210,6 +216,7 @@ pub enum BlobBackend {
     S3(Arc<S3Store>),
     #[cfg(feature = "enterprise")]
     Composite(Arc<backend::composite::distributed_blob::DistributedBlob>),
+    Tiered(Arc<backend::tiered::TieredBlob>),
 }
 
 #[derive(Clone)]
@@ -269,6 +276,13 @@ impl From<RocksDbStore> for Store {
     }
 }
 
+#[cfg(feature = "redb")]
+impl From<RedbStore> for Store {
+    fn from(store: RedbStore) -> Self {
+        Self::Redb(Arc::new(store))
+    }
+}
+
 impl From<FsStore> for BlobStore {
     fn from(store: FsStore) -> Self {
         BlobStore {
@@ -714,6 +728,8 @@ impl std::fmt::Debug for Store {
             Self::MySQL(_) => f.debug_tuple("MySQL").finish(),
             #[cfg(feature = "rocks")]
             Self::RocksDb(_) => f.debug_tuple("RocksDb").finish(),
+            #[cfg(feature = "redb")]
+            Self::Redb(_) => f.debug_tuple("Redb").finish(),
             #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
             Self::SQLReadReplica(_) => f.debug_tuple("SQLReadReplica").finish(),
             Self::None => f.debug_tuple("None").finish(),
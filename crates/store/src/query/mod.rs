@@ -9,6 +9,7 @@ pub mod filter;
 pub mod log;
 pub mod sort;
 
+use ahash::AHashMap;
 use roaring::RoaringBitmap;
 
 use crate::{
@@ -47,8 +48,18 @@ pub enum Filter {
 
 #[derive(Debug)]
 pub enum Comparator {
-    Field { field: u8, ascending: bool },
-    DocumentSet { set: RoaringBitmap, ascending: bool },
+    Field {
+        field: u8,
+        ascending: bool,
+    },
+    DocumentSet {
+        set: RoaringBitmap,
+        ascending: bool,
+    },
+    Score {
+        scores: AHashMap<u32, f64>,
+        ascending: bool,
+    },
 }
 
 #[derive(Debug)]
@@ -167,6 +178,10 @@ impl Comparator {
         Self::DocumentSet { set, ascending }
     }
 
+    pub fn score(scores: AHashMap<u32, f64>, ascending: bool) -> Self {
+        Self::Score { scores, ascending }
+    }
+
     pub fn ascending(field: impl Into<u8>) -> Self {
         Self::Field {
             field: field.into(),
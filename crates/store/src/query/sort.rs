@@ -7,6 +7,7 @@
 use std::cmp::Ordering;
 
 use ahash::{AHashMap, AHashSet};
+use roaring::RoaringBitmap;
 use trc::AddContext;
 
 use crate::{
@@ -102,6 +103,13 @@ impl Store {
                         }
                     }
                 }
+                Comparator::Score { scores, ascending } => {
+                    for (document_id, _) in sort_by_score(&result_set.results, &scores, ascending) {
+                        if !paginate.add(0, document_id) {
+                            break;
+                        }
+                    }
+                }
             }
 
             // Obtain prefixes
@@ -202,6 +210,20 @@ impl Store {
                             }
                         }
                     }
+                    Comparator::Score { scores, ascending } => {
+                        let mut prev_score = None;
+                        let mut idx = 0;
+
+                        for (document_id, score) in
+                            sort_by_score(&result_set.results, &scores, ascending)
+                        {
+                            if prev_score != Some(score) {
+                                idx += 1;
+                                prev_score = Some(score);
+                            }
+                            sorted_ids.entry(document_id).or_insert([0u32; 4])[pos] = idx;
+                        }
+                    }
                 }
             }
 
@@ -270,6 +292,32 @@ impl Store {
     }
 }
 
+// Documents without a score are ranked as if they had a score of zero
+fn sort_by_score(
+    results: &RoaringBitmap,
+    scores: &AHashMap<u32, f64>,
+    ascending: bool,
+) -> Vec<(u32, f64)> {
+    let mut sorted = results
+        .iter()
+        .map(|document_id| {
+            (
+                document_id,
+                scores.get(&document_id).copied().unwrap_or_default(),
+            )
+        })
+        .collect::<Vec<_>>();
+    sorted.sort_by(|a, b| {
+        let order = if ascending {
+            a.1.total_cmp(&b.1)
+        } else {
+            b.1.total_cmp(&a.1)
+        };
+        order.then_with(|| a.0.cmp(&b.0))
+    });
+    sorted
+}
+
 impl Pagination {
     pub fn new(limit: usize, position: i32, anchor: Option<u32>, anchor_offset: i32) -> Self {
         let (has_anchor, anchor) = anchor.map(|anchor| (true, anchor)).unwrap_or((false, 0));
@@ -4,7 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
+use futures::{StreamExt, TryStreamExt};
 use trc::AddContext;
 use utils::{BlobHash, BLOB_HASH_LEN};
 
@@ -21,6 +22,45 @@ pub struct BlobQuota {
     pub count: usize,
 }
 
+// Only the first blobs to be purged are listed, all of them are counted
+pub const MAX_LISTED_BLOBS: usize = 1000;
+
+// Blobs whose links are held in memory at once, and concurrent size lookups
+const BLOB_USAGE_BATCH: usize = 1000;
+const BLOB_SIZE_CONCURRENCY: usize = 16;
+
+struct BlobLinks {
+    hash: BlobHash,
+    is_committed: bool,
+    links: Vec<Option<u32>>,
+}
+
+/// Blob store usage, sizes are the ones stored by the blob backend after compression.
+#[derive(Debug, Default)]
+pub struct BlobUsage {
+    pub blobs: u64,
+    pub links: u64,
+    pub physical_bytes: u64,
+    pub logical_bytes: u64,
+    pub accounts: AHashMap<u32, BlobAccountUsage>,
+    pub purge: BlobPurgeUsage,
+}
+
+#[derive(Debug, Default)]
+pub struct BlobAccountUsage {
+    pub links: u64,
+    pub physical_bytes: u64,
+    pub logical_bytes: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct BlobPurgeUsage {
+    pub blobs: Vec<BlobHash>,
+    pub count: u64,
+    pub bytes: u64,
+    pub expired_reservations: u64,
+}
+
 impl Store {
     pub async fn blob_exists(&self, hash: impl AsRef<BlobHash> + Sync + Send) -> trc::Result<bool> {
         self.get_value::<()>(ValueKey {
@@ -115,7 +155,7 @@ impl Store {
         self.get_value::<()>(key).await.map(|v| v.is_some())
     }
 
-    pub async fn purge_blobs(&self, blob_store: BlobStore) -> trc::Result<()> {
+    pub async fn blob_purge_candidates(&self) -> trc::Result<Vec<(u32, BlobOp)>> {
         // Remove expired temporary blobs
         let from_key = ValueKey {
             account_id: 0,
@@ -159,25 +199,11 @@ impl Store {
         .caused_by(trc::location!())?;
 
         // Validate linked blobs
-        let from_key = ValueKey {
-            account_id: 0,
-            collection: 0,
-            document_id: 0,
-            class: ValueClass::Blob(BlobOp::Link {
-                hash: BlobHash::default(),
-            }),
-        };
-        let to_key = ValueKey {
-            account_id: u32::MAX,
-            collection: u8::MAX,
-            document_id: u32::MAX,
-            class: ValueClass::Blob(BlobOp::Link {
-                hash: BlobHash::new_max(),
-            }),
-        };
         let mut last_hash = BlobHash::default();
         self.iterate(
-            IterateParams::new(from_key, to_key).ascending().no_values(),
+            IterateParams::new(blob_link_start(), blob_link_end())
+                .ascending()
+                .no_values(),
             |key, _| {
                 let hash = BlobHash::try_from_hash_slice(
                     key.get(0..BLOB_HASH_LEN)
@@ -201,6 +227,130 @@ impl Store {
         .await
         .caused_by(trc::location!())?;
 
+        Ok(delete_keys)
+    }
+
+    pub async fn blob_usage(&self, blob_store: &BlobStore) -> trc::Result<BlobUsage> {
+        // Blobs to be removed by the next purge
+        let mut usage = BlobUsage::default();
+        let mut unlinked = AHashSet::new();
+        for (_, op) in self
+            .blob_purge_candidates()
+            .await
+            .caused_by(trc::location!())?
+        {
+            match op {
+                BlobOp::Reserve { .. } => {
+                    usage.purge.expired_reservations += 1;
+                }
+                BlobOp::Commit { hash } => {
+                    unlinked.insert(hash);
+                }
+                _ => (),
+            }
+        }
+
+        // Walk the links in hash order, one bounded group of blobs at a time
+        let mut from_hash = BlobHash::default();
+        loop {
+            let mut blobs: Vec<BlobLinks> = Vec::with_capacity(BLOB_USAGE_BATCH);
+            let mut next_hash = None;
+            self.iterate(
+                IterateParams::new(blob_link_from(from_hash), blob_link_end())
+                    .ascending()
+                    .no_values(),
+                |key, _| {
+                    let hash =
+                        BlobHash::try_from_hash_slice(key.get(0..BLOB_HASH_LEN).ok_or_else(
+                            || trc::Error::corrupted_key(key, None, trc::location!()),
+                        )?)
+                        .unwrap();
+                    if blobs.last().map_or(true, |blob| blob.hash != hash) {
+                        if blobs.len() == BLOB_USAGE_BATCH {
+                            next_hash = Some(hash);
+                            return Ok(false);
+                        }
+                        blobs.push(BlobLinks {
+                            hash,
+                            is_committed: false,
+                            links: Vec::new(),
+                        });
+                    }
+
+                    let account_id = key.deserialize_be_u32(BLOB_HASH_LEN)?;
+                    let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+                    let blob = blobs.last_mut().unwrap();
+                    if document_id == u32::MAX && account_id == u32::MAX {
+                        blob.is_committed = true;
+                    } else if key[BLOB_HASH_LEN + U32_LEN] != u8::MAX {
+                        blob.links.push(Some(account_id));
+                    } else {
+                        blob.links.push(None);
+                    }
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+            // Obtain the sizes of the committed blobs with bounded concurrency
+            blobs.retain(|blob| blob.is_committed);
+            let sizes = futures::stream::iter(
+                blobs
+                    .iter()
+                    .map(|blob| blob_store.blob_size(blob.hash.as_slice())),
+            )
+            .buffered(BLOB_SIZE_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await
+            .caused_by(trc::location!())?;
+
+            // Calculate logical and physical sizes
+            for (blob, size) in blobs.into_iter().zip(sizes) {
+                let size = size.unwrap_or_default() as u64;
+
+                usage.blobs += 1;
+                usage.physical_bytes += size;
+                usage.links += blob.links.len() as u64;
+                usage.logical_bytes += size * blob.links.len() as u64;
+
+                let mut last_account_id = None;
+                for account_id in blob.links.into_iter().flatten() {
+                    let account = usage.accounts.entry(account_id).or_default();
+                    account.links += 1;
+                    account.logical_bytes += size;
+                    if last_account_id != Some(account_id) {
+                        account.physical_bytes += size;
+                        last_account_id = Some(account_id);
+                    }
+                }
+
+                if unlinked.contains(&blob.hash) {
+                    usage.purge.count += 1;
+                    usage.purge.bytes += size;
+                    if usage.purge.blobs.len() < MAX_LISTED_BLOBS {
+                        usage.purge.blobs.push(blob.hash);
+                    }
+                }
+            }
+
+            if let Some(hash) = next_hash {
+                from_hash = hash;
+            } else {
+                break;
+            }
+        }
+
+        Ok(usage)
+    }
+
+    pub async fn purge_blobs(&self, blob_store: BlobStore) -> trc::Result<()> {
+        let delete_keys = self
+            .blob_purge_candidates()
+            .await
+            .caused_by(trc::location!())?;
+
         // Delete expired or unlinked blobs
         for (_, op) in &delete_keys {
             if let BlobOp::Commit { hash } = op {
@@ -242,25 +392,11 @@ impl Store {
 
     pub async fn blob_hash_unlink_account(&self, account_id: u32) -> trc::Result<()> {
         // Validate linked blobs
-        let from_key = ValueKey {
-            account_id: 0,
-            collection: 0,
-            document_id: 0,
-            class: ValueClass::Blob(BlobOp::Link {
-                hash: BlobHash::default(),
-            }),
-        };
-        let to_key = ValueKey {
-            account_id: u32::MAX,
-            collection: u8::MAX,
-            document_id: u32::MAX,
-            class: ValueClass::Blob(BlobOp::Link {
-                hash: BlobHash::new_max(),
-            }),
-        };
         let mut delete_keys = Vec::new();
         self.iterate(
-            IterateParams::new(from_key, to_key).ascending().no_values(),
+            IterateParams::new(blob_link_start(), blob_link_end())
+                .ascending()
+                .no_values(),
             |key, _| {
                 let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
 
@@ -317,3 +453,27 @@ impl Store {
         Ok(())
     }
 }
+
+fn blob_link_start() -> ValueKey<ValueClass<u32>> {
+    blob_link_from(BlobHash::default())
+}
+
+fn blob_link_from(hash: BlobHash) -> ValueKey<ValueClass<u32>> {
+    ValueKey {
+        account_id: 0,
+        collection: 0,
+        document_id: 0,
+        class: ValueClass::Blob(BlobOp::Link { hash }),
+    }
+}
+
+fn blob_link_end() -> ValueKey<ValueClass<u32>> {
+    ValueKey {
+        account_id: u32::MAX,
+        collection: u8::MAX,
+        document_id: u32::MAX,
+        class: ValueClass::Blob(BlobOp::Link {
+            hash: BlobHash::new_max(),
+        }),
+    }
+}
@@ -11,10 +11,11 @@ use crate::{
     BitmapKey, Deserialize, IndexKey, IndexKeyPrefix, Key, LogKey, ValueKey, SUBSPACE_ACL,
     SUBSPACE_BITMAP_ID, SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SUBSPACE_BLOB_LINK,
     SUBSPACE_BLOB_RESERVE, SUBSPACE_COUNTER, SUBSPACE_DIRECTORY, SUBSPACE_FTS_INDEX,
-    SUBSPACE_FTS_QUEUE, SUBSPACE_INDEXES, SUBSPACE_LOGS, SUBSPACE_LOOKUP_VALUE, SUBSPACE_PROPERTY,
-    SUBSPACE_QUEUE_EVENT, SUBSPACE_QUEUE_MESSAGE, SUBSPACE_QUOTA, SUBSPACE_REPORT_IN,
-    SUBSPACE_REPORT_OUT, SUBSPACE_SETTINGS, SUBSPACE_TELEMETRY_INDEX, SUBSPACE_TELEMETRY_METRIC,
-    SUBSPACE_TELEMETRY_SPAN, U32_LEN, U64_LEN, WITH_SUBSPACE,
+    SUBSPACE_FTS_QUEUE, SUBSPACE_FTS_TERM, SUBSPACE_INDEXES, SUBSPACE_LOGS, SUBSPACE_LOOKUP_VALUE,
+    SUBSPACE_PROPERTY, SUBSPACE_QUEUE_EVENT, SUBSPACE_QUEUE_MESSAGE, SUBSPACE_QUOTA,
+    SUBSPACE_REPORT_IN, SUBSPACE_REPORT_OUT, SUBSPACE_SETTINGS, SUBSPACE_SNOOZE_EVENT,
+    SUBSPACE_TELEMETRY_INDEX, SUBSPACE_TELEMETRY_METRIC, SUBSPACE_TELEMETRY_SPAN, U32_LEN, U64_LEN,
+    WITH_SUBSPACE,
 };
 
 use super::{
@@ -181,6 +182,10 @@ impl Key for IndexKeyPrefix {
     fn subspace(&self) -> u8 {
         SUBSPACE_INDEXES
     }
+
+    fn account_id(&self) -> Option<u32> {
+        Some(self.account_id)
+    }
 }
 
 impl IndexKeyPrefix {
@@ -194,6 +199,10 @@ impl Key for LogKey {
         SUBSPACE_LOGS
     }
 
+    fn account_id(&self) -> Option<u32> {
+        Some(self.account_id)
+    }
+
     fn serialize(&self, flags: u32) -> Vec<u8> {
         {
             if (flags & WITH_SUBSPACE) != 0 {
@@ -214,6 +223,10 @@ impl<T: AsRef<ValueClass<u32>> + Sync + Send + Clone> Key for ValueKey<T> {
         self.class.as_ref().subspace(self.collection)
     }
 
+    fn account_id(&self) -> Option<u32> {
+        Some(self.account_id)
+    }
+
     fn serialize(&self, flags: u32) -> Vec<u8> {
         self.class.as_ref().serialize(
             self.account_id,
@@ -272,6 +285,13 @@ impl<T: ResolveId> ValueClass<T> {
                 .write(collection)
                 .write(document_id)
                 .write::<&[u8]>(queue.hash.as_ref()),
+            ValueClass::FtsTerm(term) => serializer
+                .write(account_id)
+                .write(collection)
+                .write(term.as_slice()),
+            ValueClass::SnoozeEvent(due) => {
+                serializer.write(*due).write(account_id).write(document_id)
+            }
             ValueClass::Blob(op) => match op {
                 BlobOp::Reserve { hash, until } => serializer
                     .write(account_id)
@@ -390,6 +410,10 @@ impl<T: AsRef<[u8]> + Sync + Send + Clone> Key for IndexKey<T> {
         SUBSPACE_INDEXES
     }
 
+    fn account_id(&self) -> Option<u32> {
+        Some(self.account_id)
+    }
+
     fn serialize(&self, flags: u32) -> Vec<u8> {
         let key = self.key.as_ref();
         {
@@ -414,6 +438,10 @@ impl<T: AsRef<BitmapClass<u32>> + Sync + Send + Clone> Key for BitmapKey<T> {
         self.class.as_ref().subspace()
     }
 
+    fn account_id(&self) -> Option<u32> {
+        Some(self.account_id)
+    }
+
     fn serialize(&self, flags: u32) -> Vec<u8> {
         self.class.as_ref().serialize(
             self.account_id,
@@ -543,6 +571,8 @@ impl<T> ValueClass<T> {
                 }
             },
             ValueClass::FtsQueue { .. } => BLOB_HASH_LEN + U64_LEN * 2,
+            ValueClass::FtsTerm(term) => U32_LEN + term.len() + 1,
+            ValueClass::SnoozeEvent(_) => U64_LEN + U32_LEN * 2,
             ValueClass::Queue(q) => match q {
                 QueueClass::Message(_) => U64_LEN,
                 QueueClass::MessageEvent(_) => U64_LEN * 2,
@@ -576,6 +606,8 @@ impl<T> ValueClass<T> {
             ValueClass::Acl(_) => SUBSPACE_ACL,
             ValueClass::FtsIndex(_) => SUBSPACE_FTS_INDEX,
             ValueClass::FtsQueue { .. } => SUBSPACE_FTS_QUEUE,
+            ValueClass::FtsTerm(_) => SUBSPACE_FTS_TERM,
+            ValueClass::SnoozeEvent(_) => SUBSPACE_SNOOZE_EVENT,
             ValueClass::Blob(op) => match op {
                 BlobOp::Reserve { .. } => SUBSPACE_BLOB_RESERVE,
                 BlobOp::Commit { .. } | BlobOp::Link { .. } | BlobOp::LinkId { .. } => {
@@ -614,6 +646,7 @@ impl<T> ValueClass<T> {
         match self {
             ValueClass::Directory(DirectoryClass::UsedQuota(_))
             | ValueClass::Lookup(LookupClass::Counter(_))
+            | ValueClass::FtsTerm(_)
             | ValueClass::Queue(QueueClass::QuotaCount(_) | QueueClass::QuotaSize(_)) => true,
             ValueClass::Property(84) if collection == 1 => true, // TODO: Find a more elegant way to do this
             _ => false,
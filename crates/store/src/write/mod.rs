@@ -148,6 +148,8 @@ pub enum ValueClass<T> {
     Lookup(LookupClass),
     FtsIndex(BitmapHash),
     FtsQueue(FtsQueueClass),
+    FtsTerm(Vec<u8>),
+    SnoozeEvent(u64),
     Directory(DirectoryClass<T>),
     Blob(BlobOp),
     Config(Vec<u8>),
@@ -177,6 +177,9 @@ impl HousekeeperEvent {
             HousekeeperEvent::PurgeAccounts => "Purging accounts",
             HousekeeperEvent::PurgeSessions => "Purging sessions",
             HousekeeperEvent::PurgeStore => "Purging store",
+            HousekeeperEvent::DeprovisionAccounts => "Deprovisioned inactive accounts",
+            HousekeeperEvent::Backup => "Backup completed",
+            HousekeeperEvent::WakeSnoozed => "Snoozed messages woke up",
         }
     }
 
@@ -188,6 +191,13 @@ impl HousekeeperEvent {
             HousekeeperEvent::PurgeAccounts => "Purging accounts",
             HousekeeperEvent::PurgeSessions => "Purging sessions",
             HousekeeperEvent::PurgeStore => "Purging store",
+            HousekeeperEvent::DeprovisionAccounts => {
+                "Accounts that have not logged in for the configured period were disabled"
+            }
+            HousekeeperEvent::Backup => "An online backup of the data store was completed",
+            HousekeeperEvent::WakeSnoozed => {
+                "Snoozed messages that reached their wake-up time were moved back"
+            }
         }
     }
 }
@@ -1535,6 +1545,7 @@ impl StoreEvent {
             StoreEvent::MysqlError => "MySQL error",
             StoreEvent::PostgresqlError => "PostgreSQL error",
             StoreEvent::RocksdbError => "RocksDB error",
+            StoreEvent::RedbError => "redb error",
             StoreEvent::SqliteError => "SQLite error",
             StoreEvent::LdapError => "LDAP error",
             StoreEvent::ElasticsearchError => "ElasticSearch error",
@@ -1559,6 +1570,17 @@ impl StoreEvent {
             StoreEvent::BlobWrite => "Blob write operation",
             StoreEvent::BlobDelete => "Blob delete operation",
             StoreEvent::DataIterate => "Data store iteration operation",
+            StoreEvent::MigrationStart => "Store migration started",
+            StoreEvent::MigrationProgress => "Store migration progress",
+            StoreEvent::MigrationCutover => "Store migration cut-over",
+            StoreEvent::MigrationComplete => "Store migration completed",
+            StoreEvent::ConsistencyCheck => "Store consistency check completed",
+            StoreEvent::ConsistencyIssue => "Store inconsistency found",
+            StoreEvent::BlobPromoted => "Blob moved to hot tier",
+            StoreEvent::BlobDemoted => "Blob moved to cold tier",
+            StoreEvent::SlowOperation => "Slow store operation",
+            StoreEvent::CacheHit => "Store cache hit",
+            StoreEvent::CacheMiss => "Store cache miss",
         }
     }
 
@@ -1569,6 +1591,7 @@ impl StoreEvent {
             StoreEvent::MysqlError => "A MySQL error occurred",
             StoreEvent::PostgresqlError => "A PostgreSQL error occurred",
             StoreEvent::RocksdbError => "A RocksDB error occurred",
+            StoreEvent::RedbError => "A redb error occurred",
             StoreEvent::SqliteError => "An SQLite error occurred",
             StoreEvent::LdapError => "An LDAP error occurred",
             StoreEvent::ElasticsearchError => "An ElasticSearch error occurred",
@@ -1593,6 +1616,27 @@ impl StoreEvent {
             StoreEvent::BlobWrite => "A blob write operation was executed",
             StoreEvent::BlobDelete => "A blob delete operation was executed",
             StoreEvent::DataIterate => "A data store iteration operation was executed",
+            StoreEvent::MigrationStart => "A migration to a different data store was started",
+            StoreEvent::MigrationProgress => "A migration pass was copied to the destination store",
+            StoreEvent::MigrationCutover => {
+                "Writes were suspended to switch the server over to the destination store"
+            }
+            StoreEvent::MigrationComplete => {
+                "The server was switched over to the destination store"
+            }
+            StoreEvent::ConsistencyCheck => "The data store consistency check has finished",
+            StoreEvent::ConsistencyIssue => {
+                "An entry in the data store does not match the data it refers to"
+            }
+            StoreEvent::BlobPromoted => {
+                "A blob read from the cold tier was copied back to the hot tier"
+            }
+            StoreEvent::BlobDemoted => "A blob was moved from the hot tier to the cold tier",
+            StoreEvent::SlowOperation => {
+                "A store operation took longer than the configured slow operation threshold"
+            }
+            StoreEvent::CacheHit => "A value or bitmap was served from the store cache",
+            StoreEvent::CacheMiss => "A value or bitmap was not found in the store cache",
         }
     }
 }
@@ -1719,6 +1763,7 @@ impl ManageEvent {
             ManageEvent::AssertFailed => "Management assertion failed",
             ManageEvent::NotFound => "Managed resource not found",
             ManageEvent::NotSupported => "Management operation not supported",
+            ManageEvent::PasswordPolicy => "Password policy violation",
             ManageEvent::Error => "Management error",
         }
     }
@@ -1730,6 +1775,7 @@ impl ManageEvent {
             ManageEvent::AssertFailed => "A management assertion has failed",
             ManageEvent::NotFound => "The managed resource was not found",
             ManageEvent::NotSupported => "The management operation is not supported",
+            ManageEvent::PasswordPolicy => "The password does not comply with the password policy",
             ManageEvent::Error => "A management error occurred",
         }
     }
@@ -26,6 +26,7 @@ impl EventType {
                 | StoreEvent::MysqlError
                 | StoreEvent::PostgresqlError
                 | StoreEvent::RocksdbError
+                | StoreEvent::RedbError
                 | StoreEvent::SqliteError
                 | StoreEvent::LdapError
                 | StoreEvent::ElasticsearchError
@@ -41,6 +42,15 @@ impl EventType {
                 | StoreEvent::UnexpectedError
                 | StoreEvent::CryptoError => Level::Error,
                 StoreEvent::BlobMissingMarker => Level::Warn,
+                StoreEvent::MigrationStart
+                | StoreEvent::MigrationProgress
+                | StoreEvent::MigrationCutover
+                | StoreEvent::MigrationComplete
+                | StoreEvent::ConsistencyCheck => Level::Info,
+                StoreEvent::ConsistencyIssue => Level::Warn,
+                StoreEvent::BlobPromoted | StoreEvent::BlobDemoted => Level::Debug,
+                StoreEvent::SlowOperation => Level::Warn,
+                StoreEvent::CacheHit | StoreEvent::CacheMiss => Level::Trace,
             },
             EventType::Jmap(_) => Level::Debug,
             EventType::Imap(event) => match event {
@@ -373,6 +383,9 @@ impl EventType {
                 | HousekeeperEvent::PurgeAccounts
                 | HousekeeperEvent::PurgeSessions
                 | HousekeeperEvent::PurgeStore
+                | HousekeeperEvent::DeprovisionAccounts
+                | HousekeeperEvent::Backup
+                | HousekeeperEvent::WakeSnoozed
                 | HousekeeperEvent::Stop => Level::Info,
                 HousekeeperEvent::Schedule => Level::Debug,
             },
@@ -36,6 +36,10 @@ impl MetricType {
             Self::QueueCount => "queue.count",
             Self::UserCount => "user.count",
             Self::DomainCount => "domain.count",
+            Self::BlobCount => "store.blob-count",
+            Self::BlobPhysicalSize => "store.blob-physical-size",
+            Self::BlobLogicalSize => "store.blob-logical-size",
+            Self::StoreOperationTime => "store.operation-time",
         }
     }
 
@@ -68,6 +72,10 @@ impl MetricType {
             Self::QueueCount => "Total number of messages in the queue",
             Self::UserCount => "Total number of users",
             Self::DomainCount => "Total number of domains",
+            Self::BlobCount => "Total number of stored blobs",
+            Self::BlobPhysicalSize => "Total size of stored blobs",
+            Self::BlobLogicalSize => "Total size of blobs before deduplication",
+            Self::StoreOperationTime => "Store operation duration by store and operation",
         }
     }
 
@@ -86,11 +94,14 @@ impl MetricType {
             | Self::ImapRequestTime
             | Self::Pop3RequestTime
             | Self::SmtpRequestTime
-            | Self::SieveRequestTime => "milliseconds",
+            | Self::SieveRequestTime
+            | Self::StoreOperationTime => "milliseconds",
             Self::MessageSize
             | Self::MessageAuthSize
             | Self::ReportOutgoingSize
-            | Self::ServerMemory => "bytes",
+            | Self::ServerMemory
+            | Self::BlobPhysicalSize
+            | Self::BlobLogicalSize => "bytes",
             Self::HttpActiveConnections
             | Self::ImapActiveConnections
             | Self::Pop3ActiveConnections
@@ -100,6 +111,7 @@ impl MetricType {
             Self::QueueCount => "messages",
             Self::UserCount => "users",
             Self::DomainCount => "domains",
+            Self::BlobCount => "blobs",
         }
     }
 
@@ -132,6 +144,10 @@ impl MetricType {
             Self::QueueCount => 24,
             Self::UserCount => 25,
             Self::DomainCount => 26,
+            Self::BlobCount => 27,
+            Self::BlobPhysicalSize => 28,
+            Self::BlobLogicalSize => 29,
+            Self::StoreOperationTime => 30,
         }
     }
 
@@ -164,6 +180,10 @@ impl MetricType {
             24 => Some(Self::QueueCount),
             25 => Some(Self::UserCount),
             26 => Some(Self::DomainCount),
+            27 => Some(Self::BlobCount),
+            28 => Some(Self::BlobPhysicalSize),
+            29 => Some(Self::BlobLogicalSize),
+            30 => Some(Self::StoreOperationTime),
             _ => None,
         }
     }
@@ -197,6 +217,10 @@ impl MetricType {
             "queue.count" => Some(Self::QueueCount),
             "user.count" => Some(Self::UserCount),
             "domain.count" => Some(Self::DomainCount),
+            "store.blob-count" => Some(Self::BlobCount),
+            "store.blob-physical-size" => Some(Self::BlobPhysicalSize),
+            "store.blob-logical-size" => Some(Self::BlobLogicalSize),
+            "store.operation-time" => Some(Self::StoreOperationTime),
             _ => None,
         }
     }
@@ -230,6 +254,10 @@ impl MetricType {
             Self::QueueCount,
             Self::UserCount,
             Self::DomainCount,
+            Self::BlobCount,
+            Self::BlobPhysicalSize,
+            Self::BlobLogicalSize,
+            Self::StoreOperationTime,
         ]
     }
 }
@@ -302,6 +302,7 @@ impl StoreEvent {
             Self::MysqlError => "MySQL error",
             Self::PostgresqlError => "PostgreSQL error",
             Self::RocksdbError => "RocksDB error",
+            Self::RedbError => "redb error",
             Self::SqliteError => "SQLite error",
             Self::LdapError => "LDAP error",
             Self::ElasticsearchError => "ElasticSearch error",
@@ -391,6 +392,7 @@ impl ManageEvent {
             Self::AssertFailed => "Assertion failed",
             Self::NotFound => "Not found",
             Self::NotSupported => "Operation not supported",
+            Self::PasswordPolicy => "Password policy violation",
             Self::Error => "Management API Error",
         }
     }
@@ -4,13 +4,18 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::sync::atomic::Ordering;
+use std::sync::{atomic::Ordering, Arc};
 
-use atomics::{array::AtomicU32Array, gauge::AtomicGauge, histogram::AtomicHistogram};
+use atomics::{
+    array::{AtomicU32Array, AtomicU64Array},
+    gauge::AtomicGauge,
+    histogram::AtomicHistogram,
+};
 use ipc::{
     collector::{Collector, GlobalInterests, EVENT_TYPES},
     subscriber::Interests,
 };
+use parking_lot::RwLock;
 
 use crate::*;
 
@@ -49,6 +54,11 @@ static SERVER_MEMORY: AtomicGauge = AtomicGauge::new(MetricType::ServerMemory);
 static QUEUE_COUNT: AtomicGauge = AtomicGauge::new(MetricType::QueueCount);
 static USER_COUNT: AtomicGauge = AtomicGauge::new(MetricType::UserCount);
 static DOMAIN_COUNT: AtomicGauge = AtomicGauge::new(MetricType::DomainCount);
+static BLOB_COUNT: AtomicGauge = AtomicGauge::new(MetricType::BlobCount);
+static BLOB_PHYSICAL_SIZE: AtomicGauge = AtomicGauge::new(MetricType::BlobPhysicalSize);
+static BLOB_LOGICAL_SIZE: AtomicGauge = AtomicGauge::new(MetricType::BlobLogicalSize);
+
+static STORE_METRICS: RwLock<Vec<Arc<StoreMetrics>>> = RwLock::new(Vec::new());
 
 const CONN_SMTP_IN: usize = 0;
 const CONN_SMTP_OUT: usize = 1;
@@ -63,6 +73,26 @@ pub struct ConnectionMetrics {
     pub elapsed: AtomicHistogram<12>,
 }
 
+pub struct StoreMetrics {
+    id: String,
+    operations: [AtomicHistogram<12>; TOTAL_STORE_OPS],
+    errors: AtomicU64Array<TOTAL_STORE_OPS>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreOperation {
+    GetValue,
+    GetBitmap,
+    GetCounter,
+    Iterate,
+    Write,
+    BlobGet,
+    BlobPut,
+    BlobDelete,
+}
+
+const TOTAL_STORE_OPS: usize = 8;
+
 pub struct EventCounter {
     id: EventType,
     value: u32,
@@ -226,9 +256,23 @@ impl Collector {
     }
 
     pub fn collect_gauges(is_enterprise: bool) -> impl Iterator<Item = &'static AtomicGauge> {
-        static E_GAUGES: &[&AtomicGauge] =
-            &[&SERVER_MEMORY, &QUEUE_COUNT, &USER_COUNT, &DOMAIN_COUNT];
-        static C_GAUGES: &[&AtomicGauge] = &[&SERVER_MEMORY, &USER_COUNT, &DOMAIN_COUNT];
+        static E_GAUGES: &[&AtomicGauge] = &[
+            &SERVER_MEMORY,
+            &QUEUE_COUNT,
+            &USER_COUNT,
+            &DOMAIN_COUNT,
+            &BLOB_COUNT,
+            &BLOB_PHYSICAL_SIZE,
+            &BLOB_LOGICAL_SIZE,
+        ];
+        static C_GAUGES: &[&AtomicGauge] = &[
+            &SERVER_MEMORY,
+            &USER_COUNT,
+            &DOMAIN_COUNT,
+            &BLOB_COUNT,
+            &BLOB_PHYSICAL_SIZE,
+            &BLOB_LOGICAL_SIZE,
+        ];
 
         if is_enterprise { E_GAUGES } else { C_GAUGES }
             .iter()
@@ -269,6 +313,36 @@ impl Collector {
         .filter(|h| h.is_active())
     }
 
+    /// Returns the operation histograms of a store, registering the store
+    /// the first time it is seen.
+    pub fn store_metrics(store_id: &str) -> Arc<StoreMetrics> {
+        if let Some(metrics) = STORE_METRICS.read().iter().find(|m| m.id == store_id) {
+            return metrics.clone();
+        }
+
+        let mut stores = STORE_METRICS.write();
+        if let Some(metrics) = stores.iter().find(|m| m.id == store_id) {
+            metrics.clone()
+        } else {
+            let metrics = Arc::new(StoreMetrics::new(store_id));
+            stores.push(metrics.clone());
+            metrics
+        }
+    }
+
+    pub fn collect_store_histograms(is_enterprise: bool) -> Vec<Arc<StoreMetrics>> {
+        if is_enterprise {
+            STORE_METRICS
+                .read()
+                .iter()
+                .filter(|m| m.operations.iter().any(|h| h.is_active()))
+                .cloned()
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
     #[inline(always)]
     pub fn read_event_metric(metric_id: usize) -> u32 {
         EVENT_COUNTERS.get(metric_id)
@@ -315,6 +389,21 @@ impl Collector {
             MetricType::SieveRequestTime => CONNECTION_METRICS[CONN_SIEVE].elapsed.average(),
             MetricType::UserCount => USER_COUNT.get() as f64,
             MetricType::DomainCount => DOMAIN_COUNT.get() as f64,
+            MetricType::BlobCount => BLOB_COUNT.get() as f64,
+            MetricType::BlobPhysicalSize => BLOB_PHYSICAL_SIZE.get() as f64,
+            MetricType::BlobLogicalSize => BLOB_LOGICAL_SIZE.get() as f64,
+            MetricType::StoreOperationTime => {
+                let (sum, count) = STORE_METRICS
+                    .read()
+                    .iter()
+                    .flat_map(|m| m.operations.iter())
+                    .fold((0, 0), |(sum, count), h| (sum + h.sum(), count + h.count()));
+                if count > 0 {
+                    sum as f64 / count as f64
+                } else {
+                    0.0
+                }
+            }
         }
     }
 
@@ -324,6 +413,9 @@ impl Collector {
             MetricType::QueueCount => QUEUE_COUNT.set(value),
             MetricType::UserCount => USER_COUNT.set(value),
             MetricType::DomainCount => DOMAIN_COUNT.set(value),
+            MetricType::BlobCount => BLOB_COUNT.set(value),
+            MetricType::BlobPhysicalSize => BLOB_PHYSICAL_SIZE.set(value),
+            MetricType::BlobLogicalSize => BLOB_LOGICAL_SIZE.set(value),
             _ => {}
         }
     }
@@ -354,6 +446,74 @@ impl EventCounter {
     }
 }
 
+impl StoreMetrics {
+    fn new(id: &str) -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const INIT: AtomicHistogram<12> =
+            AtomicHistogram::<10>::new_short_durations(MetricType::StoreOperationTime);
+        Self {
+            id: id.to_string(),
+            operations: [INIT; TOTAL_STORE_OPS],
+            errors: AtomicU64Array::new(),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn observe(&self, operation: StoreOperation, elapsed: u64, is_error: bool) {
+        self.operations[operation as usize].observe(elapsed);
+        if is_error {
+            self.errors.add(operation as usize, 1);
+        }
+    }
+
+    pub fn histograms(&self) -> impl Iterator<Item = (StoreOperation, &AtomicHistogram<12>)> {
+        StoreOperation::variants()
+            .iter()
+            .copied()
+            .zip(self.operations.iter())
+            .filter(|(_, h)| h.is_active())
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = (StoreOperation, u64)> + '_ {
+        StoreOperation::variants()
+            .iter()
+            .copied()
+            .map(|op| (op, self.errors.get(op as usize)))
+            .filter(|(_, errors)| *errors > 0)
+    }
+}
+
+impl StoreOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StoreOperation::GetValue => "get-value",
+            StoreOperation::GetBitmap => "get-bitmap",
+            StoreOperation::GetCounter => "get-counter",
+            StoreOperation::Iterate => "iterate",
+            StoreOperation::Write => "write",
+            StoreOperation::BlobGet => "blob-get",
+            StoreOperation::BlobPut => "blob-put",
+            StoreOperation::BlobDelete => "blob-delete",
+        }
+    }
+
+    pub fn variants() -> &'static [StoreOperation; TOTAL_STORE_OPS] {
+        &[
+            StoreOperation::GetValue,
+            StoreOperation::GetBitmap,
+            StoreOperation::GetCounter,
+            StoreOperation::Iterate,
+            StoreOperation::Write,
+            StoreOperation::BlobGet,
+            StoreOperation::BlobPut,
+            StoreOperation::BlobDelete,
+        ]
+    }
+}
+
 impl ConnectionMetrics {
     #[allow(clippy::new_without_default)]
     pub const fn new() -> Self {
@@ -435,6 +595,7 @@ impl EventType {
                 | StoreEvent::MysqlError
                 | StoreEvent::PostgresqlError
                 | StoreEvent::RocksdbError
+                | StoreEvent::RedbError
                 | StoreEvent::SqliteError
                 | StoreEvent::LdapError
                 | StoreEvent::ElasticsearchError
@@ -455,7 +616,10 @@ impl EventType {
                 | StoreEvent::DataIterate
                 | StoreEvent::BlobRead
                 | StoreEvent::BlobWrite
-                | StoreEvent::BlobDelete,
+                | StoreEvent::BlobDelete
+                | StoreEvent::SlowOperation
+                | StoreEvent::CacheHit
+                | StoreEvent::CacheMiss,
             ) => true,
             EventType::MessageIngest(_) => true,
             EventType::Jmap(
@@ -232,6 +232,9 @@ pub enum HousekeeperEvent {
     PurgeAccounts,
     PurgeSessions,
     PurgeStore,
+    DeprovisionAccounts,
+    Backup,
+    WakeSnoozed,
 }
 
 #[event_type]
@@ -823,6 +826,7 @@ pub enum StoreEvent {
     MysqlError,
     PostgresqlError,
     RocksdbError,
+    RedbError,
     SqliteError,
     LdapError,
     ElasticsearchError,
@@ -851,6 +855,27 @@ pub enum StoreEvent {
     SqlQuery,
     LdapQuery,
     LdapBind,
+
+    // Migration
+    MigrationStart,
+    MigrationProgress,
+    MigrationCutover,
+    MigrationComplete,
+
+    // Consistency check
+    ConsistencyCheck,
+    ConsistencyIssue,
+
+    // Blob tiering
+    BlobPromoted,
+    BlobDemoted,
+
+    // Telemetry
+    SlowOperation,
+
+    // Read-through cache
+    CacheHit,
+    CacheMiss,
 }
 
 #[event_type]
@@ -918,6 +943,7 @@ pub enum ManageEvent {
     AssertFailed,
     NotFound,
     NotSupported,
+    PasswordPolicy,
     Error,
 }
 
@@ -976,6 +1002,10 @@ pub enum MetricType {
     SieveRequestTime,
     UserCount,
     DomainCount,
+    BlobCount,
+    BlobPhysicalSize,
+    BlobLogicalSize,
+    StoreOperationTime,
 }
 
 pub const TOTAL_EVENT_COUNT: usize = total_event_count!();
@@ -864,6 +864,22 @@ impl EventType {
             EventType::Ai(AiEvent::LlmResponse) => 556,
             EventType::Ai(AiEvent::ApiError) => 557,
             EventType::Security(SecurityEvent::ScanBan) => 558,
+            EventType::Housekeeper(HousekeeperEvent::DeprovisionAccounts) => 559,
+            EventType::Manage(ManageEvent::PasswordPolicy) => 560,
+            EventType::Housekeeper(HousekeeperEvent::Backup) => 561,
+            EventType::Store(StoreEvent::MigrationStart) => 562,
+            EventType::Store(StoreEvent::MigrationProgress) => 563,
+            EventType::Store(StoreEvent::MigrationCutover) => 564,
+            EventType::Store(StoreEvent::MigrationComplete) => 565,
+            EventType::Store(StoreEvent::RedbError) => 566,
+            EventType::Store(StoreEvent::ConsistencyCheck) => 567,
+            EventType::Store(StoreEvent::ConsistencyIssue) => 568,
+            EventType::Store(StoreEvent::BlobPromoted) => 569,
+            EventType::Store(StoreEvent::BlobDemoted) => 570,
+            EventType::Store(StoreEvent::SlowOperation) => 571,
+            EventType::Store(StoreEvent::CacheHit) => 572,
+            EventType::Store(StoreEvent::CacheMiss) => 573,
+            EventType::Housekeeper(HousekeeperEvent::WakeSnoozed) => 574,
         }
     }
 
@@ -1468,6 +1484,24 @@ impl EventType {
             556 => Some(EventType::Ai(AiEvent::LlmResponse)),
             557 => Some(EventType::Ai(AiEvent::ApiError)),
             558 => Some(EventType::Security(SecurityEvent::ScanBan)),
+            559 => Some(EventType::Housekeeper(
+                HousekeeperEvent::DeprovisionAccounts,
+            )),
+            560 => Some(EventType::Manage(ManageEvent::PasswordPolicy)),
+            561 => Some(EventType::Housekeeper(HousekeeperEvent::Backup)),
+            562 => Some(EventType::Store(StoreEvent::MigrationStart)),
+            563 => Some(EventType::Store(StoreEvent::MigrationProgress)),
+            564 => Some(EventType::Store(StoreEvent::MigrationCutover)),
+            565 => Some(EventType::Store(StoreEvent::MigrationComplete)),
+            566 => Some(EventType::Store(StoreEvent::RedbError)),
+            567 => Some(EventType::Store(StoreEvent::ConsistencyCheck)),
+            568 => Some(EventType::Store(StoreEvent::ConsistencyIssue)),
+            569 => Some(EventType::Store(StoreEvent::BlobPromoted)),
+            570 => Some(EventType::Store(StoreEvent::BlobDemoted)),
+            571 => Some(EventType::Store(StoreEvent::SlowOperation)),
+            572 => Some(EventType::Store(StoreEvent::CacheHit)),
+            573 => Some(EventType::Store(StoreEvent::CacheMiss)),
+            574 => Some(EventType::Housekeeper(HousekeeperEvent::WakeSnoozed)),
             _ => None,
         }
     }
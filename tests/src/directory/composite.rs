@@ -0,0 +1,98 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::{backend::internal::manage::ManageDirectory, QueryBy};
+use mail_send::Credentials;
+
+use crate::directory::{map_account_ids, DirectoryTest, IntoTestPrincipal};
+
+#[tokio::test]
+async fn composite_directory() {
+    // Obtain directory handle
+    let mut config = DirectoryTest::new("sqlite".into()).await;
+    let handle = config.directories.directories.remove("chain").unwrap();
+    let base_store = config.stores.stores.get("sqlite").unwrap();
+
+    // Credentials are only verified by the authentication backend
+    assert!(handle
+        .query(
+            QueryBy::Credentials(&Credentials::Plain {
+                username: "john".to_string(),
+                secret: "12345".to_string()
+            }),
+            true
+        )
+        .await
+        .unwrap()
+        .is_some());
+    assert!(handle
+        .query(
+            QueryBy::Credentials(&Credentials::Plain {
+                username: "mike".to_string(),
+                secret: "mikepass".to_string()
+            }),
+            true
+        )
+        .await
+        .unwrap()
+        .is_none());
+    assert!(handle
+        .query(
+            QueryBy::Credentials(&Credentials::Plain {
+                username: "jane".to_string(),
+                secret: "fghij".to_string()
+            }),
+            true
+        )
+        .await
+        .unwrap()
+        .is_none());
+
+    // Lookups fall through to the next backend
+    let mike = handle
+        .query(QueryBy::Name("mike"), true)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mike.name(), "mike");
+    assert_eq!(
+        mike.id(),
+        base_store.get_principal_id("mike").await.unwrap().unwrap()
+    );
+
+    // Group memberships are merged across backends
+    let mut member_of = handle
+        .query(QueryBy::Name("jane"), true)
+        .await
+        .unwrap()
+        .unwrap()
+        .into_test()
+        .member_of;
+    member_of.sort_unstable();
+    let mut expected = map_account_ids(base_store, vec!["sales", "support", "engineering"])
+        .await
+        .into_iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>();
+    expected.sort_unstable();
+    assert_eq!(member_of, expected);
+
+    // Recipient and domain lookups are performed on all backends
+    assert!(handle.rcpt("john@example.org").await.unwrap());
+    assert!(handle.rcpt("mike@example.net").await.unwrap());
+    assert!(!handle.rcpt("unknown@example.net").await.unwrap());
+    assert!(handle.is_local_domain("example.org").await.unwrap());
+    assert!(handle.is_local_domain("example.net").await.unwrap());
+    assert!(!handle.is_local_domain("other.net").await.unwrap());
+    assert_eq!(
+        handle.email_to_ids("mike@example.net").await.unwrap(),
+        vec![mike.id()]
+    );
+    assert_eq!(
+        handle.email_to_ids("jane@example.org").await.unwrap(),
+        map_account_ids(base_store, vec!["jane"]).await
+    );
+}
@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::time::Duration;
+
 use ahash::AHashSet;
 use directory::{
     backend::internal::{
@@ -11,13 +13,17 @@ use directory::{
         manage::{self, ManageDirectory, UpdatePrincipal},
         PrincipalField, PrincipalUpdate, PrincipalValue,
     },
+    core::{
+        policy::{BreachedPasswords, PasswordPolicy},
+        secret::is_password_hash,
+    },
     Principal, QueryBy, Type,
 };
 use jmap_proto::types::collection::Collection;
 use mail_send::Credentials;
 use store::{
     roaring::RoaringBitmap,
-    write::{BatchBuilder, BitmapClass, ValueClass},
+    write::{now, BatchBuilder, BitmapClass, ValueClass},
     BitmapKey, Store, ValueKey,
 };
 
@@ -703,6 +709,196 @@ async fn internal_directory() {
     }
 }
 
+#[tokio::test]
+async fn password_policy() {
+    let config = DirectoryTest::new(None).await;
+
+    // Create a breached password range file
+    let breached_path = config.temp_dir.path.join("breached");
+    std::fs::create_dir_all(&breached_path).unwrap();
+    std::fs::write(
+        breached_path.join("B2E98.txt"),
+        "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\nAD6F6EB8508DD6A14CFA704BAD7F05F6FB1:250\r\n",
+    )
+    .unwrap();
+    let policy = PasswordPolicy {
+        min_length: 8,
+        max_length: 64,
+        require_lowercase: false,
+        require_uppercase: true,
+        require_digit: true,
+        require_special: false,
+        allow_hashed: false,
+        history: 2,
+        max_age: Some(Duration::from_secs(30 * 86400)),
+        breached: Some(BreachedPasswords {
+            path: breached_path,
+            min_count: 1,
+        }),
+    };
+
+    for (store_id, store) in config
+        .stores
+        .stores
+        .into_iter()
+        .filter(|(id, _)| id == "sqlite")
+    {
+        println!("Testing password policy with store {:?}", store_id);
+        store.destroy().await;
+
+        let account_id = store
+            .create_principal(
+                TestPrincipal {
+                    name: "jdoe".to_string(),
+                    secrets: vec!["Initial-pass1".to_string()],
+                    ..Default::default()
+                }
+                .into(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let principal = store.get_principal(account_id).await.unwrap().unwrap();
+        assert!(principal.get_int(PrincipalField::PasswordChanged).is_some());
+        assert!(!policy.is_expired(&principal));
+
+        // Passwords not complying with the policy should fail
+        for (password, rule) in [
+            ("Short1", "minLength"),
+            ("longenough1", "uppercase"),
+            ("Longenough", "digit"),
+            ("Password123", "breached"),
+            ("Initial-pass1", "history"),
+            // Secrets that only look like hashes are validated as passwords
+            ("$short", "minLength"),
+            ("{SSHA}S1", "minLength"),
+            (BCRYPT_HASH, "hashed"),
+        ] {
+            let err = set_password(&store, account_id, password, Some(&policy))
+                .await
+                .unwrap_err();
+            assert!(
+                err.matches(trc::EventType::Manage(trc::ManageEvent::PasswordPolicy)),
+                "{password}: {err:?}"
+            );
+            assert_eq!(err.value_as_str(trc::Key::Key), Some(rule), "{password}");
+        }
+
+        // Well-formed hashes are accepted only when explicitly allowed
+        let allow_hashed = PasswordPolicy {
+            allow_hashed: true,
+            ..policy.clone()
+        };
+        set_password(&store, account_id, "$2y$10$short", Some(&allow_hashed))
+            .await
+            .unwrap_err();
+        set_password(&store, account_id, BCRYPT_HASH, Some(&allow_hashed))
+            .await
+            .unwrap();
+
+        // Only the last two passwords are remembered
+        for password in ["Second-pass2", "Third-pass3", "Fourth-pass4"] {
+            set_password(&store, account_id, password, Some(&policy))
+                .await
+                .unwrap();
+        }
+        for password in ["Fourth-pass4", "Third-pass3", "Second-pass2"] {
+            assert_eq!(
+                set_password(&store, account_id, password, Some(&policy))
+                    .await
+                    .unwrap_err()
+                    .value_as_str(trc::Key::Key),
+                Some("history")
+            );
+        }
+        set_password(&store, account_id, "Initial-pass1", Some(&policy))
+            .await
+            .unwrap();
+        let principal = store.get_principal(account_id).await.unwrap().unwrap();
+        assert_eq!(
+            principal.get_str_array(PrincipalField::Secrets),
+            Some(&["Initial-pass1".to_string()][..])
+        );
+        assert_eq!(
+            principal.get_str_array(PrincipalField::PasswordHistory),
+            Some(&["Fourth-pass4".to_string(), "Third-pass3".to_string()][..])
+        );
+
+        // Passwords are not validated without a policy
+        set_password(&store, account_id, "short", None)
+            .await
+            .unwrap();
+
+        // Expired passwords and forced changes
+        for (changed, expected) in [(now() - 31 * 86400, true), (0, true), (now(), false)] {
+            store
+                .update_principal(UpdatePrincipal::by_id(account_id).with_updates(vec![
+                    PrincipalUpdate::set(
+                        PrincipalField::PasswordChanged,
+                        PrincipalValue::Integer(changed),
+                    ),
+                ]))
+                .await
+                .unwrap();
+            assert_eq!(
+                policy.is_expired(&store.get_principal(account_id).await.unwrap().unwrap()),
+                expected
+            );
+        }
+    }
+}
+
+const BCRYPT_HASH: &str = "$2y$10$.vGA1O9wmRjrwAVXD98HNOgsNpDczlqm3Jq7KnEd1rVAGv3Fykk1a";
+
+#[test]
+fn password_hash_formats() {
+    for (secret, expected) in [
+        (BCRYPT_HASH, true),
+        ("$1$saltsalt$qjXMvbEw8oaL.CzflDugX/", true),
+        ("{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=", true),
+        ("{SSHA}5en6G6MezRroT3XKqkdPOmY/BfQtaWNO", true),
+        ("{CRYPT}abJnggxhB/yWI", true),
+        (
+            "$argon2id$v=19$m=19456,t=2,p=1$c29tZXNhbHQ$RdescudvJCsgt3ub+b+dWRWJTmaaJObG",
+            true,
+        ),
+        ("$short", false),
+        ("$2y$10$short", false),
+        ("_short", false),
+        ("{SSHA}", false),
+        ("{PLAIN}secret", false),
+        ("{UNKNOWN}secret", false),
+        ("secret", false),
+    ] {
+        assert_eq!(is_password_hash(secret), expected, "{secret}");
+    }
+}
+
+async fn set_password(
+    store: &Store,
+    account_id: u32,
+    password: &str,
+    policy: Option<&PasswordPolicy>,
+) -> trc::Result<()> {
+    store
+        .update_principal(
+            UpdatePrincipal::by_id(account_id)
+                .with_updates(vec![
+                    PrincipalUpdate::remove_item(
+                        PrincipalField::Secrets,
+                        PrincipalValue::String(String::new()),
+                    ),
+                    PrincipalUpdate::add_item(
+                        PrincipalField::Secrets,
+                        PrincipalValue::String(password.to_string()),
+                    ),
+                ])
+                .with_password_policy(policy),
+        )
+        .await
+}
+
 #[allow(async_fn_in_trait)]
 pub trait TestInternalDirectory {
     async fn create_test_user(&self, login: &str, secret: &str, name: &str, emails: &[&str])
@@ -6,7 +6,10 @@
 
 use std::fmt::Debug;
 
-use directory::{backend::internal::manage::ManageDirectory, QueryBy, Type, ROLE_USER};
+use directory::{
+    backend::internal::{manage::ManageDirectory, PrincipalField},
+    Permission, QueryBy, Type, ROLE_USER,
+};
 use mail_send::Credentials;
 
 use crate::directory::{map_account_ids, DirectoryTest, IntoTestPrincipal, TestPrincipal};
@@ -103,7 +106,7 @@ async fn ldap_directory() {
         .unwrap()
         .is_none());
 
-    // Get user by name
+    // Get user by name, 'support' is listed by name and resolved with the group filter
     assert_eq!(
         handle
             .query(QueryBy::Name("jane"), true)
@@ -130,6 +133,23 @@ async fn ldap_directory() {
         .into_sorted()
     );
 
+    // Group policies should be applied to members
+    assert!(handle
+        .query(QueryBy::Name("jane"), true)
+        .await
+        .unwrap()
+        .unwrap()
+        .has_int_value(
+            PrincipalField::EnabledPermissions,
+            Permission::Impersonate.id() as u64
+        ));
+    assert!(!handle
+        .query(QueryBy::Name("john"), true)
+        .await
+        .unwrap()
+        .unwrap()
+        .has_field(PrincipalField::EnabledPermissions));
+
     // Get group by name
     assert_eq!(
         handle
@@ -4,6 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod composite;
 pub mod imap;
 pub mod internal;
 pub mod ldap;
@@ -153,6 +154,7 @@ email = "(&(|(objectClass=posixAccount)(objectClass=posixGroup))(|(mail=?)(given
 verify = "(&(|(objectClass=posixAccount)(objectClass=posixGroup))(|(mail=*?*)(givenName=*?*)))"
 expand = "(&(|(objectClass=posixAccount)(objectClass=posixGroup))(sn=?))"
 domains = "(&(|(objectClass=posixAccount)(objectClass=posixGroup))(|(mail=*@?)(givenName=*@?)(sn=*@?)))"
+group = "(&(objectClass=posixGroup)(ou=?))"
 
 # Glauth does not support searchable custom attributes so
 # 'sn' and 'givenName' are used to search for aliases/lists.
@@ -167,6 +169,18 @@ email-alias = "givenName"
 quota = "diskQuota"
 class = "objectClass"
 
+[directory."ldap".groups.nested]
+enable = true
+max-depth = 4
+
+[directory."ldap".groups.cache]
+size = 100
+ttl = "1m"
+
+[directory."ldap".groups.policy."support"]
+group = "support"
+enabled-permissions = ["impersonate"]
+
 ##############################################################################
 
 [directory."imap"]
@@ -245,6 +259,33 @@ name = "support"
 class = "group"
 description = "Support Team"
 
+[directory."local-extra"]
+type = "memory"
+
+[[directory."local-extra".principals]]
+name = "mike"
+class = "individual"
+description = "Mike Smith"
+secret = "mikepass"
+email = "mike@example.net"
+member-of = ["engineering"]
+
+[[directory."local-extra".principals]]
+name = "jane"
+class = "individual"
+description = "Jane Doe"
+secret = "fghij"
+email = "jane@example.org"
+member-of = ["engineering"]
+
+[directory."chain"]
+type = "composite"
+backends = ["local", "local-extra"]
+strategy = "lookup-all"
+auth.backend = "local"
+merge.member-of = true
+timeout.default = "5s"
+
 ##############################################################################
 
 [directory."oidc-userinfo"]
@@ -304,6 +345,21 @@ fields.email = "email"
 fields.username = "preferred_username"
 fields.full-name = "name"
 
+[directory."oidc-provisioning"]
+type = "oidc"
+store = "rocksdb"
+timeout = "1s"
+endpoint.url = "https://127.0.0.1:9090/userinfo-provisioning"
+endpoint.method = "userinfo"
+fields.email = "email"
+fields.username = "preferred_username"
+fields.quota = "quota"
+fields.roles = "groups"
+provisioning.enable = true
+provisioning.role.admins.value = "mail-admins"
+provisioning.role.admins.roles = ["admin"]
+provisioning.deprovision.after = "30d"
+
 "#;
 
 pub struct DirectoryStore {
@@ -348,8 +404,12 @@ impl DirectoryTest {
                 )
         } else {
             // Disable internal store
-            config_file =
-                config_file.replace("type = \"memory\"", "type = \"memory\"\ndisable = true")
+            config_file = config_file
+                .replace("type = \"memory\"", "type = \"memory\"\ndisable = true")
+                .replace(
+                    "type = \"composite\"",
+                    "type = \"composite\"\ndisable = true",
+                )
         }
         let mut config = utils::config::Config::new(&config_file).unwrap();
         let stores = Stores::parse_all(&mut config).await;
@@ -11,7 +11,13 @@
 use std::sync::Arc;
 
 use base64::{engine::general_purpose, Engine};
-use directory::{backend::internal::PrincipalField, QueryBy};
+use directory::{
+    backend::internal::{
+        manage::{ManageDirectory, UpdatePrincipal},
+        PrincipalField, PrincipalUpdate, PrincipalValue,
+    },
+    Permission, QueryBy, Type, ROLE_ADMIN,
+};
 use hyper::{Method, StatusCode};
 use jmap::api::{http::ToHttpResponse, JsonResponse};
 use mail_send::Credentials;
@@ -19,7 +25,7 @@ use serde_json::json;
 use trc::{AuthEvent, EventType};
 
 use crate::{
-    directory::DirectoryTest,
+    directory::{internal::TestInternalDirectory, DirectoryTest},
     http_server::{spawn_mock_http_server, HttpMessage},
 };
 
@@ -88,6 +94,13 @@ async fn oidc_directory() {
                 Some(_) => StatusCode::UNAUTHORIZED.into_http_response(),
                 None => panic!("Missing Authorization header: {req:#?}"),
             },
+            (Method::GET, Some("userinfo-provisioning")) => JsonResponse::new(json!({
+                "email": "jane@example.org",
+                "preferred_username": "jane.doe",
+                "quota": 1024,
+                "groups": ["mail-admins", "staff"],
+            }))
+            .into_http_response(),
             _ => panic!("Unexpected request: {:?}", req),
         }
     }))
@@ -137,4 +150,107 @@ async fn oidc_directory() {
         );
         assert_eq!(principal.description(), Some("John Doe"));
     }
+
+    // Accounts are provisioned on login
+    println!("Running OIDC provisioning test...");
+    let directory = config
+        .directories
+        .directories
+        .remove("oidc-provisioning")
+        .unwrap();
+    let store = config.stores.stores.get("rocksdb").unwrap();
+    let principal = directory
+        .query(
+            QueryBy::Credentials(&Credentials::OAuthBearer {
+                token: TEST_TOKEN.to_string(),
+            }),
+            true,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(principal.name(), "jane.doe");
+    assert_eq!(principal.quota(), 1024);
+    assert_eq!(
+        principal
+            .iter_int(PrincipalField::Roles)
+            .collect::<Vec<_>>(),
+        vec![ROLE_ADMIN as u64]
+    );
+    let stored = store.get_principal(principal.id()).await.unwrap().unwrap();
+    assert_eq!(stored.quota(), 1024);
+    assert!(stored.get_int(PrincipalField::LastLogin).is_some());
+    assert_eq!(
+        store
+            .get_member_of(principal.id())
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|m| m.typ == Type::Role)
+            .map(|m| m.principal_id)
+            .collect::<Vec<_>>(),
+        vec![ROLE_ADMIN]
+    );
+
+    assert_eq!(
+        stored.get_str(PrincipalField::ProvisionedBy),
+        Some("oidc-provisioning")
+    );
+
+    // Recently seen accounts are not deprovisioned
+    assert_eq!(directory.deprovision().await.unwrap(), Vec::<u32>::new());
+
+    // Inactive accounts are disabled, unless they were not provisioned by this directory
+    let other_id = store
+        .create_test_user("bill", "12345", "Bill Foobar", &["bill@example.org"])
+        .await;
+    for account_id in [principal.id(), other_id] {
+        store
+            .update_principal(UpdatePrincipal::by_id(account_id).with_updates(vec![
+                PrincipalUpdate::set(PrincipalField::LastLogin, PrincipalValue::Integer(1)),
+            ]))
+            .await
+            .unwrap();
+    }
+    assert_eq!(directory.deprovision().await.unwrap(), vec![principal.id()]);
+    for (account_id, is_disabled) in [(principal.id(), true), (other_id, false)] {
+        assert_eq!(
+            store
+                .get_principal(account_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .has_int_value(
+                    PrincipalField::DisabledPermissions,
+                    Permission::Authenticate.id() as u64
+                ),
+            is_disabled
+        );
+    }
+    assert_eq!(directory.deprovision().await.unwrap(), Vec::<u32>::new());
+
+    // Logging in through the identity provider re-enables the account
+    let principal = directory
+        .query(
+            QueryBy::Credentials(&Credentials::OAuthBearer {
+                token: TEST_TOKEN.to_string(),
+            }),
+            true,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!principal.has_int_value(
+        PrincipalField::DisabledPermissions,
+        Permission::Authenticate.id() as u64
+    ));
+    assert!(!store
+        .get_principal(principal.id())
+        .await
+        .unwrap()
+        .unwrap()
+        .has_int_value(
+            PrincipalField::DisabledPermissions,
+            Permission::Authenticate.id() as u64
+        ));
 }
@@ -0,0 +1,193 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{io::Cursor, path::PathBuf, sync::Arc, time::Duration};
+
+use imap_proto::ResponseType;
+use rustls::ClientConfig;
+use rustls_pki_types::ServerName;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines, ReadHalf, WriteHalf},
+    net::TcpStream,
+};
+use tokio_rustls::{client::TlsStream, TlsConnector};
+use utils::DummyVerifier;
+
+use super::AssertResult;
+
+pub async fn test() {
+    println!("Running SASL EXTERNAL tests...");
+
+    // Without a client certificate EXTERNAL is neither offered nor accepted
+    let mut imap = ExternalConnection::connect(None).await.unwrap();
+    imap.assert_greeting().await;
+    imap.send("CAPABILITY").await;
+    imap.assert_read(ResponseType::Ok)
+        .await
+        .assert_count("AUTH=EXTERNAL", 0);
+    imap.send("AUTHENTICATE EXTERNAL =").await;
+    imap.assert_read(ResponseType::No).await;
+
+    // Certificates not issued by the client CA are rejected during the handshake
+    if let Some(mut imap) = ExternalConnection::connect(Some("rogue")).await {
+        imap.assert_rejected().await;
+    }
+
+    // Certificates issued by the client CA for unknown identities fail to authenticate
+    let mut imap = ExternalConnection::connect(Some("unknown")).await.unwrap();
+    imap.assert_greeting().await;
+    imap.send("CAPABILITY").await;
+    imap.assert_read(ResponseType::Ok)
+        .await
+        .assert_contains("AUTH=EXTERNAL");
+    imap.send("AUTHENTICATE EXTERNAL =").await;
+    imap.assert_read(ResponseType::No).await;
+
+    // Authorization identities other than the certificate's are refused
+    let mut imap = ExternalConnection::connect(Some("jdoe")).await.unwrap();
+    imap.assert_greeting().await;
+    imap.send("AUTHENTICATE EXTERNAL amFuZS5zbWl0aEBleGFtcGxlLmNvbQ==")
+        .await;
+    imap.assert_read(ResponseType::No).await;
+
+    // Authenticate with the identity from the certificate, using an initial response
+    imap.send("AUTHENTICATE EXTERNAL =").await;
+    imap.assert_read(ResponseType::Ok).await;
+    imap.send("LIST \"\" \"INBOX\"").await;
+    imap.assert_read(ResponseType::Ok)
+        .await
+        .assert_contains("INBOX");
+    imap.send("LOGOUT").await;
+
+    // Authenticate with a matching authorization identity, using a continuation
+    let mut imap = ExternalConnection::connect(Some("jdoe")).await.unwrap();
+    imap.assert_greeting().await;
+    imap.send("AUTHENTICATE EXTERNAL").await;
+    imap.assert_continuation().await;
+    imap.send_untagged("amRvZUBleGFtcGxlLmNvbQ==").await;
+    imap.assert_read(ResponseType::Ok).await;
+    imap.send("LOGOUT").await;
+}
+
+pub fn client_cert_path(name: &str) -> String {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push("tls_client");
+    path.push(name);
+    path.to_str().unwrap().to_string()
+}
+
+pub struct ExternalConnection {
+    reader: Lines<BufReader<ReadHalf<TlsStream<TcpStream>>>>,
+    writer: WriteHalf<TlsStream<TcpStream>>,
+}
+
+impl ExternalConnection {
+    pub async fn connect(cert: Option<&str>) -> Option<Self> {
+        let config = ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(DummyVerifier));
+        let config = if let Some(cert) = cert {
+            let certs = rustls_pemfile::certs(&mut Cursor::new(
+                std::fs::read(client_cert_path(&format!("{cert}_cert.pem"))).unwrap(),
+            ))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+            let key = rustls_pemfile::private_key(&mut Cursor::new(
+                std::fs::read(client_cert_path(&format!("{cert}_key.pem"))).unwrap(),
+            ))
+            .unwrap()
+            .unwrap();
+            config.with_client_auth_cert(certs, key).unwrap()
+        } else {
+            config.with_no_client_auth()
+        };
+
+        let (reader, writer) = tokio::io::split(
+            TlsConnector::from(Arc::new(config))
+                .connect(
+                    ServerName::try_from("imap.example.org").unwrap().to_owned(),
+                    TcpStream::connect("127.0.0.1:9993").await.unwrap(),
+                )
+                .await
+                .ok()?,
+        );
+        Some(ExternalConnection {
+            reader: BufReader::new(reader).lines(),
+            writer,
+        })
+    }
+
+    pub async fn assert_greeting(&mut self) {
+        match tokio::time::timeout(Duration::from_millis(1500), self.reader.next_line()).await {
+            Ok(Ok(Some(line))) if line.starts_with("* OK") => {}
+            result => panic!("Expected greeting from server but got: {:?}", result),
+        }
+    }
+
+    pub async fn assert_read(&mut self, rt: ResponseType) -> Vec<String> {
+        let lines = self.read().await;
+        let mut buf = b"A1 ".to_vec();
+        rt.serialize(&mut buf);
+        if lines
+            .last()
+            .unwrap()
+            .starts_with(&String::from_utf8(buf).unwrap())
+        {
+            lines
+        } else {
+            panic!("Expected {:?} from server but got: {:?}", rt, lines);
+        }
+    }
+
+    pub async fn assert_continuation(&mut self) {
+        match tokio::time::timeout(Duration::from_millis(1500), self.reader.next_line()).await {
+            Ok(Ok(Some(line))) if line.starts_with('+') => {}
+            result => panic!("Expected continuation from server but got: {:?}", result),
+        }
+    }
+
+    pub async fn assert_rejected(&mut self) {
+        // With TLS 1.3 the client finishes its handshake before the server
+        // verifies the certificate, so the rejection shows up on the first read
+        match tokio::time::timeout(Duration::from_millis(1500), self.reader.next_line()).await {
+            Ok(Ok(None)) | Ok(Err(_)) => {}
+            result => panic!("Expected certificate to be rejected but got: {:?}", result),
+        }
+    }
+
+    pub async fn read(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            match tokio::time::timeout(Duration::from_millis(1500), self.reader.next_line()).await {
+                Ok(Ok(Some(line))) => {
+                    let is_done = line.starts_with("A1 ");
+                    lines.push(line);
+                    if is_done {
+                        return lines;
+                    }
+                }
+                Ok(Ok(None)) => {
+                    panic!("Invalid response: {:?}.", lines);
+                }
+                Ok(Err(err)) => {
+                    panic!("Connection broken: {} ({:?})", err, lines);
+                }
+                Err(_) => panic!("Timeout while waiting for server response: {:?}", lines),
+            }
+        }
+    }
+
+    pub async fn send(&mut self, text: &str) {
+        self.writer.write_all(b"A1 ").await.unwrap();
+        self.send_untagged(text).await;
+    }
+
+    pub async fn send_untagged(&mut self, text: &str) {
+        self.writer.write_all(text.as_bytes()).await.unwrap();
+        self.writer.write_all(b"\r\n").await.unwrap();
+    }
+}
@@ -10,6 +10,7 @@ pub mod basic;
 pub mod body_structure;
 pub mod condstore;
 pub mod copy_move;
+pub mod external;
 pub mod fetch;
 pub mod idle;
 pub mod mailbox;
@@ -69,6 +70,14 @@ protocol = "imap"
 max-connections = 81920
 tls.implicit = true
 
+[server.listener.imapcert]
+bind = ["127.0.0.1:9993"]
+protocol = "imap"
+max-connections = 81920
+tls.implicit = true
+tls.client-auth = "optional"
+tls.client-ca = "%{file:{CLIENT_CA}}%"
+
 [server.listener.sieve]
 bind = ["127.0.0.1:4190"]
 protocol = "managesieve"
@@ -144,6 +153,10 @@ path = "{TMP}/sqlite.db"
 type = "rocksdb"
 path = "{TMP}/rocks.db"
 
+[store."redb"]
+type = "redb"
+path = "{TMP}/redb"
+
 [store."foundationdb"]
 type = "foundationdb"
 
@@ -185,6 +198,9 @@ private-key = "%{file:{PK}}%"
 [imap.protocol]
 uidplus = true
 
+[authentication.external]
+enable = true
+
 [storage]
 data = "{STORE}"
 fts = "{STORE}"
@@ -291,6 +307,7 @@ async fn init_imap_tests(store_id: &str, delete_if_exists: bool) -> IMAPTest {
     let temp_dir = TempDir::new("imap_tests", delete_if_exists);
     let mut config = Config::new(
         add_test_certs(SERVER)
+            .replace("{CLIENT_CA}", &external::client_cert_path("ca.pem"))
             .replace("{STORE}", store_id)
             .replace("{TMP}", &temp_dir.path.display().to_string())
             .replace(
@@ -488,6 +505,9 @@ pub async fn imap_tests() {
     // Run POP3 tests
     pop::test().await;
 
+    // Run SASL EXTERNAL tests
+    external::test().await;
+
     // Print elapsed time
     let elapsed = start_time.elapsed();
     println!(
@@ -0,0 +1,309 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, mailbox::destroy_all_mailboxes, wait_for_index},
+    store::TempDir,
+};
+use common::manager::manifest::BackupKind;
+use jmap::{changes::write::ChangeLog, mailbox::INBOX_ID, JmapMethods};
+use jmap_client::{
+    core::query::Filter,
+    email,
+    mailbox::{self, Role},
+};
+use jmap_proto::types::{id::Id, state::State};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running account backup tests...");
+    let server = params.server.clone();
+    let mut account_ids = Vec::new();
+
+    for (email, name) in [
+        ("john.backup@example.com", "John Doe"),
+        ("jane.backup@example.com", "Jane Smith"),
+        ("bill.backup@example.com", "Bill Foobar"),
+    ] {
+        account_ids.push(
+            server
+                .core
+                .storage
+                .data
+                .create_test_user(email, "secret", name, &[email][..])
+                .await,
+        );
+    }
+    let (john_id, jane_id, bill_id) = (account_ids[0], account_ids[1], account_ids[2]);
+
+    // Populate John's account
+    let client = &mut params.client;
+    client.set_default_account_id(Id::from(john_id).to_string());
+    let archive_id = client
+        .mailbox_create("Archive", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+    let reports_id = client
+        .mailbox_create("Reports", Some(&archive_id), Role::None)
+        .await
+        .unwrap()
+        .take_id();
+    let inbox_id = Id::from(INBOX_ID).to_string();
+    let mut email_ids = Vec::new();
+    for (raw, mailbox_ids) in [
+        (
+            concat!(
+                "From: bill@example.com\r\n",
+                "To: john.backup@example.com\r\n",
+                "Message-ID: <tps-report@example.com>\r\n",
+                "Subject: TPS Report\r\n",
+                "\r\n",
+                "Please remember the new cover sheets on the TPS reports.\r\n"
+            ),
+            vec![inbox_id.clone(), reports_id.clone()],
+        ),
+        (
+            concat!(
+                "From: john.backup@example.com\r\n",
+                "To: bill@example.com\r\n",
+                "Message-ID: <tps-report-reply@example.com>\r\n",
+                "In-Reply-To: <tps-report@example.com>\r\n",
+                "Subject: Re: TPS Report\r\n",
+                "\r\n",
+                "I already sent the TPS reports with the new cover sheets.\r\n"
+            ),
+            vec![reports_id.clone()],
+        ),
+        (
+            concat!(
+                "From: jane@example.com\r\n",
+                "To: john.backup@example.com\r\n",
+                "Subject: Lunch\r\n",
+                "\r\n",
+                "Are we still on for lunch?\r\n"
+            ),
+            vec![inbox_id.clone()],
+        ),
+    ] {
+        email_ids.push(
+            client
+                .email_import(
+                    raw.as_bytes().to_vec(),
+                    mailbox_ids,
+                    None::<Vec<String>>,
+                    None,
+                )
+                .await
+                .unwrap()
+                .take_id(),
+        );
+    }
+    client
+        .identity_create("John Doe", "john.backup@example.com")
+        .await
+        .unwrap();
+    wait_for_index(&server).await;
+    let john_quota = server.get_used_quota(john_id).await.unwrap();
+    assert!(john_quota > 0);
+
+    // Export John's account
+    let temp_dir = TempDir::new("account_backup_tests", true);
+    let manifest = server
+        .core
+        .export_account(john_id, temp_dir.path.join("john"))
+        .await
+        .unwrap();
+    assert_eq!(manifest.kind, BackupKind::Account);
+    assert_eq!(manifest.accounts, vec![john_id]);
+    assert_eq!(manifest.quota, Some(john_quota));
+
+    // Restore John's backup into Jane's account, which already has mailboxes
+    client.set_default_account_id(Id::from(jane_id).to_string());
+    let jane_quota = server.get_used_quota(jane_id).await.unwrap();
+    assert!(!client
+        .mailbox_query(None::<mailbox::query::Filter>, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .ids()
+        .is_empty());
+    let changes = server
+        .core
+        .import_account(&temp_dir.path.join("john"), jane_id)
+        .await
+        .unwrap();
+    server.commit_changes(jane_id, changes).await.unwrap();
+    wait_for_index(&server).await;
+    assert_eq!(
+        server.get_used_quota(jane_id).await.unwrap(),
+        jane_quota + john_quota
+    );
+
+    // Mailboxes are assigned new ids, roles are not duplicated
+    let mut mailboxes = Vec::new();
+    for id in client
+        .mailbox_query(None::<mailbox::query::Filter>, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .take_ids()
+    {
+        mailboxes.push(
+            client
+                .mailbox_get(&id, None::<Vec<_>>)
+                .await
+                .unwrap()
+                .unwrap(),
+        );
+    }
+    assert_eq!(
+        mailboxes
+            .iter()
+            .filter(|mailbox| mailbox.role() == Role::Inbox)
+            .count(),
+        1
+    );
+    assert_eq!(
+        mailboxes
+            .iter()
+            .filter(|mailbox| mailbox.name() == Some("Inbox"))
+            .count(),
+        2
+    );
+    let restored_archive = mailboxes
+        .iter()
+        .find(|mailbox| mailbox.name() == Some("Archive"))
+        .unwrap();
+    let restored_reports = mailboxes
+        .iter()
+        .find(|mailbox| mailbox.name() == Some("Reports"))
+        .unwrap();
+    assert_eq!(restored_reports.parent_id(), restored_archive.id());
+    assert_ne!(restored_archive.id(), Some(archive_id.as_str()));
+
+    // Emails are linked to the restored mailboxes and keep their threads
+    let restored_email_ids = client
+        .email_query(None::<email::query::Filter>, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .take_ids();
+    assert_eq!(restored_email_ids.len(), 3);
+    let mut thread_ids = Vec::new();
+    for id in &restored_email_ids {
+        let email = client.email_get(id, None::<Vec<_>>).await.unwrap().unwrap();
+        assert!(!email.mailbox_ids().is_empty());
+        assert!(!email.mailbox_ids().contains(&inbox_id.as_str()));
+        if email.subject().map_or(false, |s| s.contains("TPS")) {
+            thread_ids.push(email.thread_id().unwrap().to_string());
+        }
+        assert_eq!(
+            client
+                .download(email.blob_id().unwrap())
+                .await
+                .unwrap()
+                .len(),
+            email.size()
+        );
+    }
+    assert_eq!(thread_ids.len(), 2);
+    assert_eq!(thread_ids[0], thread_ids[1]);
+    assert_eq!(
+        client
+            .email_query(
+                Filter::and([
+                    email::query::Filter::text("TPS"),
+                    email::query::Filter::in_mailbox(restored_reports.id().unwrap()),
+                ])
+                .into(),
+                None::<Vec<_>>,
+            )
+            .await
+            .unwrap()
+            .ids()
+            .len(),
+        2
+    );
+
+    // Restored records are reported as created
+    let changes = client
+        .email_changes(State::Initial.to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(changes.created().len(), 3);
+    let mut request = client.build();
+    request.get_identity();
+    assert_eq!(
+        request
+            .send_get_identity()
+            .await
+            .unwrap()
+            .list()
+            .iter()
+            .filter(|identity| identity.email() == Some("john.backup@example.com"))
+            .count(),
+        1
+    );
+
+    // Restoring into an empty account preserves the original ids
+    client.set_default_account_id(Id::from(bill_id).to_string());
+    let changes = server
+        .core
+        .import_account(&temp_dir.path.join("john"), bill_id)
+        .await
+        .unwrap();
+    server.commit_changes(bill_id, changes).await.unwrap();
+    let mut restored_email_ids = client
+        .email_query(None::<email::query::Filter>, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .take_ids();
+    restored_email_ids.sort_unstable();
+    email_ids.sort_unstable();
+    assert_eq!(restored_email_ids, email_ids);
+    assert_eq!(server.get_used_quota(bill_id).await.unwrap(), john_quota);
+    let inbox = client
+        .mailbox_get(&inbox_id, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(inbox.role(), Role::Inbox);
+    assert_eq!(
+        client
+            .mailbox_get(&reports_id, None::<Vec<_>>)
+            .await
+            .unwrap()
+            .unwrap()
+            .parent_id(),
+        Some(archive_id.as_str())
+    );
+
+    // Missing backups are rejected
+    assert!(server
+        .core
+        .import_account(&temp_dir.path.join("missing"), bill_id)
+        .await
+        .is_err());
+
+    // Remove test data
+    for account_id in [john_id, jane_id, bill_id] {
+        params
+            .client
+            .set_default_account_id(Id::from(account_id).to_string());
+        let mut request = params.client.build();
+        request.get_identity();
+        for identity in request.send_get_identity().await.unwrap().take_list() {
+            params
+                .client
+                .identity_destroy(identity.id().unwrap())
+                .await
+                .unwrap();
+        }
+        destroy_all_mailboxes(params).await;
+    }
+    temp_dir.delete();
+    assert_is_empty(server).await;
+}
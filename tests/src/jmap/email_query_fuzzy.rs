@@ -0,0 +1,158 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_client::mailbox::Role;
+use jmap_proto::types::id::Id;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, jmap_json_request, mailbox::destroy_all_mailboxes, wait_for_index},
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Email/query fuzzy search tests...");
+    let server = params.server.clone();
+
+    // Create a test account
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    )
+    .to_string();
+    let client = &mut params.client;
+    client.set_default_account_id(&account_id);
+    let mailbox_id = client
+        .mailbox_create("Fuzzy", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+
+    // Import test messages
+    let mut email_ids = Vec::new();
+    for (subject, body) in [
+        (
+            "Quarterly budget meeting",
+            "The meeting about the quarterly budget has been moved to Friday.",
+        ),
+        ("Lunch", "The notes from the meeting are attached."),
+        ("Holiday pictures", "Here are the photos from our trip."),
+    ] {
+        email_ids.push(
+            client
+                .email_import(
+                    format!(
+                        concat!(
+                            "From: Bill Foobar <bill@remote.org>\r\n",
+                            "To: jdoe@example.com\r\n",
+                            "Subject: {}\r\n",
+                            "\r\n",
+                            "{}\r\n"
+                        ),
+                        subject, body
+                    )
+                    .into_bytes(),
+                    [&mailbox_id],
+                    None::<Vec<&str>>,
+                    None,
+                )
+                .await
+                .unwrap()
+                .take_id(),
+        );
+    }
+    wait_for_index(&server).await;
+
+    for (filter, is_fuzzy, expected_ids) in [
+        // Misspelled words only match when fuzzy search is enabled
+        (r#"{"text": "meetnig"}"#, false, vec![]),
+        (
+            r#"{"text": "meetnig"}"#,
+            true,
+            vec![email_ids[0].as_str(), email_ids[1].as_str()],
+        ),
+        (
+            r#"{"body": "quartely budgte"}"#,
+            true,
+            vec![email_ids[0].as_str()],
+        ),
+        (
+            r#"{"subject": "holliday"}"#,
+            true,
+            vec![email_ids[2].as_str()],
+        ),
+        // Quoted phrases are always matched exactly
+        (r#"{"body": "\"meetnig\""}"#, true, vec![]),
+    ] {
+        let response = jmap_json_request(
+            r#"[[
+                "Email/query",
+                {
+                 "accountId": "$$",
+                 "filter": $FILTER,
+                 "sort": [{"property": "relevance", "isAscending": false}],
+                 "fuzzy": $FUZZY
+                },
+                "R1"
+               ]]"#
+            .replace("$$", &account_id)
+            .replace("$FILTER", filter)
+            .replace("$FUZZY", if is_fuzzy { "true" } else { "false" }),
+            "jdoe@example.com",
+            "12345",
+        )
+        .await;
+        let ids = response
+            .pointer("/methodResponses/0/1/ids")
+            .and_then(|ids| ids.as_array())
+            .unwrap_or_else(|| panic!("Unexpected response: {response:?}"))
+            .iter()
+            .map(|id| id.as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, expected_ids, "{filter} (fuzzy: {is_fuzzy})");
+    }
+
+    // Sorting by relevance ranks messages with more matches first
+    let response = jmap_json_request(
+        r#"[[
+            "Email/query",
+            {
+             "accountId": "$$",
+             "filter": {"text": "meeting"},
+             "sort": [{"property": "relevance", "isAscending": true}]
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/ids")
+            .and_then(|ids| ids.as_array())
+            .unwrap_or_else(|| panic!("Unexpected response: {response:?}"))
+            .iter()
+            .map(|id| id.as_str().unwrap())
+            .collect::<Vec<_>>(),
+        vec![email_ids[1].as_str(), email_ids[0].as_str()]
+    );
+
+    // Destroy test data
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
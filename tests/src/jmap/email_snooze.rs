@@ -0,0 +1,201 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use jmap::mailbox::INBOX_ID;
+use jmap_client::mailbox::Role;
+use jmap_proto::types::{date::UTCDate, id::Id};
+use store::write::now;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty, email_set::assert_email_properties, jmap_json_request,
+        mailbox::destroy_all_mailboxes,
+    },
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Email snooze tests...");
+    let server = params.server.clone();
+
+    // Create a test account
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    )
+    .to_string();
+    let client = &mut params.client;
+    client.set_default_account_id(&account_id);
+
+    // Import two messages into the snooze mailbox and a label
+    let inbox_id = Id::from(INBOX_ID).to_string();
+    let mailbox_id = client
+        .mailbox_create(
+            "Snoozed",
+            None::<String>,
+            Role::Other("snoozed".to_string()),
+        )
+        .await
+        .unwrap()
+        .take_id();
+    let label_id = client
+        .mailbox_create("Follow up", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+    let mut email_ids = Vec::new();
+    for subject in ["Call me later", "Unsnoozed by the user"] {
+        email_ids.push(
+            client
+                .email_import(
+                    format!(
+                        concat!(
+                            "From: Bill Foobar <bill@remote.org>\r\n",
+                            "To: jdoe@example.com\r\n",
+                            "Subject: {}\r\n",
+                            "\r\n",
+                            "Remind me about this.\r\n"
+                        ),
+                        subject
+                    )
+                    .into_bytes(),
+                    [&mailbox_id, &label_id],
+                    None::<Vec<&str>>,
+                    None,
+                )
+                .await
+                .unwrap()
+                .take_id(),
+        );
+    }
+
+    // Snooze both messages
+    let until = UTCDate::from_timestamp((now() + 2) as i64).to_string();
+    let response = jmap_json_request(
+        r#"[[
+            "Email/set",
+            {
+             "accountId": "$$",
+             "update": {
+              "$EMAIL1": {
+               "snoozed": {
+                "until": "$UNTIL",
+                "moveToMailboxId": "$INBOX"
+               }
+              },
+              "$EMAIL2": {
+               "snoozed": {
+                "until": "$UNTIL"
+               }
+              }
+             }
+            },
+            "R1"
+           ],
+           [
+            "Email/get",
+            {
+             "accountId": "$$",
+             "ids": [ "$EMAIL1" ],
+             "properties": [ "snoozed" ]
+            },
+            "R2"
+           ]]"#
+        .replace("$$", &account_id)
+        .replace("$EMAIL1", &email_ids[0])
+        .replace("$EMAIL2", &email_ids[1])
+        .replace("$UNTIL", &until)
+        .replace("$INBOX", &inbox_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    for email_id in &email_ids {
+        assert!(
+            response
+                .pointer(&format!("/methodResponses/0/1/updated/{email_id}"))
+                .is_some(),
+            "Response: {:?}",
+            response
+        );
+    }
+    for (pointer, expected) in [
+        ("/methodResponses/1/1/list/0/snoozed/until", until.as_str()),
+        (
+            "/methodResponses/1/1/list/0/snoozed/moveToMailboxId",
+            inbox_id.as_str(),
+        ),
+    ] {
+        assert_eq!(
+            response
+                .pointer(pointer)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+            expected,
+            "Response: {:?}",
+            response
+        );
+    }
+    for email_id in &email_ids {
+        assert_email_properties(client, email_id, &[&mailbox_id, &label_id], &["$snoozed"]).await;
+    }
+
+    // Removing the $snoozed keyword cancels the move
+    client
+        .email_set_keyword(&email_ids[1], "$snoozed", false)
+        .await
+        .unwrap();
+
+    // Wait for the housekeeper to wake up the message, only the snooze mailbox is replaced
+    tokio::time::sleep(Duration::from_secs(4)).await;
+    assert_email_properties(client, &email_ids[0], &[&inbox_id, &label_id], &[]).await;
+    assert_email_properties(client, &email_ids[1], &[&mailbox_id, &label_id], &[]).await;
+
+    // Snooze details are removed after waking up
+    let response = jmap_json_request(
+        r#"[[
+            "Email/get",
+            {
+             "accountId": "$$",
+             "ids": [ "$EMAIL1", "$EMAIL2" ],
+             "properties": [ "snoozed" ]
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id)
+        .replace("$EMAIL1", &email_ids[0])
+        .replace("$EMAIL2", &email_ids[1]),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    for idx in 0..2 {
+        assert!(
+            response
+                .pointer(&format!("/methodResponses/0/1/list/{idx}/snoozed"))
+                .is_some_and(|v| v.is_null()),
+            "Response: {:?}",
+            response
+        );
+    }
+
+    // Destroy test data
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
@@ -5,14 +5,19 @@
  */
 
 use ahash::AHashMap;
+use jmap::JmapMethods;
 use jmap_client::{
     core::set::{SetError, SetErrorType, SetObject},
     email_submission::{query::Filter, Address, Delivered, DeliveryStatus, Displayed, UndoStatus},
     mailbox::Role,
     Error,
 };
-use jmap_proto::types::id::Id;
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, id::Id, property::Property, value::Value},
+};
 use mail_parser::DateTime;
+use smtp::queue::spool::SmtpSpool;
 use std::{
     sync::Arc,
     time::{Duration, Instant},
@@ -27,7 +32,10 @@ use tokio::{
 
 use crate::{
     directory::internal::TestInternalDirectory,
-    jmap::{assert_is_empty, email_set::assert_email_properties, mailbox::destroy_all_mailboxes},
+    jmap::{
+        assert_is_empty, delivery::SmtpConnection, email_set::assert_email_properties,
+        jmap_json_request, mailbox::destroy_all_mailboxes,
+    },
 };
 
 use super::JMAPTest;
@@ -430,6 +438,391 @@ pub async fn test(params: &mut JMAPTest) {
         ),])
     );
 
+    // Pending submissions can be rescheduled and cancelled back to Drafts
+    let draft_id = client
+        .email_import(
+            email_body.as_bytes().to_vec(),
+            [&mailbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+    let scheduled_id = client
+        .email_submission_create_envelope(
+            &draft_id,
+            &identity_id,
+            Address::new("jdoe@example.com").parameter("HOLDUNTIL", Some(hold_until.to_string())),
+            ["jane_smith@remote.org"],
+        )
+        .await
+        .unwrap()
+        .take_id();
+    let response = jmap_json_request(
+        r#"[[
+            "EmailSubmission/set",
+            {
+             "accountId": "$$",
+             "update": {
+              "$ID": {
+               "sendAt": "2079-11-21T05:00:00Z"
+              }
+             }
+            },
+            "R1"
+           ],
+           [
+            "EmailSubmission/get",
+            {
+             "accountId": "$$",
+             "ids": [ "$ID" ],
+             "properties": [ "sendAt", "undoStatus" ]
+            },
+            "R2"
+           ],
+           [
+            "EmailSubmission/query",
+            {
+             "accountId": "$$",
+             "filter": { "isScheduled": true }
+            },
+            "R3"
+           ]]"#
+        .replace("$$", &account_id)
+        .replace("$ID", &scheduled_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert!(
+        response
+            .pointer(&format!("/methodResponses/0/1/updated/{scheduled_id}"))
+            .is_some(),
+        "Response: {:?}",
+        response
+    );
+    for (pointer, expected) in [
+        ("/methodResponses/1/1/list/0/sendAt", "2079-11-21T05:00:00Z"),
+        ("/methodResponses/1/1/list/0/undoStatus", "pending"),
+        ("/methodResponses/2/1/ids/0", scheduled_id.as_str()),
+    ] {
+        assert_eq!(
+            response.pointer(pointer).and_then(|v| v.as_str()),
+            Some(expected),
+            "Pointer: {pointer}, Response: {:?}",
+            response
+        );
+    }
+
+    // Rescheduling into the past is not allowed
+    let response = jmap_json_request(
+        r#"[[
+            "EmailSubmission/set",
+            {
+             "accountId": "$$",
+             "update": {
+              "$ID": {
+               "sendAt": "2000-01-01T00:00:00Z"
+              }
+             }
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id)
+        .replace("$ID", &scheduled_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert!(
+        response
+            .pointer(&format!("/methodResponses/0/1/notUpdated/{scheduled_id}"))
+            .is_some(),
+        "Response: {:?}",
+        response
+    );
+
+    // Cancelling the submission moves the email back to Drafts
+    client
+        .email_submission_change_status(&scheduled_id, UndoStatus::Canceled)
+        .await
+        .unwrap();
+    expect_nothing(&mut smtp_rx).await;
+    let response = jmap_json_request(
+        r#"[[
+            "Mailbox/query",
+            {
+             "accountId": "$$",
+             "filter": { "role": "drafts" }
+            },
+            "R1"
+           ],
+           [
+            "EmailSubmission/query",
+            {
+             "accountId": "$$",
+             "filter": { "isScheduled": true }
+            },
+            "R2"
+           ]]"#
+        .replace("$$", &account_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    let drafts_id = response
+        .pointer("/methodResponses/0/1/ids/0")
+        .and_then(|v| v.as_str())
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        response
+            .pointer("/methodResponses/1/1/ids")
+            .and_then(|v| v.as_array())
+            .map(|v| v.len()),
+        Some(0),
+        "Response: {:?}",
+        response
+    );
+    assert_email_properties(client, &draft_id, &[&drafts_id], &["$draft"]).await;
+    client.email_destroy(&draft_id).await.unwrap();
+
+    // Submissions released by the queue are no longer pending
+    let queue_id = server
+        .get_property::<Object<Value>>(
+            Id::from_bytes(account_id.as_bytes()).unwrap().document_id(),
+            Collection::EmailSubmission,
+            Id::from_bytes(email_submission_id.as_bytes())
+                .unwrap()
+                .document_id(),
+            Property::Value,
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .get(&Property::MessageId)
+        .as_uint()
+        .unwrap();
+    let message = server.read_message(queue_id).await.unwrap();
+    let due = message.next_event().unwrap_or_default();
+    message.remove(&server, due).await;
+    for (undo_status, is_listed) in [("pending", false), ("final", true)] {
+        let response = jmap_json_request(
+            r#"[[
+                "EmailSubmission/query",
+                {
+                 "accountId": "$$",
+                 "filter": { "undoStatus": "$STATUS" }
+                },
+                "R1"
+               ]]"#
+            .replace("$$", &account_id)
+            .replace("$STATUS", undo_status),
+            "jdoe@example.com",
+            "12345",
+        )
+        .await;
+        assert_eq!(
+            response
+                .pointer("/methodResponses/0/1/ids")
+                .and_then(|v| v.as_array())
+                .map(|ids| ids
+                    .iter()
+                    .any(|id| id.as_str() == Some(email_submission_id.as_str()))),
+            Some(is_listed),
+            "Response: {:?}",
+            response
+        );
+    }
+
+    // Inbound DSNs and MDNs should update the originating submission
+    let email_body = concat!(
+        "From: jdoe@example.com\r\n",
+        "To: jane_smith@remote.org\r\n",
+        "Message-ID: <submission-report@example.com>\r\n",
+        "Subject: report\r\n",
+        "\r\n",
+        "test"
+    );
+    let report_email_id = client
+        .email_import(
+            email_body.as_bytes().to_vec(),
+            [&mailbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+    let email_submission_id = client
+        .email_submission_create(&report_email_id, &identity_id)
+        .await
+        .unwrap()
+        .take_id();
+    assert_message_delivery(
+        &mut smtp_rx,
+        MockMessage::new(
+            "<jdoe@example.com>",
+            ["<jane_smith@remote.org>"],
+            email_body,
+        ),
+    )
+    .await;
+
+    // An envelope id is assigned to submissions to match DSNs against
+    let response = jmap_json_request(
+        r#"[[
+            "EmailSubmission/get",
+            {
+             "accountId": "$$",
+             "ids": [ "$ID" ],
+             "properties": [ "envelope" ]
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id)
+        .replace("$ID", &email_submission_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    let envelope_id = response
+        .pointer("/methodResponses/0/1/list/0/envelope/mailFrom/parameters/ENVID")
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Missing ENVID: {response:?}"))
+        .to_string();
+
+    // DSNs without an envelope id are matched by Message-ID and recipient,
+    // otherwise the envelope id has to match the submission
+    let mut lmtp = SmtpConnection::connect().await;
+    for (num, envelope_id, action) in [
+        (1, None, "delayed"),
+        (2, Some(envelope_id.as_str()), "failed"),
+        (3, Some("other-envelope"), "delivered"),
+    ] {
+        lmtp.ingest(
+            "mailer-daemon@remote.org",
+            &["jdoe@example.com"],
+            &format!(
+                concat!(
+                    "From: Mail Delivery Subsystem <mailer-daemon@remote.org>\r\n",
+                    "To: jdoe@example.com\r\n",
+                    "Subject: Delivery Status Notification\r\n",
+                    "Message-ID: <dsn-{}@remote.org>\r\n",
+                    "MIME-Version: 1.0\r\n",
+                    "Content-Type: multipart/report; report-type=delivery-status; ",
+                    "boundary=\"dsn\"\r\n",
+                    "\r\n",
+                    "--dsn\r\n",
+                    "Content-Type: text/plain\r\n",
+                    "\r\n",
+                    "Delivery report.\r\n",
+                    "\r\n",
+                    "--dsn\r\n",
+                    "Content-Type: message/delivery-status\r\n",
+                    "\r\n",
+                    "Reporting-MTA: dns; mx.remote.org\r\n",
+                    "{}",
+                    "\r\n",
+                    "Final-Recipient: rfc822; jane_smith@remote.org\r\n",
+                    "Action: {}\r\n",
+                    "Status: 5.1.1\r\n",
+                    "Diagnostic-Code: smtp; 550 5.1.1 User unknown\r\n",
+                    "\r\n",
+                    "--dsn\r\n",
+                    "Content-Type: text/rfc822-headers\r\n",
+                    "\r\n",
+                    "From: jdoe@example.com\r\n",
+                    "To: jane_smith@remote.org\r\n",
+                    "Message-ID: <submission-report@example.com>\r\n",
+                    "Subject: report\r\n",
+                    "\r\n",
+                    "--dsn--\r\n"
+                ),
+                num,
+                envelope_id
+                    .map(|id| format!("Original-Envelope-Id: {id}\r\n"))
+                    .unwrap_or_default(),
+                action
+            ),
+        )
+        .await;
+    }
+    lmtp.ingest(
+        "jane_smith@remote.org",
+        &["jdoe@example.com"],
+        concat!(
+            "From: Jane Smith <jane_smith@remote.org>\r\n",
+            "To: jdoe@example.com\r\n",
+            "Subject: Read: report\r\n",
+            "Message-ID: <mdn-1@remote.org>\r\n",
+            "MIME-Version: 1.0\r\n",
+            "Content-Type: multipart/report; report-type=disposition-notification; ",
+            "boundary=\"mdn\"\r\n",
+            "\r\n",
+            "--mdn\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "Your message was displayed.\r\n",
+            "\r\n",
+            "--mdn\r\n",
+            "Content-Type: message/disposition-notification\r\n",
+            "\r\n",
+            "Reporting-UA: remote.org; Foomail\r\n",
+            "Final-Recipient: rfc822; jane_smith@remote.org\r\n",
+            "Original-Message-ID: <submission-report@example.com>\r\n",
+            "Disposition: manual-action/MDN-sent-manually; displayed\r\n",
+            "\r\n",
+            "--mdn--\r\n"
+        ),
+    )
+    .await;
+    let response = jmap_json_request(
+        r#"[[
+            "EmailSubmission/get",
+            {
+             "accountId": "$$",
+             "ids": [ "$ID" ]
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id)
+        .replace("$ID", &email_submission_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    for (pointer, expected) in [
+        ("deliveryStatus/jane_smith@remote.org/delivered", "no"),
+        (
+            "deliveryStatus/jane_smith@remote.org/smtpReply",
+            "550 5.1.1 User unknown",
+        ),
+        ("deliveryStatus/jane_smith@remote.org/displayed", "yes"),
+    ] {
+        assert_eq!(
+            response
+                .pointer(&format!("/methodResponses/0/1/list/0/{pointer}"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+            expected,
+            "Response: {:?}",
+            response
+        );
+    }
+    for (pointer, expected) in [("dsnBlobIds", 2), ("mdnBlobIds", 1)] {
+        assert_eq!(
+            response
+                .pointer(&format!("/methodResponses/0/1/list/0/{pointer}"))
+                .and_then(|v| v.as_array())
+                .map_or(0, |v| v.len()),
+            expected,
+            "Response: {:?}",
+            response
+        );
+    }
+
     // Verify onSuccessUpdateEmail action
     let mut request = client.build();
     let set_request = request.set_email_submission();
@@ -0,0 +1,181 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, mailbox::destroy_all_mailboxes, wait_for_index, ManagementApi},
+};
+use jmap::{
+    mailbox::INBOX_ID,
+    services::fsck::{FsckIssueKind, FsckReport, StoreCheck},
+    JmapMethods,
+};
+use jmap_proto::types::{collection::Collection, id::Id, keyword::Keyword, property::Property};
+use std::time::Duration;
+use store::write::{BatchBuilder, BlobOp, DirectoryClass, ValueClass, F_CLEAR, F_INDEX};
+use utils::BlobHash;
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running store consistency tests...");
+    let server = params.server.clone();
+    let account_id = server
+        .core
+        .storage
+        .data
+        .create_test_user(
+            "john.fsck@example.com",
+            "secret",
+            "John Doe",
+            &["john.fsck@example.com"][..],
+        )
+        .await;
+
+    // Populate the account
+    let client = &mut params.client;
+    client.set_default_account_id(Id::from(account_id).to_string());
+    let inbox_id = Id::from(INBOX_ID).to_string();
+    let mut messages = Vec::new();
+    for raw in [
+        concat!(
+            "From: bill@example.com\r\n",
+            "To: john.fsck@example.com\r\n",
+            "Subject: TPS Report\r\n",
+            "\r\n",
+            "Please remember the new cover sheets on the TPS reports.\r\n"
+        ),
+        concat!(
+            "From: jane@example.com\r\n",
+            "To: john.fsck@example.com\r\n",
+            "Subject: Lunch\r\n",
+            "\r\n",
+            "Are we still on for lunch?\r\n"
+        ),
+    ] {
+        let document_id = Id::from_bytes(
+            client
+                .email_import(
+                    raw.as_bytes().to_vec(),
+                    [&inbox_id],
+                    None::<Vec<String>>,
+                    None,
+                )
+                .await
+                .unwrap()
+                .id()
+                .unwrap()
+                .as_bytes(),
+        )
+        .unwrap()
+        .document_id();
+        messages.push((document_id, raw.len() as u32));
+    }
+    wait_for_index(&server).await;
+
+    // A consistent store has no issues
+    let report = server.check_store(account_id.into(), false).await.unwrap();
+    assert_eq!(report.accounts, 1);
+    assert_issues(&report, &[]);
+
+    // Corrupt the store
+    let (document_id, size) = messages[0];
+    let hash = BlobHash::from(b"orphaned blob link".as_slice());
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(account_id)
+        .with_collection(Collection::Email)
+        .update_document(999)
+        .tag(Property::Keywords, Keyword::Seen, 0)
+        .set(
+            ValueClass::Property(Property::Subject.into()),
+            b"orphaned".to_vec(),
+        )
+        .set(ValueClass::Blob(BlobOp::Link { hash }), vec![])
+        .update_document(document_id)
+        .value(Property::Size, size, F_INDEX | F_CLEAR)
+        .with_collection(Collection::Thread)
+        .create_document_with_id(999)
+        .add(DirectoryClass::UsedQuota(account_id), 100);
+    server.core.storage.data.write(batch.build()).await.unwrap();
+    let quota = server.get_used_quota(account_id).await.unwrap();
+    let expected = [
+        FsckIssueKind::DanglingBitmap,
+        FsckIssueKind::OrphanedProperty,
+        FsckIssueKind::OrphanedBlobLink,
+        FsckIssueKind::OrphanedThread,
+        FsckIssueKind::MissingIndex,
+        FsckIssueKind::QuotaMismatch,
+    ];
+
+    // Issues are reported but left untouched when not repairing
+    let report = server.check_store(account_id.into(), false).await.unwrap();
+    assert_issues(&report, &expected);
+    assert_eq!(report.repaired, 0);
+    let report = server.check_store(account_id.into(), false).await.unwrap();
+    assert_issues(&report, &expected);
+    assert_eq!(server.get_used_quota(account_id).await.unwrap(), quota);
+
+    // Repair the store
+    let report = server.check_store(account_id.into(), true).await.unwrap();
+    assert_issues(&report, &expected);
+    assert_eq!(report.repaired, expected.len() as u64);
+    assert!(report.issues.iter().all(|issue| issue.repaired));
+    assert_eq!(
+        server.get_used_quota(account_id).await.unwrap(),
+        quota - 100
+    );
+    let report = server.check_store(account_id.into(), false).await.unwrap();
+    assert_issues(&report, &[]);
+
+    // Check all accounts
+    let report = server.check_store(None, false).await.unwrap();
+    assert!(report.accounts >= 1);
+    assert_issues(&report, &[]);
+
+    // Checks requested through the API run as housekeeper jobs
+    let api = ManagementApi::new(8899, "admin", "secret");
+    let id = api
+        .get::<String>("/api/store/fsck/john.fsck@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    let job = loop {
+        let job = api
+            .get::<serde_json::Value>(&format!("/api/store/fsck-jobs/{id}"))
+            .await
+            .unwrap()
+            .unwrap_data();
+        if job.get("completed").is_some() {
+            break job;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+    assert_eq!(job["id"], id, "{job}");
+    assert_eq!(job["accountId"], account_id, "{job}");
+    assert_eq!(job["report"]["accounts"], 1, "{job}");
+    assert_eq!(job["report"]["total"], 0, "{job}");
+
+    // Remove test data
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+fn assert_issues(report: &FsckReport, expected: &[FsckIssueKind]) {
+    let mut found = report
+        .issues
+        .iter()
+        .map(|issue| issue.kind.as_str())
+        .collect::<Vec<_>>();
+    let mut expected = expected
+        .iter()
+        .map(|kind| kind.as_str())
+        .collect::<Vec<_>>();
+    found.sort_unstable();
+    expected.sort_unstable();
+    assert_eq!(found, expected, "{report:#?}");
+    assert_eq!(report.total, expected.len() as u64);
+}
@@ -0,0 +1,319 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Instant;
+
+use jmap_client::mailbox::Role;
+use jmap_proto::types::id::Id;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty,
+        email_set::assert_email_properties,
+        email_submission::{expect_message_delivery, expect_nothing, spawn_mock_smtp_server},
+        jmap_json_request,
+        mailbox::destroy_all_mailboxes,
+    },
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running MDN tests...");
+    // Start mock SMTP server
+    let server = params.server.clone();
+    let (mut smtp_rx, _smtp_settings) = spawn_mock_smtp_server();
+    server.core.smtp.resolvers.dns.ipv4_add(
+        "localhost",
+        vec!["127.0.0.1".parse().unwrap()],
+        Instant::now() + std::time::Duration::from_secs(10),
+    );
+
+    // Create a test account
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    )
+    .to_string();
+    let identity_id = Id::from(0u64).to_string();
+    let client = &mut params.client;
+    client.set_default_account_id(&account_id);
+
+    // Import a message requesting a read receipt and one that does not
+    let mailbox_id = client
+        .mailbox_create("JMAP MDN", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+    let email_id = client
+        .email_import(
+            concat!(
+                "From: Bill Foobar <bill@remote.org>\r\n",
+                "To: jdoe@example.com\r\n",
+                "Subject: Quarterly report\r\n",
+                "Message-ID: <report-1234@remote.org>\r\n",
+                "Disposition-Notification-To: Bill Foobar <bill@remote.org>\r\n",
+                "\r\n",
+                "Please confirm you have read this.\r\n"
+            )
+            .as_bytes()
+            .to_vec(),
+            [&mailbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+    let email_id_no_dnt = client
+        .email_import(
+            concat!(
+                "From: Bill Foobar <bill@remote.org>\r\n",
+                "To: jdoe@example.com\r\n",
+                "Subject: No receipt please\r\n",
+                "\r\n",
+                "Nothing to see here.\r\n"
+            )
+            .as_bytes()
+            .to_vec(),
+            [&mailbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+
+    // Send a read receipt and update the email on success
+    let send_request = r##"[[
+        "MDN/send",
+        {
+         "accountId": "$$",
+         "identityId": "$ID",
+         "send": {
+          "k1": {
+           "forEmailId": "$EMAIL",
+           "subject": "Read receipt for: Quarterly report",
+           "textBody": "Your message has been displayed.",
+           "includeOriginalMessage": true,
+           "reportingUA": "joes-pc.example.com; Foomail 97.1",
+           "disposition": {
+            "actionMode": "manual-action",
+            "sendingMode": "mdn-sent-manually",
+            "type": "displayed"
+           },
+           "extensionFields": {
+            "X-EXTENSION-EXAMPLE": "example.com"
+           }
+          }
+         },
+         "onSuccessUpdateEmail": {
+          "#k1": {
+           "keywords/$seen": true
+          }
+         }
+        },
+        "R1"
+       ]]"##
+        .replace("$$", &account_id)
+        .replace("$ID", &identity_id);
+    let response = jmap_json_request(
+        send_request.replace("$EMAIL", &email_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    for (pointer, expected) in [
+        (
+            "/methodResponses/0/1/sent/k1/finalRecipient",
+            "rfc822; jdoe@example.com",
+        ),
+        (
+            "/methodResponses/0/1/sent/k1/originalMessageId",
+            "<report-1234@remote.org>",
+        ),
+        ("/methodResponses/1/0", "Email/set"),
+    ] {
+        assert_eq!(
+            response
+                .pointer(pointer)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+            expected,
+            "Response: {:?}",
+            response
+        );
+    }
+    assert!(
+        response
+            .pointer(&format!("/methodResponses/1/1/updated/{email_id}"))
+            .is_some(),
+        "Response: {:?}",
+        response
+    );
+
+    // Make sure the report was delivered
+    let message = expect_message_delivery(&mut smtp_rx).await;
+    assert_eq!(message.mail_from, "<>");
+    assert_eq!(message.rcpt_to, vec!["<bill@remote.org>".to_string()]);
+    for expected in [
+        "report-type=disposition-notification",
+        "message/disposition-notification",
+        "Reporting-UA: joes-pc.example.com; Foomail 97.1",
+        "Final-Recipient: rfc822; jdoe@example.com",
+        "Original-Message-ID: <report-1234@remote.org>",
+        "Disposition: manual-action/MDN-sent-manually; displayed",
+        "X-EXTENSION-EXAMPLE: example.com",
+        "Content-Type: message/rfc822\r\nContent-Transfer-Encoding: 7bit\r\n",
+        "Message-ID: <report-1234@remote.org>\r\n",
+        "Please confirm you have read this.\r\n",
+    ] {
+        assert!(
+            message.message.contains(expected),
+            "Missing {expected:?} in {}",
+            message.message
+        );
+    }
+    assert_email_properties(client, &email_id, &[&mailbox_id], &["$mdnsent", "$seen"]).await;
+
+    // Sending a second receipt should fail
+    let response = jmap_json_request(
+        send_request.replace("$EMAIL", &email_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/notSent/k1/type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default(),
+        "mdnAlreadySent",
+        "Response: {:?}",
+        response
+    );
+
+    // Messages without a Disposition-Notification-To header have no recipients
+    let response = jmap_json_request(
+        send_request.replace("$EMAIL", &email_id_no_dnt),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/notSent/k1/type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default(),
+        "noRecipients",
+        "Response: {:?}",
+        response
+    );
+    expect_nothing(&mut smtp_rx).await;
+
+    // Receipts without an email id are rejected
+    let response = jmap_json_request(
+        send_request.replace("\"forEmailId\": \"$EMAIL\",", ""),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/notSent/k1/type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default(),
+        "invalidProperties",
+        "Response: {:?}",
+        response
+    );
+    expect_nothing(&mut smtp_rx).await;
+
+    // Import the delivered report and parse it
+    let mdn_blob_id = client
+        .email_import(
+            message.message.into_bytes(),
+            [&mailbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .blob_id()
+        .unwrap()
+        .to_string();
+    let original_blob_id = client
+        .email_get(&email_id, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .unwrap()
+        .blob_id()
+        .unwrap()
+        .to_string();
+    let response = jmap_json_request(
+        r#"[[
+            "MDN/parse",
+            {
+             "accountId": "$$",
+             "blobIds": [ "$MDN", "$ORIG" ]
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &account_id)
+        .replace("$MDN", &mdn_blob_id)
+        .replace("$ORIG", &original_blob_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    for (pointer, expected) in [
+        ("forEmailId", email_id.as_str()),
+        ("subject", "Read receipt for: Quarterly report"),
+        ("reportingUA", "joes-pc.example.com; Foomail 97.1"),
+        ("finalRecipient", "rfc822; jdoe@example.com"),
+        ("originalMessageId", "<report-1234@remote.org>"),
+        ("disposition/actionMode", "manual-action"),
+        ("disposition/sendingMode", "mdn-sent-manually"),
+        ("disposition/type", "displayed"),
+        ("extensionFields/X-EXTENSION-EXAMPLE", "example.com"),
+    ] {
+        assert_eq!(
+            response
+                .pointer(&format!(
+                    "/methodResponses/0/1/parsed/{mdn_blob_id}/{pointer}"
+                ))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+            expected,
+            "Response: {:?}",
+            response
+        );
+    }
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/notParsable/0")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default(),
+        original_blob_id,
+        "Response: {:?}",
+        response
+    );
+
+    // Destroy test data
+    client.identity_destroy(&identity_id).await.unwrap();
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
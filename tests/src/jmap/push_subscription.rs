@@ -15,7 +15,13 @@ use std::{
 use base64::{engine::general_purpose, Engine};
 use common::{config::server::Listeners, listener::SessionData, Core, Data, Inner};
 use ece::EcKeyComponents;
-use hyper::{body, header::CONTENT_ENCODING, server::conn::http1, service::service_fn, StatusCode};
+use hyper::{
+    body,
+    header::{AUTHORIZATION, CONTENT_ENCODING},
+    server::conn::http1,
+    service::service_fn,
+    StatusCode,
+};
 use hyper_util::rt::TokioIo;
 use jmap::{
     api::{
@@ -34,7 +40,9 @@ use utils::config::Config;
 use crate::{
     add_test_certs,
     directory::internal::TestInternalDirectory,
-    jmap::{assert_is_empty, mailbox::destroy_all_mailboxes, test_account_login},
+    jmap::{
+        assert_is_empty, jmap_json_request, mailbox::destroy_all_mailboxes, test_account_login,
+    },
     AssertConfig,
 };
 
@@ -93,7 +101,15 @@ pub async fn test(params: &mut JMAPTest) {
         keypair: keypair.raw_components().unwrap(),
         auth_secret: auth_secret.to_vec(),
         tx: event_tx,
+        vapid_key: server
+            .core
+            .jmap
+            .push_vapid
+            .as_ref()
+            .map(|vapid| vapid.public_key.clone())
+            .unwrap(),
         fail_requests: false.into(),
+        gone_requests: false.into(),
     });
 
     // Start mock push server
@@ -221,8 +237,37 @@ pub async fn test(params: &mut JMAPTest) {
     assert_state(&mut event_rx, &account_id, &[DataType::Mailbox]).await;
     expect_nothing(&mut event_rx).await;
 
+    // Subscriptions rejected by the push service should be destroyed
+    push_server.gone_requests.store(true, Ordering::Relaxed);
+    client
+        .mailbox_update_sort_order(&mailbox_id, 202)
+        .await
+        .unwrap();
+    expect_nothing(&mut event_rx).await;
+    push_server.gone_requests.store(false, Ordering::Relaxed);
+    let response = jmap_json_request(
+        r#"[[
+            "PushSubscription/get",
+            {
+             "ids": ["$$"]
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &push_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/notFound/0")
+            .and_then(|v| v.as_str()),
+        Some(push_id.as_str()),
+        "Response: {:?}",
+        response
+    );
+
     // Destroy mailbox
-    client.push_subscription_destroy(&push_id).await.unwrap();
     client.mailbox_destroy(&mailbox_id, true).await.unwrap();
     expect_nothing(&mut event_rx).await;
 
@@ -244,7 +289,9 @@ pub struct PushServer {
     keypair: EcKeyComponents,
     auth_secret: Vec<u8>,
     tx: mpsc::Sender<PushMessage>,
+    vapid_key: String,
     fail_requests: AtomicBool,
+    gone_requests: AtomicBool,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -308,7 +355,27 @@ impl common::listener::SessionManager for SessionManager {
                                 )
                                 .into_http_response()
                                 .build());
+                            } else if push.gone_requests.load(Ordering::Relaxed) {
+                                return Ok(HtmlResponse::with_status(
+                                    StatusCode::GONE,
+                                    "gone".to_string(),
+                                )
+                                .into_http_response()
+                                .build());
                             }
+
+                            // Requests must carry a VAPID authorization
+                            let authorization = req
+                                .headers()
+                                .get(AUTHORIZATION)
+                                .and_then(|value| value.to_str().ok())
+                                .unwrap_or_default();
+                            assert!(
+                                authorization.starts_with("vapid t=")
+                                    && authorization.ends_with(&format!(", k={}", push.vapid_key)),
+                                "Invalid authorization: {authorization:?}"
+                            );
+
                             let is_encrypted = req
                                 .headers()
                                 .get(CONTENT_ENCODING)
@@ -0,0 +1,407 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use base64::{engine::general_purpose, Engine};
+use jmap_client::mailbox::Role;
+use jmap_proto::types::id::Id;
+use reqwest::header;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, jmap_json_request, mailbox::destroy_all_mailboxes},
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running ShareNotification tests...");
+    let server = params.server.clone();
+
+    // Create two test accounts
+    let john_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    )
+    .to_string();
+    let jane_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jane.smith@example.com",
+                "abcde",
+                "Jane Smith",
+                &["jane.smith@example.com"],
+            )
+            .await,
+    )
+    .to_string();
+    let client = &mut params.client;
+    client.set_default_account_id(&john_id);
+
+    // The session should include the principal capabilities
+    let session = jmap_session("jane.smith@example.com", "abcde").await;
+    for (pointer, expected) in [
+        (
+            "/capabilities/urn:ietf:params:jmap:principals".to_string(),
+            serde_json::json!({}),
+        ),
+        (
+            format!("/accounts/{jane_id}/accountCapabilities/urn:ietf:params:jmap:principals"),
+            serde_json::json!({ "currentUserPrincipalId": jane_id }),
+        ),
+        (
+            format!(
+                "/accounts/{jane_id}/accountCapabilities/urn:ietf:params:jmap:principals:owner"
+            ),
+            serde_json::json!({ "accountIdForPrincipal": jane_id, "principalId": jane_id }),
+        ),
+    ] {
+        assert_eq!(
+            session.pointer(&pointer),
+            Some(&expected),
+            "Session: {:?}",
+            session
+        );
+    }
+
+    // Share a mailbox with Jane
+    let mailbox_id = client
+        .mailbox_create("Shared Folder", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+    let response = jmap_json_request(
+        r#"[[
+            "Mailbox/set",
+            {
+             "accountId": "$$",
+             "update": {
+              "$MAILBOX": {
+               "shareWith/$JANE": {
+                "mayReadItems": true,
+                "mayAddItems": true
+               }
+              }
+             }
+            },
+            "R1"
+           ],
+           [
+            "Mailbox/get",
+            {
+             "accountId": "$$",
+             "ids": ["$MAILBOX"],
+             "properties": ["shareWith", "myRights"]
+            },
+            "R2"
+           ]]"#
+        .replace("$$", &john_id)
+        .replace("$MAILBOX", &mailbox_id)
+        .replace("$JANE", &jane_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert!(
+        response
+            .pointer(&format!("/methodResponses/0/1/updated/{mailbox_id}"))
+            .is_some(),
+        "Response: {:?}",
+        response
+    );
+    for (pointer, expected) in [
+        (format!("shareWith/{jane_id}/mayReadItems"), true),
+        (format!("shareWith/{jane_id}/mayAddItems"), true),
+        (format!("shareWith/{jane_id}/mayDelete"), false),
+        (format!("shareWith/{jane_id}/mayAdmin"), false),
+        ("myRights/mayAdmin".to_string(), true),
+    ] {
+        assert_eq!(
+            response
+                .pointer(&format!("/methodResponses/1/1/list/0/{pointer}"))
+                .and_then(|v| v.as_bool()),
+            Some(expected),
+            "Pointer: {pointer}, Response: {:?}",
+            response
+        );
+    }
+
+    // Jane should have received a share notification
+    let response = jmap_json_request(
+        r##"[[
+            "ShareNotification/query",
+            {
+             "accountId": "$$",
+             "filter": {
+              "objectType": "Mailbox",
+              "objectAccountId": "$JOHN"
+             }
+            },
+            "R1"
+           ],
+           [
+            "ShareNotification/get",
+            {
+             "accountId": "$$",
+             "#ids": {
+              "resultOf": "R1",
+              "name": "ShareNotification/query",
+              "path": "/ids"
+             }
+            },
+            "R2"
+           ]]"##
+            .replace("$$", &jane_id)
+            .replace("$JOHN", &john_id),
+        "jane.smith@example.com",
+        "abcde",
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/ids")
+            .and_then(|v| v.as_array())
+            .map(|v| v.len()),
+        Some(1),
+        "Response: {:?}",
+        response
+    );
+    for (pointer, expected) in [
+        ("objectType", serde_json::json!("Mailbox")),
+        ("objectAccountId", serde_json::json!(john_id)),
+        ("objectId", serde_json::json!(mailbox_id)),
+        ("name", serde_json::json!("Shared Folder")),
+        ("changedBy/principalId", serde_json::json!(john_id)),
+        ("changedBy/name", serde_json::json!("John Doe")),
+        ("changedBy/email", serde_json::json!("jdoe@example.com")),
+        ("oldRights", serde_json::Value::Null),
+        ("newRights/mayReadItems", serde_json::json!(true)),
+        ("newRights/mayAddItems", serde_json::json!(true)),
+        ("newRights/mayRemoveItems", serde_json::json!(false)),
+    ] {
+        assert_eq!(
+            response.pointer(&format!("/methodResponses/1/1/list/0/{pointer}")),
+            Some(&expected),
+            "Pointer: {pointer}, Response: {:?}",
+            response
+        );
+    }
+
+    // Revoke a single right and make sure Jane's rights are reported consistently
+    let response = jmap_json_request(
+        r#"[[
+            "Mailbox/set",
+            {
+             "accountId": "$$",
+             "update": {
+              "$MAILBOX": {
+               "shareWith/$JANE/mayAddItems": false
+              }
+             }
+            },
+            "R1"
+           ]]"#
+        .replace("$$", &john_id)
+        .replace("$MAILBOX", &mailbox_id)
+        .replace("$JANE", &jane_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert!(
+        response
+            .pointer(&format!("/methodResponses/0/1/updated/{mailbox_id}"))
+            .is_some(),
+        "Response: {:?}",
+        response
+    );
+    let response = jmap_json_request(
+        r##"[[
+            "Mailbox/get",
+            {
+             "accountId": "$JOHN",
+             "ids": ["$MAILBOX"],
+             "properties": ["shareWith", "myRights"]
+            },
+            "R1"
+           ],
+           [
+            "ShareNotification/query",
+            {
+             "accountId": "$$",
+             "sort": [{"property": "created", "isAscending": true}]
+            },
+            "R2"
+           ],
+           [
+            "ShareNotification/get",
+            {
+             "accountId": "$$",
+             "#ids": {
+              "resultOf": "R2",
+              "name": "ShareNotification/query",
+              "path": "/ids"
+             },
+             "properties": ["oldRights", "newRights"]
+            },
+            "R3"
+           ]]"##
+            .replace("$$", &jane_id)
+            .replace("$JOHN", &john_id)
+            .replace("$MAILBOX", &mailbox_id),
+        "jane.smith@example.com",
+        "abcde",
+    )
+    .await;
+    for (pointer, expected) in [
+        (
+            "/methodResponses/0/1/list/0/shareWith",
+            serde_json::Value::Null,
+        ),
+        (
+            "/methodResponses/0/1/list/0/myRights/mayReadItems",
+            serde_json::json!(true),
+        ),
+        (
+            "/methodResponses/0/1/list/0/myRights/mayAddItems",
+            serde_json::json!(false),
+        ),
+        (
+            "/methodResponses/0/1/list/0/myRights/mayAdmin",
+            serde_json::json!(false),
+        ),
+        (
+            "/methodResponses/2/1/list/1/oldRights/mayAddItems",
+            serde_json::json!(true),
+        ),
+        (
+            "/methodResponses/2/1/list/1/newRights/mayAddItems",
+            serde_json::json!(false),
+        ),
+        (
+            "/methodResponses/2/1/list/1/newRights/mayReadItems",
+            serde_json::json!(true),
+        ),
+    ] {
+        assert_eq!(
+            response.pointer(pointer),
+            Some(&expected),
+            "Pointer: {pointer}, Response: {:?}",
+            response
+        );
+    }
+
+    // Share notifications can only be destroyed
+    let notification_ids = response
+        .pointer("/methodResponses/1/1/ids")
+        .and_then(|v| v.as_array())
+        .unwrap()
+        .clone();
+    assert_eq!(notification_ids.len(), 2);
+    let response = jmap_json_request(
+        r#"[[
+            "ShareNotification/set",
+            {
+             "accountId": "$$",
+             "update": {
+              "$ID": {
+               "name": "Renamed"
+              }
+             },
+             "destroy": $IDS
+            },
+            "R1"
+           ],
+           [
+            "ShareNotification/query",
+            {
+             "accountId": "$$"
+            },
+            "R2"
+           ]]"#
+        .replace("$$", &jane_id)
+        .replace("$ID", notification_ids[0].as_str().unwrap())
+        .replace(
+            "$IDS",
+            &serde_json::Value::Array(notification_ids.clone()).to_string(),
+        ),
+        "jane.smith@example.com",
+        "abcde",
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/destroyed")
+            .and_then(|v| v.as_array())
+            .map(|v| v.len()),
+        Some(2),
+        "Response: {:?}",
+        response
+    );
+    assert!(
+        response
+            .pointer("/methodResponses/0/1/notUpdated")
+            .and_then(|v| v.as_object())
+            .map_or(false, |v| v.len() == 1),
+        "Response: {:?}",
+        response
+    );
+    assert_eq!(
+        response
+            .pointer("/methodResponses/1/1/ids")
+            .and_then(|v| v.as_array())
+            .map(|v| v.len()),
+        Some(0),
+        "Response: {:?}",
+        response
+    );
+
+    // Destroy test data
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+async fn jmap_session(username: &str, secret: &str) -> serde_json::Value {
+    let mut headers = header::HeaderMap::new();
+    headers.insert(
+        header::AUTHORIZATION,
+        header::HeaderValue::from_str(&format!(
+            "Basic {}",
+            general_purpose::STANDARD.encode(format!("{}:{}", username, secret))
+        ))
+        .unwrap(),
+    );
+
+    serde_json::from_slice(
+        &reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .timeout(Duration::from_millis(1000))
+            .default_headers(headers)
+            .build()
+            .unwrap()
+            .get("https://127.0.0.1:8899/.well-known/jmap")
+            .send()
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap(),
+    )
+    .unwrap()
+}
@@ -0,0 +1,172 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap::mailbox::INBOX_ID;
+use jmap_client::{client::Client, email::query::Filter, mailbox::Role};
+use jmap_proto::types::id::Id;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty, delivery::SmtpConnection, email_set::assert_email_properties,
+        mailbox::destroy_all_mailboxes,
+    },
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running thread muting tests...");
+    let server = params.server.clone();
+
+    // Create a test account
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    )
+    .to_string();
+    let client = &mut params.client;
+    client.set_default_account_id(&account_id);
+    let inbox_id = Id::from(INBOX_ID).to_string();
+    let archive_id = client
+        .mailbox_create("Archive", None::<String>, Role::Archive)
+        .await
+        .unwrap()
+        .take_id();
+    let muted_id = client
+        .mailbox_create("Muted", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+
+    // Deliver the first message of the thread and mute it
+    let mut lmtp = SmtpConnection::connect().await;
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        &thread_message(0, "Party plans"),
+    )
+    .await;
+    let email_id = mailbox_email_ids(client, &inbox_id).await.pop().unwrap();
+    client
+        .email_set_keyword(&email_id, "$muted", true)
+        .await
+        .unwrap();
+
+    // Replies to a muted thread are marked as read and archived
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        &thread_message(1, "Re: Party plans"),
+    )
+    .await;
+    assert_eq!(
+        mailbox_email_ids(client, &inbox_id).await,
+        vec![email_id.clone()]
+    );
+    let reply_id = mailbox_email_ids(client, &archive_id).await.pop().unwrap();
+    assert_email_properties(client, &reply_id, &[&archive_id], &["$seen"]).await;
+
+    // Sieve scripts can test whether a message belongs to a muted thread
+    client
+        .sieve_script_create(
+            "test_mute",
+            concat!(
+                "require [\"fileinto\", \"vnd.stalwart.expressions\"];\n",
+                "if eval \"is_thread_muted()\" {\n",
+                "    fileinto \"Muted\";\n",
+                "}\n"
+            )
+            .as_bytes()
+            .to_vec(),
+            true,
+        )
+        .await
+        .unwrap();
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        &thread_message(2, "Re: Party plans"),
+    )
+    .await;
+    let reply_id = mailbox_email_ids(client, &muted_id).await.pop().unwrap();
+    assert_email_properties(client, &reply_id, &[&muted_id], &["$seen"]).await;
+
+    // Messages in other threads are not affected
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        concat!(
+            "From: bill@remote.org\r\n",
+            "To: jdoe@example.com\r\n",
+            "Subject: Quarterly report\r\n",
+            "Message-ID: <report@remote.org>\r\n",
+            "\r\n",
+            "See attached.\r\n"
+        ),
+    )
+    .await;
+    assert_eq!(mailbox_email_ids(client, &inbox_id).await.len(), 2);
+
+    // Unmuting the thread delivers replies to the inbox again
+    client
+        .email_set_keyword(&email_id, "$muted", false)
+        .await
+        .unwrap();
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        &thread_message(3, "Re: Party plans"),
+    )
+    .await;
+    assert_eq!(mailbox_email_ids(client, &inbox_id).await.len(), 3);
+    assert_eq!(mailbox_email_ids(client, &archive_id).await.len(), 1);
+    assert_eq!(mailbox_email_ids(client, &muted_id).await.len(), 1);
+    for email_id in mailbox_email_ids(client, &inbox_id).await {
+        assert_email_properties(client, &email_id, &[&inbox_id], &[]).await;
+    }
+
+    // Destroy test data
+    client.sieve_script_deactivate().await.unwrap();
+    let mut request = client.build();
+    request.query_sieve_script();
+    for id in request.send_query_sieve_script().await.unwrap().take_ids() {
+        client.sieve_script_destroy(&id).await.unwrap();
+    }
+    destroy_all_mailboxes(params).await;
+    assert_is_empty(server).await;
+}
+
+fn thread_message(num: usize, subject: &str) -> String {
+    let mut message = format!(
+        "From: bill@remote.org\r\nTo: jdoe@example.com\r\nSubject: {subject}\r\nMessage-ID: <party-{num}@remote.org>\r\n"
+    );
+    if num > 0 {
+        message.push_str(&format!(
+            "In-Reply-To: <party-{}@remote.org>\r\nReferences: <party-0@remote.org>\r\n",
+            num - 1
+        ));
+    }
+    message.push_str("\r\nLet's meet on Friday.\r\n");
+    message
+}
+
+async fn mailbox_email_ids(client: &mut Client, mailbox_id: &str) -> Vec<String> {
+    client
+        .email_query(Filter::in_mailbox(mailbox_id).into(), None::<Vec<_>>)
+        .await
+        .unwrap()
+        .take_ids()
+}
@@ -8,7 +8,10 @@ use std::{borrow::Cow, path::PathBuf, sync::Arc};
 
 use common::{
     config::server::ServerProtocol,
-    listener::{limiter::ConcurrencyLimiter, ServerInstance, SessionStream, TcpAcceptor},
+    listener::{
+        limiter::ConcurrencyLimiter, tls::ClientCertificate, ServerInstance, SessionStream,
+        TcpAcceptor,
+    },
     Server,
 };
 use rustls::{server::ResolvesServerCert, ServerConfig};
@@ -76,6 +79,10 @@ impl SessionStream for DummyIo {
     fn tls_version_and_cipher(&self) -> (Cow<'static, str>, Cow<'static, str>) {
         ("".into(), "".into())
     }
+
+    fn client_certificate(&self) -> Option<ClientCertificate> {
+        None
+    }
 }
 
 impl Unpin for DummyIo {}
@@ -107,6 +114,7 @@ impl TestSession for Session<DummyIo> {
                 tx_buf: vec![],
                 tls: false,
             },
+            client_cert: None,
             data: SessionData::new(
                 "127.0.0.1".parse().unwrap(),
                 0,
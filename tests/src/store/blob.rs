@@ -4,10 +4,13 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::sync::Arc;
+
 use ahash::AHashMap;
 use store::{
+    backend::tiered::TieredBlob,
     write::{blob::BlobQuota, now, BatchBuilder, BlobOp},
-    BlobClass, BlobStore, Serialize, Stores,
+    BlobBackend, BlobClass, BlobStore, CompressionAlgo, Serialize, Stores,
 };
 use utils::{config::Config, BlobHash};
 
@@ -25,6 +28,9 @@ pub async fn blob_tests() {
         test_store(blob_store.clone()).await;
     }
 
+    println!("Testing tiered blob store...");
+    test_tiered_store(stores.blob_stores.get("tiered").unwrap().clone()).await;
+
     for (store_id, store) in stores.stores {
         println!("Testing blob management on store {}...", store_id);
 
@@ -287,6 +293,66 @@ pub async fn blob_tests() {
             .await
             .unwrap();
 
+        // Share one blob between accounts and check deduplication statistics
+        let shared_hash = BlobHash::from(b"456".as_slice());
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(0)
+            .with_collection(0)
+            .update_document(3)
+            .set(
+                BlobOp::Link {
+                    hash: shared_hash.clone(),
+                },
+                vec![],
+            )
+            .with_account_id(1)
+            .update_document(4)
+            .set(
+                BlobOp::Link {
+                    hash: shared_hash.clone(),
+                },
+                vec![],
+            );
+        store.write(batch.build()).await.unwrap();
+        let usage = store.blob_usage(&blob_store).await.unwrap();
+        assert_eq!(usage.blobs, 5);
+        assert_eq!(usage.physical_bytes, 15);
+        assert_eq!(usage.links, 4);
+        assert_eq!(usage.logical_bytes, 12);
+        for (account_id, links, physical_bytes, logical_bytes) in [(0, 2, 3, 6), (1, 2, 6, 6)] {
+            let account = &usage.accounts[&account_id];
+            assert_eq!(
+                (account.links, account.physical_bytes, account.logical_bytes),
+                (links, physical_bytes, logical_bytes),
+                "account {account_id}"
+            );
+        }
+        assert_eq!(usage.purge.blobs, vec![BlobHash::from(b"789".as_slice())]);
+        assert_eq!(usage.purge.count, 1);
+        assert_eq!(usage.purge.bytes, 3);
+        assert_eq!(usage.purge.expired_reservations, 0);
+
+        // Dry runs do not delete anything
+        let candidates = store.blob_purge_candidates().await.unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert!(store
+            .blob_exists(BlobHash::from(b"789".as_slice()))
+            .await
+            .unwrap());
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(0)
+            .with_collection(0)
+            .update_document(3)
+            .clear(BlobOp::Link {
+                hash: shared_hash.clone(),
+            })
+            .with_account_id(1)
+            .update_document(4)
+            .clear(BlobOp::Link { hash: shared_hash });
+        store.write(batch.build()).await.unwrap();
+
         // Purge and make sure blob is deleted
         store.purge_blobs(blob_store.clone()).await.unwrap();
         for (pos, (blob, blob_class)) in [
@@ -431,7 +497,12 @@ async fn test_store(store: BlobStore) {
         .unwrap(),
         std::str::from_utf8(&DATA[11..57]).unwrap()
     );
+    let size = store.blob_size(hash.as_slice()).await.unwrap().unwrap();
+    if matches!(store.compression, CompressionAlgo::None) {
+        assert_eq!(size, DATA.len());
+    }
     assert!(store.delete_blob(hash.as_slice()).await.unwrap());
+    assert_eq!(store.blob_size(hash.as_slice()).await.unwrap(), None);
     assert!(store
         .get_blob(hash.as_slice(), 0..usize::MAX)
         .await
@@ -470,6 +541,12 @@ async fn test_store(store: BlobStore) {
         .unwrap(),
         std::str::from_utf8(&data[3000111..4000999]).unwrap()
     );
+    if matches!(store.compression, CompressionAlgo::None) {
+        assert_eq!(
+            store.blob_size(hash.as_slice()).await.unwrap(),
+            Some(data.len())
+        );
+    }
     assert!(store.delete_blob(hash.as_slice()).await.unwrap());
     assert!(store
         .get_blob(hash.as_slice(), 0..usize::MAX)
@@ -477,3 +554,121 @@ async fn test_store(store: BlobStore) {
         .unwrap()
         .is_none());
 }
+
+async fn test_tiered_store(store: BlobStore) {
+    let tiered = match &store.backend {
+        BlobBackend::Tiered(tiered) => tiered.clone(),
+        _ => panic!("Expected tiered store"),
+    };
+    let hot = BlobStore {
+        backend: BlobBackend::Fs(tiered.hot.clone()),
+        compression: CompressionAlgo::None,
+    };
+    let cold = tiered.cold.clone();
+    let promoting = BlobStore {
+        backend: BlobBackend::Tiered(Arc::new(TieredBlob {
+            hot: tiered.hot.clone(),
+            cold: cold.clone(),
+            max_age: tiered.max_age,
+            promote: true,
+        })),
+        compression: store.compression,
+    };
+
+    // New blobs are written to the hot tier
+    let blobs = [b"tiered blob 1".as_slice(), b"tiered blob 2".as_slice()];
+    for blob in blobs {
+        let hash = BlobHash::from(blob);
+        store.put_blob(hash.as_slice(), blob).await.unwrap();
+        assert!(hot
+            .get_blob(hash.as_slice(), 0..usize::MAX)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(cold
+            .get_blob(hash.as_slice(), 0..usize::MAX)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    // Blobs older than the maximum age are moved to the cold tier
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    tiered.migrate_cold_blobs().await.unwrap();
+    for blob in blobs {
+        let hash = BlobHash::from(blob);
+        assert!(hot
+            .get_blob(hash.as_slice(), 0..usize::MAX)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(cold
+            .get_blob(hash.as_slice(), 0..usize::MAX)
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            store
+                .get_blob(hash.as_slice(), 0..usize::MAX)
+                .await
+                .unwrap()
+                .unwrap(),
+            blob
+        );
+        assert_eq!(
+            store
+                .get_blob(hash.as_slice(), 0..6)
+                .await
+                .unwrap()
+                .unwrap(),
+            &blob[0..6]
+        );
+    }
+
+    // Obtaining the size of a blob does not promote it
+    for blob in blobs {
+        let hash = BlobHash::from(blob);
+        assert!(promoting
+            .blob_size(hash.as_slice())
+            .await
+            .unwrap()
+            .is_some());
+        assert!(hot
+            .get_blob(hash.as_slice(), 0..usize::MAX)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    // Reading from the cold tier promotes blobs back to the hot tier
+    let hash = BlobHash::from(blobs[0]);
+    assert_eq!(
+        promoting
+            .get_blob(hash.as_slice(), 7..11)
+            .await
+            .unwrap()
+            .unwrap(),
+        &blobs[0][7..11]
+    );
+    assert!(hot
+        .get_blob(hash.as_slice(), 0..usize::MAX)
+        .await
+        .unwrap()
+        .is_some());
+    assert!(cold
+        .get_blob(hash.as_slice(), 0..usize::MAX)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Deleting removes blobs from either tier
+    for blob in blobs {
+        let hash = BlobHash::from(blob);
+        assert!(store.delete_blob(hash.as_slice()).await.unwrap());
+        assert!(store
+            .get_blob(hash.as_slice(), 0..usize::MAX)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
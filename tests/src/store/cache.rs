@@ -0,0 +1,137 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use store::{
+    dispatch::cache::{
+        apply_invalidation, enable_cluster_invalidations, take_invalidations, CacheInvalidation,
+    },
+    roaring::RoaringBitmap,
+    write::{BatchBuilder, BitmapClass, TagValue, ValueClass, F_CLEAR},
+    BitmapKey, Store, Stores, ValueKey,
+};
+use utils::config::Config;
+
+use crate::store::TempDir;
+
+const ACCOUNT_ID: u32 = 100;
+
+pub async fn test(db: Store) {
+    println!("Testing store cache...");
+    enable_cluster_invalidations();
+    take_invalidations();
+
+    // Cached values should be refreshed after a write
+    for value in ["first", "second"] {
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(ACCOUNT_ID)
+            .with_collection(0)
+            .update_document(0)
+            .set(ValueClass::Property(0), value.as_bytes().to_vec())
+            .tag(0u8, TagValue::Id(value.len() as u32), 0);
+        db.write(batch.build_batch()).await.unwrap();
+        assert_eq!(
+            take_invalidations(),
+            Some(CacheInvalidation::Accounts(vec![ACCOUNT_ID]))
+        );
+
+        for _ in 0..2 {
+            assert_eq!(get_property(&db).await.as_deref(), Some(value));
+            assert!(get_tag(&db, value.len() as u32).await.contains(0));
+        }
+    }
+    assert!(get_tag(&db, "first".len() as u32).await.contains(0));
+
+    // Remote invalidations should not be broadcast again
+    apply_invalidation(&CacheInvalidation::Accounts(vec![ACCOUNT_ID]));
+    assert_eq!(take_invalidations(), None);
+    assert_eq!(get_property(&db).await.as_deref(), Some("second"));
+
+    // Missing values are cached as well
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(ACCOUNT_ID)
+        .with_collection(0)
+        .update_document(0)
+        .clear(ValueClass::Property(0))
+        .tag(0u8, TagValue::Id("first".len() as u32), F_CLEAR)
+        .tag(0u8, TagValue::Id("second".len() as u32), F_CLEAR);
+    db.write(batch.build_batch()).await.unwrap();
+    for _ in 0..2 {
+        assert_eq!(get_property(&db).await, None);
+        assert!(get_tag(&db, "first".len() as u32).await.is_empty());
+    }
+    take_invalidations();
+
+    // Range deletions flush the whole cache
+    db.purge_account(ACCOUNT_ID).await.unwrap();
+    assert_eq!(take_invalidations(), Some(CacheInvalidation::All));
+}
+
+#[tokio::test]
+pub async fn cache_config_tests() {
+    let temp_dir = TempDir::new("cache_config_tests", true);
+    let mut config = Config::new(
+        r#"
+[cluster]
+bind-addr = "127.0.0.1"
+
+[store."default-ttl"]
+type = "sqlite"
+path = "{TMP}/default-ttl.db"
+cache.size = 1048576
+
+[store."no-ttl"]
+type = "sqlite"
+path = "{TMP}/no-ttl.db"
+cache.size = 1048576
+cache.ttl = false
+"#
+        .replace("{TMP}", temp_dir.path.as_path().to_str().unwrap()),
+    )
+    .unwrap();
+
+    // Clustered nodes can not cache entries without an expiration
+    Stores::parse_all(&mut config).await;
+    assert!(
+        !config.errors.contains_key("store.default-ttl.cache.ttl"),
+        "{:?}",
+        config.errors
+    );
+    assert!(
+        config.errors.contains_key("store.no-ttl.cache.ttl"),
+        "{:?}",
+        config.errors
+    );
+
+    temp_dir.delete();
+}
+
+async fn get_property(db: &Store) -> Option<String> {
+    db.get_value::<String>(ValueKey {
+        account_id: ACCOUNT_ID,
+        collection: 0,
+        document_id: 0,
+        class: ValueClass::Property(0),
+    })
+    .await
+    .unwrap()
+}
+
+async fn get_tag(db: &Store, id: u32) -> RoaringBitmap {
+    db.get_bitmap(BitmapKey {
+        account_id: ACCOUNT_ID,
+        collection: 0,
+        class: BitmapClass::Tag {
+            field: 0,
+            value: TagValue::Id(id),
+        },
+        document_id: 0,
+    })
+    .await
+    .unwrap()
+    .unwrap_or_default()
+}
@@ -4,18 +4,28 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::time::Duration;
+
 use ahash::AHashSet;
-use common::{manager::backup::BackupParams, Core};
+use common::{
+    config::storage::BackupSettings,
+    manager::{
+        backup::BackupParams,
+        manifest::{BackupKind, BackupManifest},
+    },
+    Core,
+};
 use jmap_proto::types::{collection::Collection, property::Property};
 use store::{
     rand,
     write::{
-        AnyKey, BatchBuilder, BitmapClass, BitmapHash, BlobOp, DirectoryClass, LookupClass,
-        MaybeDynamicId, MaybeDynamicValue, Operation, QueueClass, QueueEvent, TagValue, ValueClass,
+        log::ChangeLogBuilder, AnyKey, BatchBuilder, BitmapClass, BitmapHash, BlobOp,
+        DirectoryClass, LookupClass, MaybeDynamicId, MaybeDynamicValue, Operation, QueueClass,
+        QueueEvent, TagValue, ValueClass,
     },
     *,
 };
-use utils::BlobHash;
+use utils::{snowflake::SnowflakeIdGenerator, BlobHash};
 
 use crate::store::TempDir;
 
@@ -81,6 +91,10 @@ pub async fn test(db: Store) {
                         random_bytes(value_size * 2),
                     );
                 }
+                batch.add(
+                    ValueClass::FtsTerm(random_bytes(12)),
+                    rand::random::<u16>() as i64 + 1,
+                );
 
                 for grant_account_id in 0u32..10u32 {
                     if account_id != grant_account_id {
@@ -162,6 +176,10 @@ pub async fn test(db: Store) {
             })),
             random_bytes(idx),
         );
+        batch
+            .with_account_id(idx as u32)
+            .update_document(rand::random())
+            .set(ValueClass::SnoozeEvent(rand::random()), vec![]);
         batch.set(
             ValueClass::Lookup(LookupClass::Key(random_bytes(idx))),
             random_bytes(idx),
@@ -243,20 +261,130 @@ pub async fn test(db: Store) {
 
     // Import store
     println!("Importing store...");
-    core.restore(temp_dir.path.clone()).await;
+    core.restore(temp_dir.path.clone(), None).await;
 
     // Verify hash
     print!("Verifying store hash...");
     snapshot.assert_is_eq(&Snapshot::new(&db).await);
     println!(" GREAT SUCCESS!");
+    temp_dir.delete();
+
+    // Add a principal without account data, to be removed later
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(u32::MAX)
+        .with_collection(Collection::Principal)
+        .create_document_with_id(20)
+        .set(
+            ValueClass::Directory(DirectoryClass::Principal(MaybeDynamicId::Static(20))),
+            random_bytes(30),
+        );
+    db.write(batch.build()).await.unwrap();
+    let snapshot = Snapshot::new(&db).await;
+
+    // Full online backup
+    println!("Creating full online backup...");
+    let backup_dir = TempDir::new("art_vandelay_online_tests", true);
+    core.storage.backup = Some(BackupSettings {
+        path: backup_dir.path.clone(),
+        schedule: None,
+        full_interval: 7,
+        retention: 0,
+    });
+    let full = core.online_backup(false).await.unwrap();
+    assert_eq!(full.kind, BackupKind::Full);
+    assert_eq!(full.principals, vec![1, 2, 3, 4, 5, 20]);
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    // Modify account 3, remove principal 20 and update global data
+    let data = random_bytes(2048);
+    let hash = BlobHash::from(data.as_slice());
+    core.storage
+        .blob
+        .put_blob(hash.as_ref(), &data)
+        .await
+        .unwrap();
+    let mut batch = BatchBuilder::new();
+    batch
+        .set(
+            ValueClass::Blob(BlobOp::Commit { hash: hash.clone() }),
+            vec![],
+        )
+        .with_account_id(3)
+        .with_collection(Collection::Email)
+        .create_document_with_id(50)
+        .set(ValueClass::Property(0), random_bytes(100))
+        .set(ValueClass::Blob(BlobOp::Link { hash }), vec![])
+        .custom(
+            ChangeLogBuilder::with_change_id(SnowflakeIdGenerator::new().generate().unwrap())
+                .with_log_insert(Collection::Email, 50u64),
+        );
+    batch
+        .with_account_id(u32::MAX)
+        .with_collection(Collection::Principal)
+        .update_document(20)
+        .clear(ValueClass::Directory(DirectoryClass::Principal(
+            MaybeDynamicId::Static(20),
+        )))
+        .set(
+            ValueClass::Lookup(LookupClass::Key(random_bytes(20))),
+            random_bytes(20),
+        );
+    db.write(batch.build()).await.unwrap();
+    let snapshot_incremental = Snapshot::new(&db).await;
+
+    // Incremental online backup
+    println!("Creating incremental online backup...");
+    let incremental = core.online_backup(false).await.unwrap();
+    assert_eq!(incremental.kind, BackupKind::Incremental);
+    assert_eq!(incremental.parent.as_ref(), Some(&full.id));
+    assert_eq!(
+        incremental
+            .accounts
+            .iter()
+            .copied()
+            .collect::<AHashSet<_>>(),
+        AHashSet::from_iter([u32::MAX])
+    );
+    assert_eq!(incremental.changed, vec![3]);
+    assert_eq!(incremental.principals, vec![1, 2, 3, 4, 5]);
+    let blob_size = |manifest: &BackupManifest| {
+        manifest
+            .files
+            .iter()
+            .find(|file| file.name == "blob")
+            .unwrap()
+            .size
+    };
+    assert!(blob_size(&incremental) < blob_size(&full) / 10);
+    assert_eq!(core.list_backups().unwrap().len(), 2);
+
+    // Restore the full chain
+    println!("Restoring incremental backup chain...");
+    db.destroy().await;
+    db.assert_is_empty(db.clone().into()).await;
+    core.restore(backup_dir.path.clone(), None).await;
+    snapshot_incremental.assert_is_eq(&Snapshot::new(&db).await);
+
+    // Restore up to the full backup
+    println!("Restoring full backup at point in time...");
+    db.destroy().await;
+    core.restore(backup_dir.path.clone(), Some(full.created))
+        .await;
+    snapshot.assert_is_eq(&Snapshot::new(&db).await);
+
+    // Corrupted archives must be rejected
+    let incremental_path = backup_dir.path.join(&incremental.id);
+    std::fs::write(incremental_path.join("blob"), b"corrupted").unwrap();
+    assert!(incremental.verify(&incremental_path).is_err());
 
     // Destroy store
     db.destroy().await;
-    temp_dir.delete();
+    backup_dir.delete();
 }
 
 #[derive(Debug, PartialEq, Eq)]
-struct Snapshot {
+pub(crate) struct Snapshot {
     keys: AHashSet<KeyValue>,
 }
 
@@ -268,12 +396,11 @@ struct KeyValue {
 }
 
 impl Snapshot {
-    async fn new(db: &Store) -> Self {
-        let is_sql = matches!(
-            db,
-            Store::SQLite(_) | Store::PostgreSQL(_) | Store::MySQL(_)
-        );
+    pub(crate) async fn new(db: &Store) -> Self {
+        Self::new_with_counters(db, !is_sql(db)).await
+    }
 
+    pub(crate) async fn new_with_counters(db: &Store, with_counters: bool) -> Self {
         let mut keys = AHashSet::new();
 
         for (subspace, with_values) in [
@@ -288,16 +415,18 @@ impl Snapshot {
             (SUBSPACE_BLOB_LINK, true),
             (SUBSPACE_BLOBS, true),
             (SUBSPACE_LOGS, true),
-            (SUBSPACE_COUNTER, !is_sql),
+            (SUBSPACE_COUNTER, with_counters),
             (SUBSPACE_LOOKUP_VALUE, true),
             (SUBSPACE_PROPERTY, true),
             (SUBSPACE_SETTINGS, true),
             (SUBSPACE_QUEUE_MESSAGE, true),
             (SUBSPACE_QUEUE_EVENT, true),
-            (SUBSPACE_QUOTA, !is_sql),
+            (SUBSPACE_QUOTA, with_counters),
             (SUBSPACE_REPORT_OUT, true),
             (SUBSPACE_REPORT_IN, true),
             (SUBSPACE_FTS_INDEX, true),
+            (SUBSPACE_SNOOZE_EVENT, true),
+            (SUBSPACE_FTS_TERM, with_counters),
         ] {
             let from_key = AnyKey {
                 subspace,
@@ -327,7 +456,7 @@ impl Snapshot {
         Snapshot { keys }
     }
 
-    fn assert_is_eq(&self, other: &Self) {
+    pub(crate) fn assert_is_eq(&self, other: &Self) {
         let mut is_err = false;
         for key in &self.keys {
             if !other.keys.contains(key) {
@@ -356,6 +485,13 @@ impl Snapshot {
     }
 }
 
-fn random_bytes(len: usize) -> Vec<u8> {
+pub(crate) fn is_sql(db: &Store) -> bool {
+    matches!(
+        db,
+        Store::SQLite(_) | Store::PostgreSQL(_) | Store::MySQL(_)
+    )
+}
+
+pub(crate) fn random_bytes(len: usize) -> Vec<u8> {
     (0..len).map(|_| rand::random::<u8>()).collect()
 }
@@ -0,0 +1,321 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use common::{
+    config::storage::MigrationSettings,
+    core::BuildServer,
+    manager::{
+        boot::build_ipc,
+        config::{ConfigManager, Patterns},
+        migrate::{MigrationState, MigrationTarget},
+    },
+    Core, Data, Inner,
+};
+use jmap_proto::types::collection::Collection;
+use store::{
+    dispatch::READ_ONLY_KEY,
+    write::{
+        log::ChangeLogBuilder, AnyKey, Batch, BatchBuilder, BlobOp, DirectoryClass, LookupClass,
+        MaybeDynamicId, ValueClass,
+    },
+    BlobStore, Store, Stores, ValueKey, SUBSPACE_PROPERTY,
+};
+use utils::{config::Config, snowflake::SnowflakeIdGenerator, BlobHash};
+
+use crate::{
+    store::{
+        import_export::{is_sql, random_bytes, Snapshot},
+        TempDir,
+    },
+    AssertConfig,
+};
+
+pub async fn test(source: Store, dest: Store) {
+    let mut core = Core::default();
+    core.storage.data = source.clone();
+    core.storage.blob = source.clone().into();
+    core.storage.fts = source.clone().into();
+    core.storage.lookup = source.clone().into();
+
+    let migration_dir = TempDir::new("store_migration_tests", true);
+    core.storage.migration = MigrationSettings {
+        path: migration_dir.path.clone(),
+        cutover_grace: Duration::from_millis(100),
+    };
+    source.assert_is_empty(source.clone().into()).await;
+    dest.destroy().await;
+
+    // Populate the source store
+    println!("Populating source store...");
+    for account_id in 1u32..=5u32 {
+        write_account(&source, account_id, account_id * 10).await;
+    }
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(u32::MAX)
+        .with_collection(Collection::Principal);
+    for account_id in 1u32..=5u32 {
+        batch
+            .create_document_with_id(account_id)
+            .set(
+                ValueClass::Directory(DirectoryClass::Principal(MaybeDynamicId::Static(
+                    account_id,
+                ))),
+                random_bytes(30),
+            )
+            .set(
+                ValueClass::Directory(DirectoryClass::NameToId(
+                    format!("user{account_id}").into_bytes(),
+                )),
+                random_bytes(4),
+            )
+            .set(
+                ValueClass::Lookup(LookupClass::Key(random_bytes(10))),
+                random_bytes(10),
+            );
+    }
+    source.write(batch.build()).await.unwrap();
+
+    // Initial copy
+    println!("Copying store...");
+    let target = MigrationTarget {
+        store: dest.clone(),
+        blob: Some(BlobStore::from(dest.clone())),
+    };
+    let path = migration_dir.path.join("dest");
+    std::fs::create_dir_all(&path).unwrap();
+    let mut state = MigrationState {
+        source: "source".to_string(),
+        store: "dest".to_string(),
+        blob: Some("dest".to_string()),
+        ..Default::default()
+    };
+    core.migrate_data(&path, &mut state, &target, false)
+        .await
+        .unwrap();
+    assert_eq!(state.imported.len(), 2);
+    assert_snapshot_eq(&source, &dest).await;
+    assert_eq!(core.list_migrations().unwrap().len(), 1);
+
+    // Changes made after the copy are applied by the final pass
+    println!("Catching up with changes...");
+    write_account(&source, 3, 100).await;
+    write_account(&source, 6, 10).await;
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(u32::MAX)
+        .with_collection(Collection::Principal)
+        .update_document(5)
+        .clear(ValueClass::Directory(DirectoryClass::Principal(
+            MaybeDynamicId::Static(5),
+        )))
+        .create_document_with_id(6)
+        .set(
+            ValueClass::Directory(DirectoryClass::Principal(MaybeDynamicId::Static(6))),
+            random_bytes(30),
+        );
+    source.write(batch.build()).await.unwrap();
+    core.migrate_data(&path, &mut state, &target, true)
+        .await
+        .unwrap();
+    assert_eq!(state.imported.len(), 3);
+    assert_snapshot_eq(&source, &dest).await;
+
+    // Interrupted imports are resumed from the exported passes
+    println!("Resuming interrupted migration...");
+    let last_pass = state.imported.pop().unwrap();
+    let mut batch = BatchBuilder::new();
+    batch.set(ValueClass::Config(b"dirty".to_vec()), b"value".to_vec());
+    dest.write(batch.build()).await.unwrap();
+    core.migrate_data(&path, &mut state, &target, false)
+        .await
+        .unwrap();
+    assert_eq!(state.imported.last(), Some(&last_pass));
+    assert_eq!(state.imported.len(), 3);
+    assert_snapshot_eq(&source, &dest).await;
+
+    // Read-only stores reject writes, except to lookup keys
+    source.set_read_only(true).await.unwrap();
+    assert!(source.write(frozen()).await.is_err());
+    let mut batch = BatchBuilder::new();
+    batch.add(
+        ValueClass::Lookup(LookupClass::Counter(b"rate".to_vec())),
+        1,
+    );
+    assert!(source.write(batch.build()).await.is_ok());
+    assert!(dest.write(frozen()).await.is_ok());
+    assert!(source.refresh_read_only().await.unwrap());
+    source.set_read_only(false).await.unwrap();
+    assert!(!source.refresh_read_only().await.unwrap());
+    assert!(source.write(frozen()).await.is_ok());
+
+    // Destroy stores
+    source.destroy().await;
+    dest.destroy().await;
+    migration_dir.delete();
+}
+
+const CUTOVER_CONFIG: &str = r#"
+[store."source"]
+type = "sqlite"
+path = "{TMP}/source.db"
+
+[store."dest"]
+type = "sqlite"
+path = "{TMP}/dest.db"
+
+[directory."internal"]
+type = "internal"
+store = "source"
+
+[storage]
+data = "source"
+fts = "source"
+blob = "source"
+lookup = "source"
+directory = "internal"
+
+[storage.migration]
+path = "{TMP}/migration"
+cutover-grace = "100ms"
+"#;
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn migrate_store_tests() {
+    let temp_dir = TempDir::new("store_cutover_tests", true);
+    let config_path = temp_dir.path.join("config.toml");
+    let config_text = CUTOVER_CONFIG.replace("{TMP}", &temp_dir.path.to_string_lossy());
+    std::fs::write(&config_path, &config_text).unwrap();
+    let mut config = Config::new(&config_text).unwrap().assert_no_errors();
+    let stores = Stores::parse_all(&mut config).await;
+    let source = stores.stores.get("source").unwrap().clone();
+    let dest = stores.stores.get("dest").unwrap().clone();
+    let config_manager = ConfigManager {
+        cfg_local: Default::default(),
+        cfg_local_path: config_path.clone(),
+        cfg_local_patterns: Patterns::parse(&mut config).into(),
+        cfg_store: source.clone(),
+    };
+    config_manager.cfg_local.store(config.keys.clone().into());
+    let core = Core::parse(&mut config, stores, config_manager).await;
+    let data = Data::parse(&mut config);
+    let (ipc, _ipc_rxs) = build_ipc();
+    let server = Arc::new(Inner {
+        shared_core: core.into_shared(),
+        data,
+        ipc,
+    })
+    .build_server();
+    config.assert_no_errors();
+
+    for account_id in 1u32..=3u32 {
+        write_account(&source, account_id, account_id * 10).await;
+    }
+    let snapshot = Snapshot::new_with_counters(&source, false).await;
+
+    // Migrate the data and blob stores and switch over to the destination
+    println!("Migrating store...");
+    let state = server.migrate_store("dest", None).await.unwrap();
+    assert!(state.completed.is_some());
+    assert_eq!(state.blob.as_deref(), Some("dest"));
+    snapshot.assert_is_eq(&Snapshot::new_with_counters(&dest, false).await);
+    assert!(server
+        .migrate_store("dest", None)
+        .await
+        .unwrap_err()
+        .matches(trc::EventType::Manage(trc::ManageEvent::AssertFailed)));
+
+    // The settings point to the destination
+    let core = server.inner.shared_core.load();
+    for key in ["storage.data", "storage.blob", "directory.internal.store"] {
+        assert_eq!(
+            core.storage.config.get(key).await.unwrap().as_deref(),
+            Some("dest"),
+            "{key}"
+        );
+    }
+    let config_text = std::fs::read_to_string(&config_path).unwrap();
+    assert!(
+        config_text.contains("storage.data = \"dest\""),
+        "{config_text}"
+    );
+    assert!(!core.storage.data.is_read_only());
+    core.storage.data.write(frozen()).await.unwrap();
+    assert_eq!(
+        dest.get_value::<String>(ValueKey::from(ValueClass::Config(READ_ONLY_KEY.to_vec())))
+            .await
+            .unwrap(),
+        None
+    );
+
+    // The source stays read-only, including on nodes that load it afterwards
+    assert!(source.is_read_only());
+    assert!(source.write(frozen()).await.is_err());
+    assert!(source
+        .delete_range(
+            AnyKey {
+                subspace: SUBSPACE_PROPERTY,
+                key: vec![0u8],
+            },
+            AnyKey {
+                subspace: SUBSPACE_PROPERTY,
+                key: vec![u8::MAX; 8],
+            },
+        )
+        .await
+        .is_err());
+    assert!(BlobStore::from(source.clone())
+        .put_blob(b"frozen", b"data")
+        .await
+        .is_err());
+    let mut config = Config::new(&config_text).unwrap();
+    let stores = Stores::parse_all(&mut config).await;
+    assert!(stores.stores.get("source").unwrap().is_read_only());
+    assert!(!stores.stores.get("dest").unwrap().is_read_only());
+
+    temp_dir.delete();
+}
+
+fn frozen() -> Batch {
+    let mut batch = BatchBuilder::new();
+    batch.set(ValueClass::Config(b"frozen".to_vec()), b"value".to_vec());
+    batch.build()
+}
+
+async fn write_account(store: &Store, account_id: u32, document_id: u32) {
+    let data = random_bytes(1024);
+    let hash = BlobHash::from(data.as_slice());
+    BlobStore::from(store.clone())
+        .put_blob(hash.as_ref(), &data)
+        .await
+        .unwrap();
+
+    let mut batch = BatchBuilder::new();
+    batch
+        .set(
+            ValueClass::Blob(BlobOp::Commit { hash: hash.clone() }),
+            vec![],
+        )
+        .with_account_id(account_id)
+        .with_collection(Collection::Email)
+        .create_document_with_id(document_id)
+        .set(ValueClass::Property(0), random_bytes(100))
+        .set(ValueClass::Blob(BlobOp::Link { hash }), vec![])
+        .custom(
+            ChangeLogBuilder::with_change_id(SnowflakeIdGenerator::new().generate().unwrap())
+                .with_log_insert(Collection::Email, document_id),
+        );
+    store.write(batch.build()).await.unwrap();
+}
+
+async fn assert_snapshot_eq(source: &Store, dest: &Store) {
+    let with_counters = !is_sql(source) && !is_sql(dest);
+    Snapshot::new_with_counters(source, with_counters)
+        .await
+        .assert_is_eq(&Snapshot::new_with_counters(dest, with_counters).await);
+}
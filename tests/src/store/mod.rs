@@ -6,10 +6,15 @@
 
 pub mod assign_id;
 pub mod blob;
+pub mod cache;
 pub mod import_export;
 pub mod lookup;
+pub mod migrate;
 pub mod ops;
 pub mod query;
+#[cfg(feature = "redis")]
+pub mod sentinel;
+pub mod telemetry;
 
 use std::io::Read;
 
@@ -35,16 +40,40 @@ bucket = "tmp"
 type = "fs"
 path = "{TMP}"
 
+[store."fs-hot"]
+type = "fs"
+path = "{TMP}/fs-hot"
+
+[store."tiered"]
+type = "tiered"
+hot = "fs-hot"
+cold = "sqlite"
+migrate.max-age = "10ms"
+
 [store."rocksdb"]
 type = "rocksdb"
 path = "{TMP}/rocksdb"
 
+[store."redb"]
+type = "redb"
+path = "{TMP}/redb"
+
 [store."foundationdb"]
 type = "foundationdb"
 
 [store."sqlite"]
 type = "sqlite"
 path = "{TMP}/sqlite.db"
+telemetry.slow-threshold = "1s"
+
+[store."sqlite-cache"]
+type = "sqlite"
+path = "{TMP}/sqlite-cache.db"
+cache.size = 1048576
+
+[store."sqlite-migrate"]
+type = "sqlite"
+path = "{TMP}/sqlite-migrate.db"
 
 [store."postgresql"]
 type = "postgresql"
@@ -92,8 +121,15 @@ pub async fn store_tests() {
     }
 
     import_export::test(store.clone()).await;
+    migrate::test(
+        store.clone(),
+        stores.stores.get("sqlite-migrate").unwrap().clone(),
+    )
+    .await;
     assign_id::test(store.clone()).await;
     ops::test(store.clone()).await;
+    telemetry::test(store.clone(), &store_id).await;
+    cache::test(stores.stores.get("sqlite-cache").unwrap().clone()).await;
     query::test(store.clone(), FtsStore::Store(store.clone()), insert).await;
 
     if insert {
@@ -0,0 +1,404 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU16, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use store::{LookupStore, Stores};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use utils::config::Config;
+
+use crate::AssertConfig;
+
+const CONFIG: &str = r#"
+[store."sentinel"]
+type = "redis"
+redis-type = "sentinel"
+urls = ["redis://127.0.0.1:1", "redis://127.0.0.1:{SENTINEL}"]
+master-name = "mymaster"
+user = "stalwart"
+password = "secret"
+timeout = "2s"
+pool.max-connections = 1
+"#;
+
+#[tokio::test]
+pub async fn redis_sentinel_tests() {
+    // Start two Redis stand-ins and a sentinel monitoring them
+    let primary = RedisNode::start(true).await;
+    let replica = RedisNode::start(false).await;
+    let sentinel = Sentinel::start("mymaster", primary.port).await;
+
+    let mut config = Config::new(CONFIG.replace("{SENTINEL}", &sentinel.port.to_string()))
+        .unwrap()
+        .assert_no_errors();
+    let stores = Stores::parse_all(&mut config).await;
+    let store = stores.lookup_stores.get("sentinel").unwrap().clone();
+    assert!(matches!(store, LookupStore::Redis(_)));
+
+    // Keys and counters are written to the master
+    println!("Testing Redis Sentinel master discovery...");
+    let key = b"xyz".to_vec();
+    let counter = b"abc".to_vec();
+    store
+        .key_set(key.clone(), b"world".to_vec(), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        store.key_get::<String>(key.clone()).await.unwrap(),
+        Some("world".to_string())
+    );
+    assert!(store.key_exists(key.clone()).await.unwrap());
+    store
+        .counter_incr(counter.clone(), 2, 60.into(), false)
+        .await
+        .unwrap();
+    assert_eq!(store.counter_get(counter.clone()).await.unwrap(), 2);
+    assert_eq!(primary.len(), 2);
+    assert_eq!(replica.len(), 0);
+
+    // Promote the replica, connections to the old master have to be replaced
+    println!("Testing Redis Sentinel failover...");
+    replica.replicate_from(&primary);
+    primary.set_master(false);
+    replica.set_master(true);
+    sentinel.set_master(replica.port);
+
+    assert_eq!(
+        store.key_get::<String>(key.clone()).await.unwrap(),
+        Some("world".to_string())
+    );
+    store
+        .counter_incr(counter.clone(), 3, None, false)
+        .await
+        .unwrap();
+    assert_eq!(store.counter_get(counter.clone()).await.unwrap(), 5);
+    store.key_delete(key.clone()).await.unwrap();
+    assert!(!store.key_exists(key.clone()).await.unwrap());
+    store
+        .key_set(b"new".to_vec(), b"value".to_vec(), 60.into())
+        .await
+        .unwrap();
+    assert_eq!(primary.len(), 2);
+    assert_eq!(replica.len(), 2);
+
+    // Unauthenticated clients are rejected by the nodes
+    let mut config = Config::new(
+        CONFIG
+            .replace("{SENTINEL}", &sentinel.port.to_string())
+            .replace("\"secret\"", "\"wrong\""),
+    )
+    .unwrap()
+    .assert_no_errors();
+    let stores = Stores::parse_all(&mut config).await;
+    assert!(stores
+        .lookup_stores
+        .get("sentinel")
+        .unwrap()
+        .key_exists(counter.clone())
+        .await
+        .is_err());
+}
+
+enum Reply {
+    Status(&'static str),
+    Error(String),
+    Int(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Vec<Reply>),
+}
+
+impl Reply {
+    fn write(&self, buf: &mut Vec<u8>) {
+        match self {
+            Reply::Status(status) => buf.extend_from_slice(format!("+{status}\r\n").as_bytes()),
+            Reply::Error(err) => buf.extend_from_slice(format!("-{err}\r\n").as_bytes()),
+            Reply::Int(value) => buf.extend_from_slice(format!(":{value}\r\n").as_bytes()),
+            Reply::Bulk(Some(value)) => {
+                buf.extend_from_slice(format!("${}\r\n", value.len()).as_bytes());
+                buf.extend_from_slice(value);
+                buf.extend_from_slice(b"\r\n");
+            }
+            Reply::Bulk(None) => buf.extend_from_slice(b"$-1\r\n"),
+            Reply::Array(items) => {
+                buf.extend_from_slice(format!("*{}\r\n", items.len()).as_bytes());
+                for item in items {
+                    item.write(buf);
+                }
+            }
+        }
+    }
+
+    fn text(value: impl ToString) -> Self {
+        Reply::Bulk(Some(value.to_string().into_bytes()))
+    }
+}
+
+type Entries = AHashMap<Vec<u8>, (Vec<u8>, Option<Instant>)>;
+
+struct RedisNode {
+    port: u16,
+    is_master: Arc<AtomicBool>,
+    data: Arc<Mutex<Entries>>,
+}
+
+impl RedisNode {
+    async fn start(is_master: bool) -> Self {
+        let is_master = Arc::new(AtomicBool::new(is_master));
+        let data = Arc::new(Mutex::new(AHashMap::new()));
+        let port = serve({
+            let is_master = is_master.clone();
+            let data = data.clone();
+            move |authenticated: &mut bool, args: Vec<Vec<u8>>| {
+                node_command(&is_master, &data, authenticated, args)
+            }
+        })
+        .await;
+
+        RedisNode {
+            port,
+            is_master,
+            data,
+        }
+    }
+
+    fn set_master(&self, is_master: bool) {
+        self.is_master.store(is_master, Ordering::Relaxed);
+    }
+
+    fn replicate_from(&self, other: &RedisNode) {
+        let entries = other.data.lock().unwrap().clone();
+        *self.data.lock().unwrap() = entries;
+    }
+
+    fn len(&self) -> usize {
+        self.data.lock().unwrap().len()
+    }
+}
+
+fn node_command(
+    is_master: &AtomicBool,
+    data: &Mutex<Entries>,
+    authenticated: &mut bool,
+    args: Vec<Vec<u8>>,
+) -> Reply {
+    let command = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+    match command.as_str() {
+        "AUTH" => {
+            return if args.len() == 3 && args[1] == b"stalwart" && args[2] == b"secret" {
+                *authenticated = true;
+                Reply::Status("OK")
+            } else {
+                Reply::Error("WRONGPASS invalid username-password pair".to_string())
+            };
+        }
+        _ if !*authenticated => {
+            return Reply::Error("NOAUTH Authentication required.".to_string());
+        }
+        _ => (),
+    }
+
+    let is_master = is_master.load(Ordering::Relaxed);
+    let is_write = matches!(
+        command.as_str(),
+        "SET" | "SETEX" | "INCRBY" | "EXPIRE" | "DEL"
+    );
+    if is_write && !is_master {
+        return Reply::Error("READONLY You can't write against a read only replica.".to_string());
+    }
+
+    let mut data = data.lock().unwrap();
+    let now = Instant::now();
+    data.retain(|_, (_, expires)| expires.map_or(true, |expires| expires > now));
+    let seconds =
+        |arg: &[u8]| Duration::from_secs(std::str::from_utf8(arg).unwrap().parse().unwrap());
+
+    match (command.as_str(), args.as_slice()) {
+        ("ROLE", _) => {
+            if is_master {
+                Reply::Array(vec![
+                    Reply::text("master"),
+                    Reply::Int(0),
+                    Reply::Array(vec![]),
+                ])
+            } else {
+                Reply::Array(vec![
+                    Reply::text("slave"),
+                    Reply::text("127.0.0.1"),
+                    Reply::Int(6379),
+                    Reply::text("connected"),
+                    Reply::Int(0),
+                ])
+            }
+        }
+        ("PING", _) => Reply::Status("PONG"),
+        ("CLIENT", _) => Reply::Status("OK"),
+        ("GET", [_, key]) => Reply::Bulk(data.get(key).map(|(value, _)| value.clone())),
+        ("EXISTS", [_, key]) => Reply::Int(data.contains_key(key) as i64),
+        ("SET", [_, key, value]) => {
+            data.insert(key.clone(), (value.clone(), None));
+            Reply::Status("OK")
+        }
+        ("SETEX", [_, key, expires, value]) => {
+            data.insert(key.clone(), (value.clone(), Some(now + seconds(expires))));
+            Reply::Status("OK")
+        }
+        ("INCRBY", [_, key, value]) => {
+            let entry = data
+                .entry(key.clone())
+                .or_insert_with(|| (b"0".to_vec(), None));
+            let value = std::str::from_utf8(&entry.0)
+                .unwrap()
+                .parse::<i64>()
+                .unwrap()
+                + std::str::from_utf8(value).unwrap().parse::<i64>().unwrap();
+            entry.0 = value.to_string().into_bytes();
+            Reply::Int(value)
+        }
+        ("EXPIRE", [_, key, expires]) => {
+            if let Some(entry) = data.get_mut(key) {
+                entry.1 = Some(now + seconds(expires));
+                Reply::Int(1)
+            } else {
+                Reply::Int(0)
+            }
+        }
+        ("DEL", [_, key]) => Reply::Int(data.remove(key).is_some() as i64),
+        _ => Reply::Error(format!("ERR unknown command '{command}'")),
+    }
+}
+
+struct Sentinel {
+    port: u16,
+    master_port: Arc<AtomicU16>,
+}
+
+impl Sentinel {
+    async fn start(master_name: &'static str, master_port: u16) -> Self {
+        let master_port = Arc::new(AtomicU16::new(master_port));
+        let port = serve({
+            let master_port = master_port.clone();
+            move |_: &mut bool, args: Vec<Vec<u8>>| {
+                let command = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+                let subcommand = args
+                    .get(1)
+                    .map(|arg| String::from_utf8_lossy(arg).to_ascii_uppercase());
+                match (command.as_str(), subcommand.as_deref()) {
+                    ("SENTINEL", Some("MASTERS")) => Reply::Array(vec![Reply::Array(vec![
+                        Reply::text("name"),
+                        Reply::text(master_name),
+                        Reply::text("ip"),
+                        Reply::text("127.0.0.1"),
+                        Reply::text("port"),
+                        Reply::text(master_port.load(Ordering::Relaxed)),
+                        Reply::text("flags"),
+                        Reply::text("master"),
+                    ])]),
+                    ("CLIENT", _) => Reply::Status("OK"),
+                    ("PING", _) => Reply::Status("PONG"),
+                    _ => Reply::Error(format!("ERR unknown command '{command}'")),
+                }
+            }
+        })
+        .await;
+
+        Sentinel { port, master_port }
+    }
+
+    fn set_master(&self, port: u16) {
+        self.master_port.store(port, Ordering::Relaxed);
+    }
+}
+
+async fn serve<F>(handler: F) -> u16
+where
+    F: Fn(&mut bool, Vec<Vec<u8>>) -> Reply + Clone + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let _ = handle_connection(stream, handler).await;
+            });
+        }
+    });
+
+    port
+}
+
+async fn handle_connection<F>(stream: TcpStream, handler: F) -> std::io::Result<()>
+where
+    F: Fn(&mut bool, Vec<Vec<u8>>) -> Reply,
+{
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut authenticated = false;
+    let mut transaction: Option<Vec<Vec<Vec<u8>>>> = None;
+
+    loop {
+        // Commands are sent as arrays of bulk strings
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+        let num_args = line
+            .trim_end()
+            .trim_start_matches('*')
+            .parse::<usize>()
+            .unwrap();
+        let mut args = Vec::with_capacity(num_args);
+        for _ in 0..num_args {
+            line.clear();
+            reader.read_line(&mut line).await?;
+            let len = line
+                .trim_end()
+                .trim_start_matches('$')
+                .parse::<usize>()
+                .unwrap();
+            let mut arg = vec![0u8; len + 2];
+            reader.read_exact(&mut arg).await?;
+            arg.truncate(len);
+            args.push(arg);
+        }
+
+        let command = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+        let reply = match (command.as_str(), transaction.as_mut()) {
+            ("MULTI", None) => {
+                transaction = Some(Vec::new());
+                Reply::Status("OK")
+            }
+            ("EXEC", Some(_)) => Reply::Array(
+                transaction
+                    .take()
+                    .unwrap()
+                    .into_iter()
+                    .map(|args| handler(&mut authenticated, args))
+                    .collect(),
+            ),
+            (_, Some(queued)) => {
+                queued.push(args);
+                Reply::Status("QUEUED")
+            }
+            _ => handler(&mut authenticated, args),
+        };
+
+        let mut buf = Vec::new();
+        reply.write(&mut buf);
+        writer.write_all(&buf).await?;
+    }
+}
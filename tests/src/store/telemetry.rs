@@ -0,0 +1,64 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use store::{
+    write::{BatchBuilder, ValueClass},
+    Store, ValueKey,
+};
+use trc::{ipc::metrics::StoreOperation, Collector};
+
+pub async fn test(db: Store, store_id: &str) {
+    println!("Testing store telemetry...");
+
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(0)
+        .with_collection(0)
+        .update_document(0)
+        .set(ValueClass::Config(b"telemetry".to_vec()), b"value".to_vec());
+    db.write(batch.build_batch()).await.unwrap();
+    assert_eq!(
+        db.get_value::<String>(ValueKey {
+            account_id: 0,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::Config(b"telemetry".to_vec()),
+        })
+        .await
+        .unwrap(),
+        Some("value".to_string())
+    );
+
+    // Operations should be recorded under the configured store id
+    let metrics = Collector::collect_store_histograms(true)
+        .into_iter()
+        .find(|m| m.id() == store_id)
+        .expect("Store metrics not found");
+    let operations = metrics
+        .histograms()
+        .map(|(operation, histogram)| (operation, histogram.count()))
+        .collect::<Vec<_>>();
+    for operation in [StoreOperation::GetValue, StoreOperation::Write] {
+        assert!(
+            operations
+                .iter()
+                .any(|(op, count)| *op == operation && *count > 0),
+            "{operation:?} not recorded: {operations:?}"
+        );
+    }
+    assert_eq!(metrics.errors().count(), 0);
+
+    // Store metrics are not exported in the community edition
+    assert!(Collector::collect_store_histograms(false).is_empty());
+
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(0)
+        .with_collection(0)
+        .update_document(0)
+        .clear(ValueClass::Config(b"telemetry".to_vec()));
+    db.write(batch.build_batch()).await.unwrap();
+}